@@ -8,7 +8,12 @@ fn main() {
     let workspace_root = manifest_dir.parent().unwrap();
 
     println!("cargo:rerun-if-changed=../crates/mcp-run/Cargo.toml");
+    println!("cargo:rerun-if-changed=../crates/mcp-run/build.rs");
     println!("cargo:rerun-if-changed=../crates/mcp-run/src");
+    println!(
+        "cargo:rustc-env=CLADDING_MCP_RUN_VERSION={}",
+        package_version(&workspace_root.join("crates/mcp-run/Cargo.toml"))
+    );
 
     let target_triple = env::var("TARGET").ok();
     let build_target = env::var("CARGO_BUILD_TARGET").ok();
@@ -42,6 +47,22 @@ fn release_dir(target_dir: &Path, target: Option<&str>) -> PathBuf {
     }
 }
 
+/// `version` of the `[package]` table in the manifest at `path`.
+fn package_version(path: &Path) -> String {
+    let manifest = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+    manifest
+        .lines()
+        .skip_while(|line| line.trim() != "[package]")
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "version").then(|| value.trim().trim_matches('"').to_string())
+        })
+        .unwrap_or_else(|| panic!("no package version in {}", path.display()))
+}
+
 fn copy_bin(src: &Path, dst: &Path) {
     fs::copy(src, dst).unwrap_or_else(|err| {
         panic!("failed to copy {} to {}: {err}", src.display(), dst.display())
@@ -67,11 +88,24 @@ fn build_locally(workspace_root: &Path, target_dir: &Path, target: Option<&str>)
 }
 
 fn build_with_podman(crate_dir: &Path) {
+    // The container only sees the crate directory, so resolve the commit here.
+    let git_commit = Command::new("git")
+        .arg("-C")
+        .arg(crate_dir)
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+
     let status = Command::new("podman")
         .arg("run")
         .arg("--rm")
         .arg("-e")
         .arg("CARGO_TARGET_DIR=/work/mcp-run/target")
+        .arg("-e")
+        .arg(format!("MCP_RUN_GIT_COMMIT={git_commit}"))
         .arg("-v")
         .arg(format!("{}:/work/mcp-run", crate_dir.display()))
         .arg("-w")
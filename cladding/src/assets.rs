@@ -2,6 +2,9 @@ use crate::error::Result;
 use crate::fs_utils::set_permissions;
 use anyhow::Context as _;
 use include_dir::{Dir, include_dir};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -9,27 +12,135 @@ const CONTAINERFILE_CLADDING: &str = include_str!("../../Containerfile.cladding"
 
 static CONFIG_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../config-template");
 static SCRIPTS_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../scripts");
+static HOME_TEMPLATES_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../home-templates");
 
 static MCP_RUN_BIN: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/mcp-run"));
 static RUN_REMOTE_BIN: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/run-remote"));
+
+/// Written into `.cladding` by `init` and `build` to record which embedded assets were used.
+pub const ASSET_MANIFEST_FILE: &str = "assets.json";
+
+/// Version and content hashes of the assets a cladding binary materializes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetManifest {
+    /// Version of the cladding binary that embedded the assets.
+    pub version: String,
+    /// SHA-256 over all file hashes, identifying the exact asset set.
+    pub digest: String,
+    /// SHA-256 of each file, keyed by its path relative to `.cladding`.
+    pub files: BTreeMap<String, String>,
+}
+
+impl AssetManifest {
+    /// The first 12 hex digits of [`AssetManifest::digest`], for display.
+    pub fn short_digest(&self) -> &str {
+        self.digest.get(..12).unwrap_or(&self.digest)
+    }
+}
+
+/// A materialized file that does not match the embedded one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetDrift {
+    Missing(String),
+    Modified(String),
+}
+
+/// SHA-256 of the embedded Containerfile, which `cladding build` records on its images.
+pub fn containerfile_digest() -> String {
+    sha256_hex(CONTAINERFILE_CLADDING.as_bytes())
+}
+
+/// The manifest of the assets embedded in this binary.
+pub fn embedded_manifest() -> AssetManifest {
+    let mut files = BTreeMap::new();
+    for (prefix, dir) in [("config", &CONFIG_DIR), ("scripts", &SCRIPTS_DIR)] {
+        let mut contents = Vec::new();
+        collect_dir_files(dir, &mut contents);
+        for (path, bytes) in contents {
+            files.insert(
+                format!("{prefix}/{}", path.display()),
+                sha256_hex(&bytes),
+            );
+        }
+    }
+    files.insert("tools/bin/mcp-run".to_string(), sha256_hex(MCP_RUN_BIN));
+    files.insert(
+        "tools/bin/run-with-network".to_string(),
+        sha256_hex(RUN_REMOTE_BIN),
+    );
+
+    let mut digest = Sha256::new();
+    for (path, hash) in &files {
+        digest.update(format!("{hash}  {path}\n"));
+    }
+    AssetManifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        digest: hex(&digest.finalize()),
+        files,
+    }
+}
+
+/// Records the embedded manifest in `project_root`.
+pub fn write_asset_manifest(project_root: &Path) -> Result<()> {
+    let path = project_root.join(ASSET_MANIFEST_FILE);
+    let mut contents = serde_json::to_string_pretty(&embedded_manifest())
+        .context("failed to serialize asset manifest")?;
+    contents.push('\n');
+    fs::write(&path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// The manifest last written to `project_root`, or `None` if there is none.
+pub fn read_asset_manifest(project_root: &Path) -> Result<Option<AssetManifest>> {
+    let path = project_root.join(ASSET_MANIFEST_FILE);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(anyhow::Error::new(err)
+                .context(format!("failed to read {}", path.display()))
+                .into());
+        }
+    };
+    let manifest = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(manifest))
+}
+
+/// Compares the files under `project_root` with `manifest`.
+pub fn asset_drift(project_root: &Path, manifest: &AssetManifest) -> Vec<AssetDrift> {
+    let mut drift = Vec::new();
+    for (path, hash) in &manifest.files {
+        match fs::read(project_root.join(path)) {
+            Ok(contents) if sha256_hex(&contents) == *hash => {}
+            Ok(_) => drift.push(AssetDrift::Modified(path.clone())),
+            Err(_) => drift.push(AssetDrift::Missing(path.clone())),
+        }
+    }
+    drift
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
 pub fn config_top_level_entries() -> Vec<String> {
     let mut names = std::collections::BTreeSet::new();
     for entry in CONFIG_DIR.dirs() {
-        if let Some(component) = entry.path().components().next() {
-            if let std::path::Component::Normal(name) = component {
-                if let Some(name) = name.to_str() {
-                    names.insert(name.to_string());
-                }
-            }
+        if let Some(std::path::Component::Normal(name)) = entry.path().components().next()
+            && let Some(name) = name.to_str()
+        {
+            names.insert(name.to_string());
         }
     }
     for entry in CONFIG_DIR.files() {
-        if let Some(component) = entry.path().components().next() {
-            if let std::path::Component::Normal(name) = component {
-                if let Some(name) = name.to_str() {
-                    names.insert(name.to_string());
-                }
-            }
+        if let Some(std::path::Component::Normal(name)) = entry.path().components().next()
+            && let Some(name) = name.to_str()
+        {
+            names.insert(name.to_string());
         }
     }
     names.into_iter().collect()
@@ -38,21 +149,17 @@ pub fn config_top_level_entries() -> Vec<String> {
 pub fn scripts_top_level_entries() -> Vec<String> {
     let mut names = std::collections::BTreeSet::new();
     for entry in SCRIPTS_DIR.dirs() {
-        if let Some(component) = entry.path().components().next() {
-            if let std::path::Component::Normal(name) = component {
-                if let Some(name) = name.to_str() {
-                    names.insert(name.to_string());
-                }
-            }
+        if let Some(std::path::Component::Normal(name)) = entry.path().components().next()
+            && let Some(name) = name.to_str()
+        {
+            names.insert(name.to_string());
         }
     }
     for entry in SCRIPTS_DIR.files() {
-        if let Some(component) = entry.path().components().next() {
-            if let std::path::Component::Normal(name) = component {
-                if let Some(name) = name.to_str() {
-                    names.insert(name.to_string());
-                }
-            }
+        if let Some(std::path::Component::Normal(name)) = entry.path().components().next()
+            && let Some(name) = name.to_str()
+        {
+            names.insert(name.to_string());
         }
     }
     names.into_iter().collect()
@@ -76,6 +183,28 @@ pub fn scripts_files() -> Vec<(PathBuf, Vec<u8>)> {
     files
 }
 
+pub fn home_template_presets() -> Vec<String> {
+    let mut names: Vec<String> = HOME_TEMPLATES_DIR
+        .dirs()
+        .filter_map(|dir| dir.path().file_name()?.to_str().map(str::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Files of an embedded home preset, with paths relative to the preset root.
+pub fn home_template_preset_files(preset: &str) -> Option<Vec<(PathBuf, Vec<u8>)>> {
+    let dir = HOME_TEMPLATES_DIR.get_dir(preset)?;
+    let mut files = Vec::new();
+    collect_dir_files(dir, &mut files);
+    for (path, _) in &mut files {
+        if let Ok(relative) = path.strip_prefix(preset) {
+            *path = relative.to_path_buf();
+        }
+    }
+    Some(files)
+}
+
 pub fn write_embedded_tools(bin_dir: &Path) -> Result<()> {
     let mcp_run_path = bin_dir.join("mcp-run");
     fs::write(&mcp_run_path, MCP_RUN_BIN)
@@ -130,3 +259,67 @@ fn collect_dir_files(dir: &Dir<'_>, files: &mut Vec<(PathBuf, Vec<u8>)>) {
 pub fn containerfile() -> &'static str {
     CONTAINERFILE_CLADDING
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "cladding-assets-{label}-{}-{nanos}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn embedded_manifest_covers_materialized_files() {
+        let manifest = embedded_manifest();
+        assert_eq!(manifest.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(manifest.digest.len(), 64);
+        assert_eq!(manifest.short_digest().len(), 12);
+        assert!(manifest.files.contains_key("tools/bin/mcp-run"));
+        for (path, _) in scripts_files() {
+            assert!(
+                manifest
+                    .files
+                    .contains_key(&format!("scripts/{}", path.display()))
+            );
+        }
+        assert_eq!(embedded_manifest(), manifest);
+    }
+
+    #[test]
+    fn drift_reports_missing_and_modified_files() {
+        let root = temp_dir("drift");
+        materialize_config(&root.join("config")).unwrap();
+        materialize_scripts(&root.join("scripts")).unwrap();
+        fs::create_dir_all(root.join("tools/bin")).unwrap();
+        write_embedded_tools(&root.join("tools/bin")).unwrap();
+        write_asset_manifest(&root).unwrap();
+
+        let manifest = read_asset_manifest(&root).unwrap().expect("manifest written");
+        assert_eq!(manifest, embedded_manifest());
+        assert!(asset_drift(&root, &manifest).is_empty());
+
+        let (edited, _) = scripts_files().into_iter().next().expect("embedded scripts");
+        fs::write(root.join("scripts").join(&edited), "edited").unwrap();
+        fs::remove_file(root.join("tools/bin/run-with-network")).unwrap();
+        assert_eq!(
+            asset_drift(&root, &manifest),
+            vec![
+                AssetDrift::Modified(format!("scripts/{}", edited.display())),
+                AssetDrift::Missing("tools/bin/run-with-network".to_string()),
+            ]
+        );
+
+        assert_eq!(read_asset_manifest(&root.join("missing")).unwrap(), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
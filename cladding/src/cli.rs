@@ -1,38 +1,76 @@
 use anyhow::Context as _;
-use cladding::assets::{
-    config_top_level_entries, materialize_config, materialize_scripts, materialize_scripts_force,
-    scripts_files, scripts_top_level_entries, write_embedded_tools,
-};
-use cladding::config::{Config, load_cladding_config, write_default_cladding_config};
+use cladding::config::{Config, load_cladding_config, parse_size, validate_sidecar_name};
+use cladding::dashboard::DEFAULT_DASHBOARD_PORT;
+use cladding::engine::{resolve_engine, select_engine};
 use cladding::error::{Error, Result};
-use cladding::fs_utils::{canonicalize_path, is_broken_symlink, is_executable, path_is_symlink};
-use cladding::network::{parse_cladding_pool_index, resolve_network_settings};
-use cladding::podman::{
-    EnsureNetworkOutcome, ensure_pool_network_settings, list_podman_network_subnets,
-    list_project_expose_proxies, list_running_project_networks, list_running_projects,
-    podman_build_image, podman_container_exists, podman_play_kube, podman_remove_containers,
-    podman_required,
-};
-use cladding::pods::{host_paths_from_rendered, render_pods_yaml};
-use clap::{ArgAction, Args, Parser, Subcommand};
+use cladding::lock::{acquire_project_lock, force_unlock};
+use cladding::output;
+use cladding::pool::{POOL_MEMBER_ENV, parse_pool_member_index};
+use cladding::progress::{self, LogFormat};
+use cladding::runtime::{ContainerRuntime, engine_runtime};
+use cladding::services::validate_service_name;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use std::env;
-use std::fs;
-use std::io::{self, IsTerminal};
-use std::net::TcpListener;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::thread;
-use std::time::{SystemTime, UNIX_EPOCH};
-use signal_hook::consts::signal::{SIGINT, SIGTERM};
-use signal_hook::iterator::Signals;
-
-const DEFAULT_CLADDING_BUILD_IMAGE: &str = "localhost/cladding-default:latest";
-const DEFAULT_CLI_BUILD_IMAGE: &str = DEFAULT_CLADDING_BUILD_IMAGE;
-const DEFAULT_SANDBOX_BUILD_IMAGE: &str = DEFAULT_CLADDING_BUILD_IMAGE;
+use std::rc::Rc;
+
+mod build;
+mod check;
+mod config;
+mod dashboard;
+mod disk_usage;
+mod doctor;
+mod exec;
+mod expose;
+mod images;
+mod init;
+mod lifecycle;
+mod logs;
+mod mcp_config;
+mod network;
+mod policy;
+mod pool;
+mod proxy;
+mod ps;
+mod service;
+mod status;
+mod support_bundle;
+mod verify;
+mod volume;
+#[cfg(test)]
+mod test_support;
+
+use build::cmd_build;
+use check::cmd_check;
+use config::cmd_config;
+use dashboard::cmd_dashboard;
+use disk_usage::cmd_disk_usage;
+use doctor::cmd_doctor;
+use exec::{cmd_exec_proxy, cmd_run, cmd_run_with_scissors, cmd_shell};
+use expose::cmd_expose;
+use images::cmd_images;
+use init::cmd_init;
+use lifecycle::{
+    AllProjectsAction, cmd_all_projects, cmd_destroy, cmd_down, cmd_restart, cmd_up, cmd_up_watch,
+};
+use logs::cmd_logs;
+use mcp_config::cmd_mcp_config;
+use policy::cmd_policy;
+use pool::cmd_pool;
+use proxy::{cmd_edit_domain_list, cmd_proxy, cmd_reload_proxy};
+use ps::cmd_ps;
+use service::cmd_service;
+use status::{DEFAULT_WATCH_INTERVAL_SECS, cmd_status};
+use support_bundle::cmd_support_bundle;
+use verify::cmd_verify;
+use volume::cmd_volume;
 
 #[derive(Debug, Clone)]
 struct Context {
     project_root: PathBuf,
+    runtime: Rc<dyn ContainerRuntime>,
+    /// Pool member from `CLADDING_POOL_MEMBER` that commands act on instead of the project.
+    pool_member: Option<String>,
 }
 
 #[derive(Parser)]
@@ -40,6 +78,18 @@ struct Context {
 struct Cli {
     #[arg(long, global = true, hide = true)]
     project_root: Option<PathBuf>,
+    /// Wait for another cladding process to release the project lock
+    #[arg(long, global = true)]
+    wait: bool,
+    /// Remove an existing project lock before running the command
+    #[arg(long, global = true)]
+    force_unlock: bool,
+    /// Never color messages (also set by a non-empty NO_COLOR)
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Report build, up and down steps as JSON lines on stdout
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
     #[command(subcommand)]
     command: Option<CommandSpec>,
 }
@@ -47,42 +97,217 @@ struct Cli {
 #[derive(Debug, Subcommand)]
 enum CommandSpec {
     /// Build local container images
-    Build,
+    Build {
+        /// Also tag the built image as REF, e.g. registry.example.com/team/cladding:1.4
+        #[arg(long = "tag", value_name = "REF")]
+        tags: Vec<String>,
+        /// Push every --tag to its registry after building
+        #[arg(long, requires = "tags")]
+        push: bool,
+    },
     /// Create config and default mount directories
     Init {
         name: Option<String>,
         /// Overwrite scripts with embedded defaults
         #[arg(long)]
         update_scripts: bool,
+        /// Generate cladding.json from the workspace's .devcontainer/devcontainer.json
+        #[arg(long)]
+        from_devcontainer: bool,
     },
     /// Check requirements
-    Check,
+    Check {
+        /// Create missing directories, config files, scripts and tools binaries, then check
+        #[arg(long)]
+        fix: bool,
+    },
     /// Start the system
-    Up,
+    Up {
+        /// Keep running and re-apply the project when cladding.json or config/ changes
+        #[arg(long)]
+        watch: bool,
+        /// Recreate an idle cladding-N network found on the wrong subnet instead of skipping
+        /// its slot
+        #[arg(long, conflicts_with = "watch")]
+        fix_network: bool,
+    },
     /// Stop the system
-    Down,
+    Down {
+        /// Stop every cladding project running on this host
+        #[arg(long)]
+        all: bool,
+        /// Do not ask before stopping every project
+        #[arg(long, short = 'y', requires = "all")]
+        yes: bool,
+    },
+    /// Restart the whole system, or one pod, keeping its network slot and addresses
+    Restart {
+        /// Only restart this pod
+        #[arg(value_enum, value_name = "POD")]
+        pod: Option<PodRole>,
+    },
     /// Force-remove running containers
-    Destroy,
-    /// Run a command in the cli container
+    Destroy {
+        /// Force-remove the containers of every cladding project running on this host
+        #[arg(long)]
+        all: bool,
+        /// Do not ask before removing every project
+        #[arg(long, short = 'y', requires = "all")]
+        yes: bool,
+        /// Also remove the project network once no container uses it; with --all, every idle
+        /// cladding-N network
+        #[arg(long)]
+        network: bool,
+    },
+    /// Keep warm copies of the project running for CI jobs to check out
+    Pool(PoolArgs),
+    /// Inspect cladding.json
+    Config(ConfigArgs),
+    /// Write sandbox command policies
+    Policy(PolicyArgs),
+    /// Manage the project's named volumes, the `volume` entries of cladding.json mounts
+    Volume(VolumeArgs),
+    /// Run a command in the cli container, or the one --container names
     Run {
         #[arg(long = "env", value_name = "KEY[=VALUE]", action = ArgAction::Append)]
         env: Vec<String>,
+        /// Run in the cli pod of this entry of cladding.json "agents"
+        #[arg(long, value_name = "NAME", conflicts_with = "sandbox")]
+        agent: Option<String>,
+        /// Run in the sandbox container, like --container sandbox-app
+        #[arg(long, conflicts_with = "container")]
+        sandbox: bool,
+        /// Container of the project to run in
+        #[arg(long, value_enum, value_name = "NAME")]
+        container: Option<RunContainer>,
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Open an interactive login shell in the cli container
+    Shell {
+        #[arg(long = "env", value_name = "KEY[=VALUE]", action = ArgAction::Append)]
+        env: Vec<String>,
+        /// Open the shell in the cli pod of this entry of cladding.json "agents"
+        #[arg(long, value_name = "NAME")]
+        agent: Option<String>,
+    },
     /// Run a command in the sandbox container
+    #[command(visible_alias = "exec-sandbox")]
     RunWithScissors {
         #[arg(long = "env", value_name = "KEY[=VALUE]", action = ArgAction::Append)]
         env: Vec<String>,
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Run a command in the proxy container
+    ExecProxy {
+        #[arg(long = "env", value_name = "KEY[=VALUE]", action = ArgAction::Append)]
+        env: Vec<String>,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Reload the squid proxy configuration
     ReloadProxy,
-    /// Show running cladding projects
-    Ps,
+    /// Debug proxy access rules
+    Proxy(ProxyArgs),
+    /// Add a domain to a proxy allowlist and reload the proxy
+    AllowDomain {
+        #[arg(value_name = "DOMAIN")]
+        domain: String,
+        /// Allowlist to edit
+        #[arg(long = "for", value_enum, default_value_t = DomainList::Cli)]
+        list: DomainList,
+    },
+    /// Remove a domain from a proxy allowlist and reload the proxy
+    DenyDomain {
+        #[arg(value_name = "DOMAIN")]
+        domain: String,
+        /// Allowlist to edit
+        #[arg(long = "for", value_enum, default_value_t = DomainList::Cli)]
+        list: DomainList,
+    },
+    /// Show running cladding projects on this host, with their networks and subnets
+    Ps {
+        #[arg(long, value_enum, default_value_t = PsFormat::Table)]
+        format: PsFormat,
+    },
+    /// List the cli, sandbox and proxy images with their provenance and sizes
+    Images {
+        #[arg(long, value_enum, default_value_t = PsFormat::Table)]
+        format: PsFormat,
+    },
+    /// Show disk used by the project's home and tools directories, volumes and images
+    DiskUsage {
+        /// Warn when the total is over SIZE, e.g. 20GB; cladding.json disk_usage_threshold
+        /// when unset
+        #[arg(long, value_name = "SIZE", value_parser = parse_size_arg)]
+        threshold: Option<u64>,
+        #[arg(long, value_enum, default_value_t = PsFormat::Table)]
+        format: PsFormat,
+    },
+    /// Show pod and container state for the current project
+    Status {
+        /// Redraw every few seconds, adding recent proxy denials and mcp-run activity
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between redraws with --watch
+        #[arg(long, default_value_t = DEFAULT_WATCH_INTERVAL_SECS, requires = "watch")]
+        interval: u64,
+    },
+    /// Serve a local web page with the project's status, proxy denials and mcp-run activity,
+    /// with buttons to reload the proxy and the sandbox policy
+    Dashboard {
+        /// Port on 127.0.0.1 to serve the page on
+        #[arg(long, default_value_t = DEFAULT_DASHBOARD_PORT)]
+        port: u16,
+        /// Seconds between page refreshes
+        #[arg(long, default_value_t = DEFAULT_WATCH_INTERVAL_SECS)]
+        interval: u64,
+    },
+    /// Verify that the cli and sandbox containers can only reach the network via the proxy
+    Verify,
+    /// Diagnose podman, networking and the running services, with a fix for each problem
+    Doctor,
+    /// Collect config, check and doctor output, engine details and recent logs into a
+    /// tarball for bug reports, with secrets redacted
+    SupportBundle {
+        /// Where to write the tarball (default: cladding-support-<name>-<time>.tar.gz)
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Show logs for a project container
+    Logs {
+        /// proxy, sandbox, cli, service/<NAME> or sidecar/<NAME>
+        #[arg(value_name = "TARGET", default_value = "proxy")]
+        target: LogsTarget,
+        /// Keep streaming new log output
+        #[arg(short, long)]
+        follow: bool,
+        /// Only show logs since a timestamp or duration (e.g. 10m)
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+    },
     /// Publish a cli-app TCP port to the host
     Expose(ExposeArgs),
+    /// Run and manage long-running commands in the background of the cli container
+    Service(ServiceArgs),
+    /// Print MCP client configuration for the sandbox's mcp-run server
+    McpConfig {
+        #[arg(long, value_enum, default_value_t = McpConfigFormat::Claude)]
+        format: McpConfigFormat,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PsFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum McpConfigFormat {
+    Claude,
+    GenericJson,
 }
 
 #[derive(Debug, Args)]
@@ -100,6 +325,225 @@ struct ExposeArgs {
     host_port: Option<u16>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LogsTarget {
+    Proxy,
+    Sandbox,
+    Cli,
+    Service(String),
+    Sidecar(String),
+}
+
+impl std::str::FromStr for LogsTarget {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "proxy" => Ok(LogsTarget::Proxy),
+            "sandbox" => Ok(LogsTarget::Sandbox),
+            "cli" => Ok(LogsTarget::Cli),
+            _ => {
+                if let Some(name) = value.strip_prefix("service/") {
+                    validate_service_name(name)?;
+                    Ok(LogsTarget::Service(name.to_string()))
+                } else if let Some(name) = value.strip_prefix("sidecar/") {
+                    validate_sidecar_name(name)?;
+                    Ok(LogsTarget::Sidecar(name.to_string()))
+                } else {
+                    Err(format!(
+                        "unknown target '{value}' \
+                         (expected proxy, sandbox, cli, service/<NAME> or sidecar/<NAME>)"
+                    ))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct ServiceArgs {
+    #[command(subcommand)]
+    command: ServiceSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ServiceSubcommand {
+    /// Start a command detached in the cli container under a tracked name
+    Start {
+        #[arg(value_name = "NAME", value_parser = parse_service_name)]
+        name: String,
+        #[arg(long = "env", value_name = "KEY[=VALUE]", action = ArgAction::Append)]
+        env: Vec<String>,
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Stop a service and its child processes
+    Stop {
+        #[arg(value_name = "NAME", value_parser = parse_service_name)]
+        name: String,
+    },
+    /// List services started in the cli container
+    Ls,
+}
+
+fn parse_service_name(value: &str) -> std::result::Result<String, String> {
+    validate_service_name(value).map(|()| value.to_string())
+}
+
+fn parse_size_arg(value: &str) -> std::result::Result<u64, String> {
+    parse_size(value).ok_or_else(|| format!("expected a size such as 20GB, not '{value}'"))
+}
+
+#[derive(Debug, Args)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigSubcommand {
+    /// Check cladding.json against its schema, reporting every problem at once
+    Validate,
+}
+
+#[derive(Debug, Args)]
+struct PolicyArgs {
+    #[command(subcommand)]
+    command: PolicySubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum PolicySubcommand {
+    /// Write a skeleton COMMAND.rego and COMMAND_test.json to config/sandbox_commands
+    New {
+        #[arg(value_name = "COMMAND")]
+        command: String,
+    },
+}
+
+#[derive(Debug, Args)]
+struct VolumeArgs {
+    #[command(subcommand)]
+    command: VolumeSubcommand,
+}
+
+/// Volumes are named as in cladding.json; the engine stores them as `<project>-<name>`.
+#[derive(Debug, Subcommand)]
+enum VolumeSubcommand {
+    /// List the project's volumes and the mounts using them
+    Ls,
+    /// Create a volume before the first `up`
+    Create {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+    /// Remove volumes and their data
+    Rm {
+        #[arg(value_name = "NAME", required = true)]
+        names: Vec<String>,
+        /// Also remove volumes cladding.json still mounts
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the engine's details of a volume as JSON
+    Inspect {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
+#[derive(Debug, Args)]
+struct PoolArgs {
+    #[command(subcommand)]
+    command: PoolSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum PoolSubcommand {
+    /// Start members until COUNT are running, and stop free members beyond COUNT
+    Up {
+        #[arg(value_name = "COUNT")]
+        count: usize,
+    },
+    /// List running members and the job each one is assigned to
+    Ls,
+    /// Assign a free member to a job and print its name
+    Assign {
+        /// Job recorded with the assignment, shown by 'pool ls'
+        #[arg(long, value_name = "ID")]
+        job: Option<String>,
+    },
+    /// Return an assigned member to the pool
+    Release {
+        #[arg(value_name = "MEMBER")]
+        member: String,
+    },
+}
+
+#[derive(Debug, Args)]
+struct ProxyArgs {
+    #[command(subcommand)]
+    command: ProxySubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ProxySubcommand {
+    /// Request a URL through the proxy and report whether squid allows it
+    Test {
+        #[arg(value_name = "URL")]
+        url: String,
+        /// Container the request is sent from
+        #[arg(long, value_enum, default_value_t = ProxyTestSource::Cli)]
+        from: ProxyTestSource,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PodRole {
+    Proxy,
+    Sandbox,
+    Cli,
+}
+
+impl PodRole {
+    fn pod_name(self, network_settings: &cladding::network::NetworkSettings) -> &str {
+        match self {
+            PodRole::Proxy => &network_settings.proxy_pod_name,
+            PodRole::Sandbox => &network_settings.sandbox_pod_name,
+            PodRole::Cli => &network_settings.cli_pod_name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DomainList {
+    Cli,
+    Sandbox,
+}
+
+impl DomainList {
+    fn file_name(self) -> &'static str {
+        match self {
+            DomainList::Cli => "cli_domains.lst",
+            DomainList::Sandbox => "sandbox_domains.lst",
+        }
+    }
+}
+
+/// Containers of a project's pods that `cladding run --container` can exec into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RunContainer {
+    CliApp,
+    SandboxApp,
+    Proxy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ProxyTestSource {
+    Cli,
+    Sandbox,
+}
+
 #[derive(Debug, Subcommand)]
 enum ExposeSubcommand {
     /// Remove a published host port for the current project
@@ -113,33 +557,175 @@ enum ExposeSubcommand {
 
 pub fn run() -> Result<()> {
     let cli = Cli::parse();
+    if cli.no_color {
+        output::disable_color();
+    }
+    progress::set_log_format(cli.log_format);
     let command = cli.command.unwrap();
 
     let cwd = env::current_dir().with_context(|| "failed to determine current directory")?;
     let project_root = resolve_project_root(&cwd, cli.project_root.as_ref(), &command)?;
 
-    let context = Context { project_root };
+    let engine = resolve_engine(&project_root)?;
+    select_engine(engine);
+    let context = Context {
+        project_root,
+        runtime: engine_runtime(engine),
+        pool_member: env::var(POOL_MEMBER_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+    };
+
+    if cli.force_unlock
+        && let Some(owner) = force_unlock(&context.project_root)?
+    {
+        output::note(format!("removed project lock held by {owner}"));
+    }
+    let _lock = match command.lock_name() {
+        Some(name) if context.project_root.is_dir() => {
+            Some(acquire_project_lock(&context.project_root, name, cli.wait)?)
+        }
+        _ => None,
+    };
 
     match command {
-        CommandSpec::Build => cmd_build(&context),
+        CommandSpec::Build { tags, push } => cmd_build(&context, &tags, push),
         CommandSpec::Init {
             name,
             update_scripts,
-        } => cmd_init(&context, name.as_deref(), update_scripts),
-        CommandSpec::Check => cmd_check(&context),
-        CommandSpec::Up => cmd_up(&context),
-        CommandSpec::Down => cmd_down(&context),
-        CommandSpec::Destroy => cmd_destroy(&context),
-        CommandSpec::Run { env, args } => cmd_run(&context, &env, &args),
+            from_devcontainer,
+        } => cmd_init(&context, name.as_deref(), update_scripts, from_devcontainer),
+        CommandSpec::Check { fix } => cmd_check(&context, fix),
+        CommandSpec::Up {
+            watch: false,
+            fix_network,
+        } => cmd_up(&context, fix_network),
+        CommandSpec::Up { watch: true, .. } => cmd_up_watch(&context, cli.wait),
+        CommandSpec::Down { all: false, .. } => cmd_down(&context),
+        CommandSpec::Down { all: true, yes } => {
+            cmd_all_projects(&context, AllProjectsAction::Down, yes, cli.wait)
+        }
+        CommandSpec::Restart { pod } => cmd_restart(&context, pod),
+        CommandSpec::Destroy {
+            all: false,
+            network,
+            ..
+        } => cmd_destroy(&context, network),
+        CommandSpec::Destroy {
+            all: true,
+            yes,
+            network,
+        } => cmd_all_projects(&context, AllProjectsAction::Destroy { network }, yes, cli.wait),
+        CommandSpec::Pool(args) => cmd_pool(&context, &args),
+        CommandSpec::Config(args) => cmd_config(&context, &args),
+        CommandSpec::Policy(args) => cmd_policy(&context, &args),
+        CommandSpec::Volume(args) => cmd_volume(&context, &args),
+        CommandSpec::Run {
+            env,
+            agent,
+            sandbox,
+            container,
+            args,
+        } => {
+            let container = if sandbox {
+                RunContainer::SandboxApp
+            } else {
+                container.unwrap_or(RunContainer::CliApp)
+            };
+            cmd_run(&context, &env, agent.as_deref(), container, &args)
+        }
+        CommandSpec::Shell { env, agent } => cmd_shell(&context, &env, agent.as_deref()),
         CommandSpec::RunWithScissors { env, args } => cmd_run_with_scissors(&context, &env, &args),
+        CommandSpec::ExecProxy { env, args } => cmd_exec_proxy(&context, &env, &args),
         CommandSpec::ReloadProxy => cmd_reload_proxy(&context),
-        CommandSpec::Ps => cmd_ps(&context),
+        CommandSpec::Proxy(args) => cmd_proxy(&context, &args),
+        CommandSpec::AllowDomain { domain, list } => {
+            cmd_edit_domain_list(&context, list, &domain, true)
+        }
+        CommandSpec::DenyDomain { domain, list } => {
+            cmd_edit_domain_list(&context, list, &domain, false)
+        }
+        CommandSpec::Ps { format } => cmd_ps(&context, format),
+        CommandSpec::Images { format } => cmd_images(&context, format),
+        CommandSpec::DiskUsage { threshold, format } => {
+            cmd_disk_usage(&context, threshold, format)
+        }
+        CommandSpec::Status { watch, interval } => cmd_status(&context, watch, interval),
+        CommandSpec::Dashboard { port, interval } => cmd_dashboard(&context, port, interval),
+        CommandSpec::Verify => cmd_verify(&context),
+        CommandSpec::Doctor => cmd_doctor(&context),
+        CommandSpec::SupportBundle { output } => cmd_support_bundle(&context, output.as_deref()),
+        CommandSpec::Logs {
+            target,
+            follow,
+            since,
+        } => cmd_logs(&context, target, follow, since.as_deref()),
         CommandSpec::Expose(args) => cmd_expose(&context, &args),
+        CommandSpec::Service(args) => cmd_service(&context, &args),
+        CommandSpec::McpConfig { format } => cmd_mcp_config(&context, format),
+    }
+}
+
+impl CommandSpec {
+    /// Name recorded in the project lock, or `None` for read-only commands.
+    fn lock_name(&self) -> Option<&'static str> {
+        match self {
+            CommandSpec::Build { .. } => Some("build"),
+            CommandSpec::Init { .. } => Some("init"),
+            CommandSpec::Check { fix: true } => Some("check --fix"),
+            CommandSpec::Up { watch: false, .. } => Some("up"),
+            // Holds the lock only while applying changes, so `down` can still stop it.
+            CommandSpec::Up { watch: true, .. } => None,
+            CommandSpec::Down { all: false, .. } => Some("down"),
+            CommandSpec::Restart { .. } => Some("restart"),
+            CommandSpec::Destroy { all: false, .. } => Some("destroy"),
+            // Each project is locked in turn instead.
+            CommandSpec::Down { all: true, .. } | CommandSpec::Destroy { all: true, .. } => None,
+            CommandSpec::Pool(args) => match args.command {
+                PoolSubcommand::Up { .. } => Some("pool up"),
+                PoolSubcommand::Assign { .. } => Some("pool assign"),
+                PoolSubcommand::Release { .. } => Some("pool release"),
+                PoolSubcommand::Ls => None,
+            },
+            CommandSpec::AllowDomain { .. } => Some("allow-domain"),
+            CommandSpec::DenyDomain { .. } => Some("deny-domain"),
+            CommandSpec::Policy(_) => Some("policy new"),
+            CommandSpec::Volume(args) => match args.command {
+                VolumeSubcommand::Create { .. } => Some("volume create"),
+                VolumeSubcommand::Rm { .. } => Some("volume rm"),
+                VolumeSubcommand::Ls | VolumeSubcommand::Inspect { .. } => None,
+            },
+            CommandSpec::Expose(args) => match args.command {
+                Some(ExposeSubcommand::List) => None,
+                Some(ExposeSubcommand::Stop { .. }) => Some("expose stop"),
+                None => Some("expose"),
+            },
+            CommandSpec::Check { fix: false }
+            | CommandSpec::Run { .. }
+            | CommandSpec::Shell { .. }
+            | CommandSpec::RunWithScissors { .. }
+            | CommandSpec::ExecProxy { .. }
+            | CommandSpec::ReloadProxy
+            | CommandSpec::Proxy(_)
+            | CommandSpec::Config(_)
+            | CommandSpec::Ps { .. }
+            | CommandSpec::Images { .. }
+            | CommandSpec::DiskUsage { .. }
+            | CommandSpec::Status { .. }
+            | CommandSpec::Dashboard { .. }
+            | CommandSpec::Verify
+            | CommandSpec::Doctor
+            | CommandSpec::SupportBundle { .. }
+            | CommandSpec::Logs { .. }
+            | CommandSpec::Service(_)
+            | CommandSpec::McpConfig { .. } => None,
+        }
     }
 }
 
 pub fn print_error_and_exit(err: Error) -> ! {
-    eprintln!("{err}");
+    output::error(&err);
     std::process::exit(err.exit_code());
 }
 
@@ -170,1012 +756,366 @@ fn resolve_project_root(
         Some(root) => Ok(root),
         None => match command {
             CommandSpec::Init { .. } => Ok(cwd.join(".cladding")),
-            CommandSpec::Ps => Ok(cwd.join(".cladding")),
+            CommandSpec::Ps { .. }
+            | CommandSpec::Down { all: true, .. }
+            | CommandSpec::Destroy { all: true, .. } => Ok(cwd.join(".cladding")),
             _ => {
-                eprintln!(
-                    "error: no .cladding directory found in {} or any parent directory",
+                output::error(format!(
+                    "no .cladding directory found in {} or any parent directory",
                     cwd.display()
-                );
-                eprintln!("hint: run 'cladding init' from the project directory to create one");
+                ));
+                output::hint("run 'cladding init' from the project directory to create one");
                 Err(Error::message("missing .cladding"))
             }
         },
     }
 }
 
-fn cmd_build(context: &Context) -> Result<()> {
-    let config = load_cladding_config(&context.project_root)?;
-
-    let host_uid = unsafe { libc::getuid() };
-    let host_gid = unsafe { libc::getgid() };
-
-    let tools_dir = context.project_root.join("tools");
-    if is_broken_symlink(&tools_dir)? {
-        eprintln!("missing: tools (broken symlink at {})", tools_dir.display());
-        eprintln!("hint: create or relink {}", tools_dir.display());
-        return Err(Error::message("missing tools"));
-    }
-
-    let tools_bin_dir = tools_dir.join("bin");
-    fs::create_dir_all(&tools_bin_dir).with_context(|| "failed to create tools directory")?;
-
-    write_embedded_tools(&tools_bin_dir)?;
-
-    let mut cli_image_built = false;
-    if config.cli_image == DEFAULT_CLI_BUILD_IMAGE {
-        podman_build_image(&config.cli_image, host_uid, host_gid)?;
-        cli_image_built = true;
-    } else {
-        println!(
-            "skip: not building cli image (config cli_image is {}, build target is {})",
-            config.cli_image, DEFAULT_CLADDING_BUILD_IMAGE
-        );
-    }
-
-    if config.sandbox_image == DEFAULT_SANDBOX_BUILD_IMAGE {
-        if config.sandbox_image == config.cli_image && cli_image_built {
-            println!(
-                "skip: sandbox image already built (config cli_image and sandbox_image are both {})",
-                config.sandbox_image
-            );
-        } else {
-            podman_build_image(&config.sandbox_image, host_uid, host_gid)?;
-        }
-    } else {
-        println!(
-            "skip: not building sandbox image (config sandbox_image is {}, build target is {})",
-            config.sandbox_image, DEFAULT_CLADDING_BUILD_IMAGE
-        );
-    }
-
-    Ok(())
-}
-
-fn cmd_init(context: &Context, name_override: Option<&str>, update_scripts: bool) -> Result<()> {
-    let project_root = &context.project_root;
-    let config_dir = project_root.join("config");
-    let scripts_dir = project_root.join("scripts");
-    let home_dir = project_root.join("home");
-    let tools_dir = project_root.join("tools");
-    let cladding_config = project_root.join("cladding.json");
-    let cladding_gitignore = project_root.join(".gitignore");
-
-    if project_root.exists() && !project_root.is_dir() {
-        eprintln!(
-            "error: .cladding path exists but is not a directory: {}",
-            project_root.display()
-        );
-        return Err(Error::message("invalid .cladding path"));
-    }
-
-    let project_root_created = !project_root.exists();
-    fs::create_dir_all(project_root)
-        .with_context(|| format!("failed to create {}", project_root.display()))?;
-
-    if project_root_created {
-        fs::write(&cladding_gitignore, "*\n")
-            .with_context(|| format!("failed to write {}", cladding_gitignore.display()))?;
-    }
-
-    if config_dir.exists() || path_is_symlink(&config_dir) {
-        println!("config already exists: {}", config_dir.display());
-    } else {
-        fs::create_dir_all(&config_dir)
-            .with_context(|| format!("failed to create {}", config_dir.display()))?;
-        println!("initialized: {}", config_dir.display());
-    }
-
-    materialize_config(&config_dir)?;
-
-    if scripts_dir.exists() || path_is_symlink(&scripts_dir) {
-        println!("scripts already exists: {}", scripts_dir.display());
-    } else {
-        fs::create_dir_all(&scripts_dir)
-            .with_context(|| format!("failed to create {}", scripts_dir.display()))?;
-        println!("initialized: {}", scripts_dir.display());
-    }
-
-    if home_dir.exists() || path_is_symlink(&home_dir) {
-        println!("home already exists: {}", home_dir.display());
-    } else {
-        fs::create_dir_all(&home_dir)
-            .with_context(|| format!("failed to create {}", home_dir.display()))?;
-        println!("initialized: {}", home_dir.display());
-    }
-
-    if tools_dir.exists() || path_is_symlink(&tools_dir) {
-        println!("tools already exists: {}", tools_dir.display());
-    } else {
-        fs::create_dir_all(&tools_dir)
-            .with_context(|| format!("failed to create {}", tools_dir.display()))?;
-        println!("initialized: {}", tools_dir.display());
-    }
-
-    if update_scripts {
-        materialize_scripts_force(&scripts_dir)?;
-    } else {
-        materialize_scripts(&scripts_dir)?;
-    }
-
-    if cladding_config.exists() {
-        println!(
-            "cladding config already exists: {}",
-            cladding_config.display()
-        );
-    } else {
-        let generated = write_default_cladding_config(
-            name_override,
-            DEFAULT_SANDBOX_BUILD_IMAGE,
-            DEFAULT_CLI_BUILD_IMAGE,
-        )?;
-        fs::write(&cladding_config, generated)
-            .with_context(|| format!("failed to write {}", cladding_config.display()))?;
-        println!("generated: {}", cladding_config.display());
-    }
-
-    Ok(())
-}
-
-fn cmd_check(context: &Context) -> Result<()> {
-    check_required_binaries(context)?;
-    let config = load_cladding_config(&context.project_root)?;
-    let network_settings = resolve_network_settings(&config.name, 0)?;
-    check_required_host_paths(context, &config, &network_settings)?;
-    check_required_config_files(context)?;
-    check_required_scripts_files(context)?;
-    check_required_images(&config)?;
-    println!("check: ok");
-    Ok(())
-}
-
-fn check_required_binaries(context: &Context) -> Result<()> {
-    let mut missing = false;
-    let bin_dir = context.project_root.join("tools/bin");
-
-    for name in ["mcp-run", "run-with-network"] {
-        let path = bin_dir.join(name);
-        if !is_executable(&path) {
-            eprintln!("missing: tools/bin/{name} ({})", path.display());
-            eprintln!("hint: run cladding build");
-            missing = true;
+/// cladding.json, with the name of the pool member from `CLADDING_POOL_MEMBER` if one is set.
+fn load_config(context: &Context) -> Result<Config> {
+    let mut config = load_cladding_config(&context.project_root)?;
+    if let Some(member) = &context.pool_member {
+        if parse_pool_member_index(&config.name, member).is_none() {
+            output::error(format!(
+                "{POOL_MEMBER_ENV}={member} is not a pool member of project '{}'",
+                config.name
+            ));
+            output::hint("use a name printed by 'cladding pool assign' or 'cladding pool ls'");
+            return Err(Error::message("invalid pool member"));
         }
+        config.name = member.clone();
     }
-
-    if missing {
-        return Err(Error::message("missing tools binaries"));
-    }
-
-    Ok(())
+    Ok(config)
 }
 
-fn check_required_config_files(context: &Context) -> Result<()> {
-    let dst = context.project_root.join("config");
-    let mut missing = false;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for name in config_top_level_entries() {
-        let path = dst.join(&name);
-        if !path.exists() {
-            eprintln!("missing: config/{name} ({})", path.display());
-            missing = true;
+    #[test]
+    fn expose_create_args_parse_without_subcommand() {
+        let cli = Cli::try_parse_from(["cladding", "expose", "3000", "9000"]).expect("cli parse");
+        match cli.command.expect("command") {
+            CommandSpec::Expose(args) => {
+                assert!(args.command.is_none());
+                assert_eq!(args.container_port, Some(3000));
+                assert_eq!(args.host_port, Some(9000));
+            }
+            other => panic!("unexpected command: {other:?}"),
         }
     }
 
-    if missing {
-        eprintln!(
-            "hint: run cladding init, or add missing top-level entries into {}",
-            dst.display()
-        );
-        return Err(Error::message("missing config files"));
-    }
-
-    Ok(())
-}
-
-fn check_required_scripts_files(context: &Context) -> Result<()> {
-    let dst = context.project_root.join("scripts");
-    let mut missing = false;
-
-    for name in scripts_top_level_entries() {
-        let path = dst.join(&name);
-        if !path.exists() {
-            eprintln!("missing: scripts/{name} ({})", path.display());
-            missing = true;
+    #[test]
+    fn expose_stop_subcommand_parses() {
+        let cli =
+            Cli::try_parse_from(["cladding", "expose", "stop", "9000"]).expect("cli parse");
+        match cli.command.expect("command") {
+            CommandSpec::Expose(ExposeArgs {
+                command: Some(ExposeSubcommand::Stop { host_port }),
+                ..
+            }) => assert_eq!(host_port, 9000),
+            other => panic!("unexpected command: {other:?}"),
         }
     }
 
-    if missing {
-        eprintln!(
-            "hint: run cladding init, or add missing top-level entries into {}",
-            dst.display()
-        );
-        return Err(Error::message("missing scripts files"));
+    #[test]
+    fn lock_flags_are_global() {
+        let cli = Cli::try_parse_from(["cladding", "down", "--wait", "--force-unlock"])
+            .expect("cli parse");
+        assert!(cli.wait);
+        assert!(cli.force_unlock);
+        assert_eq!(cli.command.expect("command").lock_name(), Some("down"));
     }
 
-    Ok(())
-}
-
-fn warn_on_script_mismatch(context: &Context) -> Result<()> {
-    let dst = context.project_root.join("scripts");
-    let mut warned = false;
-
-    for (rel_path, contents) in scripts_files() {
-        let target = dst.join(&rel_path);
-        match fs::read(&target) {
-            Ok(existing) => {
-                if existing != contents {
-                    eprintln!(
-                        "warning: scripts/{} differs from embedded version",
-                        rel_path.display()
-                    );
-                    warned = true;
-                }
-            }
-            Err(_) => {
-                eprintln!("warning: scripts/{} is missing", rel_path.display());
-                warned = true;
+    #[test]
+    fn build_push_requires_a_tag() {
+        let cli = Cli::try_parse_from([
+            "cladding",
+            "build",
+            "--tag",
+            "registry.example.com/team/cladding:1",
+            "--tag",
+            "registry.example.com/team/cladding:latest",
+            "--push",
+        ])
+        .expect("cli parse");
+        match cli.command.expect("command") {
+            CommandSpec::Build { tags, push } => {
+                assert_eq!(tags.len(), 2);
+                assert!(push);
             }
+            other => panic!("unexpected command: {other:?}"),
         }
-    }
-
-    if warned {
-        eprintln!("hint: run cladding init --update-scripts to re-materialize scripts");
-    }
-
-    Ok(())
-}
 
-fn check_required_host_paths(
-    context: &Context,
-    config: &Config,
-    network_settings: &cladding::network::NetworkSettings,
-) -> Result<()> {
-    let rendered = render_pods_yaml(&context.project_root, config, network_settings);
-
-    let mut missing = false;
-    let mut seen = std::collections::HashSet::new();
-    for path in host_paths_from_rendered(&rendered) {
-        if !seen.insert(path.clone()) {
-            continue;
-        }
-        let host_path = Path::new(&path);
-        if !host_path.exists() {
-            eprintln!("missing: hostPath {}", host_path.display());
-            eprintln!("hint: create or relink {}", host_path.display());
-            missing = true;
-        }
+        assert!(Cli::try_parse_from(["cladding", "build", "--push"]).is_err());
     }
 
-    if missing {
-        return Err(Error::message("missing host paths"));
+    #[test]
+    fn up_watch_parses_and_locks_per_apply() {
+        let cli = Cli::try_parse_from(["cladding", "up", "--watch"]).expect("cli parse");
+        let command = cli.command.expect("command");
+        assert!(matches!(command, CommandSpec::Up { watch: true, .. }));
+        assert_eq!(command.lock_name(), None);
+
+        let cli = Cli::try_parse_from(["cladding", "up"]).expect("cli parse");
+        assert_eq!(cli.command.expect("command").lock_name(), Some("up"));
     }
 
-    Ok(())
-}
-
-fn check_required_images(config: &Config) -> Result<()> {
-    let mut missing = false;
-    for image in [&config.cli_image, &config.sandbox_image] {
-        let status = Command::new("podman")
-            .args(["image", "exists", image])
-            .status();
-
-        match status {
-            Ok(status) if status.success() => {}
-            Ok(_) => {
-                eprintln!("missing: image {image}");
-                if image_is_buildable_by_cladding(image) {
-                    eprintln!("hint: run cladding build");
-                } else {
-                    eprintln!(
-                        "hint: pull/tag image '{image}', or set cladding.json image to a supported build target and run cladding build"
-                    );
+    #[test]
+    fn run_selects_its_container() {
+        let container_of = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).expect("cli parse");
+            match cli.command.expect("command") {
+                CommandSpec::Run {
+                    sandbox,
+                    container,
+                    args,
+                    ..
+                } => {
+                    assert_eq!(args, vec!["ls".to_string()]);
+                    (sandbox, container)
                 }
-                missing = true;
-            }
-            Err(err) => {
-                eprintln!("error: failed to check image {image}: {err}");
-                return Err(Error::message("failed to check image"));
+                other => panic!("unexpected command: {other:?}"),
             }
-        }
-    }
-
-    if missing {
-        return Err(Error::message("missing required images"));
-    }
-
-    Ok(())
-}
-
-struct ProjectRuntimeStatus {
-    current_project_root: String,
-    already_running: bool,
-}
-
-fn current_project_root(context: &Context) -> Result<String> {
-    Ok(canonicalize_path(&context.project_root)?
-        .display()
-        .to_string())
-}
-
-fn project_runtime_status(context: &Context, config: &Config) -> Result<ProjectRuntimeStatus> {
-    let current_project_root = current_project_root(context)?;
-
-    let mut conflicting_roots = Vec::new();
-    let mut already_running = false;
-    for project in list_running_projects()? {
-        if project.name != config.name {
-            continue;
-        }
-
-        let normalized_root = canonicalize_path(Path::new(&project.project_root))
-            .map(|path| path.display().to_string())
-            .unwrap_or_else(|_| project.project_root.clone());
-
-        if normalized_root == current_project_root {
-            already_running = true;
-        } else {
-            conflicting_roots.push(project.project_root);
-        }
-    }
-
-    if !conflicting_roots.is_empty() {
-        eprintln!(
-            "error: cladding project '{}' is already running from a different PROJECT_ROOT",
-            config.name
-        );
-        eprintln!("current PROJECT_ROOT: {current_project_root}");
-        for root in conflicting_roots {
-            eprintln!("running PROJECT_ROOT: {root}");
-        }
-        return Err(Error::message(
-            "project already running from different PROJECT_ROOT",
-        ));
-    }
-
-    Ok(ProjectRuntimeStatus {
-        current_project_root,
-        already_running,
-    })
-}
-
-fn cmd_up(context: &Context) -> Result<()> {
-    let config = load_cladding_config(&context.project_root)?;
-    let status = project_runtime_status(context, &config)?;
-
-    if status.already_running {
-        println!(
-            "already running: {} ({})",
-            config.name, status.current_project_root
+        };
+        assert_eq!(container_of(&["cladding", "run", "ls"]), (false, None));
+        assert_eq!(container_of(&["cladding", "run", "--sandbox", "ls"]), (true, None));
+        assert_eq!(
+            container_of(&["cladding", "run", "--container", "proxy", "ls"]),
+            (false, Some(RunContainer::Proxy))
         );
-        return Ok(());
-    }
-
-    check_required_binaries(context)?;
-    let network_settings = select_available_network_settings(&config.name)?;
-    check_required_images(&config)?;
-    check_required_host_paths(context, &config, &network_settings)?;
-    check_required_config_files(context)?;
-    check_required_scripts_files(context)?;
-    warn_on_script_mismatch(context)?;
-    let rendered = render_pods_yaml(&context.project_root, &config, &network_settings);
-    podman_play_kube(&rendered, &network_settings, false)
-}
-
-fn cmd_down(context: &Context) -> Result<()> {
-    let config = load_cladding_config(&context.project_root)?;
-    let project_root = current_project_root(context)?;
-    let network_settings =
-        resolve_active_project_network_settings(context, &config, "cladding down")?;
-    let rendered = render_pods_yaml(&context.project_root, &config, &network_settings);
-    let pod_result = podman_play_kube(&rendered, &network_settings, true);
-    let cleanup_result = remove_project_expose_proxies(&config, &project_root, true);
-
-    pod_result?;
-    cleanup_result
-}
-
-fn cmd_destroy(context: &Context) -> Result<()> {
-    let config = load_cladding_config(&context.project_root)?;
-    let project_root = current_project_root(context)?;
-    let network_settings =
-        resolve_active_project_network_settings(context, &config, "cladding destroy")?;
-
-    let status = Command::new("podman")
-        .args([
-            "rm",
-            "-f",
-            &network_settings.cli_pod_name,
-            &network_settings.sandbox_pod_name,
-            &network_settings.proxy_pod_name,
-        ])
-        .status()
-        .with_context(|| "failed to run podman rm")?;
-
-    let destroy_result = cladding::podman::ensure_success(status, "podman rm");
-    let cleanup_result = remove_project_expose_proxies(&config, &project_root, true);
-
-    destroy_result?;
-    cleanup_result
-}
-
-fn cmd_ps(_context: &Context) -> Result<()> {
-    podman_required("podman (required for cladding ps)")?;
-    let projects = list_running_projects()?;
-    if projects.is_empty() {
-        println!("no running cladding projects");
-        return Ok(());
-    }
-
-    println!("running cladding projects:");
-    for project in projects {
-        println!(
-            "{}  {}  (pods: {})",
-            project.name, project.project_root, project.pod_count
+        assert_eq!(
+            container_of(&["cladding", "run", "--container", "sandbox-app", "ls"]),
+            (false, Some(RunContainer::SandboxApp))
         );
-    }
-
-    Ok(())
-}
 
-fn cmd_expose(context: &Context, args: &ExposeArgs) -> Result<()> {
-    match &args.command {
-        Some(ExposeSubcommand::Stop { host_port }) => cmd_expose_stop(context, *host_port),
-        Some(ExposeSubcommand::List) => cmd_expose_list(context),
-        None => {
-            let Some(container_port) = args.container_port else {
-                return Err(Error::message("missing container port"));
-            };
-            cmd_expose_create(context, container_port, args.host_port)
+        for args in [
+            ["cladding", "run", "--sandbox", "--container", "proxy", "ls"].as_slice(),
+            &["cladding", "run", "--sandbox", "--agent", "reviewer", "ls"],
+            &["cladding", "run", "--container", "sidecar", "ls"],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
         }
     }
-}
-
-fn cmd_run(context: &Context, env_vars: &[String], args: &[String]) -> Result<()> {
-    let config = load_cladding_config(&context.project_root)?;
-    let network_settings =
-        resolve_active_project_network_settings(context, &config, "cladding run")?;
-    let container_name = format!("{}-cli-app", network_settings.cli_pod_name);
-    run_podman_exec(context, &config, "run", &container_name, env_vars, args)
-}
-
-fn cmd_run_with_scissors(context: &Context, env_vars: &[String], args: &[String]) -> Result<()> {
-    let config = load_cladding_config(&context.project_root)?;
-    let network_settings =
-        resolve_active_project_network_settings(context, &config, "cladding run-with-scissors")?;
-    let container_name = format!("{}-sandbox-app", network_settings.sandbox_pod_name);
-    run_podman_exec(
-        context,
-        &config,
-        "run-with-scissors",
-        &container_name,
-        env_vars,
-        args,
-    )
-}
-
-fn run_podman_exec(
-    context: &Context,
-    config: &Config,
-    command_name: &str,
-    container_name: &str,
-    env_vars: &[String],
-    args: &[String],
-) -> Result<()> {
-    if args.is_empty() {
-        eprintln!("usage: cladding {command_name} [--env KEY[=VALUE] ...] <command> [args...]");
-        return Err(Error::message(format!("missing {command_name} command")));
-    }
-
-    let status = project_runtime_status(context, config)?;
-    if !status.already_running {
-        eprintln!("error: cladding project '{}' is not running", config.name);
-        eprintln!("hint: run 'cladding up'");
-        return Err(Error::message("project is not running"));
-    }
-
-    let project_dir = context
-        .project_root
-        .parent()
-        .ok_or_else(|| Error::message("could not resolve project directory"))?
-        .to_path_buf();
-
-    let cwd = env::current_dir().with_context(|| "failed to determine current directory")?;
-
-    let project_dir = canonicalize_path(&project_dir)?;
-    let cwd = canonicalize_path(&cwd)?;
-
-    let workdir_rel = cwd.strip_prefix(&project_dir).map_err(|_| {
-        eprintln!(
-            "error: could not determine current path relative to project dir ({}): {}",
-            project_dir.display(),
-            cwd.display()
-        );
-        eprintln!(
-            "hint: run cladding from {} or one of its subdirectories",
-            project_dir.display()
-        );
-        Error::message("invalid working directory")
-    })?;
-
-    let mut container_workdir = PathBuf::from("/home/user/workspace");
-    if !workdir_rel.as_os_str().is_empty() {
-        container_workdir = container_workdir.join(workdir_rel);
-    }
 
-    let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
-
-    let mut cmd = Command::new("podman");
-    if interactive {
-        let colorterm = env::var("COLORTERM").unwrap_or_else(|_| "truecolor".to_string());
-        let force_color = env::var("FORCE_COLOR").unwrap_or_else(|_| "3".to_string());
-        cmd.args([
-            "exec",
-            "-it",
-            "-w",
-            &container_workdir.display().to_string(),
-            "--env",
-            "LANG=C.UTF-8",
-            "--env",
-            "TERM=xterm-256color",
-            "--env",
-            &format!("COLORTERM={colorterm}"),
-            "--env",
-            &format!("FORCE_COLOR={force_color}"),
-        ]);
-    } else {
-        cmd.args([
-            "exec",
-            "-i",
-            "-w",
-            &container_workdir.display().to_string(),
+    #[test]
+    fn exec_sandbox_is_an_alias_for_run_with_scissors() {
+        let cli = Cli::try_parse_from([
+            "cladding",
+            "exec-sandbox",
             "--env",
-            "LANG=C.UTF-8",
-        ]);
-    }
-
-    for env_var in env_vars {
-        cmd.arg("--env").arg(env_var);
-    }
-
-    cmd.arg(container_name);
-
-    for arg in args {
-        cmd.arg(arg);
-    }
-
-    let mut child = cmd
-        .spawn()
-        .with_context(|| format!("failed to run podman exec for {command_name}"))?;
-
-    let mut signal_handle = None;
-    let mut signal_thread = None;
-    if !interactive {
-        let kill_pattern = args.join(" ");
-        let mut signals = Signals::new([SIGINT, SIGTERM])
-            .with_context(|| "failed to install signal handlers")?;
-        signal_handle = Some(signals.handle());
-        let container_name = container_name.to_string();
-        signal_thread = Some(thread::spawn(move || {
-            if signals.forever().next().is_some() {
-                if !kill_pattern.is_empty() {
-                    let _ = Command::new("podman")
-                        .args([
-                            "exec",
-                            &container_name,
-                            "pkill",
-                            "-f",
-                            &kill_pattern,
-                        ])
-                        .status();
-                }
+            "FOO=bar",
+            "ls",
+            "-la",
+        ])
+        .expect("cli parse");
+        let command = cli.command.expect("command");
+        assert_eq!(command.lock_name(), None);
+        match command {
+            CommandSpec::RunWithScissors { env, args } => {
+                assert_eq!(env, vec!["FOO=bar".to_string()]);
+                assert_eq!(args, vec!["ls".to_string(), "-la".to_string()]);
             }
-        }));
-    }
-
-    let status = child
-        .wait()
-        .with_context(|| format!("failed to run podman exec for {command_name}"))?;
-
-    if let Some(handle) = signal_handle {
-        handle.close();
-    }
-    if let Some(thread) = signal_thread {
-        let _ = thread.join();
-    }
-
-    if let Some(code) = status.code() {
-        if code == 0 {
-            Ok(())
-        } else {
-            Err(Error::CommandFailed {
-                context: "podman exec",
-                code,
-            })
+            other => panic!("unexpected command: {other:?}"),
         }
-    } else {
-        Err(Error::message("podman exec failed"))
-    }
-}
-
-fn cmd_reload_proxy(context: &Context) -> Result<()> {
-    let config = load_cladding_config(&context.project_root)?;
-    let network_settings =
-        resolve_active_project_network_settings(context, &config, "cladding reload-proxy")?;
-
-    let status = Command::new("podman")
-        .args([
-            "exec",
-            &format!("{}-proxy", network_settings.proxy_pod_name),
-            "squid",
-            "-k",
-            "reconfigure",
-            "-f",
-            "/tmp/squid_generated.conf",
-        ])
-        .status()
-        .with_context(|| "failed to run podman exec")?;
-
-    cladding::podman::ensure_success(status, "podman exec")
-}
-
-fn cmd_expose_create(context: &Context, container_port: u16, host_port: Option<u16>) -> Result<()> {
-    podman_required("podman (required for cladding expose)")?;
-
-    let config = load_cladding_config(&context.project_root)?;
-    let project_root = current_project_root(context)?;
-    let network_settings =
-        resolve_active_project_network_settings(context, &config, "cladding expose")?;
-    let cli_container_name = format!("{}-cli-app", network_settings.cli_pod_name);
-
-    if !podman_container_exists(&cli_container_name)? {
-        eprintln!(
-            "error: target container '{}' is missing for project '{}'",
-            cli_container_name, config.name
-        );
-        eprintln!("hint: run 'cladding up'");
-        return Err(Error::message("missing cli container"));
     }
 
-    let existing = list_project_expose_proxies(&config.name, &project_root, false)?;
-    if let Some(proxy) = existing
-        .iter()
-        .find(|proxy| proxy.container_port == container_port)
-    {
-        eprintln!(
-            "error: container port {container_port} is already exposed for project '{}' on localhost:{}",
-            config.name, proxy.host_port
-        );
-        return Err(Error::message("container port already exposed"));
-    }
-
-    let start_host_port = host_port.unwrap_or(container_port);
-    for candidate_host_port in start_host_port..=u16::MAX {
-        if !host_port_appears_available(candidate_host_port) {
-            continue;
+    #[test]
+    fn read_only_commands_do_not_lock() {
+        for args in [
+            vec!["cladding", "ps"],
+            vec!["cladding", "check"],
+            vec!["cladding", "expose", "list"],
+            vec!["cladding", "run", "true"],
+        ] {
+            let cli = Cli::try_parse_from(args).expect("cli parse");
+            assert_eq!(cli.command.expect("command").lock_name(), None);
         }
+    }
 
-        match try_start_expose_proxy(
-            &config,
-            &project_root,
-            &network_settings,
-            container_port,
-            candidate_host_port,
-        )? {
-            ExposeCreateOutcome::Started => {
-                println!(
-                    "exposed: localhost:{candidate_host_port} -> {}:{container_port}",
-                    cli_container_name
-                );
-                return Ok(());
+    #[test]
+    fn proxy_test_defaults_to_cli_source() {
+        let cli = Cli::try_parse_from(["cladding", "proxy", "test", "https://example.com"])
+            .expect("cli parse");
+        match cli.command.expect("command") {
+            CommandSpec::Proxy(ProxyArgs {
+                command: ProxySubcommand::Test { url, from },
+            }) => {
+                assert_eq!(url, "https://example.com");
+                assert_eq!(from, ProxyTestSource::Cli);
             }
-            ExposeCreateOutcome::HostPortConflict => continue,
+            other => panic!("unexpected command: {other:?}"),
         }
     }
 
-    eprintln!(
-        "error: could not allocate a free host port starting at {start_host_port}"
-    );
-    Err(Error::message("could not allocate free host port"))
-}
-
-fn cmd_expose_stop(context: &Context, host_port: u16) -> Result<()> {
-    podman_required("podman (required for cladding expose stop)")?;
-
-    let config = load_cladding_config(&context.project_root)?;
-    let project_root = current_project_root(context)?;
-    let proxies = list_project_expose_proxies(&config.name, &project_root, true)?;
-    let matched: Vec<_> = proxies
-        .into_iter()
-        .filter(|proxy| proxy.host_port == host_port)
-        .collect();
-
-    if matched.is_empty() {
-        eprintln!(
-            "error: no expose proxy for project '{}' publishes localhost:{host_port}",
-            config.name
-        );
-        return Err(Error::message("host port not found"));
-    }
-
-    let ids: Vec<String> = matched.iter().map(|proxy| proxy.id.clone()).collect();
-    podman_remove_containers(&ids, true, true)?;
-    println!("stopped: localhost:{host_port}");
-    Ok(())
-}
-
-fn cmd_expose_list(context: &Context) -> Result<()> {
-    podman_required("podman (required for cladding expose list)")?;
-
-    let config = load_cladding_config(&context.project_root)?;
-    let project_root = current_project_root(context)?;
-    let proxies = list_project_expose_proxies(&config.name, &project_root, false)?;
-
-    if proxies.is_empty() {
-        println!("no exposed ports for project '{}'", config.name);
-        return Ok(());
-    }
+    #[test]
+    fn domain_commands_parse_target_list() {
+        let cli =
+            Cli::try_parse_from(["cladding", "allow-domain", "example.com"]).expect("cli parse");
+        assert!(matches!(
+            cli.command,
+            Some(CommandSpec::AllowDomain {
+                ref domain,
+                list: DomainList::Cli,
+            }) if domain == "example.com"
+        ));
 
-    println!("HOST PORT  CONTAINER PORT  STATUS");
-    for proxy in proxies {
-        println!(
-            "{:<9}  {:<14}  {}",
-            proxy.host_port, proxy.container_port, proxy.status
-        );
+        let cli = Cli::try_parse_from([
+            "cladding",
+            "deny-domain",
+            ".example.com",
+            "--for",
+            "sandbox",
+        ])
+        .expect("cli parse");
+        let command = cli.command.expect("command");
+        assert_eq!(command.lock_name(), Some("deny-domain"));
+        assert!(matches!(
+            command,
+            CommandSpec::DenyDomain {
+                list: DomainList::Sandbox,
+                ..
+            }
+        ));
     }
 
-    Ok(())
-}
+    #[test]
+    fn status_watch_parses_interval() {
+        let cli = Cli::try_parse_from(["cladding", "status", "--watch", "--interval", "5"])
+            .expect("cli parse");
+        let command = cli.command.expect("command");
+        assert_eq!(command.lock_name(), None);
+        assert!(matches!(
+            command,
+            CommandSpec::Status {
+                watch: true,
+                interval: 5,
+            }
+        ));
 
-fn remove_project_expose_proxies(config: &Config, project_root: &str, force: bool) -> Result<()> {
-    let proxies = list_project_expose_proxies(&config.name, project_root, true)?;
-    if proxies.is_empty() {
-        return Ok(());
+        assert!(Cli::try_parse_from(["cladding", "status", "--interval", "5"]).is_err());
     }
 
-    let ids: Vec<String> = proxies.into_iter().map(|proxy| proxy.id).collect();
-    podman_remove_containers(&ids, force, true)
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ExposeCreateOutcome {
-    Started,
-    HostPortConflict,
-}
-
-fn try_start_expose_proxy(
-    config: &Config,
-    project_root: &str,
-    network_settings: &cladding::network::NetworkSettings,
-    container_port: u16,
-    host_port: u16,
-) -> Result<ExposeCreateOutcome> {
-    let container_name = unique_expose_proxy_name(&config.name, container_port, host_port);
-    let mut cmd = Command::new("podman");
-    cmd.arg("run")
-        .arg("-d")
-        .arg("--name")
-        .arg(&container_name)
-        .arg("--network")
-        .arg(&network_settings.network)
-        .arg("-p")
-        .arg(format!("{host_port}:{container_port}"));
-
-    for (key, value) in expose_proxy_labels(&config.name, project_root, container_port, host_port) {
-        cmd.arg("--label").arg(format!("{key}={value}"));
+    #[test]
+    fn verify_is_read_only() {
+        let cli = Cli::try_parse_from(["cladding", "verify"]).expect("cli parse");
+        let command = cli.command.expect("command");
+        assert!(matches!(command, CommandSpec::Verify));
+        assert_eq!(command.lock_name(), None);
     }
 
-    cmd.arg("alpine/socat")
-        .arg(format!("TCP-LISTEN:{container_port},fork,reuseaddr"))
-        .arg(format!("TCP:{}:{container_port}", network_settings.cli_ip));
-
-    let output = cmd
-        .output()
-        .with_context(|| "failed to run podman run for cladding expose")?;
-
-    if output.status.success() {
-        return Ok(ExposeCreateOutcome::Started);
+    #[test]
+    fn doctor_is_read_only() {
+        let cli = Cli::try_parse_from(["cladding", "doctor"]).expect("cli parse");
+        let command = cli.command.expect("command");
+        assert!(matches!(command, CommandSpec::Doctor));
+        assert_eq!(command.lock_name(), None);
     }
 
-    if podman_output_is_bind_conflict(&output) {
-        return Ok(ExposeCreateOutcome::HostPortConflict);
+    #[test]
+    fn shell_parses_env() {
+        let cli =
+            Cli::try_parse_from(["cladding", "shell", "--env", "FOO=bar"]).expect("cli parse");
+        match cli.command.expect("command") {
+            CommandSpec::Shell { env, .. } => assert_eq!(env, vec!["FOO=bar".to_string()]),
+            other => panic!("unexpected command: {other:?}"),
+        }
     }
 
-    cladding::podman::ensure_success_output(&output, "podman run")?;
-    Err(Error::message("podman run failed"))
-}
-
-fn expose_proxy_labels(
-    project_name: &str,
-    project_root: &str,
-    container_port: u16,
-    host_port: u16,
-) -> [(&'static str, String); 6] {
-    [
-        ("cladding", project_name.to_string()),
-        ("project_root", project_root.to_string()),
-        ("cladding_expose", "true".to_string()),
-        ("cladding_expose_target", "cli-app".to_string()),
-        (
-            "cladding_expose_container_port",
-            container_port.to_string(),
-        ),
-        ("cladding_expose_host_port", host_port.to_string()),
-    ]
-}
-
-fn unique_expose_proxy_name(project_name: &str, container_port: u16, host_port: u16) -> String {
-    let suffix = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|duration| duration.as_millis())
-        .unwrap_or(0);
-    format!("{project_name}-expose-{container_port}-{host_port}-{suffix}")
-}
-
-fn host_port_appears_available(port: u16) -> bool {
-    TcpListener::bind(("127.0.0.1", port)).is_ok()
-}
-
-fn podman_output_is_bind_conflict(output: &std::process::Output) -> bool {
-    let stderr = String::from_utf8_lossy(&output.stderr).to_ascii_lowercase();
-    stderr.contains("address already in use")
-        || stderr.contains("port is already allocated")
-        || stderr.contains("bind")
-}
-
-fn image_is_buildable_by_cladding(image: &str) -> bool {
-    image == DEFAULT_CLADDING_BUILD_IMAGE
-}
-
-fn select_available_network_settings(name: &str) -> Result<cladding::network::NetworkSettings> {
-    let running = list_running_project_networks()?;
-    let mut used = std::collections::HashSet::new();
-    for project in running {
-        let Some(index) = parse_cladding_pool_index(&project.network) else {
-            continue;
-        };
-        used.insert(index);
-    }
+    #[test]
+    fn mcp_config_parses_format() {
+        let cli = Cli::try_parse_from(["cladding", "mcp-config", "--format", "generic-json"])
+            .expect("cli parse");
+        assert!(matches!(
+            cli.command,
+            Some(CommandSpec::McpConfig {
+                format: McpConfigFormat::GenericJson,
+            })
+        ));
 
-    let mut subnet_to_networks: std::collections::HashMap<String, Vec<String>> =
-        std::collections::HashMap::new();
-    for entry in list_podman_network_subnets()? {
-        subnet_to_networks
-            .entry(entry.subnet)
-            .or_default()
-            .push(entry.name);
+        let cli = Cli::try_parse_from(["cladding", "mcp-config"]).expect("cli parse");
+        assert!(matches!(
+            cli.command,
+            Some(CommandSpec::McpConfig {
+                format: McpConfigFormat::Claude,
+            })
+        ));
     }
 
-    let mut mismatched = 0usize;
-    let mut attempted = 0usize;
-    let mut conflicts = 0usize;
-    for index in 0u16..=255 {
-        let index = index as u8;
-        if !used.contains(&index) {
-            let candidate_subnet = format!("10.90.{index}.0/24");
-            let candidate_network = format!("cladding-{index}");
-            if let Some(names) = subnet_to_networks.get(&candidate_subnet) {
-                if names.iter().any(|name| name != &candidate_network) {
-                    conflicts += 1;
-                    continue;
-                }
-            }
-            let candidate = resolve_network_settings(name, index)?;
-            attempted += 1;
-            match ensure_pool_network_settings(&candidate)? {
-                EnsureNetworkOutcome::Ready => return Ok(candidate),
-                EnsureNetworkOutcome::SubnetMismatch => {
-                    mismatched += 1;
-                    continue;
-                }
+    #[test]
+    fn logs_parses_target_and_flags() {
+        let cli = Cli::try_parse_from(["cladding", "logs", "sandbox", "-f", "--since", "10m"])
+            .expect("cli parse");
+        match cli.command.expect("command") {
+            CommandSpec::Logs {
+                target,
+                follow,
+                since,
+            } => {
+                assert_eq!(target, LogsTarget::Sandbox);
+                assert!(follow);
+                assert_eq!(since.as_deref(), Some("10m"));
             }
+            other => panic!("unexpected command: {other:?}"),
         }
-    }
 
-    eprintln!("error: no free cladding network slots in pool cladding-0..cladding-255");
-    if mismatched > 0 {
-        eprintln!(
-            "hint: {mismatched} cladding-N networks exist with unexpected subnets; remove them with 'podman network rm cladding-N'"
-        );
-    } else if conflicts > 0 {
-        eprintln!(
-            "hint: {conflicts} pool subnets are already used by non-cladding networks; free those subnets or remove the conflicting networks"
-        );
-    } else if attempted == 0 {
-        eprintln!("hint: run 'cladding ps' and stop a running project with 'cladding down'");
-    } else {
-        eprintln!("hint: run 'cladding ps' and stop a running project with 'cladding down'");
+        let cli = Cli::try_parse_from(["cladding", "logs"]).expect("cli parse");
+        assert!(matches!(
+            cli.command,
+            Some(CommandSpec::Logs {
+                target: LogsTarget::Proxy,
+                follow: false,
+                since: None,
+            })
+        ));
     }
-    Err(Error::message("no free cladding network slots"))
-}
-
-fn resolve_active_project_network_settings(
-    context: &Context,
-    config: &Config,
-    command_name: &str,
-) -> Result<cladding::network::NetworkSettings> {
-    let current_project_root = canonicalize_path(&context.project_root)?
-        .display()
-        .to_string();
-
-    let mut matched_network: Option<String> = None;
-    for project in list_running_project_networks()? {
-        if project.name != config.name {
-            continue;
-        }
-
-        let normalized_root = canonicalize_path(Path::new(&project.project_root))
-            .map(|path| path.display().to_string())
-            .unwrap_or_else(|_| project.project_root.clone());
-
-        if normalized_root != current_project_root {
-            continue;
-        }
 
-        if let Some(existing) = &matched_network {
-            if existing != &project.network {
-                eprintln!(
-                    "error: active project '{}' has inconsistent cladding network assignment",
-                    config.name
-                );
-                eprintln!("project_root: {current_project_root}");
-                eprintln!("networks: {existing}, {}", project.network);
-                return Err(Error::message("inconsistent active network"));
+    #[test]
+    fn logs_accepts_service_targets() {
+        let cli = Cli::try_parse_from(["cladding", "logs", "service/web", "-f"]).expect("cli parse");
+        match cli.command.expect("command") {
+            CommandSpec::Logs { target, follow, .. } => {
+                assert_eq!(target, LogsTarget::Service("web".to_string()));
+                assert!(follow);
             }
-            continue;
+            other => panic!("unexpected command: {other:?}"),
         }
 
-        matched_network = Some(project.network);
-    }
+        assert!(Cli::try_parse_from(["cladding", "logs", "service/../x"]).is_err());
+        assert!(Cli::try_parse_from(["cladding", "logs", "web"]).is_err());
 
-    let Some(network_name) = matched_network else {
-        eprintln!(
-            "error: could not resolve active cladding network for project '{}'",
-            config.name
-        );
-        eprintln!("hint: ensure the project is running, then retry '{command_name}'");
-        return Err(Error::message("missing active cladding network"));
-    };
-
-    let Some(index) = parse_cladding_pool_index(&network_name) else {
-        eprintln!(
-            "error: active project '{}' is attached to unexpected network '{}'",
-            config.name, network_name
-        );
-        eprintln!("hint: restart the project with 'cladding down' then 'cladding up'");
-        return Err(Error::message("unexpected active network"));
-    };
-
-    resolve_network_settings(&config.name, index)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn expose_create_args_parse_without_subcommand() {
-        let cli = Cli::try_parse_from(["cladding", "expose", "3000", "9000"]).expect("cli parse");
+        let cli = Cli::try_parse_from(["cladding", "logs", "sidecar/postgres"]).expect("cli parse");
         match cli.command.expect("command") {
-            CommandSpec::Expose(args) => {
-                assert!(args.command.is_none());
-                assert_eq!(args.container_port, Some(3000));
-                assert_eq!(args.host_port, Some(9000));
+            CommandSpec::Logs { target, .. } => {
+                assert_eq!(target, LogsTarget::Sidecar("postgres".to_string()));
             }
             other => panic!("unexpected command: {other:?}"),
         }
+        assert!(Cli::try_parse_from(["cladding", "logs", "sidecar/cli-app"]).is_err());
     }
 
     #[test]
-    fn expose_stop_subcommand_parses() {
-        let cli =
-            Cli::try_parse_from(["cladding", "expose", "stop", "9000"]).expect("cli parse");
+    fn service_start_collects_command_after_name() {
+        let cli = Cli::try_parse_from([
+            "cladding", "service", "start", "web", "--env", "PORT=5173", "--", "npm", "run", "dev",
+            "--host",
+        ])
+        .expect("cli parse");
         match cli.command.expect("command") {
-            CommandSpec::Expose(ExposeArgs {
-                command: Some(ExposeSubcommand::Stop { host_port }),
-                ..
-            }) => assert_eq!(host_port, 9000),
+            CommandSpec::Service(ServiceArgs {
+                command: ServiceSubcommand::Start { name, env, args },
+            }) => {
+                assert_eq!(name, "web");
+                assert_eq!(env, vec!["PORT=5173"]);
+                assert_eq!(args, vec!["npm", "run", "dev", "--host"]);
+            }
             other => panic!("unexpected command: {other:?}"),
         }
+
+        assert!(Cli::try_parse_from(["cladding", "service", "start", "web"]).is_err());
+        assert!(Cli::try_parse_from(["cladding", "service", "stop", "a/b"]).is_err());
+        let cli = Cli::try_parse_from(["cladding", "service", "ls"]).expect("cli parse");
+        assert!(cli.command.expect("command").lock_name().is_none());
     }
 
     #[test]
@@ -0,0 +1,146 @@
+//! `cladding build`: the default image from the embedded Containerfile, tagged and pushed
+//! on request.
+
+use anyhow::Context as _;
+use cladding::assets::{write_asset_manifest, write_embedded_tools};
+use cladding::error::{Error, Result};
+use cladding::fs_utils::{host_ids, is_broken_symlink};
+use cladding::output;
+use cladding::progress;
+use std::fs;
+
+use super::{Context, load_config};
+
+pub(super) const DEFAULT_CLADDING_BUILD_IMAGE: &str = "localhost/cladding-default:latest";
+pub(super) const DEFAULT_CLI_BUILD_IMAGE: &str = DEFAULT_CLADDING_BUILD_IMAGE;
+pub(super) const DEFAULT_SANDBOX_BUILD_IMAGE: &str = DEFAULT_CLADDING_BUILD_IMAGE;
+
+pub(super) fn cmd_build(context: &Context, tags: &[String], push: bool) -> Result<()> {
+    if let Some(tag) = tags.iter().find(|tag| tag.is_empty() || tag.starts_with('-')) {
+        output::error(format!("invalid image reference for --tag: '{tag}'"));
+        return Err(Error::message("invalid --tag"));
+    }
+    let config = progress::step("load-config", || load_config(context))?;
+
+    let (host_uid, host_gid) = host_ids();
+
+    progress::step("write-tools", || {
+        let tools_dir = context.project_root.join("tools");
+        if is_broken_symlink(&tools_dir)? {
+            output::missing(format!("tools (broken symlink at {})", tools_dir.display()));
+            output::hint(format!("create or relink {}", tools_dir.display()));
+            return Err(Error::message("missing tools"));
+        }
+
+        let tools_bin_dir = tools_dir.join("bin");
+        fs::create_dir_all(&tools_bin_dir).with_context(|| "failed to create tools directory")?;
+
+        write_embedded_tools(&tools_bin_dir)?;
+        write_asset_manifest(&context.project_root)
+    })?;
+
+    let mut cli_image_built = false;
+    if config.cli_image == DEFAULT_CLI_BUILD_IMAGE {
+        progress::step("build-cli-image", || {
+            context.runtime.build_image(&config.cli_image, host_uid, host_gid)
+        })?;
+        cli_image_built = true;
+    } else {
+        progress::skipped(
+            "build-cli-image",
+            format!(
+                "not building cli image (config cli_image is {}, build target is {})",
+                config.cli_image, DEFAULT_CLADDING_BUILD_IMAGE
+            ),
+        );
+    }
+
+    let mut sandbox_image_built = false;
+    if config.sandbox_image == DEFAULT_SANDBOX_BUILD_IMAGE {
+        if config.sandbox_image == config.cli_image && cli_image_built {
+            progress::skipped(
+                "build-sandbox-image",
+                format!(
+                    "sandbox image already built (config cli_image and sandbox_image are both {})",
+                    config.sandbox_image
+                ),
+            );
+        } else {
+            progress::step("build-sandbox-image", || {
+                context.runtime.build_image(&config.sandbox_image, host_uid, host_gid)
+            })?;
+            sandbox_image_built = true;
+        }
+    } else {
+        progress::skipped(
+            "build-sandbox-image",
+            format!(
+                "not building sandbox image (config sandbox_image is {}, build target is {})",
+                config.sandbox_image, DEFAULT_CLADDING_BUILD_IMAGE
+            ),
+        );
+    }
+
+    if tags.is_empty() {
+        return Ok(());
+    }
+    if !cli_image_built && !sandbox_image_built {
+        output::error(format!(
+            "--tag needs a built image, but cladding.json uses neither {} for cli_image nor \
+             sandbox_image",
+            DEFAULT_CLADDING_BUILD_IMAGE
+        ));
+        output::hint(format!(
+            "set cli_image or sandbox_image to {DEFAULT_CLADDING_BUILD_IMAGE} to build and tag it"
+        ));
+        return Err(Error::message("nothing built to tag"));
+    }
+    progress::step("tag-image", || {
+        tags.iter()
+            .try_for_each(|tag| context.runtime.tag_image(DEFAULT_CLADDING_BUILD_IMAGE, tag))
+    })?;
+    if push {
+        progress::step("push-image", || {
+            tags.iter().try_for_each(|tag| context.runtime.push_image(tag))
+        })?;
+        for tag in tags {
+            output::note(format!(
+                "pushed {tag}; set cli_image and sandbox_image to it in cladding.json to use it"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn image_is_buildable_by_cladding(image: &str) -> bool {
+    image == DEFAULT_CLADDING_BUILD_IMAGE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::podman::IMAGE_UID_LABEL;
+    use cladding::runtime::FakeRuntime;
+    use crate::cli::test_support::fake_project;
+    use std::rc::Rc;
+
+    #[test]
+    fn build_tags_and_pushes_the_default_image_through_the_runtime() {
+        let runtime = Rc::new(FakeRuntime::default());
+        let context = fake_project("build", &runtime);
+        let tag = "registry.example/team/cladding:1".to_string();
+
+        cmd_build(&context, std::slice::from_ref(&tag), true).expect("build");
+
+        let state = runtime.state();
+        assert!(state.images.contains(DEFAULT_CLADDING_BUILD_IMAGE));
+        assert!(state.images.contains(&tag));
+        assert_eq!(state.pushed, vec![tag.clone()]);
+        let (host_uid, _) = host_ids();
+        assert_eq!(
+            state.image_details[&tag].labels.get(IMAGE_UID_LABEL),
+            Some(&host_uid.to_string())
+        );
+    }
+}
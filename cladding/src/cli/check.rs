@@ -0,0 +1,379 @@
+//! `cladding check`: the files, tools and images a project needs, restored by `--fix`.
+
+use anyhow::Context as _;
+use cladding::assets::{
+    AssetDrift, asset_drift, config_top_level_entries, embedded_manifest, materialize_config,
+    materialize_scripts, read_asset_manifest, scripts_files, scripts_top_level_entries,
+    write_embedded_tools,
+};
+use cladding::config::{Config, load_cladding_config};
+use cladding::error::{Error, Result};
+use cladding::excludes::unmasked_exclude_symlinks;
+use cladding::fs_utils::{host_path_from_engine, is_executable, path_is_symlink};
+use cladding::output;
+use cladding::pods::{host_paths_from_rendered, render_pods_yaml};
+use std::fs;
+use std::path::PathBuf;
+
+use super::{Context, load_config};
+use super::build::image_is_buildable_by_cladding;
+use super::disk_usage::warn_over_disk_usage_threshold;
+use super::network::project_network_settings;
+
+pub(super) fn cmd_check(context: &Context, fix: bool) -> Result<()> {
+    if fix {
+        let fixed = fix_project(context)?;
+        if fixed.is_empty() {
+            println!("fix: nothing to fix");
+        }
+        for item in fixed {
+            println!("fixed: {item}");
+        }
+    }
+    check_required_binaries(context)?;
+    let config = load_config(context)?;
+    let network_settings = project_network_settings(&config, 0)?;
+    check_required_host_paths(context, &config, &network_settings)?;
+    check_required_config_files(context)?;
+    check_required_scripts_files(context)?;
+    check_required_images(context, &config)?;
+    warn_over_disk_usage_threshold(context, &config, config.disk_usage_threshold)?;
+    report_asset_versions(context)?;
+    println!("check: ok");
+    Ok(())
+}
+
+/// Repairs what `cladding check` would report and can be repaired without a build: missing
+/// directories under `.cladding`, missing config and scripts entries (existing files are never
+/// overwritten) and missing tools binaries. Images and paths outside `.cladding` are left to
+/// the checks and their hints. Returns a line per repair.
+fn fix_project(context: &Context) -> Result<Vec<String>> {
+    let project_root = &context.project_root;
+    let mut fixed = Vec::new();
+
+    let mut dirs: Vec<PathBuf> = ["config", "scripts", "home", "tools"]
+        .iter()
+        .map(|name| project_root.join(name))
+        .collect();
+    if let Ok(config) = load_cladding_config(project_root) {
+        let network_settings = project_network_settings(&config, 0)?;
+        let rendered = render_pods_yaml(project_root, &config, &network_settings)?;
+        dirs.extend(
+            host_paths_from_rendered(&rendered)
+                .into_iter()
+                .map(|path| host_path_from_engine(&path))
+                .filter(|path| path.starts_with(project_root)),
+        );
+    }
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        // A dangling symlink points somewhere the user chose; creating a directory in its
+        // place would hide that.
+        if dir.exists() || path_is_symlink(&dir) {
+            continue;
+        }
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        fixed.push(format!("created {}", dir.display()));
+    }
+
+    let config_dir = project_root.join("config");
+    let missing_config: Vec<String> = config_top_level_entries()
+        .into_iter()
+        .filter(|name| !config_dir.join(name).exists())
+        .collect();
+    if config_dir.is_dir() && !missing_config.is_empty() {
+        materialize_config(&config_dir)?;
+        for name in missing_config {
+            fixed.push(format!("restored config/{name}"));
+        }
+    }
+
+    let scripts_dir = project_root.join("scripts");
+    let missing_scripts: Vec<String> = scripts_top_level_entries()
+        .into_iter()
+        .filter(|name| !scripts_dir.join(name).exists())
+        .collect();
+    if scripts_dir.is_dir() && !missing_scripts.is_empty() {
+        materialize_scripts(&scripts_dir)?;
+        for name in missing_scripts {
+            fixed.push(format!("restored scripts/{name}"));
+        }
+    }
+
+    let tools_dir = project_root.join("tools");
+    let bin_dir = tools_dir.join("bin");
+    let binaries_missing = ["mcp-run", "run-with-network"]
+        .iter()
+        .any(|name| !is_executable(&bin_dir.join(name)));
+    if tools_dir.is_dir() && binaries_missing {
+        fs::create_dir_all(&bin_dir)
+            .with_context(|| format!("failed to create {}", bin_dir.display()))?;
+        write_embedded_tools(&bin_dir)?;
+        fixed.push("rewrote tools/bin/mcp-run and tools/bin/run-with-network".to_string());
+    }
+
+    Ok(fixed)
+}
+
+/// Prints the embedded asset version next to the one recorded in `.cladding`, and warns about
+/// materialized files that differ from the embedded ones. Never fails on drift.
+pub(super) fn report_asset_versions(context: &Context) -> Result<()> {
+    let embedded = embedded_manifest();
+    println!(
+        "assets: embedded cladding {} ({})",
+        embedded.version,
+        embedded.short_digest()
+    );
+    match read_asset_manifest(&context.project_root)? {
+        Some(recorded) => {
+            println!(
+                "assets: materialized by cladding {} ({})",
+                recorded.version,
+                recorded.short_digest()
+            );
+            if recorded.digest != embedded.digest {
+                output::warning(
+                    ".cladding was set up with different assets than this cladding binary embeds"
+                );
+            }
+        }
+        None => println!("assets: no record in .cladding (created before asset tracking)"),
+    }
+
+    let mut stale_scripts = false;
+    let mut stale_tools = false;
+    for drift in asset_drift(&context.project_root, &embedded) {
+        let (path, problem) = match &drift {
+            AssetDrift::Missing(path) => (path, "is missing"),
+            AssetDrift::Modified(path) => (path, "differs from embedded version"),
+        };
+        if path.starts_with("config/") {
+            // Config is the user's to edit; only missing top-level entries are errors.
+            println!("note: {path} {problem}");
+            continue;
+        }
+        output::warning(format!("{path} {problem}"));
+        stale_scripts |= path.starts_with("scripts/");
+        stale_tools |= path.starts_with("tools/");
+    }
+    if stale_scripts {
+        output::hint("run cladding init --update-scripts to re-materialize scripts");
+    }
+    if stale_tools {
+        output::hint("run cladding build to rewrite tools/bin");
+    }
+    Ok(())
+}
+
+pub(super) fn check_required_binaries(context: &Context) -> Result<()> {
+    let mut missing = false;
+    let bin_dir = context.project_root.join("tools/bin");
+
+    for name in ["mcp-run", "run-with-network"] {
+        let path = bin_dir.join(name);
+        if !is_executable(&path) {
+            output::missing(format!("tools/bin/{name} ({})", path.display()));
+            output::hint("run cladding check --fix or cladding build");
+            missing = true;
+        }
+    }
+
+    if missing {
+        return Err(Error::message("missing tools binaries"));
+    }
+
+    Ok(())
+}
+
+pub(super) fn check_required_config_files(context: &Context) -> Result<()> {
+    let dst = context.project_root.join("config");
+    let mut missing = false;
+
+    for name in config_top_level_entries() {
+        let path = dst.join(&name);
+        if !path.exists() {
+            output::missing(format!("config/{name} ({})", path.display()));
+            missing = true;
+        }
+    }
+
+    if missing {
+        output::hint(format!(
+            "run cladding check --fix, or add missing top-level entries into {}",
+            dst.display()
+        ));
+        return Err(Error::message("missing config files"));
+    }
+
+    Ok(())
+}
+
+pub(super) fn check_required_scripts_files(context: &Context) -> Result<()> {
+    let dst = context.project_root.join("scripts");
+    let mut missing = false;
+
+    for name in scripts_top_level_entries() {
+        let path = dst.join(&name);
+        if !path.exists() {
+            output::missing(format!("scripts/{name} ({})", path.display()));
+            missing = true;
+        }
+    }
+
+    if missing {
+        output::hint(format!(
+            "run cladding check --fix, or add missing top-level entries into {}",
+            dst.display()
+        ));
+        return Err(Error::message("missing scripts files"));
+    }
+
+    Ok(())
+}
+
+pub(super) fn warn_on_script_mismatch(context: &Context) -> Result<()> {
+    let dst = context.project_root.join("scripts");
+    let mut warned = false;
+
+    for (rel_path, contents) in scripts_files() {
+        let target = dst.join(&rel_path);
+        match fs::read(&target) {
+            Ok(existing) => {
+                if existing != contents {
+                    output::warning(format!(
+                        "scripts/{} differs from embedded version",
+                        rel_path.display()
+                    ));
+                    warned = true;
+                }
+            }
+            Err(_) => {
+                output::warning(format!("scripts/{} is missing", rel_path.display()));
+                warned = true;
+            }
+        }
+    }
+
+    if warned {
+        output::hint("run cladding init --update-scripts to re-materialize scripts");
+    }
+
+    Ok(())
+}
+
+pub(super) fn check_required_host_paths(
+    context: &Context,
+    config: &Config,
+    network_settings: &cladding::network::NetworkSettings,
+) -> Result<()> {
+    let rendered = render_pods_yaml(&context.project_root, config, network_settings)?;
+
+    let mut missing = false;
+    let mut seen = std::collections::HashSet::new();
+    for path in host_paths_from_rendered(&rendered) {
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let host_path = host_path_from_engine(&path);
+        if !host_path.exists() {
+            output::missing(format!("hostPath {}", host_path.display()));
+            output::hint(format!("create or relink {}", host_path.display()));
+            missing = true;
+        }
+    }
+
+    if missing {
+        return Err(Error::message("missing host paths"));
+    }
+
+    if let Some(workspace) = context.project_root.parent() {
+        for link in unmasked_exclude_symlinks(workspace, &config.workspace_excludes) {
+            output::warning(format!(
+                "workspace_excludes matches symlink {link}, which stays visible; exclude its target instead"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn check_required_images(context: &Context, config: &Config) -> Result<()> {
+    let mut missing = false;
+    for image in [&config.cli_image, &config.sandbox_image] {
+        match context.runtime.image_exists(image) {
+            Ok(true) => {}
+            Ok(false) => {
+                output::missing(format!("image {image}"));
+                if image_is_buildable_by_cladding(image) {
+                    output::hint("run cladding build");
+                } else {
+                    output::hint(format!(
+                        "pull/tag image '{image}', or set cladding.json image to a supported build target and run cladding build"
+                    ));
+                }
+                missing = true;
+            }
+            Err(err) => {
+                output::error(format!("failed to check image {image}: {err}"));
+                return Err(Error::message("failed to check image"));
+            }
+        }
+    }
+
+    if missing {
+        return Err(Error::message("missing required images"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::runtime::FakeRuntime;
+    use crate::cli::build::DEFAULT_CLADDING_BUILD_IMAGE;
+    use crate::cli::test_support::{fake_project, fake_runtime_with_images};
+    use std::rc::Rc;
+
+    #[test]
+    fn check_reports_missing_images() {
+        let runtime = Rc::new(FakeRuntime::default());
+        let context = fake_project("check", &runtime);
+        assert!(cmd_check(&context, false).is_err());
+
+        runtime
+            .state_mut()
+            .images
+            .insert(DEFAULT_CLADDING_BUILD_IMAGE.to_string());
+        cmd_check(&context, false).expect("check passes once the image exists");
+    }
+
+    #[test]
+    fn check_fix_restores_missing_files_without_overwriting() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("check-fix", &runtime);
+        let root = &context.project_root;
+        let config_path = root.join("config/squid.conf");
+        fs::remove_file(&config_path).unwrap();
+        fs::remove_dir_all(root.join("scripts")).unwrap();
+        fs::remove_dir_all(root.join("home")).unwrap();
+        fs::remove_file(root.join("tools/bin/mcp-run")).unwrap();
+        fs::write(root.join("config/cli_domains.lst"), "example.com\n").unwrap();
+        assert!(cmd_check(&context, false).is_err());
+
+        let fixed = fix_project(&context).expect("fix");
+        assert!(fixed.contains(&format!("created {}", root.join("home").display())));
+        assert!(fixed.contains(&"restored config/squid.conf".to_string()));
+        assert!(fixed.iter().any(|item| item.starts_with("rewrote tools/bin/mcp-run")));
+        assert!(config_path.exists());
+        assert!(is_executable(&root.join("tools/bin/mcp-run")));
+        assert_eq!(
+            fs::read_to_string(root.join("config/cli_domains.lst")).unwrap(),
+            "example.com\n"
+        );
+        cmd_check(&context, true).expect("check passes after fixing");
+        assert!(fix_project(&context).expect("fix again").is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+//! `cladding config validate`.
+
+use anyhow::Context as _;
+use cladding::config::load_cladding_config;
+use cladding::config_schema::{ConfigProblem, schema_problems};
+use cladding::error::{Error, Result};
+use cladding::output;
+use std::fs;
+
+use super::{ConfigArgs, ConfigSubcommand, Context};
+
+pub(super) fn cmd_config(context: &Context, args: &ConfigArgs) -> Result<()> {
+    match args.command {
+        ConfigSubcommand::Validate => cmd_config_validate(context),
+    }
+}
+
+fn cmd_config_validate(context: &Context) -> Result<()> {
+    let config_path = context.project_root.join("cladding.json");
+    if config_path.exists() {
+        let raw = fs::read_to_string(&config_path)
+            .with_context(|| format!("failed to read {}", config_path.display()))?;
+        let problems = match serde_json::from_str(&raw) {
+            Ok(parsed) => schema_problems(&parsed),
+            Err(err) => vec![ConfigProblem {
+                field: String::new(),
+                message: format!("is not valid JSON: {err}"),
+            }],
+        };
+        if !problems.is_empty() {
+            for problem in &problems {
+                output::error(format!("cladding.json {problem}"));
+            }
+            output::file(format!("{}", config_path.display()));
+            return Err(Error::message(format!(
+                "{} problem(s) in cladding.json",
+                problems.len()
+            )));
+        }
+    }
+    // Also reports a missing file, and what the schema cannot express, such as duplicate
+    // mount paths or an unknown home_template preset.
+    load_cladding_config(&context.project_root)?;
+    println!("valid: {}", config_path.display());
+    Ok(())
+}
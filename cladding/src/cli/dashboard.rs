@@ -0,0 +1,144 @@
+//! `cladding dashboard`: the status page served on localhost.
+
+use anyhow::Context as _;
+use cladding::config::Config;
+use cladding::dashboard::{DashboardBackend, DashboardSnapshot, serve_dashboard};
+use cladding::engine::selected_engine;
+use cladding::error::{Error, Result};
+use cladding::mcp_config::parse_metric_samples;
+use cladding::network::project_container_name;
+use cladding::output;
+use cladding::podman::engine_required;
+use cladding::runtime::{ContainerRuntime, engine_runtime};
+use cladding::squid::parse_access_log_denials;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::{Context, load_config};
+use super::lifecycle::current_project_root;
+use super::proxy::cmd_reload_proxy;
+use super::status::{WATCH_PROXY_LOG_LINES, client_execution_counts, render_status};
+
+/// Proxy denials and mcp-run commands listed by the dashboard.
+const DASHBOARD_ROWS: usize = 20;
+
+pub(super) fn cmd_dashboard(context: &Context, port: u16, interval: u64) -> Result<()> {
+    engine_required("cladding dashboard")?;
+    let config = load_config(context)?;
+    let engine_project_root = current_project_root(context)?;
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(error) => {
+            output::error(format!("cannot listen on 127.0.0.1:{port}: {error}"));
+            output::hint("pick another port with 'cladding dashboard --port PORT'");
+            return Err(Error::message("dashboard port unavailable"));
+        }
+    };
+    println!(
+        "dashboard for {} at http://127.0.0.1:{port}/; press Ctrl-C to exit",
+        config.name
+    );
+    let backend = ProjectDashboard {
+        project_root: context.project_root.clone(),
+        pool_member: context.pool_member.clone(),
+        config,
+        engine_project_root,
+    };
+    serve_dashboard(listener, Arc::new(backend), interval)
+}
+
+/// The project as `cladding dashboard` reads it. A `Context` cannot move to the server's
+/// threads, so reloads rebuild one from these.
+struct ProjectDashboard {
+    project_root: PathBuf,
+    pool_member: Option<String>,
+    config: Config,
+    /// The project root as the engine's labels record it.
+    engine_project_root: String,
+}
+
+impl ProjectDashboard {
+    fn context(&self) -> Context {
+        Context {
+            project_root: self.project_root.clone(),
+            runtime: engine_runtime(selected_engine()),
+            pool_member: self.pool_member.clone(),
+        }
+    }
+}
+
+impl DashboardBackend for ProjectDashboard {
+    fn snapshot(&self) -> DashboardSnapshot {
+        let config = &self.config;
+        let runtime = self.context().runtime;
+        let mut snapshot = DashboardSnapshot {
+            project: config.name.clone(),
+            ..DashboardSnapshot::default()
+        };
+        match render_status(runtime.as_ref(), config, &self.engine_project_root) {
+            Ok(report) => {
+                snapshot.status = report.text;
+                snapshot.unhealthy = report.unhealthy;
+                snapshot.degraded = report.degraded;
+            }
+            Err(error) => snapshot.errors.push(format!("status: {error}")),
+        }
+
+        let proxy_container = project_container_name(&config.name, "proxy");
+        match runtime.container_log_tail(&proxy_container, WATCH_PROXY_LOG_LINES) {
+            Ok(log) => {
+                let denials = parse_access_log_denials(&log);
+                snapshot.denials = denials.into_iter().rev().take(DASHBOARD_ROWS).collect();
+            }
+            Err(error) => snapshot.errors.push(format!("proxy logs: {error}")),
+        }
+
+        let sandbox_container = project_container_name(&config.name, "sandbox");
+        match sandbox_mcp_json(runtime.as_ref(), &sandbox_container, "GET", "/admin/status") {
+            Ok(status) => snapshot.mcp_status = Some(status),
+            Err(error) => snapshot.errors.push(format!("mcp-run status: {error}")),
+        }
+        let history_path = format!("/admin/history?limit={DASHBOARD_ROWS}");
+        match sandbox_mcp_json::<Vec<serde_json::Value>>(
+            runtime.as_ref(),
+            &sandbox_container,
+            "GET",
+            &history_path,
+        ) {
+            Ok(history) => snapshot.mcp_history = history.into_iter().rev().collect(),
+            Err(error) => snapshot.errors.push(format!("mcp-run history: {error}")),
+        }
+        match runtime.sandbox_metrics(&sandbox_container) {
+            Ok(text) => {
+                snapshot.mcp_clients = client_execution_counts(&parse_metric_samples(&text));
+            }
+            Err(error) => snapshot.errors.push(format!("mcp-run metrics: {error}")),
+        }
+        snapshot
+    }
+
+    fn reload_proxy(&self) -> Result<String> {
+        cmd_reload_proxy(&self.context())?;
+        Ok("proxy reloaded".to_string())
+    }
+
+    fn reload_policy(&self) -> Result<String> {
+        let sandbox_container = project_container_name(&self.config.name, "sandbox");
+        let runtime = self.context().runtime;
+        let reloaded: serde_json::Value =
+            sandbox_mcp_json(runtime.as_ref(), &sandbox_container, "POST", "/admin/reload")?;
+        let mode = reloaded["policyMode"].as_str().unwrap_or("unknown");
+        Ok(format!("policy reloaded (mode: {mode})"))
+    }
+}
+
+fn sandbox_mcp_json<T: serde::de::DeserializeOwned>(
+    runtime: &dyn ContainerRuntime,
+    container_name: &str,
+    method: &str,
+    path: &str,
+) -> Result<T> {
+    let body = runtime.sandbox_mcp_request(container_name, method, path)?;
+    Ok(serde_json::from_str(&body).with_context(|| format!("invalid JSON from mcp-run {path}"))?)
+}
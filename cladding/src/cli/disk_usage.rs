@@ -0,0 +1,193 @@
+//! `cladding disk-usage`: space taken by the project's directories, volumes and images.
+
+use cladding::config::Config;
+use cladding::error::Result;
+use cladding::fs_utils::dir_size;
+use cladding::output;
+use cladding::podman::engine_required;
+use cladding::pods::agent_home_dir;
+
+use super::{Context, PsFormat, load_config};
+use super::images::format_size;
+use super::volume::project_volumes;
+
+/// One row of `cladding disk-usage`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+struct DiskUsageEntry {
+    /// `directory`, `volume` or `image`.
+    kind: &'static str,
+    /// Directories relative to `.cladding`, volumes by their cladding.json name.
+    name: String,
+    /// `None` when the engine did not report a size.
+    size_bytes: Option<u64>,
+}
+
+/// What the project keeps on disk: its home and tools directories and each agent's home, its
+/// named volumes and the images it runs, which other projects may share.
+fn project_disk_usage(context: &Context, config: &Config) -> Result<Vec<DiskUsageEntry>> {
+    let project_root = &context.project_root;
+    let mut dirs = vec![project_root.join("home"), project_root.join("tools")];
+    dirs.extend(
+        config
+            .agents
+            .iter()
+            .map(|agent| agent_home_dir(project_root, &agent.name)),
+    );
+    let mut entries: Vec<DiskUsageEntry> = dirs
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .map(|dir| DiskUsageEntry {
+            kind: "directory",
+            name: dir
+                .strip_prefix(project_root)
+                .unwrap_or(&dir)
+                .display()
+                .to_string(),
+            size_bytes: Some(dir_size(&dir)),
+        })
+        .collect();
+
+    let volumes = project_volumes(context, config)?;
+    if !volumes.is_empty() {
+        let sizes = context.runtime.volume_sizes()?;
+        entries.extend(volumes.into_iter().map(|name| DiskUsageEntry {
+            kind: "volume",
+            size_bytes: sizes.get(&config.volume_name(&name)).copied(),
+            name,
+        }));
+    }
+
+    let mut images = vec![&config.cli_image, &config.sandbox_image];
+    images.dedup();
+    for image in images {
+        if let Some(details) = context.runtime.inspect_image(image)? {
+            entries.push(DiskUsageEntry {
+                kind: "image",
+                name: image.clone(),
+                size_bytes: Some(details.size_bytes),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Warns when the project uses more than `threshold` bytes; does nothing without one.
+pub(super) fn warn_over_disk_usage_threshold(
+    context: &Context,
+    config: &Config,
+    threshold: Option<u64>,
+) -> Result<()> {
+    let Some(threshold) = threshold else {
+        return Ok(());
+    };
+    let total = disk_usage_total(&project_disk_usage(context, config)?);
+    if warn_if_over_threshold(config, total, threshold) {
+        output::hint("run 'cladding disk-usage' to see what uses it");
+    }
+    Ok(())
+}
+
+fn warn_if_over_threshold(config: &Config, total: u64, threshold: u64) -> bool {
+    let over = total > threshold;
+    if over {
+        output::warning(format!(
+            "project '{}' uses {} on disk, over its threshold of {}",
+            config.name,
+            format_size(total),
+            format_size(threshold)
+        ));
+    }
+    over
+}
+
+fn disk_usage_total(entries: &[DiskUsageEntry]) -> u64 {
+    entries.iter().filter_map(|entry| entry.size_bytes).sum()
+}
+
+pub(super) fn cmd_disk_usage(
+    context: &Context,
+    threshold: Option<u64>,
+    format: PsFormat,
+) -> Result<()> {
+    engine_required("cladding disk-usage")?;
+    let config = load_config(context)?;
+    let entries = project_disk_usage(context, &config)?;
+    let total = disk_usage_total(&entries);
+    if format == PsFormat::Json {
+        let report = serde_json::json!({ "entries": entries, "total_bytes": total });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("disk usage serializes")
+        );
+    } else {
+        let name_width = entries
+            .iter()
+            .map(|entry| entry.name.len())
+            .max()
+            .unwrap_or_default()
+            .max("NAME".len());
+        println!("{:<9}  {:<name_width$}  SIZE", "KIND", "NAME");
+        for entry in &entries {
+            let size = entry.size_bytes.map_or("-".to_string(), format_size);
+            println!("{:<9}  {:<name_width$}  {size}", entry.kind, entry.name);
+        }
+        println!("{:<9}  {:<name_width$}  {}", "total", "", format_size(total));
+    }
+
+    if let Some(threshold) = threshold.or(config.disk_usage_threshold) {
+        warn_if_over_threshold(&config, total, threshold);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::podman::ImageDetails;
+    use crate::cli::build::DEFAULT_CLADDING_BUILD_IMAGE;
+    use crate::cli::test_support::{fake_project, fake_runtime_with_images};
+    use std::fs;
+
+    #[test]
+    fn disk_usage_counts_directories_volumes_and_images() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("disk-usage", &runtime);
+        fs::write(context.project_root.join("home/history"), [0u8; 300]).unwrap();
+        let config = load_config(&context).unwrap();
+        {
+            let mut state = runtime.state_mut();
+            state.volumes.insert(config.volume_name("cache"));
+            state.volume_sizes.insert(config.volume_name("cache"), 5_000);
+            state.image_details.insert(
+                DEFAULT_CLADDING_BUILD_IMAGE.to_string(),
+                ImageDetails {
+                    size_bytes: 1_000_000,
+                    ..ImageDetails::default()
+                },
+            );
+        }
+
+        let entries = project_disk_usage(&context, &config).expect("disk usage");
+        let home = entries
+            .iter()
+            .find(|entry| entry.name == "home")
+            .expect("home entry");
+        assert!(home.size_bytes.is_some_and(|size| size >= 300));
+        assert!(entries.iter().any(|entry| entry.name == "tools"));
+        let rest: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.kind != "directory")
+            .map(|entry| (entry.kind, entry.name.as_str(), entry.size_bytes))
+            .collect();
+        assert_eq!(
+            rest,
+            [
+                ("volume", "cache", Some(5_000)),
+                ("image", DEFAULT_CLADDING_BUILD_IMAGE, Some(1_000_000)),
+            ]
+        );
+        assert!(disk_usage_total(&entries) > 1_005_000);
+        assert!(warn_if_over_threshold(&config, 1_005_300, 1_000_000));
+        assert!(!warn_if_over_threshold(&config, 1_005_300, 2_000_000));
+    }
+}
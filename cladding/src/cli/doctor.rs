@@ -0,0 +1,306 @@
+//! `cladding doctor`: host problems that keep projects from starting.
+
+use anyhow::Context as _;
+use cladding::config::Config;
+use cladding::docker::docker_server_version;
+use cladding::engine::{Engine, engine_command, selected_engine};
+use cladding::error::{Error, Result};
+use cladding::fs_utils::{
+    Wsl, canonicalize_path, detect_wsl, engine_host_path, is_windows_drive_mount,
+};
+use cladding::mcp_config::sandbox_mcp_endpoint;
+use cladding::network::{
+    CLADDING_POOL_SUBNET, PROJECT_POD_ROLES, ipv4_cidrs_overlap, parse_cladding_pool_index,
+    parse_proc_net_routes, project_container_name, project_pod_name,
+};
+use cladding::output;
+use cladding::podman::{MIN_PODMAN_VERSION, engine_required, parse_podman_version, podman_host_info};
+use cladding::squid::{DEFAULT_PROXY_PORT, parse_http_port};
+use std::env;
+use std::fs;
+
+use super::{Context, load_config};
+use super::check::{
+    check_required_binaries, check_required_config_files, check_required_host_paths,
+    check_required_images, check_required_scripts_files,
+};
+use super::network::project_network_settings;
+
+/// Outcome lines for `cladding doctor`, each failure or warning followed by its fix.
+#[derive(Debug, Default)]
+struct Diagnosis {
+    failures: usize,
+    warnings: usize,
+}
+
+impl Diagnosis {
+    fn ok(&mut self, check: &str) {
+        println!("ok    {check}");
+    }
+
+    fn warn(&mut self, check: &str, fix: &str) {
+        println!("warn  {check}");
+        println!("      fix: {fix}");
+        self.warnings += 1;
+    }
+
+    fn fail(&mut self, check: &str, fix: &str) {
+        println!("FAIL  {check}");
+        println!("      fix: {fix}");
+        self.failures += 1;
+    }
+
+    fn skip(&mut self, check: &str) {
+        println!("skip  {check}");
+    }
+}
+
+/// Goes beyond `check`: the engine host setup, subnet conflicts, and whether the proxy and
+/// mcp-run of a running project actually answer.
+pub(super) fn cmd_doctor(context: &Context) -> Result<()> {
+    engine_required("cladding doctor")?;
+    let config = load_config(context)?;
+    let mut doctor = Diagnosis::default();
+
+    match selected_engine() {
+        Engine::Podman => diagnose_podman_host(&mut doctor),
+        Engine::Docker => match docker_server_version() {
+            Ok(version) => doctor.ok(&format!("docker {version}")),
+            Err(_) => doctor.fail(
+                "docker daemon is not reachable",
+                "start Docker Desktop (or the docker service) and check 'docker version'",
+            ),
+        },
+    }
+    diagnose_wsl_host(context, &mut doctor)?;
+
+    let network_settings = project_network_settings(&config, 0)?;
+    let files_ok = [
+        check_required_binaries(context),
+        check_required_host_paths(context, &config, &network_settings),
+        check_required_config_files(context),
+        check_required_scripts_files(context),
+        check_required_images(context, &config),
+    ]
+    .iter()
+    .all(Result::is_ok);
+    if files_ok {
+        doctor.ok("project files and images (cladding check)");
+    } else {
+        doctor.fail(
+            "project files or images are missing",
+            "follow the missing:/hint: lines above",
+        );
+    }
+
+    diagnose_subnet_conflicts(context, &mut doctor)?;
+
+    let proxy_pod = project_pod_name(&config.name, "proxy");
+    if context.runtime.inspect_pod_state(&proxy_pod)?.is_some_and(|pod| pod.is_running()) {
+        diagnose_running_project(context, &config, &mut doctor)?;
+    } else {
+        doctor.skip("proxy and mcp-run probes: project is not running (start it with 'cladding up')");
+    }
+
+    if doctor.failures > 0 {
+        output::error(format!("{} doctor checks failed", doctor.failures));
+        return Err(Error::message("doctor found problems"));
+    }
+    if doctor.warnings > 0 {
+        println!("doctor: ok ({} warnings)", doctor.warnings);
+    } else {
+        println!("doctor: ok");
+    }
+    Ok(())
+}
+
+/// Podman version, rootless mode and subordinate uid range.
+fn diagnose_podman_host(doctor: &mut Diagnosis) {
+    match podman_host_info() {
+        Ok(info) => {
+            let (min_major, min_minor) = MIN_PODMAN_VERSION;
+            match parse_podman_version(&info.version) {
+                Some(version) if version >= MIN_PODMAN_VERSION => {
+                    doctor.ok(&format!("podman {}", info.version));
+                }
+                _ => doctor.fail(
+                    &format!("podman {} is older than {min_major}.{min_minor}", info.version),
+                    &format!("upgrade podman to {min_major}.{min_minor} or newer"),
+                ),
+            }
+            if !info.rootless {
+                doctor.warn(
+                    "podman is running rootful",
+                    "run cladding as a regular user so containers cannot create root-owned files on the host",
+                );
+            } else if info.uid_map_entries < 2 {
+                doctor.fail(
+                    "rootless podman has no subordinate uid range",
+                    "add ranges for your user to /etc/subuid and /etc/subgid (e.g. 'sudo usermod --add-subuids 100000-165535 --add-subgids 100000-165535 $USER'), then run 'podman system migrate'",
+                );
+            } else {
+                doctor.ok("rootless podman with a subordinate uid range");
+            }
+        }
+        Err(_) => doctor.fail(
+            "podman info failed",
+            "run 'podman info' to see the error; 'podman system reset' repairs broken storage",
+        ),
+    }
+}
+
+/// Podman on Windows runs in WSL2: from inside a distro, the project should be on the distro's
+/// own filesystem; from Windows, on a drive the podman machine mounts at `/mnt/<drive>`.
+fn diagnose_wsl_host(context: &Context, doctor: &mut Diagnosis) -> Result<()> {
+    let project_root = canonicalize_path(&context.project_root)?;
+    if cfg!(windows) {
+        if engine_host_path(&project_root).starts_with("/mnt/") {
+            doctor.ok("project is on a drive the podman machine mounts");
+        } else {
+            doctor.fail(
+                &format!("{} is not on a local drive", project_root.display()),
+                "podman's WSL2 machine only sees drive letters; move the project to e.g. C:\\src",
+            );
+        }
+        return Ok(());
+    }
+
+    let distro = env::var("WSL_DISTRO_NAME").unwrap_or_else(|_| "unknown distro".to_string());
+    match detect_wsl() {
+        None => {}
+        Some(Wsl::V1) => doctor.fail(
+            &format!("WSL1 ({distro}) cannot run containers"),
+            &format!("convert the distro from Windows with 'wsl --set-version {distro} 2'"),
+        ),
+        Some(Wsl::V2) if is_windows_drive_mount(&project_root) => doctor.warn(
+            &format!("WSL2 ({distro}): project is on a Windows drive"),
+            "file access through /mnt is slow and 'cladding up --watch' misses edits made from \
+             Windows; move the project into the distro, e.g. under ~/src",
+        ),
+        Some(Wsl::V2) => doctor.ok(&format!("WSL2 ({distro})")),
+    }
+    Ok(())
+}
+
+/// Other podman networks or host routes on cladding's address pool make containers unable to
+/// reach those addresses, or `podman network create` fail.
+fn diagnose_subnet_conflicts(context: &Context, doctor: &mut Diagnosis) -> Result<()> {
+    let mut conflicts = false;
+    for network in context.runtime.list_network_subnets()? {
+        if parse_cladding_pool_index(&network.name).is_some()
+            || !ipv4_cidrs_overlap(&network.subnet, CLADDING_POOL_SUBNET)
+        {
+            continue;
+        }
+        conflicts = true;
+        doctor.fail(
+            &format!(
+                "podman network {} ({}) overlaps the cladding pool {CLADDING_POOL_SUBNET}",
+                network.name, network.subnet
+            ),
+            &format!(
+                "remove it with 'podman network rm {}' or recreate it on another subnet",
+                network.name
+            ),
+        );
+    }
+    if let Ok(routes) = fs::read_to_string("/proc/net/route") {
+        for route in parse_proc_net_routes(&routes) {
+            let engine_bridge = ["podman", "cni-podman", "veth", "docker", "br-"]
+                .iter()
+                .any(|prefix| route.interface.starts_with(prefix));
+            if engine_bridge || !ipv4_cidrs_overlap(&route.subnet, CLADDING_POOL_SUBNET) {
+                continue;
+            }
+            conflicts = true;
+            doctor.warn(
+                &format!(
+                    "host route {} on {} overlaps the cladding pool {CLADDING_POOL_SUBNET}",
+                    route.subnet, route.interface
+                ),
+                "containers cannot reach hosts in the overlap; move that network (often a VPN or LAN) off 10.90.0.0/16",
+            );
+        }
+    }
+    if !conflicts {
+        doctor.ok(&format!("no other networks overlap {CLADDING_POOL_SUBNET}"));
+    }
+    Ok(())
+}
+
+fn diagnose_running_project(
+    context: &Context,
+    config: &Config,
+    doctor: &mut Diagnosis,
+) -> Result<()> {
+    for role in PROJECT_POD_ROLES {
+        let pod_name = project_pod_name(&config.name, role);
+        if context.runtime.inspect_pod_state(&pod_name)?.is_some_and(|pod| pod.is_running()) {
+            doctor.ok(&format!("{pod_name} is running"));
+        } else {
+            doctor.fail(
+                &format!("{pod_name} is not running"),
+                "see 'cladding status', then recreate the pods with 'cladding down' and 'cladding up'",
+            );
+        }
+    }
+
+    let cli_container = project_container_name(&config.name, "cli");
+    let squid_conf = fs::read_to_string(context.project_root.join("config/squid.conf"))
+        .unwrap_or_default();
+    let proxy_port = parse_http_port(&squid_conf).unwrap_or(DEFAULT_PROXY_PORT);
+    let proxy_url = format!("http://proxy-pod:{proxy_port}/");
+    match container_http_status(&cli_container, &proxy_url)? {
+        None => doctor.skip(&format!("{cli_container}: curl is not installed")),
+        Some(code) if code != "000" => {
+            doctor.ok(&format!("squid answers on proxy-pod:{proxy_port} from the cli container"));
+        }
+        Some(_) => doctor.fail(
+            &format!("squid does not answer on proxy-pod:{proxy_port} from the cli container"),
+            "check 'cladding logs proxy' for squid configuration errors, then 'cladding reload-proxy'",
+        ),
+    }
+
+    let endpoint = sandbox_mcp_endpoint(&config.name);
+    match container_http_status(&cli_container, &endpoint.url)? {
+        None => doctor.skip(&format!("{cli_container}: curl is not installed")),
+        Some(code) if code != "000" => {
+            doctor.ok(&format!("mcp-run answers on {} (HTTP {code})", endpoint.url));
+        }
+        Some(_) => doctor.fail(
+            &format!("mcp-run does not answer on {} from the cli container", endpoint.url),
+            "check 'cladding logs sandbox'; rebuild tools with 'cladding build' if mcp-run fails to start",
+        ),
+    }
+    Ok(())
+}
+
+/// HTTP status curl gets for `url` from inside `container_name`, bypassing the proxy; `000`
+/// when nothing answered and `None` when the container has no curl.
+fn container_http_status(container_name: &str, url: &str) -> Result<Option<String>> {
+    let output = engine_command()
+        .args([
+            "exec",
+            container_name,
+            "curl",
+            "--noproxy",
+            "*",
+            "-sS",
+            "-o",
+            "/dev/null",
+            "-m",
+            "5",
+            "-w",
+            "%{http_code}",
+            url,
+        ])
+        .output()
+        .with_context(|| "failed to run podman exec")?;
+    match output.status.code() {
+        Some(127) => Ok(None),
+        Some(_) => Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )),
+        None => Err(Error::message("podman exec failed")),
+    }
+}
@@ -0,0 +1,298 @@
+//! `run`, `shell`, `run-with-scissors` and `exec-proxy`: commands in project containers.
+
+use anyhow::Context as _;
+use cladding::config::Config;
+use cladding::engine::engine_command;
+use cladding::error::{Error, Result};
+use cladding::fs_utils::canonicalize_path;
+use cladding::output;
+use std::env;
+use std::thread;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+#[cfg(unix)]
+use signal_hook::consts::signal::SIGINT;
+#[cfg(unix)]
+use signal_hook::consts::signal::SIGTERM;
+#[cfg(unix)]
+use signal_hook::iterator::Handle as SignalsHandle;
+#[cfg(unix)]
+use signal_hook::iterator::Signals;
+
+use super::{Context, RunContainer, load_config};
+use super::lifecycle::project_runtime_status;
+use super::network::resolve_active_project_network_settings;
+
+/// Used by `cladding shell` when the image has no usable login shell for the user.
+const DEFAULT_CONTAINER_SHELL: &str = "/bin/sh";
+
+/// The `cli-app` container of the project's cli pod, or of `agent`'s.
+pub(super) fn cli_app_container(
+    config: &Config,
+    network_settings: &cladding::network::NetworkSettings,
+    agent: Option<&str>,
+) -> Result<String> {
+    let Some(agent) = agent else {
+        return Ok(format!("{}-cli-app", network_settings.cli_pod_name));
+    };
+    match network_settings.agent_pod(agent) {
+        Some(pod) => Ok(format!("{}-cli-app", pod.pod_name)),
+        None => {
+            output::error(format!("no agent named '{agent}' in cladding.json"));
+            if !config.agents.is_empty() {
+                let names: Vec<&str> = config.agents.iter().map(|agent| agent.name.as_str()).collect();
+                output::hint(format!("agents: {}", names.join(", ")));
+            }
+            Err(Error::message("unknown agent"))
+        }
+    }
+}
+
+pub(super) fn cmd_run(
+    context: &Context,
+    env_vars: &[String],
+    agent: Option<&str>,
+    container: RunContainer,
+    args: &[String],
+) -> Result<()> {
+    exec_in_project_container(context, "run", container, agent, env_vars, args)
+}
+
+/// Runs `args` in one of the project's containers. The host working directory is mapped into
+/// the containers that mount the workspace; the proxy runs from its own working directory.
+fn exec_in_project_container(
+    context: &Context,
+    command_name: &str,
+    container: RunContainer,
+    agent: Option<&str>,
+    env_vars: &[String],
+    args: &[String],
+) -> Result<()> {
+    let config = load_config(context)?;
+    if agent.is_some() && container != RunContainer::CliApp {
+        output::error("--agent only applies to the cli-app container");
+        return Err(Error::message("--agent needs the cli-app container"));
+    }
+    let network_settings = resolve_active_project_network_settings(
+        context,
+        &config,
+        &format!("cladding {command_name}"),
+    )?;
+    let (container_name, map_workdir) = match container {
+        RunContainer::CliApp => (cli_app_container(&config, &network_settings, agent)?, true),
+        RunContainer::SandboxApp => (
+            format!("{}-sandbox-app", network_settings.sandbox_pod_name),
+            true,
+        ),
+        RunContainer::Proxy => (format!("{}-proxy", network_settings.proxy_pod_name), false),
+    };
+    run_podman_exec(
+        context,
+        &config,
+        command_name,
+        &container_name,
+        map_workdir,
+        env_vars,
+        args,
+    )
+}
+
+pub(super) fn cmd_shell(context: &Context, env_vars: &[String], agent: Option<&str>) -> Result<()> {
+    if !(io::stdin().is_terminal() && io::stdout().is_terminal()) {
+        output::error("cladding shell needs an interactive terminal");
+        output::hint("use 'cladding run <command>' for non-interactive commands");
+        return Err(Error::message("not a terminal"));
+    }
+
+    let config = load_config(context)?;
+    let network_settings =
+        resolve_active_project_network_settings(context, &config, "cladding shell")?;
+    let container_name = cli_app_container(&config, &network_settings, agent)?;
+    let shell = context
+        .runtime
+        .container_login_shell(&container_name)
+        .unwrap_or_else(|| DEFAULT_CONTAINER_SHELL.to_string());
+    let args = [shell, "-l".to_string()];
+    run_podman_exec(context, &config, "shell", &container_name, true, env_vars, &args)
+}
+
+pub(super) fn cmd_run_with_scissors(
+    context: &Context,
+    env_vars: &[String],
+    args: &[String],
+) -> Result<()> {
+    exec_in_project_container(
+        context,
+        "run-with-scissors",
+        RunContainer::SandboxApp,
+        None,
+        env_vars,
+        args,
+    )
+}
+
+fn run_podman_exec(
+    context: &Context,
+    config: &Config,
+    command_name: &str,
+    container_name: &str,
+    map_workdir: bool,
+    env_vars: &[String],
+    args: &[String],
+) -> Result<()> {
+    if args.is_empty() {
+        eprintln!("usage: cladding {command_name} [--env KEY[=VALUE] ...] <command> [args...]");
+        return Err(Error::message(format!("missing {command_name} command")));
+    }
+
+    let status = project_runtime_status(context, config)?;
+    if !status.already_running {
+        output::error(format!("cladding project '{}' is not running", config.name));
+        output::hint("run 'cladding up'");
+        return Err(Error::message("project is not running"));
+    }
+
+    let container_workdir = if map_workdir {
+        Some(container_workdir(context)?)
+    } else {
+        None
+    };
+
+    let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+
+    let mut cmd = engine_command();
+    cmd.arg("exec");
+    if let Some(container_workdir) = &container_workdir {
+        cmd.arg("-w").arg(container_workdir);
+    }
+    if interactive {
+        let colorterm = env::var("COLORTERM").unwrap_or_else(|_| "truecolor".to_string());
+        let force_color = env::var("FORCE_COLOR").unwrap_or_else(|_| "3".to_string());
+        cmd.args([
+            "-it",
+            "--env",
+            "LANG=C.UTF-8",
+            "--env",
+            "TERM=xterm-256color",
+            "--env",
+            &format!("COLORTERM={colorterm}"),
+            "--env",
+            &format!("FORCE_COLOR={force_color}"),
+        ]);
+    } else {
+        cmd.args(["-i", "--env", "LANG=C.UTF-8"]);
+    }
+
+    for env_var in env_vars {
+        cmd.arg("--env").arg(env_var);
+    }
+
+    cmd.arg(container_name);
+
+    for arg in args {
+        cmd.arg(arg);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("failed to run podman exec for {command_name}"))?;
+
+    #[cfg(unix)]
+    let forwarder = if interactive {
+        None
+    } else {
+        Some(forward_interrupts(container_name, args)?)
+    };
+
+    let status = child
+        .wait()
+        .with_context(|| format!("failed to run podman exec for {command_name}"))?;
+
+    #[cfg(unix)]
+    if let Some((handle, thread)) = forwarder {
+        handle.close();
+        let _ = thread.join();
+    }
+
+    if let Some(code) = status.code() {
+        if code == 0 {
+            Ok(())
+        } else {
+            Err(Error::CommandFailed {
+                context: "podman exec",
+                code,
+            })
+        }
+    } else {
+        Err(Error::message("podman exec failed"))
+    }
+}
+
+/// Until the handle is closed, turns Ctrl-C or SIGTERM into a `pkill -f` of `args` inside the
+/// container, which a non-interactive `podman exec` does not forward. On Windows the console
+/// delivers Ctrl-C to podman itself.
+#[cfg(unix)]
+fn forward_interrupts(
+    container_name: &str,
+    args: &[String],
+) -> Result<(SignalsHandle, thread::JoinHandle<()>)> {
+    let kill_pattern = args.join(" ");
+    let mut signals =
+        Signals::new([SIGINT, SIGTERM]).with_context(|| "failed to install signal handlers")?;
+    let handle = signals.handle();
+    let container_name = container_name.to_string();
+    let thread = thread::spawn(move || {
+        if signals.forever().next().is_some() && !kill_pattern.is_empty() {
+            let _ = engine_command()
+                .args([
+                    "exec",
+                    &container_name,
+                    "pkill",
+                    "-f",
+                    &kill_pattern,
+                ])
+                .status();
+        }
+    });
+    Ok((handle, thread))
+}
+
+pub(super) fn container_workdir(context: &Context) -> Result<PathBuf> {
+    let project_dir = context
+        .project_root
+        .parent()
+        .ok_or_else(|| Error::message("could not resolve project directory"))?
+        .to_path_buf();
+
+    let cwd = env::current_dir().with_context(|| "failed to determine current directory")?;
+
+    let project_dir = canonicalize_path(&project_dir)?;
+    let cwd = canonicalize_path(&cwd)?;
+
+    let workdir_rel = cwd.strip_prefix(&project_dir).map_err(|_| {
+        output::error(format!(
+            "could not determine current path relative to project dir ({}): {}",
+            project_dir.display(),
+            cwd.display()
+        ));
+        output::hint(format!(
+            "run cladding from {} or one of its subdirectories",
+            project_dir.display()
+        ));
+        Error::message("invalid working directory")
+    })?;
+
+    let mut container_workdir = PathBuf::from("/home/user/workspace");
+    if !workdir_rel.as_os_str().is_empty() {
+        container_workdir = container_workdir.join(workdir_rel);
+    }
+    Ok(container_workdir)
+}
+
+pub(super) fn cmd_exec_proxy(
+    context: &Context,
+    env_vars: &[String],
+    args: &[String],
+) -> Result<()> {
+    exec_in_project_container(context, "exec-proxy", RunContainer::Proxy, None, env_vars, args)
+}
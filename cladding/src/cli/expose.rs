@@ -0,0 +1,244 @@
+//! `cladding expose`: host ports published to a container port through a proxy container.
+
+use anyhow::Context as _;
+use cladding::config::Config;
+use cladding::engine::engine_command;
+use cladding::error::{Error, Result};
+use cladding::output;
+use cladding::podman::engine_required;
+use std::net::TcpListener;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{Context, ExposeArgs, ExposeSubcommand, load_config};
+use super::lifecycle::current_project_root;
+use super::network::resolve_active_project_network_settings;
+
+pub(super) fn cmd_expose(context: &Context, args: &ExposeArgs) -> Result<()> {
+    match &args.command {
+        Some(ExposeSubcommand::Stop { host_port }) => cmd_expose_stop(context, *host_port),
+        Some(ExposeSubcommand::List) => cmd_expose_list(context),
+        None => {
+            let Some(container_port) = args.container_port else {
+                return Err(Error::message("missing container port"));
+            };
+            cmd_expose_create(context, container_port, args.host_port)
+        }
+    }
+}
+
+fn cmd_expose_create(context: &Context, container_port: u16, host_port: Option<u16>) -> Result<()> {
+    engine_required("cladding expose")?;
+
+    let config = load_config(context)?;
+    let project_root = current_project_root(context)?;
+    let network_settings =
+        resolve_active_project_network_settings(context, &config, "cladding expose")?;
+    let cli_container_name = format!("{}-cli-app", network_settings.cli_pod_name);
+
+    if !context.runtime.container_exists(&cli_container_name)? {
+        output::error(format!(
+            "target container '{}' is missing for project '{}'",
+            cli_container_name, config.name
+        ));
+        output::hint("run 'cladding up'");
+        return Err(Error::message("missing cli container"));
+    }
+
+    let existing = context
+        .runtime
+        .list_project_expose_proxies(&config.name, &project_root, false)?;
+    if let Some(proxy) = existing
+        .iter()
+        .find(|proxy| proxy.container_port == container_port)
+    {
+        output::error(format!(
+            "container port {container_port} is already exposed for project '{}' on localhost:{}",
+            config.name, proxy.host_port
+        ));
+        return Err(Error::message("container port already exposed"));
+    }
+
+    let start_host_port = host_port.unwrap_or(container_port);
+    for candidate_host_port in start_host_port..=u16::MAX {
+        if !host_port_appears_available(candidate_host_port) {
+            continue;
+        }
+
+        match try_start_expose_proxy(
+            &config,
+            &project_root,
+            &network_settings,
+            container_port,
+            candidate_host_port,
+        )? {
+            ExposeCreateOutcome::Started => {
+                println!(
+                    "exposed: localhost:{candidate_host_port} -> {}:{container_port}",
+                    cli_container_name
+                );
+                return Ok(());
+            }
+            ExposeCreateOutcome::HostPortConflict => continue,
+        }
+    }
+
+    output::error(format!(
+        "could not allocate a free host port starting at {start_host_port}"
+    ));
+    Err(Error::message("could not allocate free host port"))
+}
+
+fn cmd_expose_stop(context: &Context, host_port: u16) -> Result<()> {
+    engine_required("cladding expose stop")?;
+
+    let config = load_config(context)?;
+    let project_root = current_project_root(context)?;
+    let proxies = context
+        .runtime
+        .list_project_expose_proxies(&config.name, &project_root, true)?;
+    let matched: Vec<_> = proxies
+        .into_iter()
+        .filter(|proxy| proxy.host_port == host_port)
+        .collect();
+
+    if matched.is_empty() {
+        output::error(format!(
+            "no expose proxy for project '{}' publishes localhost:{host_port}",
+            config.name
+        ));
+        return Err(Error::message("host port not found"));
+    }
+
+    let ids: Vec<String> = matched.iter().map(|proxy| proxy.id.clone()).collect();
+    context.runtime.remove_containers(&ids, true, true)?;
+    println!("stopped: localhost:{host_port}");
+    Ok(())
+}
+
+fn cmd_expose_list(context: &Context) -> Result<()> {
+    engine_required("cladding expose list")?;
+
+    let config = load_config(context)?;
+    let project_root = current_project_root(context)?;
+    let proxies = context
+        .runtime
+        .list_project_expose_proxies(&config.name, &project_root, false)?;
+
+    if proxies.is_empty() {
+        println!("no exposed ports for project '{}'", config.name);
+        return Ok(());
+    }
+
+    println!("HOST PORT  CONTAINER PORT  STATUS");
+    for proxy in proxies {
+        println!(
+            "{:<9}  {:<14}  {}",
+            proxy.host_port, proxy.container_port, proxy.status
+        );
+    }
+
+    Ok(())
+}
+
+pub(super) fn remove_project_expose_proxies(
+    context: &Context,
+    config: &Config,
+    project_root: &str,
+    force: bool,
+) -> Result<()> {
+    let proxies = context
+        .runtime
+        .list_project_expose_proxies(&config.name, project_root, true)?;
+    if proxies.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<String> = proxies.into_iter().map(|proxy| proxy.id).collect();
+    context.runtime.remove_containers(&ids, force, true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExposeCreateOutcome {
+    Started,
+    HostPortConflict,
+}
+
+fn try_start_expose_proxy(
+    config: &Config,
+    project_root: &str,
+    network_settings: &cladding::network::NetworkSettings,
+    container_port: u16,
+    host_port: u16,
+) -> Result<ExposeCreateOutcome> {
+    let container_name = unique_expose_proxy_name(&config.name, container_port, host_port);
+    let mut cmd = engine_command();
+    cmd.arg("run")
+        .arg("-d")
+        .arg("--name")
+        .arg(&container_name)
+        .arg("--network")
+        .arg(&network_settings.network)
+        .arg("-p")
+        .arg(format!("{host_port}:{container_port}"));
+
+    for (key, value) in expose_proxy_labels(&config.name, project_root, container_port, host_port) {
+        cmd.arg("--label").arg(format!("{key}={value}"));
+    }
+
+    cmd.arg("alpine/socat")
+        .arg(format!("TCP-LISTEN:{container_port},fork,reuseaddr"))
+        .arg(format!("TCP:{}:{container_port}", network_settings.cli_ip));
+
+    let output = cmd
+        .output()
+        .with_context(|| "failed to run podman run for cladding expose")?;
+
+    if output.status.success() {
+        return Ok(ExposeCreateOutcome::Started);
+    }
+
+    if podman_output_is_bind_conflict(&output) {
+        return Ok(ExposeCreateOutcome::HostPortConflict);
+    }
+
+    cladding::podman::ensure_success_output(&output, "podman run")?;
+    Err(Error::message("podman run failed"))
+}
+
+fn expose_proxy_labels(
+    project_name: &str,
+    project_root: &str,
+    container_port: u16,
+    host_port: u16,
+) -> [(&'static str, String); 6] {
+    [
+        ("cladding", project_name.to_string()),
+        ("project_root", project_root.to_string()),
+        ("cladding_expose", "true".to_string()),
+        ("cladding_expose_target", "cli-app".to_string()),
+        (
+            "cladding_expose_container_port",
+            container_port.to_string(),
+        ),
+        ("cladding_expose_host_port", host_port.to_string()),
+    ]
+}
+
+fn unique_expose_proxy_name(project_name: &str, container_port: u16, host_port: u16) -> String {
+    let suffix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    format!("{project_name}-expose-{container_port}-{host_port}-{suffix}")
+}
+
+fn host_port_appears_available(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+fn podman_output_is_bind_conflict(output: &std::process::Output) -> bool {
+    let stderr = String::from_utf8_lossy(&output.stderr).to_ascii_lowercase();
+    stderr.contains("address already in use")
+        || stderr.contains("port is already allocated")
+        || stderr.contains("bind")
+}
@@ -0,0 +1,233 @@
+//! `cladding images`: the images a project uses and whether they are current.
+
+use cladding::assets::containerfile_digest;
+use cladding::config::Config;
+use cladding::error::Result;
+use cladding::fs_utils::host_ids;
+use cladding::output;
+use cladding::podman::{
+    IMAGE_CONTAINERFILE_LABEL, IMAGE_GID_LABEL, IMAGE_UID_LABEL, engine_required,
+};
+use cladding::pods::proxy_image;
+
+use super::{Context, PsFormat, load_config};
+use super::build::image_is_buildable_by_cladding;
+
+/// One row of `cladding images`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+struct ImageSummary {
+    /// `cli`, `sandbox` or `proxy`.
+    role: &'static str,
+    image: String,
+    id: Option<String>,
+    digest: Option<String>,
+    size_bytes: Option<u64>,
+    created: Option<String>,
+    /// The `UID` and `GID` build args, for images `cladding build` made.
+    uid: Option<String>,
+    gid: Option<String>,
+    /// `ok`, `missing`, `stale` (built for another user or Containerfile) or `unknown`
+    /// (buildable by cladding but built without its labels).
+    status: &'static str,
+    problems: Vec<String>,
+}
+
+fn image_summaries(context: &Context, config: &Config) -> Result<Vec<ImageSummary>> {
+    let (host_uid, host_gid) = host_ids();
+    let containerfile = containerfile_digest();
+    let mut roles = vec![
+        ("cli", config.cli_image.clone()),
+        ("sandbox", config.sandbox_image.clone()),
+    ];
+    roles.extend(proxy_image().map(|image| ("proxy", image)));
+
+    let mut summaries = Vec::new();
+    for (role, image) in roles {
+        let Some(details) = context.runtime.inspect_image(&image)? else {
+            let hint = if image_is_buildable_by_cladding(&image) {
+                "run cladding build"
+            } else {
+                "pull it or run cladding up"
+            };
+            summaries.push(ImageSummary {
+                role,
+                problems: vec![format!("{image} is not present locally; {hint}")],
+                image,
+                id: None,
+                digest: None,
+                size_bytes: None,
+                created: None,
+                uid: None,
+                gid: None,
+                status: "missing",
+            });
+            continue;
+        };
+
+        let label = |name: &str| details.labels.get(name).cloned();
+        let (uid, gid) = (label(IMAGE_UID_LABEL), label(IMAGE_GID_LABEL));
+        let mut problems = Vec::new();
+        if let Some(uid) = &uid
+            && *uid != host_uid.to_string()
+        {
+            problems.push(format!("{image} was built for UID {uid}, not {host_uid}"));
+        }
+        if let Some(gid) = &gid
+            && *gid != host_gid.to_string()
+        {
+            problems.push(format!("{image} was built for GID {gid}, not {host_gid}"));
+        }
+        if label(IMAGE_CONTAINERFILE_LABEL).is_some_and(|digest| digest != containerfile) {
+            problems.push(format!(
+                "{image} was built from another Containerfile than this cladding embeds"
+            ));
+        }
+        let status = if !problems.is_empty() {
+            "stale"
+        } else if image_is_buildable_by_cladding(&image) && uid.is_none() {
+            problems.push(format!("{image} has no cladding build labels to check"));
+            "unknown"
+        } else {
+            "ok"
+        };
+        summaries.push(ImageSummary {
+            role,
+            image,
+            id: Some(details.id),
+            digest: details.digest,
+            size_bytes: Some(details.size_bytes),
+            created: Some(details.created).filter(|created| !created.is_empty()),
+            uid,
+            gid,
+            status,
+            problems,
+        });
+    }
+    Ok(summaries)
+}
+
+/// `1.2GB`-style size, in the decimal units the engines print.
+pub(super) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{bytes}B");
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit + 1 < UNITS.len() {
+        size /= 1000.0;
+        unit += 1;
+    }
+    let precision = match size {
+        size if size < 10.0 => 2,
+        size if size < 100.0 => 1,
+        _ => 0,
+    };
+    format!("{size:.precision$}{}", UNITS[unit])
+}
+
+pub(super) fn cmd_images(context: &Context, format: PsFormat) -> Result<()> {
+    engine_required("cladding images")?;
+    let config = load_config(context)?;
+    let summaries = image_summaries(context, &config)?;
+    if format == PsFormat::Json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summaries).expect("image summaries serialize")
+        );
+        return Ok(());
+    }
+
+    let image_width = summaries
+        .iter()
+        .map(|summary| summary.image.len())
+        .max()
+        .unwrap_or_default()
+        .max("IMAGE".len());
+    println!(
+        "{:<7}  {:<image_width$}  {:<12}  {:<7}  {:<20}  {:<11}  STATUS",
+        "ROLE", "IMAGE", "ID", "SIZE", "CREATED", "UID:GID"
+    );
+    for summary in &summaries {
+        let id = summary.id.as_deref().map_or("-", |id| &id[..id.len().min(12)]);
+        let size = summary.size_bytes.map_or("-".to_string(), format_size);
+        let created = summary.created.as_deref().map_or("-".to_string(), |created| {
+            created.chars().take(20).collect()
+        });
+        let ids = match (&summary.uid, &summary.gid) {
+            (None, None) => "-".to_string(),
+            (uid, gid) => format!(
+                "{}:{}",
+                uid.as_deref().unwrap_or("?"),
+                gid.as_deref().unwrap_or("?")
+            ),
+        };
+        println!(
+            "{:<7}  {:<image_width$}  {id:<12}  {size:<7}  {created:<20}  {ids:<11}  {}",
+            summary.role, summary.image, summary.status
+        );
+    }
+    for problem in summaries.iter().flat_map(|summary| &summary.problems) {
+        output::warning(problem);
+    }
+    if summaries.iter().any(|summary| summary.status == "stale") {
+        output::hint("run cladding build to rebuild stale images");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::podman::ImageDetails;
+    use crate::cli::build::DEFAULT_CLADDING_BUILD_IMAGE;
+    use crate::cli::test_support::{fake_project, fake_runtime_with_images};
+
+    #[test]
+    fn images_flags_missing_stale_and_unlabelled_images() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("images", &runtime);
+        let config = load_config(&context).unwrap();
+        let summaries = image_summaries(&context, &config).expect("images");
+        let statuses: Vec<_> = summaries
+            .iter()
+            .map(|summary| (summary.role, summary.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [("cli", "unknown"), ("sandbox", "unknown"), ("proxy", "missing")]
+        );
+
+        let (host_uid, host_gid) = host_ids();
+        let labels = [
+            (IMAGE_UID_LABEL, (host_uid + 1).to_string()),
+            (IMAGE_GID_LABEL, host_gid.to_string()),
+            (IMAGE_CONTAINERFILE_LABEL, containerfile_digest()),
+        ];
+        runtime.state_mut().image_details.insert(
+            DEFAULT_CLADDING_BUILD_IMAGE.to_string(),
+            ImageDetails {
+                id: "0123456789abcdef".to_string(),
+                size_bytes: 1_234_567_890,
+                labels: labels
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+                ..ImageDetails::default()
+            },
+        );
+        let summaries = image_summaries(&context, &config).expect("images");
+        assert_eq!(summaries[0].status, "stale");
+        assert_eq!(
+            summaries[0].problems,
+            [format!(
+                "{DEFAULT_CLADDING_BUILD_IMAGE} was built for UID {}, not {host_uid}",
+                host_uid + 1
+            )]
+        );
+        assert_eq!(format_size(1_234_567_890), "1.23GB");
+        assert_eq!(format_size(45_600_000), "45.6MB");
+        assert_eq!(format_size(512), "512B");
+    }
+}
@@ -0,0 +1,248 @@
+//! `cladding init`: a new `.cladding` directory, optionally seeded from devcontainer.json.
+
+use anyhow::Context as _;
+use cladding::assets::{
+    materialize_config, materialize_scripts, materialize_scripts_force, write_asset_manifest,
+};
+use cladding::config::{Config, load_cladding_config, write_default_cladding_config};
+use cladding::devcontainer::{DevcontainerImport, find_devcontainer, parse_devcontainer};
+use cladding::error::{Error, Result};
+use cladding::fs_utils::path_is_symlink;
+use cladding::home::{describe_home_template, seed_home};
+use cladding::output;
+use cladding::pods::agent_home_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::Context;
+use super::build::{DEFAULT_CLI_BUILD_IMAGE, DEFAULT_SANDBOX_BUILD_IMAGE};
+
+pub(super) fn cmd_init(
+    context: &Context,
+    name_override: Option<&str>,
+    update_scripts: bool,
+    from_devcontainer: bool,
+) -> Result<()> {
+    let project_root = &context.project_root;
+    let config_dir = project_root.join("config");
+    let scripts_dir = project_root.join("scripts");
+    let home_dir = project_root.join("home");
+    let tools_dir = project_root.join("tools");
+    let cladding_config = project_root.join("cladding.json");
+    let cladding_gitignore = project_root.join(".gitignore");
+
+    if project_root.exists() && !project_root.is_dir() {
+        output::error(format!(
+            ".cladding path exists but is not a directory: {}",
+            project_root.display()
+        ));
+        return Err(Error::message("invalid .cladding path"));
+    }
+
+    // Read before anything is created, so a bad devcontainer.json leaves no half-made project.
+    let devcontainer = if from_devcontainer && !cladding_config.exists() {
+        Some(read_devcontainer(project_root)?)
+    } else {
+        None
+    };
+
+    let project_root_created = !project_root.exists();
+    fs::create_dir_all(project_root)
+        .with_context(|| format!("failed to create {}", project_root.display()))?;
+
+    if project_root_created {
+        fs::write(&cladding_gitignore, "*\n")
+            .with_context(|| format!("failed to write {}", cladding_gitignore.display()))?;
+    }
+
+    if config_dir.exists() || path_is_symlink(&config_dir) {
+        println!("config already exists: {}", config_dir.display());
+    } else {
+        fs::create_dir_all(&config_dir)
+            .with_context(|| format!("failed to create {}", config_dir.display()))?;
+        println!("initialized: {}", config_dir.display());
+    }
+
+    materialize_config(&config_dir)?;
+
+    if scripts_dir.exists() || path_is_symlink(&scripts_dir) {
+        println!("scripts already exists: {}", scripts_dir.display());
+    } else {
+        fs::create_dir_all(&scripts_dir)
+            .with_context(|| format!("failed to create {}", scripts_dir.display()))?;
+        println!("initialized: {}", scripts_dir.display());
+    }
+
+    if home_dir.exists() || path_is_symlink(&home_dir) {
+        println!("home already exists: {}", home_dir.display());
+    } else {
+        fs::create_dir_all(&home_dir)
+            .with_context(|| format!("failed to create {}", home_dir.display()))?;
+        println!("initialized: {}", home_dir.display());
+    }
+
+    if tools_dir.exists() || path_is_symlink(&tools_dir) {
+        println!("tools already exists: {}", tools_dir.display());
+    } else {
+        fs::create_dir_all(&tools_dir)
+            .with_context(|| format!("failed to create {}", tools_dir.display()))?;
+        println!("initialized: {}", tools_dir.display());
+    }
+
+    if update_scripts {
+        materialize_scripts_force(&scripts_dir)?;
+    } else {
+        materialize_scripts(&scripts_dir)?;
+    }
+
+    if cladding_config.exists() {
+        println!(
+            "cladding config already exists: {}",
+            cladding_config.display()
+        );
+        if from_devcontainer {
+            output::warning("devcontainer.json not imported: cladding.json already exists");
+            output::hint("remove it and run 'cladding init --from-devcontainer' again");
+        }
+    } else {
+        let mut generated = write_default_cladding_config(
+            name_override,
+            DEFAULT_SANDBOX_BUILD_IMAGE,
+            DEFAULT_CLI_BUILD_IMAGE,
+        )?;
+        if let Some((_, import)) = &devcontainer {
+            generated = import.apply(&generated)?;
+        }
+        fs::write(&cladding_config, generated)
+            .with_context(|| format!("failed to write {}", cladding_config.display()))?;
+        println!("generated: {}", cladding_config.display());
+        if let Some((path, import)) = &devcontainer {
+            report_devcontainer_import(path, import);
+        }
+    }
+
+    write_asset_manifest(project_root)?;
+
+    let config = load_cladding_config(project_root)?;
+    seed_home_template(context, &config)
+}
+
+/// The devcontainer.json of the workspace `project_root` belongs to, and what it maps to.
+fn read_devcontainer(project_root: &Path) -> Result<(PathBuf, DevcontainerImport)> {
+    let workspace = project_root.parent().unwrap_or(project_root);
+    let Some(path) = find_devcontainer(workspace) else {
+        output::missing(format!(
+            "devcontainer.json ({})",
+            workspace.join(".devcontainer/devcontainer.json").display()
+        ));
+        return Err(Error::message("missing devcontainer.json"));
+    };
+    let raw = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let import = parse_devcontainer(&raw).inspect_err(|_| output::file(path.display()))?;
+    Ok((path, import))
+}
+
+fn report_devcontainer_import(path: &Path, import: &DevcontainerImport) {
+    println!("imported: {}", path.display());
+    for warning in &import.warnings {
+        output::warning(format!("devcontainer.json {warning}"));
+    }
+    for port in &import.forward_ports {
+        output::hint(format!(
+            "forwarded port {port}: run 'cladding expose {port}' after 'cladding up'"
+        ));
+    }
+    if import.image.is_some() {
+        output::note(
+            "cli_image is the devcontainer image; cladding check reports it if it is not present",
+        );
+    }
+}
+
+/// Creates the home directory of each of the config's agents.
+pub(super) fn ensure_agent_homes(project_root: &Path, config: &Config) -> Result<()> {
+    for agent in &config.agents {
+        let home_dir = agent_home_dir(project_root, &agent.name);
+        fs::create_dir_all(&home_dir)
+            .with_context(|| format!("failed to create {}", home_dir.display()))?;
+    }
+    Ok(())
+}
+
+pub(super) fn seed_home_template(context: &Context, config: &Config) -> Result<()> {
+    let Some(template) = &config.home_template else {
+        return Ok(());
+    };
+
+    let mut homes = vec![("home".to_string(), context.project_root.join("home"))];
+    homes.extend(config.agents.iter().map(|agent| {
+        (
+            format!("agents/{}/home", agent.name),
+            agent_home_dir(&context.project_root, &agent.name),
+        )
+    }));
+    for (label, home_dir) in homes {
+        let summary = seed_home(&home_dir, template)?;
+        if summary.copied > 0 {
+            println!(
+                "{label}: seeded {} files from {} ({} already present)",
+                summary.copied,
+                describe_home_template(template),
+                summary.skipped
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_support::fake_runtime_with_images;
+    use std::env;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn init_from_devcontainer_generates_the_mapped_config() {
+        let workspace = env::temp_dir().join(format!(
+            "cladding-cli-devcontainer-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let context = Context {
+            project_root: workspace.join(".cladding"),
+            runtime: fake_runtime_with_images(),
+            pool_member: None,
+        };
+        fs::create_dir_all(&workspace).unwrap();
+        assert!(cmd_init(&context, Some("demo"), false, true).is_err());
+        assert!(!context.project_root.exists());
+
+        fs::create_dir_all(workspace.join(".devcontainer")).unwrap();
+        fs::write(
+            workspace.join(".devcontainer/devcontainer.json"),
+            r#"{
+  "image": "localhost/devcontainer:latest",
+  "mounts": ["source=${localWorkspaceFolder}/data,target=/opt/data,type=bind"],
+  "containerEnv": { "EDITOR": "vim" }, // trailing comment
+}"#,
+        )
+        .unwrap();
+        cmd_init(&context, Some("demo"), false, true).expect("init");
+
+        let config = load_cladding_config(&context.project_root).expect("config loads");
+        assert_eq!(config.cli_image, "localhost/devcontainer:latest");
+        assert_eq!(config.sandbox_image, DEFAULT_SANDBOX_BUILD_IMAGE);
+        assert_eq!(config.mounts[0].mount_path, "/opt/data");
+        assert_eq!(
+            config.mounts[0].host_path,
+            Some(context.project_root.join("../data"))
+        );
+        assert_eq!(config.env["EDITOR"], "vim");
+        fs::remove_dir_all(&workspace).unwrap();
+    }
+}
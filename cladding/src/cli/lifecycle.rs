@@ -0,0 +1,865 @@
+//! `up`, `down`, `restart` and `destroy`, for one project or every running one.
+
+use anyhow::Context as _;
+use cladding::config::{Config, load_cladding_config};
+use cladding::error::{Error, Result};
+use cladding::fs_utils::{canonicalize_path, engine_host_path, host_path_from_engine};
+use cladding::lock::acquire_project_lock;
+use cladding::network::{EGRESS_NETWORK, parse_cladding_pool_index};
+use cladding::output;
+use cladding::podman::{EnsureNetworkOutcome, engine_required};
+use cladding::pods::{render_pods_yaml, select_pod_from_rendered};
+use cladding::pool::parse_pool_member_index;
+use cladding::progress;
+use notify::{RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::io::{self, IsTerminal, Write as _};
+use std::path::Path;
+use std::time::Duration;
+
+use super::{Context, PodRole, load_config};
+use super::check::{
+    check_required_binaries, check_required_config_files, check_required_host_paths,
+    check_required_images, check_required_scripts_files, warn_on_script_mismatch,
+};
+use super::expose::remove_project_expose_proxies;
+use super::init::{ensure_agent_homes, seed_home_template};
+use super::network::{resolve_active_project_network_settings, select_available_network_settings};
+use super::proxy::cmd_reload_proxy;
+
+pub(super) struct ProjectRuntimeStatus {
+    pub(super) current_project_root: String,
+    pub(super) already_running: bool,
+}
+
+pub(super) fn current_project_root(context: &Context) -> Result<String> {
+    Ok(engine_host_path(&canonicalize_path(&context.project_root)?))
+}
+
+pub(super) fn project_runtime_status(
+    context: &Context,
+    config: &Config,
+) -> Result<ProjectRuntimeStatus> {
+    let current_project_root = current_project_root(context)?;
+
+    let mut conflicting_roots = Vec::new();
+    let mut already_running = false;
+    for project in context.runtime.list_running_projects()? {
+        if project.name != config.name {
+            continue;
+        }
+
+        let normalized_root = canonicalize_path(Path::new(&project.project_root))
+            .map(|path| engine_host_path(&path))
+            .unwrap_or_else(|_| project.project_root.clone());
+
+        if normalized_root == current_project_root {
+            already_running = true;
+        } else {
+            conflicting_roots.push(project.project_root);
+        }
+    }
+
+    if !conflicting_roots.is_empty() {
+        output::error(format!(
+            "cladding project '{}' is already running from a different PROJECT_ROOT",
+            config.name
+        ));
+        eprintln!("current PROJECT_ROOT: {current_project_root}");
+        for root in conflicting_roots {
+            eprintln!("running PROJECT_ROOT: {root}");
+        }
+        return Err(Error::message(
+            "project already running from different PROJECT_ROOT",
+        ));
+    }
+
+    Ok(ProjectRuntimeStatus {
+        current_project_root,
+        already_running,
+    })
+}
+
+pub(super) fn cmd_up(context: &Context, fix_network: bool) -> Result<()> {
+    let config = progress::step("load-config", || load_config(context))?;
+    let status = progress::step("check-running", || project_runtime_status(context, &config))?;
+
+    if status.already_running {
+        progress::message(format!(
+            "already running: {} ({})",
+            config.name, status.current_project_root
+        ));
+        return Ok(());
+    }
+
+    start_project(context, &config, fix_network)
+}
+
+/// Checks the project and starts its pods on a free network slot.
+pub(super) fn start_project(context: &Context, config: &Config, fix_network: bool) -> Result<()> {
+    progress::step("check-binaries", || check_required_binaries(context))?;
+    let network_settings = progress::step("select-network", || {
+        select_available_network_settings(context, config, fix_network)
+    })?;
+    progress::step("check-requirements", || {
+        check_required_images(context, config)?;
+        ensure_agent_homes(&context.project_root, config)?;
+        check_required_host_paths(context, config, &network_settings)?;
+        check_required_config_files(context)?;
+        check_required_scripts_files(context)?;
+        warn_on_script_mismatch(context)
+    })?;
+    progress::step("seed-home", || seed_home_template(context, config))?;
+    progress::step("start-pods", || {
+        if config.isolated {
+            context.runtime.ensure_egress_network()?;
+        }
+        let rendered = render_pods_yaml(&context.project_root, config, &network_settings)?;
+        context
+            .runtime
+            .play_kube(&rendered, &network_settings, false)?;
+        if config.isolated {
+            // The pool network is internal, so the proxy pod is the only one with a way out.
+            context
+                .runtime
+                .pod_network_connect(&network_settings.proxy_pod_name, EGRESS_NETWORK)?;
+        }
+        Ok(())
+    })
+}
+
+/// Events within this window after a change are applied together.
+const UP_WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// What a change to a file under `.cladding` requires of a running project, in increasing
+/// order of cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ConfigChange {
+    /// Not read by the pods, or reloaded by them without help (mcp-run watches its policy).
+    Ignored,
+    /// A domain list, which `squid -k reconfigure` picks up.
+    ReloadProxy,
+    /// Rendered into pods.yaml or read only at container start.
+    Reapply,
+}
+
+fn classify_config_change(project_root: &Path, path: &Path) -> ConfigChange {
+    let Ok(relative) = path.strip_prefix(project_root) else {
+        return ConfigChange::Ignored;
+    };
+    if relative == Path::new("cladding.json") {
+        return ConfigChange::Reapply;
+    }
+    let Ok(config_path) = relative.strip_prefix("config") else {
+        return ConfigChange::Ignored;
+    };
+    if config_path.starts_with("sandbox_commands") {
+        return ConfigChange::Ignored;
+    }
+    let is_domain_list = config_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with("_domains.lst"));
+    if is_domain_list {
+        ConfigChange::ReloadProxy
+    } else {
+        ConfigChange::Reapply
+    }
+}
+
+/// Runs `cladding up`, then re-applies the project whenever its config changes until
+/// interrupted. The pods keep running after Ctrl-C.
+pub(super) fn cmd_up_watch(context: &Context, wait: bool) -> Result<()> {
+    engine_required("cladding up --watch")?;
+    {
+        let _lock = acquire_project_lock(&context.project_root, "up --watch", wait)?;
+        cmd_up(context, false)?;
+    }
+    let mut applied = load_config(context)?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })
+    .with_context(|| "failed to start file watcher")?;
+    let config_dir = context.project_root.join("config");
+    watcher
+        .watch(&config_dir, RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {}", config_dir.display()))?;
+    // cladding.json is watched through its directory so editors that replace the file on save
+    // keep being seen.
+    watcher
+        .watch(&context.project_root, RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch {}", context.project_root.display()))?;
+    println!(
+        "watching {} and cladding.json; press Ctrl-C to stop (pods keep running)",
+        config_dir.display()
+    );
+
+    let classify = |event: notify::Result<notify::Event>| match event {
+        Ok(event) => event
+            .paths
+            .iter()
+            .map(|path| classify_config_change(&context.project_root, path))
+            .max()
+            .unwrap_or(ConfigChange::Ignored),
+        Err(err) => {
+            output::warning(format!("file watcher error: {err}"));
+            ConfigChange::Ignored
+        }
+    };
+    loop {
+        let Ok(event) = receiver.recv() else {
+            return Err(Error::message("file watcher stopped"));
+        };
+        let mut change = classify(event);
+        let deadline = std::time::Instant::now() + UP_WATCH_DEBOUNCE;
+        while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+            match receiver.recv_timeout(remaining) {
+                Ok(event) => change = change.max(classify(event)),
+                Err(_) => break,
+            }
+        }
+
+        let result = match change {
+            ConfigChange::Ignored => continue,
+            ConfigChange::ReloadProxy => {
+                let _lock = acquire_project_lock(&context.project_root, "up --watch", true)?;
+                cmd_reload_proxy(context).map(|()| println!("proxy reloaded"))
+            }
+            ConfigChange::Reapply => {
+                let _lock = acquire_project_lock(&context.project_root, "up --watch", true)?;
+                reapply_project(context, &applied).map(|config| {
+                    applied = config;
+                    println!("project re-applied");
+                })
+            }
+        };
+        if let Err(err) = result {
+            output::error(format!("failed to apply config change: {err}"));
+            output::hint("fix the config and save again, or stop with Ctrl-C");
+        }
+    }
+}
+
+/// Takes down the pods started from `applied` and starts them again from the config on disk,
+/// returning that config. A config that does not load leaves the pods running.
+fn reapply_project(context: &Context, applied: &Config) -> Result<Config> {
+    let config = load_config(context)?;
+    // After a failed re-apply the pods may already be gone.
+    if project_runtime_status(context, applied)?.already_running {
+        let network_settings =
+            resolve_active_project_network_settings(context, applied, "cladding up --watch")?;
+        let rendered = render_pods_yaml(&context.project_root, applied, &network_settings)?;
+        context
+            .runtime
+            .play_kube(&rendered, &network_settings, true)?;
+    }
+    cmd_up(context, false)?;
+    Ok(config)
+}
+
+pub(super) fn cmd_down(context: &Context) -> Result<()> {
+    let config = progress::step("load-config", || load_config(context))?;
+    stop_project(context, &config, "cladding down")
+}
+
+/// Takes down the running project's pods and removes its expose proxies.
+pub(super) fn stop_project(context: &Context, config: &Config, command_name: &str) -> Result<()> {
+    let (project_root, network_settings) = progress::step("find-running-project", || {
+        Ok((
+            current_project_root(context)?,
+            resolve_active_project_network_settings(context, config, command_name)?,
+        ))
+    })?;
+    let rendered = render_pods_yaml(&context.project_root, config, &network_settings)?;
+    let pod_result = progress::step("stop-pods", || {
+        context.runtime.play_kube(&rendered, &network_settings, true)
+    });
+    let cleanup_result = progress::step("remove-expose-proxies", || {
+        remove_project_expose_proxies(context, config, &project_root, true)
+    });
+
+    pod_result?;
+    cleanup_result
+}
+
+/// Takes down and starts again the running project's pods, or just `pod`, on the network slot
+/// they already have. Everything `cladding up` checks is checked before anything is stopped.
+/// Expose proxies keep working because the pods come back on the same addresses.
+pub(super) fn cmd_restart(context: &Context, pod: Option<PodRole>) -> Result<()> {
+    let config = load_config(context)?;
+    let status = project_runtime_status(context, &config)?;
+    if !status.already_running {
+        output::error(format!(
+            "cladding project '{}' is not running ({})",
+            config.name, status.current_project_root
+        ));
+        output::hint("start it with 'cladding up'");
+        return Err(Error::message("project not running"));
+    }
+    let network_settings =
+        resolve_active_project_network_settings(context, &config, "cladding restart")?;
+
+    check_required_binaries(context)?;
+    check_required_images(context, &config)?;
+    ensure_agent_homes(&context.project_root, &config)?;
+    check_required_host_paths(context, &config, &network_settings)?;
+    check_required_config_files(context)?;
+    check_required_scripts_files(context)?;
+    warn_on_script_mismatch(context)?;
+    seed_home_template(context, &config)?;
+
+    let rendered = render_pods_yaml(&context.project_root, &config, &network_settings)?;
+    let rendered = match pod {
+        Some(role) => {
+            let pod_name = role.pod_name(&network_settings);
+            select_pod_from_rendered(&rendered, pod_name).ok_or_else(|| {
+                Error::message(format!("pod {pod_name} missing from rendered pods.yaml"))
+            })?
+        }
+        None => rendered,
+    };
+    context
+        .runtime
+        .play_kube(&rendered, &network_settings, true)?;
+    if pod.is_none() {
+        // Nothing is attached to the network now, so a changed `isolated` setting can apply.
+        match context
+            .runtime
+            .ensure_pool_network(&network_settings, config.isolated)?
+        {
+            EnsureNetworkOutcome::Ready => {}
+            EnsureNetworkOutcome::SubnetMismatch | EnsureNetworkOutcome::IsolationMismatch => {
+                output::error(format!(
+                    "network {} no longer matches the project",
+                    network_settings.network
+                ));
+                output::hint("run 'cladding up' to start on a fresh network slot");
+                return Err(Error::message("network mismatch on restart"));
+            }
+        }
+    }
+    if config.isolated {
+        context.runtime.ensure_egress_network()?;
+    }
+    context
+        .runtime
+        .play_kube(&rendered, &network_settings, false)?;
+    if config.isolated && matches!(pod, None | Some(PodRole::Proxy)) {
+        context
+            .runtime
+            .pod_network_connect(&network_settings.proxy_pod_name, EGRESS_NETWORK)?;
+    }
+    match pod {
+        Some(role) => println!("restarted: {}", role.pod_name(&network_settings)),
+        None => println!("restarted: {}", config.name),
+    }
+    Ok(())
+}
+
+pub(super) fn cmd_destroy(context: &Context, remove_network: bool) -> Result<()> {
+    let config = load_config(context)?;
+    let project_root = current_project_root(context)?;
+    let network_settings =
+        resolve_active_project_network_settings(context, &config, "cladding destroy")?;
+
+    let destroy_result = context.runtime.force_remove(&[
+        &network_settings.cli_pod_name,
+        &network_settings.sandbox_pod_name,
+        &network_settings.proxy_pod_name,
+    ]);
+    let cleanup_result = remove_project_expose_proxies(context, &config, &project_root, true);
+
+    destroy_result?;
+    cleanup_result?;
+    if remove_network {
+        remove_idle_network(context, &network_settings.network)?;
+    }
+    Ok(())
+}
+
+/// Removes `network` unless a container still uses it, which only warrants a warning: the
+/// pods are gone either way, and the next `cladding up` reuses the network.
+fn remove_idle_network(context: &Context, network: &str) -> Result<()> {
+    let users = context.runtime.network_containers(network)?;
+    if !users.is_empty() {
+        output::warning(format!(
+            "kept network {network}: still used by {}",
+            users.join(", ")
+        ));
+        return Ok(());
+    }
+    context.runtime.remove_network(network)?;
+    println!("removed network: {network}");
+    Ok(())
+}
+
+/// `destroy --all --network`: every `cladding-N` network nothing uses, including those left
+/// behind by earlier destroys. The shared `cladding-egress` network is kept.
+fn remove_idle_project_networks(context: &Context) -> Result<()> {
+    let networks: BTreeSet<String> = context
+        .runtime
+        .list_network_subnets()?
+        .into_iter()
+        .map(|network| network.name)
+        .filter(|name| parse_cladding_pool_index(name).is_some())
+        .collect();
+    for network in &networks {
+        remove_idle_network(context, network)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum AllProjectsAction {
+    Down,
+    /// With `network`, idle project networks are removed once every project is destroyed.
+    Destroy { network: bool },
+}
+
+impl AllProjectsAction {
+    fn command_name(self) -> &'static str {
+        match self {
+            AllProjectsAction::Down => "down",
+            AllProjectsAction::Destroy { .. } => "destroy",
+        }
+    }
+}
+
+/// `down --all` and `destroy --all`: every cladding project running on the host, including
+/// pool members, each under its own project lock. A project that fails is reported and the
+/// rest are still taken down.
+pub(super) fn cmd_all_projects(
+    context: &Context,
+    action: AllProjectsAction,
+    yes: bool,
+    wait: bool,
+) -> Result<()> {
+    let command_name = action.command_name();
+    let remove_networks = matches!(action, AllProjectsAction::Destroy { network: true });
+    let projects = context.runtime.list_running_projects()?;
+    if projects.is_empty() {
+        println!("no cladding projects are running");
+        if remove_networks {
+            return remove_idle_project_networks(context);
+        }
+        return Ok(());
+    }
+    println!("running cladding projects:");
+    for project in &projects {
+        println!("  {}  {}", project.name, project.project_root);
+    }
+    if !yes && !confirm(&format!("{command_name} all {} projects?", projects.len()))? {
+        return Err(Error::message("aborted"));
+    }
+
+    let mut failed = 0usize;
+    for project in &projects {
+        let result = project_context(context, &project.name, &project.project_root).and_then(
+            |project_context| {
+                let _lock =
+                    acquire_project_lock(&project_context.project_root, command_name, wait)?;
+                match action {
+                    AllProjectsAction::Down => cmd_down(&project_context),
+                    AllProjectsAction::Destroy { .. } => cmd_destroy(&project_context, false),
+                }
+            },
+        );
+        match result {
+            Ok(()) => println!("{command_name}: {}", project.name),
+            Err(error) => {
+                failed += 1;
+                output::error(format!("{}: {error}", project.name));
+            }
+        }
+    }
+    // Networks of projects that failed are still in use and only draw a warning.
+    let networks_result = if remove_networks {
+        remove_idle_project_networks(context)
+    } else {
+        Ok(())
+    };
+    if failed > 0 {
+        output::hint(format!(
+            "run 'cladding {command_name}' in the project, or 'cladding ps' to see what is left"
+        ));
+        return Err(Error::message(format!(
+            "{failed} of {} projects failed to {command_name}",
+            projects.len()
+        )));
+    }
+    networks_result
+}
+
+/// A context for the running project `name` labeled with `project_root`, acting on the pool
+/// member when `name` is one of the project's members.
+fn project_context(context: &Context, name: &str, project_root: &str) -> Result<Context> {
+    let project_root = host_path_from_engine(project_root);
+    let config = load_cladding_config(&project_root)?;
+    let pool_member = if config.name == name {
+        None
+    } else if parse_pool_member_index(&config.name, name).is_some() {
+        Some(name.to_string())
+    } else {
+        return Err(Error::message(format!(
+            "{} now names the project '{}'",
+            project_root.join("cladding.json").display(),
+            config.name
+        )));
+    };
+    Ok(Context {
+        project_root,
+        runtime: context.runtime.clone(),
+        pool_member,
+    })
+}
+
+/// Asks `question` on the terminal. Without one there is nobody to answer, so callers must
+/// be told to pass `--yes`.
+fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        output::error(format!("{question} needs confirmation, but stdin is not a terminal"));
+        output::hint("pass --yes to skip the question");
+        return Err(Error::message("confirmation required"));
+    }
+    eprint!("{question} [y/N] ");
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .with_context(|| "failed to read the answer")?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "YES" | "Yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::runtime::{ContainerRuntime, FakeNetwork, FakePod};
+    use crate::cli::exec::cli_app_container;
+    use crate::cli::network::project_network_settings;
+    use crate::cli::test_support::{expose_proxy, fake_project, fake_runtime_with_images};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn config_changes_are_classified_by_path() {
+        let root = Path::new("/work/.cladding");
+        for (path, expected) in [
+            ("/work/.cladding/cladding.json", ConfigChange::Reapply),
+            ("/work/.cladding/config/squid.conf", ConfigChange::Reapply),
+            ("/work/.cladding/config/cli_host_ports.lst", ConfigChange::Reapply),
+            ("/work/.cladding/config/cli_domains.lst", ConfigChange::ReloadProxy),
+            ("/work/.cladding/config/sandbox_domains.lst", ConfigChange::ReloadProxy),
+            ("/work/.cladding/config/sandbox_commands/curl.rego", ConfigChange::Ignored),
+            ("/work/.cladding/cladding.lock", ConfigChange::Ignored),
+            ("/work/.cladding/home/.bashrc", ConfigChange::Ignored),
+            ("/elsewhere/cladding.json", ConfigChange::Ignored),
+        ] {
+            assert_eq!(classify_config_change(root, Path::new(path)), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn up_starts_a_cli_pod_per_agent() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("agents", &runtime);
+        let config_path = context.project_root.join("cladding.json");
+        let mut raw: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        raw["agents"] = serde_json::json!([{ "name": "codex" }]);
+        fs::write(&config_path, raw.to_string()).unwrap();
+
+        cmd_up(&context, false).expect("up");
+        let pod_names: Vec<String> = runtime
+            .state()
+            .pods
+            .iter()
+            .map(|pod| pod.name.clone())
+            .collect();
+        assert!(pod_names.contains(&"demo-cli-codex-pod".to_string()), "{pod_names:?}");
+        assert!(context.project_root.join("agents/codex/home").is_dir());
+
+        let config = load_config(&context).unwrap();
+        let network_settings = project_network_settings(&config, 0).unwrap();
+        assert_eq!(
+            cli_app_container(&config, &network_settings, Some("codex")).unwrap(),
+            "demo-cli-codex-pod-cli-app"
+        );
+        assert!(cli_app_container(&config, &network_settings, Some("aider")).is_err());
+    }
+
+    #[test]
+    fn up_skips_busy_pool_slots_and_down_removes_pods() {
+        let runtime = fake_runtime_with_images();
+        runtime.state_mut().pods.push(FakePod {
+            name: "other-proxy-pod".to_string(),
+            project_name: "other".to_string(),
+            project_root: "/elsewhere/.cladding".to_string(),
+            network: "cladding-0".to_string(),
+            extra_networks: Vec::new(),
+            containers: Vec::new(),
+        });
+        runtime.state_mut().networks.insert(
+            "cladding-1".to_string(),
+            FakeNetwork {
+                subnet: "10.90.1.0/24".to_string(),
+                internal: false,
+            },
+        );
+        runtime.state_mut().networks.insert(
+            "lan".to_string(),
+            FakeNetwork {
+                subnet: "10.90.2.0/24".to_string(),
+                internal: false,
+            },
+        );
+        let context = fake_project("up-down", &runtime);
+        let project_root = current_project_root(&context).unwrap();
+
+        cmd_up(&context, false).expect("up");
+        let demo_pods: Vec<FakePod> = runtime
+            .state()
+            .pods
+            .iter()
+            .filter(|pod| pod.project_name == "demo")
+            .cloned()
+            .collect();
+        assert_eq!(demo_pods.len(), 3);
+        for pod in &demo_pods {
+            // cladding-0 is in use and cladding-2's subnet belongs to another network.
+            assert_eq!(pod.network, "cladding-1");
+            assert_eq!(pod.project_root, project_root);
+        }
+
+        cmd_up(&context, false).expect("second up is a no-op");
+        assert_eq!(runtime.state().pods.len(), 4);
+
+        runtime
+            .state_mut()
+            .expose_proxies
+            .push(expose_proxy(&context, "abc"));
+        cmd_down(&context).expect("down");
+        let state = runtime.state();
+        assert_eq!(state.pods.len(), 1);
+        assert_eq!(state.pods[0].project_name, "other");
+        assert!(state.expose_proxies.is_empty());
+    }
+
+    #[test]
+    fn reapply_restarts_pods_with_the_new_config() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("reapply", &runtime);
+        cmd_up(&context, false).expect("up");
+        let applied = load_cladding_config(&context.project_root).unwrap();
+
+        fs::write(context.project_root.join("cladding.json"), "{ not json").unwrap();
+        assert!(reapply_project(&context, &applied).is_err());
+        assert_eq!(runtime.state().pods.len(), 3, "invalid config keeps the pods");
+
+        fs::write(
+            context.project_root.join("cladding.json"),
+            r#"{"name": "demo", "cli_image": "localhost/cladding-default:latest", "sandbox_image": "localhost/cladding-default:latest", "isolated": true}"#,
+        )
+        .unwrap();
+        let config = reapply_project(&context, &applied).expect("reapply");
+        assert!(config.isolated);
+        let state = runtime.state();
+        assert_eq!(state.pods.len(), 3);
+        let proxy = state
+            .pods
+            .iter()
+            .find(|pod| pod.name == "demo-proxy-pod")
+            .expect("proxy pod");
+        assert_eq!(proxy.extra_networks, [EGRESS_NETWORK]);
+    }
+
+    #[test]
+    fn restart_keeps_the_network_slot_and_can_target_one_pod() {
+        let runtime = fake_runtime_with_images();
+        runtime.state_mut().pods.push(FakePod {
+            name: "other-proxy-pod".to_string(),
+            project_name: "other".to_string(),
+            project_root: "/elsewhere/.cladding".to_string(),
+            network: "cladding-0".to_string(),
+            extra_networks: Vec::new(),
+            containers: Vec::new(),
+        });
+        let context = fake_project("restart", &runtime);
+        assert!(cmd_restart(&context, None).is_err(), "not running yet");
+        fs::write(
+            context.project_root.join("cladding.json"),
+            r#"{"name": "demo", "cli_image": "localhost/cladding-default:latest", "sandbox_image": "localhost/cladding-default:latest", "isolated": true}"#,
+        )
+        .unwrap();
+        cmd_up(&context, false).expect("up");
+        runtime.state_mut().pods.retain(|pod| pod.project_name == "demo");
+
+        cmd_restart(&context, Some(PodRole::Proxy)).expect("restart proxy");
+        let names: Vec<String> = runtime
+            .state()
+            .pods
+            .iter()
+            .map(|pod| pod.name.clone())
+            .collect();
+        assert_eq!(names, ["demo-sandbox-pod", "demo-cli-pod", "demo-proxy-pod"]);
+
+        cmd_restart(&context, None).expect("restart all");
+        let state = runtime.state();
+        assert_eq!(state.pods.len(), 3);
+        for pod in &state.pods {
+            // cladding-0 is free again, but the project keeps the slot it had.
+            assert_eq!(pod.network, "cladding-1");
+            let expected: &[&str] = if pod.name == "demo-proxy-pod" {
+                &[EGRESS_NETWORK]
+            } else {
+                &[]
+            };
+            assert_eq!(pod.extra_networks, expected, "{}", pod.name);
+        }
+    }
+
+    #[test]
+    fn isolated_up_gives_only_the_proxy_egress() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("isolated", &runtime);
+        fs::write(
+            context.project_root.join("cladding.json"),
+            r#"{"name": "demo", "cli_image": "localhost/cladding-default:latest", "sandbox_image": "localhost/cladding-default:latest", "isolated": true}"#,
+        )
+        .unwrap();
+
+        cmd_up(&context, false).expect("up");
+        let state = runtime.state();
+        assert!(state.networks["cladding-0"].internal);
+        assert!(state.networks.contains_key(EGRESS_NETWORK));
+        for pod in &state.pods {
+            let expected: &[&str] = if pod.name == "demo-proxy-pod" {
+                &[EGRESS_NETWORK]
+            } else {
+                &[]
+            };
+            assert_eq!(pod.extra_networks, expected, "{}", pod.name);
+        }
+    }
+
+    #[test]
+    fn up_refuses_a_project_running_from_another_root() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("conflict", &runtime);
+        runtime.state_mut().pods.push(FakePod {
+            name: "demo-proxy-pod".to_string(),
+            project_name: "demo".to_string(),
+            project_root: "/elsewhere/.cladding".to_string(),
+            network: "cladding-0".to_string(),
+            extra_networks: Vec::new(),
+            containers: Vec::new(),
+        });
+
+        assert!(cmd_up(&context, false).is_err());
+        assert_eq!(runtime.state().pods.len(), 1);
+    }
+
+    #[test]
+    fn destroy_removes_pods_and_expose_proxies() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("destroy", &runtime);
+        cmd_up(&context, false).expect("up");
+        runtime
+            .state_mut()
+            .expose_proxies
+            .push(expose_proxy(&context, "abc"));
+
+        cmd_destroy(&context, false).expect("destroy");
+        let state = runtime.state();
+        assert!(state.pods.is_empty());
+        assert!(state.expose_proxies.is_empty());
+        assert!(cmd_down(&context).is_err(), "nothing left to take down");
+    }
+
+    #[test]
+    fn destroy_network_removes_the_project_network_once_nothing_uses_it() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("destroy-network", &runtime);
+        cmd_up(&context, false).expect("up");
+        assert!(runtime.state().networks.contains_key("cladding-0"));
+        cmd_destroy(&context, true).expect("destroy --network");
+        assert!(!runtime.state().networks.contains_key("cladding-0"));
+
+        cmd_up(&context, false).expect("up again");
+        runtime.state_mut().pods.push(FakePod {
+            name: "stray".to_string(),
+            project_name: String::new(),
+            project_root: String::new(),
+            network: "cladding-0".to_string(),
+            extra_networks: Vec::new(),
+            containers: Vec::new(),
+        });
+        cmd_destroy(&context, true).expect("a network in use is only a warning");
+        assert!(runtime.state().networks.contains_key("cladding-0"));
+
+        runtime.state_mut().pods.clear();
+        runtime.ensure_egress_network().expect("egress network");
+        let elsewhere = Context {
+            project_root: env::temp_dir().join("cladding-cli-destroy-network-elsewhere/.cladding"),
+            runtime: runtime.clone(),
+            pool_member: None,
+        };
+        cmd_all_projects(&elsewhere, AllProjectsAction::Destroy { network: true }, true, false)
+            .expect("destroy --all --network");
+        let networks: Vec<String> = runtime.state().networks.keys().cloned().collect();
+        assert_eq!(networks, [EGRESS_NETWORK]);
+    }
+
+    #[test]
+    fn up_fix_network_recreates_an_idle_network_on_the_wrong_subnet() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("fix-network", &runtime);
+        runtime.state_mut().networks.insert(
+            "cladding-0".to_string(),
+            FakeNetwork {
+                subnet: "10.1.2.0/24".to_string(),
+                internal: false,
+            },
+        );
+        cmd_up(&context, false).expect("up");
+        assert_eq!(runtime.state().pods[0].network, "cladding-1");
+        cmd_down(&context).expect("down");
+
+        cmd_up(&context, true).expect("up --fix-network");
+        let state = runtime.state();
+        assert!(state.pods.iter().all(|pod| pod.network == "cladding-0"));
+        assert_eq!(state.networks["cladding-0"].subnet, "10.90.0.0/24");
+    }
+
+    #[test]
+    fn down_all_stops_every_running_project_and_pool_member() {
+        let runtime = fake_runtime_with_images();
+        let first = fake_project("down-all-first", &runtime);
+        let second = fake_project("down-all-second", &runtime);
+        let config_path = second.project_root.join("cladding.json");
+        let renamed = fs::read_to_string(&config_path)
+            .unwrap()
+            .replace("\"demo\"", "\"other\"");
+        fs::write(&config_path, renamed).unwrap();
+        cmd_up(&first, false).expect("up first");
+        cmd_up(&second, false).expect("up second");
+        let member = Context {
+            project_root: first.project_root.clone(),
+            runtime: runtime.clone(),
+            pool_member: Some("demo-pool-0".to_string()),
+        };
+        cmd_up(&member, false).expect("up pool member");
+        assert_eq!(runtime.list_running_projects().unwrap().len(), 3);
+
+        let elsewhere = Context {
+            project_root: env::temp_dir().join("cladding-cli-down-all-elsewhere/.cladding"),
+            runtime: runtime.clone(),
+            pool_member: None,
+        };
+        cmd_all_projects(&elsewhere, AllProjectsAction::Down, true, false).expect("down --all");
+        assert!(runtime.state().pods.is_empty());
+        cmd_all_projects(&elsewhere, AllProjectsAction::Destroy { network: false }, true, false)
+            .expect("nothing left is not an error");
+    }
+}
@@ -0,0 +1,92 @@
+//! `cladding logs`.
+
+use anyhow::Context as _;
+use cladding::config::Config;
+use cladding::engine::engine_command;
+use cladding::error::{Error, Result};
+use cladding::network::{project_container_name, sidecar_container_name};
+use cladding::output;
+use cladding::podman::engine_required;
+
+use super::{Context, LogsTarget, load_config};
+use super::service::cmd_service_logs;
+
+pub(super) fn cmd_logs(
+    context: &Context,
+    target: LogsTarget,
+    follow: bool,
+    since: Option<&str>,
+) -> Result<()> {
+    engine_required("cladding logs")?;
+    let config = load_config(context)?;
+    let role = match target {
+        LogsTarget::Proxy => "proxy",
+        LogsTarget::Sandbox => "sandbox",
+        LogsTarget::Cli => "cli",
+        LogsTarget::Service(name) => {
+            if since.is_some() {
+                output::error("--since is not supported for service logs");
+                return Err(Error::message("unsupported --since"));
+            }
+            return cmd_service_logs(context, &config, &name, follow);
+        }
+        LogsTarget::Sidecar(name) => {
+            if !config.sidecars.iter().any(|sidecar| sidecar.name == name) {
+                output::error(format!("no sidecar named '{name}' in cladding.json"));
+                return Err(Error::message("unknown sidecar"));
+            }
+            return run_container_logs(
+                context,
+                &config,
+                "sidecar",
+                &sidecar_container_name(&config.name, &name),
+                follow,
+                since,
+            );
+        }
+    };
+    run_container_logs(
+        context,
+        &config,
+        role,
+        &project_container_name(&config.name, role),
+        follow,
+        since,
+    )
+}
+
+fn run_container_logs(
+    context: &Context,
+    config: &Config,
+    role: &str,
+    container_name: &str,
+    follow: bool,
+    since: Option<&str>,
+) -> Result<()> {
+
+    if !context.runtime.container_exists(container_name)? {
+        output::error(format!(
+            "{} container '{}' is missing for project '{}'",
+            role,
+            container_name,
+            config.name
+        ));
+        output::hint("run 'cladding up'");
+        return Err(Error::message("missing container"));
+    }
+
+    let mut cmd = engine_command();
+    cmd.arg("logs");
+    if follow {
+        cmd.arg("--follow");
+    }
+    if let Some(since) = since {
+        cmd.args(["--since", since]);
+    }
+    let status = cmd
+        .arg(container_name)
+        .status()
+        .with_context(|| "failed to run podman logs")?;
+
+    cladding::podman::ensure_success(status, "podman logs")
+}
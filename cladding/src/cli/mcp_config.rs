@@ -0,0 +1,31 @@
+//! `cladding mcp-config`: client config for the sandbox MCP server.
+
+use cladding::error::Result;
+use cladding::mcp_config::{render_claude_config, render_generic_config, sandbox_mcp_endpoint};
+use cladding::output;
+
+use super::{Context, McpConfigFormat, load_config};
+
+pub(super) fn cmd_mcp_config(context: &Context, format: McpConfigFormat) -> Result<()> {
+    let config = load_config(context)?;
+    let endpoint = sandbox_mcp_endpoint(&config.name);
+
+    match format {
+        McpConfigFormat::Claude => {
+            println!("{}", render_claude_config(&endpoint));
+            output::hint(
+                "merge this into .mcp.json in the workspace, or run inside 'cladding run':"
+            );
+            eprintln!(
+                "  claude mcp add --transport http {} {}",
+                endpoint.server_name, endpoint.url
+            );
+        }
+        McpConfigFormat::GenericJson => println!("{}", render_generic_config(&endpoint)),
+    }
+    output::note(format!(
+        "{} only resolves inside the cli container; start the project with 'cladding up'",
+        endpoint.url
+    ));
+    Ok(())
+}
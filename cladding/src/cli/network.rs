@@ -0,0 +1,191 @@
+//! Picking and checking the pool subnet a project runs on.
+
+use cladding::config::Config;
+use cladding::error::{Error, Result};
+use cladding::fs_utils::canonicalize_path;
+use cladding::network::{parse_cladding_pool_index, resolve_network_settings};
+use cladding::output;
+use cladding::podman::EnsureNetworkOutcome;
+use std::path::Path;
+
+use super::Context;
+
+/// The first free `cladding-N` slot whose network is, or can be made, ready for `config`.
+/// With `fix_network`, an idle network found on the wrong subnet is recreated rather than
+/// its slot skipped.
+pub(super) fn select_available_network_settings(
+    context: &Context,
+    config: &Config,
+    fix_network: bool,
+) -> Result<cladding::network::NetworkSettings> {
+    let running = context.runtime.list_running_project_networks()?;
+    let mut used = std::collections::HashSet::new();
+    for project in running {
+        let Some(index) = parse_cladding_pool_index(&project.network) else {
+            continue;
+        };
+        used.insert(index);
+    }
+
+    let mut subnet_to_networks: std::collections::HashMap<String, Vec<String>> =
+        std::collections::HashMap::new();
+    for entry in context.runtime.list_network_subnets()? {
+        subnet_to_networks
+            .entry(entry.subnet)
+            .or_default()
+            .push(entry.name);
+    }
+
+    let mut mismatched = 0usize;
+    let mut isolation_mismatched = 0usize;
+    let mut conflicts = 0usize;
+    for index in 0u16..=255 {
+        let index = index as u8;
+        if !used.contains(&index) {
+            let candidate_subnet = format!("10.90.{index}.0/24");
+            let candidate_network = format!("cladding-{index}");
+            if let Some(names) = subnet_to_networks.get(&candidate_subnet)
+                && names.iter().any(|name| name != &candidate_network)
+            {
+                conflicts += 1;
+                continue;
+            }
+            let candidate = project_network_settings(config, index)?;
+            match context
+                .runtime
+                .ensure_pool_network(&candidate, config.isolated)?
+            {
+                EnsureNetworkOutcome::Ready => return Ok(candidate),
+                EnsureNetworkOutcome::SubnetMismatch => {
+                    if fix_network && recreate_pool_network(context, config, &candidate)? {
+                        return Ok(candidate);
+                    }
+                    mismatched += 1;
+                    continue;
+                }
+                EnsureNetworkOutcome::IsolationMismatch => {
+                    isolation_mismatched += 1;
+                    continue;
+                }
+            }
+        }
+    }
+
+    output::error("no free cladding network slots in pool cladding-0..cladding-255");
+    if mismatched > 0 {
+        output::hint(format!(
+            "{mismatched} cladding-N networks exist with unexpected subnets; run 'cladding up --fix-network' to recreate idle ones, or remove them with 'podman network rm cladding-N'"
+        ));
+    } else if isolation_mismatched > 0 {
+        output::hint(format!(
+            "{isolation_mismatched} idle cladding-N networks have the wrong 'internal' setting and are still attached to stopped containers; remove those containers or run 'podman network rm cladding-N'"
+        ));
+    } else if conflicts > 0 {
+        output::hint(format!(
+            "{conflicts} pool subnets are already used by non-cladding networks; free those subnets or remove the conflicting networks"
+        ));
+    } else {
+        output::hint("run 'cladding ps' and stop a running project with 'cladding down'");
+    }
+    Err(Error::message("no free cladding network slots"))
+}
+
+/// `up --fix-network`: replaces `candidate`'s network, which is on another subnet, with one
+/// on the slot's own. Returns false, leaving it alone, when a container still uses it.
+fn recreate_pool_network(
+    context: &Context,
+    config: &Config,
+    candidate: &cladding::network::NetworkSettings,
+) -> Result<bool> {
+    let users = context.runtime.network_containers(&candidate.network)?;
+    if !users.is_empty() {
+        output::warning(format!(
+            "network {} is not on {} but is used by {}; skipping it",
+            candidate.network,
+            candidate.network_subnet,
+            users.join(", ")
+        ));
+        return Ok(false);
+    }
+    context.runtime.remove_network(&candidate.network)?;
+    let outcome = context
+        .runtime
+        .ensure_pool_network(candidate, config.isolated)?;
+    if outcome != EnsureNetworkOutcome::Ready {
+        return Ok(false);
+    }
+    output::note(format!(
+        "recreated network {} on {}",
+        candidate.network, candidate.network_subnet
+    ));
+    Ok(true)
+}
+
+pub(super) fn resolve_active_project_network_settings(
+    context: &Context,
+    config: &Config,
+    command_name: &str,
+) -> Result<cladding::network::NetworkSettings> {
+    let current_project_root = canonicalize_path(&context.project_root)?
+        .display()
+        .to_string();
+
+    let mut matched_network: Option<String> = None;
+    for project in context.runtime.list_running_project_networks()? {
+        if project.name != config.name {
+            continue;
+        }
+
+        let normalized_root = canonicalize_path(Path::new(&project.project_root))
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| project.project_root.clone());
+
+        if normalized_root != current_project_root {
+            continue;
+        }
+
+        if let Some(existing) = &matched_network {
+            if existing != &project.network {
+                output::error(format!(
+                    "active project '{}' has inconsistent cladding network assignment",
+                    config.name
+                ));
+                eprintln!("project_root: {current_project_root}");
+                eprintln!("networks: {existing}, {}", project.network);
+                return Err(Error::message("inconsistent active network"));
+            }
+            continue;
+        }
+
+        matched_network = Some(project.network);
+    }
+
+    let Some(network_name) = matched_network else {
+        output::error(format!(
+            "could not resolve active cladding network for project '{}'",
+            config.name
+        ));
+        output::hint(format!("ensure the project is running, then retry '{command_name}'"));
+        return Err(Error::message("missing active cladding network"));
+    };
+
+    let Some(index) = parse_cladding_pool_index(&network_name) else {
+        output::error(format!(
+            "active project '{}' is attached to unexpected network '{}'",
+            config.name, network_name
+        ));
+        output::hint("restart the project with 'cladding down' then 'cladding up'");
+        return Err(Error::message("unexpected active network"));
+    };
+
+    project_network_settings(config, index)
+}
+
+/// Network settings for `config` on slot `index`, including its agents' cli pods.
+pub(super) fn project_network_settings(
+    config: &Config,
+    index: u8,
+) -> Result<cladding::network::NetworkSettings> {
+    Ok(resolve_network_settings(&config.name, index)?
+        .with_agents(&config.name, config.agents.iter().map(|agent| agent.name.as_str())))
+}
@@ -0,0 +1,74 @@
+//! `cladding policy new`: a Rego module and test file for one sandbox command.
+
+use anyhow::Context as _;
+use cladding::error::{Error, Result};
+use cladding::output;
+use cladding::policy_scaffold::scaffold_command_policy;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{Context, PolicyArgs, PolicySubcommand};
+
+pub(super) fn cmd_policy(context: &Context, args: &PolicyArgs) -> Result<()> {
+    match &args.command {
+        PolicySubcommand::New { command } => cmd_policy_new(context, command),
+    }
+}
+
+fn cmd_policy_new(context: &Context, command: &str) -> Result<()> {
+    let scaffold = scaffold_command_policy(command)?;
+    let commands_dir = context.project_root.join("config").join("sandbox_commands");
+    if !commands_dir.is_dir() {
+        output::missing(format!("{}", commands_dir.display()));
+        output::hint("run 'cladding init' first");
+        return Err(Error::message("missing sandbox_commands directory"));
+    }
+
+    let module_path = commands_dir.join(format!("{command}.rego"));
+    let tests_path = commands_dir.join(format!("{command}_test.json"));
+    let existing: Vec<&PathBuf> = [&module_path, &tests_path]
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect();
+    if !existing.is_empty() {
+        for path in existing {
+            output::file(format!("{}", path.display()));
+        }
+        return Err(Error::message(format!(
+            "a policy for '{command}' already exists; edit it or remove it first"
+        )));
+    }
+
+    for (path, contents) in [(&module_path, &scaffold.module), (&tests_path, &scaffold.tests)] {
+        fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+        println!("wrote {}", path.display());
+    }
+    output::hint(format!(
+        "edit the prefixes and env rules in {command}.rego, then check them with \
+         'cladding exec-sandbox mcp-run policy test'"
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::runtime::FakeRuntime;
+    use crate::cli::test_support::fake_project;
+    use std::rc::Rc;
+
+    #[test]
+    fn policy_new_writes_a_module_and_tests_once() {
+        let context = fake_project("policy-new", &Rc::new(FakeRuntime::default()));
+        let commands_dir = context.project_root.join("config/sandbox_commands");
+
+        cmd_policy_new(&context, "rg").expect("policy new");
+        let module = fs::read_to_string(commands_dir.join("rg.rego")).unwrap();
+        assert!(module.starts_with("package sandbox.rg\n"));
+        assert!(commands_dir.join("rg_test.json").is_file());
+
+        assert!(cmd_policy_new(&context, "rg").is_err());
+        assert!(cmd_policy_new(&context, "../rg").is_err());
+        fs::remove_dir_all(context.project_root.parent().unwrap()).unwrap();
+    }
+}
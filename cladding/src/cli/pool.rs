@@ -0,0 +1,218 @@
+//! `cladding pool`: numbered copies of the project handed out to jobs.
+
+use cladding::config::{Config, load_cladding_config};
+use cladding::error::{Error, Result};
+use cladding::fs_utils::canonicalize_path;
+use cladding::output;
+use cladding::podman::engine_required;
+use cladding::pool::{
+    PoolAssignment, load_pool_state, parse_pool_member_index, pool_member_name, save_pool_state,
+};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{Context, PoolArgs, PoolSubcommand};
+use super::lifecycle::{current_project_root, project_runtime_status, start_project, stop_project};
+
+pub(super) fn cmd_pool(context: &Context, args: &PoolArgs) -> Result<()> {
+    engine_required("cladding pool")?;
+    // Pool commands act on the project itself, never on a `CLADDING_POOL_MEMBER`.
+    let config = load_cladding_config(&context.project_root)?;
+    match &args.command {
+        PoolSubcommand::Up { count } => cmd_pool_up(context, &config, *count),
+        PoolSubcommand::Ls => cmd_pool_ls(context, &config),
+        PoolSubcommand::Assign { job } => cmd_pool_assign(context, &config, job.as_deref()),
+        PoolSubcommand::Release { member } => cmd_pool_release(context, &config, member),
+    }
+}
+
+/// The project's config renamed to pool member `index`.
+fn pool_member_config(config: &Config, index: usize) -> Config {
+    Config {
+        name: pool_member_name(&config.name, index),
+        ..config.clone()
+    }
+}
+
+/// Names of the pool members running from this project, ordered by index.
+fn running_pool_members(context: &Context, config: &Config) -> Result<Vec<(usize, String)>> {
+    let current_project_root = current_project_root(context)?;
+    let mut members = Vec::new();
+    for project in context.runtime.list_running_projects()? {
+        let Some(index) = parse_pool_member_index(&config.name, &project.name) else {
+            continue;
+        };
+        let normalized_root = canonicalize_path(Path::new(&project.project_root))
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| project.project_root.clone());
+        if normalized_root == current_project_root {
+            members.push((index, project.name));
+        }
+    }
+    members.sort();
+    members.dedup();
+    Ok(members)
+}
+
+/// Starts members `<name>-pool-0` up to `<name>-pool-<count - 1>` that are not running, each
+/// on its own network slot like any project, then stops free members beyond `count`.
+fn cmd_pool_up(context: &Context, config: &Config, count: usize) -> Result<()> {
+    for index in 0..count {
+        let member = pool_member_config(config, index);
+        if project_runtime_status(context, &member)?.already_running {
+            println!("already running: {}", member.name);
+            continue;
+        }
+        start_project(context, &member, false)?;
+        println!("started: {}", member.name);
+    }
+
+    let state = load_pool_state(&context.project_root)?;
+    for (index, name) in running_pool_members(context, config)? {
+        if index < count {
+            continue;
+        }
+        if let Some(assignment) = state.assignments.get(&name) {
+            output::warning(format!(
+                "{name} is assigned to job '{}'; leaving it running",
+                assignment.job
+            ));
+            continue;
+        }
+        stop_project(context, &pool_member_config(config, index), "cladding pool up")?;
+        println!("stopped: {name}");
+    }
+    Ok(())
+}
+
+fn cmd_pool_ls(context: &Context, config: &Config) -> Result<()> {
+    let members = running_pool_members(context, config)?;
+    let state = load_pool_state(&context.project_root)?;
+    if members.is_empty() && state.assignments.is_empty() {
+        println!("no running pool members");
+        return Ok(());
+    }
+    for (_, name) in &members {
+        match state.assignments.get(name) {
+            Some(assignment) => println!("{name}  assigned  {}", assignment.job),
+            None => println!("{name}  free"),
+        }
+    }
+    for (name, assignment) in &state.assignments {
+        if !members.iter().any(|(_, member)| member == name) {
+            println!("{name}  assigned  {}  (not running)", assignment.job);
+        }
+    }
+    Ok(())
+}
+
+/// Records the first free member as assigned and prints its name, for
+/// `CLADDING_POOL_MEMBER=$(cladding pool assign)`.
+fn cmd_pool_assign(context: &Context, config: &Config, job: Option<&str>) -> Result<()> {
+    let members: Vec<String> = running_pool_members(context, config)?
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+    let mut state = load_pool_state(&context.project_root)?;
+    let Some(member) = state.first_free(&members).map(str::to_string) else {
+        output::error(format!(
+            "no free pool members for project '{}' ({} running)",
+            config.name,
+            members.len()
+        ));
+        output::hint("release a member with 'cladding pool release', or grow the pool with 'cladding pool up N'");
+        return Err(Error::message("no free pool members"));
+    };
+    let assigned_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    state.assignments.insert(
+        member.clone(),
+        PoolAssignment {
+            job: job.unwrap_or_default().to_string(),
+            assigned_at,
+        },
+    );
+    save_pool_state(&context.project_root, &state)?;
+    println!("{member}");
+    Ok(())
+}
+
+fn cmd_pool_release(context: &Context, config: &Config, member: &str) -> Result<()> {
+    if parse_pool_member_index(&config.name, member).is_none() {
+        output::error(format!(
+            "'{member}' is not a pool member of project '{}'",
+            config.name
+        ));
+        output::hint("list the members with 'cladding pool ls'");
+        return Err(Error::message("invalid pool member"));
+    }
+    let mut state = load_pool_state(&context.project_root)?;
+    if state.assignments.remove(member).is_none() {
+        output::warning(format!("{member} was not assigned"));
+        return Ok(());
+    }
+    save_pool_state(&context.project_root, &state)?;
+    println!("released: {member}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_support::{fake_project, fake_runtime_with_images};
+    use crate::cli::load_config;
+
+    #[test]
+    fn pool_members_start_on_their_own_slots_and_are_checked_out_once() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("pool", &runtime);
+        let config = load_cladding_config(&context.project_root).unwrap();
+
+        cmd_pool_up(&context, &config, 2).expect("pool up");
+        let networks: Vec<(String, String)> = runtime
+            .state()
+            .pods
+            .iter()
+            .map(|pod| (pod.project_name.clone(), pod.network.clone()))
+            .collect();
+        assert_eq!(networks.len(), 6);
+        assert!(networks.contains(&("demo-pool-0".to_string(), "cladding-0".to_string())));
+        assert!(networks.contains(&("demo-pool-1".to_string(), "cladding-1".to_string())));
+        cmd_pool_up(&context, &config, 2).expect("pool up is idempotent");
+        assert_eq!(runtime.state().pods.len(), 6);
+
+        cmd_pool_assign(&context, &config, Some("job-a")).expect("assign");
+        cmd_pool_assign(&context, &config, Some("job-b")).expect("assign");
+        assert!(cmd_pool_assign(&context, &config, Some("job-c")).is_err());
+        let state = load_pool_state(&context.project_root).unwrap();
+        assert_eq!(state.assignments["demo-pool-0"].job, "job-a");
+        assert_eq!(state.assignments["demo-pool-1"].job, "job-b");
+
+        // An assigned member survives shrinking the pool; a free one is stopped.
+        cmd_pool_up(&context, &config, 1).expect("shrink");
+        assert_eq!(runtime.state().pods.len(), 6);
+        cmd_pool_release(&context, &config, "demo-pool-1").expect("release");
+        assert!(cmd_pool_release(&context, &config, "demo-1").is_err());
+        cmd_pool_up(&context, &config, 1).expect("shrink");
+        assert!(
+            runtime
+                .state()
+                .pods
+                .iter()
+                .all(|pod| pod.project_name == "demo-pool-0")
+        );
+
+        let member_context = Context {
+            pool_member: Some("demo-pool-0".to_string()),
+            ..context.clone()
+        };
+        assert_eq!(load_config(&member_context).unwrap().name, "demo-pool-0");
+        let stranger = Context {
+            pool_member: Some("other-pool-0".to_string()),
+            ..context.clone()
+        };
+        assert!(load_config(&stranger).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! `cladding proxy test`, the domain allow lists and proxy reloads.
+
+use anyhow::Context as _;
+use cladding::engine::engine_command;
+use cladding::error::{Error, Result};
+use cladding::output;
+use cladding::squid::{
+    ProxyRequest, SquidAccessConfig, add_domain_entry, covering_domain_entries, parse_domain_entry,
+    parse_list_file, parse_proxy_test_url, remove_domain_entry,
+};
+use std::fs;
+
+use super::{Context, DomainList, ProxyArgs, ProxySubcommand, ProxyTestSource, load_config};
+use super::lifecycle::project_runtime_status;
+use super::network::resolve_active_project_network_settings;
+
+pub(super) fn cmd_proxy(context: &Context, args: &ProxyArgs) -> Result<()> {
+    match &args.command {
+        ProxySubcommand::Test { url, from } => cmd_proxy_test(context, url, *from),
+    }
+}
+
+fn cmd_proxy_test(context: &Context, url: &str, from: ProxyTestSource) -> Result<()> {
+    let target = parse_proxy_test_url(url)?;
+    let config = load_config(context)?;
+    let network_settings =
+        resolve_active_project_network_settings(context, &config, "cladding proxy test")?;
+    let (source_name, source_ip, container_name) = match from {
+        ProxyTestSource::Cli => (
+            "cli",
+            network_settings.cli_ip.clone(),
+            format!("{}-cli-app", network_settings.cli_pod_name),
+        ),
+        ProxyTestSource::Sandbox => (
+            "sandbox",
+            network_settings.sandbox_ip.clone(),
+            format!("{}-sandbox-app", network_settings.sandbox_pod_name),
+        ),
+    };
+
+    let squid_conf_path = context.project_root.join("config/squid.conf");
+    let squid_conf = fs::read_to_string(&squid_conf_path)
+        .with_context(|| format!("failed to read {}", squid_conf_path.display()))?;
+    let access = SquidAccessConfig::parse(&squid_conf, |path| match path {
+        "/tmp/cli_ips.lst" => Some(vec![network_settings.cli_ip.clone()]),
+        "/tmp/sandbox_ips.lst" => Some(vec![network_settings.sandbox_ip.clone()]),
+        // Policy domains, open only while their commands run.
+        "/tmp/sandbox_domains.lst" | "/opt/egress/sandbox_domains.lst" => Some(Vec::new()),
+        _ => {
+            let relative = path.strip_prefix("/opt/config/")?;
+            let contents =
+                fs::read_to_string(context.project_root.join("config").join(relative)).ok()?;
+            Some(parse_list_file(&contents))
+        }
+    });
+    let method = if target.scheme == "https" { "CONNECT" } else { "GET" };
+    let decision = access.evaluate(&ProxyRequest {
+        source_ip: source_ip.clone(),
+        host: target.host.clone(),
+        port: target.port,
+        method: method.to_string(),
+    });
+
+    let output = engine_command()
+        .args([
+            "exec",
+            &container_name,
+            "curl",
+            "-sS",
+            "-o",
+            "/dev/null",
+            "--max-time",
+            "20",
+            "-w",
+            "%{http_connect} %{http_code} %header{x-squid-error}",
+            url,
+        ])
+        .output()
+        .with_context(|| "failed to run podman exec for proxy test")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split_whitespace();
+    let http_connect = fields.next().unwrap_or("000");
+    let http_code = fields.next().unwrap_or("000");
+    let squid_error = fields.next().unwrap_or_default();
+
+    println!(
+        "request: {method} {}:{} from {source_name} ({source_ip})",
+        target.host, target.port
+    );
+
+    let live_allowed = if http_connect == "403" || squid_error.starts_with("ERR_ACCESS_DENIED") {
+        println!("result: denied by proxy");
+        Some(false)
+    } else if http_code != "000" {
+        println!("result: allowed (HTTP {http_code})");
+        Some(true)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        println!("result: request failed ({})", stderr.trim());
+        None
+    };
+
+    match &decision.rule {
+        Some(rule) => println!("rule: config/squid.conf:{}: {}", rule.line, rule.text),
+        None => println!("rule: no http_access line matched (squid default applies)"),
+    }
+
+    if let Some(live_allowed) = live_allowed
+        && live_allowed != decision.allowed
+    {
+        output::warning(format!(
+            "config/squid.conf evaluates to {}, but the running proxy {} the request",
+            if decision.allowed { "allow" } else { "deny" },
+            if live_allowed { "allowed" } else { "denied" }
+        ));
+        output::hint("run 'cladding reload-proxy' if the config changed since startup");
+    }
+
+    match live_allowed {
+        Some(true) => Ok(()),
+        Some(false) => Err(Error::message("request denied by proxy")),
+        None => Err(Error::message("proxy test request failed")),
+    }
+}
+
+pub(super) fn cmd_edit_domain_list(
+    context: &Context,
+    list: DomainList,
+    domain: &str,
+    allow: bool,
+) -> Result<()> {
+    let config = load_config(context)?;
+    let entry = parse_domain_entry(domain)?;
+    let list_path = context.project_root.join("config").join(list.file_name());
+    let contents = fs::read_to_string(&list_path)
+        .with_context(|| format!("failed to read {}", list_path.display()))?;
+
+    for covering in covering_domain_entries(&contents, &entry) {
+        if allow {
+            output::note(format!("'{entry}' is already covered by '{covering}'"));
+        } else {
+            output::warning(format!("'{entry}' is still allowed by '{covering}'"));
+        }
+    }
+    let updated = if allow {
+        add_domain_entry(&contents, &entry)
+    } else {
+        remove_domain_entry(&contents, &entry)
+    };
+    let Some(updated) = updated else {
+        let state = if allow { "already in" } else { "not in" };
+        println!("{entry} is {state} {}; nothing to do", list_path.display());
+        return Ok(());
+    };
+
+    fs::write(&list_path, updated)
+        .with_context(|| format!("failed to write {}", list_path.display()))?;
+    let action = if allow { "added" } else { "removed" };
+    println!("{action} {entry} in {}", list_path.display());
+
+    if !project_runtime_status(context, &config)?.already_running {
+        println!("project is not running; the change applies on the next 'cladding up'");
+        return Ok(());
+    }
+    cmd_reload_proxy(context)?;
+    println!("proxy reloaded");
+    Ok(())
+}
+
+pub(super) fn cmd_reload_proxy(context: &Context) -> Result<()> {
+    let config = load_config(context)?;
+    let network_settings =
+        resolve_active_project_network_settings(context, &config, "cladding reload-proxy")?;
+
+    let status = engine_command()
+        .args([
+            "exec",
+            &format!("{}-proxy", network_settings.proxy_pod_name),
+            "squid",
+            "-k",
+            "reconfigure",
+            "-f",
+            "/tmp/squid_generated.conf",
+        ])
+        .status()
+        .with_context(|| "failed to run podman exec")?;
+
+    cladding::podman::ensure_success(status, "podman exec")
+}
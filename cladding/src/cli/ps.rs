@@ -0,0 +1,147 @@
+//! `cladding ps`: every running project.
+
+use cladding::error::Result;
+use cladding::podman::engine_required;
+
+use super::{Context, PsFormat};
+
+/// One row of `cladding ps`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+struct ProjectSummary {
+    name: String,
+    project_root: String,
+    pods: usize,
+    /// The `cladding-N` network the project's pods are on, if any.
+    network: Option<String>,
+    subnet: Option<String>,
+}
+
+fn running_project_summaries(context: &Context) -> Result<Vec<ProjectSummary>> {
+    let projects = context.runtime.list_running_projects()?;
+    let networks = context.runtime.list_running_project_networks()?;
+    let subnets = context.runtime.list_network_subnets()?;
+    let mut summaries: Vec<ProjectSummary> = projects
+        .into_iter()
+        .map(|project| {
+            let network = networks
+                .iter()
+                .find(|network| {
+                    network.name == project.name && network.project_root == project.project_root
+                })
+                .map(|network| network.network.clone());
+            let subnet = network.as_ref().and_then(|network| {
+                subnets
+                    .iter()
+                    .find(|subnet| &subnet.name == network)
+                    .map(|subnet| subnet.subnet.clone())
+            });
+            ProjectSummary {
+                name: project.name,
+                project_root: project.project_root,
+                pods: project.pod_count,
+                network,
+                subnet,
+            }
+        })
+        .collect();
+    summaries.sort_by(|a, b| (&a.name, &a.project_root).cmp(&(&b.name, &b.project_root)));
+    Ok(summaries)
+}
+
+pub(super) fn cmd_ps(context: &Context, format: PsFormat) -> Result<()> {
+    engine_required("cladding ps")?;
+    let summaries = running_project_summaries(context)?;
+    if format == PsFormat::Json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summaries).expect("project summaries serialize")
+        );
+        return Ok(());
+    }
+    if summaries.is_empty() {
+        println!("no running cladding projects");
+        return Ok(());
+    }
+
+    let width = |column: &str, value: fn(&ProjectSummary) -> usize| {
+        summaries.iter().map(value).max().unwrap_or_default().max(column.len())
+    };
+    let name_width = width("NAME", |summary| summary.name.len());
+    let network_width = width("NETWORK", |summary| {
+        summary.network.as_deref().unwrap_or("-").len()
+    });
+    let subnet_width = width("SUBNET", |summary| {
+        summary.subnet.as_deref().unwrap_or("-").len()
+    });
+    println!(
+        "{:<name_width$}  {:<4}  {:<network_width$}  {:<subnet_width$}  PROJECT ROOT",
+        "NAME", "PODS", "NETWORK", "SUBNET"
+    );
+    for summary in summaries {
+        println!(
+            "{:<name_width$}  {:<4}  {:<network_width$}  {:<subnet_width$}  {}",
+            summary.name,
+            summary.pods,
+            summary.network.as_deref().unwrap_or("-"),
+            summary.subnet.as_deref().unwrap_or("-"),
+            summary.project_root
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::runtime::FakePod;
+    use crate::cli::lifecycle::{cmd_up, current_project_root};
+    use crate::cli::test_support::{fake_project, fake_runtime_with_images};
+
+    #[test]
+    fn ps_lists_every_project_with_its_network() {
+        let runtime = fake_runtime_with_images();
+        runtime.state_mut().pods.push(FakePod {
+            name: "other-proxy-pod".to_string(),
+            project_name: "other".to_string(),
+            project_root: "/elsewhere/.cladding".to_string(),
+            network: "podman".to_string(),
+            extra_networks: Vec::new(),
+            containers: Vec::new(),
+        });
+        let context = fake_project("ps", &runtime);
+        cmd_up(&context, false).expect("up");
+
+        let summaries = running_project_summaries(&context).expect("summaries");
+        let subnet = runtime.state().networks["cladding-0"].subnet.clone();
+        assert_eq!(
+            summaries,
+            vec![
+                ProjectSummary {
+                    name: "demo".to_string(),
+                    project_root: current_project_root(&context).unwrap(),
+                    pods: 3,
+                    network: Some("cladding-0".to_string()),
+                    subnet: Some(subnet),
+                },
+                ProjectSummary {
+                    name: "other".to_string(),
+                    project_root: "/elsewhere/.cladding".to_string(),
+                    pods: 1,
+                    network: Some("podman".to_string()),
+                    subnet: None,
+                },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&summaries[1]).unwrap(),
+            serde_json::json!({
+                "name": "other",
+                "project_root": "/elsewhere/.cladding",
+                "pods": 1,
+                "network": "podman",
+                "subnet": null,
+            })
+        );
+    }
+}
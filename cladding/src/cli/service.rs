@@ -0,0 +1,167 @@
+//! `cladding service`: long-running processes in the cli container.
+
+use anyhow::Context as _;
+use cladding::config::Config;
+use cladding::engine::engine_command;
+use cladding::error::{Error, Result};
+use cladding::output;
+use cladding::podman::engine_required;
+use cladding::services::{
+    SERVICE_CONFLICT_EXIT, SERVICE_DIR, SERVICE_LIST_SCRIPT, SERVICE_LOGS_SCRIPT,
+    SERVICE_PREPARE_SCRIPT, SERVICE_START_SCRIPT, SERVICE_STOP_SCRIPT, parse_service_list,
+};
+
+use super::{Context, ServiceArgs, ServiceSubcommand, load_config};
+use super::exec::container_workdir;
+use super::lifecycle::project_runtime_status;
+use super::network::resolve_active_project_network_settings;
+
+pub(super) fn cmd_service(context: &Context, args: &ServiceArgs) -> Result<()> {
+    engine_required("cladding service")?;
+    let config = load_config(context)?;
+    match &args.command {
+        ServiceSubcommand::Start { name, env, args } => {
+            cmd_service_start(context, &config, name, env, args)
+        }
+        ServiceSubcommand::Stop { name } => cmd_service_stop(context, &config, name),
+        ServiceSubcommand::Ls => cmd_service_ls(context, &config),
+    }
+}
+
+/// Resolves the cli-app container of a running project for the `service` commands.
+fn running_cli_container(context: &Context, config: &Config, command_name: &str) -> Result<String> {
+    let network_settings = resolve_active_project_network_settings(context, config, command_name)?;
+    let status = project_runtime_status(context, config)?;
+    if !status.already_running {
+        output::error(format!("cladding project '{}' is not running", config.name));
+        output::hint("run 'cladding up'");
+        return Err(Error::message("project is not running"));
+    }
+    Ok(format!("{}-cli-app", network_settings.cli_pod_name))
+}
+
+fn service_script_output(
+    container_name: &str,
+    script: &str,
+    args: &[&str],
+) -> Result<std::process::Output> {
+    engine_command()
+        .args(["exec", container_name, "sh", "-c", script, "sh", SERVICE_DIR])
+        .args(args)
+        .output()
+        .with_context(|| "failed to run podman exec")
+        .map_err(Into::into)
+}
+
+fn cmd_service_start(
+    context: &Context,
+    config: &Config,
+    name: &str,
+    env_vars: &[String],
+    args: &[String],
+) -> Result<()> {
+    let container_name = running_cli_container(context, config, "cladding service start")?;
+
+    let output = service_script_output(&container_name, SERVICE_PREPARE_SCRIPT, &[name])?;
+    if output.status.code() == Some(SERVICE_CONFLICT_EXIT) {
+        output::error(format!("service '{name}' is already running"));
+        output::hint(format!("run 'cladding service stop {name}' first"));
+        return Err(Error::message("service already running"));
+    }
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        return Err(Error::message("failed to prepare service directory"));
+    }
+
+    let mut cmd = engine_command();
+    cmd.args(["exec", "-d", "-w"])
+        .arg(container_workdir(context)?)
+        .args(["--env", "LANG=C.UTF-8"]);
+    for env_var in env_vars {
+        cmd.arg("--env").arg(env_var);
+    }
+    let status = cmd
+        .arg(&container_name)
+        .args(["sh", "-c", SERVICE_START_SCRIPT, "sh", SERVICE_DIR, name])
+        .args(args)
+        .status()
+        .with_context(|| "failed to run podman exec")?;
+    cladding::podman::ensure_success(status, "podman exec")?;
+
+    println!("started service '{name}' in {container_name}");
+    println!("logs: cladding logs service/{name} -f");
+    Ok(())
+}
+
+fn cmd_service_stop(context: &Context, config: &Config, name: &str) -> Result<()> {
+    let container_name = running_cli_container(context, config, "cladding service stop")?;
+    let output = service_script_output(&container_name, SERVICE_STOP_SCRIPT, &[name])?;
+    if output.status.code() == Some(SERVICE_CONFLICT_EXIT) {
+        output::error(format!(
+            "no service named '{name}' in project '{}'",
+            config.name
+        ));
+        output::hint("run 'cladding service ls' to see tracked services");
+        return Err(Error::message("service not found"));
+    }
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        return Err(Error::message("failed to stop service"));
+    }
+    println!("stopped service '{name}'");
+    Ok(())
+}
+
+fn cmd_service_ls(context: &Context, config: &Config) -> Result<()> {
+    let container_name = running_cli_container(context, config, "cladding service ls")?;
+    let output = service_script_output(&container_name, SERVICE_LIST_SCRIPT, &[])?;
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        return Err(Error::message("failed to list services"));
+    }
+
+    let services = parse_service_list(&String::from_utf8_lossy(&output.stdout));
+    if services.is_empty() {
+        println!("no services for project '{}'", config.name);
+        return Ok(());
+    }
+
+    let width = services
+        .iter()
+        .map(|service| service.name.len())
+        .max()
+        .unwrap_or_default()
+        .max("NAME".len());
+    println!("{:<width$}  {:<7}  {:<7}  COMMAND", "NAME", "STATUS", "PID");
+    for service in services {
+        let state = if service.running { "running" } else { "exited" };
+        println!(
+            "{:<width$}  {:<7}  {:<7}  {}",
+            service.name, state, service.pid, service.command
+        );
+    }
+    Ok(())
+}
+
+pub(super) fn cmd_service_logs(
+    context: &Context,
+    config: &Config,
+    name: &str,
+    follow: bool,
+) -> Result<()> {
+    let container_name = running_cli_container(context, config, "cladding logs")?;
+    let status = engine_command()
+        .args(["exec", &container_name, "sh", "-c", SERVICE_LOGS_SCRIPT, "sh", SERVICE_DIR, name])
+        .arg(if follow { "follow" } else { "" })
+        .status()
+        .with_context(|| "failed to run podman exec")?;
+    if status.code() == Some(SERVICE_CONFLICT_EXIT) {
+        output::error(format!(
+            "no log for service '{name}' in project '{}'",
+            config.name
+        ));
+        output::hint(format!("start it with 'cladding service start {name} -- <command>'"));
+        return Err(Error::message("service not found"));
+    }
+    cladding::podman::ensure_success(status, "podman exec")
+}
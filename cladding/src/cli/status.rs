@@ -0,0 +1,391 @@
+//! `cladding status`, once or refreshed with `--watch`.
+
+use cladding::config::Config;
+use cladding::error::{Error, Result};
+use cladding::mcp_config::{BUNDLED_MCP_RUN_VERSION, parse_metric_samples};
+use cladding::network::{
+    PROJECT_POD_ROLES, project_container_name, project_pod_name, sidecar_container_name,
+};
+use cladding::output;
+use cladding::podman::engine_required;
+use cladding::runtime::ContainerRuntime;
+use cladding::squid::parse_access_log_denials;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{Context, load_config};
+use super::check::report_asset_versions;
+use super::disk_usage::warn_over_disk_usage_threshold;
+use super::lifecycle::current_project_root;
+
+/// Log lines shown under a container that is crash-looping or unhealthy.
+const STATUS_LOG_TAIL_LINES: usize = 5;
+
+/// Container and pod summary for `cladding status`, rendered before printing so `--watch` can
+/// redraw a whole frame at once.
+pub(super) struct StatusReport {
+    pub(super) text: String,
+    /// Pods that are missing or not running.
+    pub(super) unhealthy: usize,
+    /// Containers that are crash-looping or failing liveness checks.
+    pub(super) degraded: usize,
+}
+
+pub(super) fn cmd_status(context: &Context, watch: bool, interval: u64) -> Result<()> {
+    engine_required("cladding status")?;
+    let config = load_config(context)?;
+    let project_root = current_project_root(context)?;
+
+    if watch {
+        return watch_status(context, &config, &project_root, interval);
+    }
+
+    let report = render_status(context.runtime.as_ref(), &config, &project_root)?;
+    print!("{}", report.text);
+    if report.unhealthy == 0 {
+        report_mcp_run_version(context.runtime.as_ref(), &config);
+    }
+    report_asset_versions(context)?;
+    warn_over_disk_usage_threshold(context, &config, config.disk_usage_threshold)?;
+    if report.unhealthy > 0 {
+        output::error(format!(
+            "{} of {} pods are missing or not running",
+            report.unhealthy,
+            PROJECT_POD_ROLES.len()
+        ));
+        output::hint("run 'cladding up' to start the project");
+        return Err(Error::message("project is not running"));
+    }
+    if report.degraded > 0 {
+        output::error(format!(
+            "{} containers are crash-looping or failing liveness checks",
+            report.degraded
+        ));
+        output::hint("inspect full output with 'cladding logs proxy' or 'cladding logs sandbox'");
+        return Err(Error::message("project is degraded"));
+    }
+
+    Ok(())
+}
+
+/// Prints the running mcp-run's `/version`, warning when it is not the release this cladding
+/// embeds. Returns whether it is.
+pub(super) fn report_mcp_run_version(runtime: &dyn ContainerRuntime, config: &Config) -> bool {
+    let sandbox_container = project_container_name(&config.name, "sandbox");
+    let version = match runtime.sandbox_mcp_version(&sandbox_container) {
+        Ok(version) => version,
+        Err(err) => {
+            output::warning(format!("could not read mcp-run's version: {err}"));
+            return false;
+        }
+    };
+    println!("mcp-run: {}", version.summary());
+    if !version.is_bundled() {
+        output::warning(format!(
+            "mcp-run {} is running, but this cladding embeds {BUNDLED_MCP_RUN_VERSION}",
+            version.version
+        ));
+        output::hint("run 'cladding build', then 'cladding restart sandbox'");
+    }
+    version.is_bundled()
+}
+
+pub(super) fn render_status(
+    runtime: &dyn ContainerRuntime,
+    config: &Config,
+    project_root: &str,
+) -> Result<StatusReport> {
+    let mut report = StatusReport {
+        text: format!("project: {} ({project_root})\n", config.name),
+        unhealthy: 0,
+        degraded: 0,
+    };
+    let out = &mut report.text;
+    for role in PROJECT_POD_ROLES {
+        let pod_name = project_pod_name(&config.name, role);
+        let Some(pod) = runtime.inspect_pod_state(&pod_name)? else {
+            let _ = writeln!(out, "{role}: {pod_name}  missing");
+            report.unhealthy += 1;
+            continue;
+        };
+
+        if !pod.is_running() {
+            report.unhealthy += 1;
+        }
+        let addresses = if pod.addresses.is_empty() {
+            "-".to_string()
+        } else {
+            pod.addresses
+                .iter()
+                .map(|address| format!("{}={}", address.network, address.ip))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let _ = writeln!(out, "{role}: {}  {}  ip: {addresses}", pod.name, pod.status);
+        for container in &pod.containers {
+            let mut line = format!(
+                "  {}  {}  image: {}",
+                container.name, container.state, container.image
+            );
+            if let Some(health) = &container.health {
+                line.push_str(&format!("  health: {health}"));
+            }
+            if container.restarts > 0 {
+                line.push_str(&format!("  restarts: {}", container.restarts));
+            }
+            let _ = writeln!(out, "{line}");
+
+            if container.is_crash_looping() || container.is_unhealthy() {
+                report.degraded += 1;
+                let reason = if container.is_crash_looping() {
+                    format!("crash-looping after {} restarts", container.restarts)
+                } else {
+                    "failing its liveness check".to_string()
+                };
+                let _ = writeln!(out, "    !! {reason}; last log lines:");
+                match runtime.container_log_tail(&container.name, STATUS_LOG_TAIL_LINES) {
+                    Ok(tail) => {
+                        for log_line in tail.lines() {
+                            let _ = writeln!(out, "    | {log_line}");
+                        }
+                    }
+                    Err(_) => {
+                        let _ = writeln!(out, "    | (logs unavailable)");
+                    }
+                }
+            }
+        }
+
+        if role == "cli" {
+            for sidecar in &config.sidecars {
+                let container_name = sidecar_container_name(&config.name, &sidecar.name);
+                if !pod.containers.iter().any(|c| c.name == container_name) {
+                    report.degraded += 1;
+                    let _ = writeln!(out, "  {container_name}  missing  image: {}", sidecar.image);
+                    let _ = writeln!(
+                        out,
+                        "    !! sidecar not in the pod; run 'cladding down' then 'cladding up'"
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+pub(super) const DEFAULT_WATCH_INTERVAL_SECS: u64 = 3;
+/// Proxy log lines scanned for denials on each `status --watch` refresh.
+pub(super) const WATCH_PROXY_LOG_LINES: usize = 500;
+const WATCH_DENIALS_SHOWN: usize = 8;
+/// mcp-run counters shown by `status --watch`, with their labels.
+const WATCH_MCP_METRICS: [(&str, &str); 6] = [
+    ("mcp_run_executions_active", "running"),
+    ("mcp_run_commands_exited_total", "exited"),
+    ("mcp_run_executions_rejected_total", "rejected (limit)"),
+    ("mcp_run_raw_streams_active", "raw streams"),
+    ("mcp_run_raw_streams_queued", "raw queued"),
+    ("mcp_run_command_max_rss_bytes", "max rss bytes"),
+];
+
+/// Redraws the status with proxy denials and mcp-run counters until interrupted.
+fn watch_status(
+    context: &Context,
+    config: &Config,
+    project_root: &str,
+    interval: u64,
+) -> Result<()> {
+    loop {
+        let frame = render_watch_frame(context.runtime.as_ref(), config, project_root);
+
+        // Clear the screen and home the cursor, then draw the frame in one write.
+        print!("\x1b[2J\x1b[H");
+        print!("{frame}");
+        let secs_of_day = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() % 86_400)
+            .unwrap_or_default();
+        println!(
+            "\nupdated {:02}:{:02}:{:02} UTC, every {interval}s; press Ctrl-C to exit",
+            secs_of_day / 3600,
+            secs_of_day % 3600 / 60,
+            secs_of_day % 60
+        );
+        let _ = std::io::stdout().flush();
+        std::thread::sleep(Duration::from_secs(interval.max(1)));
+    }
+}
+
+/// One `status --watch` frame: the status, recent proxy denials and mcp-run counters.
+fn render_watch_frame(
+    runtime: &dyn ContainerRuntime,
+    config: &Config,
+    project_root: &str,
+) -> String {
+    let proxy_container = project_container_name(&config.name, "proxy");
+    let sandbox_container = project_container_name(&config.name, "sandbox");
+    let mut frame = match render_status(runtime, config, project_root) {
+        Ok(report) => {
+            let mut text = report.text;
+            if report.unhealthy > 0 || report.degraded > 0 {
+                let _ = writeln!(
+                    text,
+                    "!! {} pods down, {} containers degraded",
+                    report.unhealthy, report.degraded
+                );
+            }
+            text
+        }
+        Err(error) => format!("status unavailable: {error}\n"),
+    };
+
+    let _ = writeln!(frame, "\nrecent proxy denials:");
+    match runtime.container_log_tail(&proxy_container, WATCH_PROXY_LOG_LINES) {
+        Ok(log) => {
+            let denials = parse_access_log_denials(&log);
+            if denials.is_empty() {
+                let _ = writeln!(frame, "  (none)");
+            }
+            for denial in denials.iter().rev().take(WATCH_DENIALS_SHOWN) {
+                let _ = writeln!(
+                    frame,
+                    "  {}  {}  {} {}",
+                    denial.timestamp, denial.source_ip, denial.method, denial.url
+                );
+            }
+        }
+        Err(_) => {
+            let _ = writeln!(frame, "  (proxy logs unavailable)");
+        }
+    }
+
+    let _ = writeln!(frame, "\nmcp-run activity:");
+    match runtime.sandbox_metrics(&sandbox_container) {
+        Ok(text) => {
+            let samples = parse_metric_samples(&text);
+            for (name, label) in WATCH_MCP_METRICS {
+                if let Some(value) = samples.get(name) {
+                    let _ = writeln!(frame, "  {label}: {value}");
+                }
+            }
+            for (client, value) in client_execution_counts(&samples) {
+                let _ = writeln!(frame, "  started by {client}: {value}");
+            }
+        }
+        Err(_) => {
+            let _ = writeln!(frame, "  (metrics unavailable)");
+        }
+    }
+    frame
+}
+
+/// Commands mcp-run started per `X-Client-Id`, from its metrics.
+pub(super) fn client_execution_counts(samples: &BTreeMap<String, f64>) -> BTreeMap<String, f64> {
+    samples
+        .iter()
+        .filter_map(|(name, value)| {
+            let client = name
+                .strip_prefix("mcp_run_client_executions_total{client_id=\"")?
+                .strip_suffix("\"}")?;
+            Some((client.to_string(), *value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::lifecycle::cmd_up;
+    use crate::cli::test_support::{fake_project, fake_runtime_with_images};
+
+    #[test]
+    fn status_flags_missing_pods_and_tails_crash_looping_containers() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("status", &runtime);
+        cmd_up(&context, false).expect("up");
+        let config = load_config(&context).unwrap();
+        let project_root = current_project_root(&context).unwrap();
+
+        let report = render_status(runtime.as_ref(), &config, &project_root).unwrap();
+        assert_eq!((report.unhealthy, report.degraded), (0, 0), "{}", report.text);
+        assert!(report.text.contains("demo-sandbox-pod-sandbox-app  running"), "{}", report.text);
+
+        let sandbox_container = project_container_name("demo", "sandbox");
+        {
+            let mut state = runtime.state_mut();
+            state.pods.retain(|pod| pod.name != "demo-proxy-pod");
+            let sandbox = state
+                .pods
+                .iter_mut()
+                .flat_map(|pod| &mut pod.containers)
+                .find(|container| container.name == sandbox_container)
+                .expect("sandbox container");
+            sandbox.restarts = 4;
+            state
+                .container_logs
+                .insert(sandbox_container.clone(), "starting\nPOLICY_DIR is empty\n".to_string());
+        }
+
+        let report = render_status(runtime.as_ref(), &config, &project_root).unwrap();
+        assert_eq!((report.unhealthy, report.degraded), (1, 1), "{}", report.text);
+        assert!(report.text.contains("proxy: demo-proxy-pod  missing"), "{}", report.text);
+        assert!(report.text.contains("crash-looping after 4 restarts"), "{}", report.text);
+        assert!(report.text.contains("    | POLICY_DIR is empty\n"), "{}", report.text);
+    }
+
+    #[test]
+    fn watch_frame_shows_proxy_denials_and_mcp_run_metrics() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("status-watch", &runtime);
+        let config = load_config(&context).unwrap();
+        let project_root = current_project_root(&context).unwrap();
+
+        let frame = render_watch_frame(runtime.as_ref(), &config, &project_root);
+        assert!(frame.contains("(proxy logs unavailable)"), "{frame}");
+        assert!(frame.contains("(metrics unavailable)"), "{frame}");
+
+        cmd_up(&context, false).expect("up");
+        {
+            let mut state = runtime.state_mut();
+            state.container_logs.insert(
+                project_container_name("demo", "proxy"),
+                "1767604450.002      0 10.90.0.2 TCP_DENIED/403 3920 CONNECT evil.example:443 - \
+                 HIER_NONE/- text/html\n"
+                    .to_string(),
+            );
+            state.mcp_responses.insert(
+                "GET /metrics".to_string(),
+                "mcp_run_executions_active 2\n\
+                 mcp_run_client_executions_total{client_id=\"builder\"} 7\n"
+                    .to_string(),
+            );
+        }
+
+        let frame = render_watch_frame(runtime.as_ref(), &config, &project_root);
+        assert!(frame.contains("10.90.0.2  CONNECT evil.example:443"), "{frame}");
+        assert!(frame.contains("  running: 2\n"), "{frame}");
+        assert!(frame.contains("  started by builder: 7\n"), "{frame}");
+    }
+
+    #[test]
+    fn mcp_run_version_must_match_the_embedded_release() {
+        let runtime = fake_runtime_with_images();
+        let context = fake_project("status-version", &runtime);
+        let config = load_config(&context).unwrap();
+        cmd_up(&context, false).expect("up");
+        assert!(!report_mcp_run_version(runtime.as_ref(), &config));
+
+        let version = |version: &str| {
+            format!(r#"{{"version":"{version}","gitCommit":"0123456789ab","features":["pty"]}}"#)
+        };
+        let set_version = |body: String| {
+            runtime.state_mut().mcp_responses.insert("GET /version".to_string(), body);
+        };
+        set_version(version(BUNDLED_MCP_RUN_VERSION));
+        assert!(report_mcp_run_version(runtime.as_ref(), &config));
+        set_version(version("0.0.1"));
+        assert!(!report_mcp_run_version(runtime.as_ref(), &config));
+    }
+}
@@ -0,0 +1,139 @@
+//! `cladding support-bundle`: gathers the files and logs for a bug report.
+
+use anyhow::Context as _;
+use cladding::assets::{ASSET_MANIFEST_FILE, embedded_manifest};
+use cladding::engine::{Engine, engine_command, selected_engine};
+use cladding::error::Result;
+use cladding::network::{
+    PROJECT_POD_ROLES, agent_pod_name, project_container_name, project_pod_name,
+    sidecar_container_name,
+};
+use cladding::output;
+use cladding::podman::{
+    PodmanCommand, PodmanTimeout, bounded_output_with_timeout,
+};
+use cladding::support_bundle::SupportBundle;
+use std::env;
+use std::fs;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{Context, load_config};
+
+/// Log lines kept per container by `cladding support-bundle`.
+const SUPPORT_LOG_TAIL_LINES: usize = 200;
+
+/// Gathers what triage usually asks for. Every piece that cannot be collected, such as logs
+/// of a project that is not running, is recorded in the bundle instead of failing it.
+pub(super) fn cmd_support_bundle(context: &Context, output_path: Option<&Path>) -> Result<()> {
+    let project_root = &context.project_root;
+    let config = load_config(context).ok();
+    let name = config.as_ref().map_or("cladding", |config| config.name.as_str());
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let root = format!("cladding-support-{name}-{secs}");
+    let mut bundle = SupportBundle::new();
+
+    let embedded = embedded_manifest();
+    bundle.add(
+        "version.txt",
+        format!(
+            "cladding {} (assets {})\nengine {}\nos {} {}\n",
+            embedded.version,
+            embedded.short_digest(),
+            selected_engine().as_str(),
+            env::consts::OS,
+            env::consts::ARCH
+        ),
+    );
+    match fs::read_to_string(project_root.join("cladding.json")) {
+        Ok(raw) => bundle.add_cladding_json(&raw),
+        Err(err) => bundle.add("cladding.json", format!("not readable: {err}\n")),
+    }
+    match fs::read_to_string(project_root.join(ASSET_MANIFEST_FILE)) {
+        Ok(manifest) => bundle.add(ASSET_MANIFEST_FILE, manifest),
+        Err(err) => bundle.add(ASSET_MANIFEST_FILE, format!("not readable: {err}\n")),
+    }
+
+    // check and doctor print as they go, so they run as child processes to capture it all.
+    let exe = env::current_exe().with_context(|| "failed to locate the cladding binary")?;
+    for command in ["check", "doctor"] {
+        let mut cmd = std::process::Command::new(&exe);
+        cmd.arg("--project-root")
+            .arg(project_root)
+            .args(["--no-color", command]);
+        bundle.add(
+            &format!("{command}.txt"),
+            captured_output(bounded_output_with_timeout(&mut cmd, PodmanTimeout::Long)),
+        );
+    }
+
+    let engine = selected_engine().as_str();
+    for subcommand in ["version", "info"] {
+        bundle.add(
+            &format!("{engine}-{subcommand}.txt"),
+            captured_output(engine_command().arg(subcommand).bounded_output()),
+        );
+    }
+    if let Some(config) = &config {
+        let mut pods: Vec<String> = PROJECT_POD_ROLES
+            .iter()
+            .map(|role| project_pod_name(&config.name, role))
+            .collect();
+        pods.extend(config.agents.iter().map(|agent| agent_pod_name(&config.name, &agent.name)));
+        let mut inspect = engine_command();
+        match selected_engine() {
+            Engine::Podman => inspect.args(["pod", "inspect"]),
+            // Docker has a container standing in for each pod, named after it.
+            Engine::Docker => inspect.arg("inspect"),
+        };
+        bundle.add("pods.json", captured_output(inspect.args(&pods).bounded_output()));
+
+        let mut containers: Vec<String> = PROJECT_POD_ROLES
+            .iter()
+            .map(|role| project_container_name(&config.name, role))
+            .collect();
+        containers.extend(
+            config
+                .sidecars
+                .iter()
+                .map(|sidecar| sidecar_container_name(&config.name, &sidecar.name)),
+        );
+        containers.extend(
+            pods[PROJECT_POD_ROLES.len()..]
+                .iter()
+                .map(|pod| format!("{pod}-cli-app")),
+        );
+        for container in containers {
+            let log = context
+                .runtime
+                .container_log_tail(&container, SUPPORT_LOG_TAIL_LINES)
+                .unwrap_or_else(|err| format!("not available: {err}\n"));
+            bundle.add(&format!("logs/{container}.txt"), log);
+        }
+    }
+
+    let path = output_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("{root}.tar.gz")));
+    bundle.write_tarball(&path, &root)?;
+    println!("wrote {}", path.display());
+    output::note("secrets are redacted, but review the bundle before attaching it to a public issue");
+    Ok(())
+}
+
+/// Stdout and stderr of a finished command followed by its exit status, or why it did not run.
+fn captured_output(output: Result<std::process::Output>) -> String {
+    match output {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            let _ = writeln!(text, "[{}]", output.status);
+            text
+        }
+        Err(err) => format!("failed to run: {err}\n"),
+    }
+}
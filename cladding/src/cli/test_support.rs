@@ -0,0 +1,56 @@
+use cladding::podman::ExposeProxy;
+use cladding::runtime::{FakeExposeProxy, FakeRuntime};
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Context;
+use super::build::DEFAULT_CLADDING_BUILD_IMAGE;
+use super::init::cmd_init;
+use super::lifecycle::current_project_root;
+
+/// An initialized `.cladding` project with stub tools, run against `runtime`.
+pub(super) fn fake_project(label: &str, runtime: &Rc<FakeRuntime>) -> Context {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let workspace = env::temp_dir().join(format!(
+        "cladding-cli-{label}-{}-{nanos}",
+        std::process::id()
+    ));
+    let context = Context {
+        project_root: workspace.join(".cladding"),
+        runtime: runtime.clone(),
+        pool_member: None,
+    };
+    cmd_init(&context, Some("demo"), false, false).expect("init");
+    let bin_dir = context.project_root.join("tools/bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    for name in ["mcp-run", "run-with-network"] {
+        let path = bin_dir.join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    context
+}
+
+pub(super) fn fake_runtime_with_images() -> Rc<FakeRuntime> {
+    Rc::new(FakeRuntime::with_images([DEFAULT_CLADDING_BUILD_IMAGE]))
+}
+
+pub(super) fn expose_proxy(context: &Context, id: &str) -> FakeExposeProxy {
+    FakeExposeProxy {
+        project_name: "demo".to_string(),
+        project_root: current_project_root(context).unwrap(),
+        proxy: ExposeProxy {
+            id: id.to_string(),
+            name: format!("demo-expose-3000-9000-{id}"),
+            host_port: 9000,
+            container_port: 3000,
+            status: "Up 3 seconds".to_string(),
+        },
+    }
+}
@@ -0,0 +1,92 @@
+//! `cladding verify`: checks that the running sandbox is isolated and runs the mcp-run release
+//! this cladding embeds.
+
+use anyhow::Context as _;
+use cladding::engine::engine_command;
+use cladding::error::{Error, Result};
+use cladding::network::{
+    EGRESS_NETWORK, PROJECT_POD_ROLES, project_container_name, project_pod_name,
+};
+use cladding::output;
+use cladding::podman::engine_required;
+
+use super::{Context, load_config};
+use super::network::resolve_active_project_network_settings;
+use super::status::report_mcp_run_version;
+
+/// Address probed to detect direct egress; an IP so the check does not depend on DNS.
+const DIRECT_EGRESS_PROBE_URL: &str = "http://1.1.1.1/";
+
+pub(super) fn cmd_verify(context: &Context) -> Result<()> {
+    engine_required("cladding verify")?;
+    let config = load_config(context)?;
+    let network_settings =
+        resolve_active_project_network_settings(context, &config, "cladding verify")?;
+
+    let mut failures = 0usize;
+    let mut report = |passed: bool, check: String| {
+        println!("{}  {check}", if passed { "ok  " } else { "FAIL" });
+        if !passed {
+            failures += 1;
+        }
+    };
+
+    if config.isolated {
+        report(
+            context.runtime.network_is_internal(&network_settings.network)?,
+            format!("network {} is internal", network_settings.network),
+        );
+        for role in PROJECT_POD_ROLES {
+            let pod_name = project_pod_name(&config.name, role);
+            let on_egress = context.runtime.inspect_pod_state(&pod_name)?.is_some_and(|pod| {
+                pod.addresses
+                    .iter()
+                    .any(|address| address.network == EGRESS_NETWORK)
+            });
+            let expected = role == "proxy";
+            let state = if expected { "is" } else { "is not" };
+            report(
+                on_egress == expected,
+                format!("{pod_name} {state} attached to {EGRESS_NETWORK}"),
+            );
+        }
+    }
+
+    for role in ["cli", "sandbox"] {
+        let container_name = project_container_name(&config.name, role);
+        let output = engine_command()
+            .args([
+                "exec",
+                &container_name,
+                "curl",
+                "--noproxy",
+                "*",
+                "-sS",
+                "-o",
+                "/dev/null",
+                "-m",
+                "5",
+                DIRECT_EGRESS_PROBE_URL,
+            ])
+            .output()
+            .with_context(|| "failed to run podman exec")?;
+        match output.status.code() {
+            Some(127) => println!("skip  {container_name}: curl is not installed"),
+            Some(code) => report(
+                code != 0,
+                format!("{container_name} cannot reach {DIRECT_EGRESS_PROBE_URL} without the proxy"),
+            ),
+            None => return Err(Error::message("podman exec failed")),
+        }
+    }
+
+    if failures > 0 {
+        output::error(format!("{failures} egress checks failed"));
+        output::hint("run 'cladding down' and 'cladding up' to recreate the pods and network");
+        return Err(Error::message("egress verification failed"));
+    }
+    if !report_mcp_run_version(context.runtime.as_ref(), &config) {
+        return Err(Error::message("mcp-run version check failed"));
+    }
+    Ok(())
+}
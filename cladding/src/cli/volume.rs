@@ -0,0 +1,165 @@
+//! `cladding volume`: named volumes owned by the project.
+
+use cladding::config::Config;
+use cladding::error::{Error, Result};
+use cladding::output;
+use cladding::podman::engine_required;
+
+use super::{Context, VolumeArgs, VolumeSubcommand, load_config};
+
+pub(super) fn cmd_volume(context: &Context, args: &VolumeArgs) -> Result<()> {
+    engine_required("cladding volume")?;
+    let config = load_config(context)?;
+    match &args.command {
+        VolumeSubcommand::Ls => cmd_volume_ls(context, &config),
+        VolumeSubcommand::Create { name } => cmd_volume_create(context, &config, name),
+        VolumeSubcommand::Rm { names, force } => cmd_volume_rm(context, &config, names, *force),
+        VolumeSubcommand::Inspect { name } => cmd_volume_inspect(context, &config, name),
+    }
+}
+
+/// Checks `name` is a volume name the engine accepts once prefixed with the project's name.
+fn validate_volume_name(name: &str) -> Result<()> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        output::error(format!(
+            "invalid volume name '{name}': use letters, digits, '_', '.' and '-', starting with \
+             a letter or digit"
+        ));
+        Err(Error::message("invalid volume name"))
+    }
+}
+
+/// The project's volumes, by their cladding.json name. Another project whose name starts with
+/// `<project>-` shares the prefix, so its volumes are listed too.
+pub(super) fn project_volumes(context: &Context, config: &Config) -> Result<Vec<String>> {
+    let prefix = config.volume_name("");
+    Ok(context
+        .runtime
+        .list_volumes()?
+        .into_iter()
+        .filter_map(|name| name.strip_prefix(&prefix).map(str::to_string))
+        .filter(|name| !name.is_empty())
+        .collect())
+}
+
+fn cmd_volume_ls(context: &Context, config: &Config) -> Result<()> {
+    let existing = project_volumes(context, config)?;
+    let referenced = config.referenced_volumes();
+    if existing.is_empty() && referenced.is_empty() {
+        println!("no volumes for project '{}'", config.name);
+        return Ok(());
+    }
+    for name in &existing {
+        let full_name = config.volume_name(name);
+        match referenced.get(name.as_str()) {
+            Some(mounts) => println!("{name}  {full_name}  {}", mounts.join(", ")),
+            None => println!("{name}  {full_name}  (not in cladding.json)"),
+        }
+    }
+    for (name, mounts) in &referenced {
+        if !existing.iter().any(|existing| existing == name) {
+            let full_name = config.volume_name(name);
+            println!("{name}  {full_name}  {}  (not created)", mounts.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn cmd_volume_create(context: &Context, config: &Config, name: &str) -> Result<()> {
+    validate_volume_name(name)?;
+    let full_name = config.volume_name(name);
+    context.runtime.create_volume(&full_name)?;
+    println!("created {full_name}");
+    if !config.referenced_volumes().contains_key(name) {
+        output::hint(format!(
+            "mount it with {{\"mount\": \"/path\", \"volume\": \"{name}\"}} in cladding.json mounts"
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_volume_rm(context: &Context, config: &Config, names: &[String], force: bool) -> Result<()> {
+    for name in names {
+        validate_volume_name(name)?;
+    }
+    let referenced = config.referenced_volumes();
+    let in_use: Vec<&String> = names
+        .iter()
+        .filter(|name| referenced.contains_key(name.as_str()))
+        .collect();
+    for name in &in_use {
+        output::warning(format!(
+            "volume '{name}' is mounted at {} by cladding.json; the next 'cladding up' recreates \
+             it empty",
+            referenced[name.as_str()].join(", ")
+        ));
+    }
+    if !in_use.is_empty() && !force {
+        output::hint("pass --force to remove it anyway, or remove the mount from cladding.json");
+        return Err(Error::message("volume is still in cladding.json"));
+    }
+
+    for name in names {
+        let full_name = config.volume_name(name);
+        context.runtime.remove_volume(&full_name)?;
+        println!("removed {full_name}");
+    }
+    Ok(())
+}
+
+fn cmd_volume_inspect(context: &Context, config: &Config, name: &str) -> Result<()> {
+    validate_volume_name(name)?;
+    print!("{}", context.runtime.inspect_volume(&config.volume_name(name))?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cladding::runtime::FakeRuntime;
+    use crate::cli::test_support::fake_project;
+    use std::fs;
+    use std::rc::Rc;
+
+    #[test]
+    fn volume_rm_refuses_volumes_cladding_json_mounts() {
+        let runtime = Rc::new(FakeRuntime::default());
+        let context = fake_project("volume", &runtime);
+        let config_path = context.project_root.join("cladding.json");
+        let mut raw: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        raw["mounts"] = serde_json::json!([{ "mount": "/data", "volume": "cache" }]);
+        fs::write(&config_path, raw.to_string()).unwrap();
+        let config = load_config(&context).unwrap();
+        runtime
+            .state_mut()
+            .volumes
+            .extend(["other-cache".to_string(), "demo-scratch".to_string()]);
+
+        cmd_volume_create(&context, &config, "cache").expect("create");
+        assert!(cmd_volume_create(&context, &config, "../cache").is_err());
+        assert_eq!(
+            project_volumes(&context, &config).unwrap(),
+            ["cache".to_string(), "scratch".to_string()]
+        );
+
+        cmd_volume_rm(&context, &config, &["scratch".to_string()], false).expect("rm unused");
+        assert!(cmd_volume_rm(&context, &config, &["cache".to_string()], false).is_err());
+        assert!(runtime.state().volumes.contains("demo-cache"));
+        cmd_volume_rm(&context, &config, &["cache".to_string()], true).expect("rm --force");
+        assert_eq!(
+            runtime.state().volumes.iter().collect::<Vec<_>>(),
+            ["other-cache"]
+        );
+        fs::remove_dir_all(context.project_root.parent().unwrap()).unwrap();
+    }
+}
@@ -1,6 +1,9 @@
+use crate::engine::Engine;
 use crate::error::{Error, Result};
+use crate::excludes::compile_exclude_glob;
+use crate::output;
 use anyhow::Context as _;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
 use std::ffi::OsStr;
 use std::fs;
@@ -12,6 +15,58 @@ pub struct Config {
     pub sandbox_image: String,
     pub cli_image: String,
     pub mounts: Vec<MountConfig>,
+    pub home_template: Option<HomeTemplate>,
+    /// Put the project on an internal network whose only way out is the proxy pod.
+    pub isolated: bool,
+    /// Container engine from the `engine` key; `CLADDING_ENGINE` takes precedence.
+    pub engine: Option<Engine>,
+    /// Extra containers run in the cli pod next to `cli-app`, e.g. a database or cache.
+    pub sidecars: Vec<SidecarConfig>,
+    /// Globs over the workspace whose matches are hidden from `cli-app` and `sandbox-app`.
+    pub workspace_excludes: Vec<String>,
+    /// Extra cli pods, one per agent, sharing the project's proxy and sandbox.
+    pub agents: Vec<AgentConfig>,
+    /// Extra env vars for `cli-app`, in the project's cli pod and every agent's.
+    pub env: BTreeMap<String, String>,
+    /// Extra env vars for `sandbox-app`.
+    pub sandbox_env: BTreeMap<String, String>,
+    /// Bytes the project may use on disk before `cladding status` and `check` warn.
+    pub disk_usage_threshold: Option<u64>,
+}
+
+impl Config {
+    /// Engine name of the named volume `volume`: `<name>-<volume>`, so projects and pool
+    /// members never share one.
+    pub fn volume_name(&self, volume: &str) -> String {
+        format!("{}-{volume}", self.name)
+    }
+
+    /// Named volumes from `mounts` and sidecar mounts, each with the mount paths using it.
+    pub fn referenced_volumes(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut volumes: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mounts = self
+            .mounts
+            .iter()
+            .map(|mount| (mount.volume.as_deref(), mount.mount_path.as_str()));
+        let sidecar_mounts = self
+            .sidecars
+            .iter()
+            .flat_map(|sidecar| &sidecar.mounts)
+            .map(|mount| (mount.volume.as_deref(), mount.mount_path.as_str()));
+        for (volume, mount_path) in mounts.chain(sidecar_mounts) {
+            if let Some(volume) = volume {
+                volumes.entry(volume).or_default().push(mount_path);
+            }
+        }
+        volumes
+    }
+}
+
+/// Source of files seeded into `.cladding/home` when they are not already present.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HomeTemplate {
+    Path(PathBuf),
+    Preset(String),
 }
 
 #[derive(Debug, Clone)]
@@ -23,12 +78,59 @@ pub struct MountConfig {
     pub sandbox_only: bool,
 }
 
+/// A container from the `sidecars` list. It shares the cli pod's network namespace, so
+/// `cli-app` reaches its ports on `localhost`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidecarConfig {
+    pub name: String,
+    pub image: String,
+    pub env: BTreeMap<String, String>,
+    pub mounts: Vec<SidecarMount>,
+    pub ports: Vec<u16>,
+}
+
+/// An entry of the `agents` list: a cli pod of its own (`<project>-cli-<name>-pod`) with its
+/// own address and home directory (`.cladding/agents/<name>/home`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentConfig {
+    pub name: String,
+}
+
+/// Agents get the addresses after the cli pod's, so the list is capped to keep the
+/// `cladding-N` subnet's fixed addresses in one small block.
+pub const MAX_AGENTS: usize = 16;
+
+/// Sidecar mounts always need a backing `hostPath` or named `volume`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidecarMount {
+    pub mount_path: String,
+    pub host_path: Option<PathBuf>,
+    pub volume: Option<String>,
+    pub read_only: bool,
+}
+
+/// Container names already used in the cli pod.
+const RESERVED_SIDECAR_NAMES: [&str; 2] = ["cli-app", "cli-node"];
+/// Env vars pods.yaml sets to route traffic through the proxy and reach the sandbox, which
+/// `env` and `sandbox_env` must not override. Compared case-insensitively.
+const RESERVED_ENV_KEYS: [&str; 6] = [
+    "PATH",
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "NO_PROXY",
+    "ALL_PROXY",
+    "RUN_REMOTE_SERVER",
+];
+/// Prefixes of env vars `env` and `sandbox_env` must not set: the dynamic loader's, which
+/// change what code every process runs, and the sandbox server's own configuration.
+const RESERVED_ENV_PREFIXES: [&str; 4] = ["LD_", "MCP_", "POLICY_", "CLADDING_"];
+
 pub fn load_cladding_config(project_root: &Path) -> Result<Config> {
     let config_path = project_root.join("cladding.json");
 
     if !config_path.exists() {
-        eprintln!("missing: cladding.json ({})", config_path.display());
-        eprintln!("hint: run cladding init");
+        output::missing(format!("cladding.json ({})", config_path.display()));
+        output::hint("run cladding init");
         return Err(Error::message("missing cladding.json"));
     }
 
@@ -36,7 +138,7 @@ pub fn load_cladding_config(project_root: &Path) -> Result<Config> {
         .with_context(|| format!("failed to read {}", config_path.display()))?;
 
     let parsed: serde_json::Value = serde_json::from_str(&raw).map_err(|_| {
-        eprintln!("error: cladding.json must include string key: name");
+        output::error("cladding.json must include string key: name");
         Error::message("invalid cladding.json")
     })?;
 
@@ -45,10 +147,26 @@ pub fn load_cladding_config(project_root: &Path) -> Result<Config> {
     let cli_image = get_config_string(&parsed, "cli_image", &config_path)?;
     let mut used_mount_paths = HashSet::new();
     let mounts = parse_mounts(project_root, &parsed, &config_path, &mut used_mount_paths)?;
+    let home_template = parse_home_template(project_root, &parsed, &config_path)?;
+    let isolated = match parsed.get("isolated") {
+        None => false,
+        Some(value) => value.as_bool().ok_or_else(|| {
+            output::error("cladding.json invalid field 'isolated' (expected true or false)");
+            output::file(format!("{}", config_path.display()));
+            Error::message("invalid cladding.json")
+        })?,
+    };
+    let engine = parse_engine(&parsed, &config_path)?;
+    let sidecars = parse_sidecars(project_root, &parsed, &config_path)?;
+    let workspace_excludes = parse_workspace_excludes(&parsed, &config_path)?;
+    let agents = parse_agents(&parsed, &config_path)?;
+    let env = parse_container_env(&parsed, "env", &config_path)?;
+    let sandbox_env = parse_container_env(&parsed, "sandbox_env", &config_path)?;
+    let disk_usage_threshold = parse_disk_usage_threshold(&parsed, &config_path)?;
 
     if !is_lowercase_alnum(&name) {
-        eprintln!("error: config key 'name' must be lowercase alphanumeric ([a-z0-9]+)");
-        eprintln!("file: {}", config_path.display());
+        output::error("config key 'name' must be lowercase alphanumeric ([a-z0-9]+)");
+        output::file(format!("{}", config_path.display()));
         return Err(Error::message("invalid name"));
     }
 
@@ -57,9 +175,87 @@ pub fn load_cladding_config(project_root: &Path) -> Result<Config> {
         sandbox_image,
         cli_image,
         mounts,
+        home_template,
+        isolated,
+        engine,
+        sidecars,
+        workspace_excludes,
+        agents,
+        env,
+        sandbox_env,
+        disk_usage_threshold,
     })
 }
 
+/// Just the `engine` key, read before the command runs. A missing or unparsable
+/// cladding.json yields `None`; the command's own config load reports it.
+pub fn load_configured_engine(project_root: &Path) -> Result<Option<Engine>> {
+    let config_path = project_root.join("cladding.json");
+    let Ok(raw) = fs::read_to_string(&config_path) else {
+        return Ok(None);
+    };
+    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&raw) else {
+        return Ok(None);
+    };
+    parse_engine(&parsed, &config_path)
+}
+
+fn parse_engine(parsed: &serde_json::Value, config_path: &Path) -> Result<Option<Engine>> {
+    let Some(value) = parsed.get("engine") else {
+        return Ok(None);
+    };
+    value.as_str().and_then(Engine::parse).map(Some).ok_or_else(|| {
+        output::error("cladding.json invalid field 'engine' (expected \"podman\" or \"docker\")");
+        output::file(format!("{}", config_path.display()));
+        Error::message("invalid cladding.json")
+    })
+}
+
+/// `disk_usage_threshold`: a byte count, or a size such as `"20GB"` or `"512MiB"`.
+fn parse_disk_usage_threshold(
+    parsed: &serde_json::Value,
+    config_path: &Path,
+) -> Result<Option<u64>> {
+    let Some(value) = parsed.get("disk_usage_threshold") else {
+        return Ok(None);
+    };
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(parse_size))
+        .map(Some)
+        .ok_or_else(|| {
+            output::error(
+                "cladding.json invalid field 'disk_usage_threshold' (expected a size such as \"20GB\")",
+            );
+            output::file(format!("{}", config_path.display()));
+            Error::message("invalid cladding.json")
+        })
+}
+
+/// Bytes in a size such as `512`, `1.5GB` or `20GiB`: decimal units as the engines print
+/// them (`kB`, `MB`, `GB`, `TB`, case-insensitive) or binary ones (`KiB` to `TiB`).
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" | "k" => 1000,
+        "mb" | "m" => 1000_u64.pow(2),
+        "gb" | "g" => 1000_u64.pow(3),
+        "tb" | "t" => 1000_u64.pow(4),
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
+
 pub fn write_default_cladding_config(
     name_override: Option<&str>,
     default_sandbox_image: &str,
@@ -87,8 +283,8 @@ fn get_config_string(
         .and_then(|value| value.as_str())
         .map(|value| value.to_string())
         .ok_or_else(|| {
-            eprintln!("error: cladding.json must include string key: {key}");
-            eprintln!("file: {}", config_path.display());
+            output::error(format!("cladding.json must include string key: {key}"));
+            output::file(format!("{}", config_path.display()));
             Error::message("invalid cladding.json")
         })
 }
@@ -104,16 +300,16 @@ fn parse_mounts(
     };
 
     let array = raw.as_array().ok_or_else(|| {
-        eprintln!("error: cladding.json field 'mounts' must be an array");
-        eprintln!("file: {}", config_path.display());
+        output::error("cladding.json field 'mounts' must be an array");
+        output::file(format!("{}", config_path.display()));
         Error::message("invalid cladding.json")
     })?;
 
     let mut mounts = Vec::with_capacity(array.len());
     for (index, entry) in array.iter().enumerate() {
         let Some(object) = entry.as_object() else {
-            eprintln!("error: cladding.json field 'mounts[{index}]' must be an object");
-            eprintln!("file: {}", config_path.display());
+            output::error(format!("cladding.json field 'mounts[{index}]' must be an object"));
+            output::file(format!("{}", config_path.display()));
             return Err(Error::message("invalid cladding.json"));
         };
 
@@ -121,27 +317,27 @@ fn parse_mounts(
             .get("mount")
             .and_then(|value| value.as_str())
             .ok_or_else(|| {
-                eprintln!(
-                    "error: cladding.json invalid field 'mounts[{index}].mount' (expected string)"
-                );
-                eprintln!("file: {}", config_path.display());
+                output::error(format!(
+                    "cladding.json invalid field 'mounts[{index}].mount' (expected string)"
+                ));
+                output::file(format!("{}", config_path.display()));
                 Error::message("invalid cladding.json")
             })?;
         ensure_absolute_mount_path(config_path, &format!("mounts[{index}].mount"), mount_path)?;
 
         if !used_mount_paths.insert(mount_path.to_string()) {
-            eprintln!(
-                "error: cladding.json duplicate mount path '{mount_path}' in mounts"
-            );
-            eprintln!("file: {}", config_path.display());
+            output::error(format!(
+                "cladding.json duplicate mount path '{mount_path}' in mounts"
+            ));
+            output::file(format!("{}", config_path.display()));
             return Err(Error::message("duplicate mount path"));
         }
 
         let host_path = match object.get("hostPath") {
             Some(value) => {
                 let raw = value.as_str().ok_or_else(|| {
-                    eprintln!("error: cladding.json invalid field 'mounts[{index}].hostPath' (expected string)");
-                    eprintln!("file: {}", config_path.display());
+                    output::error(format!("cladding.json invalid field 'mounts[{index}].hostPath' (expected string)"));
+                    output::file(format!("{}", config_path.display()));
                     Error::message("invalid cladding.json")
                 })?;
                 let candidate = PathBuf::from(raw);
@@ -156,25 +352,25 @@ fn parse_mounts(
 
         let volume = match object.get("volume") {
             Some(value) => Some(value.as_str().ok_or_else(|| {
-                eprintln!("error: cladding.json invalid field 'mounts[{index}].volume' (expected string)");
-                eprintln!("file: {}", config_path.display());
+                output::error(format!("cladding.json invalid field 'mounts[{index}].volume' (expected string)"));
+                output::file(format!("{}", config_path.display()));
                 Error::message("invalid cladding.json")
             })?.to_string()),
             None => None,
         };
 
         if host_path.is_some() && volume.is_some() {
-            eprintln!(
-                "error: cladding.json invalid field 'mounts[{index}]' (hostPath and volume are mutually exclusive)"
-            );
-            eprintln!("file: {}", config_path.display());
+            output::error(format!(
+                "cladding.json invalid field 'mounts[{index}]' (hostPath and volume are mutually exclusive)"
+            ));
+            output::file(format!("{}", config_path.display()));
             return Err(Error::message("invalid cladding.json"));
         }
 
         let read_only = match object.get("readOnly") {
             Some(value) => value.as_bool().ok_or_else(|| {
-                eprintln!("error: cladding.json invalid field 'mounts[{index}].readOnly' (expected boolean)");
-                eprintln!("file: {}", config_path.display());
+                output::error(format!("cladding.json invalid field 'mounts[{index}].readOnly' (expected boolean)"));
+                output::file(format!("{}", config_path.display()));
                 Error::message("invalid cladding.json")
             })?,
             None => false,
@@ -182,18 +378,18 @@ fn parse_mounts(
 
         let sandbox_only = match object.get("sandboxOnly") {
             Some(value) => value.as_bool().ok_or_else(|| {
-                eprintln!("error: cladding.json invalid field 'mounts[{index}].sandboxOnly' (expected boolean)");
-                eprintln!("file: {}", config_path.display());
+                output::error(format!("cladding.json invalid field 'mounts[{index}].sandboxOnly' (expected boolean)"));
+                output::file(format!("{}", config_path.display()));
                 Error::message("invalid cladding.json")
             })?,
             None => false,
         };
 
         if volume.is_some() && read_only {
-            eprintln!(
-                "error: cladding.json invalid field 'mounts[{index}].readOnly' (readOnly not supported for volume mounts)"
-            );
-            eprintln!("file: {}", config_path.display());
+            output::error(format!(
+                "cladding.json invalid field 'mounts[{index}].readOnly' (readOnly not supported for volume mounts)"
+            ));
+            output::file(format!("{}", config_path.display()));
             return Err(Error::message("invalid cladding.json"));
         }
 
@@ -215,6 +411,403 @@ fn parse_mounts(
     Ok(mounts)
 }
 
+fn parse_sidecars(
+    project_root: &Path,
+    parsed: &serde_json::Value,
+    config_path: &Path,
+) -> Result<Vec<SidecarConfig>> {
+    let Some(raw) = parsed.get("sidecars") else {
+        return Ok(Vec::new());
+    };
+
+    let invalid = |field: &str, detail: &str| {
+        output::error(format!("cladding.json invalid field '{field}' ({detail})"));
+        output::file(format!("{}", config_path.display()));
+        Error::message("invalid cladding.json")
+    };
+
+    let array = raw
+        .as_array()
+        .ok_or_else(|| invalid("sidecars", "expected array"))?;
+    let mut names = HashSet::new();
+    let mut ports = HashSet::new();
+    let mut sidecars = Vec::with_capacity(array.len());
+    for (index, entry) in array.iter().enumerate() {
+        let field = format!("sidecars[{index}]");
+        let object = entry
+            .as_object()
+            .ok_or_else(|| invalid(&field, "expected object"))?;
+
+        let name = object
+            .get("name")
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| invalid(&format!("{field}.name"), "expected string"))?;
+        validate_sidecar_name(name).map_err(|detail| invalid(&format!("{field}.name"), &detail))?;
+        if !names.insert(name.to_string()) {
+            return Err(invalid(&format!("{field}.name"), "duplicate sidecar name"));
+        }
+
+        let image = object
+            .get("image")
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| invalid(&format!("{field}.image"), "expected non-empty string"))?;
+
+        let mut env = BTreeMap::new();
+        if let Some(value) = object.get("env") {
+            let vars = value
+                .as_object()
+                .ok_or_else(|| invalid(&format!("{field}.env"), "expected object of strings"))?;
+            for (key, value) in vars {
+                let value = value.as_str().ok_or_else(|| {
+                    invalid(&format!("{field}.env.{key}"), "expected string")
+                })?;
+                env.insert(key.clone(), value.to_string());
+            }
+        }
+
+        let mut sidecar_ports = Vec::new();
+        if let Some(value) = object.get("ports") {
+            let entries = value
+                .as_array()
+                .ok_or_else(|| invalid(&format!("{field}.ports"), "expected array of ports"))?;
+            for (port_index, entry) in entries.iter().enumerate() {
+                let port_field = format!("{field}.ports[{port_index}]");
+                let port = entry
+                    .as_u64()
+                    .and_then(|port| u16::try_from(port).ok())
+                    .filter(|port| *port > 0)
+                    .ok_or_else(|| invalid(&port_field, "expected port 1-65535"))?;
+                // Every container in the pod shares one network namespace.
+                if !ports.insert(port) {
+                    return Err(invalid(&port_field, "port already used by another sidecar"));
+                }
+                sidecar_ports.push(port);
+            }
+        }
+
+        let mut mounts = Vec::new();
+        if let Some(value) = object.get("mounts") {
+            let entries = value
+                .as_array()
+                .ok_or_else(|| invalid(&format!("{field}.mounts"), "expected array"))?;
+            let mut mount_paths = HashSet::new();
+            for (mount_index, entry) in entries.iter().enumerate() {
+                let mount_field = format!("{field}.mounts[{mount_index}]");
+                mounts.push(parse_sidecar_mount(
+                    project_root,
+                    entry,
+                    &mount_field,
+                    &mut mount_paths,
+                    config_path,
+                )?);
+            }
+        }
+
+        sidecars.push(SidecarConfig {
+            name: name.to_string(),
+            image: image.to_string(),
+            env,
+            mounts,
+            ports: sidecar_ports,
+        });
+    }
+
+    Ok(sidecars)
+}
+
+fn parse_workspace_excludes(
+    parsed: &serde_json::Value,
+    config_path: &Path,
+) -> Result<Vec<String>> {
+    let Some(raw) = parsed.get("workspace_excludes") else {
+        return Ok(Vec::new());
+    };
+
+    let invalid = |field: &str, detail: &str| {
+        output::error(format!("cladding.json invalid field '{field}' ({detail})"));
+        output::file(format!("{}", config_path.display()));
+        Error::message("invalid cladding.json")
+    };
+
+    let array = raw
+        .as_array()
+        .ok_or_else(|| invalid("workspace_excludes", "expected array of globs"))?;
+    let mut patterns = Vec::with_capacity(array.len());
+    for (index, entry) in array.iter().enumerate() {
+        let field = format!("workspace_excludes[{index}]");
+        let pattern = entry
+            .as_str()
+            .ok_or_else(|| invalid(&field, "expected string"))?;
+        compile_exclude_glob(pattern).map_err(|detail| invalid(&field, &detail))?;
+        patterns.push(pattern.to_string());
+    }
+    Ok(patterns)
+}
+
+fn parse_agents(parsed: &serde_json::Value, config_path: &Path) -> Result<Vec<AgentConfig>> {
+    let Some(raw) = parsed.get("agents") else {
+        return Ok(Vec::new());
+    };
+
+    let invalid = |field: &str, detail: &str| {
+        output::error(format!("cladding.json invalid field '{field}' ({detail})"));
+        output::file(format!("{}", config_path.display()));
+        Error::message("invalid cladding.json")
+    };
+
+    let array = raw
+        .as_array()
+        .ok_or_else(|| invalid("agents", "expected array"))?;
+    if array.len() > MAX_AGENTS {
+        return Err(invalid("agents", &format!("at most {MAX_AGENTS} agents")));
+    }
+    let mut names = HashSet::new();
+    let mut agents = Vec::with_capacity(array.len());
+    for (index, entry) in array.iter().enumerate() {
+        let field = format!("agents[{index}].name");
+        let name = entry
+            .as_object()
+            .and_then(|object| object.get("name"))
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| invalid(&field, "expected string"))?;
+        validate_agent_name(name).map_err(|detail| invalid(&field, &detail))?;
+        if !names.insert(name.to_string()) {
+            return Err(invalid(&field, "duplicate agent name"));
+        }
+        agents.push(AgentConfig {
+            name: name.to_string(),
+        });
+    }
+    Ok(agents)
+}
+
+/// The `env` or `sandbox_env` object: string values under names a shell could export, none
+/// of them [reserved](validate_container_env_key).
+fn parse_container_env(
+    parsed: &serde_json::Value,
+    key: &str,
+    config_path: &Path,
+) -> Result<BTreeMap<String, String>> {
+    let Some(raw) = parsed.get(key) else {
+        return Ok(BTreeMap::new());
+    };
+
+    let invalid = |field: &str, detail: &str| {
+        output::error(format!("cladding.json invalid field '{field}' ({detail})"));
+        output::file(format!("{}", config_path.display()));
+        Error::message("invalid cladding.json")
+    };
+
+    let vars = raw
+        .as_object()
+        .ok_or_else(|| invalid(key, "expected object of strings"))?;
+    let mut env = BTreeMap::new();
+    for (name, value) in vars {
+        let field = format!("{key}.{name}");
+        validate_container_env_key(name).map_err(|detail| invalid(&field, &detail))?;
+        let value = value
+            .as_str()
+            .ok_or_else(|| invalid(&field, "expected string"))?;
+        env.insert(name.clone(), value.to_string());
+    }
+    Ok(env)
+}
+
+/// Rejects names that are not `[A-Za-z_][A-Za-z0-9_]*`, the proxy and sandbox wiring from
+/// pods.yaml, and loader or server configuration variables.
+pub fn validate_container_env_key(name: &str) -> std::result::Result<(), String> {
+    let mut chars = name.chars();
+    if !chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err("expected letters, digits and '_', not starting with a digit".to_string());
+    }
+    if RESERVED_ENV_KEYS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name))
+    {
+        return Err(format!("{name} is set by cladding and cannot be overridden"));
+    }
+    let upper = name.to_ascii_uppercase();
+    if let Some(prefix) = RESERVED_ENV_PREFIXES
+        .iter()
+        .find(|prefix| upper.starts_with(*prefix))
+    {
+        return Err(format!("{prefix}* variables are not allowed"));
+    }
+    Ok(())
+}
+
+/// Agent names become pod names (`<project>-cli-<name>-pod`) and directory names, so they
+/// follow the same lowercase DNS-label rules as sidecar names.
+pub fn validate_agent_name(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() || name.len() > 32 {
+        return Err("agent name must be 1-32 characters".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        || name.starts_with('-')
+        || name.ends_with('-')
+    {
+        return Err(format!(
+            "invalid agent name '{name}': expected lowercase letters, digits and inner '-'"
+        ));
+    }
+    Ok(())
+}
+
+fn parse_sidecar_mount(
+    project_root: &Path,
+    entry: &serde_json::Value,
+    field: &str,
+    mount_paths: &mut HashSet<String>,
+    config_path: &Path,
+) -> Result<SidecarMount> {
+    let invalid = |field: &str, detail: &str| {
+        output::error(format!("cladding.json invalid field '{field}' ({detail})"));
+        output::file(format!("{}", config_path.display()));
+        Error::message("invalid cladding.json")
+    };
+    let object = entry
+        .as_object()
+        .ok_or_else(|| invalid(field, "expected object"))?;
+
+    let mount_path = object
+        .get("mount")
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| invalid(&format!("{field}.mount"), "expected string"))?;
+    ensure_absolute_mount_path(config_path, &format!("{field}.mount"), mount_path)?;
+    if !mount_paths.insert(mount_path.to_string()) {
+        return Err(invalid(&format!("{field}.mount"), "duplicate mount path"));
+    }
+
+    let host_path = match object.get("hostPath") {
+        Some(value) => {
+            let raw = value
+                .as_str()
+                .ok_or_else(|| invalid(&format!("{field}.hostPath"), "expected string"))?;
+            let candidate = PathBuf::from(raw);
+            Some(if candidate.is_absolute() {
+                candidate
+            } else {
+                project_root.join(candidate)
+            })
+        }
+        None => None,
+    };
+    let volume = match object.get("volume") {
+        Some(value) => Some(
+            value
+                .as_str()
+                .ok_or_else(|| invalid(&format!("{field}.volume"), "expected string"))?
+                .to_string(),
+        ),
+        None => None,
+    };
+    let read_only = match object.get("readOnly") {
+        Some(value) => value
+            .as_bool()
+            .ok_or_else(|| invalid(&format!("{field}.readOnly"), "expected boolean"))?,
+        None => false,
+    };
+
+    match (&host_path, &volume) {
+        (Some(_), Some(_)) => Err(invalid(field, "hostPath and volume are mutually exclusive")),
+        (None, None) => Err(invalid(field, "expected hostPath or volume")),
+        (None, Some(_)) if read_only => Err(invalid(
+            &format!("{field}.readOnly"),
+            "readOnly not supported for volume mounts",
+        )),
+        _ => Ok(SidecarMount {
+            mount_path: mount_path.to_string(),
+            host_path,
+            volume,
+            read_only,
+        }),
+    }
+}
+
+/// Sidecar names become container names (`<project>-cli-pod-<name>`), so they follow
+/// the same lowercase DNS-label rules as the pod containers in pods.yaml.
+pub fn validate_sidecar_name(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() || name.len() > 63 {
+        return Err("sidecar name must be 1-63 characters".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        || name.starts_with('-')
+        || name.ends_with('-')
+    {
+        return Err(format!(
+            "invalid sidecar name '{name}': expected lowercase letters, digits and inner '-'"
+        ));
+    }
+    if RESERVED_SIDECAR_NAMES.contains(&name) {
+        return Err(format!("sidecar name '{name}' is reserved"));
+    }
+    Ok(())
+}
+
+fn parse_home_template(
+    project_root: &Path,
+    parsed: &serde_json::Value,
+    config_path: &Path,
+) -> Result<Option<HomeTemplate>> {
+    let Some(raw) = parsed.get("home_template") else {
+        return Ok(None);
+    };
+
+    let invalid = |detail: &str| {
+        output::error(format!("cladding.json invalid field 'home_template' ({detail})"));
+        output::file(format!("{}", config_path.display()));
+        Error::message("invalid cladding.json")
+    };
+
+    let object = raw
+        .as_object()
+        .ok_or_else(|| invalid("expected object with 'path' or 'preset'"))?;
+    let path = match object.get("path") {
+        Some(value) => Some(value.as_str().ok_or_else(|| invalid("'path' must be a string"))?),
+        None => None,
+    };
+    let preset = match object.get("preset") {
+        Some(value) => Some(
+            value
+                .as_str()
+                .ok_or_else(|| invalid("'preset' must be a string"))?,
+        ),
+        None => None,
+    };
+
+    match (path, preset) {
+        (Some(path), None) => {
+            let candidate = PathBuf::from(path);
+            Ok(Some(HomeTemplate::Path(if candidate.is_absolute() {
+                candidate
+            } else {
+                project_root.join(candidate)
+            })))
+        }
+        (None, Some(preset)) => {
+            let presets = crate::assets::home_template_presets();
+            if !presets.iter().any(|name| name == preset) {
+                output::error(format!("cladding.json unknown home_template preset '{preset}'"));
+                output::hint(format!("available presets: {}", presets.join(", ")));
+                output::file(format!("{}", config_path.display()));
+                return Err(Error::message("invalid cladding.json"));
+            }
+            Ok(Some(HomeTemplate::Preset(preset.to_string())))
+        }
+        (Some(_), Some(_)) => Err(invalid("path and preset are mutually exclusive")),
+        (None, None) => Err(invalid("expected object with 'path' or 'preset'")),
+    }
+}
+
 fn ensure_absolute_mount_path(
     config_path: &Path,
     field: &str,
@@ -223,10 +816,10 @@ fn ensure_absolute_mount_path(
     if Path::new(mount_path).is_absolute() {
         return Ok(());
     }
-    eprintln!(
-        "error: cladding.json invalid field '{field}' (mount path must be absolute)"
-    );
-    eprintln!("file: {}", config_path.display());
+    output::error(format!(
+        "cladding.json invalid field '{field}' (mount path must be absolute)"
+    ));
+    output::file(format!("{}", config_path.display()));
     Err(Error::message("invalid cladding.json"))
 }
 
@@ -247,10 +840,10 @@ fn derive_cladding_name_from_pwd() -> Result<String> {
         .collect::<String>();
 
     if name.is_empty() {
-        eprintln!(
-            "error: could not derive an alphanumeric name from directory: {}",
+        output::error(format!(
+            "could not derive an alphanumeric name from directory: {}",
             cwd.display()
-        );
+        ));
         return Err(Error::message("could not derive name"));
     }
 
@@ -260,7 +853,7 @@ fn derive_cladding_name_from_pwd() -> Result<String> {
 fn normalize_cladding_name_arg(name_arg: &str) -> Result<String> {
     let name = name_arg.to_ascii_lowercase();
     if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
-        eprintln!("error: init name must be alphanumeric ([a-zA-Z0-9]+)");
+        output::error("init name must be alphanumeric ([a-zA-Z0-9]+)");
         return Err(Error::message("invalid init name"));
     }
     Ok(name)
@@ -270,9 +863,149 @@ fn normalize_cladding_name_arg(name_arg: &str) -> Result<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn sizes_parse_with_decimal_and_binary_units() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("0B"), Some(0));
+        assert_eq!(parse_size("1.5GB"), Some(1_500_000_000));
+        assert_eq!(parse_size("12.3kB"), Some(12_300));
+        assert_eq!(parse_size("20 GiB"), Some(20 << 30));
+        assert_eq!(parse_size("GB"), None);
+        assert_eq!(parse_size("3 parsecs"), None);
+    }
+
     #[test]
     fn normalize_init_name() {
         assert_eq!(normalize_cladding_name_arg("MyProject").unwrap(), "myproject");
         assert!(normalize_cladding_name_arg("bad-name").is_err());
     }
+
+    #[test]
+    fn parse_sidecars_reads_and_validates_entries() {
+        let root = Path::new("/tmp/project/.cladding");
+        let config_path = root.join("cladding.json");
+        let parsed = serde_json::json!({
+            "sidecars": [{
+                "name": "postgres",
+                "image": "docker.io/library/postgres:16",
+                "env": { "POSTGRES_PASSWORD": "dev" },
+                "mounts": [{ "mount": "/var/lib/postgresql/data", "volume": "pgdata" }],
+                "ports": [5432]
+            }]
+        });
+        let sidecars = parse_sidecars(root, &parsed, &config_path).expect("valid sidecars");
+        assert_eq!(
+            sidecars,
+            vec![SidecarConfig {
+                name: "postgres".to_string(),
+                image: "docker.io/library/postgres:16".to_string(),
+                env: BTreeMap::from([("POSTGRES_PASSWORD".to_string(), "dev".to_string())]),
+                mounts: vec![SidecarMount {
+                    mount_path: "/var/lib/postgresql/data".to_string(),
+                    host_path: None,
+                    volume: Some("pgdata".to_string()),
+                    read_only: false,
+                }],
+                ports: vec![5432],
+            }]
+        );
+
+        for invalid in [
+            serde_json::json!({ "sidecars": [{ "name": "cli-app", "image": "redis" }] }),
+            serde_json::json!({ "sidecars": [{ "name": "Redis", "image": "redis" }] }),
+            serde_json::json!({ "sidecars": [{ "name": "redis" }] }),
+            serde_json::json!({ "sidecars": [
+                { "name": "a", "image": "redis", "ports": [6379] },
+                { "name": "b", "image": "redis", "ports": [6379] }
+            ] }),
+            serde_json::json!({ "sidecars": [
+                { "name": "a", "image": "redis", "mounts": [{ "mount": "/data" }] }
+            ] }),
+        ] {
+            assert!(parse_sidecars(root, &invalid, &config_path).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_agents_validates_names() {
+        let config_path = Path::new("/tmp/project/.cladding/cladding.json");
+        let parsed = serde_json::json!({ "agents": [{ "name": "codex" }, { "name": "aider-2" }] });
+        assert_eq!(
+            parse_agents(&parsed, config_path).expect("valid agents"),
+            vec![
+                AgentConfig { name: "codex".to_string() },
+                AgentConfig { name: "aider-2".to_string() },
+            ]
+        );
+
+        let too_many: Vec<serde_json::Value> = (0..=MAX_AGENTS)
+            .map(|index| serde_json::json!({ "name": format!("a{index}") }))
+            .collect();
+        for invalid in [
+            serde_json::json!({ "agents": { "name": "codex" } }),
+            serde_json::json!({ "agents": ["codex"] }),
+            serde_json::json!({ "agents": [{ "name": "Codex" }] }),
+            serde_json::json!({ "agents": [{ "name": "codex" }, { "name": "codex" }] }),
+            serde_json::json!({ "agents": too_many }),
+        ] {
+            assert!(parse_agents(&invalid, config_path).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_container_env_rejects_reserved_keys() {
+        let config_path = Path::new("/tmp/project/.cladding/cladding.json");
+        let parsed = serde_json::json!({
+            "env": { "EDITOR": "vim", "OPENAI_BASE_URL": "http://localhost:4000" }
+        });
+        assert_eq!(
+            parse_container_env(&parsed, "env", config_path).expect("valid env"),
+            BTreeMap::from([
+                ("EDITOR".to_string(), "vim".to_string()),
+                ("OPENAI_BASE_URL".to_string(), "http://localhost:4000".to_string()),
+            ])
+        );
+        assert!(
+            parse_container_env(&parsed, "sandbox_env", config_path)
+                .expect("optional")
+                .is_empty()
+        );
+
+        for invalid in [
+            serde_json::json!({ "env": ["EDITOR=vim"] }),
+            serde_json::json!({ "env": { "EDITOR": 1 } }),
+            serde_json::json!({ "env": { "1EDITOR": "vim" } }),
+            serde_json::json!({ "env": { "MY-VAR": "x" } }),
+            serde_json::json!({ "env": { "PATH": "/tmp" } }),
+            serde_json::json!({ "env": { "https_proxy": "http://evil:8080" } }),
+            serde_json::json!({ "env": { "LD_PRELOAD": "/tmp/x.so" } }),
+            serde_json::json!({ "env": { "policy_dir": "/tmp" } }),
+        ] {
+            assert!(parse_container_env(&invalid, "env", config_path).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_workspace_excludes_validates_globs() {
+        let config_path = Path::new("/tmp/project/.cladding/cladding.json");
+        let parsed = serde_json::json!({ "workspace_excludes": [".env", "**/*.pem"] });
+        assert_eq!(
+            parse_workspace_excludes(&parsed, config_path).expect("valid globs"),
+            vec![".env".to_string(), "**/*.pem".to_string()]
+        );
+        assert!(
+            parse_workspace_excludes(&serde_json::json!({}), config_path)
+                .expect("optional")
+                .is_empty()
+        );
+
+        for invalid in [
+            serde_json::json!({ "workspace_excludes": ".env" }),
+            serde_json::json!({ "workspace_excludes": [1] }),
+            serde_json::json!({ "workspace_excludes": ["../.env"] }),
+            serde_json::json!({ "workspace_excludes": ["/etc"] }),
+        ] {
+            assert!(parse_workspace_excludes(&invalid, config_path).is_err(), "{invalid}");
+        }
+    }
 }
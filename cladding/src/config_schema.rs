@@ -0,0 +1,165 @@
+//! Checks cladding.json against `cladding.schema.json` for `cladding config validate`, which
+//! reports every problem at once instead of stopping at the first like
+//! [`load_cladding_config`](crate::config::load_cladding_config). Checks the schema cannot
+//! express, such as duplicate mount paths, are left to the config loader.
+
+use jsonschema::error::{TypeKind, ValidationErrorKind};
+use jsonschema::paths::LocationSegment;
+use serde_json::Value;
+use std::fmt;
+
+pub const CONFIG_SCHEMA: &str = include_str!("../../cladding.schema.json");
+
+/// One schema violation, located by a field path such as `mounts[0].mount`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigProblem {
+    /// Empty for a problem with the document as a whole.
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "invalid field '{}' ({})", self.field, self.message)
+        }
+    }
+}
+
+/// Every schema violation in `config`, ordered by field.
+pub fn schema_problems(config: &Value) -> Vec<ConfigProblem> {
+    let schema: Value = serde_json::from_str(CONFIG_SCHEMA).expect("embedded schema is JSON");
+    let validator = jsonschema::validator_for(&schema).expect("embedded schema is valid");
+
+    let mut problems = Vec::new();
+    for error in validator.iter_errors(config) {
+        let field = field_path(&error.instance_path);
+        match &error.kind {
+            ValidationErrorKind::AdditionalProperties { unexpected } => {
+                problems.extend(unexpected.iter().map(|key| ConfigProblem {
+                    field: join_field(&field, key),
+                    message: "unknown key".to_string(),
+                }));
+            }
+            ValidationErrorKind::Required { property } => problems.push(ConfigProblem {
+                field: join_field(&field, property.as_str().unwrap_or_default()),
+                message: "missing required key".to_string(),
+            }),
+            kind => problems.push(ConfigProblem {
+                field,
+                message: describe(kind).unwrap_or_else(|| error.to_string()),
+            }),
+        }
+    }
+    problems.sort_by(|a, b| a.field.cmp(&b.field));
+    problems
+}
+
+fn field_path(location: &jsonschema::paths::Location) -> String {
+    let mut field = String::new();
+    for segment in location {
+        match segment {
+            LocationSegment::Property(name) => field = join_field(&field, name),
+            LocationSegment::Index(index) => field.push_str(&format!("[{index}]")),
+        }
+    }
+    field
+}
+
+fn join_field(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    }
+}
+
+/// Messages in the style of the config loader; `None` falls back to the validator's own.
+fn describe(kind: &ValidationErrorKind) -> Option<String> {
+    let message = match kind {
+        ValidationErrorKind::Type {
+            kind: TypeKind::Single(expected),
+        } => format!("expected {expected}"),
+        ValidationErrorKind::Pattern { pattern } if pattern == "^/" => {
+            "mount path must be absolute".to_string()
+        }
+        ValidationErrorKind::Pattern { pattern } => format!("expected a value matching {pattern}"),
+        ValidationErrorKind::Enum { options } => {
+            let options: Vec<String> = options
+                .as_array()?
+                .iter()
+                .map(|option| option.to_string())
+                .collect();
+            format!("expected one of {}", options.join(", "))
+        }
+        ValidationErrorKind::Minimum { limit } => format!("must be at least {limit}"),
+        ValidationErrorKind::Maximum { limit } => format!("must be at most {limit}"),
+        ValidationErrorKind::MinLength { .. } => "must not be empty".to_string(),
+        ValidationErrorKind::MaxItems { limit } => format!("at most {limit} entries"),
+        ValidationErrorKind::MinProperties { limit } | ValidationErrorKind::MaxProperties { limit }
+            if *limit == 1 =>
+        {
+            "expected exactly one key".to_string()
+        }
+        _ => return None,
+    };
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::write_default_cladding_config;
+
+    fn problem(field: &str, message: &str) -> ConfigProblem {
+        ConfigProblem {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn default_config_has_no_problems() {
+        let raw = write_default_cladding_config(Some("demo"), "sandbox:latest", "cli:latest")
+            .expect("default config");
+        let parsed: Value = serde_json::from_str(&raw).expect("default config is JSON");
+        assert_eq!(schema_problems(&parsed), Vec::new());
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_field() {
+        let parsed = serde_json::json!({
+            "name": "Demo",
+            "cli_image": 3,
+            "engine": "lxc",
+            "mounts": [
+                { "mount": "relative", "readonly": true },
+                { "hostPath": "../data" }
+            ],
+            "sidecars": [{ "name": "db", "image": "postgres", "ports": [0] }],
+            "home_template": { "path": "a", "preset": "default" },
+            "subnet": "10.0.0.0/24"
+        });
+        assert_eq!(
+            schema_problems(&parsed),
+            vec![
+                problem("cli_image", "expected string"),
+                problem("engine", "expected one of \"podman\", \"docker\""),
+                problem("home_template", "expected exactly one key"),
+                problem("mounts[0].mount", "mount path must be absolute"),
+                problem("mounts[0].readonly", "unknown key"),
+                problem("mounts[1].mount", "missing required key"),
+                problem("name", "expected a value matching ^[a-z0-9]+$"),
+                problem("sandbox_image", "missing required key"),
+                problem("sidecars[0].ports[0]", "must be at least 1"),
+                problem("subnet", "unknown key"),
+            ]
+        );
+        assert_eq!(
+            problem("mounts[0].mount", "mount path must be absolute").to_string(),
+            "invalid field 'mounts[0].mount' (mount path must be absolute)"
+        );
+    }
+}
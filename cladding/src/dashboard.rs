@@ -0,0 +1,340 @@
+//! `cladding dashboard`: a local web page with what `status --watch` shows plus mcp-run's
+//! recent commands, and buttons for the proxy and policy reloads. The page only talks to
+//! 127.0.0.1, and every action needs a header a plain cross-site form cannot send.
+
+use crate::error::Result;
+use crate::squid::ProxyDenial;
+use anyhow::Context as _;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::{Next, from_fn};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub const DEFAULT_DASHBOARD_PORT: u16 = 7390;
+/// Header the page sends with each action. Browsers only send custom headers cross-origin
+/// after a CORS preflight, which the dashboard never grants.
+pub const DASHBOARD_ACTION_HEADER: &str = "x-cladding-dashboard";
+
+/// One refresh of the page.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardSnapshot {
+    pub project: String,
+    /// What `cladding status` prints.
+    pub status: String,
+    /// Pods that are missing or not running.
+    pub unhealthy: usize,
+    /// Containers that are crash-looping or failing liveness checks.
+    pub degraded: usize,
+    /// Most recent first.
+    pub denials: Vec<ProxyDenial>,
+    /// mcp-run's `GET /admin/status`.
+    pub mcp_status: Option<Value>,
+    /// mcp-run's `GET /admin/history`, most recent first.
+    pub mcp_history: Vec<Value>,
+    /// Commands started per `X-Client-Id`, from mcp-run's metrics.
+    pub mcp_clients: BTreeMap<String, f64>,
+    /// Parts of the snapshot that could not be read, e.g. `proxy logs: ...`.
+    pub errors: Vec<String>,
+}
+
+/// What the dashboard reads and does. The CLI backs it with the running project; each call
+/// may block on the engine, so the server runs them off its event loop.
+pub trait DashboardBackend: Send + Sync + 'static {
+    fn snapshot(&self) -> DashboardSnapshot;
+    /// Reconfigures squid, as `cladding reload-proxy` does.
+    fn reload_proxy(&self) -> Result<String>;
+    /// Asks mcp-run to reload its policy directory now.
+    fn reload_policy(&self) -> Result<String>;
+}
+
+/// Serves the dashboard on `listener` until the process is interrupted.
+pub fn serve_dashboard(
+    listener: std::net::TcpListener,
+    backend: Arc<dyn DashboardBackend>,
+    refresh_secs: u64,
+) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .with_context(|| "failed to start the dashboard runtime")?;
+    runtime.block_on(async {
+        listener
+            .set_nonblocking(true)
+            .with_context(|| "failed to configure the dashboard listener")?;
+        let listener = tokio::net::TcpListener::from_std(listener)
+            .with_context(|| "failed to configure the dashboard listener")?;
+        axum::serve(listener, dashboard_router(backend, refresh_secs))
+            .await
+            .with_context(|| "dashboard server failed")?;
+        Ok(())
+    })
+}
+
+pub fn dashboard_router(backend: Arc<dyn DashboardBackend>, refresh_secs: u64) -> Router {
+    let refresh_ms = refresh_secs.max(1) * 1000;
+    let page = DASHBOARD_PAGE.replace("{{REFRESH_MS}}", &refresh_ms.to_string());
+    Router::new()
+        .route("/", get(move || async move { Html(page) }))
+        .route("/api/snapshot", get(snapshot_handler))
+        .route("/api/reload-proxy", post(reload_proxy_handler))
+        .route("/api/reload-policy", post(reload_policy_handler))
+        .with_state(backend)
+        .layer(from_fn(require_local_host))
+}
+
+/// Refuses requests for any other host name, so a DNS rebinding page cannot read the
+/// dashboard through a name that resolves to 127.0.0.1.
+async fn require_local_host(request: Request, next: Next) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if !is_local_host(host) {
+        return (StatusCode::FORBIDDEN, "dashboard is only served to localhost").into_response();
+    }
+    next.run(request).await
+}
+
+fn is_local_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    matches!(name, "localhost" | "127.0.0.1" | "::1")
+}
+
+async fn snapshot_handler(State(backend): State<Arc<dyn DashboardBackend>>) -> Response {
+    match tokio::task::spawn_blocking(move || backend.snapshot()).await {
+        Ok(snapshot) => Json(snapshot).into_response(),
+        Err(error) => action_error(format!("snapshot failed: {error}")),
+    }
+}
+
+async fn reload_proxy_handler(
+    State(backend): State<Arc<dyn DashboardBackend>>,
+    headers: HeaderMap,
+) -> Response {
+    run_action(&headers, move || backend.reload_proxy()).await
+}
+
+async fn reload_policy_handler(
+    State(backend): State<Arc<dyn DashboardBackend>>,
+    headers: HeaderMap,
+) -> Response {
+    run_action(&headers, move || backend.reload_policy()).await
+}
+
+async fn run_action(
+    headers: &HeaderMap,
+    action: impl FnOnce() -> Result<String> + Send + 'static,
+) -> Response {
+    if !headers.contains_key(DASHBOARD_ACTION_HEADER) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": format!("missing {DASHBOARD_ACTION_HEADER} header") })),
+        )
+            .into_response();
+    }
+    match tokio::task::spawn_blocking(action).await {
+        Ok(Ok(message)) => Json(json!({ "message": message })).into_response(),
+        Ok(Err(error)) => action_error(error.to_string()),
+        Err(error) => action_error(format!("action failed: {error}")),
+    }
+}
+
+fn action_error(message: String) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": message })),
+    )
+        .into_response()
+}
+
+const DASHBOARD_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>cladding</title>
+<style>
+  body { font: 14px system-ui, sans-serif; margin: 1.5em; color: #222; }
+  pre { background: #f5f5f5; padding: .75em; overflow-x: auto; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { text-align: left; padding: .2em .6em; border-bottom: 1px solid #ddd; }
+  .bad { color: #b00; }
+  #message { margin-left: 1em; }
+</style>
+</head>
+<body>
+<h1 id="project">cladding</h1>
+<p>
+  <button data-action="reload-proxy">Reload proxy</button>
+  <button data-action="reload-policy">Reload policy</button>
+  <span id="message"></span>
+</p>
+<p id="health"></p>
+<h2>Pods</h2>
+<pre id="status"></pre>
+<h2>Recent proxy denials</h2>
+<table><thead><tr><th>Time</th><th>Source</th><th>Method</th><th>URL</th></tr></thead>
+<tbody id="denials"></tbody></table>
+<h2>mcp-run</h2>
+<pre id="mcp-status"></pre>
+<table><thead><tr><th>Client</th><th>Commands started</th></tr></thead>
+<tbody id="clients"></tbody></table>
+<h3>Recent commands</h3>
+<table><thead><tr><th>Time</th><th>Client</th><th>Command</th><th>Decision</th><th>Exit</th></tr>
+</thead><tbody id="history"></tbody></table>
+<p id="errors" class="bad"></p>
+<script>
+const byId = (id) => document.getElementById(id);
+function rows(id, items, cells) {
+  const body = byId(id);
+  body.replaceChildren(...items.map((item) => {
+    const row = document.createElement("tr");
+    for (const value of cells(item)) {
+      const cell = document.createElement("td");
+      cell.textContent = value ?? "";
+      row.append(cell);
+    }
+    return row;
+  }));
+}
+async function refresh() {
+  try {
+    const response = await fetch("/api/snapshot");
+    const snapshot = await response.json();
+    byId("project").textContent = "cladding: " + snapshot.project;
+    const problems = snapshot.unhealthy + snapshot.degraded;
+    byId("health").textContent = problems === 0 ? "all pods running"
+      : `${snapshot.unhealthy} pods down, ${snapshot.degraded} containers degraded`;
+    byId("health").className = problems === 0 ? "" : "bad";
+    byId("status").textContent = snapshot.status;
+    rows("denials", snapshot.denials, (d) => [
+      new Date(Number(d.timestamp) * 1000).toLocaleTimeString(), d.sourceIp, d.method, d.url]);
+    byId("mcp-status").textContent = snapshot.mcpStatus
+      ? JSON.stringify(snapshot.mcpStatus, null, 2) : "(unavailable)";
+    rows("clients", Object.entries(snapshot.mcpClients), ([client, count]) => [client, count]);
+    rows("history", snapshot.mcpHistory, (r) => [
+      r.timestamp, r.clientId, [r.executable, ...(r.args ?? [])].join(" "), r.decision,
+      r.exitCode]);
+    byId("errors").textContent = snapshot.errors.join("\n");
+  } catch (error) {
+    byId("errors").textContent = "dashboard unreachable: " + error;
+  }
+}
+for (const button of document.querySelectorAll("button[data-action]")) {
+  button.addEventListener("click", async () => {
+    byId("message").textContent = "...";
+    const response = await fetch("/api/" + button.dataset.action, {
+      method: "POST", headers: { "X-Cladding-Dashboard": "1" } });
+    const body = await response.json();
+    byId("message").textContent = body.message ?? body.error;
+    refresh();
+  });
+}
+refresh();
+setInterval(refresh, {{REFRESH_MS}});
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use axum::body::{Body, to_bytes};
+    use std::sync::Mutex;
+    use tower::ServiceExt as _;
+
+    #[derive(Default)]
+    struct FakeBackend {
+        reloads: Mutex<Vec<&'static str>>,
+    }
+
+    impl DashboardBackend for FakeBackend {
+        fn snapshot(&self) -> DashboardSnapshot {
+            DashboardSnapshot {
+                project: "demo".to_string(),
+                ..DashboardSnapshot::default()
+            }
+        }
+
+        fn reload_proxy(&self) -> Result<String> {
+            self.reloads.lock().unwrap().push("proxy");
+            Ok("proxy reloaded".to_string())
+        }
+
+        fn reload_policy(&self) -> Result<String> {
+            Err(Error::message("mcp-run is not running"))
+        }
+    }
+
+    async fn send(
+        router: &Router,
+        method: &str,
+        path: &str,
+        host: &str,
+        action: bool,
+    ) -> (StatusCode, String) {
+        let mut request = axum::http::Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::HOST, host);
+        if action {
+            request = request.header(DASHBOARD_ACTION_HEADER, "1");
+        }
+        let request = request.body(Body::empty()).expect("request");
+        let response = router.clone().oneshot(request).await.expect("response");
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.expect("body");
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[test]
+    fn dashboard_serves_localhost_and_actions_need_the_header() {
+        let backend = Arc::new(FakeBackend::default());
+        let router = dashboard_router(backend.clone(), 3);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime");
+        runtime.block_on(async {
+            let (status, page) = send(&router, "GET", "/", "127.0.0.1:7390", false).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(page.contains("setInterval(refresh, 3000)"));
+
+            let (status, body) = send(&router, "GET", "/api/snapshot", "localhost", false).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(body.contains(r#""project":"demo""#), "{body}");
+
+            // A DNS rebinding page reaches 127.0.0.1 under its own name.
+            let (status, _) =
+                send(&router, "GET", "/api/snapshot", "evil.example:7390", false).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+
+            let (status, _) = send(&router, "POST", "/api/reload-proxy", "[::1]:7390", false).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert!(backend.reloads.lock().unwrap().is_empty());
+
+            let (status, body) =
+                send(&router, "POST", "/api/reload-proxy", "127.0.0.1:7390", true).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body, r#"{"message":"proxy reloaded"}"#);
+            assert_eq!(*backend.reloads.lock().unwrap(), ["proxy"]);
+
+            let (status, body) =
+                send(&router, "POST", "/api/reload-policy", "127.0.0.1:7390", true).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            assert!(body.contains("mcp-run is not running"), "{body}");
+        });
+    }
+}
@@ -0,0 +1,380 @@
+//! `cladding init --from-devcontainer`: maps the parts of a `devcontainer.json` that have a
+//! cladding equivalent into the generated cladding.json and lists the rest as warnings.
+
+use crate::config::validate_container_env_key;
+use crate::error::{Error, Result};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Where `${containerWorkspaceFolder}` is in `cli-app`.
+const CONTAINER_WORKSPACE: &str = "/home/user/workspace";
+/// `${localWorkspaceFolder}` relative to `.cladding`, which relative `hostPath`s start from.
+const LOCAL_WORKSPACE: &str = "..";
+/// Keys with no effect on the container that are dropped without a warning.
+const IGNORED_KEYS: [&str; 3] = ["$schema", "name", "customizations"];
+
+/// What a devcontainer.json contributes to cladding.json.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DevcontainerImport {
+    /// From `image`; becomes `cli_image`.
+    pub image: Option<String>,
+    /// cladding.json `mounts` entries.
+    pub mounts: Vec<Value>,
+    /// `containerEnv` and `remoteEnv`, the latter winning; becomes `env`.
+    pub env: BTreeMap<String, String>,
+    /// `forwardPorts` on the container itself, for `cladding expose`.
+    pub forward_ports: Vec<u16>,
+    /// Everything that could not be mapped.
+    pub warnings: Vec<String>,
+}
+
+/// `.devcontainer/devcontainer.json` or, failing that, `.devcontainer.json` in `workspace`.
+pub fn find_devcontainer(workspace: &Path) -> Option<PathBuf> {
+    [
+        workspace.join(".devcontainer").join("devcontainer.json"),
+        workspace.join(".devcontainer.json"),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+/// Parses devcontainer.json, which allows comments and trailing commas.
+pub fn parse_devcontainer(raw: &str) -> Result<DevcontainerImport> {
+    let parsed: Value = serde_json::from_str(&strip_jsonc(raw))
+        .map_err(|error| Error::message(format!("invalid devcontainer.json: {error}")))?;
+    let object = parsed
+        .as_object()
+        .ok_or_else(|| Error::message("invalid devcontainer.json: expected an object"))?;
+
+    let mut import = DevcontainerImport::default();
+    for (key, value) in object {
+        match key.as_str() {
+            "image" => match value.as_str() {
+                Some(image) => import.image = Some(image.to_string()),
+                None => import.warn("image: expected a string"),
+            },
+            "mounts" => import.add_mounts(value),
+            "containerEnv" | "remoteEnv" => {}
+            "forwardPorts" => import.add_forward_ports(value),
+            "features" => {
+                let ids: Vec<&String> = value
+                    .as_object()
+                    .map(|features| features.keys().collect())
+                    .unwrap_or_default();
+                for id in ids {
+                    import.warn(format!(
+                        "feature '{id}' is not supported; install it in the image instead"
+                    ));
+                }
+            }
+            "build" | "dockerFile" | "dockerfile" => import.warn(format!(
+                "{key}: cladding does not build devcontainer images; set cli_image to a built image"
+            )),
+            "dockerComposeFile" => import.warn(
+                "dockerComposeFile: compose services are not imported; add them as sidecars",
+            ),
+            key if IGNORED_KEYS.contains(&key) => {}
+            key => import.warn(format!("{key}: no cladding equivalent; ignored")),
+        }
+    }
+    // remoteEnv after containerEnv, so its values win as they do in a devcontainer.
+    for key in ["containerEnv", "remoteEnv"] {
+        if let Some(value) = object.get(key) {
+            import.add_env(key, value);
+        }
+    }
+    Ok(import)
+}
+
+impl DevcontainerImport {
+    fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+
+    fn add_mounts(&mut self, value: &Value) {
+        let Some(entries) = value.as_array() else {
+            self.warn("mounts: expected an array");
+            return;
+        };
+        let mut targets = HashSet::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let field = format!("mounts[{index}]");
+            let fields = match entry {
+                Value::String(spec) => parse_mount_string(spec),
+                Value::Object(object) => object
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(text) => text.clone(),
+                            other => other.to_string(),
+                        };
+                        (key.to_ascii_lowercase(), value)
+                    })
+                    .collect(),
+                _ => {
+                    self.warn(format!("{field}: expected a string or object"));
+                    continue;
+                }
+            };
+            match mount_from_fields(&fields) {
+                Ok(mount) => {
+                    let target = mount["mount"].as_str().unwrap_or_default().to_string();
+                    if targets.insert(target.clone()) {
+                        self.mounts.push(mount);
+                    } else {
+                        self.warn(format!("{field}: duplicate target {target}; skipped"));
+                    }
+                }
+                Err(detail) => self.warn(format!("{field}: {detail}; skipped")),
+            }
+        }
+    }
+
+    fn add_env(&mut self, key: &str, value: &Value) {
+        let Some(vars) = value.as_object() else {
+            self.warn(format!("{key}: expected an object"));
+            return;
+        };
+        for (name, value) in vars {
+            let Some(value) = value.as_str() else {
+                self.warn(format!("{key}.{name}: expected a string; skipped"));
+                continue;
+            };
+            if let Err(detail) = validate_container_env_key(name) {
+                self.warn(format!("{key}.{name}: {detail}; skipped"));
+                continue;
+            }
+            if value.contains("${") {
+                self.warn(format!(
+                    "{key}.{name}: variables in values are not supported; skipped"
+                ));
+                continue;
+            }
+            self.env.insert(name.clone(), value.to_string());
+        }
+    }
+
+    fn add_forward_ports(&mut self, value: &Value) {
+        let Some(entries) = value.as_array() else {
+            self.warn("forwardPorts: expected an array");
+            return;
+        };
+        for entry in entries {
+            match entry.as_u64().and_then(|port| u16::try_from(port).ok()) {
+                Some(port) if port > 0 => self.forward_ports.push(port),
+                _ => self.warn(format!(
+                    "forwardPorts: {entry} is not a port of the container; skipped"
+                )),
+            }
+        }
+    }
+
+    /// `cladding_json` with `cli_image`, `mounts` and `env` replaced by the imported values,
+    /// where there are any.
+    pub fn apply(&self, cladding_json: &str) -> Result<String> {
+        let mut config: Map<String, Value> = serde_json::from_str(cladding_json)
+            .map_err(|error| Error::message(format!("invalid cladding.json: {error}")))?;
+        if let Some(image) = &self.image {
+            config.insert("cli_image".to_string(), Value::String(image.clone()));
+        }
+        if !self.mounts.is_empty() {
+            config.insert("mounts".to_string(), Value::Array(self.mounts.clone()));
+        }
+        if !self.env.is_empty() {
+            let env = self
+                .env
+                .iter()
+                .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                .collect();
+            config.insert("env".to_string(), Value::Object(env));
+        }
+        let mut rendered = serde_json::to_string_pretty(&Value::Object(config))
+            .map_err(|error| Error::message(format!("failed to render cladding.json: {error}")))?;
+        rendered.push('\n');
+        Ok(rendered)
+    }
+}
+
+/// `source=...,target=...,type=bind,readonly` as lowercase keys; bare flags map to `true`.
+fn parse_mount_string(spec: &str) -> BTreeMap<String, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.split_once('=') {
+            Some((key, value)) => (key.trim().to_ascii_lowercase(), value.trim().to_string()),
+            None => (part.to_ascii_lowercase(), "true".to_string()),
+        })
+        .collect()
+}
+
+/// A cladding.json mount from the fields of a devcontainer mount.
+fn mount_from_fields(
+    fields: &BTreeMap<String, String>,
+) -> std::result::Result<Value, String> {
+    let field = |names: &[&str]| names.iter().find_map(|name| fields.get(*name).cloned());
+    let target = field(&["target", "destination", "dst"])
+        .map(|target| target.replace("${containerWorkspaceFolder}", CONTAINER_WORKSPACE))
+        .ok_or("no target")?;
+    let source = field(&["source", "src"])
+        .map(|source| source.replace("${localWorkspaceFolder}", LOCAL_WORKSPACE))
+        .unwrap_or_default();
+    if target.contains("${") || source.contains("${") {
+        return Err("unsupported variable".to_string());
+    }
+    if !target.starts_with('/') {
+        return Err(format!("target '{target}' is not absolute"));
+    }
+    let read_only = field(&["readonly", "ro"]).is_some_and(|value| value != "false");
+
+    let mut mount = Map::new();
+    mount.insert("mount".to_string(), Value::String(target));
+    match field(&["type"]).as_deref().unwrap_or("bind") {
+        "bind" if !source.is_empty() => {
+            mount.insert("hostPath".to_string(), Value::String(source));
+            if read_only {
+                mount.insert("readOnly".to_string(), Value::Bool(true));
+            }
+        }
+        "volume" if !source.is_empty() => {
+            if read_only {
+                return Err("read-only volumes are not supported".to_string());
+            }
+            mount.insert("volume".to_string(), Value::String(source));
+        }
+        "bind" | "volume" => return Err("no source".to_string()),
+        other => return Err(format!("mount type '{other}' is not supported")),
+    }
+    Ok(Value::Object(mount))
+}
+
+/// `raw` without `//` and `/* */` comments or commas before a closing bracket, leaving
+/// string contents alone.
+fn strip_jsonc(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            (']' | '}', _) => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEVCONTAINER: &str = r#"{
+  // Comments and trailing commas are allowed.
+  "name": "api",
+  "image": "mcr.microsoft.com/devcontainers/rust:1", /* the base image */
+  "features": { "ghcr.io/devcontainers/features/node:1": {}, },
+  "mounts": [
+    "source=${localWorkspaceFolder}/../data,target=/opt/data,type=bind,readonly",
+    { "source": "cargo-cache", "target": "/usr/local/cargo/registry", "type": "volume" },
+    "source=/tmp,target=/scratch,type=tmpfs",
+    "source=${localEnv:HOME}/.ssh,target=/home/user/.ssh,type=bind"
+  ],
+  "containerEnv": { "EDITOR": "vi", "RUST_LOG": "info", "PATH": "/bin" },
+  "remoteEnv": { "EDITOR": "vim", "HOME_URL": "http://x//y", "SHELL_HOME": "${localEnv:HOME}" },
+  "forwardPorts": [3000, "db:5432"],
+  "postCreateCommand": "cargo fetch",
+}"#;
+
+    #[test]
+    fn maps_image_mounts_env_and_ports() {
+        let import = parse_devcontainer(DEVCONTAINER).expect("devcontainer parses");
+        assert_eq!(
+            import.image.as_deref(),
+            Some("mcr.microsoft.com/devcontainers/rust:1")
+        );
+        assert_eq!(
+            import.mounts,
+            vec![
+                serde_json::json!({ "mount": "/opt/data", "hostPath": "../../data", "readOnly": true }),
+                serde_json::json!({ "mount": "/usr/local/cargo/registry", "volume": "cargo-cache" }),
+            ]
+        );
+        assert_eq!(
+            import.env,
+            BTreeMap::from([
+                ("EDITOR".to_string(), "vim".to_string()),
+                ("HOME_URL".to_string(), "http://x//y".to_string()),
+                ("RUST_LOG".to_string(), "info".to_string()),
+            ])
+        );
+        assert_eq!(import.forward_ports, vec![3000]);
+        assert_eq!(
+            import.warnings,
+            vec![
+                "feature 'ghcr.io/devcontainers/features/node:1' is not supported; install it in the image instead",
+                "forwardPorts: \"db:5432\" is not a port of the container; skipped",
+                "mounts[2]: mount type 'tmpfs' is not supported; skipped",
+                "mounts[3]: unsupported variable; skipped",
+                "postCreateCommand: no cladding equivalent; ignored",
+                "containerEnv.PATH: PATH is set by cladding and cannot be overridden; skipped",
+                "remoteEnv.SHELL_HOME: variables in values are not supported; skipped",
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_overrides_the_generated_config() {
+        let import = parse_devcontainer(DEVCONTAINER).expect("devcontainer parses");
+        let generated = "{\n  \"sandbox_image\": \"sandbox:latest\",\n  \"cli_image\": \"cli:latest\",\n  \"name\": \"api\"\n}\n";
+        let applied: Value =
+            serde_json::from_str(&import.apply(generated).expect("apply")).expect("json");
+        assert_eq!(applied["name"], "api");
+        assert_eq!(applied["sandbox_image"], "sandbox:latest");
+        assert_eq!(applied["cli_image"], "mcr.microsoft.com/devcontainers/rust:1");
+        assert_eq!(applied["mounts"][1]["volume"], "cargo-cache");
+        assert_eq!(applied["env"]["EDITOR"], "vim");
+
+        let empty = parse_devcontainer("{}").expect("empty devcontainer");
+        let unchanged: Value =
+            serde_json::from_str(&empty.apply(generated).expect("apply")).expect("json");
+        assert_eq!(unchanged["cli_image"], "cli:latest");
+        assert!(unchanged.get("mounts").is_none());
+        assert!(parse_devcontainer("[]").is_err());
+    }
+}
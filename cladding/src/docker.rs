@@ -0,0 +1,634 @@
+//! Docker has no pods and no `play kube`, so with `engine: docker` the rendered pods.yaml is
+//! translated into plain containers. Each pod becomes a container named after the pod that
+//! owns its IP, host aliases and network namespace, plus its init and app containers joined
+//! to that namespace. App containers keep podman's `<pod>-<container>` names, so `exec`,
+//! `logs` and the rest of cladding address them the same way on both engines.
+//!
+//! Not translated: the `keep-id` user namespace annotation (cladding's images are built with
+//! the host uid instead) and container ports, which only matter for `hostPort`.
+
+use crate::engine::engine_command;
+use crate::error::{Error, Result};
+use crate::network::NetworkSettings;
+use crate::podman::{
+    ContainerState, PodState, PodmanCommand, PodmanTimeout, RunningPodItem, RunningProject,
+    RunningProjectNetwork, bounded_output_with_timeout, ensure_success, ensure_success_output,
+    get_json_name, get_json_string, inspect_pool_network_for_container, parse_labels,
+    parse_network_addresses, parse_ps_json, podman_remove_containers,
+    running_project_networks_from, running_projects_from,
+};
+use anyhow::Context as _;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Label naming the pod a container belongs to.
+pub const POD_LABEL: &str = "io.cladding.pod";
+/// Label marking the container that stands in for the pod itself.
+pub const POD_INFRA_LABEL: &str = "io.cladding.infra";
+/// Image of the pod containers; pods.yaml already needs it for the init containers.
+const POD_INFRA_IMAGE: &str = "alpine:latest";
+
+/// One `docker run` of the translated pods.yaml.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockerRun {
+    pub args: Vec<String>,
+    /// Init containers run to completion before the pod's app containers start.
+    pub wait: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubeDocument {
+    kind: String,
+    metadata: KubeMetadata,
+    #[serde(default)]
+    spec: Option<PodSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubeMetadata {
+    name: String,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodSpec {
+    #[serde(default)]
+    restart_policy: Option<String>,
+    #[serde(default)]
+    host_aliases: Vec<HostAlias>,
+    #[serde(default)]
+    init_containers: Vec<KubeContainer>,
+    #[serde(default)]
+    containers: Vec<KubeContainer>,
+    #[serde(default)]
+    volumes: Vec<KubeVolume>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HostAlias {
+    ip: String,
+    #[serde(default)]
+    hostnames: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KubeContainer {
+    name: String,
+    image: String,
+    #[serde(default)]
+    image_pull_policy: Option<String>,
+    #[serde(default)]
+    command: Vec<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    working_dir: Option<String>,
+    #[serde(default)]
+    env: Vec<EnvVar>,
+    #[serde(default)]
+    volume_mounts: Vec<VolumeMount>,
+    #[serde(default)]
+    security_context: Option<SecurityContext>,
+    #[serde(default)]
+    liveness_probe: Option<Probe>,
+    #[serde(default)]
+    stdin: bool,
+    #[serde(default)]
+    tty: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnvVar {
+    name: String,
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VolumeMount {
+    name: String,
+    mount_path: String,
+    #[serde(default)]
+    read_only: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SecurityContext {
+    #[serde(default)]
+    run_as_user: Option<u32>,
+    #[serde(default)]
+    run_as_group: Option<u32>,
+    #[serde(default)]
+    capabilities: Option<Capabilities>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Capabilities {
+    #[serde(default)]
+    add: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Probe {
+    exec: Option<ExecAction>,
+    #[serde(default)]
+    initial_delay_seconds: Option<u32>,
+    #[serde(default)]
+    period_seconds: Option<u32>,
+    #[serde(default)]
+    failure_threshold: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecAction {
+    command: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KubeVolume {
+    name: String,
+    #[serde(default)]
+    host_path: Option<HostPathSource>,
+    #[serde(default)]
+    persistent_volume_claim: Option<ClaimSource>,
+    #[serde(default)]
+    empty_dir: Option<Value>,
+    #[serde(default)]
+    config_map: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HostPathSource {
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClaimSource {
+    claim_name: String,
+}
+
+struct KubePod {
+    metadata: KubeMetadata,
+    spec: PodSpec,
+}
+
+fn pods_in_rendered(rendered: &str) -> Result<Vec<KubePod>> {
+    let mut pods = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(rendered) {
+        let document = KubeDocument::deserialize(document)
+            .map_err(|error| Error::message(format!("invalid pods.yaml: {error}")))?;
+        if document.kind != "Pod" {
+            continue;
+        }
+        let spec = document.spec.ok_or_else(|| {
+            Error::message(format!("invalid pods.yaml: pod {} has no spec", document.metadata.name))
+        })?;
+        pods.push(KubePod {
+            metadata: document.metadata,
+            spec,
+        });
+    }
+    Ok(pods)
+}
+
+/// The `docker run` invocations that bring up the pods in `rendered`, in order.
+pub fn docker_run_plan(rendered: &str, network: &NetworkSettings) -> Result<Vec<DockerRun>> {
+    let mut plan = Vec::new();
+    for pod in pods_in_rendered(rendered)? {
+        let pod_name = &pod.metadata.name;
+        let restart = match pod.spec.restart_policy.as_deref() {
+            Some("Never") => "no",
+            Some("OnFailure") => "on-failure",
+            _ => "unless-stopped",
+        };
+
+        let mut args = strings(["run", "-d", "--name", pod_name]);
+        args.extend(strings(["--network", &network.network]));
+        if let Some(ip) = network.pod_ip(pod_name) {
+            args.extend(strings(["--ip", ip]));
+        }
+        args.extend(strings(["--restart", restart]));
+        push_labels(&mut args, &pod.metadata, pod_name);
+        args.extend(strings(["--label", &format!("{POD_INFRA_LABEL}=true")]));
+        for alias in &pod.spec.host_aliases {
+            for hostname in &alias.hostnames {
+                args.extend(strings(["--add-host", &format!("{hostname}:{}", alias.ip)]));
+            }
+        }
+        args.extend(strings([POD_INFRA_IMAGE, "sleep", "infinity"]));
+        plan.push(DockerRun { args, wait: false });
+
+        for container in &pod.spec.init_containers {
+            let mut args = strings(["run", "--rm"]);
+            push_container_args(&mut args, &pod, container)?;
+            plan.push(DockerRun { args, wait: true });
+        }
+        for container in &pod.spec.containers {
+            let mut args = strings(["run", "-d", "--restart", restart]);
+            push_labels(&mut args, &pod.metadata, pod_name);
+            if container.stdin {
+                args.push("-i".to_string());
+            }
+            if container.tty {
+                args.push("-t".to_string());
+            }
+            if container.image_pull_policy.as_deref() == Some("Never") {
+                args.extend(strings(["--pull", "never"]));
+            }
+            if let Some(probe) = &container.liveness_probe
+                && let Some(exec) = &probe.exec
+            {
+                args.extend(strings(["--health-cmd", &shell_join(&exec.command)]));
+                if let Some(seconds) = probe.period_seconds {
+                    args.extend(strings(["--health-interval", &format!("{seconds}s")]));
+                }
+                if let Some(seconds) = probe.initial_delay_seconds {
+                    args.extend(strings(["--health-start-period", &format!("{seconds}s")]));
+                }
+                if let Some(retries) = probe.failure_threshold {
+                    args.extend(strings(["--health-retries", &retries.to_string()]));
+                }
+            }
+            push_container_args(&mut args, &pod, container)?;
+            plan.push(DockerRun { args, wait: false });
+        }
+    }
+    Ok(plan)
+}
+
+fn push_labels(args: &mut Vec<String>, metadata: &KubeMetadata, pod_name: &str) {
+    for (key, value) in &metadata.labels {
+        args.extend(strings(["--label", &format!("{key}={value}")]));
+    }
+    args.extend(strings(["--label", &format!("{POD_LABEL}={pod_name}")]));
+}
+
+/// Name, namespace, user, mounts, env and command: what init and app containers share.
+fn push_container_args(
+    args: &mut Vec<String>,
+    pod: &KubePod,
+    container: &KubeContainer,
+) -> Result<()> {
+    let pod_name = &pod.metadata.name;
+    args.extend(strings(["--name", &format!("{pod_name}-{}", container.name)]));
+    args.extend(strings(["--network", &format!("container:{pod_name}")]));
+    if let Some(security) = &container.security_context {
+        let user = match (security.run_as_user, security.run_as_group) {
+            (Some(user), Some(group)) => Some(format!("{user}:{group}")),
+            (Some(user), None) => Some(user.to_string()),
+            _ => None,
+        };
+        if let Some(user) = user {
+            args.extend(strings(["--user", &user]));
+        }
+        for capability in security.capabilities.iter().flat_map(|caps| &caps.add) {
+            args.extend(strings(["--cap-add", capability]));
+        }
+    }
+    for mount in &container.volume_mounts {
+        let volume = pod
+            .spec
+            .volumes
+            .iter()
+            .find(|volume| volume.name == mount.name)
+            .ok_or_else(|| {
+                Error::message(format!(
+                    "invalid pods.yaml: {pod_name}/{} mounts undefined volume {}",
+                    container.name, mount.name
+                ))
+            })?;
+        args.extend(volume_args(volume, mount)?);
+    }
+    for var in &container.env {
+        args.extend(strings(["-e", &format!("{}={}", var.name, var.value)]));
+    }
+    if let Some(working_dir) = &container.working_dir {
+        args.extend(strings(["-w", working_dir]));
+    }
+    // A pod's `command` replaces the image entrypoint, `args` its default arguments.
+    let mut command = container.command.iter();
+    if let Some(entrypoint) = command.next() {
+        args.extend(strings(["--entrypoint", entrypoint]));
+    }
+    args.push(container.image.clone());
+    args.extend(command.cloned());
+    args.extend(container.args.iter().cloned());
+    Ok(())
+}
+
+fn volume_args(volume: &KubeVolume, mount: &VolumeMount) -> Result<Vec<String>> {
+    let read_only = if mount.read_only { ":ro" } else { "" };
+    let target = &mount.mount_path;
+    if let Some(host_path) = &volume.host_path {
+        return Ok(strings(["-v", &format!("{}:{target}{read_only}", host_path.path)]));
+    }
+    if let Some(claim) = &volume.persistent_volume_claim {
+        return Ok(strings(["-v", &format!("{}:{target}{read_only}", claim.claim_name)]));
+    }
+    if volume.empty_dir.is_some() {
+        let readonly = if mount.read_only { ",readonly" } else { "" };
+        return Ok(strings([
+            "--mount",
+            &format!("type=volume,destination={target}{readonly}"),
+        ]));
+    }
+    // The only config map in pods.yaml is the empty one masking `.cladding`.
+    if volume.config_map.is_some() {
+        return Ok(strings(["--tmpfs", &format!("{target}{read_only}")]));
+    }
+    Err(Error::message(format!(
+        "invalid pods.yaml: volume {} has no source docker supports",
+        volume.name
+    )))
+}
+
+fn strings<const N: usize>(values: [&str; N]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+/// Joins `words` into a `sh -c` string, single-quoting each.
+fn shell_join(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|word| format!("'{}'", word.replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `podman play kube` for docker: starts the pods in `rendered`, or with `down` removes them.
+/// A failed start removes whatever it had already created.
+pub fn docker_play_kube(rendered: &str, network: &NetworkSettings, down: bool) -> Result<()> {
+    let pod_names: Vec<String> = pods_in_rendered(rendered)?
+        .into_iter()
+        .map(|pod| pod.metadata.name)
+        .collect();
+    let pod_names: Vec<&str> = pod_names.iter().map(String::as_str).collect();
+    if down {
+        return docker_remove_pods(&pod_names);
+    }
+
+    for step in docker_run_plan(rendered, network)? {
+        let mut cmd = engine_command();
+        cmd.args(&step.args);
+        let result = bounded_output_with_timeout(&mut cmd, PodmanTimeout::Long)
+            .and_then(|output| ensure_success_output(&output, "docker run"));
+        if let Err(error) = result {
+            let _ = docker_remove_pods(&pod_names);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Force-removes every container of the named pods.
+pub fn docker_remove_pods(pod_names: &[&str]) -> Result<()> {
+    for pod_name in pod_names {
+        let ids = list_pod_container_ids(pod_name)?;
+        podman_remove_containers(&ids, true, true)?;
+    }
+    Ok(())
+}
+
+/// Ids of the pod's containers, newest first, so app containers go before the pod container.
+fn list_pod_container_ids(pod_name: &str) -> Result<Vec<String>> {
+    let output = engine_command()
+        .args(["ps", "-aq", "--filter", &format!("label={POD_LABEL}={pod_name}")])
+        .bounded_output()?;
+    ensure_success_output(&output, "docker ps")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Attaches a running pod to an additional network through the container owning its namespace.
+pub fn docker_pod_network_connect(pod_name: &str, network: &str) -> Result<()> {
+    let status = engine_command()
+        .args(["network", "connect", network, pod_name])
+        .bounded_status()?;
+    ensure_success(status, "docker network connect")
+}
+
+fn list_running_pod_items() -> Result<Vec<RunningPodItem>> {
+    let output = engine_command()
+        .args([
+            "ps",
+            "--filter",
+            "label=cladding",
+            "--filter",
+            &format!("label={POD_INFRA_LABEL}=true"),
+            "--format",
+            "{{json .}}",
+        ])
+        .bounded_output()?;
+    ensure_success_output(&output, "docker ps")?;
+    let parsed = parse_ps_json(&String::from_utf8_lossy(&output.stdout))
+        .with_context(|| "failed to parse docker ps json output")?;
+    Ok(parse_running_pod_items(&parsed))
+}
+
+fn parse_running_pod_items(value: &Value) -> Vec<RunningPodItem> {
+    let Some(items) = value.as_array() else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let labels = parse_labels(item.get("Labels")?);
+            Some(RunningPodItem {
+                pod_id: get_json_string(item, &["ID", "Id"])?,
+                name: labels.get("cladding")?.to_string(),
+                project_root: labels.get("project_root")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn docker_list_running_projects() -> Result<Vec<RunningProject>> {
+    Ok(running_projects_from(list_running_pod_items()?))
+}
+
+pub fn docker_list_running_project_networks() -> Result<Vec<RunningProjectNetwork>> {
+    running_project_networks_from(list_running_pod_items()?, inspect_pool_network_for_container)
+}
+
+/// The docker daemon's version; fails when the daemon is not reachable.
+pub fn docker_server_version() -> Result<String> {
+    let output = engine_command()
+        .args(["version", "--format", "{{.Server.Version}}"])
+        .bounded_output()?;
+    ensure_success_output(&output, "docker version")?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// [`crate::podman::podman_inspect_pod_state`] for a translated pod.
+pub fn docker_inspect_pod_state(pod_name: &str) -> Result<Option<PodState>> {
+    let ids = list_pod_container_ids(pod_name)?;
+    if ids.is_empty() {
+        return Ok(None);
+    }
+    let output = engine_command()
+        .args(["container", "inspect"])
+        .args(&ids)
+        .bounded_output()?;
+    ensure_success_output(&output, "docker container inspect")?;
+    let parsed: Value = serde_json::from_slice(&output.stdout)
+        .with_context(|| "failed to parse docker container inspect json output")?;
+    Ok(parse_pod_state(pod_name, &parsed))
+}
+
+fn parse_pod_state(pod_name: &str, value: &Value) -> Option<PodState> {
+    let items = value.as_array()?;
+    let is_pod_container = |item: &&Value| {
+        item.get("Config")
+            .and_then(|config| config.get("Labels"))
+            .map(parse_labels)
+            .is_some_and(|labels| labels.get(POD_INFRA_LABEL).map(String::as_str) == Some("true"))
+    };
+    let pod = items.iter().find(is_pod_container)?;
+    let mut containers: Vec<ContainerState> = items
+        .iter()
+        .filter(|item| !is_pod_container(item))
+        .filter_map(|item| {
+            let state = item.get("State");
+            Some(ContainerState {
+                name: get_json_name(item)?.trim_start_matches('/').to_string(),
+                state: state
+                    .and_then(|state| get_json_string(state, &["Status"]))
+                    .unwrap_or_else(|| "unknown".to_string()),
+                image: item
+                    .get("Config")
+                    .and_then(|config| get_json_string(config, &["Image"]))
+                    .unwrap_or_default(),
+                restarts: item
+                    .get("RestartCount")
+                    .and_then(Value::as_u64)
+                    .and_then(|restarts| u32::try_from(restarts).ok())
+                    .unwrap_or(0),
+                health: state
+                    .and_then(|state| state.get("Health"))
+                    .and_then(|health| get_json_string(health, &["Status"])),
+            })
+        })
+        .collect();
+    containers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Some(PodState {
+        name: pod_name.to_string(),
+        status: pod
+            .get("State")
+            .and_then(|state| get_json_string(state, &["Status"]))
+            .unwrap_or_else(|| "unknown".to_string()),
+        addresses: parse_network_addresses(pod),
+        containers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::network::resolve_network_settings;
+    use crate::pods::render_pods_yaml;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn rendered_plan() -> (NetworkSettings, Vec<DockerRun>) {
+        let config = Config {
+            name: "demo".to_string(),
+            sandbox_image: "localhost/sandbox:latest".to_string(),
+            cli_image: "localhost/cli:latest".to_string(),
+            mounts: Vec::new(),
+            home_template: None,
+            isolated: false,
+            engine: None,
+            sidecars: Vec::new(),
+            workspace_excludes: Vec::new(),
+            agents: Vec::new(),
+            env: BTreeMap::new(),
+            sandbox_env: BTreeMap::new(),
+            disk_usage_threshold: None,
+        };
+        let network = resolve_network_settings("demo", 3).expect("network settings");
+        let rendered =
+            render_pods_yaml(Path::new("/work/demo/.cladding"), &config, &network).unwrap();
+        let plan = docker_run_plan(&rendered, &network).expect("plan");
+        (network, plan)
+    }
+
+    #[test]
+    fn run_plan_gives_each_pod_a_namespace_container_then_its_containers() {
+        let (network, plan) = rendered_plan();
+        let names: Vec<&str> = plan
+            .iter()
+            .map(|step| {
+                let index = step.args.iter().position(|arg| arg == "--name").expect("name");
+                step.args[index + 1].as_str()
+            })
+            .collect();
+        let proxy = &network.proxy_pod_name;
+        let sandbox = &network.sandbox_pod_name;
+        assert_eq!(names[0], proxy);
+        assert_eq!(names[1], format!("{proxy}-proxy"));
+        assert_eq!(names[2], sandbox);
+        assert_eq!(names[3], format!("{sandbox}-sandbox-node"));
+        assert_eq!(names[4], format!("{sandbox}-sandbox-app"));
+
+        let pod = plan[2].args.join(" ");
+        assert!(pod.contains(&format!("--ip {}", network.sandbox_ip)), "{pod}");
+        assert!(pod.contains(&format!("--add-host proxy-pod:{}", network.proxy_ip)), "{pod}");
+        assert!(pod.contains("--label cladding=demo"), "{pod}");
+        assert!(pod.contains(&format!("--label {POD_INFRA_LABEL}=true")), "{pod}");
+
+        let jailer = &plan[3];
+        assert!(jailer.wait);
+        let jailer = jailer.args.join(" ");
+        assert!(jailer.contains(&format!("--network container:{sandbox}")), "{jailer}");
+        assert!(jailer.contains("--user 0:0 --cap-add NET_ADMIN"), "{jailer}");
+        assert!(jailer.ends_with("alpine:latest /opt/scripts/jail_sandbox.sh"), "{jailer}");
+
+        let app = plan[4].args.join(" ");
+        assert!(app.contains("--pull never"), "{app}");
+        assert!(app.contains("--health-cmd 'curl' '-fsS'"), "{app}");
+        assert!(app.contains("-v /work/demo/.cladding/..:/home/user/workspace "), "{app}");
+        assert!(app.contains("--tmpfs /home/user/workspace/.cladding:ro"), "{app}");
+        assert!(app.contains("-e MCP_BIND_ADDR=0.0.0.0:3000"), "{app}");
+        assert!(app.ends_with("--entrypoint mcp-run localhost/sandbox:latest"), "{app}");
+    }
+
+    #[test]
+    fn pod_state_comes_from_the_namespace_container() {
+        let parsed = json!([
+            {
+                "Name": "/demo-sandbox-sandbox-app",
+                "RestartCount": 4,
+                "State": {"Status": "running", "Health": {"Status": "unhealthy"}},
+                "Config": {"Image": "localhost/sandbox:latest", "Labels": {}}
+            },
+            {
+                "Name": "/demo-sandbox",
+                "State": {"Status": "running"},
+                "Config": {"Image": "alpine:latest", "Labels": {POD_INFRA_LABEL: "true"}},
+                "NetworkSettings": {"Networks": {"cladding-3": {"IPAddress": "10.90.3.2"}}}
+            }
+        ]);
+        let state = parse_pod_state("demo-sandbox", &parsed).expect("pod state");
+        assert!(state.is_running());
+        assert_eq!(state.addresses[0].ip, "10.90.3.2");
+        assert_eq!(state.containers.len(), 1);
+        assert_eq!(state.containers[0].name, "demo-sandbox-sandbox-app");
+        assert!(state.containers[0].is_crash_looping());
+        assert!(state.containers[0].is_unhealthy());
+    }
+}
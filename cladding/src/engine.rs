@@ -0,0 +1,82 @@
+//! Which container engine cladding drives: podman, or docker on machines where only Docker
+//! Desktop is available. The choice is made once per process, from `CLADDING_ENGINE` or the
+//! `engine` key of cladding.json, and every engine invocation goes through [`engine_command`].
+
+use crate::config::load_configured_engine;
+use crate::error::{Error, Result};
+use crate::output;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Overrides the `engine` key of cladding.json: `podman` or `docker`.
+pub const ENGINE_ENV: &str = "CLADDING_ENGINE";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    #[default]
+    Podman,
+    Docker,
+}
+
+impl Engine {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "podman" => Some(Self::Podman),
+            "docker" => Some(Self::Docker),
+            _ => None,
+        }
+    }
+
+    /// The engine's name, which is also the program cladding runs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Podman => "podman",
+            Self::Docker => "docker",
+        }
+    }
+}
+
+static SELECTED_ENGINE: OnceLock<Engine> = OnceLock::new();
+
+/// `CLADDING_ENGINE` if set, otherwise cladding.json's `engine`, otherwise podman.
+pub fn resolve_engine(project_root: &Path) -> Result<Engine> {
+    if let Some(value) = env::var_os(ENGINE_ENV) {
+        let value = value.to_string_lossy();
+        return Engine::parse(&value).ok_or_else(|| {
+            output::error(format!("{ENGINE_ENV}={value} is not a container engine"));
+            output::hint(format!("set {ENGINE_ENV} to podman or docker"));
+            Error::message(format!("invalid {ENGINE_ENV}"))
+        });
+    }
+    Ok(load_configured_engine(project_root)?.unwrap_or_default())
+}
+
+/// Makes `engine` the one [`engine_command`] runs for the rest of the process; only the first
+/// call has an effect.
+pub fn select_engine(engine: Engine) {
+    let _ = SELECTED_ENGINE.set(engine);
+}
+
+pub fn selected_engine() -> Engine {
+    SELECTED_ENGINE.get().copied().unwrap_or_default()
+}
+
+/// A command for the selected engine's CLI, with no arguments yet.
+pub fn engine_command() -> Command {
+    Command::new(selected_engine().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_engine_names() {
+        assert_eq!(Engine::parse("docker"), Some(Engine::Docker));
+        assert_eq!(Engine::parse(" podman\n"), Some(Engine::Podman));
+        assert_eq!(Engine::parse("Docker"), None);
+        assert_eq!(Engine::default().as_str(), "podman");
+    }
+}
@@ -4,6 +4,8 @@ pub enum Error {
     Message(String),
     #[error("{context} failed (exit code {code})")]
     CommandFailed { context: &'static str, code: i32 },
+    #[error("interrupted")]
+    Interrupted,
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
@@ -14,6 +16,7 @@ impl Error {
     pub fn exit_code(&self) -> i32 {
         match self {
             Error::CommandFailed { code, .. } => *code,
+            Error::Interrupted => 130,
             _ => 1,
         }
     }
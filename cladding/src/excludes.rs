@@ -0,0 +1,244 @@
+//! `workspace_excludes`: globs over the workspace whose matches are masked inside `cli-app`
+//! and `sandbox-app`. Globs are resolved against the files present when pods.yaml is
+//! rendered, so a matching path created later stays visible until the next `cladding up`.
+
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use std::ffi::OsStr;
+use std::path::{Component, Path};
+use walkdir::WalkDir;
+
+/// A workspace path hidden by `workspace_excludes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExcludedPath {
+    /// Relative to the workspace, with `/` separators.
+    pub relative: String,
+    pub is_dir: bool,
+}
+
+/// Compiles one `workspace_excludes` entry; `*` and `?` stop at `/`, `**` crosses it.
+pub fn compile_exclude_glob(pattern: &str) -> Result<Glob, String> {
+    if pattern.is_empty() {
+        return Err("expected non-empty glob".to_string());
+    }
+    let path = Path::new(pattern);
+    if path.is_absolute() {
+        return Err("expected a path relative to the workspace".to_string());
+    }
+    if path
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        return Err("'..' is not allowed".to_string());
+    }
+    GlobBuilder::new(pattern.trim_end_matches('/'))
+        .literal_separator(true)
+        .build()
+        .map_err(|error| error.kind().to_string())
+}
+
+/// The paths under `workspace` matching any of `patterns`, in walk order. A matched directory
+/// is masked whole, so nothing below it is listed. Symlinks are never matched, since a mount
+/// over one would land on its target. `.cladding` is skipped; it is always masked.
+pub fn resolve_workspace_excludes(workspace: &Path, patterns: &[String]) -> Vec<ExcludedPath> {
+    walk_workspace_excludes(workspace, patterns).0
+}
+
+/// The symlinks under `workspace` that match `patterns` but stay visible, relative to the
+/// workspace, for `cladding up` to warn about.
+pub fn unmasked_exclude_symlinks(workspace: &Path, patterns: &[String]) -> Vec<String> {
+    walk_workspace_excludes(workspace, patterns).1
+}
+
+/// The matches to mask, then the matching symlinks left alone.
+fn walk_workspace_excludes(
+    workspace: &Path,
+    patterns: &[String],
+) -> (Vec<ExcludedPath>, Vec<String>) {
+    let Some(globs) = build_glob_set(patterns) else {
+        return (Vec::new(), Vec::new());
+    };
+    let prefixes = build_component_matchers(patterns);
+
+    let mut excluded = Vec::new();
+    let mut symlinks = Vec::new();
+    let mut walker = WalkDir::new(workspace).min_depth(1).sort_by_file_name().into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let Ok(relative) = entry.path().strip_prefix(workspace) else {
+            continue;
+        };
+        if relative == Path::new(".cladding") {
+            walker.skip_current_dir();
+            continue;
+        }
+        let file_type = entry.file_type();
+        if !globs.is_match(relative) {
+            if file_type.is_dir()
+                && let Some(prefixes) = &prefixes
+            {
+                let dir = relative.iter().collect::<Vec<_>>();
+                if !may_match_below(prefixes, &dir) {
+                    walker.skip_current_dir();
+                }
+            }
+            continue;
+        }
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if file_type.is_symlink() {
+            symlinks.push(relative);
+            continue;
+        }
+        excluded.push(ExcludedPath {
+            relative,
+            is_dir: file_type.is_dir(),
+        });
+        if file_type.is_dir() {
+            walker.skip_current_dir();
+        }
+    }
+    (excluded, symlinks)
+}
+
+fn build_glob_set(patterns: &[String]) -> Option<GlobSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // Config loading already rejected invalid globs.
+        builder.add(compile_exclude_glob(pattern).ok()?);
+    }
+    builder.build().ok()
+}
+
+/// Each pattern split at `/`, with `None` standing for `**`, so the walk can skip directories
+/// no pattern reaches into. `None` when some pattern does not split cleanly, e.g. `{a/b,c}`.
+fn build_component_matchers(patterns: &[String]) -> Option<Vec<Vec<Option<GlobMatcher>>>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            pattern
+                .trim_end_matches('/')
+                .split('/')
+                .map(|component| match component {
+                    "**" => Some(None),
+                    "" | "." => None,
+                    _ => GlobBuilder::new(component)
+                        .literal_separator(true)
+                        .build()
+                        .ok()
+                        .map(|glob| Some(glob.compile_matcher())),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether a path strictly below `dir` could match one of the split patterns.
+fn may_match_below(patterns: &[Vec<Option<GlobMatcher>>], dir: &[&OsStr]) -> bool {
+    patterns.iter().any(|components| {
+        for (index, component) in components.iter().enumerate() {
+            let Some(matcher) = component else {
+                return true;
+            };
+            let Some(name) = dir.get(index) else {
+                return true;
+            };
+            if !matcher.is_match(name) {
+                return false;
+            }
+        }
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn resolves_files_and_whole_directories() {
+        let dir = std::env::temp_dir().join(format!(
+            "cladding-excludes-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        for path in ["secrets/nested", "src/config", ".cladding", ".git"] {
+            fs::create_dir_all(dir.join(path)).unwrap();
+        }
+        for file in [
+            ".env",
+            "src/.env",
+            "src/config/key.pem",
+            "secrets/nested/key.pem",
+            ".cladding/cert.pem",
+            ".git/config",
+        ] {
+            fs::write(dir.join(file), "x").unwrap();
+        }
+
+        let patterns = [".env", "secrets/", "**/*.pem", ".git/config"].map(String::from);
+        let excluded = resolve_workspace_excludes(&dir, &patterns);
+        let entry = |relative: &str, is_dir| ExcludedPath {
+            relative: relative.to_string(),
+            is_dir,
+        };
+        assert_eq!(
+            excluded,
+            vec![
+                entry(".env", false),
+                entry(".git/config", false),
+                entry("secrets", true),
+                entry("src/config/key.pem", false),
+            ]
+        );
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("../.env", dir.join("src/linked.pem")).unwrap();
+            assert_eq!(resolve_workspace_excludes(&dir, &patterns), excluded);
+            assert_eq!(
+                unmasked_exclude_symlinks(&dir, &patterns),
+                vec!["src/linked.pem".to_string()]
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prunes_directories_no_pattern_reaches_into() {
+        let patterns = ["secrets/", "src/*/key.pem", "docs/**/draft.md"].map(String::from);
+        let prefixes = build_component_matchers(&patterns).unwrap();
+        let below = |dir: &str| {
+            let dir = Path::new(dir).iter().collect::<Vec<_>>();
+            may_match_below(&prefixes, &dir)
+        };
+        assert!(below("src"));
+        assert!(below("src/config"));
+        assert!(!below("src/config/nested"));
+        assert!(below("docs/a/b"));
+        assert!(!below("node_modules"));
+        assert!(!below("secrets"));
+
+        assert!(build_component_matchers(&["{a/b,c}".to_string()]).is_none());
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_workspace() {
+        assert!(compile_exclude_glob("**/.env").is_ok());
+        assert!(compile_exclude_glob("").is_err());
+        assert!(compile_exclude_glob("/etc/passwd").is_err());
+        assert!(compile_exclude_glob("../other/.env").is_err());
+        assert!(compile_exclude_glob("src/[").is_err());
+    }
+}
@@ -35,6 +35,18 @@ pub fn path_is_symlink(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+/// Bytes of the files under `path`, without following symlinks. Entries that cannot be read,
+/// such as files another uid owns in a bind mount, are skipped; a missing `path` is 0.
+pub fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
 pub fn canonicalize_path(path: &Path) -> Result<PathBuf> {
     fs::canonicalize(path)
         .with_context(|| format!("failed to resolve {}", path.display()))
@@ -50,3 +62,144 @@ pub fn set_permissions(path: &Path, mode: u32) -> Result<()> {
     }
     Ok(())
 }
+
+/// uid and gid the images are built for, so files written to bind mounts stay the user's.
+/// Without unix ids (cladding.exe on Windows) this is podman machine's default user.
+pub fn host_ids() -> (u32, u32) {
+    #[cfg(unix)]
+    {
+        unsafe { (libc::getuid(), libc::getgid()) }
+    }
+    #[cfg(not(unix))]
+    {
+        (1000, 1000)
+    }
+}
+
+/// `path` as the container engine sees it. On Windows podman runs in a WSL2 machine that
+/// mounts each drive at `/mnt/<drive>`, so `C:\src\app` becomes `/mnt/c/src/app`; elsewhere
+/// the engine shares the host's paths.
+pub fn engine_host_path(path: &Path) -> String {
+    let path = path.display().to_string();
+    #[cfg(windows)]
+    if let Some(translated) = windows_path_to_wsl(&path) {
+        return translated;
+    }
+    path
+}
+
+/// The host path behind a path [`engine_host_path`] returned, e.g. a rendered `hostPath`.
+pub fn host_path_from_engine(path: &str) -> PathBuf {
+    #[cfg(windows)]
+    if let Some(translated) = wsl_path_to_windows(path) {
+        return PathBuf::from(translated);
+    }
+    PathBuf::from(path)
+}
+
+/// `C:\src\app`, or `\\?\C:\src\app` as `canonicalize` returns it, as `/mnt/c/src/app`. `None`
+/// for paths without a drive letter, like UNC shares, which the WSL2 machine cannot see.
+#[cfg(any(windows, test))]
+pub fn windows_path_to_wsl(path: &str) -> Option<String> {
+    let path = path.strip_prefix(r"\\?\").unwrap_or(path);
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let rest = chars.as_str().strip_prefix(':')?;
+    if !(rest.is_empty() || rest.starts_with(['\\', '/'])) {
+        return None;
+    }
+    let rest = rest.replace('\\', "/");
+    Some(format!(
+        "/mnt/{}{}",
+        drive.to_ascii_lowercase(),
+        rest.trim_end_matches('/')
+    ))
+}
+
+/// `/mnt/c/src/app` as `C:\src\app`; `None` outside the WSL2 machine's drive mounts.
+#[cfg(any(windows, test))]
+pub fn wsl_path_to_windows(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/mnt/")?;
+    let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+    let mut chars = drive.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(format!("{}:\\{}", drive.to_ascii_uppercase(), rest.replace('/', "\\")))
+}
+
+/// The WSL version cladding runs under, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wsl {
+    V1,
+    V2,
+}
+
+pub fn detect_wsl() -> Option<Wsl> {
+    let release = fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    parse_wsl_release(&release)
+}
+
+/// WSL2 kernels report e.g. `5.15.153.1-microsoft-standard-WSL2`, WSL1 `4.4.0-19041-Microsoft`.
+fn parse_wsl_release(release: &str) -> Option<Wsl> {
+    let release = release.trim().to_ascii_lowercase();
+    if !release.contains("microsoft") {
+        return None;
+    }
+    if release.contains("wsl2") || release.contains("microsoft-standard") {
+        Some(Wsl::V2)
+    } else {
+        Some(Wsl::V1)
+    }
+}
+
+/// Whether `path` is on a Windows drive WSL mounts at `/mnt/<drive>`, where file I/O goes
+/// through 9P and inotify does not see edits made from Windows.
+pub fn is_windows_drive_mount(path: &Path) -> bool {
+    let mut components = path.components().map(|component| component.as_os_str());
+    components.next() == Some(std::ffi::OsStr::new("/"))
+        && components.next() == Some(std::ffi::OsStr::new("mnt"))
+        && components.next().and_then(|drive| drive.to_str()).is_some_and(|drive| {
+            drive.len() == 1 && drive.chars().all(|ch| ch.is_ascii_alphabetic())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_drive_paths_map_to_the_wsl_machine() {
+        assert_eq!(
+            windows_path_to_wsl(r"C:\Users\dev\app\.cladding").as_deref(),
+            Some("/mnt/c/Users/dev/app/.cladding")
+        );
+        assert_eq!(
+            windows_path_to_wsl(r"\\?\D:\src\").as_deref(),
+            Some("/mnt/d/src")
+        );
+        assert_eq!(windows_path_to_wsl("E:").as_deref(), Some("/mnt/e"));
+        assert_eq!(windows_path_to_wsl(r"\\server\share\app"), None);
+        assert_eq!(windows_path_to_wsl("C:relative"), None);
+        assert_eq!(windows_path_to_wsl("/home/dev/app"), None);
+
+        assert_eq!(
+            wsl_path_to_windows("/mnt/c/Users/dev/app").as_deref(),
+            Some(r"C:\Users\dev\app")
+        );
+        assert_eq!(wsl_path_to_windows("/mnt/d").as_deref(), Some(r"D:\"));
+        assert_eq!(wsl_path_to_windows("/mnt/wsl/app"), None);
+
+        assert!(is_windows_drive_mount(Path::new("/mnt/c/Users/dev/app")));
+        assert!(!is_windows_drive_mount(Path::new("/mnt/wsl/app")));
+        assert!(!is_windows_drive_mount(Path::new("/home/dev/app")));
+    }
+
+    #[test]
+    fn wsl_version_comes_from_the_kernel_release() {
+        assert_eq!(parse_wsl_release("5.15.153.1-microsoft-standard-WSL2\n"), Some(Wsl::V2));
+        assert_eq!(parse_wsl_release("4.4.0-19041-Microsoft"), Some(Wsl::V1));
+        assert_eq!(parse_wsl_release("6.8.0-45-generic"), None);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::assets::home_template_preset_files;
+use crate::config::HomeTemplate;
+use crate::error::{Error, Result};
+use crate::output;
+use crate::fs_utils::path_is_symlink;
+use anyhow::Context as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HomeSeedSummary {
+    pub copied: usize,
+    pub skipped: usize,
+}
+
+enum TemplateFile {
+    Embedded(Vec<u8>),
+    Host(PathBuf),
+}
+
+/// Copies template files into `home_dir`, leaving any file that already exists untouched.
+pub fn seed_home(home_dir: &Path, template: &HomeTemplate) -> Result<HomeSeedSummary> {
+    let files = match template {
+        HomeTemplate::Preset(preset) => home_template_preset_files(preset)
+            .ok_or_else(|| {
+                output::error(format!("unknown home_template preset '{preset}'"));
+                Error::message("unknown home template preset")
+            })?
+            .into_iter()
+            .map(|(path, contents)| (path, TemplateFile::Embedded(contents)))
+            .collect::<Vec<_>>(),
+        HomeTemplate::Path(dir) => {
+            if !dir.is_dir() {
+                output::missing(format!("home_template directory ({})", dir.display()));
+                output::hint("create the directory or update home_template in cladding.json");
+                return Err(Error::message("missing home template"));
+            }
+            let mut files = Vec::new();
+            collect_template_files(dir, Path::new(""), &mut files)?;
+            files
+                .into_iter()
+                .map(|(relative, source)| (relative, TemplateFile::Host(source)))
+                .collect()
+        }
+    };
+
+    let mut summary = HomeSeedSummary::default();
+    for (relative, file) in files {
+        let target = home_dir.join(&relative);
+        if target.exists() || path_is_symlink(&target) {
+            summary.skipped += 1;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        match file {
+            TemplateFile::Embedded(contents) => {
+                fs::write(&target, contents)
+                    .with_context(|| format!("failed to write {}", target.display()))?;
+            }
+            TemplateFile::Host(source) => {
+                fs::copy(&source, &target).with_context(|| {
+                    format!("failed to copy {} to {}", source.display(), target.display())
+                })?;
+            }
+        }
+        summary.copied += 1;
+    }
+
+    Ok(summary)
+}
+
+pub fn describe_home_template(template: &HomeTemplate) -> String {
+    match template {
+        HomeTemplate::Preset(preset) => format!("preset '{preset}'"),
+        HomeTemplate::Path(path) => path.display().to_string(),
+    }
+}
+
+fn collect_template_files(
+    dir: &Path,
+    relative: &Path,
+    files: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("failed to read {}", dir.display()))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+        if path.is_dir() {
+            collect_template_files(&path, &relative, files)?;
+        } else if path.is_file() {
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "cladding-home-{label}-{}-{nanos}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn path_template_skips_existing_files() {
+        let root = temp_dir("path");
+        let template = root.join("template");
+        let home = root.join("home");
+        fs::create_dir_all(template.join(".config/tool")).unwrap();
+        fs::write(template.join(".gitconfig"), "template").unwrap();
+        fs::write(template.join(".config/tool/settings"), "nested").unwrap();
+        fs::create_dir_all(&home).unwrap();
+        fs::write(home.join(".gitconfig"), "user").unwrap();
+
+        let summary = seed_home(&home, &HomeTemplate::Path(template)).unwrap();
+        assert_eq!(summary, HomeSeedSummary { copied: 1, skipped: 1 });
+        assert_eq!(fs::read_to_string(home.join(".gitconfig")).unwrap(), "user");
+        assert_eq!(
+            fs::read_to_string(home.join(".config/tool/settings")).unwrap(),
+            "nested"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn default_preset_seeds_dotfiles() {
+        let home = temp_dir("preset");
+        let summary = seed_home(&home, &HomeTemplate::Preset("default".into())).unwrap();
+        assert!(summary.copied > 0);
+        assert!(home.join(".gitconfig").is_file());
+
+        let again = seed_home(&home, &HomeTemplate::Preset("default".into())).unwrap();
+        assert_eq!(again.copied, 0);
+        assert_eq!(again.skipped, summary.copied);
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -1,7 +1,24 @@
 pub mod assets;
 pub mod config;
+pub mod config_schema;
+pub mod dashboard;
+pub mod devcontainer;
+pub mod docker;
+pub mod engine;
+pub mod excludes;
 pub mod pods;
+pub mod policy_scaffold;
 pub mod error;
 pub mod fs_utils;
+pub mod home;
+pub mod lock;
+pub mod mcp_config;
 pub mod network;
+pub mod output;
 pub mod podman;
+pub mod pool;
+pub mod progress;
+pub mod runtime;
+pub mod services;
+pub mod squid;
+pub mod support_bundle;
@@ -0,0 +1,287 @@
+use crate::error::{Error, Result};
+use crate::output;
+use anyhow::Context as _;
+use std::fmt;
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+pub const LOCK_FILE_NAME: &str = "cladding.lock";
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Who holds a lock, as recorded in the file. Only informational: whether the lock is held is
+/// decided by the OS file lock, which is released when its holder exits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    /// `None` while the holder has not written its pid yet, or when the file is garbled.
+    pub pid: Option<i32>,
+    pub command: String,
+}
+
+impl fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "pid {pid} ({})", self.command),
+            None => write!(f, "an unidentified process ({})", self.command),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ProjectLock {
+    path: PathBuf,
+    file: File,
+}
+
+impl ProjectLock {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        // Remove the file while still holding it, so a process that opened it meanwhile sees
+        // it is gone once it gets the lock. Leave it alone if --force-unlock replaced it.
+        if is_current_lock_file(&self.path, &self.file) {
+            let _ = fs::remove_file(&self.path);
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+pub fn lock_path(project_root: &Path) -> PathBuf {
+    project_root.join(LOCK_FILE_NAME)
+}
+
+pub fn acquire_project_lock(project_root: &Path, command: &str, wait: bool) -> Result<ProjectLock> {
+    let path = lock_path(project_root);
+    let mut announced_wait = false;
+
+    loop {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("failed to open lock file {}", path.display()))?;
+        match file.try_lock() {
+            Ok(()) => {
+                // The holder we waited on removed the file, or --force-unlock replaced it.
+                if !is_current_lock_file(&path, &file) {
+                    continue;
+                }
+                write_lock_owner(&file, command)
+                    .with_context(|| format!("failed to write lock file {}", path.display()))?;
+                return Ok(ProjectLock { path, file });
+            }
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(err)) => {
+                return Err(Error::from(anyhow::Error::new(err).context(format!(
+                    "failed to lock {}",
+                    path.display()
+                ))));
+            }
+        }
+        drop(file);
+
+        let owner = read_lock_owner(&path)?.unwrap_or_else(|| parse_lock_contents(""));
+        if !wait {
+            output::error(format!("project is locked by another cladding process, {owner}"));
+            output::hint(
+                "retry with --wait, or use --force-unlock if the holder is not a running \
+                 cladding process",
+            );
+            return Err(Error::message("project is locked"));
+        }
+
+        if !announced_wait {
+            output::note(format!("waiting for lock held by {owner}..."));
+            announced_wait = true;
+        }
+        thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// Removes the lock file whether or not it is held, so the next command takes a new lock.
+pub fn force_unlock(project_root: &Path) -> Result<Option<LockOwner>> {
+    let path = lock_path(project_root);
+    let owner = read_lock_owner(&path)?;
+    if owner.is_some() {
+        remove_lock_file(&path)?;
+    }
+    Ok(owner)
+}
+
+pub fn read_lock_owner(path: &Path) -> Result<Option<LockOwner>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(Error::from(
+                anyhow::Error::new(err).context(format!("failed to read {}", path.display())),
+            ));
+        }
+    };
+    Ok(Some(parse_lock_contents(&contents)))
+}
+
+fn parse_lock_contents(contents: &str) -> LockOwner {
+    let mut lines = contents.lines();
+    let pid = lines
+        .next()
+        .and_then(|line| line.trim().parse::<i32>().ok())
+        .filter(|pid| *pid > 0);
+    let command = lines
+        .next()
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .unwrap_or("unknown")
+        .to_string();
+    LockOwner { pid, command }
+}
+
+fn write_lock_owner(mut file: &File, command: &str) -> io::Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    let pid = std::process::id();
+    writeln!(file, "{pid}\n{command}")?;
+    file.flush()
+}
+
+/// Whether `file` is still the one at `path`, rather than an unlinked or replaced copy.
+#[cfg(unix)]
+fn is_current_lock_file(path: &Path, file: &File) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::metadata(path), file.metadata()) {
+        (Ok(on_disk), Ok(locked)) => {
+            on_disk.dev() == locked.dev() && on_disk.ino() == locked.ino()
+        }
+        _ => false,
+    }
+}
+
+/// Elsewhere an open file cannot be removed, so the path always names the locked file.
+#[cfg(not(unix))]
+fn is_current_lock_file(path: &Path, _file: &File) -> bool {
+    path.exists()
+}
+
+fn remove_lock_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err)
+            .with_context(|| format!("failed to remove lock file {}", path.display()))
+            .map_err(Error::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_project_root(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "cladding-lock-{label}-{}-{nanos}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn acquire_writes_pid_and_releases_on_drop() {
+        let root = temp_project_root("acquire");
+        let lock = acquire_project_lock(&root, "up", false).unwrap();
+        let owner = read_lock_owner(lock.path()).unwrap().unwrap();
+        assert_eq!(owner.pid, Some(std::process::id() as i32));
+        assert_eq!(owner.command, "up");
+
+        drop(lock);
+        assert!(!lock_path(&root).exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn held_lock_rejects_second_acquire_without_wait() {
+        let root = temp_project_root("held");
+        let lock = acquire_project_lock(&root, "up", false).unwrap();
+        assert!(acquire_project_lock(&root, "down", false).is_err());
+        drop(lock);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn stale_lock_is_replaced() {
+        let root = temp_project_root("stale");
+        fs::write(lock_path(&root), "garbage\n").unwrap();
+
+        let lock = acquire_project_lock(&root, "down", false).unwrap();
+        let owner = read_lock_owner(lock.path()).unwrap().unwrap();
+        assert_eq!(owner.pid, Some(std::process::id() as i32));
+        assert_eq!(owner.command, "down");
+        drop(lock);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn held_lock_without_a_pid_is_not_stale() {
+        let root = temp_project_root("unwritten");
+        let holder = File::create(lock_path(&root)).unwrap();
+        holder.try_lock().unwrap();
+
+        assert!(acquire_project_lock(&root, "up", false).is_err());
+        assert_eq!(read_lock_owner(&lock_path(&root)).unwrap().unwrap().pid, None);
+        assert!(lock_path(&root).exists());
+        drop(holder);
+
+        drop(acquire_project_lock(&root, "up", false).unwrap());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn force_unlock_removes_live_lock() {
+        let root = temp_project_root("force");
+        let stuck = acquire_project_lock(&root, "up", false).unwrap();
+        let owner = force_unlock(&root).unwrap().unwrap();
+        assert_eq!(owner.command, "up");
+        assert!(!lock_path(&root).exists());
+        assert_eq!(force_unlock(&root).unwrap(), None);
+
+        let lock = acquire_project_lock(&root, "down", false).unwrap();
+        drop(stuck);
+        let owner = read_lock_owner(lock.path()).unwrap().unwrap();
+        assert_eq!(owner.command, "down");
+        drop(lock);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn parse_lock_contents_defaults() {
+        assert_eq!(
+            parse_lock_contents(""),
+            LockOwner {
+                pid: None,
+                command: "unknown".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_lock_contents("42\nexpose\n"),
+            LockOwner {
+                pid: Some(42),
+                command: "expose".to_string(),
+            }
+        );
+    }
+}
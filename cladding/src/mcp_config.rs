@@ -0,0 +1,153 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Hostname of the sandbox pod as seen from the cli container (a `hostAliases` entry).
+pub const SANDBOX_HOST: &str = "sandbox-pod";
+/// Port mcp-run listens on inside the sandbox pod (`MCP_BIND_ADDR` in pods.yaml).
+pub const SANDBOX_MCP_PORT: u16 = 3000;
+
+/// mcp-run as reached from inside the sandbox-app container, for its `/metrics` endpoint.
+pub const SANDBOX_LOCAL_MCP_URL: &str = "http://127.0.0.1:3000";
+
+/// mcp-run's loopback-only `/admin` listener inside the sandbox-app container
+/// (`MCP_ADMIN_BIND_ADDR` in pods.yaml).
+pub const SANDBOX_LOCAL_ADMIN_URL: &str = "http://127.0.0.1:3001";
+
+/// Version of the mcp-run binary this cladding embeds and writes to `tools/bin`.
+pub const BUNDLED_MCP_RUN_VERSION: &str = env!("CLADDING_MCP_RUN_VERSION");
+
+/// The fields of mcp-run's `GET /version` that `status` and `verify` report.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpRunVersion {
+    pub version: String,
+    pub git_commit: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl McpRunVersion {
+    /// Whether the server is the mcp-run release this cladding embeds.
+    pub fn is_bundled(&self) -> bool {
+        self.version == BUNDLED_MCP_RUN_VERSION
+    }
+
+    /// `0.1.0 (0123456789ab), features: auth, pty`.
+    pub fn summary(&self) -> String {
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+        format!("{} ({}), features: {features}", self.version, self.git_commit)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpEndpoint {
+    pub server_name: String,
+    pub url: String,
+}
+
+pub fn sandbox_mcp_endpoint(project_name: &str) -> McpEndpoint {
+    McpEndpoint {
+        server_name: format!("cladding-{project_name}"),
+        url: format!("http://{SANDBOX_HOST}:{SANDBOX_MCP_PORT}/mcp"),
+    }
+}
+
+/// `mcpServers` entry in the shape Claude's `.mcp.json` and settings files expect.
+pub fn render_claude_config(endpoint: &McpEndpoint) -> String {
+    let value = json!({
+        "mcpServers": {
+            endpoint.server_name.as_str(): {
+                "type": "http",
+                "url": endpoint.url,
+            }
+        }
+    });
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// Client-neutral description of the server for agents with their own config format.
+pub fn render_generic_config(endpoint: &McpEndpoint) -> String {
+    let value = json!({
+        "name": endpoint.server_name,
+        "transport": "streamable-http",
+        "url": endpoint.url,
+        "tools": ["run_network_tool"],
+    });
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// Samples from a Prometheus text exposition, keyed by metric name plus any labels exactly as
+/// written (`name{label="value"}`).
+pub fn parse_metric_samples(text: &str) -> BTreeMap<String, f64> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (name, value) = line.trim().rsplit_once(' ')?;
+            Some((name.to_string(), value.parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn renders_claude_and_generic_configs() {
+        let endpoint = sandbox_mcp_endpoint("demo");
+        assert_eq!(endpoint.url, "http://sandbox-pod:3000/mcp");
+
+        let claude: Value = serde_json::from_str(&render_claude_config(&endpoint)).unwrap();
+        assert_eq!(claude["mcpServers"]["cladding-demo"]["type"], "http");
+        assert_eq!(
+            claude["mcpServers"]["cladding-demo"]["url"],
+            "http://sandbox-pod:3000/mcp"
+        );
+
+        let generic: Value = serde_json::from_str(&render_generic_config(&endpoint)).unwrap();
+        assert_eq!(generic["name"], "cladding-demo");
+        assert_eq!(generic["transport"], "streamable-http");
+    }
+
+    #[test]
+    fn parses_metric_samples() {
+        let samples = parse_metric_samples(
+            "# HELP mcp_run_executions_active Commands running.\n\
+             # TYPE mcp_run_executions_active gauge\n\
+             mcp_run_executions_active 2\n\
+             mcp_run_command_user_cpu_seconds_total 1.250\n\
+             mcp_run_client_executions_total{client_id=\"builder\"} 7\n",
+        );
+        assert_eq!(samples.get("mcp_run_executions_active"), Some(&2.0));
+        assert_eq!(samples.get("mcp_run_command_user_cpu_seconds_total"), Some(&1.25));
+        assert_eq!(
+            samples.get("mcp_run_client_executions_total{client_id=\"builder\"}"),
+            Some(&7.0)
+        );
+        assert_eq!(samples.len(), 3);
+    }
+
+    #[test]
+    fn reads_the_version_endpoint() {
+        let version: McpRunVersion = serde_json::from_str(&format!(
+            r#"{{"name":"mcp-run","version":"{BUNDLED_MCP_RUN_VERSION}","gitCommit":"0123456789ab","features":["auth","pty"],"policyMode":"rego","protocols":{{"mcp":"2025-03-26","raw":1}}}}"#
+        ))
+        .unwrap();
+        assert!(version.is_bundled());
+        assert_eq!(
+            version.summary(),
+            format!("{BUNDLED_MCP_RUN_VERSION} (0123456789ab), features: auth, pty")
+        );
+
+        let older: McpRunVersion =
+            serde_json::from_str(r#"{"version":"0.0.1","gitCommit":"unknown"}"#).unwrap();
+        assert!(!older.is_bundled());
+        assert_eq!(older.summary(), "0.0.1 (unknown), features: none");
+    }
+}
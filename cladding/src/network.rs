@@ -11,8 +11,61 @@ pub struct NetworkSettings {
     pub proxy_pod_name: String,
     pub sandbox_pod_name: String,
     pub cli_pod_name: String,
+    /// The cli pods of the config's `agents`, in config order.
+    pub agent_pods: Vec<AgentPod>,
 }
 
+/// The cli pod of one entry of the config's `agents` list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentPod {
+    pub agent: String,
+    pub pod_name: String,
+    pub ip: String,
+}
+
+impl NetworkSettings {
+    /// The fixed address of one of the project's pods on its `cladding-N` network.
+    pub fn pod_ip(&self, pod_name: &str) -> Option<&str> {
+        [
+            (&self.proxy_pod_name, &self.proxy_ip),
+            (&self.sandbox_pod_name, &self.sandbox_ip),
+            (&self.cli_pod_name, &self.cli_ip),
+        ]
+        .into_iter()
+        .chain(self.agent_pods.iter().map(|pod| (&pod.pod_name, &pod.ip)))
+        .find(|(name, _)| *name == pod_name)
+        .map(|(_, ip)| ip.as_str())
+    }
+
+    /// Adds a cli pod for each of `agents`, addressed after the project's own pods
+    /// (`.5` onwards in the `/24`).
+    pub fn with_agents<'a>(mut self, name: &str, agents: impl IntoIterator<Item = &'a str>) -> Self {
+        let network_base = ipv4_cidr_range(&self.network_subnet)
+            .map(|(base, _)| base)
+            .unwrap_or_default();
+        self.agent_pods = agents
+            .into_iter()
+            .enumerate()
+            .map(|(index, agent)| AgentPod {
+                agent: agent.to_string(),
+                pod_name: agent_pod_name(name, agent),
+                ip: int_to_ipv4(network_base + FIRST_AGENT_HOST + index as u32),
+            })
+            .collect();
+        self
+    }
+
+    pub fn agent_pod(&self, agent: &str) -> Option<&AgentPod> {
+        self.agent_pods.iter().find(|pod| pod.agent == agent)
+    }
+}
+
+/// Host part of the first agent pod's address; `.2` to `.4` are the project's own pods.
+const FIRST_AGENT_HOST: u32 = 5;
+
+/// Address range the per-project `cladding-N` networks (`10.90.N.0/24`) are carved from.
+pub const CLADDING_POOL_SUBNET: &str = "10.90.0.0/16";
+
 pub fn resolve_network_settings(name: &str, pool_index: u8) -> Result<NetworkSettings> {
     let network_subnet = format!("10.90.{pool_index}.0/24");
     let network_base = ipv4_to_int(&format!("10.90.{pool_index}.0"))
@@ -28,12 +81,42 @@ pub fn resolve_network_settings(name: &str, pool_index: u8) -> Result<NetworkSet
         proxy_ip,
         sandbox_ip,
         cli_ip,
-        proxy_pod_name: format!("{}-proxy-pod", name),
-        sandbox_pod_name: format!("{}-sandbox-pod", name),
-        cli_pod_name: format!("{}-cli-pod", name),
+        proxy_pod_name: project_pod_name(name, "proxy"),
+        sandbox_pod_name: project_pod_name(name, "sandbox"),
+        cli_pod_name: project_pod_name(name, "cli"),
+        agent_pods: Vec::new(),
     })
 }
 
+pub const PROJECT_POD_ROLES: [&str; 3] = ["proxy", "sandbox", "cli"];
+
+pub fn project_pod_name(name: &str, role: &str) -> String {
+    format!("{name}-{role}-pod")
+}
+
+/// Name of an agent's cli pod, e.g. `demo-cli-codex-pod`.
+pub fn agent_pod_name(name: &str, agent: &str) -> String {
+    project_pod_name(name, &format!("cli-{agent}"))
+}
+
+/// Name of the main container in a project pod, e.g. `demo-proxy-pod-proxy` or
+/// `demo-cli-pod-cli-app`.
+pub fn project_container_name(name: &str, role: &str) -> String {
+    let pod_name = project_pod_name(name, role);
+    match role {
+        "proxy" => format!("{pod_name}-proxy"),
+        _ => format!("{pod_name}-{role}-app"),
+    }
+}
+
+/// Name of a `sidecars` container in the cli pod, e.g. `demo-cli-pod-postgres`.
+pub fn sidecar_container_name(name: &str, sidecar: &str) -> String {
+    format!("{}-{sidecar}", project_pod_name(name, "cli"))
+}
+
+/// Regular (non-internal) network that gives isolated projects' proxy pods their egress.
+pub const EGRESS_NETWORK: &str = "cladding-egress";
+
 pub fn cladding_pool_network_name(pool_index: u8) -> String {
     format!("cladding-{pool_index}")
 }
@@ -77,6 +160,53 @@ pub fn int_to_ipv4(value: u32) -> String {
     )
 }
 
+/// Network address and netmask of an IPv4 CIDR such as `10.90.0.0/24`.
+pub fn ipv4_cidr_range(value: &str) -> Option<(u32, u32)> {
+    let (ip, prefix) = value.split_once('/')?;
+    let prefix = prefix.parse::<u32>().ok().filter(|prefix| *prefix <= 32)?;
+    let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+    Some((ipv4_to_int(ip)? & mask, mask))
+}
+
+/// Whether two IPv4 CIDRs share any address.
+pub fn ipv4_cidrs_overlap(a: &str, b: &str) -> bool {
+    match (ipv4_cidr_range(a), ipv4_cidr_range(b)) {
+        (Some((a_base, a_mask)), Some((b_base, b_mask))) => {
+            let mask = a_mask & b_mask;
+            a_base & mask == b_base & mask
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostRoute {
+    pub interface: String,
+    pub subnet: String,
+}
+
+/// Parses Linux `/proc/net/route` into CIDRs, skipping the default route. Addresses in the
+/// file are little-endian hex.
+pub fn parse_proc_net_routes(contents: &str) -> Vec<HostRoute> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let interface = fields.first()?;
+            let destination = u32::from_str_radix(fields.get(1)?, 16).ok()?.swap_bytes();
+            let mask = u32::from_str_radix(fields.get(7)?, 16).ok()?.swap_bytes();
+            if mask == 0 {
+                return None;
+            }
+            Some(HostRoute {
+                interface: interface.to_string(),
+                subnet: format!("{}/{}", int_to_ipv4(destination), mask.count_ones()),
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,6 +228,37 @@ mod tests {
         assert_eq!(settings.cli_ip, "10.90.5.4");
     }
 
+    #[test]
+    fn agents_are_addressed_after_the_cli_pod() {
+        let settings = resolve_network_settings("demo", 5)
+            .unwrap()
+            .with_agents("demo", ["codex", "aider"]);
+        assert_eq!(
+            settings.agent_pods,
+            vec![
+                AgentPod {
+                    agent: "codex".to_string(),
+                    pod_name: "demo-cli-codex-pod".to_string(),
+                    ip: "10.90.5.5".to_string(),
+                },
+                AgentPod {
+                    agent: "aider".to_string(),
+                    pod_name: "demo-cli-aider-pod".to_string(),
+                    ip: "10.90.5.6".to_string(),
+                },
+            ]
+        );
+        assert_eq!(settings.pod_ip("demo-cli-aider-pod"), Some("10.90.5.6"));
+        assert_eq!(settings.pod_ip("demo-cli-pod"), Some("10.90.5.4"));
+    }
+
+    #[test]
+    fn project_container_names() {
+        assert_eq!(project_container_name("demo", "proxy"), "demo-proxy-pod-proxy");
+        assert_eq!(project_container_name("demo", "sandbox"), "demo-sandbox-pod-sandbox-app");
+        assert_eq!(project_container_name("demo", "cli"), "demo-cli-pod-cli-app");
+    }
+
     #[test]
     fn parse_pool_index() {
         assert_eq!(parse_cladding_pool_index("cladding-0"), Some(0));
@@ -105,4 +266,34 @@ mod tests {
         assert_eq!(parse_cladding_pool_index("cladding-256"), None);
         assert_eq!(parse_cladding_pool_index("demo_cladding_net"), None);
     }
+
+    #[test]
+    fn ipv4_cidr_overlap() {
+        assert!(ipv4_cidrs_overlap("10.90.0.0/24", "10.90.0.128/25"));
+        assert!(ipv4_cidrs_overlap("10.0.0.0/8", "10.90.3.0/24"));
+        assert!(!ipv4_cidrs_overlap("10.90.0.0/24", "10.90.1.0/24"));
+        assert!(ipv4_cidrs_overlap("0.0.0.0/0", "192.168.1.0/24"));
+        assert!(!ipv4_cidrs_overlap("10.90.0.0/24", "not-a-cidr"));
+    }
+
+    #[test]
+    fn parse_host_routes() {
+        let contents = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n\
+wg0\t00005A0A\t00000000\t0001\t0\t0\t0\t0000FFFF\t0\t0\t0\n";
+        assert_eq!(
+            parse_proc_net_routes(contents),
+            vec![
+                HostRoute {
+                    interface: "eth0".to_string(),
+                    subnet: "192.168.1.0/24".to_string(),
+                },
+                HostRoute {
+                    interface: "wg0".to_string(),
+                    subnet: "10.90.0.0/16".to_string(),
+                },
+            ]
+        );
+    }
 }
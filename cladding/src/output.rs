@@ -0,0 +1,138 @@
+//! Prefixed messages on stderr. Prefixes are padded to one width so a message lines up with
+//! the hints and details under it, and are colored when stderr is a terminal, unless
+//! `NO_COLOR` is set or `--no-color` was passed.
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefix {
+    Error,
+    Warning,
+    Missing,
+    Hint,
+    Note,
+    /// The file a preceding error is about.
+    File,
+}
+
+impl Prefix {
+    fn label(self) -> &'static str {
+        match self {
+            Prefix::Error => "error:",
+            Prefix::Warning => "warning:",
+            Prefix::Missing => "missing:",
+            Prefix::Hint => "hint:",
+            Prefix::Note => "note:",
+            Prefix::File => "file:",
+        }
+    }
+
+    /// SGR parameters for the label.
+    fn color(self) -> Option<&'static str> {
+        match self {
+            Prefix::Error | Prefix::Missing => Some("1;31"),
+            Prefix::Warning => Some("1;33"),
+            Prefix::Hint => Some("1;36"),
+            Prefix::Note => Some("1"),
+            Prefix::File => None,
+        }
+    }
+}
+
+/// Width of the longest label, `warning:` and `missing:`.
+const LABEL_WIDTH: usize = 8;
+
+/// Turns color off for the rest of the process, for `--no-color`.
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether messages written now are colored.
+pub fn color_enabled() -> bool {
+    !COLOR_DISABLED.load(Ordering::Relaxed)
+        && !no_color_requested(std::env::var_os("NO_COLOR").as_deref())
+        && std::io::stderr().is_terminal()
+}
+
+/// `NO_COLOR` turns color off when set to anything but the empty string (no-color.org).
+fn no_color_requested(value: Option<&std::ffi::OsStr>) -> bool {
+    value.is_some_and(|value| !value.is_empty())
+}
+
+/// `message` after its padded label; further lines of `message` are indented to line up
+/// with the first.
+pub fn format_message(prefix: Prefix, message: &str, color: bool) -> String {
+    let label = prefix.label();
+    let padding = " ".repeat(LABEL_WIDTH + 1 - label.len());
+    let body = message
+        .lines()
+        .collect::<Vec<_>>()
+        .join(&format!("\n{}", " ".repeat(LABEL_WIDTH + 1)));
+    match prefix.color() {
+        Some(sgr) if color => format!("\x1b[{sgr}m{label}\x1b[0m{padding}{body}"),
+        _ => format!("{label}{padding}{body}"),
+    }
+}
+
+pub fn emit(prefix: Prefix, message: impl Display) {
+    eprintln!(
+        "{}",
+        format_message(prefix, &message.to_string(), color_enabled())
+    );
+}
+
+pub fn error(message: impl Display) {
+    emit(Prefix::Error, message);
+}
+
+pub fn warning(message: impl Display) {
+    emit(Prefix::Warning, message);
+}
+
+pub fn missing(message: impl Display) {
+    emit(Prefix::Missing, message);
+}
+
+pub fn hint(message: impl Display) {
+    emit(Prefix::Hint, message);
+}
+
+pub fn note(message: impl Display) {
+    emit(Prefix::Note, message);
+}
+
+pub fn file(path: impl Display) {
+    emit(Prefix::File, path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_align_and_color_only_when_asked() {
+        assert_eq!(
+            format_message(Prefix::Error, "bad config", false),
+            "error:   bad config"
+        );
+        assert_eq!(
+            format_message(Prefix::Warning, "first\nsecond", false),
+            "warning: first\n         second"
+        );
+        assert_eq!(
+            format_message(Prefix::Hint, "run cladding init", true),
+            "\x1b[1;36mhint:\x1b[0m    run cladding init"
+        );
+        assert_eq!(
+            format_message(Prefix::File, "/tmp/x", true),
+            "file:    /tmp/x"
+        );
+        assert!(no_color_requested(Some("1".as_ref())));
+        assert!(!no_color_requested(Some("".as_ref())));
+        assert!(!no_color_requested(None));
+    }
+}
@@ -1,170 +1,302 @@
-use crate::assets::containerfile;
+use crate::assets::{containerfile, containerfile_digest};
+use crate::config::parse_size;
+use crate::engine::{Engine, engine_command, selected_engine};
 use crate::error::{Error, Result};
-use crate::network::{is_ipv4_cidr, parse_cladding_pool_index, NetworkSettings};
+use crate::mcp_config::{SANDBOX_LOCAL_ADMIN_URL, SANDBOX_LOCAL_MCP_URL};
+use crate::network::{
+    EGRESS_NETWORK, NetworkSettings, is_ipv4_cidr, parse_cladding_pool_index,
+};
+use crate::output;
+use crate::pods::pod_names_from_rendered;
+use crate::progress;
 use anyhow::Context as _;
 use serde_json::Value;
-use std::collections::HashMap;
+use signal_hook::consts::signal::{SIGINT, SIGTERM};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::io::Read;
 use std::process::{Command, ExitStatus, Output, Stdio};
-
-pub fn podman_required(message: &str) -> Result<()> {
-    if command_exists("podman") {
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Fails unless the selected engine's CLI is on PATH; `command` names what needs it.
+pub fn engine_required(command: &str) -> Result<()> {
+    let engine = selected_engine().as_str();
+    if command_exists(engine) {
         Ok(())
     } else {
-        eprintln!("missing: {message}");
-        Err(Error::message("missing podman"))
+        output::missing(format!("{engine} (required for {command})"));
+        Err(Error::message(format!("missing {engine}")))
     }
 }
 
-pub fn ensure_network_settings(network_settings: &NetworkSettings) -> Result<()> {
-    let status = Command::new("podman")
-        .args(["network", "exists", &network_settings.network])
-        .status()
-        .with_context(|| "failed to check existing networks via podman")?;
-
-    match status.code() {
-        Some(0) => {
-            let output = Command::new("podman")
-                .args(["network", "inspect", &network_settings.network])
-                .output()
-                .with_context(|| "failed to inspect podman network")?;
-
-            if !output.status.success() {
-                return ensure_success_output(&output, "podman network inspect");
-            }
-
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if !stdout.contains(&format!("\"subnet\": \"{}\"", network_settings.network_subnet))
-            {
-                eprintln!(
-                    "error: network {} exists but is not on {}",
-                    network_settings.network, network_settings.network_subnet
-                );
-                eprintln!(
-                    "hint: run 'podman network rm {}' and retry",
-                    network_settings.network
-                );
-                return Err(Error::message("network subnet mismatch"));
-            }
-        }
-        Some(1) => {
-            let status = Command::new("podman")
-                .args([
-                    "network",
-                    "create",
-                    "--subnet",
-                    &network_settings.network_subnet,
-                    &network_settings.network,
-                ])
-                .status()
-                .with_context(|| "failed to create podman network")?;
-            ensure_success(status, "podman network create")?;
-        }
-        _ => {
-            eprintln!("error: failed to check existing networks via podman");
-            return Err(Error::message("podman network exists failed"));
-        }
-    }
-
-    Ok(())
+/// Exit status of `<kind> exists <name>`: 0 when it exists, 1 when it does not. Docker has
+/// no `exists` subcommands, so there `<kind> inspect` stands in with its output discarded.
+fn object_exists_status(engine: Engine, kind: &str, name: &str) -> Result<ExitStatus> {
+    let mut cmd = engine_command();
+    match engine {
+        Engine::Podman => cmd.args([kind, "exists", name]),
+        Engine::Docker => cmd
+            .args([kind, "inspect", name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null()),
+    };
+    cmd.bounded_status()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EnsureNetworkOutcome {
     Ready,
     SubnetMismatch,
+    /// The network's `internal` flag differs and it could not be recreated (still in use).
+    IsolationMismatch,
 }
 
+/// Ensures the pool network exists on its subnet, as an `--internal` network when `internal`.
+/// An idle network with the wrong `internal` flag is recreated.
 pub fn ensure_pool_network_settings(
+    engine: Engine,
     network_settings: &NetworkSettings,
+    internal: bool,
 ) -> Result<EnsureNetworkOutcome> {
-    let status = Command::new("podman")
-        .args(["network", "exists", &network_settings.network])
-        .status()
-        .with_context(|| "failed to check existing networks via podman")?;
+    let status = object_exists_status(engine, "network", &network_settings.network)?;
 
     match status.code() {
         Some(0) => {
-            let output = Command::new("podman")
+            let output = engine_command()
                 .args(["network", "inspect", &network_settings.network])
-                .output()
-                .with_context(|| "failed to inspect podman network")?;
+                .bounded_output()?;
 
             if !output.status.success() {
                 return ensure_success_output(&output, "podman network inspect")
                     .map(|_| EnsureNetworkOutcome::Ready);
             }
 
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if stdout.contains(&format!("\"subnet\": \"{}\"", network_settings.network_subnet)) {
-                Ok(EnsureNetworkOutcome::Ready)
-            } else {
-                Ok(EnsureNetworkOutcome::SubnetMismatch)
+            let parsed: Value = serde_json::from_slice(&output.stdout)
+                .with_context(|| "failed to parse network inspect json output")?;
+            if !parse_network_subnets(&parsed).contains(&network_settings.network_subnet) {
+                return Ok(EnsureNetworkOutcome::SubnetMismatch);
+            }
+            if parse_network_internal(&parsed) == internal {
+                return Ok(EnsureNetworkOutcome::Ready);
+            }
+
+            let removed = engine_command()
+                .args(["network", "rm", &network_settings.network])
+                .bounded_output()?;
+            if !removed.status.success() {
+                return Ok(EnsureNetworkOutcome::IsolationMismatch);
             }
+            create_pool_network(network_settings, internal)?;
+            Ok(EnsureNetworkOutcome::Ready)
         }
         Some(1) => {
-            let status = Command::new("podman")
-                .args([
-                    "network",
-                    "create",
-                    "--subnet",
-                    &network_settings.network_subnet,
-                    &network_settings.network,
-                ])
-                .status()
-                .with_context(|| "failed to create podman network")?;
-            ensure_success(status, "podman network create")?;
+            create_pool_network(network_settings, internal)?;
             Ok(EnsureNetworkOutcome::Ready)
         }
         _ => {
-            eprintln!("error: failed to check existing networks via podman");
+            output::error("failed to check existing networks via podman");
             Err(Error::message("podman network exists failed"))
         }
     }
 }
 
+fn create_pool_network(network_settings: &NetworkSettings, internal: bool) -> Result<()> {
+    let mut cmd = engine_command();
+    cmd.args(["network", "create", "--subnet", &network_settings.network_subnet]);
+    if internal {
+        cmd.arg("--internal");
+    }
+    let status = cmd.arg(&network_settings.network).bounded_status()?;
+    ensure_success(status, "podman network create")
+}
+
+pub fn podman_network_is_internal(network: &str) -> Result<bool> {
+    let output = engine_command()
+        .args(["network", "inspect", network])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman network inspect")?;
+    let parsed: Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+        .with_context(|| "failed to parse podman network inspect json output")?;
+    Ok(parse_network_internal(&parsed))
+}
+
+/// Podman reports `internal`, docker `Internal`.
+fn parse_network_internal(value: &Value) -> bool {
+    let network = value.as_array().and_then(|items| items.first()).unwrap_or(value);
+    ["internal", "Internal"]
+        .iter()
+        .any(|key| network.get(key).and_then(Value::as_bool) == Some(true))
+}
+
+/// Subnets from `network inspect`: podman's `subnets[].subnet` or docker's
+/// `IPAM.Config[].Subnet`.
+fn parse_network_subnets(value: &Value) -> Vec<String> {
+    let network = value.as_array().and_then(|items| items.first()).unwrap_or(value);
+    let podman = network.get("subnets").and_then(Value::as_array);
+    let docker = network
+        .get("IPAM")
+        .and_then(|ipam| ipam.get("Config"))
+        .and_then(Value::as_array);
+    podman
+        .into_iter()
+        .flatten()
+        .filter_map(|subnet| subnet.get("subnet"))
+        .chain(docker.into_iter().flatten().filter_map(|config| config.get("Subnet")))
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Oldest podman `cladding doctor` accepts; `play kube` before 4.4 mishandles the pod specs.
+pub const MIN_PODMAN_VERSION: (u32, u32) = (4, 4);
+
+/// The parts of `podman info` that `cladding doctor` checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PodmanHostInfo {
+    pub version: String,
+    pub rootless: bool,
+    /// Entries in the user namespace uid map; rootless podman needs a subordinate range
+    /// (from /etc/subuid) beyond the user's own uid.
+    pub uid_map_entries: usize,
+}
+
+pub fn podman_host_info() -> Result<PodmanHostInfo> {
+    let output = engine_command()
+        .args(["info", "--format", "json"])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman info")?;
+    let parsed: Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+        .with_context(|| "failed to parse podman info json output")?;
+    parse_podman_host_info(&parsed)
+        .ok_or_else(|| Error::message("unexpected podman info json output"))
+}
+
+fn parse_podman_host_info(value: &Value) -> Option<PodmanHostInfo> {
+    let host = value.get("host")?;
+    Some(PodmanHostInfo {
+        version: value.get("version")?.get("Version")?.as_str()?.to_string(),
+        rootless: host.get("security")?.get("rootless")?.as_bool()?,
+        uid_map_entries: host
+            .get("idMappings")
+            .and_then(|mappings| mappings.get("uidmap"))
+            .and_then(Value::as_array)
+            .map_or(0, Vec::len),
+    })
+}
+
+/// Major and minor version from strings like `4.9.3` or `5.0.0-dev`.
+pub fn parse_podman_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Creates the shared egress network used by isolated projects' proxy pods, if missing.
+pub fn ensure_egress_network(engine: Engine) -> Result<()> {
+    let status = object_exists_status(engine, "network", EGRESS_NETWORK)?;
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(1) => {
+            let status = engine_command()
+                .args(["network", "create", EGRESS_NETWORK])
+                .bounded_status()?;
+            ensure_success(status, "podman network create")
+        }
+        _ => {
+            output::error("failed to check existing networks via podman");
+            Err(Error::message("podman network exists failed"))
+        }
+    }
+}
+
+/// Attaches a running pod to an additional network through its infra container.
+pub fn podman_pod_network_connect(pod_name: &str, network: &str) -> Result<()> {
+    let inspect = engine_command()
+        .args(["pod", "inspect", pod_name, "--format", "json"])
+        .bounded_output()?;
+    ensure_success_output(&inspect, "podman pod inspect")?;
+    let parsed: Value = serde_json::from_str(&String::from_utf8_lossy(&inspect.stdout))
+        .with_context(|| "failed to parse podman pod inspect json output")?;
+    let Some(infra_id) = find_infra_container_id(&parsed) else {
+        output::error(format!("pod {pod_name} has no infra container to attach to {network}"));
+        return Err(Error::message("missing infra container"));
+    };
+
+    let status = engine_command()
+        .args(["network", "connect", network, &infra_id])
+        .bounded_status()?;
+    ensure_success(status, "podman network connect")
+}
+
+/// Labels `cladding build` puts on its images, so `cladding images` can tell what they were
+/// built with.
+pub const IMAGE_UID_LABEL: &str = "io.cladding.uid";
+pub const IMAGE_GID_LABEL: &str = "io.cladding.gid";
+pub const IMAGE_CONTAINERFILE_LABEL: &str = "io.cladding.containerfile";
+
 pub fn podman_build_image(image: &str, host_uid: u32, host_gid: u32) -> Result<()> {
-    let mut cmd = Command::new("podman");
+    let mut cmd = engine_command();
     cmd.args([
         "build",
         "--build-arg",
         &format!("UID={host_uid}"),
         "--build-arg",
         &format!("GID={host_gid}"),
+        "--label",
+        &format!("{IMAGE_UID_LABEL}={host_uid}"),
+        "--label",
+        &format!("{IMAGE_GID_LABEL}={host_gid}"),
+        "--label",
+        &format!("{IMAGE_CONTAINERFILE_LABEL}={}", containerfile_digest()),
         "-t",
         image,
         "-f",
         "-",
         ".",
-    ])
-    .stdin(Stdio::piped());
-
-    let mut child = cmd.spawn().with_context(|| "failed to run podman build")?;
-
-    if let Some(mut stdin) = child.stdin.take() {
-        use std::io::Write;
-        stdin
-            .write_all(containerfile().as_bytes())
-            .and_then(|_| stdin.flush())
-            .with_context(|| "failed to write Containerfile to podman")?;
+    ]);
+    if progress::json_enabled() {
+        // Keeps stdout to progress events.
+        cmd.stdout(std::io::stderr());
     }
 
-    let status = child.wait().with_context(|| "failed to wait on podman build")?;
+    let status =
+        bounded_status_with_input(&mut cmd, containerfile().as_bytes(), PodmanTimeout::Long)?;
 
     ensure_success(status, "podman build")
 }
 
+pub fn podman_tag_image(image: &str, tag: &str) -> Result<()> {
+    let status = engine_command().args(["tag", image, tag]).bounded_status()?;
+    ensure_success(status, "podman tag")
+}
+
+/// Pushes with the engine's stored registry credentials (`podman login`).
+pub fn podman_push_image(tag: &str) -> Result<()> {
+    let mut cmd = engine_command();
+    cmd.args(["push", tag]);
+    if progress::json_enabled() {
+        // Keeps stdout to progress events.
+        cmd.stdout(std::io::stderr());
+    }
+    let limit = PodmanTimeout::Long.limit()?;
+    let status = run_bounded(&mut cmd, None, false, PodmanTimeout::Long, limit)?.status;
+    ensure_success(status, "podman push")
+}
+
 #[derive(Debug, Clone)]
 pub struct NetworkSubnet {
     pub name: String,
     pub subnet: String,
 }
 
-pub fn list_podman_network_subnets() -> Result<Vec<NetworkSubnet>> {
-    let output = Command::new("podman")
+pub fn list_podman_network_subnets(engine: Engine) -> Result<Vec<NetworkSubnet>> {
+    let output = engine_command()
         .args(["network", "ls", "--format", "{{.Name}}"])
-        .output()
-        .with_context(|| "failed to list podman networks")?;
+        .bounded_output()?;
 
     if !output.status.success() {
         return ensure_success_output(&output, "podman network ls").map(|_| Vec::new());
@@ -174,16 +306,13 @@ pub fn list_podman_network_subnets() -> Result<Vec<NetworkSubnet>> {
     let mut subnets = Vec::new();
 
     for name in stdout.lines().map(str::trim).filter(|s| !s.is_empty()) {
-        let output = Command::new("podman")
-            .args([
-                "network",
-                "inspect",
-                "-f",
-                "{{range .Subnets}}{{.Subnet}}{{\"\\n\"}}{{end}}",
-                name,
-            ])
-            .output()
-            .with_context(|| "failed to inspect podman network")?;
+        let format = match engine {
+            Engine::Podman => "{{range .Subnets}}{{.Subnet}}{{\"\\n\"}}{{end}}",
+            Engine::Docker => "{{range .IPAM.Config}}{{.Subnet}}{{\"\\n\"}}{{end}}",
+        };
+        let output = engine_command()
+            .args(["network", "inspect", "-f", format, name])
+            .bounded_output()?;
 
         if !output.status.success() {
             return ensure_success_output(&output, "podman network inspect")
@@ -209,35 +338,23 @@ pub fn podman_play_kube(
     network: &NetworkSettings,
     down: bool,
 ) -> Result<()> {
-    let mut cmd = Command::new("podman");
+    let mut cmd = engine_command();
     cmd.arg("play").arg("kube");
     if down {
         cmd.arg("--down");
     } else {
-        cmd.args([
-            "--network",
-            &network.network,
-            "--ip",
-            &network.proxy_ip,
-            "--ip",
-            &network.sandbox_ip,
-            "--ip",
-            &network.cli_ip,
-        ]);
+        cmd.args(["--network", &network.network]);
+        // podman hands out `--ip` values to the pods in document order, so a rendered subset
+        // (as `cladding restart <pod>` plays) gets only its own addresses.
+        for pod_name in pod_names_from_rendered(rendered) {
+            if let Some(ip) = network.pod_ip(&pod_name) {
+                cmd.args(["--ip", ip]);
+            }
+        }
     }
     cmd.arg("-");
-    cmd.stdin(Stdio::piped());
-
-    let mut child = cmd.spawn().with_context(|| "failed to run podman play kube")?;
 
-    if let Some(mut stdin) = child.stdin.take() {
-        use std::io::Write;
-        stdin
-            .write_all(rendered.as_bytes())
-            .with_context(|| "failed to write pods.yaml to podman")?;
-    }
-
-    let status = child.wait().with_context(|| "failed to wait on podman play kube")?;
+    let status = bounded_status_with_input(&mut cmd, rendered.as_bytes(), PodmanTimeout::Long)?;
 
     ensure_success(status, "podman play kube")
 }
@@ -248,7 +365,7 @@ pub fn ensure_success(status: ExitStatus, context: &'static str) -> Result<()> {
     }
 
     let code = status.code().unwrap_or(1);
-    eprintln!("error: {context} failed (exit code {code})");
+    output::error(format!("{context} failed (exit code {code})"));
     Err(Error::CommandFailed { context, code })
 }
 
@@ -258,7 +375,7 @@ pub fn ensure_success_output(output: &Output, context: &'static str) -> Result<(
     }
 
     let code = output.status.code().unwrap_or(1);
-    eprintln!("error: {context} failed (exit code {code})");
+    output::error(format!("{context} failed (exit code {code})"));
     let stderr = String::from_utf8_lossy(&output.stderr);
     if !stderr.trim().is_empty() {
         eprintln!("{stderr}");
@@ -266,8 +383,218 @@ pub fn ensure_success_output(output: &Output, context: &'static str) -> Result<(
     Err(Error::CommandFailed { context, code })
 }
 
+/// Seconds a podman query may take before cladding gives up on it; `0` disables the limit.
+pub const PODMAN_TIMEOUT_ENV: &str = "CLADDING_PODMAN_TIMEOUT";
+/// The same for `podman build`, `podman push` and `podman play kube`, which move images.
+pub const PODMAN_LONG_TIMEOUT_ENV: &str = "CLADDING_PODMAN_LONG_TIMEOUT";
+
+const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_LONG_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Which limit applies to a podman invocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PodmanTimeout {
+    /// Inspections, listings and other calls that should return promptly.
+    Query,
+    /// Builds, pushes and `play kube`.
+    Long,
+}
+
+impl PodmanTimeout {
+    fn env_name(self) -> &'static str {
+        match self {
+            PodmanTimeout::Query => PODMAN_TIMEOUT_ENV,
+            PodmanTimeout::Long => PODMAN_LONG_TIMEOUT_ENV,
+        }
+    }
+
+    fn limit(self) -> Result<Option<Duration>> {
+        let name = self.env_name();
+        let Ok(value) = env::var(name) else {
+            return Ok(Some(match self {
+                PodmanTimeout::Query => DEFAULT_QUERY_TIMEOUT,
+                PodmanTimeout::Long => DEFAULT_LONG_TIMEOUT,
+            }));
+        };
+        parse_timeout_secs(&value).ok_or_else(|| {
+            output::error(format!("{name} must be a number of seconds (0 disables the limit)"));
+            Error::message(format!("invalid {name}"))
+        })
+    }
+}
+
+/// Parses a timeout in whole seconds; `Some(None)` for `0`, which means no limit.
+pub fn parse_timeout_secs(value: &str) -> Option<Option<Duration>> {
+    let secs: u64 = value.trim().parse().ok()?;
+    Some((secs > 0).then(|| Duration::from_secs(secs)))
+}
+
+/// `status` and `output` for podman commands, bounded by the [`PodmanTimeout::Query`] limit.
+/// On Ctrl-C or SIGTERM the podman child is killed and [`Error::Interrupted`] returned.
+pub trait PodmanCommand {
+    fn bounded_status(&mut self) -> Result<ExitStatus>;
+    fn bounded_output(&mut self) -> Result<Output>;
+}
+
+impl PodmanCommand for Command {
+    fn bounded_status(&mut self) -> Result<ExitStatus> {
+        let limit = PodmanTimeout::Query.limit()?;
+        run_bounded(self, None, false, PodmanTimeout::Query, limit).map(|output| output.status)
+    }
+
+    fn bounded_output(&mut self) -> Result<Output> {
+        let limit = PodmanTimeout::Query.limit()?;
+        run_bounded(self, None, true, PodmanTimeout::Query, limit)
+    }
+}
+
+/// Runs `cmd` with `input` on its stdin and its output going to the terminal.
+pub fn bounded_status_with_input(
+    cmd: &mut Command,
+    input: &[u8],
+    timeout: PodmanTimeout,
+) -> Result<ExitStatus> {
+    let limit = timeout.limit()?;
+    run_bounded(cmd, Some(input), false, timeout, limit).map(|output| output.status)
+}
+
+/// Runs `cmd` with its output captured, bounded by `timeout`.
+pub fn bounded_output_with_timeout(cmd: &mut Command, timeout: PodmanTimeout) -> Result<Output> {
+    let limit = timeout.limit()?;
+    run_bounded(cmd, None, true, timeout, limit)
+}
+
+fn run_bounded(
+    cmd: &mut Command,
+    input: Option<&[u8]>,
+    capture: bool,
+    timeout: PodmanTimeout,
+    limit: Option<Duration>,
+) -> Result<Output> {
+    let label = command_label(cmd);
+    if input.is_some() {
+        cmd.stdin(Stdio::piped());
+    } else if capture {
+        cmd.stdin(Stdio::null());
+    }
+    if capture {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+
+    // While the flag is registered Ctrl-C no longer kills cladding, so the loop below must
+    // notice it and kill the child itself.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let mut signal_ids = Vec::new();
+    for signal in [SIGINT, SIGTERM] {
+        signal_ids.push(
+            signal_hook::flag::register(signal, Arc::clone(&interrupted))
+                .with_context(|| "failed to install signal handlers")?,
+        );
+    }
+    let result = wait_bounded(cmd, &label, input, timeout, limit, &interrupted);
+    for id in signal_ids {
+        signal_hook::low_level::unregister(id);
+    }
+    result
+}
+
+fn wait_bounded(
+    cmd: &mut Command,
+    label: &str,
+    input: Option<&[u8]>,
+    timeout: PodmanTimeout,
+    limit: Option<Duration>,
+    interrupted: &AtomicBool,
+) -> Result<Output> {
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("failed to run {label}"))?;
+    let started = Instant::now();
+
+    if let Some(input) = input
+        && let Some(mut stdin) = child.stdin.take()
+    {
+        use std::io::Write;
+        let written = stdin.write_all(input).and_then(|_| stdin.flush());
+        drop(stdin);
+        if let Err(error) = written {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow::Error::new(error)
+                .context(format!("failed to write input to {label}"))
+                .into());
+        }
+    }
+    let stdout = child.stdout.take().map(read_pipe);
+    let stderr = child.stderr.take().map(read_pipe);
+
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("failed to wait on {label}"))?
+        {
+            break status;
+        }
+        if interrupted.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            eprintln!("interrupted: stopped {label}");
+            return Err(Error::Interrupted);
+        }
+        if let Some(limit) = limit
+            && started.elapsed() >= limit
+        {
+            let _ = child.kill();
+            let _ = child.wait();
+            let secs = limit.as_secs();
+            output::error(format!("{label} did not respond in {secs}s"));
+            output::hint(format!(
+                "check that podman works ('podman info'; restart the podman machine if its connection is stale), or raise {} (0 disables the limit)",
+                timeout.env_name()
+            ));
+            return Err(Error::message(format!(
+                "podman did not respond in {secs}s"
+            )));
+        }
+        thread::sleep(CHILD_POLL_INTERVAL);
+    };
+
+    let join = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        reader.and_then(|reader| reader.join().ok()).unwrap_or_default()
+    };
+    Ok(Output {
+        status,
+        stdout: join(stdout),
+        stderr: join(stderr),
+    })
+}
+
+fn read_pipe(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = pipe.read_to_end(&mut buffer);
+        buffer
+    })
+}
+
+/// `podman network exists` for messages: the program and its leading subcommand words.
+fn command_label(cmd: &Command) -> String {
+    let mut label = cmd.get_program().to_string_lossy().into_owned();
+    for arg in cmd
+        .get_args()
+        .map(|arg| arg.to_string_lossy())
+        .take_while(|arg| !arg.starts_with('-'))
+        .take(2)
+    {
+        label.push(' ');
+        label.push_str(&arg);
+    }
+    label
+}
+
 fn command_exists(command: &str) -> bool {
-    env::var_os("PATH").map_or(false, |paths| {
+    env::var_os("PATH").is_some_and(|paths| {
         env::split_paths(&paths).any(|path| {
             let candidate = path.join(command);
             candidate.is_file()
@@ -289,6 +616,49 @@ pub struct RunningProjectNetwork {
     pub network: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PodState {
+    pub name: String,
+    pub status: String,
+    pub addresses: Vec<NetworkAddress>,
+    pub containers: Vec<ContainerState>,
+}
+
+impl PodState {
+    pub fn is_running(&self) -> bool {
+        self.status.eq_ignore_ascii_case("running")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkAddress {
+    pub network: String,
+    pub ip: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerState {
+    pub name: String,
+    pub state: String,
+    pub image: String,
+    pub restarts: u32,
+    /// Liveness probe result (`healthy`, `unhealthy`, `starting`), if the container has one.
+    pub health: Option<String>,
+}
+
+/// Restart count at which a container is reported as crash-looping.
+pub const CRASH_LOOP_RESTARTS: u32 = 3;
+
+impl ContainerState {
+    pub fn is_crash_looping(&self) -> bool {
+        self.restarts >= CRASH_LOOP_RESTARTS
+    }
+
+    pub fn is_unhealthy(&self) -> bool {
+        self.health.as_deref() == Some("unhealthy")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExposeProxy {
     pub id: String,
@@ -299,7 +669,10 @@ pub struct ExposeProxy {
 }
 
 pub fn list_running_projects() -> Result<Vec<RunningProject>> {
-    let items = list_running_pod_items()?;
+    Ok(running_projects_from(list_running_pod_items()?))
+}
+
+pub(crate) fn running_projects_from(items: Vec<RunningPodItem>) -> Vec<RunningProject> {
     let mut projects: HashMap<(String, String), usize> = HashMap::new();
     for item in items {
         let key = (item.name, item.project_root);
@@ -322,15 +695,23 @@ pub fn list_running_projects() -> Result<Vec<RunningProject>> {
             .then_with(|| a.project_root.cmp(&b.project_root))
     });
 
-    Ok(results)
+    results
 }
 
 pub fn list_running_project_networks() -> Result<Vec<RunningProjectNetwork>> {
-    let items = list_running_pod_items()?;
+    running_project_networks_from(list_running_pod_items()?, inspect_pool_network_for_pod)
+}
+
+/// Groups running pods by project, with `pool_network` naming the `cladding-N` network a pod
+/// is attached to.
+pub(crate) fn running_project_networks_from(
+    items: Vec<RunningPodItem>,
+    pool_network: impl Fn(&str) -> Result<Option<String>>,
+) -> Result<Vec<RunningProjectNetwork>> {
     let mut networks: HashMap<(String, String), String> = HashMap::new();
 
     for item in items {
-        let network = inspect_pool_network_for_pod(&item.pod_id)?;
+        let network = pool_network(&item.pod_id)?;
         let Some(network) = network else {
             continue;
         };
@@ -338,10 +719,10 @@ pub fn list_running_project_networks() -> Result<Vec<RunningProjectNetwork>> {
         let key = (item.name.clone(), item.project_root.clone());
         if let Some(existing) = networks.get(&key) {
             if existing != &network {
-                eprintln!(
-                    "error: running project '{}' has pods on multiple cladding networks",
+                output::error(format!(
+                    "running project '{}' has pods on multiple cladding networks",
                     item.name
-                );
+                ));
                 eprintln!("project_root: {}", item.project_root);
                 eprintln!("networks: {existing}, {network}");
                 return Err(Error::message("inconsistent active network"));
@@ -370,11 +751,12 @@ pub fn list_running_project_networks() -> Result<Vec<RunningProjectNetwork>> {
 }
 
 pub fn list_project_expose_proxies(
+    engine: Engine,
     project_name: &str,
     project_root: &str,
     include_stopped: bool,
 ) -> Result<Vec<ExposeProxy>> {
-    let items = list_expose_proxy_items(project_name, include_stopped)?;
+    let items = list_expose_proxy_items(engine, project_name, include_stopped)?;
     let mut results = Vec::new();
 
     for item in items {
@@ -397,38 +779,257 @@ pub fn list_project_expose_proxies(
     Ok(results)
 }
 
-pub fn podman_container_exists(container_name: &str) -> Result<bool> {
-    let status = Command::new("podman")
-        .args(["container", "exists", container_name])
-        .status()
-        .with_context(|| "failed to run podman container exists")?;
+/// State of a podman pod and its containers; docker has no pods, see
+/// [`crate::docker::docker_inspect_pod_state`].
+pub fn podman_inspect_pod_state(pod_name: &str) -> Result<Option<PodState>> {
+    let status = object_exists_status(Engine::Podman, "pod", pod_name)?;
+    match status.code() {
+        Some(0) => {}
+        Some(1) => return Ok(None),
+        _ => {
+            output::error(format!("failed to check whether pod exists: {pod_name}"));
+            return Err(Error::message("podman pod exists failed"));
+        }
+    }
+
+    let inspect = engine_command()
+        .args(["pod", "inspect", pod_name, "--format", "json"])
+        .bounded_output()?;
+    if !inspect.status.success() {
+        return ensure_success_output(&inspect, "podman pod inspect").map(|_| None);
+    }
+    let inspect_stdout = String::from_utf8_lossy(&inspect.stdout);
+    let parsed: Value = serde_json::from_str(&inspect_stdout)
+        .with_context(|| "failed to parse podman pod inspect json output")?;
+    let pod_status = parse_pod_status(&parsed).unwrap_or_else(|| "unknown".to_string());
+
+    let mut addresses = Vec::new();
+    if let Some(infra_id) = find_infra_container_id(&parsed) {
+        let inspect_infra = engine_command()
+            .args(["container", "inspect", &infra_id, "--format", "json"])
+            .bounded_output()?;
+        if !inspect_infra.status.success() {
+            return ensure_success_output(&inspect_infra, "podman container inspect")
+                .map(|_| None);
+        }
+        let inspect_infra_stdout = String::from_utf8_lossy(&inspect_infra.stdout);
+        let parsed: Value = serde_json::from_str(&inspect_infra_stdout)
+            .with_context(|| "failed to parse podman container inspect json output")?;
+        addresses = parse_network_addresses(&parsed);
+    }
+
+    let ps = engine_command()
+        .args([
+            "ps",
+            "-a",
+            "--filter",
+            &format!("pod={pod_name}"),
+            "--format",
+            "json",
+        ])
+        .bounded_output()?;
+    if !ps.status.success() {
+        return ensure_success_output(&ps, "podman ps").map(|_| None);
+    }
+    let ps_stdout = String::from_utf8_lossy(&ps.stdout);
+    let parsed: Value =
+        serde_json::from_str(&ps_stdout).with_context(|| "failed to parse podman ps json output")?;
+
+    Ok(Some(PodState {
+        name: pod_name.to_string(),
+        status: pod_status,
+        addresses,
+        containers: parse_pod_containers(&parsed),
+    }))
+}
+
+pub fn podman_image_exists(engine: Engine, image: &str) -> Result<bool> {
+    Ok(object_exists_status(engine, "image", image)?.success())
+}
+
+/// What `image inspect` reports about a local image.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageDetails {
+    /// Image id, without docker's `sha256:` prefix.
+    pub id: String,
+    /// Registry digest of a pulled image; images built locally have none.
+    pub digest: Option<String>,
+    pub size_bytes: u64,
+    /// Creation time as the engine prints it.
+    pub created: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Details of `image`, or `None` when there is no such local image.
+pub fn podman_inspect_image(engine: Engine, image: &str) -> Result<Option<ImageDetails>> {
+    if !podman_image_exists(engine, image)? {
+        return Ok(None);
+    }
+    let output = engine_command()
+        .args(["image", "inspect", image])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman image inspect")?;
+    let parsed: Value = serde_json::from_slice(&output.stdout)
+        .with_context(|| "failed to parse image inspect json output")?;
+    Ok(Some(parse_image_details(&parsed)))
+}
+
+/// Both engines print `Id`, `RepoDigests`, `Size`, `Created` and `Config.Labels`.
+fn parse_image_details(value: &Value) -> ImageDetails {
+    let image = value.as_array().and_then(|items| items.first()).unwrap_or(value);
+    let id = image.get("Id").and_then(Value::as_str).unwrap_or_default();
+    let digest = image
+        .get("RepoDigests")
+        .and_then(Value::as_array)
+        .and_then(|digests| digests.first())
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.split_once('@'))
+        .map(|(_, digest)| digest.to_string());
+    let labels = image
+        .get("Config")
+        .and_then(|config| config.get("Labels"))
+        .map(parse_labels)
+        .unwrap_or_default();
+    ImageDetails {
+        id: id.strip_prefix("sha256:").unwrap_or(id).to_string(),
+        digest,
+        size_bytes: image.get("Size").and_then(Value::as_u64).unwrap_or_default(),
+        created: image
+            .get("Created")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        labels: labels.into_iter().collect(),
+    }
+}
+
+/// Force-removes the named pods' containers, as `cladding destroy` does.
+pub fn podman_force_remove(names: &[&str]) -> Result<()> {
+    let status = engine_command()
+        .arg("rm")
+        .arg("-f")
+        .args(names)
+        .bounded_status()?;
+    ensure_success(status, "podman rm")
+}
+
+pub fn podman_container_exists(engine: Engine, container_name: &str) -> Result<bool> {
+    let status = object_exists_status(engine, "container", container_name)?;
 
     match status.code() {
         Some(0) => Ok(true),
         Some(1) => Ok(false),
         _ => {
-            eprintln!("error: failed to check whether container exists: {container_name}");
+            output::error(format!("failed to check whether container exists: {container_name}"));
             Err(Error::message("podman container exists failed"))
         }
     }
 }
 
+/// Returns the last `lines` lines of a container's combined stdout and stderr.
+pub fn podman_container_log_tail(container_name: &str, lines: usize) -> Result<String> {
+    let output = engine_command()
+        .args(["logs", "--tail", &lines.to_string(), container_name])
+        .bounded_output()?;
+    ensure_success(output.status, "podman logs")?;
+    let mut tail = String::from_utf8_lossy(&output.stdout).into_owned();
+    tail.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(tail)
+}
+
+/// Sends `method` to mcp-run's `path` from inside the sandbox-app container and returns the
+/// response body.
+pub fn podman_sandbox_mcp_request(
+    container_name: &str,
+    method: &str,
+    path: &str,
+) -> Result<String> {
+    let output = engine_command()
+        .arg("exec")
+        .arg(container_name)
+        .args(sandbox_mcp_request_command(method, path))
+        .bounded_output()?;
+    ensure_success_output(&output, "podman exec curl")?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the first token set in the container, from `<NAME>` or the file named by
+/// `<NAME>_FILE`, and sends it to curl on stdin so it never shows up in `ps`.
+const SANDBOX_MCP_REQUEST_SCRIPT: &str = r#"method=$1 url=$2
+shift 2
+token=
+for name; do
+  eval "token=\${$name:-} file=\${${name}_FILE:-}"
+  if [ -z "$token" ] && [ -n "$file" ]; then
+    read -r token < "$file" || true
+  fi
+  [ -n "$token" ] && break
+done
+if [ -z "$token" ]; then
+  exec curl -fsS --noproxy '*' -m 2 -X "$method" "$url"
+fi
+printf 'Authorization: Bearer %s\n' "$token" |
+  curl -fsS --noproxy '*' -m 2 -H @- -X "$method" "$url"
+"#;
+
+/// Command run in the sandbox container for one mcp-run request. `/admin` takes
+/// `MCP_ADMIN_TOKEN`, falling back to `MCP_AUTH_TOKEN` as mcp-run does; every other route
+/// takes `MCP_AUTH_TOKEN`.
+fn sandbox_mcp_request_command(method: &str, path: &str) -> Vec<String> {
+    // `/admin` is only served on its own loopback listener.
+    let (base, tokens): (_, &[&str]) = if path.starts_with("/admin/") {
+        (SANDBOX_LOCAL_ADMIN_URL, &["MCP_ADMIN_TOKEN", "MCP_AUTH_TOKEN"])
+    } else {
+        (SANDBOX_LOCAL_MCP_URL, &["MCP_AUTH_TOKEN"])
+    };
+    let mut command = ["sh", "-c", SANDBOX_MCP_REQUEST_SCRIPT, "sh", method]
+        .map(String::from)
+        .to_vec();
+    command.push(format!("{base}{path}"));
+    command.extend(tokens.iter().map(|name| name.to_string()));
+    command
+}
+
+/// Login shell of the container's default user from its passwd entry, if it has a usable one.
+pub fn podman_container_login_shell(container_name: &str) -> Option<String> {
+    let output = engine_command()
+        .args([
+            "exec",
+            container_name,
+            "sh",
+            "-c",
+            "getent passwd \"$(id -u)\" | cut -d: -f7",
+        ])
+        .bounded_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_login_shell(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_login_shell(output: &str) -> Option<String> {
+    let shell = output.lines().next()?.trim();
+    let usable = shell.starts_with('/')
+        && !shell.ends_with("/nologin")
+        && !shell.ends_with("/false");
+    usable.then(|| shell.to_string())
+}
+
 pub fn podman_remove_containers(
     container_ids: &[String],
     force: bool,
     ignore_missing: bool,
 ) -> Result<()> {
     for container_id in container_ids {
-        let mut cmd = Command::new("podman");
+        let mut cmd = engine_command();
         cmd.arg("rm");
         if force {
             cmd.arg("-f");
         }
         cmd.arg(container_id);
 
-        let output = cmd
-            .output()
-            .with_context(|| "failed to run podman rm")?;
+        let output = cmd.bounded_output()?;
 
         if output.status.success() {
             continue;
@@ -444,11 +1045,112 @@ pub fn podman_remove_containers(
     Ok(())
 }
 
+pub fn podman_list_volumes() -> Result<Vec<String>> {
+    let output = engine_command()
+        .args(["volume", "ls", "--format", "{{.Name}}"])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman volume ls")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+pub fn podman_create_volume(name: &str) -> Result<()> {
+    let output = engine_command()
+        .args(["volume", "create", name])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman volume create")
+}
+
+/// Removes a volume; the engine refuses while a container still uses it.
+pub fn podman_remove_volume(name: &str) -> Result<()> {
+    let output = engine_command()
+        .args(["volume", "rm", name])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman volume rm")
+}
+
+/// Names of the containers attached to `network`, stopped ones included.
+pub fn podman_network_containers(network: &str) -> Result<Vec<String>> {
+    let output = engine_command()
+        .args([
+            "ps",
+            "-a",
+            "--filter",
+            &format!("network={network}"),
+            "--format",
+            "{{.Names}}",
+        ])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman ps")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+pub fn podman_remove_network(network: &str) -> Result<()> {
+    let output = engine_command()
+        .args(["network", "rm", network])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman network rm")
+}
+
+/// `volume inspect` output as the engine prints it, a JSON array with one entry.
+pub fn podman_inspect_volume(name: &str) -> Result<String> {
+    let output = engine_command()
+        .args(["volume", "inspect", name])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman volume inspect")?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Bytes used by each volume, from `system df -v`, which both engines can print as one JSON
+/// object with a `Volumes` list. Podman names a volume `VolumeName` and counts bytes; docker
+/// says `Name` and prints sizes such as `1.2GB`.
+pub fn podman_volume_sizes() -> Result<BTreeMap<String, u64>> {
+    let output = engine_command()
+        .args(["system", "df", "-v", "--format", "json"])
+        .bounded_output()?;
+    ensure_success_output(&output, "podman system df")?;
+    let parsed: Value = serde_json::from_slice(&output.stdout)
+        .with_context(|| "failed to parse system df json output")?;
+    Ok(parse_volume_sizes(&parsed))
+}
+
+fn parse_volume_sizes(value: &Value) -> BTreeMap<String, u64> {
+    let volumes = value
+        .get("Volumes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    volumes
+        .iter()
+        .filter_map(|volume| {
+            let name = volume
+                .get("VolumeName")
+                .or_else(|| volume.get("Name"))
+                .and_then(Value::as_str)?;
+            let size = match volume.get("Size")? {
+                Value::String(size) => parse_size(size)?,
+                size => size.as_u64()?,
+            };
+            Some((name.to_string(), size))
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
-struct RunningPodItem {
-    pod_id: String,
-    name: String,
-    project_root: String,
+pub(crate) struct RunningPodItem {
+    /// The pod's id, or with docker the id of the container standing in for the pod.
+    pub(crate) pod_id: String,
+    pub(crate) name: String,
+    pub(crate) project_root: String,
 }
 
 #[derive(Debug, Clone)]
@@ -459,7 +1161,7 @@ struct ExposeProxyItem {
 }
 
 fn list_running_pod_items() -> Result<Vec<RunningPodItem>> {
-    let output = Command::new("podman")
+    let output = engine_command()
         .args([
             "pod",
             "ps",
@@ -470,8 +1172,7 @@ fn list_running_pod_items() -> Result<Vec<RunningPodItem>> {
             "--format",
             "json",
         ])
-        .output()
-        .with_context(|| "failed to run podman pod ps")?;
+        .bounded_output()?;
 
     if !output.status.success() {
         return ensure_success_output(&output, "podman pod ps").map(|_| Vec::new());
@@ -514,8 +1215,12 @@ fn list_running_pod_items() -> Result<Vec<RunningPodItem>> {
     Ok(pods)
 }
 
-fn list_expose_proxy_items(project_name: &str, include_stopped: bool) -> Result<Vec<ExposeProxyItem>> {
-    let mut cmd = Command::new("podman");
+fn list_expose_proxy_items(
+    engine: Engine,
+    project_name: &str,
+    include_stopped: bool,
+) -> Result<Vec<ExposeProxyItem>> {
+    let mut cmd = engine_command();
     cmd.arg("ps");
     if include_stopped {
         cmd.arg("-a");
@@ -526,29 +1231,25 @@ fn list_expose_proxy_items(project_name: &str, include_stopped: bool) -> Result<
         "--filter",
         &format!("label=cladding={project_name}"),
         "--format",
-        "json",
+        ps_json_format(engine),
     ]);
 
-    let output = cmd
-        .output()
-        .with_context(|| "failed to run podman ps for expose proxies")?;
+    let output = cmd.bounded_output()?;
 
     if !output.status.success() {
         return ensure_success_output(&output, "podman ps").map(|_| Vec::new());
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let parsed: Value =
-        serde_json::from_str(&stdout).with_context(|| "failed to parse podman ps json output")?;
+    let parsed = parse_ps_json(&String::from_utf8_lossy(&output.stdout))
+        .with_context(|| "failed to parse ps json output")?;
 
     Ok(parse_expose_proxy_items(&parsed))
 }
 
 fn inspect_pool_network_for_pod(pod_id: &str) -> Result<Option<String>> {
-    let inspect = Command::new("podman")
+    let inspect = engine_command()
         .args(["pod", "inspect", pod_id, "--format", "json"])
-        .output()
-        .with_context(|| "failed to inspect running pod")?;
+        .bounded_output()?;
     if !inspect.status.success() {
         return ensure_success_output(&inspect, "podman pod inspect").map(|_| None);
     }
@@ -559,11 +1260,14 @@ fn inspect_pool_network_for_pod(pod_id: &str) -> Result<Option<String>> {
     let Some(infra_id) = find_infra_container_id(&parsed) else {
         return Ok(None);
     };
+    inspect_pool_network_for_container(&infra_id)
+}
 
-    let inspect_infra = Command::new("podman")
-        .args(["container", "inspect", &infra_id, "--format", "json"])
-        .output()
-        .with_context(|| "failed to inspect pod infra container")?;
+/// The `cladding-N` network among those the container is attached to.
+pub(crate) fn inspect_pool_network_for_container(container_id: &str) -> Result<Option<String>> {
+    let inspect_infra = engine_command()
+        .args(["container", "inspect", container_id])
+        .bounded_output()?;
     if !inspect_infra.status.success() {
         return ensure_success_output(&inspect_infra, "podman container inspect").map(|_| None);
     }
@@ -614,7 +1318,70 @@ fn find_networks_object(value: &Value) -> Option<&serde_json::Map<String, Value>
         .and_then(Value::as_object)
 }
 
-fn parse_labels(value: &Value) -> HashMap<String, String> {
+fn parse_pod_status(value: &Value) -> Option<String> {
+    if let Some(items) = value.as_array() {
+        return items.iter().find_map(parse_pod_status);
+    }
+    get_json_string(value, &["State", "Status"])
+}
+
+pub(crate) fn parse_network_addresses(value: &Value) -> Vec<NetworkAddress> {
+    let Some(networks) = find_networks_object(value) else {
+        return Vec::new();
+    };
+
+    let mut addresses: Vec<NetworkAddress> = networks
+        .iter()
+        .filter_map(|(network, settings)| {
+            let ip = get_json_string(settings, &["IPAddress"])?;
+            Some(NetworkAddress {
+                network: network.to_string(),
+                ip,
+            })
+        })
+        .collect();
+    addresses.sort_by(|a, b| a.network.cmp(&b.network));
+    addresses
+}
+
+fn parse_pod_containers(value: &Value) -> Vec<ContainerState> {
+    let Some(items) = value.as_array() else {
+        return Vec::new();
+    };
+
+    let mut containers = Vec::new();
+    for item in items {
+        if item.get("IsInfra").and_then(Value::as_bool) == Some(true) {
+            continue;
+        }
+        let Some(name) = get_json_name(item) else {
+            continue;
+        };
+        containers.push(ContainerState {
+            name,
+            state: get_json_string(item, &["State", "Status"])
+                .unwrap_or_else(|| "unknown".to_string()),
+            image: get_json_string(item, &["Image"]).unwrap_or_default(),
+            restarts: item
+                .get("Restarts")
+                .and_then(Value::as_u64)
+                .and_then(|restarts| u32::try_from(restarts).ok())
+                .unwrap_or(0),
+            health: get_json_string(item, &["Status"]).and_then(|status| parse_health(&status)),
+        });
+    }
+    containers.sort_by(|a, b| a.name.cmp(&b.name));
+    containers
+}
+
+/// Extracts the health suffix podman appends to a status, e.g. `Up 2 minutes (healthy)`.
+fn parse_health(status: &str) -> Option<String> {
+    let (_, suffix) = status.trim_end().rsplit_once('(')?;
+    let health = suffix.strip_suffix(')')?;
+    matches!(health, "healthy" | "unhealthy" | "starting").then(|| health.to_string())
+}
+
+pub(crate) fn parse_labels(value: &Value) -> HashMap<String, String> {
     let mut labels = HashMap::new();
     match value {
         Value::Object(map) => {
@@ -693,7 +1460,28 @@ fn parse_expose_proxy_item(value: &Value) -> Option<ExposeProxyItem> {
     })
 }
 
-fn get_json_string(value: &Value, keys: &[&str]) -> Option<String> {
+/// `--format` for `ps` as JSON: podman prints one array, docker one object per line.
+pub(crate) fn ps_json_format(engine: Engine) -> &'static str {
+    match engine {
+        Engine::Podman => "json",
+        Engine::Docker => "{{json .}}",
+    }
+}
+
+/// Parses either `ps` JSON form into an array.
+pub(crate) fn parse_ps_json(stdout: &str) -> serde_json::Result<Value> {
+    if stdout.trim_start().starts_with('[') {
+        return serde_json::from_str(stdout);
+    }
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<serde_json::Result<Vec<Value>>>()
+        .map(Value::Array)
+}
+
+pub(crate) fn get_json_string(value: &Value, keys: &[&str]) -> Option<String> {
     for key in keys {
         let Some(raw) = value.get(*key) else {
             continue;
@@ -705,7 +1493,7 @@ fn get_json_string(value: &Value, keys: &[&str]) -> Option<String> {
     None
 }
 
-fn get_json_name(value: &Value) -> Option<String> {
+pub(crate) fn get_json_name(value: &Value) -> Option<String> {
     for key in ["Names", "Name"] {
         let Some(raw) = value.get(key) else {
             continue;
@@ -735,6 +1523,71 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn network_inspect_is_parsed_for_both_engines() {
+        let podman = json!([{"subnets": [{"subnet": "10.90.3.0/24"}], "internal": true}]);
+        let docker = json!([{"IPAM": {"Config": [{"Subnet": "10.90.3.0/24"}]}, "Internal": false}]);
+        assert_eq!(parse_network_subnets(&podman), vec!["10.90.3.0/24"]);
+        assert_eq!(parse_network_subnets(&docker), vec!["10.90.3.0/24"]);
+        assert!(parse_network_internal(&podman));
+        assert!(!parse_network_internal(&docker));
+
+        let lines = "{\"ID\":\"a\"}\n{\"ID\":\"b\"}\n";
+        assert_eq!(parse_ps_json(lines).expect("lines"), json!([{"ID": "a"}, {"ID": "b"}]));
+        assert_eq!(parse_ps_json("[]").expect("array"), json!([]));
+    }
+
+    #[test]
+    fn volume_sizes_are_parsed_for_both_engines() {
+        let podman = json!({
+            "Images": [],
+            "Volumes": [{"VolumeName": "demo-cache", "Links": 1, "Size": 4096}]
+        });
+        assert_eq!(
+            parse_volume_sizes(&podman),
+            BTreeMap::from([("demo-cache".to_string(), 4096)])
+        );
+        let docker = json!({
+            "Volumes": [
+                {"Name": "demo-cache", "Links": "1", "Size": "1.5MB"},
+                {"Name": "demo-unknown", "Size": "N/A"}
+            ]
+        });
+        assert_eq!(
+            parse_volume_sizes(&docker),
+            BTreeMap::from([("demo-cache".to_string(), 1_500_000)])
+        );
+        assert!(parse_volume_sizes(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn image_inspect_is_parsed_for_both_engines() {
+        let podman = json!([{
+            "Id": "0123456789abcdef",
+            "RepoDigests": [],
+            "Size": 1048576,
+            "Created": "2026-01-02T03:04:05.678Z",
+            "Config": {"Labels": {"io.cladding.uid": "1000", "io.cladding.gid": "1000"}}
+        }]);
+        let details = parse_image_details(&podman);
+        assert_eq!(details.id, "0123456789abcdef");
+        assert_eq!(details.digest, None);
+        assert_eq!(details.size_bytes, 1048576);
+        assert_eq!(details.labels.get(IMAGE_UID_LABEL).map(String::as_str), Some("1000"));
+
+        let docker = json!([{
+            "Id": "sha256:fedcba9876543210",
+            "RepoDigests": ["docker.io/ubuntu/squid@sha256:aaaa"],
+            "Size": 2048,
+            "Created": "2026-01-02T03:04:05Z",
+            "Config": {"Labels": null}
+        }]);
+        let details = parse_image_details(&docker);
+        assert_eq!(details.id, "fedcba9876543210");
+        assert_eq!(details.digest.as_deref(), Some("sha256:aaaa"));
+        assert!(details.labels.is_empty());
+    }
+
     #[test]
     fn parse_labels_supports_string_and_object_forms() {
         let string_labels = parse_labels(&Value::String(
@@ -808,6 +1661,45 @@ mod tests {
         assert_eq!(item.proxy.status, "running");
     }
 
+    #[test]
+    fn parse_timeout_secs_treats_zero_as_unlimited() {
+        assert_eq!(parse_timeout_secs(" 45 "), Some(Some(Duration::from_secs(45))));
+        assert_eq!(parse_timeout_secs("0"), Some(None));
+        assert_eq!(parse_timeout_secs("1m"), None);
+    }
+
+    #[test]
+    fn run_bounded_captures_output_and_kills_hung_commands() {
+        let mut echo = Command::new("sh");
+        echo.args(["-c", "cat; echo err >&2"]);
+        let output = run_bounded(
+            &mut echo,
+            Some(b"hello"),
+            true,
+            PodmanTimeout::Query,
+            Some(Duration::from_secs(10)),
+        )
+        .expect("sh runs");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hello");
+        assert_eq!(output.stderr, b"err\n");
+
+        let mut hung = Command::new("sleep");
+        hung.arg("30");
+        let started = Instant::now();
+        let error = run_bounded(
+            &mut hung,
+            None,
+            true,
+            PodmanTimeout::Query,
+            Some(Duration::from_secs(1)),
+        )
+        .expect_err("sleep times out");
+        assert_eq!(error.to_string(), "podman did not respond in 1s");
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(command_label(&hung), "sleep 30");
+    }
+
     #[test]
     fn remove_output_is_missing_container_matches_expected_errors() {
         let output = Output {
@@ -819,4 +1711,163 @@ mod tests {
         };
         assert!(remove_output_is_missing_container(&output));
     }
+
+    #[test]
+    fn parse_network_internal_reads_inspect_flag() {
+        assert!(parse_network_internal(&json!([{ "name": "cladding-1", "internal": true }])));
+        assert!(!parse_network_internal(&json!([{ "name": "cladding-1", "internal": false }])));
+        assert!(!parse_network_internal(&json!([{ "name": "cladding-1" }])));
+    }
+
+    #[test]
+    fn parse_podman_info_and_version() {
+        let info = json!({
+            "host": {
+                "security": { "rootless": true },
+                "idMappings": {
+                    "uidmap": [
+                        { "container_id": 0, "host_id": 1000, "size": 1 },
+                        { "container_id": 1, "host_id": 100000, "size": 65536 }
+                    ]
+                }
+            },
+            "version": { "Version": "4.9.3" }
+        });
+        assert_eq!(
+            parse_podman_host_info(&info),
+            Some(PodmanHostInfo {
+                version: "4.9.3".to_string(),
+                rootless: true,
+                uid_map_entries: 2,
+            })
+        );
+        assert_eq!(parse_podman_host_info(&json!({ "host": {} })), None);
+
+        assert_eq!(parse_podman_version("4.9.3"), Some((4, 9)));
+        assert_eq!(parse_podman_version("5.0-dev"), Some((5, 0)));
+        assert_eq!(parse_podman_version("unknown"), None);
+        assert!(parse_podman_version("4.3.1").unwrap() < MIN_PODMAN_VERSION);
+    }
+
+    #[test]
+    fn parse_login_shell_rejects_unusable_entries() {
+        assert_eq!(parse_login_shell("/bin/zsh\n").as_deref(), Some("/bin/zsh"));
+        assert_eq!(parse_login_shell("/usr/sbin/nologin\n"), None);
+        assert_eq!(parse_login_shell("/bin/false"), None);
+        assert_eq!(parse_login_shell(""), None);
+        assert_eq!(parse_login_shell("bash"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sandbox_mcp_requests_send_the_matching_bearer_token() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "cladding-mcp-request-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let curl = dir.join("curl");
+        fs::write(&curl, "#!/bin/sh\necho \"$*\"\ncat\n").unwrap();
+        fs::set_permissions(&curl, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("admin-token"), "  admin-secret \n").unwrap();
+        let path = format!("{}:{}", dir.display(), std::env::var("PATH").unwrap_or_default());
+
+        let run = |method: &str, route: &str, env: &[(&str, String)]| {
+            let command = sandbox_mcp_request_command(method, route);
+            let output = Command::new(&command[0])
+                .args(&command[1..])
+                .env_clear()
+                .env("PATH", &path)
+                .envs(env.iter().map(|(name, value)| (name, value)))
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{output:?}");
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let tokens = [
+            ("MCP_AUTH_TOKEN", "auth-secret".to_string()),
+            ("MCP_ADMIN_TOKEN_FILE", dir.join("admin-token").display().to_string()),
+        ];
+
+        let metrics = run("GET", "/metrics", &tokens);
+        assert!(metrics.contains("-H @- -X GET http://"), "{metrics}");
+        assert!(metrics.ends_with("/metrics\nAuthorization: Bearer auth-secret\n"), "{metrics}");
+        let admin = run("POST", "/admin/reload", &tokens);
+        assert!(admin.ends_with("/admin/reload\nAuthorization: Bearer admin-secret\n"), "{admin}");
+        let fallback = run("POST", "/admin/reload", &tokens[..1]);
+        assert!(fallback.ends_with("Authorization: Bearer auth-secret\n"), "{fallback}");
+        let open = run("GET", "/version", &[]);
+        assert!(!open.contains("Authorization") && !open.contains("-H"), "{open}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parse_pod_state_fields_from_inspect_and_ps_output() {
+        let pod_inspect = json!([{ "State": "Degraded", "InfraContainerID": "infra1" }]);
+        assert_eq!(parse_pod_status(&pod_inspect).as_deref(), Some("Degraded"));
+
+        let infra_inspect = json!([{
+            "NetworkSettings": {
+                "Networks": {
+                    "cladding-3": { "IPAddress": "10.90.3.2" },
+                    "podman": { "IPAddress": "" }
+                }
+            }
+        }]);
+        assert_eq!(
+            parse_network_addresses(&infra_inspect),
+            vec![NetworkAddress {
+                network: "cladding-3".into(),
+                ip: "10.90.3.2".into(),
+            }]
+        );
+
+        let ps = json!([
+            { "Names": ["abc-infra"], "State": "running", "Image": "pause", "IsInfra": true },
+            {
+                "Names": ["demo-proxy-pod-proxy"],
+                "State": "exited",
+                "Image": "localhost/cladding-default:latest",
+                "IsInfra": false
+            },
+            {
+                "Names": ["demo-sandbox-pod-sandbox-app"],
+                "State": "running",
+                "Status": "Up 2 seconds (unhealthy)",
+                "Restarts": 4,
+                "Image": "localhost/cladding-default:latest"
+            }
+        ]);
+        let containers = parse_pod_containers(&ps);
+        assert_eq!(
+            containers,
+            vec![
+                ContainerState {
+                    name: "demo-proxy-pod-proxy".into(),
+                    state: "exited".into(),
+                    image: "localhost/cladding-default:latest".into(),
+                    restarts: 0,
+                    health: None,
+                },
+                ContainerState {
+                    name: "demo-sandbox-pod-sandbox-app".into(),
+                    state: "running".into(),
+                    image: "localhost/cladding-default:latest".into(),
+                    restarts: 4,
+                    health: Some("unhealthy".into()),
+                },
+            ]
+        );
+        assert!(!containers[0].is_crash_looping());
+        assert!(containers[1].is_crash_looping());
+        assert!(containers[1].is_unhealthy());
+    }
 }
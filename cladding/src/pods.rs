@@ -1,18 +1,29 @@
-use crate::config::{Config, MountConfig};
+use crate::config::{Config, MountConfig, SidecarConfig};
+use crate::error::{Error, Result};
+use crate::excludes::{ExcludedPath, resolve_workspace_excludes};
+use crate::fs_utils::engine_host_path;
 use crate::network::NetworkSettings;
 use serde::Deserialize;
 use serde_yaml::{Mapping, Value};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 const PODS_YAML: &str = include_str!("../../pods.yaml");
-
+/// Where pods.yaml mounts the workspace (`PROJECT_ROOT/..`) in `cli-app` and `sandbox-app`.
+const WORKSPACE_MOUNT_PATH: &str = "/home/user/workspace";
+const EXCLUDED_DIR_VOLUME: &str = "workspace-exclude-dir";
+const EXCLUDED_FILE_VOLUME: &str = "workspace-exclude-file";
+
+/// pods.yaml with `config` and `network_settings` applied. Fails when a substituted value,
+/// such as an image name, leaves the template unparseable, rather than returning it without
+/// the mounts, env and `workspace_excludes` masks applied on top.
 pub fn render_pods_yaml(
     project_root: &Path,
     config: &Config,
     network_settings: &NetworkSettings,
-) -> String {
+) -> Result<String> {
     let rendered = PODS_YAML
-        .replace("PROJECT_ROOT", &project_root.display().to_string())
+        .replace("PROJECT_ROOT", &engine_host_path(project_root))
         .replace("CLADDING_NAME", &config.name)
         .replace(
             "REPLACE_PROXY_POD_NAME",
@@ -27,15 +38,14 @@ pub fn render_pods_yaml(
         .replace("REPLACE_CLI_IMAGE", &config.cli_image)
         .replace("REPLACE_PROXY_IP", &network_settings.proxy_ip)
         .replace("REPLACE_SANDBOX_IP", &network_settings.sandbox_ip)
-        .replace("REPLACE_CLI_IP", &network_settings.cli_ip);
+        .replace("REPLACE_CLI_IP", &network_settings.cli_ip)
+        .replace("REPLACE_ISOLATED", if config.isolated { "1" } else { "0" })
+        .replace("REPLACE_AGENT_IPS", &agent_ips(network_settings));
 
-    let mut docs = match serde_yaml::Deserializer::from_str(&rendered)
-        .map(|doc| Value::deserialize(doc).map_err(|_| ()))
+    let mut docs = serde_yaml::Deserializer::from_str(&rendered)
+        .map(Value::deserialize)
         .collect::<std::result::Result<Vec<_>, _>>()
-    {
-        Ok(docs) => docs,
-        Err(_) => return rendered,
-    };
+        .map_err(|error| Error::message(format!("rendered pods.yaml is not valid YAML: {error}")))?;
 
     if !config.mounts.is_empty() {
         let custom_mounts = build_custom_mounts(config);
@@ -44,12 +54,167 @@ pub fn render_pods_yaml(
         }
     }
 
+    // Before agents, whose cli pods are copies of the project's.
+    for (container, env) in [("cli-app", &config.env), ("sandbox-app", &config.sandbox_env)] {
+        if !env.is_empty() {
+            for doc in &mut docs {
+                apply_container_env(doc, container, env);
+            }
+        }
+    }
+
+    // Before sidecars, which stay with the project's own cli pod.
+    if !network_settings.agent_pods.is_empty() {
+        apply_agents(&mut docs, project_root, network_settings);
+    }
+
+    if !config.sidecars.is_empty() {
+        for doc in &mut docs {
+            apply_sidecars(doc, config, &network_settings.cli_pod_name);
+        }
+    }
+
+    if !config.workspace_excludes.is_empty()
+        && let Some(workspace) = project_root.parent()
+    {
+        let excluded = resolve_workspace_excludes(workspace, &config.workspace_excludes);
+        if !excluded.is_empty() {
+            for doc in &mut docs {
+                apply_workspace_excludes(doc, &excluded);
+            }
+        }
+    }
+
+    serialize_docs(&docs)
+        .map_err(|error| Error::message(format!("failed to serialize pods.yaml: {error}")))
+}
+
+/// Home directory of an agent's cli pod, mounted at `/home/user` in place of `.cladding/home`.
+pub fn agent_home_dir(project_root: &Path, agent: &str) -> PathBuf {
+    project_root.join("agents").join(agent).join("home")
+}
+
+/// Addresses of the agents' cli pods, which the proxy allows like the cli pod's own.
+fn agent_ips(network_settings: &NetworkSettings) -> String {
+    network_settings
+        .agent_pods
+        .iter()
+        .map(|pod| pod.ip.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Inserts a copy of the cli pod after it for each agent, under the agent's pod name and
+/// with the agent's own home directory.
+fn apply_agents(docs: &mut Vec<Value>, project_root: &Path, network_settings: &NetworkSettings) {
+    let Some(cli_index) = docs
+        .iter()
+        .position(|doc| doc_name(doc) == Some(network_settings.cli_pod_name.as_str()))
+    else {
+        return;
+    };
+    for (offset, agent) in network_settings.agent_pods.iter().enumerate() {
+        let mut doc = docs[cli_index].clone();
+        if let Some(metadata) = mapping_get_mut(&mut doc, "metadata").and_then(Value::as_mapping_mut)
+        {
+            metadata.insert(
+                Value::String("name".into()),
+                Value::String(agent.pod_name.clone()),
+            );
+            if let Some(labels) = metadata
+                .get_mut(Value::String("labels".into()))
+                .and_then(Value::as_mapping_mut)
+            {
+                labels.insert(
+                    Value::String("agent".into()),
+                    Value::String(agent.agent.clone()),
+                );
+            }
+        }
+        let home = agent_home_dir(project_root, &agent.agent);
+        if let Some(volumes) = mapping_get_mut(&mut doc, "spec")
+            .and_then(Value::as_mapping_mut)
+            .and_then(|spec| seq_get_mut_mapping(spec, "volumes"))
+        {
+            for volume in volumes.iter_mut() {
+                let Some(volume_map) = volume.as_mapping_mut() else {
+                    continue;
+                };
+                if mapping_get(volume_map, "name").and_then(Value::as_str) != Some("home-dir") {
+                    continue;
+                }
+                if let Some(host_path) = volume_map
+                    .get_mut(Value::String("hostPath".into()))
+                    .and_then(Value::as_mapping_mut)
+                {
+                    host_path.insert(
+                        Value::String("path".into()),
+                        Value::String(engine_host_path(&home)),
+                    );
+                }
+            }
+        }
+        docs.insert(cli_index + 1 + offset, doc);
+    }
+}
+
+/// Image of the proxy pod's squid container, which pods.yaml pins rather than cladding.json.
+pub fn proxy_image() -> Option<String> {
+    parse_docs(PODS_YAML)?
+        .iter()
+        .filter(|doc| doc_name(doc) == Some("REPLACE_PROXY_POD_NAME"))
+        .filter_map(|doc| doc.get("spec")?.get("containers")?.as_sequence())
+        .flatten()
+        .find(|container| container.get("name").and_then(Value::as_str) == Some("proxy"))
+        .and_then(|container| container.get("image")?.as_str().map(str::to_string))
+}
+
+fn doc_name(doc: &Value) -> Option<&str> {
+    doc.get("metadata")?.get("name")?.as_str()
+}
+
+/// Only the pod named `pod_name` from a rendered pods.yaml, plus the non-pod documents it may
+/// refer to, such as ConfigMaps. `None` if the pod is not in `rendered`.
+pub fn select_pod_from_rendered(rendered: &str, pod_name: &str) -> Option<String> {
+    let docs = parse_docs(rendered)?;
+    let is_pod = |doc: &Value| doc.get("kind").and_then(Value::as_str) == Some("Pod");
+    let is_selected = |doc: &Value| {
+        doc.get("metadata")
+            .and_then(|metadata| metadata.get("name"))
+            .and_then(Value::as_str)
+            == Some(pod_name)
+    };
+    if !docs.iter().any(|doc| is_pod(doc) && is_selected(doc)) {
+        return None;
+    }
+    let selected: Vec<Value> = docs
+        .into_iter()
+        .filter(|doc| !is_pod(doc) || is_selected(doc))
+        .collect();
+    serialize_docs(&selected).ok()
+}
+
+/// Names of the pods in a rendered pods.yaml, in document order.
+pub fn pod_names_from_rendered(rendered: &str) -> Vec<String> {
+    parse_docs(rendered)
+        .unwrap_or_default()
+        .iter()
+        .filter(|doc| doc.get("kind").and_then(Value::as_str) == Some("Pod"))
+        .filter_map(|doc| doc.get("metadata")?.get("name")?.as_str().map(str::to_string))
+        .collect()
+}
+
+fn parse_docs(rendered: &str) -> Option<Vec<Value>> {
+    serde_yaml::Deserializer::from_str(rendered)
+        .map(Value::deserialize)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .ok()
+}
+
+fn serialize_docs(docs: &[Value]) -> serde_yaml::Result<String> {
     let mut output = String::new();
     for (index, doc) in docs.iter().enumerate() {
-        let mut serialized = match serde_yaml::to_string(doc) {
-            Ok(serialized) => serialized,
-            Err(_) => return rendered,
-        };
+        let mut serialized = serde_yaml::to_string(doc)?;
         if let Some(stripped) = serialized.strip_prefix("---\n") {
             serialized = stripped.to_string();
         }
@@ -58,8 +223,7 @@ pub fn render_pods_yaml(
         }
         output.push_str(&serialized);
     }
-
-    output
+    Ok(output)
 }
 
 pub fn host_paths_from_rendered(rendered: &str) -> Vec<String> {
@@ -106,10 +270,10 @@ fn build_custom_mounts(config: &Config) -> Vec<CustomMount> {
     {
         let volume = match (host_path, volume) {
             (Some(path), None) => CustomVolume::HostPath {
-                path: path.display().to_string(),
+                path: engine_host_path(path),
             },
             (None, Some(name)) => CustomVolume::Named {
-                claim_name: format!("{}-{name}", config.name),
+                claim_name: config.volume_name(name),
             },
             (None, None) => CustomVolume::EmptyDir,
             (Some(_), Some(_)) => CustomVolume::EmptyDir,
@@ -207,13 +371,219 @@ fn apply_custom_mounts(doc: &mut Value, custom_mounts: &[CustomMount]) {
 
         *volume_mounts = mount_entries
             .into_iter()
-            .map(|entry| entry.to_value())
+            .map(|entry| entry.into_value())
             .collect();
     }
 
     spec_map.insert(volumes_key, volumes_value);
 }
 
+/// Masks each excluded path in the containers that mount the workspace: directories with an
+/// empty `emptyDir` volume, files with `/dev/null`, both read-only.
+fn apply_workspace_excludes(doc: &mut Value, excluded: &[ExcludedPath]) {
+    let Some(spec_map) = mapping_get_mut(doc, "spec").and_then(Value::as_mapping_mut) else {
+        return;
+    };
+    let Some(containers) = seq_get_mut_mapping(spec_map, "containers") else {
+        return;
+    };
+
+    let mut masked_any = false;
+    for container in containers.iter_mut() {
+        let Some(container_map) = container.as_mapping_mut() else {
+            continue;
+        };
+        let Some(volume_mounts) = seq_get_mut_mapping(container_map, "volumeMounts") else {
+            continue;
+        };
+        let mut mount_entries = parse_volume_mounts(volume_mounts);
+        if !mount_entries
+            .iter()
+            .any(|entry| entry.mount_path == WORKSPACE_MOUNT_PATH)
+        {
+            continue;
+        }
+        for path in excluded {
+            let mount_path = format!("{WORKSPACE_MOUNT_PATH}/{}", path.relative);
+            if mount_entries.iter().any(|entry| entry.mount_path == mount_path) {
+                continue;
+            }
+            let name = if path.is_dir {
+                EXCLUDED_DIR_VOLUME
+            } else {
+                EXCLUDED_FILE_VOLUME
+            };
+            mount_entries.push(VolumeMountEntry {
+                name: name.to_string(),
+                mount_path,
+                read_only: true,
+            });
+        }
+        *volume_mounts = mount_entries
+            .into_iter()
+            .map(|entry| entry.into_value())
+            .collect();
+        masked_any = true;
+    }
+    if !masked_any {
+        return;
+    }
+
+    let Some(volumes) = seq_get_mut_mapping(spec_map, "volumes") else {
+        return;
+    };
+    let mut volume_index = volume_index_by_name(volumes);
+    for (name, is_dir) in [(EXCLUDED_DIR_VOLUME, true), (EXCLUDED_FILE_VOLUME, false)] {
+        if !excluded.iter().any(|path| path.is_dir == is_dir) {
+            continue;
+        }
+        let volume = if is_dir {
+            CustomVolume::EmptyDir
+        } else {
+            CustomVolume::HostPath {
+                path: "/dev/null".to_string(),
+            }
+        };
+        let mask = CustomMount {
+            mount_path: String::new(),
+            read_only: true,
+            volume,
+            sandbox_only: false,
+        };
+        volume_index = ensure_volume_definition(volumes, volume_index, name, &mask);
+    }
+}
+
+/// Appends `env` to the env list of every container named `container_name`.
+fn apply_container_env(doc: &mut Value, container_name: &str, env: &BTreeMap<String, String>) {
+    let Some(spec_map) = mapping_get_mut(doc, "spec").and_then(Value::as_mapping_mut) else {
+        return;
+    };
+    let Some(containers) = seq_get_mut_mapping(spec_map, "containers") else {
+        return;
+    };
+    for container in containers.iter_mut() {
+        let Some(container_map) = container.as_mapping_mut() else {
+            continue;
+        };
+        if mapping_get(container_map, "name").and_then(Value::as_str) != Some(container_name) {
+            continue;
+        }
+        let env_key = Value::String("env".into());
+        let vars = env_values(env);
+        match container_map
+            .get_mut(&env_key)
+            .and_then(Value::as_sequence_mut)
+        {
+            Some(existing) => existing.extend(vars),
+            None => {
+                container_map.insert(env_key, Value::Sequence(vars));
+            }
+        }
+    }
+}
+
+/// `env` as a container's list of `{name, value}` entries.
+fn env_values(env: &BTreeMap<String, String>) -> Vec<Value> {
+    env.iter()
+        .map(|(name, value)| {
+            let mut var = Mapping::new();
+            var.insert(Value::String("name".into()), Value::String(name.clone()));
+            var.insert(Value::String("value".into()), Value::String(value.clone()));
+            Value::Mapping(var)
+        })
+        .collect()
+}
+
+/// Appends each sidecar to the cli pod's containers, with a `sidecar-<name>-<n>` volume
+/// per mount. Unlike `cli-app`, sidecar images may be pulled from a registry.
+fn apply_sidecars(doc: &mut Value, config: &Config, cli_pod_name: &str) {
+    let is_cli_pod = mapping_get_mut(doc, "metadata")
+        .and_then(|metadata| mapping_get_mut(metadata, "name"))
+        .and_then(|name| name.as_str())
+        == Some(cli_pod_name);
+    if !is_cli_pod {
+        return;
+    }
+    let Some(spec_map) = mapping_get_mut(doc, "spec").and_then(Value::as_mapping_mut) else {
+        return;
+    };
+
+    let mut sidecar_volumes = Vec::new();
+    let mut sidecar_containers = Vec::new();
+    for sidecar in &config.sidecars {
+        let mut volume_mounts = Vec::new();
+        for (index, mount) in sidecar.mounts.iter().enumerate() {
+            let name = format!("sidecar-{}-{}", sidecar.name, index + 1);
+            let volume = match (&mount.host_path, &mount.volume) {
+                (Some(path), _) => CustomVolume::HostPath {
+                    path: engine_host_path(path),
+                },
+                (None, Some(volume)) => CustomVolume::Named {
+                    claim_name: config.volume_name(volume),
+                },
+                (None, None) => CustomVolume::EmptyDir,
+            };
+            let custom = CustomMount {
+                mount_path: mount.mount_path.clone(),
+                read_only: mount.read_only,
+                volume,
+                sandbox_only: false,
+            };
+            sidecar_volumes.push(build_volume_value(&name, &custom));
+            volume_mounts.push(
+                VolumeMountEntry {
+                    name,
+                    mount_path: mount.mount_path.clone(),
+                    read_only: mount.read_only,
+                }
+                .into_value(),
+            );
+        }
+        sidecar_containers.push(build_sidecar_container(sidecar, volume_mounts));
+    }
+
+    if let Some(containers) = seq_get_mut_mapping(spec_map, "containers") {
+        containers.extend(sidecar_containers);
+    }
+    let volumes_key = Value::String("volumes".into());
+    match spec_map
+        .get_mut(&volumes_key)
+        .and_then(Value::as_sequence_mut)
+    {
+        Some(volumes) => volumes.extend(sidecar_volumes),
+        None => {
+            spec_map.insert(volumes_key, Value::Sequence(sidecar_volumes));
+        }
+    }
+}
+
+fn build_sidecar_container(sidecar: &SidecarConfig, volume_mounts: Vec<Value>) -> Value {
+    let string = |value: &str| Value::String(value.to_string());
+    let mut container = Mapping::new();
+    container.insert(string("name"), string(&sidecar.name));
+    container.insert(string("image"), string(&sidecar.image));
+    if !sidecar.env.is_empty() {
+        container.insert(string("env"), Value::Sequence(env_values(&sidecar.env)));
+    }
+    if !sidecar.ports.is_empty() {
+        let ports = sidecar
+            .ports
+            .iter()
+            .map(|port| {
+                let mut entry = Mapping::new();
+                entry.insert(string("containerPort"), Value::Number((*port).into()));
+                Value::Mapping(entry)
+            })
+            .collect();
+        container.insert(string("ports"), Value::Sequence(ports));
+    }
+    if !volume_mounts.is_empty() {
+        container.insert(string("volumeMounts"), Value::Sequence(volume_mounts));
+    }
+    Value::Mapping(container)
+}
+
 #[derive(Clone)]
 struct VolumeMountEntry {
     name: String,
@@ -222,7 +592,7 @@ struct VolumeMountEntry {
 }
 
 impl VolumeMountEntry {
-    fn to_value(self) -> Value {
+    fn into_value(self) -> Value {
         let mut mapping = Mapping::new();
         mapping.insert(Value::String("name".into()), Value::String(self.name));
         mapping.insert(
@@ -243,17 +613,17 @@ fn parse_volume_mounts(volume_mounts: &[Value]) -> Vec<VolumeMountEntry> {
             continue;
         };
         let name = mapping
-            .get(&Value::String("name".into()))
+            .get(Value::String("name".into()))
             .and_then(|value| value.as_str())
             .unwrap_or_default()
             .to_string();
         let mount_path = mapping
-            .get(&Value::String("mountPath".into()))
+            .get(Value::String("mountPath".into()))
             .and_then(|value| value.as_str())
             .unwrap_or_default()
             .to_string();
         let read_only = mapping
-            .get(&Value::String("readOnly".into()))
+            .get(Value::String("readOnly".into()))
             .and_then(|value| value.as_bool())
             .unwrap_or(false);
 
@@ -283,7 +653,7 @@ fn volume_index_by_name(volumes: &[Value]) -> std::collections::HashMap<String,
             continue;
         };
         let name = mapping
-            .get(&Value::String("name".into()))
+            .get(Value::String("name".into()))
             .and_then(|value| value.as_str())
             .unwrap_or_default();
         if !name.is_empty() {
@@ -342,17 +712,17 @@ fn build_volume_value(name: &str, custom: &CustomMount) -> Value {
 }
 
 fn mapping_get<'a>(mapping: &'a Mapping, key: &str) -> Option<&'a Value> {
-    mapping.get(&Value::String(key.into()))
+    mapping.get(Value::String(key.into()))
 }
 
 fn mapping_get_mut<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
     let mapping = value.as_mapping_mut()?;
-    mapping.get_mut(&Value::String(key.into()))
+    mapping.get_mut(Value::String(key.into()))
 }
 
 fn seq_get_mut_mapping<'a>(mapping: &'a mut Mapping, key: &str) -> Option<&'a mut Vec<Value>> {
     mapping
-        .get_mut(&Value::String(key.into()))?
+        .get_mut(Value::String(key.into()))?
         .as_sequence_mut()
 }
 
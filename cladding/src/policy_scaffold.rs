@@ -0,0 +1,185 @@
+//! `cladding policy new`: writes a starting `<command>.rego` for `config/sandbox_commands`,
+//! in the package main.rego routes to, with a `<command>_test.json` that `mcp-run policy test`
+//! runs.
+
+use crate::error::{Error, Result};
+use serde_json::{Value, json};
+
+/// Package of the router every command module is looked up from.
+const ROUTER_COMMAND: &str = "main";
+
+/// The files `cladding policy new <command>` writes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PolicyScaffold {
+    /// `<command>.rego`.
+    pub module: String,
+    /// `<command>_test.json`.
+    pub tests: String,
+}
+
+/// Checks that `command` can name a module in `sandbox_commands`: main.rego looks it up as
+/// `data.sandbox[input.command]`, so it is the bare executable name.
+pub fn validate_command_name(command: &str) -> Result<()> {
+    if command.is_empty() {
+        return Err(Error::message("command name is empty"));
+    }
+    if command == ROUTER_COMMAND {
+        return Err(Error::message("'main' is the router module, not a command"));
+    }
+    if command.starts_with('.') || command.starts_with('-') {
+        return Err(Error::message(format!(
+            "command name '{command}' cannot start with '{}'",
+            &command[..1]
+        )));
+    }
+    if let Some(invalid) = command
+        .chars()
+        .find(|ch| !(ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.' | '+')))
+    {
+        return Err(Error::message(format!(
+            "command name '{command}' contains '{invalid}'; use the executable's name, not a path"
+        )));
+    }
+    Ok(())
+}
+
+/// Renders the module and its test cases for `command`. The module allows `--version` from
+/// `/usr/bin/<command>` and shows where argument prefixes and env vars are added.
+pub fn scaffold_command_policy(command: &str) -> Result<PolicyScaffold> {
+    validate_command_name(command)?;
+    let path = format!("/usr/bin/{command}");
+    let env_key = format!("{}_LOG", command.to_ascii_uppercase().replace(['-', '.', '+'], "_"));
+    let module = format!(
+        r#"package {package}
+
+description := "TODO: what {command} may be used for"
+
+default allow = false
+default allow_env = false
+
+# The binary mcp-run resolves on PATH in the sandbox; check it with `command -v {command}`.
+allow if {{
+  input.path == "{path}"
+  args_allowed
+}}
+
+# Each entry allows invocations that start with exactly these arguments. Anything after the
+# prefix is accepted, so prefer the narrowest list that covers what the agent needs.
+allowed_prefixes := [
+  ["--version"],
+]
+
+args_allowed if {{
+  some prefix in allowed_prefixes
+  count(input.args) >= count(prefix)
+  array.slice(input.args, 0, count(prefix)) == prefix
+}}
+
+# Without this rule any env var denies the invocation. Every key must have a rule below.
+allow_env if {{
+  every key, value in input.env {{
+    env_allowed(key, value)
+  }}
+}}
+
+env_allowed(key, value) if {{
+  key == "{env_key}"
+  regex.match(`^(error|warn|info|debug)$`, value)
+}}
+"#,
+        package = package_path(command),
+    );
+
+    let other_path = format!("/tmp/{command}");
+    let case = |name: &str, path: &str, args: &[&str], env: Value, allow| {
+        json!({
+            "name": name,
+            "input": {"command": command, "path": path, "args": args, "env": env},
+            "allow": allow,
+        })
+    };
+    let tests = json!({
+        "cases": [
+            case(
+                &format!("{command} --version is allowed"),
+                &path,
+                &["--version"],
+                json!({}),
+                true,
+            ),
+            case("other arguments are denied", &path, &["--unexpected"], json!({}), false),
+            case(
+                &format!("{command} from another path is denied"),
+                &other_path,
+                &["--version"],
+                json!({}),
+                false,
+            ),
+            case(
+                &format!("{env_key} is allowed"),
+                &path,
+                &["--version"],
+                json!({ &env_key: "debug" }),
+                true,
+            ),
+            case(
+                "other env vars are denied",
+                &path,
+                &["--version"],
+                json!({"OTHER": "1"}),
+                false,
+            ),
+        ]
+    });
+    let mut tests = serde_json::to_string_pretty(&tests)
+        .map_err(|error| Error::message(format!("failed to render policy tests: {error}")))?;
+    tests.push('\n');
+
+    Ok(PolicyScaffold { module, tests })
+}
+
+/// `sandbox.<command>`, or `sandbox["<command>"]` when the name is not a Rego identifier.
+fn package_path(command: &str) -> String {
+    let identifier = command
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && command.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+    if identifier {
+        format!("sandbox.{command}")
+    } else {
+        format!("sandbox[\"{command}\"]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaffold_uses_the_package_main_rego_routes_to() {
+        let scaffold = scaffold_command_policy("rg").expect("scaffold");
+        assert!(scaffold.module.starts_with("package sandbox.rg\n"));
+        assert!(scaffold.module.contains(r#"input.path == "/usr/bin/rg""#));
+        assert!(scaffold.module.contains(r#"key == "RG_LOG""#));
+
+        let tests: Value = serde_json::from_str(&scaffold.tests).expect("tests json");
+        let cases = tests["cases"].as_array().expect("cases");
+        assert_eq!(cases.len(), 5);
+        assert!(cases.iter().all(|case| case["input"]["command"] == "rg"));
+
+        let dashed = scaffold_command_policy("git-lfs").expect("scaffold");
+        assert!(dashed.module.starts_with("package sandbox[\"git-lfs\"]\n"));
+        assert!(dashed.module.contains(r#"key == "GIT_LFS_LOG""#));
+    }
+
+    #[test]
+    fn command_names_must_be_bare_executables() {
+        for name in ["", "main", "/usr/bin/rg", "../rg", "-rf", "rg tool", "rg\"x"] {
+            assert!(validate_command_name(name).is_err(), "{name:?}");
+        }
+        for name in ["rg", "git-lfs", "python3.12", "g++", "_tool"] {
+            assert!(validate_command_name(name).is_ok(), "{name:?}");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! State for `cladding pool`: warm copies of a project, named `<name>-pool-<N>`, that CI jobs
+//! check out one at a time. Each member is an ordinary project on its own `cladding-N`
+//! network; which job holds which member is recorded in `.cladding/pool.json`.
+
+use crate::error::{Error, Result};
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const POOL_STATE_FILE_NAME: &str = "pool.json";
+
+/// Set to a member name to point the other commands (`run`, `shell`, `status`, ...) at that
+/// pool member instead of the project itself.
+pub const POOL_MEMBER_ENV: &str = "CLADDING_POOL_MEMBER";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolState {
+    /// Assigned members by name; a running member without an entry is free.
+    #[serde(default)]
+    pub assignments: BTreeMap<String, PoolAssignment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolAssignment {
+    pub job: String,
+    /// Seconds since the Unix epoch.
+    pub assigned_at: u64,
+}
+
+impl PoolState {
+    /// The first of `members` without an assignment.
+    pub fn first_free<'a>(&self, members: &'a [String]) -> Option<&'a str> {
+        members
+            .iter()
+            .find(|member| !self.assignments.contains_key(*member))
+            .map(String::as_str)
+    }
+}
+
+pub fn pool_member_name(project_name: &str, index: usize) -> String {
+    format!("{project_name}-pool-{index}")
+}
+
+/// `N` when `member` is `<project_name>-pool-<N>`.
+pub fn parse_pool_member_index(project_name: &str, member: &str) -> Option<usize> {
+    let index = member.strip_prefix(project_name)?.strip_prefix("-pool-")?;
+    if index.is_empty() || (index.len() > 1 && index.starts_with('0')) {
+        return None;
+    }
+    index.parse().ok()
+}
+
+pub fn pool_state_path(project_root: &Path) -> PathBuf {
+    project_root.join(POOL_STATE_FILE_NAME)
+}
+
+/// The recorded assignments, or an empty state before the first `pool assign`.
+pub fn load_pool_state(project_root: &Path) -> Result<PoolState> {
+    let path = pool_state_path(project_root);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(PoolState::default()),
+        Err(err) => {
+            return Err(Error::from(
+                anyhow::Error::new(err).context(format!("failed to read {}", path.display())),
+            ));
+        }
+    };
+    serde_json::from_str(&contents)
+        .with_context(|| format!("invalid pool state in {}", path.display()))
+        .map_err(Error::from)
+}
+
+/// Replaces the state file in one rename, so a concurrent reader never sees half of it.
+pub fn save_pool_state(project_root: &Path, state: &PoolState) -> Result<()> {
+    let path = pool_state_path(project_root);
+    let temp_path = project_root.join(format!(".{POOL_STATE_FILE_NAME}.tmp"));
+    let contents = serde_json::to_string_pretty(state)
+        .with_context(|| "failed to serialize pool state")?;
+    fs::write(&temp_path, contents + "\n")
+        .with_context(|| format!("failed to write {}", temp_path.display()))?;
+    fs::rename(&temp_path, &path)
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn member_names_round_trip() {
+        assert_eq!(pool_member_name("demo", 3), "demo-pool-3");
+        assert_eq!(parse_pool_member_index("demo", "demo-pool-3"), Some(3));
+        assert_eq!(parse_pool_member_index("demo", "demo-pool-03"), None);
+        assert_eq!(parse_pool_member_index("demo", "demo-pool-"), None);
+        assert_eq!(parse_pool_member_index("demo", "other-pool-1"), None);
+        assert_eq!(parse_pool_member_index("demo", "demo"), None);
+    }
+
+    #[test]
+    fn state_saves_and_finds_free_members() {
+        let dir = std::env::temp_dir().join(format!(
+            "cladding-pool-state-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(load_pool_state(&dir).unwrap(), PoolState::default());
+
+        let mut state = PoolState::default();
+        state.assignments.insert(
+            "demo-pool-0".to_string(),
+            PoolAssignment {
+                job: "build-17".to_string(),
+                assigned_at: 1,
+            },
+        );
+        save_pool_state(&dir, &state).unwrap();
+        let loaded = load_pool_state(&dir).unwrap();
+        assert_eq!(loaded, state);
+
+        let members = vec!["demo-pool-0".to_string(), "demo-pool-1".to_string()];
+        assert_eq!(loaded.first_free(&members), Some("demo-pool-1"));
+        assert_eq!(loaded.first_free(&members[..1]), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
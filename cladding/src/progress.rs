@@ -0,0 +1,147 @@
+//! `--log-format json`: `build`, `up` and `down` report each step as one JSON line
+//! on stdout, for IDE tasks and other wrappers that render progress. Messages on stderr are
+//! unchanged; output of child processes that would otherwise reach stdout goes to stderr.
+
+use crate::error::Result;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+static JSON_FORMAT: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Started,
+    Ok,
+    Failed,
+    Skipped,
+}
+
+/// One line of `--log-format json` output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum ProgressEvent {
+    Step {
+        step: String,
+        status: StepStatus,
+        #[serde(rename = "durationMs", skip_serializing_if = "Option::is_none")]
+        duration_ms: Option<u64>,
+        /// Why the step failed or was skipped.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A result line that text output prints as is, such as `already running: demo`.
+    Message { message: String },
+}
+
+/// Selects the format for the rest of the process.
+pub fn set_log_format(format: LogFormat) {
+    JSON_FORMAT.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+pub fn json_enabled() -> bool {
+    JSON_FORMAT.load(Ordering::Relaxed)
+}
+
+fn emit(event: &ProgressEvent) {
+    println!(
+        "{}",
+        serde_json::to_string(event).expect("progress event serializes")
+    );
+}
+
+/// Runs `run` as step `name`, reporting its start and its outcome in JSON mode.
+pub fn step<T>(name: &str, run: impl FnOnce() -> Result<T>) -> Result<T> {
+    if !json_enabled() {
+        return run();
+    }
+    emit(&ProgressEvent::Step {
+        step: name.to_string(),
+        status: StepStatus::Started,
+        duration_ms: None,
+        error: None,
+    });
+    let started = Instant::now();
+    let result = run();
+    let (status, error) = match &result {
+        Ok(_) => (StepStatus::Ok, None),
+        Err(error) => (StepStatus::Failed, Some(error.to_string())),
+    };
+    emit(&ProgressEvent::Step {
+        step: name.to_string(),
+        status,
+        duration_ms: Some(started.elapsed().as_millis() as u64),
+        error,
+    });
+    result
+}
+
+/// Reports step `name` as not run; text output prints `skip: <reason>`.
+pub fn skipped(name: &str, reason: impl Display) {
+    if json_enabled() {
+        emit(&ProgressEvent::Step {
+            step: name.to_string(),
+            status: StepStatus::Skipped,
+            duration_ms: None,
+            error: Some(reason.to_string()),
+        });
+    } else {
+        println!("skip: {reason}");
+    }
+}
+
+/// A result line on stdout, wrapped in a `message` event in JSON mode.
+pub fn message(message: impl Display) {
+    if json_enabled() {
+        emit(&ProgressEvent::Message {
+            message: message.to_string(),
+        });
+    } else {
+        println!("{message}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_serialize_as_flat_lines() {
+        let failed = ProgressEvent::Step {
+            step: "start-pods".to_string(),
+            status: StepStatus::Failed,
+            duration_ms: Some(812),
+            error: Some("podman kube play failed (exit code 125)".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_string(&failed).unwrap(),
+            r#"{"event":"step","step":"start-pods","status":"failed","durationMs":812,"error":"podman kube play failed (exit code 125)"}"#
+        );
+        let started = ProgressEvent::Step {
+            step: "load-config".to_string(),
+            status: StepStatus::Started,
+            duration_ms: None,
+            error: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&started).unwrap(),
+            r#"{"event":"step","step":"load-config","status":"started"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ProgressEvent::Message {
+                message: "already running: demo".to_string()
+            })
+            .unwrap(),
+            r#"{"event":"message","message":"already running: demo"}"#
+        );
+    }
+}
@@ -0,0 +1,901 @@
+//! The container operations the project lifecycle commands need, behind a trait so they can
+//! run against podman, docker, or the in-memory [`FakeRuntime`] in tests.
+
+use crate::assets::containerfile_digest;
+use crate::docker::{
+    docker_inspect_pod_state, docker_list_running_project_networks, docker_list_running_projects,
+    docker_play_kube, docker_pod_network_connect, docker_remove_pods,
+};
+use crate::engine::Engine;
+use crate::error::{Error, Result};
+use crate::mcp_config::McpRunVersion;
+use crate::network::{EGRESS_NETWORK, NetworkSettings};
+use crate::podman::{
+    ContainerState, EnsureNetworkOutcome, ExposeProxy, IMAGE_CONTAINERFILE_LABEL,
+    IMAGE_GID_LABEL, IMAGE_UID_LABEL, ImageDetails, NetworkAddress, NetworkSubnet, PodState,
+    RunningProject, RunningProjectNetwork,
+    ensure_egress_network, ensure_pool_network_settings, list_podman_network_subnets,
+    list_project_expose_proxies, list_running_project_networks, list_running_projects,
+    podman_build_image, podman_container_exists, podman_container_log_tail,
+    podman_container_login_shell, podman_create_volume, podman_force_remove,
+    podman_image_exists, podman_inspect_image, podman_inspect_pod_state, podman_inspect_volume,
+    podman_list_volumes, podman_network_containers, podman_network_is_internal,
+    podman_play_kube, podman_pod_network_connect, podman_push_image, podman_remove_containers,
+    podman_remove_network, podman_remove_volume, podman_sandbox_mcp_request, podman_tag_image,
+    podman_volume_sizes,
+};
+use anyhow::Context as _;
+use serde::Deserialize;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::rc::Rc;
+
+pub trait ContainerRuntime: Debug {
+    fn image_exists(&self, image: &str) -> Result<bool>;
+    /// `None` when there is no such local image.
+    fn inspect_image(&self, image: &str) -> Result<Option<ImageDetails>>;
+    /// Builds `image` from the embedded Containerfile for the host user.
+    fn build_image(&self, image: &str, host_uid: u32, host_gid: u32) -> Result<()>;
+    fn tag_image(&self, image: &str, tag: &str) -> Result<()>;
+    fn push_image(&self, tag: &str) -> Result<()>;
+    /// `None` when there is no such pod.
+    fn inspect_pod_state(&self, pod_name: &str) -> Result<Option<PodState>>;
+    fn container_exists(&self, container_name: &str) -> Result<bool>;
+    /// Login shell of the container's default user, if it has a usable one.
+    fn container_login_shell(&self, container_name: &str) -> Option<String>;
+    /// The last `lines` lines of a container's combined stdout and stderr.
+    fn container_log_tail(&self, container_name: &str, lines: usize) -> Result<String>;
+    /// Sends `method` to mcp-run's `path` from inside the sandbox-app container and returns
+    /// the response body.
+    fn sandbox_mcp_request(&self, container_name: &str, method: &str, path: &str)
+    -> Result<String>;
+
+    /// mcp-run's `/metrics` text.
+    fn sandbox_metrics(&self, container_name: &str) -> Result<String> {
+        self.sandbox_mcp_request(container_name, "GET", "/metrics")
+    }
+
+    /// mcp-run's `/version`.
+    fn sandbox_mcp_version(&self, container_name: &str) -> Result<McpRunVersion> {
+        let body = self.sandbox_mcp_request(container_name, "GET", "/version")?;
+        Ok(serde_json::from_str(&body).with_context(|| "failed to parse mcp-run /version json")?)
+    }
+    fn list_running_projects(&self) -> Result<Vec<RunningProject>>;
+    fn list_running_project_networks(&self) -> Result<Vec<RunningProjectNetwork>>;
+    fn list_network_subnets(&self) -> Result<Vec<NetworkSubnet>>;
+    /// Ensures the pool network exists on its subnet, as an internal network when `internal`.
+    fn ensure_pool_network(
+        &self,
+        network_settings: &NetworkSettings,
+        internal: bool,
+    ) -> Result<EnsureNetworkOutcome>;
+    fn ensure_egress_network(&self) -> Result<()>;
+    fn network_is_internal(&self, network: &str) -> Result<bool>;
+    /// Creates (or with `down`, removes) the pods described by the rendered pods.yaml.
+    fn play_kube(&self, rendered: &str, network: &NetworkSettings, down: bool) -> Result<()>;
+    fn pod_network_connect(&self, pod_name: &str, network: &str) -> Result<()>;
+    /// Names of the containers attached to `network`, stopped ones included.
+    fn network_containers(&self, network: &str) -> Result<Vec<String>>;
+    fn remove_network(&self, network: &str) -> Result<()>;
+    fn force_remove(&self, names: &[&str]) -> Result<()>;
+    fn list_project_expose_proxies(
+        &self,
+        project_name: &str,
+        project_root: &str,
+        include_stopped: bool,
+    ) -> Result<Vec<ExposeProxy>>;
+    fn remove_containers(
+        &self,
+        container_ids: &[String],
+        force: bool,
+        ignore_missing: bool,
+    ) -> Result<()>;
+    /// Names of every volume the engine has, not only this project's.
+    fn list_volumes(&self) -> Result<Vec<String>>;
+    fn create_volume(&self, name: &str) -> Result<()>;
+    fn remove_volume(&self, name: &str) -> Result<()>;
+    fn inspect_volume(&self, name: &str) -> Result<String>;
+    /// Bytes used by each volume, by name.
+    fn volume_sizes(&self) -> Result<BTreeMap<String, u64>>;
+}
+
+/// The real runtime: every call shells out to podman.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Podman;
+
+impl ContainerRuntime for Podman {
+    fn image_exists(&self, image: &str) -> Result<bool> {
+        podman_image_exists(Engine::Podman, image)
+    }
+
+    fn inspect_image(&self, image: &str) -> Result<Option<ImageDetails>> {
+        podman_inspect_image(Engine::Podman, image)
+    }
+
+    fn build_image(&self, image: &str, host_uid: u32, host_gid: u32) -> Result<()> {
+        podman_build_image(image, host_uid, host_gid)
+    }
+
+    fn tag_image(&self, image: &str, tag: &str) -> Result<()> {
+        podman_tag_image(image, tag)
+    }
+
+    fn push_image(&self, tag: &str) -> Result<()> {
+        podman_push_image(tag)
+    }
+
+    fn inspect_pod_state(&self, pod_name: &str) -> Result<Option<PodState>> {
+        podman_inspect_pod_state(pod_name)
+    }
+
+    fn container_exists(&self, container_name: &str) -> Result<bool> {
+        podman_container_exists(Engine::Podman, container_name)
+    }
+
+    fn container_login_shell(&self, container_name: &str) -> Option<String> {
+        podman_container_login_shell(container_name)
+    }
+
+    fn container_log_tail(&self, container_name: &str, lines: usize) -> Result<String> {
+        podman_container_log_tail(container_name, lines)
+    }
+
+    fn sandbox_mcp_request(
+        &self,
+        container_name: &str,
+        method: &str,
+        path: &str,
+    ) -> Result<String> {
+        podman_sandbox_mcp_request(container_name, method, path)
+    }
+
+    fn list_running_projects(&self) -> Result<Vec<RunningProject>> {
+        list_running_projects()
+    }
+
+    fn list_running_project_networks(&self) -> Result<Vec<RunningProjectNetwork>> {
+        list_running_project_networks()
+    }
+
+    fn list_network_subnets(&self) -> Result<Vec<NetworkSubnet>> {
+        list_podman_network_subnets(Engine::Podman)
+    }
+
+    fn ensure_pool_network(
+        &self,
+        network_settings: &NetworkSettings,
+        internal: bool,
+    ) -> Result<EnsureNetworkOutcome> {
+        ensure_pool_network_settings(Engine::Podman, network_settings, internal)
+    }
+
+    fn ensure_egress_network(&self) -> Result<()> {
+        ensure_egress_network(Engine::Podman)
+    }
+
+    fn network_is_internal(&self, network: &str) -> Result<bool> {
+        podman_network_is_internal(network)
+    }
+
+    fn play_kube(&self, rendered: &str, network: &NetworkSettings, down: bool) -> Result<()> {
+        podman_play_kube(rendered, network, down)
+    }
+
+    fn pod_network_connect(&self, pod_name: &str, network: &str) -> Result<()> {
+        podman_pod_network_connect(pod_name, network)
+    }
+
+    fn network_containers(&self, network: &str) -> Result<Vec<String>> {
+        podman_network_containers(network)
+    }
+
+    fn remove_network(&self, network: &str) -> Result<()> {
+        podman_remove_network(network)
+    }
+
+    fn force_remove(&self, names: &[&str]) -> Result<()> {
+        podman_force_remove(names)
+    }
+
+    fn list_project_expose_proxies(
+        &self,
+        project_name: &str,
+        project_root: &str,
+        include_stopped: bool,
+    ) -> Result<Vec<ExposeProxy>> {
+        list_project_expose_proxies(Engine::Podman, project_name, project_root, include_stopped)
+    }
+
+    fn remove_containers(
+        &self,
+        container_ids: &[String],
+        force: bool,
+        ignore_missing: bool,
+    ) -> Result<()> {
+        podman_remove_containers(container_ids, force, ignore_missing)
+    }
+
+    fn list_volumes(&self) -> Result<Vec<String>> {
+        podman_list_volumes()
+    }
+
+    fn create_volume(&self, name: &str) -> Result<()> {
+        podman_create_volume(name)
+    }
+
+    fn remove_volume(&self, name: &str) -> Result<()> {
+        podman_remove_volume(name)
+    }
+
+    fn inspect_volume(&self, name: &str) -> Result<String> {
+        podman_inspect_volume(name)
+    }
+
+    fn volume_sizes(&self) -> Result<BTreeMap<String, u64>> {
+        podman_volume_sizes()
+    }
+}
+
+/// Docker, with pods translated into plain containers by [`crate::docker`]. Network, image
+/// and container calls are shared with [`Podman`] and run the selected engine's CLI.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Docker;
+
+impl ContainerRuntime for Docker {
+    fn image_exists(&self, image: &str) -> Result<bool> {
+        podman_image_exists(Engine::Docker, image)
+    }
+
+    fn inspect_image(&self, image: &str) -> Result<Option<ImageDetails>> {
+        podman_inspect_image(Engine::Docker, image)
+    }
+
+    fn build_image(&self, image: &str, host_uid: u32, host_gid: u32) -> Result<()> {
+        podman_build_image(image, host_uid, host_gid)
+    }
+
+    fn tag_image(&self, image: &str, tag: &str) -> Result<()> {
+        podman_tag_image(image, tag)
+    }
+
+    fn push_image(&self, tag: &str) -> Result<()> {
+        podman_push_image(tag)
+    }
+
+    fn inspect_pod_state(&self, pod_name: &str) -> Result<Option<PodState>> {
+        docker_inspect_pod_state(pod_name)
+    }
+
+    fn container_exists(&self, container_name: &str) -> Result<bool> {
+        podman_container_exists(Engine::Docker, container_name)
+    }
+
+    fn container_login_shell(&self, container_name: &str) -> Option<String> {
+        podman_container_login_shell(container_name)
+    }
+
+    fn container_log_tail(&self, container_name: &str, lines: usize) -> Result<String> {
+        podman_container_log_tail(container_name, lines)
+    }
+
+    fn sandbox_mcp_request(
+        &self,
+        container_name: &str,
+        method: &str,
+        path: &str,
+    ) -> Result<String> {
+        podman_sandbox_mcp_request(container_name, method, path)
+    }
+
+    fn list_running_projects(&self) -> Result<Vec<RunningProject>> {
+        docker_list_running_projects()
+    }
+
+    fn list_running_project_networks(&self) -> Result<Vec<RunningProjectNetwork>> {
+        docker_list_running_project_networks()
+    }
+
+    fn list_network_subnets(&self) -> Result<Vec<NetworkSubnet>> {
+        list_podman_network_subnets(Engine::Docker)
+    }
+
+    fn ensure_pool_network(
+        &self,
+        network_settings: &NetworkSettings,
+        internal: bool,
+    ) -> Result<EnsureNetworkOutcome> {
+        ensure_pool_network_settings(Engine::Docker, network_settings, internal)
+    }
+
+    fn ensure_egress_network(&self) -> Result<()> {
+        ensure_egress_network(Engine::Docker)
+    }
+
+    fn network_is_internal(&self, network: &str) -> Result<bool> {
+        podman_network_is_internal(network)
+    }
+
+    fn play_kube(&self, rendered: &str, network: &NetworkSettings, down: bool) -> Result<()> {
+        docker_play_kube(rendered, network, down)
+    }
+
+    fn pod_network_connect(&self, pod_name: &str, network: &str) -> Result<()> {
+        docker_pod_network_connect(pod_name, network)
+    }
+
+    fn network_containers(&self, network: &str) -> Result<Vec<String>> {
+        podman_network_containers(network)
+    }
+
+    fn remove_network(&self, network: &str) -> Result<()> {
+        podman_remove_network(network)
+    }
+
+    fn force_remove(&self, names: &[&str]) -> Result<()> {
+        docker_remove_pods(names)
+    }
+
+    fn list_project_expose_proxies(
+        &self,
+        project_name: &str,
+        project_root: &str,
+        include_stopped: bool,
+    ) -> Result<Vec<ExposeProxy>> {
+        list_project_expose_proxies(Engine::Docker, project_name, project_root, include_stopped)
+    }
+
+    fn remove_containers(
+        &self,
+        container_ids: &[String],
+        force: bool,
+        ignore_missing: bool,
+    ) -> Result<()> {
+        podman_remove_containers(container_ids, force, ignore_missing)
+    }
+
+    fn list_volumes(&self) -> Result<Vec<String>> {
+        podman_list_volumes()
+    }
+
+    fn create_volume(&self, name: &str) -> Result<()> {
+        podman_create_volume(name)
+    }
+
+    fn remove_volume(&self, name: &str) -> Result<()> {
+        podman_remove_volume(name)
+    }
+
+    fn inspect_volume(&self, name: &str) -> Result<String> {
+        podman_inspect_volume(name)
+    }
+
+    fn volume_sizes(&self) -> Result<BTreeMap<String, u64>> {
+        podman_volume_sizes()
+    }
+}
+
+/// The runtime that drives `engine`.
+pub fn engine_runtime(engine: Engine) -> Rc<dyn ContainerRuntime> {
+    match engine {
+        Engine::Podman => Rc::new(Podman),
+        Engine::Docker => Rc::new(Docker),
+    }
+}
+
+/// Subnet the fake gives `cladding-egress`, outside the 10.90.0.0/16 pool.
+const FAKE_EGRESS_SUBNET: &str = "10.89.0.0/24";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeNetwork {
+    pub subnet: String,
+    pub internal: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakePod {
+    pub name: String,
+    pub project_name: String,
+    pub project_root: String,
+    pub network: String,
+    /// Networks attached after creation, like `cladding-egress` for isolated proxies.
+    pub extra_networks: Vec<String>,
+    /// Named `<pod>-<container>` as podman names them, all running.
+    pub containers: Vec<ContainerState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeExposeProxy {
+    pub project_name: String,
+    pub project_root: String,
+    pub proxy: ExposeProxy,
+}
+
+/// Everything the fake runtime knows about; tests seed and inspect it directly.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FakeState {
+    pub images: BTreeSet<String>,
+    /// What `inspect_image` reports for an image in `images`; a default when absent.
+    pub image_details: BTreeMap<String, ImageDetails>,
+    pub networks: BTreeMap<String, FakeNetwork>,
+    pub pods: Vec<FakePod>,
+    pub expose_proxies: Vec<FakeExposeProxy>,
+    pub volumes: BTreeSet<String>,
+    /// What `volume_sizes` reports for a volume in `volumes`; 0 when absent.
+    pub volume_sizes: BTreeMap<String, u64>,
+    /// Tags `push_image` was called with, in order.
+    pub pushed: Vec<String>,
+    /// What `container_log_tail` reads for a container of a pod in `pods`; empty when absent.
+    pub container_logs: BTreeMap<String, String>,
+    /// mcp-run response bodies by `<method> <path>`, e.g. `GET /metrics`; other requests fail.
+    pub mcp_responses: BTreeMap<String, String>,
+}
+
+/// An in-memory [`ContainerRuntime`] that behaves like podman closely enough for the
+/// project lifecycle commands: pods come from the rendered pods.yaml and keep its labels.
+#[derive(Debug, Default)]
+pub struct FakeRuntime {
+    state: RefCell<FakeState>,
+}
+
+impl FakeRuntime {
+    pub fn with_images<'a>(images: impl IntoIterator<Item = &'a str>) -> Self {
+        let runtime = Self::default();
+        runtime
+            .state_mut()
+            .images
+            .extend(images.into_iter().map(str::to_string));
+        runtime
+    }
+
+    pub fn state(&self) -> Ref<'_, FakeState> {
+        self.state.borrow()
+    }
+
+    pub fn state_mut(&self) -> RefMut<'_, FakeState> {
+        self.state.borrow_mut()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KubeDocument {
+    kind: String,
+    metadata: KubeMetadata,
+    #[serde(default)]
+    spec: KubePodSpec,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubeMetadata {
+    name: String,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct KubePodSpec {
+    #[serde(default)]
+    containers: Vec<KubeContainer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubeContainer {
+    name: String,
+    #[serde(default)]
+    image: String,
+}
+
+fn pods_in_rendered(rendered: &str) -> Result<Vec<(KubeMetadata, KubePodSpec)>> {
+    let mut pods = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(rendered) {
+        let document = KubeDocument::deserialize(document)
+            .map_err(|error| Error::message(format!("invalid pods.yaml: {error}")))?;
+        if document.kind == "Pod" {
+            pods.push((document.metadata, document.spec));
+        }
+    }
+    Ok(pods)
+}
+
+impl ContainerRuntime for FakeRuntime {
+    fn image_exists(&self, image: &str) -> Result<bool> {
+        Ok(self.state().images.contains(image))
+    }
+
+    fn inspect_image(&self, image: &str) -> Result<Option<ImageDetails>> {
+        let state = self.state();
+        if !state.images.contains(image) {
+            return Ok(None);
+        }
+        Ok(Some(state.image_details.get(image).cloned().unwrap_or_default()))
+    }
+
+    fn build_image(&self, image: &str, host_uid: u32, host_gid: u32) -> Result<()> {
+        let mut state = self.state_mut();
+        state.images.insert(image.to_string());
+        let labels = [
+            (IMAGE_UID_LABEL, host_uid.to_string()),
+            (IMAGE_GID_LABEL, host_gid.to_string()),
+            (IMAGE_CONTAINERFILE_LABEL, containerfile_digest()),
+        ];
+        state.image_details.insert(
+            image.to_string(),
+            ImageDetails {
+                labels: labels
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+                ..ImageDetails::default()
+            },
+        );
+        Ok(())
+    }
+
+    fn tag_image(&self, image: &str, tag: &str) -> Result<()> {
+        let mut state = self.state_mut();
+        if !state.images.contains(image) {
+            return Err(Error::message(format!("no such image: {image}")));
+        }
+        state.images.insert(tag.to_string());
+        if let Some(details) = state.image_details.get(image).cloned() {
+            state.image_details.insert(tag.to_string(), details);
+        }
+        Ok(())
+    }
+
+    fn push_image(&self, tag: &str) -> Result<()> {
+        let mut state = self.state_mut();
+        if !state.images.contains(tag) {
+            return Err(Error::message(format!("no such image: {tag}")));
+        }
+        state.pushed.push(tag.to_string());
+        Ok(())
+    }
+
+    fn inspect_pod_state(&self, pod_name: &str) -> Result<Option<PodState>> {
+        let state = self.state();
+        let Some(pod) = state.pods.iter().find(|pod| pod.name == pod_name) else {
+            return Ok(None);
+        };
+        let addresses = std::iter::once(&pod.network)
+            .chain(&pod.extra_networks)
+            .map(|network| NetworkAddress {
+                network: network.clone(),
+                ip: String::new(),
+            })
+            .collect();
+        Ok(Some(PodState {
+            name: pod.name.clone(),
+            status: "Running".to_string(),
+            addresses,
+            containers: pod.containers.clone(),
+        }))
+    }
+
+    fn container_exists(&self, container_name: &str) -> Result<bool> {
+        Ok(self
+            .state()
+            .pods
+            .iter()
+            .flat_map(|pod| &pod.containers)
+            .any(|container| container.name == container_name))
+    }
+
+    fn container_login_shell(&self, _container_name: &str) -> Option<String> {
+        None
+    }
+
+    fn container_log_tail(&self, container_name: &str, lines: usize) -> Result<String> {
+        if !self.container_exists(container_name)? {
+            return Err(Error::message(format!("no such container: {container_name}")));
+        }
+        let state = self.state();
+        let log = state.container_logs.get(container_name).map_or("", String::as_str);
+        let skip = log.lines().count().saturating_sub(lines);
+        Ok(log.lines().skip(skip).map(|line| format!("{line}\n")).collect())
+    }
+
+    fn sandbox_mcp_request(
+        &self,
+        container_name: &str,
+        method: &str,
+        path: &str,
+    ) -> Result<String> {
+        if !self.container_exists(container_name)? {
+            return Err(Error::message(format!("no such container: {container_name}")));
+        }
+        self.state()
+            .mcp_responses
+            .get(&format!("{method} {path}"))
+            .cloned()
+            .ok_or_else(|| Error::message(format!("mcp-run returned 404 for {method} {path}")))
+    }
+
+    fn list_running_projects(&self) -> Result<Vec<RunningProject>> {
+        let mut projects: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for pod in &self.state().pods {
+            *projects
+                .entry((pod.project_name.clone(), pod.project_root.clone()))
+                .or_default() += 1;
+        }
+        Ok(projects
+            .into_iter()
+            .map(|((name, project_root), pod_count)| RunningProject {
+                name,
+                project_root,
+                pod_count,
+            })
+            .collect())
+    }
+
+    fn list_running_project_networks(&self) -> Result<Vec<RunningProjectNetwork>> {
+        let mut networks: BTreeMap<(String, String), String> = BTreeMap::new();
+        for pod in &self.state().pods {
+            networks
+                .entry((pod.project_name.clone(), pod.project_root.clone()))
+                .or_insert_with(|| pod.network.clone());
+        }
+        Ok(networks
+            .into_iter()
+            .map(|((name, project_root), network)| RunningProjectNetwork {
+                name,
+                project_root,
+                network,
+            })
+            .collect())
+    }
+
+    fn list_network_subnets(&self) -> Result<Vec<NetworkSubnet>> {
+        Ok(self
+            .state()
+            .networks
+            .iter()
+            .map(|(name, network)| NetworkSubnet {
+                name: name.clone(),
+                subnet: network.subnet.clone(),
+            })
+            .collect())
+    }
+
+    fn ensure_pool_network(
+        &self,
+        network_settings: &NetworkSettings,
+        internal: bool,
+    ) -> Result<EnsureNetworkOutcome> {
+        let mut state = self.state_mut();
+        let name = &network_settings.network;
+        if let Some(existing) = state.networks.get(name) {
+            if existing.subnet != network_settings.network_subnet {
+                return Ok(EnsureNetworkOutcome::SubnetMismatch);
+            }
+            if existing.internal != internal && state.pods.iter().any(|pod| &pod.network == name)
+            {
+                return Ok(EnsureNetworkOutcome::IsolationMismatch);
+            }
+        }
+        state.networks.insert(
+            name.clone(),
+            FakeNetwork {
+                subnet: network_settings.network_subnet.clone(),
+                internal,
+            },
+        );
+        Ok(EnsureNetworkOutcome::Ready)
+    }
+
+    fn ensure_egress_network(&self) -> Result<()> {
+        self.state_mut()
+            .networks
+            .entry(EGRESS_NETWORK.to_string())
+            .or_insert_with(|| FakeNetwork {
+                subnet: FAKE_EGRESS_SUBNET.to_string(),
+                internal: false,
+            });
+        Ok(())
+    }
+
+    fn network_is_internal(&self, network: &str) -> Result<bool> {
+        match self.state().networks.get(network) {
+            Some(network) => Ok(network.internal),
+            None => Err(Error::message(format!("network not found: {network}"))),
+        }
+    }
+
+    fn play_kube(&self, rendered: &str, network: &NetworkSettings, down: bool) -> Result<()> {
+        let pods = pods_in_rendered(rendered)?;
+        let mut state = self.state_mut();
+        if down {
+            state
+                .pods
+                .retain(|pod| !pods.iter().any(|(metadata, _)| metadata.name == pod.name));
+            return Ok(());
+        }
+        if !state.networks.contains_key(&network.network) {
+            return Err(Error::message(format!(
+                "network not found: {}",
+                network.network
+            )));
+        }
+        for (metadata, spec) in pods {
+            if state.pods.iter().any(|pod| pod.name == metadata.name) {
+                return Err(Error::message(format!("pod {} already exists", metadata.name)));
+            }
+            let label = |key: &str| metadata.labels.get(key).cloned().unwrap_or_default();
+            let containers = spec
+                .containers
+                .into_iter()
+                .map(|container| ContainerState {
+                    name: format!("{}-{}", metadata.name, container.name),
+                    state: "running".to_string(),
+                    image: container.image,
+                    restarts: 0,
+                    health: None,
+                })
+                .collect();
+            let pod = FakePod {
+                name: metadata.name.clone(),
+                project_name: label("cladding"),
+                project_root: label("project_root"),
+                network: network.network.clone(),
+                extra_networks: Vec::new(),
+                containers,
+            };
+            state.pods.push(pod);
+        }
+        Ok(())
+    }
+
+    fn pod_network_connect(&self, pod_name: &str, network: &str) -> Result<()> {
+        let mut state = self.state_mut();
+        if !state.networks.contains_key(network) {
+            return Err(Error::message(format!("network not found: {network}")));
+        }
+        let Some(pod) = state.pods.iter_mut().find(|pod| pod.name == pod_name) else {
+            return Err(Error::message(format!("no such pod: {pod_name}")));
+        };
+        pod.extra_networks.push(network.to_string());
+        Ok(())
+    }
+
+    fn network_containers(&self, network: &str) -> Result<Vec<String>> {
+        Ok(self
+            .state()
+            .pods
+            .iter()
+            .filter(|pod| {
+                pod.network == network || pod.extra_networks.iter().any(|extra| extra == network)
+            })
+            .map(|pod| pod.name.clone())
+            .collect())
+    }
+
+    fn remove_network(&self, network: &str) -> Result<()> {
+        if !self.network_containers(network)?.is_empty() {
+            return Err(Error::message(format!("network {network} is in use")));
+        }
+        if self.state_mut().networks.remove(network).is_none() {
+            return Err(Error::message(format!("network not found: {network}")));
+        }
+        Ok(())
+    }
+
+    fn force_remove(&self, names: &[&str]) -> Result<()> {
+        self.state_mut()
+            .pods
+            .retain(|pod| !names.contains(&pod.name.as_str()));
+        Ok(())
+    }
+
+    fn list_project_expose_proxies(
+        &self,
+        project_name: &str,
+        project_root: &str,
+        include_stopped: bool,
+    ) -> Result<Vec<ExposeProxy>> {
+        let mut proxies: Vec<ExposeProxy> = self
+            .state()
+            .expose_proxies
+            .iter()
+            .filter(|entry| {
+                entry.project_name == project_name && entry.project_root == project_root
+            })
+            .filter(|entry| include_stopped || entry.proxy.status.starts_with("Up"))
+            .map(|entry| entry.proxy.clone())
+            .collect();
+        proxies.sort_by_key(|proxy| (proxy.host_port, proxy.container_port));
+        Ok(proxies)
+    }
+
+    fn remove_containers(
+        &self,
+        container_ids: &[String],
+        _force: bool,
+        ignore_missing: bool,
+    ) -> Result<()> {
+        let mut state = self.state_mut();
+        for id in container_ids {
+            let before = state.expose_proxies.len();
+            state.expose_proxies.retain(|entry| &entry.proxy.id != id);
+            if state.expose_proxies.len() == before && !ignore_missing {
+                return Err(Error::message(format!("no such container: {id}")));
+            }
+        }
+        Ok(())
+    }
+
+    fn list_volumes(&self) -> Result<Vec<String>> {
+        Ok(self.state().volumes.iter().cloned().collect())
+    }
+
+    fn create_volume(&self, name: &str) -> Result<()> {
+        if !self.state_mut().volumes.insert(name.to_string()) {
+            return Err(Error::message(format!("volume {name} already exists")));
+        }
+        Ok(())
+    }
+
+    fn remove_volume(&self, name: &str) -> Result<()> {
+        if !self.state_mut().volumes.remove(name) {
+            return Err(Error::message(format!("no such volume: {name}")));
+        }
+        Ok(())
+    }
+
+    fn inspect_volume(&self, name: &str) -> Result<String> {
+        if !self.state().volumes.contains(name) {
+            return Err(Error::message(format!("no such volume: {name}")));
+        }
+        Ok(format!("[{{\"Name\": \"{name}\"}}]\n"))
+    }
+
+    fn volume_sizes(&self) -> Result<BTreeMap<String, u64>> {
+        let state = self.state();
+        Ok(state
+            .volumes
+            .iter()
+            .map(|name| {
+                let size = state.volume_sizes.get(name).copied().unwrap_or_default();
+                (name.clone(), size)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::resolve_network_settings;
+
+    #[test]
+    fn fake_pool_network_reports_mismatches_like_podman() {
+        let runtime = FakeRuntime::default();
+        let settings = resolve_network_settings("demo", 3).expect("settings");
+        assert_eq!(
+            runtime.ensure_pool_network(&settings, false).expect("create"),
+            EnsureNetworkOutcome::Ready
+        );
+        runtime.state_mut().pods.push(FakePod {
+            name: "demo-proxy-pod".to_string(),
+            project_name: "demo".to_string(),
+            project_root: "/tmp/demo/.cladding".to_string(),
+            network: settings.network.clone(),
+            extra_networks: Vec::new(),
+            containers: Vec::new(),
+        });
+        assert_eq!(
+            runtime.ensure_pool_network(&settings, true).expect("in use"),
+            EnsureNetworkOutcome::IsolationMismatch
+        );
+
+        runtime
+            .state_mut()
+            .networks
+            .get_mut(&settings.network)
+            .expect("network")
+            .subnet = "10.1.2.0/24".to_string();
+        assert_eq!(
+            runtime.ensure_pool_network(&settings, false).expect("mismatch"),
+            EnsureNetworkOutcome::SubnetMismatch
+        );
+    }
+}
@@ -0,0 +1,140 @@
+/// Directory inside the cli container holding each service's pid, command and log file.
+pub const SERVICE_DIR: &str = "/tmp/cladding-services";
+
+/// Exit status the service scripts use when the named service is unknown or already running.
+pub const SERVICE_CONFLICT_EXIT: i32 = 3;
+
+/// Fails with [`SERVICE_CONFLICT_EXIT`] if `$2` is still running, otherwise prepares `$1`.
+pub const SERVICE_PREPARE_SCRIPT: &str = r#"dir=$1; name=$2
+mkdir -p "$dir" || exit 1
+if [ -f "$dir/$name.pid" ] && kill -0 "$(cat "$dir/$name.pid")" 2>/dev/null; then
+  exit 3
+fi
+rm -f "$dir/$name.pid" "$dir/$name.cmd""#;
+
+/// Starts `$3...` in its own session so `stop` can signal the whole process group.
+pub const SERVICE_START_SCRIPT: &str = r#"dir=$1; name=$2; shift 2
+printf '%s\n' "$*" >"$dir/$name.cmd"
+if command -v setsid >/dev/null 2>&1; then
+  setsid "$@" </dev/null >"$dir/$name.log" 2>&1 &
+else
+  "$@" </dev/null >"$dir/$name.log" 2>&1 &
+fi
+echo $! >"$dir/$name.pid""#;
+
+/// Sends SIGTERM to the service's process group; the log is kept for `cladding logs`.
+pub const SERVICE_STOP_SCRIPT: &str = r#"dir=$1; name=$2
+[ -f "$dir/$name.pid" ] || exit 3
+pid=$(cat "$dir/$name.pid")
+kill -TERM "-$pid" 2>/dev/null || kill -TERM "$pid" 2>/dev/null
+rm -f "$dir/$name.pid" "$dir/$name.cmd""#;
+
+/// Prints one `name<TAB>state<TAB>pid<TAB>command` line per tracked service.
+pub const SERVICE_LIST_SCRIPT: &str = r#"dir=$1
+for pidfile in "$dir"/*.pid; do
+  [ -f "$pidfile" ] || continue
+  name=${pidfile##*/}; name=${name%.pid}
+  pid=$(cat "$pidfile")
+  if kill -0 "$pid" 2>/dev/null; then state=running; else state=exited; fi
+  printf '%s\t%s\t%s\t%s\n' "$name" "$state" "$pid" "$(cat "$dir/$name.cmd" 2>/dev/null)"
+done"#;
+
+/// Prints the service log from the start, following it when `$3` is non-empty.
+pub const SERVICE_LOGS_SCRIPT: &str = r#"log="$1/$2.log"
+[ -f "$log" ] || exit 3
+if [ -n "$3" ]; then exec tail -n +1 -f "$log"; fi
+exec tail -n +1 "$log""#;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceEntry {
+    pub name: String,
+    pub running: bool,
+    pub pid: u32,
+    pub command: String,
+}
+
+/// Service names become file names in [`SERVICE_DIR`], so only a conservative charset is allowed.
+pub fn validate_service_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("service name must not be empty".to_string());
+    }
+    if name.starts_with(['.', '-']) {
+        return Err(format!(
+            "invalid service name '{name}': must not start with '.' or '-'"
+        ));
+    }
+    if let Some(ch) = name
+        .chars()
+        .find(|ch| !(ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | '-')))
+    {
+        return Err(format!(
+            "invalid service name '{name}': unexpected character '{ch}'"
+        ));
+    }
+    Ok(())
+}
+
+pub fn parse_service_list(output: &str) -> Vec<ServiceEntry> {
+    let mut entries: Vec<ServiceEntry> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let name = fields.next()?.to_string();
+            let running = match fields.next()? {
+                "running" => true,
+                "exited" => false,
+                _ => return None,
+            };
+            let pid = fields.next()?.trim().parse().ok()?;
+            let command = fields.next().unwrap_or_default().to_string();
+            Some(ServiceEntry {
+                name,
+                running,
+                pid,
+                command,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_names_are_restricted_to_safe_file_names() {
+        assert!(validate_service_name("web").is_ok());
+        assert!(validate_service_name("api_v2.dev-server").is_ok());
+        assert!(validate_service_name("").is_err());
+        assert!(validate_service_name("../etc").is_err());
+        assert!(validate_service_name("-f").is_err());
+        assert!(validate_service_name("a/b").is_err());
+        assert!(validate_service_name("a b").is_err());
+    }
+
+    #[test]
+    fn parse_service_list_reads_tab_separated_entries() {
+        let output = "web\trunning\t42\tnpm run dev -- --port 5173\n\
+                      api\texited\t17\tcargo run\n\
+                      garbage line\n";
+        assert_eq!(
+            parse_service_list(output),
+            vec![
+                ServiceEntry {
+                    name: "api".to_string(),
+                    running: false,
+                    pid: 17,
+                    command: "cargo run".to_string(),
+                },
+                ServiceEntry {
+                    name: "web".to_string(),
+                    running: true,
+                    pid: 42,
+                    command: "npm run dev -- --port 5173".to_string(),
+                },
+            ]
+        );
+    }
+}
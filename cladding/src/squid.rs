@@ -0,0 +1,500 @@
+use crate::error::{Error, Result};
+use crate::network::ipv4_to_int;
+use crate::output;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessRule {
+    pub line: usize,
+    pub text: String,
+    pub allow: bool,
+    conditions: Vec<(bool, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyRequest {
+    pub source_ip: String,
+    pub host: String,
+    pub port: u16,
+    pub method: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessDecision {
+    pub allowed: bool,
+    /// The `http_access` line that decided the request, or `None` for squid's implicit default.
+    pub rule: Option<AccessRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyTestUrl {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+}
+
+/// A request squid refused, from an access log line in its native format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyDenial {
+    /// Seconds since the epoch, as logged.
+    pub timestamp: String,
+    pub source_ip: String,
+    pub method: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone)]
+enum AclKind {
+    Src,
+    DstDomain,
+    Port,
+    Method,
+    Unsupported,
+}
+
+#[derive(Debug, Clone)]
+struct AclDef {
+    kind: AclKind,
+    values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SquidAccessConfig {
+    acls: HashMap<String, Vec<AclDef>>,
+    rules: Vec<AccessRule>,
+}
+
+impl SquidAccessConfig {
+    /// Parses `acl` and `http_access` directives. Quoted ACL values name files;
+    /// `resolve_file` maps them to their entries (or `None` if unavailable).
+    pub fn parse(contents: &str, resolve_file: impl Fn(&str) -> Option<Vec<String>>) -> Self {
+        let mut config = SquidAccessConfig::default();
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("acl") => {
+                    let (Some(name), Some(kind)) = (tokens.next(), tokens.next()) else {
+                        continue;
+                    };
+                    let kind = match kind {
+                        "src" => AclKind::Src,
+                        "dstdomain" => AclKind::DstDomain,
+                        "port" => AclKind::Port,
+                        "method" => AclKind::Method,
+                        _ => AclKind::Unsupported,
+                    };
+                    let mut values = Vec::new();
+                    for token in tokens {
+                        match token.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+                            Some(path) => values.extend(resolve_file(path).unwrap_or_default()),
+                            None => values.push(token.to_string()),
+                        }
+                    }
+                    config
+                        .acls
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(AclDef { kind, values });
+                }
+                Some("http_access") => {
+                    let allow = match tokens.next() {
+                        Some("allow") => true,
+                        Some("deny") => false,
+                        _ => continue,
+                    };
+                    let conditions = tokens
+                        .map(|token| match token.strip_prefix('!') {
+                            Some(name) => (true, name.to_string()),
+                            None => (false, token.to_string()),
+                        })
+                        .collect();
+                    config.rules.push(AccessRule {
+                        line: index + 1,
+                        text: line.to_string(),
+                        allow,
+                        conditions,
+                    });
+                }
+                _ => {}
+            }
+        }
+        config
+    }
+
+    pub fn evaluate(&self, request: &ProxyRequest) -> AccessDecision {
+        for rule in &self.rules {
+            let matched = rule
+                .conditions
+                .iter()
+                .all(|(negated, name)| self.acl_matches(name, request) != *negated);
+            if matched {
+                return AccessDecision {
+                    allowed: rule.allow,
+                    rule: Some(rule.clone()),
+                };
+            }
+        }
+
+        // Squid applies the opposite of the last http_access line when nothing matches.
+        AccessDecision {
+            allowed: self.rules.last().map(|rule| !rule.allow).unwrap_or(false),
+            rule: None,
+        }
+    }
+
+    fn acl_matches(&self, name: &str, request: &ProxyRequest) -> bool {
+        if name == "all" {
+            return true;
+        }
+        let Some(defs) = self.acls.get(name) else {
+            return false;
+        };
+        defs.iter().any(|def| {
+            def.values.iter().any(|value| match def.kind {
+                AclKind::Src => src_matches(value, &request.source_ip),
+                AclKind::DstDomain => dstdomain_matches(value, &request.host),
+                AclKind::Port => port_matches(value, request.port),
+                AclKind::Method => value.eq_ignore_ascii_case(&request.method),
+                AclKind::Unsupported => false,
+            })
+        })
+    }
+}
+
+/// Reads a squid list file, dropping blank lines and comments.
+pub fn parse_list_file(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn parse_proxy_test_url(url: &str) -> Result<ProxyTestUrl> {
+    let invalid = || {
+        output::error(format!("invalid url '{url}' (expected http://host[:port]/... or https://...)"));
+        Error::message("invalid url")
+    };
+
+    let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => 80,
+        "https" => 443,
+        _ => return Err(invalid()),
+    };
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse::<u16>().map_err(|_| invalid())?),
+        None => (authority, default_port),
+    };
+    if host.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(ProxyTestUrl {
+        scheme,
+        host: host.to_ascii_lowercase(),
+        port,
+    })
+}
+
+/// Normalizes a `dstdomain` entry (`example.com`, or `.example.com` for subdomains too).
+pub fn parse_domain_entry(domain: &str) -> Result<String> {
+    let invalid = |detail: &str| {
+        output::error(format!("invalid domain '{domain}' ({detail})"));
+        output::hint("use a bare host name such as example.com, or .example.com to include subdomains");
+        Error::message("invalid domain")
+    };
+
+    let entry = domain.trim().to_ascii_lowercase();
+    if entry.contains("://") || entry.contains(['/', ':', '@']) {
+        return Err(invalid("expected a host name, not a URL"));
+    }
+    let host = entry.strip_prefix('.').unwrap_or(&entry);
+    if host.is_empty() || host.len() > 253 {
+        return Err(invalid("expected 1-253 characters"));
+    }
+    for label in host.split('.') {
+        let valid = !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-');
+        if !valid {
+            return Err(invalid(
+                "each label must be 1-63 letters, digits or inner hyphens",
+            ));
+        }
+    }
+    Ok(entry)
+}
+
+/// Appends `entry` to a domain list, or returns `None` if a line already allows it.
+pub fn add_domain_entry(contents: &str, entry: &str) -> Option<String> {
+    if parse_list_file(contents)
+        .iter()
+        .any(|existing| existing.eq_ignore_ascii_case(entry))
+    {
+        return None;
+    }
+    let mut updated = contents.to_string();
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(entry);
+    updated.push('\n');
+    Some(updated)
+}
+
+/// Removes every line equal to `entry`, or returns `None` if the list does not contain it.
+/// Comments and other entries are kept as they are.
+pub fn remove_domain_entry(contents: &str, entry: &str) -> Option<String> {
+    let mut removed = false;
+    let mut updated = String::with_capacity(contents.len());
+    for line in contents.split_inclusive('\n') {
+        if line.trim().eq_ignore_ascii_case(entry) {
+            removed = true;
+        } else {
+            updated.push_str(line);
+        }
+    }
+    removed.then_some(updated)
+}
+
+/// Entries other than `entry` itself that already match `entry`'s host.
+pub fn covering_domain_entries(contents: &str, entry: &str) -> Vec<String> {
+    let host = entry.trim_start_matches('.');
+    parse_list_file(contents)
+        .into_iter()
+        .filter(|existing| !existing.eq_ignore_ascii_case(entry))
+        .filter(|existing| dstdomain_matches(existing, host))
+        .collect()
+}
+
+/// Denied requests in `log`, oldest first. Lines that are not access log entries (squid's
+/// cache log shares the container output) are skipped.
+pub fn parse_access_log_denials(log: &str) -> Vec<ProxyDenial> {
+    log.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [timestamp, _elapsed, source_ip, result, _size, method, url, ..] = fields[..]
+            else {
+                return None;
+            };
+            if !result.starts_with("TCP_DENIED/") || timestamp.parse::<f64>().is_err() {
+                return None;
+            }
+            Some(ProxyDenial {
+                timestamp: timestamp.to_string(),
+                source_ip: source_ip.to_string(),
+                method: method.to_string(),
+                url: url.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Proxy port the pods' `http_proxy` settings use, matching the template squid.conf.
+pub const DEFAULT_PROXY_PORT: u16 = 8080;
+
+/// Port of the first `http_port` directive, which may be `PORT` or `ADDRESS:PORT`.
+pub fn parse_http_port(contents: &str) -> Option<u16> {
+    contents.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next() != Some("http_port") {
+            return None;
+        }
+        fields.next()?.rsplit(':').next()?.parse().ok()
+    })
+}
+
+fn src_matches(value: &str, source_ip: &str) -> bool {
+    let Some(source) = ipv4_to_int(source_ip) else {
+        return false;
+    };
+    match value.split_once('/') {
+        Some((network, prefix)) => {
+            let (Some(network), Ok(prefix)) = (ipv4_to_int(network), prefix.parse::<u32>())
+            else {
+                return false;
+            };
+            if prefix > 32 {
+                return false;
+            }
+            let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+            source & mask == network & mask
+        }
+        None => ipv4_to_int(value) == Some(source),
+    }
+}
+
+fn dstdomain_matches(value: &str, host: &str) -> bool {
+    let value = value.to_ascii_lowercase();
+    match value.strip_prefix('.') {
+        Some(domain) => host == domain || host.ends_with(&value),
+        None => host == value,
+    }
+}
+
+fn port_matches(value: &str, port: u16) -> bool {
+    match value.split_once('-') {
+        Some((low, high)) => match (low.parse::<u16>(), high.parse::<u16>()) {
+            (Ok(low), Ok(high)) => (low..=high).contains(&port),
+            _ => false,
+        },
+        None => value.parse::<u16>() == Ok(port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+acl cli_src src "/tmp/cli_ips.lst"
+acl sandbox_src src "/tmp/sandbox_ips.lst"
+acl cli_domains dstdomain "/opt/config/cli_domains.lst"
+acl SSL_ports port 443
+acl Safe_ports port 80
+acl Safe_ports port 443
+acl CONNECT method CONNECT
+
+http_access deny !Safe_ports
+http_access deny CONNECT !SSL_ports
+http_access allow cli_src cli_domains
+http_access deny all
+"#;
+
+    fn config() -> SquidAccessConfig {
+        SquidAccessConfig::parse(CONFIG, |path| match path {
+            "/tmp/cli_ips.lst" => Some(vec!["10.90.1.4".into()]),
+            "/tmp/sandbox_ips.lst" => Some(vec!["10.90.1.3".into()]),
+            "/opt/config/cli_domains.lst" => {
+                Some(parse_list_file("# comment\n.example.com\napi.test\n"))
+            }
+            _ => None,
+        })
+    }
+
+    fn request(source_ip: &str, host: &str, port: u16, method: &str) -> ProxyRequest {
+        ProxyRequest {
+            source_ip: source_ip.into(),
+            host: host.into(),
+            port,
+            method: method.into(),
+        }
+    }
+
+    #[test]
+    fn evaluate_reports_matching_rule() {
+        let config = config();
+
+        let allowed = config.evaluate(&request("10.90.1.4", "www.example.com", 443, "CONNECT"));
+        assert!(allowed.allowed);
+        assert_eq!(
+            allowed.rule.map(|rule| rule.text),
+            Some("http_access allow cli_src cli_domains".to_string())
+        );
+
+        let wrong_source = config.evaluate(&request("10.90.1.3", "example.com", 443, "CONNECT"));
+        assert!(!wrong_source.allowed);
+        assert_eq!(wrong_source.rule.map(|rule| rule.line), Some(13));
+
+        let bad_port = config.evaluate(&request("10.90.1.4", "example.com", 8443, "CONNECT"));
+        assert_eq!(
+            bad_port.rule.map(|rule| rule.text),
+            Some("http_access deny !Safe_ports".to_string())
+        );
+
+        let exact_only = config.evaluate(&request("10.90.1.4", "v2.api.test", 80, "GET"));
+        assert!(!exact_only.allowed);
+    }
+
+    #[test]
+    fn parse_proxy_test_url_defaults_ports() {
+        assert_eq!(
+            parse_proxy_test_url("https://user@Example.com/path?q=1").unwrap(),
+            ProxyTestUrl {
+                scheme: "https".into(),
+                host: "example.com".into(),
+                port: 443,
+            }
+        );
+        assert_eq!(parse_proxy_test_url("http://host:8080").unwrap().port, 8080);
+        assert!(parse_proxy_test_url("ftp://host").is_err());
+        assert!(parse_proxy_test_url("example.com").is_err());
+    }
+
+    #[test]
+    fn parse_domain_entry_validates_syntax() {
+        assert_eq!(parse_domain_entry(" Example.COM ").unwrap(), "example.com");
+        assert_eq!(parse_domain_entry(".api.test").unwrap(), ".api.test");
+        assert!(parse_domain_entry("https://example.com").is_err());
+        assert!(parse_domain_entry("example.com:443").is_err());
+        assert!(parse_domain_entry("-bad.com").is_err());
+        assert!(parse_domain_entry("a..b").is_err());
+        assert!(parse_domain_entry(".").is_err());
+    }
+
+    #[test]
+    fn domain_entries_are_added_and_removed_in_place() {
+        let list = "# models\napi.test\n.example.com";
+        let added = add_domain_entry(list, "new.test").unwrap();
+        assert_eq!(added, "# models\napi.test\n.example.com\nnew.test\n");
+        assert_eq!(add_domain_entry(&added, "API.test"), None);
+
+        let removed = remove_domain_entry(&added, "api.test").unwrap();
+        assert_eq!(removed, "# models\n.example.com\nnew.test\n");
+        assert_eq!(remove_domain_entry(&removed, "api.test"), None);
+
+        assert_eq!(
+            covering_domain_entries(list, "www.example.com"),
+            vec![".example.com".to_string()]
+        );
+        assert!(covering_domain_entries(list, "api.test").is_empty());
+    }
+
+    #[test]
+    fn access_log_denials_are_parsed() {
+        let log = "\
+2026/01/05 09:14:03| Accepting HTTP Socket connections at conn3 local=[::]:8080
+1767604443.120    312 10.90.0.3 TCP_TUNNEL/200 4620 CONNECT github.com:443 - HIER_DIRECT/140.82.121.4 -
+1767604450.002      0 10.90.0.2 TCP_DENIED/403 3920 CONNECT evil.example:443 - HIER_NONE/- text/html
+";
+        assert_eq!(
+            parse_access_log_denials(log),
+            vec![ProxyDenial {
+                timestamp: "1767604450.002".to_string(),
+                source_ip: "10.90.0.2".to_string(),
+                method: "CONNECT".to_string(),
+                url: "evil.example:443".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn http_port_is_read_from_config() {
+        assert_eq!(parse_http_port("# proxy\nhttp_port 8080\n"), Some(8080));
+        assert_eq!(parse_http_port("http_port 127.0.0.1:3128 intercept\n"), Some(3128));
+        assert_eq!(parse_http_port("http_access deny all\n"), None);
+    }
+
+    #[test]
+    fn src_matches_cidr_ranges() {
+        assert!(src_matches("10.90.0.0/16", "10.90.3.4"));
+        assert!(!src_matches("10.90.0.0/24", "10.90.3.4"));
+        assert!(src_matches("0.0.0.0/0", "1.2.3.4"));
+    }
+}
@@ -0,0 +1,268 @@
+//! `cladding support-bundle`: the files a bug report needs, packed into one tarball with
+//! secrets removed. Values of `env`, `sandbox_env` and sidecar `env` entries are taken out of
+//! every file, as is anything assigned to a name that looks like a token, password or key.
+
+use crate::error::{Error, Result};
+use anyhow::Context as _;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// What a removed secret is replaced with.
+pub const REDACTED: &str = "[REDACTED]";
+/// Shorter env values, such as `1` or `on`, are left in place so the bundle stays readable.
+const MIN_SECRET_LEN: usize = 4;
+/// Parts of a variable or key name whose value is always redacted.
+const SECRET_NAME_PARTS: [&str; 9] = [
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "PASSPHRASE",
+    "API_KEY",
+    "APIKEY",
+    "PRIVATE_KEY",
+    "CREDENTIAL",
+];
+
+/// Files for the bundle, by path inside it, and the secrets removed from all of them.
+#[derive(Debug, Default)]
+pub struct SupportBundle {
+    files: BTreeMap<String, String>,
+    secrets: BTreeSet<String>,
+}
+
+impl SupportBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `cladding.json` with every `env` value and secret-named value redacted, and
+    /// remembers those values so they are removed from the other files too.
+    pub fn add_cladding_json(&mut self, raw: &str) {
+        let contents = match serde_json::from_str::<Value>(raw) {
+            Ok(mut value) => {
+                redact_json(&mut value, false, &mut self.secrets);
+                let mut contents =
+                    serde_json::to_string_pretty(&value).unwrap_or_else(|_| raw.to_string());
+                contents.push('\n');
+                contents
+            }
+            Err(_) => raw.to_string(),
+        };
+        self.add("cladding.json", contents);
+    }
+
+    pub fn add(&mut self, name: &str, contents: impl Into<String>) {
+        self.files.insert(name.to_string(), contents.into());
+    }
+
+    /// The files as they are written: each redacted with [`redact_text`].
+    pub fn redacted_files(&self) -> BTreeMap<String, String> {
+        self.files
+            .iter()
+            .map(|(name, contents)| (name.clone(), redact_text(contents, &self.secrets)))
+            .collect()
+    }
+
+    /// Writes the redacted files below a `root` directory into a gzipped tarball at `path`,
+    /// with the system `tar`.
+    pub fn write_tarball(&self, path: &Path, root: &str) -> Result<()> {
+        let staging = std::env::temp_dir().join(format!(
+            "cladding-support-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        let result = self.write_tarball_from(&staging, path, root);
+        let _ = fs::remove_dir_all(&staging);
+        result
+    }
+
+    fn write_tarball_from(&self, staging: &Path, path: &Path, root: &str) -> Result<()> {
+        for (name, contents) in self.redacted_files() {
+            let file = staging.join(root).join(&name);
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create {}", parent.display()))?;
+            }
+            fs::write(&file, contents)
+                .with_context(|| format!("failed to write {}", file.display()))?;
+        }
+        let status = Command::new("tar")
+            .arg("-czf")
+            .arg(path)
+            .arg("-C")
+            .arg(staging)
+            .arg(root)
+            .status()
+            .with_context(|| "failed to run tar")?;
+        if !status.success() {
+            return Err(Error::message(format!(
+                "tar failed to write {}",
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Whether values assigned to `name` are secrets, e.g. `MCP_AUTH_TOKEN` or `db-password`.
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_uppercase().replace('-', "_");
+    SECRET_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// Replaces strings below an `env` or `sandbox_env` object or a secret-named key, collecting
+/// the originals.
+fn redact_json(value: &mut Value, redact_strings: bool, secrets: &mut BTreeSet<String>) {
+    match value {
+        Value::String(text) if redact_strings && text.len() >= MIN_SECRET_LEN => {
+            secrets.insert(std::mem::replace(text, REDACTED.to_string()));
+        }
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let redact = redact_strings
+                    || key == "env"
+                    || key == "sandbox_env"
+                    || is_secret_name(key);
+                redact_json(value, redact, secrets);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact_json(item, redact_strings, secrets);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `text` without the `secrets`, bearer tokens, and values assigned to secret-named
+/// variables or keys (`NAME=value`, `NAME: value`, `"NAME": "value"`).
+pub fn redact_text(text: &str, secrets: &BTreeSet<String>) -> String {
+    let mut text = text.to_string();
+    // Longest first, so a secret containing another is removed whole.
+    let mut secrets: Vec<&String> = secrets.iter().collect();
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    for secret in secrets {
+        text = text.replace(secret.as_str(), REDACTED);
+    }
+    let mut redacted = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        redacted.push_str(&redact_assignments(&redact_bearer(line)));
+    }
+    redacted
+}
+
+fn redact_bearer(line: &str) -> String {
+    let mut out = String::new();
+    let mut rest = line;
+    while let Some(index) = rest.find("Bearer ") {
+        let (before, after) = rest.split_at(index + "Bearer ".len());
+        out.push_str(before);
+        let end = after
+            .find(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+            .unwrap_or(after.len());
+        if end > 0 {
+            out.push_str(REDACTED);
+        }
+        rest = &after[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn redact_assignments(line: &str) -> String {
+    let mut out = String::new();
+    let mut rest = line;
+    while let Some(index) = rest.find(['=', ':']) {
+        let (before, after) = rest.split_at(index + 1);
+        out.push_str(before);
+        let name = before[..index]
+            .trim_end_matches(['"', '\'', ' '])
+            .rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .next()
+            .unwrap_or_default();
+        rest = after;
+        if !is_secret_name(name) {
+            continue;
+        }
+        let value = after.trim_start_matches([' ', '"', '\'']);
+        out.push_str(&after[..after.len() - value.len()]);
+        let end = value
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ','))
+            .unwrap_or(value.len());
+        if end > 0 && !value.starts_with(REDACTED) {
+            out.push_str(REDACTED);
+            rest = &value[end..];
+        } else {
+            rest = value;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cladding_json_env_values_are_removed_everywhere() {
+        let mut bundle = SupportBundle::new();
+        bundle.add_cladding_json(
+            r#"{"name":"demo","sandbox_env":{"RUST_LOG":"debug"},"sidecars":[{"name":"db","image":"postgres:16","env":{"POSTGRES_PASSWORD":"hunter22","PGPORT":"5432","DEBUG":"1"}}]}"#,
+        );
+        bundle.add("logs/db.txt", "connecting with hunter22 on 5432\n");
+        let files = bundle.redacted_files();
+
+        let json: Value = serde_json::from_str(&files["cladding.json"]).unwrap();
+        assert_eq!(json["name"], "demo");
+        assert_eq!(json["sidecars"][0]["image"], "postgres:16");
+        assert_eq!(json["sidecars"][0]["env"]["POSTGRES_PASSWORD"], REDACTED);
+        assert_eq!(json["sidecars"][0]["env"]["DEBUG"], "1");
+        assert_eq!(json["sandbox_env"]["RUST_LOG"], REDACTED);
+        assert_eq!(
+            files["logs/db.txt"],
+            "connecting with [REDACTED] on [REDACTED]\n"
+        );
+    }
+
+    #[test]
+    fn secret_assignments_and_bearer_tokens_are_redacted() {
+        let secrets = BTreeSet::new();
+        assert_eq!(
+            redact_text(
+                "MCP_AUTH_TOKEN=abc123 HOME=/home/me\n\"api-key\": \"k-1\", \"url\": \"http://proxy:3128\"\nAuthorization: Bearer eyJhbGci\n",
+                &secrets
+            ),
+            "MCP_AUTH_TOKEN=[REDACTED] HOME=/home/me\n\"api-key\": \"[REDACTED]\", \"url\": \"http://proxy:3128\"\nAuthorization: Bearer [REDACTED]\n"
+        );
+        assert!(is_secret_name("db-password"));
+        assert!(!is_secret_name("PGPORT"));
+    }
+
+    #[test]
+    fn tarball_holds_the_redacted_files_below_the_root() {
+        let dir = std::env::temp_dir().join(format!("cladding-support-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut bundle = SupportBundle::new();
+        bundle.add("logs/proxy.txt", "SQUID_PASSWORD=x1y2\n");
+        let path = dir.join("bundle.tar.gz");
+        bundle.write_tarball(&path, "support").unwrap();
+
+        let listing = Command::new("tar").arg("-xzOf").arg(&path).output().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&listing.stdout),
+            "SQUID_PASSWORD=[REDACTED]\n"
+        );
+        let names = Command::new("tar").arg("-tzf").arg(&path).output().unwrap();
+        assert!(String::from_utf8_lossy(&names.stdout).contains("support/logs/proxy.txt"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,13 @@
-use cladding::config::Config;
+use cladding::config::{AgentConfig, Config};
 use cladding::config::MountConfig;
+use cladding::config::{SidecarConfig, SidecarMount};
 use cladding::network::resolve_network_settings;
-use cladding::pods::render_pods_yaml;
+use cladding::pods::{
+    pod_names_from_rendered, proxy_image, render_pods_yaml, select_pod_from_rendered,
+};
 use serde::Deserialize;
 use serde_yaml::Value;
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -15,13 +19,159 @@ fn render_pods_yaml_replaces_placeholders() {
         sandbox_image: "sandbox:image".to_string(),
         cli_image: "cli:image".to_string(),
         mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: Vec::new(),
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
     };
-    let rendered = render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings);
+    let rendered =
+        render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings).unwrap();
 
     assert!(!rendered.contains("REPLACE_PROXY_POD_NAME"));
     assert!(!rendered.contains("REPLACE_CLI_IMAGE"));
     assert!(rendered.contains("demo-proxy-pod"));
     assert!(rendered.contains("sandbox:image"));
+    assert!(rendered.contains(&proxy_image().expect("proxy image")));
+}
+
+#[test]
+fn render_pods_yaml_rejects_a_substitution_that_breaks_the_yaml() {
+    let settings = resolve_network_settings("demo", 1).unwrap();
+    let config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image\n  bad: [".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: vec![".env".to_string()],
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
+    };
+    let error = render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings)
+        .unwrap_err();
+    assert!(error.to_string().contains("not valid YAML"), "{error}");
+}
+
+#[test]
+fn select_pod_keeps_one_pod_and_shared_documents() {
+    let settings = resolve_network_settings("demo", 1).unwrap();
+    let config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: Vec::new(),
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
+    };
+    let rendered =
+        render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings).unwrap();
+    assert_eq!(
+        pod_names_from_rendered(&rendered),
+        ["demo-proxy-pod", "demo-sandbox-pod", "demo-cli-pod"]
+    );
+
+    let selected = select_pod_from_rendered(&rendered, "demo-cli-pod").unwrap();
+    assert_eq!(pod_names_from_rendered(&selected), ["demo-cli-pod"]);
+    assert!(selected.contains("kind: ConfigMap"));
+    assert!(select_pod_from_rendered(&rendered, "demo-other-pod").is_none());
+}
+
+#[test]
+fn render_pods_yaml_sets_restart_policy_and_liveness_probes() {
+    let settings = resolve_network_settings("demo", 1).unwrap();
+    let config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: Vec::new(),
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
+    };
+    let rendered =
+        render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings).unwrap();
+    let docs = serde_yaml::Deserializer::from_str(&rendered)
+        .map(Value::deserialize)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .unwrap();
+
+    let pods: Vec<&Value> = docs.iter().filter(|doc| doc["kind"] == "Pod").collect();
+    assert_eq!(pods.len(), 3);
+    for pod in &pods {
+        assert_eq!(pod["spec"]["restartPolicy"], "Always");
+    }
+
+    let probed: Vec<&str> = pods
+        .iter()
+        .filter_map(|pod| pod["spec"]["containers"].as_sequence())
+        .flatten()
+        .filter(|container| container.get("livenessProbe").is_some())
+        .filter_map(|container| container["name"].as_str())
+        .collect();
+    assert_eq!(probed, vec!["proxy", "sandbox-app"]);
+}
+
+#[test]
+fn render_pods_yaml_passes_isolation_to_proxy() {
+    let settings = resolve_network_settings("demo", 1).unwrap();
+    let mut config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: Vec::new(),
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
+    };
+    let proxy_isolated = |config: &Config| {
+        let rendered =
+            render_pods_yaml(Path::new("/tmp/project/.cladding"), config, &settings).unwrap();
+        serde_yaml::Deserializer::from_str(&rendered)
+            .map(Value::deserialize)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap()
+            .iter()
+            .filter_map(|doc| doc["spec"]["containers"].as_sequence())
+            .flatten()
+            .filter(|container| container["name"] == "proxy")
+            .filter_map(|container| container["env"].as_sequence())
+            .flatten()
+            .find(|var| var["name"] == "CLADDING_ISOLATED")
+            .and_then(|var| var["value"].as_str().map(str::to_string))
+    };
+    assert_eq!(proxy_isolated(&config).as_deref(), Some("0"));
+
+    config.isolated = true;
+    assert_eq!(proxy_isolated(&config).as_deref(), Some("1"));
 }
 
 fn container_mount_paths(rendered: &str, container_name: &str) -> Vec<String> {
@@ -35,14 +185,14 @@ fn container_mount_paths(rendered: &str, container_name: &str) -> Vec<String> {
         let Some(mapping) = doc.as_mapping() else {
             continue;
         };
-        let Some(spec) = mapping.get(&Value::String("spec".into())) else {
+        let Some(spec) = mapping.get(Value::String("spec".into())) else {
             continue;
         };
         let Some(spec_mapping) = spec.as_mapping() else {
             continue;
         };
         let Some(containers) = spec_mapping
-            .get(&Value::String("containers".into()))
+            .get(Value::String("containers".into()))
             .and_then(Value::as_sequence)
         else {
             continue;
@@ -52,7 +202,7 @@ fn container_mount_paths(rendered: &str, container_name: &str) -> Vec<String> {
                 continue;
             };
             let Some(name) = container_mapping
-                .get(&Value::String("name".into()))
+                .get(Value::String("name".into()))
                 .and_then(Value::as_str)
             else {
                 continue;
@@ -61,7 +211,7 @@ fn container_mount_paths(rendered: &str, container_name: &str) -> Vec<String> {
                 continue;
             }
             let Some(mounts) = container_mapping
-                .get(&Value::String("volumeMounts".into()))
+                .get(Value::String("volumeMounts".into()))
                 .and_then(Value::as_sequence)
             else {
                 continue;
@@ -71,7 +221,7 @@ fn container_mount_paths(rendered: &str, container_name: &str) -> Vec<String> {
                     continue;
                 };
                 let Some(path) = mount_mapping
-                    .get(&Value::String("mountPath".into()))
+                    .get(Value::String("mountPath".into()))
                     .and_then(Value::as_str)
                 else {
                     continue;
@@ -98,11 +248,279 @@ fn sandbox_only_mounts_skip_cli() {
             read_only: true,
             sandbox_only: true,
         }],
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: Vec::new(),
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
     };
-    let rendered = render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings);
+    let rendered =
+        render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings).unwrap();
     let sandbox_mounts = container_mount_paths(&rendered, "sandbox-app");
     let cli_mounts = container_mount_paths(&rendered, "cli-app");
 
     assert!(sandbox_mounts.contains(&"/opt/sandbox-only".to_string()));
     assert!(!cli_mounts.contains(&"/opt/sandbox-only".to_string()));
 }
+
+#[test]
+fn sidecars_render_into_cli_pod_only() {
+    let settings = resolve_network_settings("demo", 1).unwrap();
+    let config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: vec![SidecarConfig {
+            name: "postgres".to_string(),
+            image: "docker.io/library/postgres:16".to_string(),
+            env: BTreeMap::from([("POSTGRES_PASSWORD".to_string(), "dev".to_string())]),
+            mounts: vec![SidecarMount {
+                mount_path: "/var/lib/postgresql/data".to_string(),
+                host_path: None,
+                volume: Some("pgdata".to_string()),
+                read_only: false,
+            }],
+            ports: vec![5432],
+        }],
+        workspace_excludes: Vec::new(),
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
+    };
+    let rendered =
+        render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings).unwrap();
+    let docs = serde_yaml::Deserializer::from_str(&rendered)
+        .map(Value::deserialize)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .expect("rendered pods.yaml parses");
+
+    let mut found = 0;
+    for doc in &docs {
+        let pod_name = doc["metadata"]["name"].as_str().expect("pod name");
+        let Some(containers) = doc["spec"]["containers"].as_sequence() else {
+            continue;
+        };
+        let sidecar = containers
+            .iter()
+            .find(|container| container["name"].as_str() == Some("postgres"));
+        if pod_name != settings.cli_pod_name {
+            assert!(sidecar.is_none(), "{pod_name}");
+            continue;
+        }
+        let sidecar = sidecar.expect("sidecar in cli pod");
+        found += 1;
+        assert_eq!(
+            sidecar["image"].as_str(),
+            Some("docker.io/library/postgres:16")
+        );
+        assert_eq!(sidecar["env"][0]["name"].as_str(), Some("POSTGRES_PASSWORD"));
+        assert_eq!(sidecar["env"][0]["value"].as_str(), Some("dev"));
+        assert_eq!(sidecar["ports"][0]["containerPort"].as_u64(), Some(5432));
+        assert_eq!(
+            sidecar["volumeMounts"][0]["name"].as_str(),
+            Some("sidecar-postgres-1")
+        );
+        let volume = doc["spec"]["volumes"]
+            .as_sequence()
+            .expect("volumes")
+            .iter()
+            .find(|volume| volume["name"].as_str() == Some("sidecar-postgres-1"))
+            .expect("sidecar volume");
+        assert_eq!(
+            volume["persistentVolumeClaim"]["claimName"].as_str(),
+            Some("demo-pgdata")
+        );
+    }
+    assert_eq!(found, 1);
+    assert!(container_mount_paths(&rendered, "cli-app")
+        .iter()
+        .all(|path| path != "/var/lib/postgresql/data"));
+}
+
+#[test]
+fn config_env_renders_into_cli_and_sandbox_apps() {
+    let settings = resolve_network_settings("demo", 1)
+        .unwrap()
+        .with_agents("demo", ["codex"]);
+    let config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: Vec::new(),
+        agents: vec![AgentConfig {
+            name: "codex".to_string(),
+        }],
+        env: BTreeMap::from([("EDITOR".to_string(), "vim".to_string())]),
+        sandbox_env: BTreeMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+        disk_usage_threshold: None,
+    };
+    let rendered =
+        render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings).unwrap();
+    let docs = serde_yaml::Deserializer::from_str(&rendered)
+        .map(Value::deserialize)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .expect("rendered pods.yaml parses");
+
+    let env_of = |pod: &str, container: &str| -> Vec<(String, String)> {
+        docs.iter()
+            .filter(|doc| doc["metadata"]["name"].as_str() == Some(pod))
+            .flat_map(|doc| doc["spec"]["containers"].as_sequence().cloned().unwrap_or_default())
+            .filter(|entry| entry["name"].as_str() == Some(container))
+            .flat_map(|entry| entry["env"].as_sequence().cloned().unwrap_or_default())
+            .map(|var| {
+                (
+                    var["name"].as_str().unwrap_or_default().to_string(),
+                    var["value"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect()
+    };
+    let editor = ("EDITOR".to_string(), "vim".to_string());
+    let rust_log = ("RUST_LOG".to_string(), "debug".to_string());
+    for pod in ["demo-cli-pod", "demo-cli-codex-pod"] {
+        let env = env_of(pod, "cli-app");
+        assert!(env.contains(&editor), "{pod}");
+        assert!(!env.contains(&rust_log), "{pod}");
+        assert!(env.iter().any(|(name, _)| name == "HTTP_PROXY"), "{pod}");
+    }
+    let sandbox = env_of("demo-sandbox-pod", "sandbox-app");
+    assert!(sandbox.contains(&rust_log));
+    assert!(!sandbox.contains(&editor));
+    assert!(sandbox.iter().any(|(name, _)| name == "POLICY_DIR"));
+}
+
+#[test]
+fn workspace_excludes_mask_matches_in_workspace_containers() {
+    let workspace = std::env::temp_dir().join(format!(
+        "cladding-workspace-excludes-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(workspace.join(".cladding")).unwrap();
+    std::fs::create_dir_all(workspace.join("secrets")).unwrap();
+    std::fs::write(workspace.join(".env"), "TOKEN=x").unwrap();
+
+    let settings = resolve_network_settings("demo", 1).unwrap();
+    let config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: Vec::new(),
+        workspace_excludes: vec![".env".to_string(), "secrets".to_string(), "*.pem".to_string()],
+        agents: Vec::new(),
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
+    };
+    let rendered = render_pods_yaml(&workspace.join(".cladding"), &config, &settings).unwrap();
+    for container in ["sandbox-app", "cli-app"] {
+        let mounts = container_mount_paths(&rendered, container);
+        assert!(mounts.contains(&"/home/user/workspace/.env".to_string()), "{container}");
+        assert!(mounts.contains(&"/home/user/workspace/secrets".to_string()), "{container}");
+    }
+
+    let docs = serde_yaml::Deserializer::from_str(&rendered)
+        .map(Value::deserialize)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .unwrap();
+    let cli_pod = docs
+        .iter()
+        .find(|doc| doc["metadata"]["name"] == settings.cli_pod_name.as_str())
+        .expect("cli pod");
+    let volume = |name: &str| {
+        cli_pod["spec"]["volumes"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .find(|volume| volume["name"] == name)
+            .cloned()
+            .unwrap_or_else(|| panic!("volume {name}"))
+    };
+    assert_eq!(volume("workspace-exclude-file")["hostPath"]["path"], "/dev/null");
+    assert_eq!(volume("workspace-exclude-dir")["configMap"]["name"], "empty-mask");
+    std::fs::remove_dir_all(&workspace).unwrap();
+}
+
+#[test]
+fn agents_get_their_own_cli_pods() {
+    let settings = resolve_network_settings("demo", 1)
+        .unwrap()
+        .with_agents("demo", ["codex"]);
+    let config = Config {
+        name: "demo".to_string(),
+        sandbox_image: "sandbox:image".to_string(),
+        cli_image: "cli:image".to_string(),
+        mounts: Vec::new(),
+        home_template: None,
+        isolated: false,
+        engine: None,
+        sidecars: vec![SidecarConfig {
+            name: "db".to_string(),
+            image: "postgres:16".to_string(),
+            env: BTreeMap::new(),
+            mounts: Vec::new(),
+            ports: vec![5432],
+        }],
+        workspace_excludes: Vec::new(),
+        agents: vec![AgentConfig {
+            name: "codex".to_string(),
+        }],
+        env: BTreeMap::new(),
+        sandbox_env: BTreeMap::new(),
+        disk_usage_threshold: None,
+    };
+    let rendered =
+        render_pods_yaml(Path::new("/tmp/project/.cladding"), &config, &settings).unwrap();
+    assert_eq!(
+        pod_names_from_rendered(&rendered),
+        ["demo-proxy-pod", "demo-sandbox-pod", "demo-cli-pod", "demo-cli-codex-pod"]
+    );
+    assert!(rendered.contains("value: 10.90.1.5"));
+
+    let docs = serde_yaml::Deserializer::from_str(&rendered)
+        .map(Value::deserialize)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .unwrap();
+    let pod = |name: &str| {
+        docs.iter()
+            .find(|doc| doc["metadata"]["name"] == name)
+            .unwrap_or_else(|| panic!("pod {name}"))
+    };
+    let home = |name: &str| {
+        pod(name)["spec"]["volumes"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .find(|volume| volume["name"] == "home-dir")
+            .map(|volume| volume["hostPath"]["path"].clone())
+            .unwrap()
+    };
+    assert_eq!(home("demo-cli-pod"), "/tmp/project/.cladding/home");
+    assert_eq!(home("demo-cli-codex-pod"), "/tmp/project/.cladding/agents/codex/home");
+    assert_eq!(pod("demo-cli-codex-pod")["metadata"]["labels"]["agent"], "codex");
+    let containers = |name: &str| pod(name)["spec"]["containers"].as_sequence().unwrap().len();
+    // Sidecars stay with the project's own cli pod.
+    assert_eq!(containers("demo-cli-pod"), 2);
+    assert_eq!(containers("demo-cli-codex-pod"), 1);
+}
@@ -1,5 +1,7 @@
 package sandbox.curl
 
+description := "HEAD request to example.com"
+
 default allow = false
 default allow_env = false
 
@@ -0,0 +1,71 @@
+//! Compiles fuzzed Rego and evaluates a fuzzed invocation against it.
+//!
+//! Input layout: the policy source, a NUL byte, then a JSON object with optional `command`,
+//! `path`, `hash`, `args`, `env` and `cwd` fields. `file_hash` reads go to an in-memory
+//! hasher, so nothing touches the filesystem.
+#![no_main]
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use libfuzzer_sys::fuzz_target;
+use mcp_run::{FileHasher, HashAlgorithm, InvocationContext, PolicyEngine};
+
+#[derive(Debug)]
+struct PathLengthHasher;
+
+impl FileHasher for PathLengthHasher {
+    fn hash_hex(&self, path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+        if !path.is_absolute() {
+            return Err(std::io::ErrorKind::NotFound.into());
+        }
+        Ok(format!("{}-{:x}", algorithm.as_str(), path.as_os_str().len()))
+    }
+}
+
+fn string_field<'a>(input: &'a serde_json::Value, key: &str) -> &'a str {
+    input.get(key).and_then(serde_json::Value::as_str).unwrap_or_default()
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some(split) = data.iter().position(|byte| *byte == 0) else {
+        return;
+    };
+    let (Ok(source), Ok(input)) = (
+        std::str::from_utf8(&data[..split]),
+        serde_json::from_slice::<serde_json::Value>(&data[split + 1..]),
+    ) else {
+        return;
+    };
+    let hasher = Arc::new(PathLengthHasher);
+    let Ok(engine) = PolicyEngine::from_modules(&[("main.rego", source)], hasher) else {
+        return;
+    };
+    let engine = engine.with_workspace(PathBuf::from("/workspace"));
+
+    let args: Vec<String> = input
+        .get("args")
+        .and_then(|args| serde_json::from_value(args.clone()).ok())
+        .unwrap_or_default();
+    let env: BTreeMap<String, String> = input
+        .get("env")
+        .and_then(|env| serde_json::from_value(env.clone()).ok())
+        .unwrap_or_default();
+    let context = InvocationContext {
+        client_id: None,
+        cwd: input.get("cwd").and_then(serde_json::Value::as_str).map(PathBuf::from),
+        canonical_cwd: None,
+    };
+    let command = string_field(&input, "command");
+    if let Ok(decision) = engine.authorize_invocation(
+        command,
+        string_field(&input, "path"),
+        string_field(&input, "hash"),
+        &args,
+        &env,
+        &context,
+    ) {
+        let _ = decision.redact_args(&args, &env);
+    }
+});
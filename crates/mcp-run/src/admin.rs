@@ -0,0 +1,890 @@
+//! Operator endpoints under `/admin`, served on their own loopback listener, and the
+//! `mcp-run admin` client that calls them on a running server.
+
+use std::net::SocketAddr;
+
+use axum::Json;
+use axum::extract::{Path, Query, State, rejection::JsonRejection};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::audit::{
+    AuditRecord, AuditStats, MAX_AUDIT_HISTORY, audit_stats, recent_audit_records,
+};
+use crate::auth::{
+    ADMIN_TOKEN_ENV, AuthToken, AuthTokenError, admin_token_from_env, auth_token_from_env,
+    authorize,
+};
+use crate::grants::{Grant, GrantError, GrantRequest};
+use crate::mcp::{ADMIN_BIND_ADDR_ENV, DEFAULT_ADMIN_BIND_ADDR};
+use crate::policy::PolicyMode;
+use crate::raw::{RawEndpointState, RawErrorBody, error_response};
+use crate::version::VersionInfo;
+
+/// Records returned by `GET /admin/history` without a `limit`.
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// Response to `GET /admin/status`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminStatus {
+    pub version: VersionInfo,
+    /// Commands running via the MCP tool, `/raw`, `/jobs` or `/run`.
+    pub executions_active: usize,
+    pub raw_streams_active: usize,
+    pub raw_streams_queued: usize,
+    pub jobs_running: usize,
+    pub jobs_finished: usize,
+    pub runs_running: usize,
+    pub runs_finished: usize,
+}
+
+/// Response to `POST /admin/reload`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyReloaded {
+    /// `deny-all` when the policy directory failed to load.
+    pub policy_mode: String,
+}
+
+/// Response to `POST /admin/kill/{id}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminKilled {
+    pub id: String,
+    /// Whether the command was still running and had to be killed.
+    pub killed: bool,
+}
+
+pub async fn admin_status_handler(State(state): State<RawEndpointState>) -> Json<AdminStatus> {
+    let raw_streams = state.raw_streams.snapshot();
+    let (jobs_running, jobs_finished) = state.jobs.counts();
+    let (runs_running, runs_finished) = state.runs.counts();
+    Json(AdminStatus {
+        version: VersionInfo::current(state.policy_engine.mode(), state.auth_required),
+        executions_active: state.executions.snapshot().active,
+        raw_streams_active: raw_streams.active,
+        raw_streams_queued: raw_streams.queued,
+        jobs_running,
+        jobs_finished,
+        runs_running,
+        runs_finished,
+    })
+}
+
+/// Reloads the policy directory now instead of waiting for the file watcher.
+pub async fn admin_reload_handler(State(state): State<RawEndpointState>) -> Json<PolicyReloaded> {
+    state.policy_engine.reload();
+    let policy_mode = state.policy_engine.mode();
+    tracing::info!(
+        policy_mode = policy_mode.as_str(),
+        "policy reloaded by admin request"
+    );
+    Json(PolicyReloaded {
+        policy_mode: policy_mode.as_str().to_string(),
+    })
+}
+
+/// Query of `GET /admin/history`.
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub limit: Option<usize>,
+}
+
+/// The most recent audit records, oldest first, whether or not `MCP_AUDIT_LOG` is set.
+/// Denial reasons are cut down to `POLICY_DENIAL_DETAIL` like the clients' own.
+pub async fn admin_history_handler(
+    State(state): State<RawEndpointState>,
+    Query(query): Query<HistoryQuery>,
+) -> Json<Vec<AuditRecord>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .min(MAX_AUDIT_HISTORY);
+    Json(recent_audit_records(limit, state.policy_engine.denial_detail()))
+}
+
+/// Records written since the server started, by decision and by command.
+pub async fn audit_stats_handler() -> Json<AuditStats> {
+    Json(audit_stats())
+}
+
+/// Kills a job (`job-N`) or `/run` execution (`exec-N`) that is still running. Both stay
+/// available to their stream and wait endpoints.
+pub async fn admin_kill_handler(
+    State(state): State<RawEndpointState>,
+    Path(id): Path<String>,
+) -> Response {
+    let killed = if id.starts_with("job-") {
+        state.jobs.kill(&id).await
+    } else if id.starts_with("raw-") {
+        state.raw_commands.kill(&id).await
+    } else {
+        state.runs.kill(&id).await
+    };
+    match killed {
+        Some(killed) => {
+            if killed {
+                tracing::info!(id, "command killed by admin request");
+            }
+            Json(AdminKilled { id, killed }).into_response()
+        }
+        None => error_response(
+            StatusCode::NOT_FOUND,
+            format!("Unknown job, execution or raw command: {id}"),
+        ),
+    }
+}
+
+/// Response to `DELETE /admin/grants/{id}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantRevoked {
+    pub id: String,
+}
+
+/// Lets one client run one exact command once, ahead of the policy. Refused unless the
+/// server has an admin token, so nothing holding only `MCP_AUTH_TOKEN` can grant itself runs.
+pub async fn admin_create_grant_handler(
+    State(state): State<RawEndpointState>,
+    payload: Result<Json<GrantRequest>, JsonRejection>,
+) -> Response {
+    if !state.grants_enabled {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            format!("allow grants require {ADMIN_TOKEN_ENV} to be set on the server"),
+        );
+    }
+    let request = match payload {
+        Ok(Json(request)) => request,
+        Err(error) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid request payload: {error}"),
+            );
+        }
+    };
+    match state.policy_engine.create_grant(request) {
+        Ok(grant) => (StatusCode::CREATED, Json(grant)).into_response(),
+        Err(error @ GrantError::TooMany) => error_response(StatusCode::CONFLICT, error.to_string()),
+        Err(error) => error_response(StatusCode::BAD_REQUEST, error.to_string()),
+    }
+}
+
+/// Grants not yet used, revoked or expired.
+pub async fn admin_list_grants_handler(State(state): State<RawEndpointState>) -> Json<Vec<Grant>> {
+    Json(state.policy_engine.grants())
+}
+
+pub async fn admin_revoke_grant_handler(
+    State(state): State<RawEndpointState>,
+    Path(id): Path<String>,
+) -> Response {
+    if state.policy_engine.revoke_grant(&id) {
+        Json(GrantRevoked { id }).into_response()
+    } else {
+        error_response(StatusCode::NOT_FOUND, format!("Unknown or used grant: {id}"))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AdminError {
+    #[error(
+        "usage: mcp-run admin status|reload|history [LIMIT]|kill <ID>|grants|revoke <GRANT_ID>\n       \
+         mcp-run admin grant <CLIENT_ID> [--ttl SECS] [--cwd DIR] [--env KEY=VALUE]... \
+         [--reason TEXT] -- <EXECUTABLE> [ARGS]..."
+    )]
+    Usage,
+    #[error("invalid history limit '{0}': expected a positive integer")]
+    InvalidLimit(String),
+    #[error("invalid server address '{0}' (set MCP_ADMIN_URL, e.g. http://127.0.0.1:8001)")]
+    InvalidServerUrl(String),
+    #[error("request failed: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("server rejected request ({status}): {message}")]
+    ServerRejected {
+        status: reqwest::StatusCode,
+        message: String,
+    },
+    #[error("policy reload failed; the server is in deny-all mode (see its log)")]
+    ReloadFailed,
+    #[error(transparent)]
+    AuthToken(#[from] AuthTokenError),
+}
+
+/// Runs `mcp-run admin <command>` against `MCP_ADMIN_URL`, or `MCP_ADMIN_BIND_ADDR` when
+/// unset, presenting `MCP_ADMIN_TOKEN` or, without one, `MCP_AUTH_TOKEN`.
+pub async fn admin_from_args(args: &[String]) -> Result<(), AdminError> {
+    let base = resolve_admin_url(
+        std::env::var("MCP_ADMIN_URL").ok(),
+        std::env::var(ADMIN_BIND_ADDR_ENV).ok(),
+    )?;
+    let token = match admin_token_from_env()? {
+        Some(token) => Some(token),
+        None => auth_token_from_env()?,
+    };
+    let token = token.as_ref();
+    let client = reqwest::Client::new();
+    match args {
+        [command] if command == "status" => {
+            let status: AdminStatus = send(client.get(endpoint(&base, "admin/status")?), token).await?;
+            print!("{}", format_status(&status));
+        }
+        [command] if command == "reload" => {
+            let reloaded: PolicyReloaded =
+                send(client.post(endpoint(&base, "admin/reload")?), token).await?;
+            if reloaded.policy_mode == PolicyMode::DenyAll.as_str() {
+                return Err(AdminError::ReloadFailed);
+            }
+            println!("policy reloaded ({})", reloaded.policy_mode);
+        }
+        [command, rest @ ..] if command == "history" && rest.len() <= 1 => {
+            let limit = match rest.first() {
+                Some(value) => value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|limit| *limit > 0)
+                    .ok_or_else(|| AdminError::InvalidLimit(value.clone()))?,
+                None => DEFAULT_HISTORY_LIMIT,
+            };
+            let mut url = endpoint(&base, "admin/history")?;
+            url.query_pairs_mut()
+                .append_pair("limit", &limit.to_string());
+            let records: Vec<serde_json::Value> = send(client.get(url), token).await?;
+            for record in &records {
+                println!("{}", format_history_line(record));
+            }
+        }
+        [command, rest @ ..] if command == "grant" => {
+            let request = parse_grant_args(rest)?;
+            let url = endpoint(&base, "admin/grants")?;
+            let grant: Grant = send(client.post(url).json(&request), token).await?;
+            println!("{}", format_grant_line(&grant));
+        }
+        [command] if command == "grants" => {
+            let grants: Vec<Grant> = send(client.get(endpoint(&base, "admin/grants")?), token).await?;
+            for grant in &grants {
+                println!("{}", format_grant_line(grant));
+            }
+        }
+        [command, id] if command == "revoke" => {
+            let url = endpoint(&base, &format!("admin/grants/{id}"))?;
+            let revoked: GrantRevoked = send(client.delete(url), token).await?;
+            println!("revoked {}", revoked.id);
+        }
+        [command, id] if command == "kill" => {
+            let url = endpoint(&base, &format!("admin/kill/{id}"))?;
+            let killed: AdminKilled = send(client.post(url), token).await?;
+            if killed.killed {
+                println!("killed {}", killed.id);
+            } else {
+                println!("{} had already finished", killed.id);
+            }
+        }
+        _ => return Err(AdminError::Usage),
+    }
+    Ok(())
+}
+
+/// `grant <CLIENT_ID> [options] -- <EXECUTABLE> [ARGS]...`; everything after `--` is the
+/// command, taken verbatim.
+fn parse_grant_args(args: &[String]) -> Result<GrantRequest, AdminError> {
+    let (options, command) = match args.iter().position(|arg| arg == "--") {
+        Some(split) => (&args[..split], &args[split + 1..]),
+        None => return Err(AdminError::Usage),
+    };
+    let (Some((client_id, mut options)), Some((executable, command_args))) =
+        (options.split_first(), command.split_first())
+    else {
+        return Err(AdminError::Usage);
+    };
+    let mut request = GrantRequest {
+        client_id: client_id.clone(),
+        executable: executable.clone(),
+        args: command_args.to_vec(),
+        env: Default::default(),
+        cwd: None,
+        ttl_secs: None,
+        reason: None,
+    };
+    while let [flag, value, rest @ ..] = options {
+        match flag.as_str() {
+            "--ttl" => {
+                request.ttl_secs = Some(value.parse().map_err(|_| AdminError::Usage)?);
+            }
+            "--cwd" => request.cwd = Some(value.into()),
+            "--env" => {
+                let (key, value) = value.split_once('=').ok_or(AdminError::Usage)?;
+                request.env.insert(key.to_string(), value.to_string());
+            }
+            "--reason" => request.reason = Some(value.clone()),
+            _ => return Err(AdminError::Usage),
+        }
+        options = rest;
+    }
+    if !options.is_empty() {
+        return Err(AdminError::Usage);
+    }
+    Ok(request)
+}
+
+/// `MCP_ADMIN_URL` as given, or `MCP_ADMIN_BIND_ADDR` with an unspecified host replaced by
+/// loopback.
+fn resolve_admin_url(
+    admin_url: Option<String>,
+    bind_addr: Option<String>,
+) -> Result<Url, AdminError> {
+    if let Some(admin_url) = admin_url.filter(|value| !value.trim().is_empty()) {
+        let admin_url = admin_url.trim();
+        return Url::parse(admin_url)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or_else(|| AdminError::InvalidServerUrl(admin_url.to_string()));
+    }
+    let bind_addr = bind_addr
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_ADMIN_BIND_ADDR.to_string());
+    let mut addr: SocketAddr = bind_addr
+        .parse()
+        .map_err(|_| AdminError::InvalidServerUrl(bind_addr.clone()))?;
+    if addr.ip().is_unspecified() {
+        let loopback = if addr.is_ipv4() {
+            std::net::Ipv4Addr::LOCALHOST.into()
+        } else {
+            std::net::Ipv6Addr::LOCALHOST.into()
+        };
+        addr.set_ip(loopback);
+    }
+    Url::parse(&format!("http://{addr}")).map_err(|_| AdminError::InvalidServerUrl(bind_addr))
+}
+
+fn endpoint(base: &Url, path: &str) -> Result<Url, AdminError> {
+    let mut base = base.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    base.join(path)
+        .map_err(|_| AdminError::InvalidServerUrl(base.to_string()))
+}
+
+async fn send<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    token: Option<&AuthToken>,
+) -> Result<T, AdminError> {
+    let response = authorize(request, token).send().await.map_err(AdminError::Request)?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.map_err(AdminError::Request)?;
+        let message = serde_json::from_str::<RawErrorBody>(&body)
+            .map(|decoded| decoded.error)
+            .unwrap_or_else(|_| body.trim().to_string());
+        return Err(AdminError::ServerRejected { status, message });
+    }
+    response.json().await.map_err(AdminError::Request)
+}
+
+fn format_status(status: &AdminStatus) -> String {
+    format!(
+        "{} {} ({})\npolicy: {}\nrunning commands: {}\nraw streams: {} active, {} queued\njobs: {} running, {} finished\nruns: {} running, {} finished\n",
+        status.version.name,
+        status.version.version,
+        status.version.git_commit,
+        status.version.policy_mode,
+        status.executions_active,
+        status.raw_streams_active,
+        status.raw_streams_queued,
+        status.jobs_running,
+        status.jobs_finished,
+        status.runs_running,
+        status.runs_finished,
+    )
+}
+
+/// The grant's id, client and expiry, then the command it allows.
+fn format_grant_line(grant: &Grant) -> String {
+    let mut command = grant.executable.clone();
+    for arg in &grant.args {
+        command.push(' ');
+        command.push_str(arg);
+    }
+    format!(
+        "{}  {}  expires {}  {}",
+        grant.id, grant.client_id, grant.expires_at, command
+    )
+}
+
+/// One line per audit record: time, source, client, decision and outcome, then the command.
+fn format_history_line(record: &serde_json::Value) -> String {
+    let field = |name: &str| record.get(name).and_then(serde_json::Value::as_str);
+    let outcome = if let Some(reason) = field("reason") {
+        reason.to_string()
+    } else if let Some(error) = field("error") {
+        format!("error: {error}")
+    } else {
+        match record.get("exitCode").and_then(serde_json::Value::as_i64) {
+            Some(code) => format!("exit {code}"),
+            None => "no exit code".to_string(),
+        }
+    };
+    let args: Vec<&str> = record
+        .get("args")
+        .and_then(serde_json::Value::as_array)
+        .map(|args| args.iter().filter_map(serde_json::Value::as_str).collect())
+        .unwrap_or_default();
+    let mut command = field("executable").unwrap_or("?").to_string();
+    for arg in args {
+        command.push(' ');
+        command.push_str(arg);
+    }
+    let mut decision = field("decision").unwrap_or("-").to_string();
+    if let Some(grant_id) = field("grantId") {
+        decision = format!("{decision} ({grant_id})");
+    }
+    format!(
+        "{}  {}  {}  {}  {}  {}",
+        field("timestamp").unwrap_or("-"),
+        field("source").unwrap_or("-"),
+        field("clientId").unwrap_or("-"),
+        decision,
+        outcome,
+        command
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::executions::{EXECUTION_KILLED, ExecutionStarted, ExecutionStatus};
+    use crate::executor::RunNetworkToolInput;
+    use crate::mcp::{ServerOptions, build_apps};
+    use crate::policy::{DenialDetail, PolicyEngine};
+    use crate::test_support::{find_executable, serve_app, sh_policy};
+    use tokio::task::JoinHandle;
+
+    const ADMIN_TOKEN: &str = "adm1n";
+
+    /// Serves the app and the `/admin` app on separate ports, returning both base URLs.
+    async fn start_servers(policy_engine: PolicyEngine) -> (String, String, [JoinHandle<()>; 2]) {
+        start_servers_with(policy_engine, ServerOptions::default()).await
+    }
+
+    async fn start_servers_with(
+        policy_engine: PolicyEngine,
+        options: ServerOptions,
+    ) -> (String, String, [JoinHandle<()>; 2]) {
+        let (app, admin) = build_apps(Arc::new(policy_engine), PathBuf::from("."), options);
+        let (base, server_task) = serve_app(app).await;
+        let (admin_base, admin_task) = serve_app(admin).await;
+        (base, admin_base, [server_task, admin_task])
+    }
+
+    #[test]
+    fn admin_url_defaults_to_the_local_bind_address() {
+        let url = |admin: Option<&str>, bind: Option<&str>| {
+            resolve_admin_url(admin.map(str::to_string), bind.map(str::to_string))
+                .map(|url| url.to_string())
+        };
+        assert_eq!(url(None, None).unwrap(), "http://127.0.0.1:8001/");
+        assert_eq!(
+            url(None, Some("0.0.0.0:3000")).unwrap(),
+            "http://127.0.0.1:3000/"
+        );
+        assert_eq!(url(None, Some("[::]:3000")).unwrap(), "http://[::1]:3000/");
+        assert_eq!(
+            url(Some("http://sandbox-pod:3000"), Some("0.0.0.0:1")).unwrap(),
+            "http://sandbox-pod:3000/"
+        );
+        assert!(matches!(
+            url(Some("sandbox-pod:3000"), None),
+            Err(AdminError::InvalidServerUrl(_))
+        ));
+        assert!(matches!(
+            url(None, Some("nope")),
+            Err(AdminError::InvalidServerUrl(_))
+        ));
+
+        let base = Url::parse("http://127.0.0.1:8000/prefix").unwrap();
+        assert_eq!(
+            endpoint(&base, "admin/status").unwrap().as_str(),
+            "http://127.0.0.1:8000/prefix/admin/status"
+        );
+    }
+
+    #[test]
+    fn history_lines_summarize_records() {
+        let denied = serde_json::json!({
+            "timestamp": "2026-01-05T09:14:03.512Z", "source": "raw", "clientId": null,
+            "executable": "curl", "args": ["-I", "https://example.com"],
+            "decision": "deny", "reason": "Command not allowed: curl", "exitCode": null
+        });
+        assert_eq!(
+            format_history_line(&denied),
+            "2026-01-05T09:14:03.512Z  raw  -  deny  Command not allowed: curl  curl -I https://example.com"
+        );
+        let exited = serde_json::json!({
+            "timestamp": "t", "source": "mcp", "clientId": "builder", "executable": "date",
+            "args": [], "decision": "allow", "reason": null, "error": null, "exitCode": 0
+        });
+        assert_eq!(
+            format_history_line(&exited),
+            "t  mcp  builder  allow  exit 0  date"
+        );
+        let granted = serde_json::json!({
+            "timestamp": "t", "source": "run", "clientId": "builder", "executable": "date",
+            "args": [], "decision": "allow", "grantId": "grant-1", "exitCode": 0
+        });
+        assert_eq!(
+            format_history_line(&granted),
+            "t  run  builder  allow (grant-1)  exit 0  date"
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_endpoints_report_reload_and_kill() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let (base, admin, tasks) = start_servers(sh_policy(&sh_path)).await;
+        let client = reqwest::Client::new();
+
+        let started: ExecutionStarted = client
+            .post(format!("{base}/run"))
+            .json(&RunNetworkToolInput {
+                executable: sh_path.clone(),
+                args: vec!["-c".to_string(), "sleep 30".to_string()],
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("start run")
+            .json()
+            .await
+            .expect("execution id");
+
+        let public = reqwest::get(format!("{base}/admin/status"))
+            .await
+            .expect("status on the public listener");
+        assert_eq!(public.status(), reqwest::StatusCode::NOT_FOUND);
+        let status: AdminStatus = reqwest::get(format!("{admin}/admin/status"))
+            .await
+            .expect("status")
+            .json()
+            .await
+            .expect("status body");
+        assert_eq!(status.version.policy_mode, "rego");
+        assert_eq!(status.runs_running, 1);
+        assert_eq!(status.executions_active, 1);
+
+        let killed: AdminKilled = client
+            .post(format!("{admin}/admin/kill/{}", started.execution_id))
+            .send()
+            .await
+            .expect("kill")
+            .json()
+            .await
+            .expect("kill body");
+        assert!(killed.killed);
+        let waited: ExecutionStatus = tokio::time::timeout(
+            Duration::from_secs(5),
+            reqwest::get(format!(
+                "{base}/executions/{}/wait?timeout=1s",
+                started.execution_id
+            )),
+        )
+        .await
+        .expect("wait in time")
+        .expect("wait")
+        .json()
+        .await
+        .expect("wait body");
+        assert_eq!(
+            waited,
+            ExecutionStatus::Failed {
+                error: EXECUTION_KILLED.to_string()
+            }
+        );
+
+        let again: AdminKilled = client
+            .post(format!("{admin}/admin/kill/{}", started.execution_id))
+            .send()
+            .await
+            .expect("kill again")
+            .json()
+            .await
+            .expect("kill again body");
+        assert!(!again.killed);
+        let unknown = client
+            .post(format!("{admin}/admin/kill/job-999"))
+            .send()
+            .await
+            .expect("kill unknown");
+        assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // The policy came from memory, so reloading finds no directory and denies all.
+        let reloaded: PolicyReloaded = client
+            .post(format!("{admin}/admin/reload"))
+            .send()
+            .await
+            .expect("reload")
+            .json()
+            .await
+            .expect("reload body");
+        assert_eq!(reloaded.policy_mode, "deny-all");
+
+        let history: Vec<serde_json::Value> =
+            reqwest::get(format!("{admin}/admin/history?limit={MAX_AUDIT_HISTORY}"))
+                .await
+                .expect("history")
+                .json()
+                .await
+                .expect("history body");
+        assert!(history.iter().any(|record| record["source"] == "run"
+            && record["args"] == serde_json::json!(["-c", "sleep 30"])));
+
+        tasks.iter().for_each(JoinHandle::abort);
+    }
+
+    #[test]
+    fn grant_args_split_options_from_the_command() {
+        let args: Vec<String> = [
+            "builder", "--ttl", "60", "--env", "A=1", "--", "curl", "--ttl", "-I",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let request = parse_grant_args(&args).expect("grant args");
+        assert_eq!(request.client_id, "builder");
+        assert_eq!(request.ttl_secs, Some(60));
+        assert_eq!(request.env.get("A").map(String::as_str), Some("1"));
+        assert_eq!(request.executable, "curl");
+        assert_eq!(request.args, vec!["--ttl", "-I"]);
+        assert!(matches!(
+            parse_grant_args(&args[..5]),
+            Err(AdminError::Usage)
+        ));
+        assert!(matches!(
+            parse_grant_args(&args[..6]),
+            Err(AdminError::Usage)
+        ));
+    }
+
+    #[tokio::test]
+    async fn grants_allow_one_denied_run_for_their_client() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let options = ServerOptions {
+            admin_token: Some(AuthToken::new(ADMIN_TOKEN).expect("admin token")),
+            ..ServerOptions::default()
+        };
+        let (base, admin, tasks) =
+            start_servers_with(PolicyEngine::from_rego_for_tests(&[]), options).await;
+        let client = reqwest::Client::new();
+        let run = |client_id: &str| {
+            client
+                .post(format!("{base}/run"))
+                .header("x-client-id", client_id)
+                .json(&RunNetworkToolInput {
+                    executable: sh_path.clone(),
+                    args: vec!["-c".to_string(), "exit 3".to_string()],
+                    ..RunNetworkToolInput::default()
+                })
+                .send()
+        };
+
+        let created = client
+            .post(format!("{admin}/admin/grants"))
+            .bearer_auth(ADMIN_TOKEN)
+            .json(&serde_json::json!({
+                "clientId": "builder",
+                "executable": sh_path,
+                "args": ["-c", "exit 3"],
+                "reason": "one-off check",
+            }))
+            .send()
+            .await
+            .expect("create grant");
+        assert_eq!(created.status(), reqwest::StatusCode::CREATED);
+        let grant: Grant = created.json().await.expect("grant body");
+        let listed: Vec<Grant> = client
+            .get(format!("{admin}/admin/grants"))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("list grants")
+            .json()
+            .await
+            .expect("grants body");
+        assert_eq!(listed, vec![grant.clone()]);
+
+        let other = run("reviewer").await.expect("run as other client");
+        assert_eq!(other.status(), reqwest::StatusCode::FORBIDDEN);
+        let granted = run("builder").await.expect("granted run");
+        assert_eq!(granted.status(), reqwest::StatusCode::CREATED);
+        let again = run("builder").await.expect("second run");
+        assert_eq!(again.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let revoked = client
+            .delete(format!("{admin}/admin/grants/{}", grant.id))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("revoke used grant");
+        assert_eq!(revoked.status(), reqwest::StatusCode::NOT_FOUND);
+        let invalid = client
+            .post(format!("{admin}/admin/grants"))
+            .bearer_auth(ADMIN_TOKEN)
+            .json(&serde_json::json!({"clientId": "builder", "executable": "sh", "ttlSecs": 0}))
+            .send()
+            .await
+            .expect("invalid grant");
+        assert_eq!(invalid.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let history: Vec<serde_json::Value> = client
+            .get(format!("{admin}/admin/history?limit={MAX_AUDIT_HISTORY}"))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+                .expect("history")
+                .json()
+                .await
+                .expect("history body");
+        assert!(history.iter().any(|record| record["source"] == "grant"
+            && record["grantId"] == grant.id.as_str()
+            && record["decision"] == "allow"));
+        assert!(history.iter().any(|record| record["source"] == "run"
+            && record["clientId"] == "builder"
+            && record["grantId"] == grant.id.as_str()));
+
+        tasks.iter().for_each(JoinHandle::abort);
+    }
+
+    #[tokio::test]
+    async fn grants_need_an_admin_token_and_never_override_deny_all() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let grant_body = serde_json::json!({
+            "clientId": "deny-all-builder",
+            "executable": sh_path,
+            "args": ["-c", "exit 5"],
+        });
+        let client = reqwest::Client::new();
+
+        let (_, admin, tasks) = start_servers(PolicyEngine::from_rego_for_tests(&[])).await;
+        let refused = client
+            .post(format!("{admin}/admin/grants"))
+            .json(&grant_body)
+            .send()
+            .await
+            .expect("grant without an admin token");
+        assert_eq!(refused.status(), reqwest::StatusCode::FORBIDDEN);
+        tasks.iter().for_each(JoinHandle::abort);
+
+        let options = ServerOptions {
+            auth_token: Some(AuthToken::new("s3cret").expect("token")),
+            admin_token: Some(AuthToken::new(ADMIN_TOKEN).expect("admin token")),
+            ..ServerOptions::default()
+        };
+        let (base, admin, tasks) =
+            start_servers_with(PolicyEngine::from_rego_for_tests(&[]), options).await;
+        let with_auth_token = client
+            .post(format!("{admin}/admin/grants"))
+            .bearer_auth("s3cret")
+            .json(&grant_body)
+            .send()
+            .await
+            .expect("grant with the auth token");
+        assert_eq!(with_auth_token.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let created = client
+            .post(format!("{admin}/admin/grants"))
+            .bearer_auth(ADMIN_TOKEN)
+            .json(&grant_body)
+            .send()
+            .await
+            .expect("create grant");
+        assert_eq!(created.status(), reqwest::StatusCode::CREATED);
+
+        // The policy came from memory, so reloading finds no directory and denies all.
+        let reloaded: PolicyReloaded = client
+            .post(format!("{admin}/admin/reload"))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("reload")
+            .json()
+            .await
+            .expect("reload body");
+        assert_eq!(reloaded.policy_mode, "deny-all");
+        let denied = client
+            .post(format!("{base}/run"))
+            .bearer_auth("s3cret")
+            .header("x-client-id", "deny-all-builder")
+            .json(&RunNetworkToolInput {
+                executable: sh_path.clone(),
+                args: vec!["-c".to_string(), "exit 5".to_string()],
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("run in deny-all mode");
+        assert_eq!(denied.status(), reqwest::StatusCode::FORBIDDEN);
+        let listed: Vec<Grant> = client
+            .get(format!("{admin}/admin/grants"))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("list grants")
+            .json()
+            .await
+            .expect("grants body");
+        assert_eq!(listed.len(), 1, "the grant is kept, not used");
+
+        tasks.iter().for_each(JoinHandle::abort);
+    }
+
+    #[tokio::test]
+    async fn history_gives_denials_at_the_configured_detail() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let policy_engine =
+            PolicyEngine::from_rego_for_tests(&[]).with_denial_detail(DenialDetail::Minimal);
+        let (base, admin, tasks) = start_servers(policy_engine).await;
+        let denied = reqwest::Client::new()
+            .post(format!("{base}/run"))
+            .json(&RunNetworkToolInput {
+                executable: sh_path.clone(),
+                args: vec!["-c".to_string(), "exit 4 # history detail".to_string()],
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("denied run");
+        assert_eq!(denied.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let history: Vec<serde_json::Value> =
+            reqwest::get(format!("{admin}/admin/history?limit={MAX_AUDIT_HISTORY}"))
+                .await
+                .expect("history")
+                .json()
+                .await
+                .expect("history body");
+        let record = history
+            .iter()
+            .find(|record| record["args"][1] == "exit 4 # history detail")
+            .expect("denied record");
+        assert_eq!(record["decision"], "deny");
+        assert_eq!(record["reason"], "Command not allowed");
+
+        tasks.iter().for_each(JoinHandle::abort);
+    }
+}
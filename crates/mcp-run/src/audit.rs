@@ -0,0 +1,690 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::egress::EgressHold;
+use crate::executor::{ResourceUsage, RunNetworkToolInput};
+use crate::grants::Grant;
+use crate::policy::{DenialDetail, InvocationContext, PolicyMode, ValidationError};
+use crate::redaction::redact_env_values;
+
+/// Process-wide sink, installed once at startup from `MCP_AUDIT_LOG`. Without it, records are
+/// dropped.
+static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+/// Records kept in memory for `GET /admin/history`, with or without `MCP_AUDIT_LOG`.
+pub const MAX_AUDIT_HISTORY: usize = 200;
+static AUDIT_HISTORY: Mutex<VecDeque<AuditRecord>> = Mutex::new(VecDeque::new());
+
+/// Version of the [`AuditRecord`] format, written as `schemaVersion` on every line. Bumped when
+/// a field is removed or changes meaning; new fields may appear without a bump.
+pub const AUDIT_SCHEMA_VERSION: u32 = 1;
+
+/// Rotated files kept next to the log (`<log>.1` is the newest) unless `MCP_AUDIT_KEEP` says
+/// otherwise.
+pub const DEFAULT_AUDIT_KEEP: usize = 5;
+
+/// Distinct executables `GET /audit/stats` counts separately; later ones share
+/// [`OTHER_COMMANDS`], so denied requests for made-up names cannot grow the map without bound.
+const MAX_STATS_COMMANDS: usize = 1000;
+const OTHER_COMMANDS: &str = "(other)";
+
+/// When the audit log is rotated. With neither threshold set it grows without bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRotation {
+    /// Rotate before a record would take the file past this size (`MCP_AUDIT_MAX_BYTES`).
+    pub max_bytes: Option<u64>,
+    /// Rotate once the file has been written to for this long (`MCP_AUDIT_MAX_AGE_SECS`).
+    pub max_age: Option<Duration>,
+    /// Rotated files to keep; older ones are deleted (`MCP_AUDIT_KEEP`).
+    pub keep: usize,
+}
+
+impl Default for AuditRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: None,
+            max_age: None,
+            keep: DEFAULT_AUDIT_KEEP,
+        }
+    }
+}
+
+/// Append-only JSONL file with one [`AuditRecord`] per line.
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    rotation: AuditRotation,
+    current: Mutex<CurrentFile>,
+}
+
+#[derive(Debug)]
+struct CurrentFile {
+    file: File,
+    bytes: u64,
+    opened: Instant,
+    rotations: u64,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path, rotation: AuditRotation) -> std::io::Result<Self> {
+        let file = open_append(path)?;
+        let bytes = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            rotation,
+            current: Mutex::new(CurrentFile {
+                file,
+                bytes,
+                opened: Instant::now(),
+                rotations: 0,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes `record` as a single line so concurrent appends never interleave, rotating first
+    /// if the line would cross a threshold. A failed rotation is logged and the record still
+    /// goes to the current file.
+    pub fn append(&self, record: &AuditRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(record).map_err(std::io::Error::other)?;
+        line.push(b'\n');
+        let mut current = self.current.lock().expect("audit log lock poisoned");
+        if self.rotation_due(&current, line.len() as u64)
+            && let Err(error) = self.rotate(&mut current)
+        {
+            tracing::error!(
+                path = %self.path.display(),
+                error = %error,
+                "failed rotating audit log"
+            );
+        }
+        current.file.write_all(&line)?;
+        current.bytes += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotation_due(&self, current: &CurrentFile, incoming: u64) -> bool {
+        if current.bytes == 0 {
+            return false;
+        }
+        let too_big = self
+            .rotation
+            .max_bytes
+            .is_some_and(|max| current.bytes + incoming > max);
+        let too_old = self
+            .rotation
+            .max_age
+            .is_some_and(|max| current.opened.elapsed() >= max);
+        too_big || too_old
+    }
+
+    /// Shifts `<log>.N` to `<log>.N+1`, renames the log to `<log>.1` and reopens it empty.
+    /// Each step is a rename, so a reader sees either the old file or the new one, never a
+    /// partial copy. With `keep` at 0 the log is removed instead.
+    fn rotate(&self, current: &mut CurrentFile) -> std::io::Result<()> {
+        let keep = self.rotation.keep;
+        if keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            remove_if_exists(&self.rotated_path(keep))?;
+            for index in (1..keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        current.file = open_append(&self.path)?;
+        current.bytes = 0;
+        current.opened = Instant::now();
+        current.rotations += 1;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn file_stats(&self) -> AuditFileStats {
+        let current = self.current.lock().expect("audit log lock poisoned");
+        AuditFileStats {
+            path: self.path.display().to_string(),
+            bytes: current.bytes,
+            rotations: current.rotations,
+        }
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// Opens `path` and makes it the destination of every later record. Only the first call
+/// installs a log; later calls leave it in place.
+pub fn install_audit_log(path: &Path, rotation: AuditRotation) -> std::io::Result<()> {
+    let log = AuditLog::open(path, rotation)?;
+    let _ = AUDIT_LOG.set(log);
+    Ok(())
+}
+
+/// Response to `GET /audit/stats`: records written since the server started, whether or not
+/// `MCP_AUDIT_LOG` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditStats {
+    pub schema_version: u32,
+    pub total: u64,
+    pub by_decision: DecisionCounts,
+    /// Keyed by `executable`; past 1000 names the rest are counted under `(other)`.
+    pub by_command: BTreeMap<String, DecisionCounts>,
+    /// The `MCP_AUDIT_LOG` file, when one is configured.
+    pub log: Option<AuditFileStats>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionCounts {
+    pub allow: u64,
+    pub deny: u64,
+}
+
+impl DecisionCounts {
+    fn add(&mut self, decision: AuditDecision) {
+        match decision {
+            AuditDecision::Allow => self.allow += 1,
+            AuditDecision::Deny => self.deny += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFileStats {
+    pub path: String,
+    /// Size of the current file.
+    pub bytes: u64,
+    /// Rotations since the server started.
+    pub rotations: u64,
+}
+
+static AUDIT_STATS: Mutex<AuditStats> = Mutex::new(AuditStats {
+    schema_version: AUDIT_SCHEMA_VERSION,
+    total: 0,
+    by_decision: DecisionCounts { allow: 0, deny: 0 },
+    by_command: BTreeMap::new(),
+    log: None,
+});
+
+pub(crate) fn audit_stats() -> AuditStats {
+    let mut stats = AUDIT_STATS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    stats.log = AUDIT_LOG.get().map(AuditLog::file_stats);
+    stats
+}
+
+fn count_audit_record(record: &AuditRecord) {
+    let mut stats = AUDIT_STATS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    stats.total += 1;
+    stats.by_decision.add(record.decision);
+    let key = if stats.by_command.contains_key(&record.executable)
+        || stats.by_command.len() < MAX_STATS_COMMANDS
+    {
+        record.executable.as_str()
+    } else {
+        OTHER_COMMANDS
+    };
+    stats
+        .by_command
+        .entry(key.to_string())
+        .or_default()
+        .add(record.decision);
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSource {
+    /// The `run_network_tool` MCP tool.
+    Mcp,
+    /// The `/raw` streaming endpoint.
+    Raw,
+    /// A background job started with `POST /jobs`.
+    Job,
+    /// An execution started with `POST /run`.
+    Run,
+    /// A download by the `fetch_verified` MCP tool.
+    Fetch,
+    /// An allow grant created with `POST /admin/grants`, or ending unused.
+    Grant,
+    /// A stale path removed by the janitor under the policy's `cleanup` rule.
+    Cleanup,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditDecision {
+    Allow,
+    Deny,
+}
+
+/// What was asked for, what the policy decided and, when it ran, how the command ended.
+///
+/// Env values are never recorded, only their keys. Until the policy allows the invocation and
+/// says which keys are sensitive, every env value is redacted from `args`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    /// [`AUDIT_SCHEMA_VERSION`] of the writer.
+    pub schema_version: u32,
+    /// RFC 3339 UTC time of the policy decision.
+    pub timestamp: String,
+    pub source: AuditSource,
+    pub client_id: Option<String>,
+    pub executable: String,
+    pub resolved_path: Option<String>,
+    pub sha256: Option<String>,
+    pub args: Vec<String>,
+    pub env_keys: Vec<String>,
+    pub cwd: String,
+    pub policy_mode: String,
+    pub decision: AuditDecision,
+    /// Why the policy denied the invocation, the operator's note on a created grant, or the
+    /// `cleanup` pattern a removed path matched.
+    pub reason: Option<String>,
+    /// The allow grant that let the invocation skip the policy, or the grant a `grant`
+    /// record is about.
+    pub grant_id: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+    pub resource_usage: Option<ResourceUsage>,
+    /// Why an allowed command did not run to completion (spawn failure, timeout, disconnect).
+    pub error: Option<String>,
+    /// What clients are told of a policy denial at `summary` and `minimal` detail, so
+    /// `/admin/history` does not show more than `POLICY_DENIAL_DETAIL` allows.
+    #[serde(skip)]
+    client_reasons: Option<[String; 2]>,
+    #[serde(skip)]
+    started: Instant,
+    /// The policy's `domains`, held open in the proxy until the command's record is written.
+    #[serde(skip)]
+    pub(crate) egress: EgressHold,
+}
+
+impl AuditRecord {
+    pub(crate) fn begin(
+        source: AuditSource,
+        policy_mode: PolicyMode,
+        context: &InvocationContext,
+        input: &RunNetworkToolInput,
+        cwd: &Path,
+    ) -> Self {
+        let no_env = BTreeMap::new();
+        let env = input.env.as_ref().unwrap_or(&no_env);
+        Self {
+            schema_version: AUDIT_SCHEMA_VERSION,
+            timestamp: format_timestamp(SystemTime::now()),
+            source,
+            client_id: context.client_id.clone(),
+            executable: input.executable.clone(),
+            resolved_path: None,
+            sha256: None,
+            args: redact_env_values(&input.args, env, |_| true),
+            env_keys: env.keys().cloned().collect(),
+            cwd: cwd.display().to_string(),
+            policy_mode: policy_mode.as_str().to_string(),
+            decision: AuditDecision::Allow,
+            reason: None,
+            grant_id: None,
+            exit_code: None,
+            duration_ms: None,
+            stdout_truncated: false,
+            stderr_truncated: false,
+            resource_usage: None,
+            error: None,
+            client_reasons: None,
+            started: Instant::now(),
+            egress: EgressHold::default(),
+        }
+    }
+
+    /// A `fetch_verified` download, recorded with the tool name as `executable`, the URL as
+    /// the only argument, the pinned digest as `sha256` and the target directory as `cwd`.
+    pub(crate) fn begin_fetch(
+        policy_mode: PolicyMode,
+        context: &InvocationContext,
+        url: &str,
+        sha256: &str,
+        dir: Option<&Path>,
+    ) -> Self {
+        Self {
+            schema_version: AUDIT_SCHEMA_VERSION,
+            timestamp: format_timestamp(SystemTime::now()),
+            source: AuditSource::Fetch,
+            client_id: context.client_id.clone(),
+            executable: "fetch_verified".to_string(),
+            resolved_path: None,
+            sha256: Some(sha256.to_string()),
+            args: vec![url.to_string()],
+            env_keys: Vec::new(),
+            cwd: dir.map(|dir| dir.display().to_string()).unwrap_or_default(),
+            policy_mode: policy_mode.as_str().to_string(),
+            decision: AuditDecision::Allow,
+            reason: None,
+            grant_id: None,
+            exit_code: None,
+            duration_ms: None,
+            stdout_truncated: false,
+            stderr_truncated: false,
+            resource_usage: None,
+            error: None,
+            client_reasons: None,
+            started: Instant::now(),
+            egress: EgressHold::default(),
+        }
+    }
+
+    /// A grant's lifecycle event, recorded with the granted command and the grant's client.
+    pub(crate) fn begin_grant(policy_mode: &PolicyMode, grant: &Grant) -> Self {
+        Self {
+            schema_version: AUDIT_SCHEMA_VERSION,
+            timestamp: format_timestamp(SystemTime::now()),
+            source: AuditSource::Grant,
+            client_id: Some(grant.client_id.clone()),
+            executable: grant.executable.clone(),
+            resolved_path: None,
+            sha256: None,
+            args: grant.args.clone(),
+            env_keys: grant.env_keys.clone(),
+            cwd: grant.cwd.clone().unwrap_or_default(),
+            policy_mode: policy_mode.as_str().to_string(),
+            decision: AuditDecision::Allow,
+            reason: grant.reason.clone(),
+            grant_id: Some(grant.id.clone()),
+            exit_code: None,
+            duration_ms: None,
+            stdout_truncated: false,
+            stderr_truncated: false,
+            resource_usage: None,
+            error: None,
+            client_reasons: None,
+            started: Instant::now(),
+            egress: EgressHold::default(),
+        }
+    }
+
+    /// A path the janitor removes, recorded as the only argument with the `cleanup` pattern
+    /// it matched as `reason`.
+    pub(crate) fn begin_cleanup(policy_mode: &PolicyMode, path: &Path, pattern: &str) -> Self {
+        Self {
+            schema_version: AUDIT_SCHEMA_VERSION,
+            timestamp: format_timestamp(SystemTime::now()),
+            source: AuditSource::Cleanup,
+            client_id: None,
+            executable: "cleanup".to_string(),
+            resolved_path: None,
+            sha256: None,
+            args: vec![path.display().to_string()],
+            env_keys: Vec::new(),
+            cwd: path
+                .parent()
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default(),
+            policy_mode: policy_mode.as_str().to_string(),
+            decision: AuditDecision::Allow,
+            reason: Some(pattern.to_string()),
+            grant_id: None,
+            exit_code: None,
+            duration_ms: None,
+            stdout_truncated: false,
+            stderr_truncated: false,
+            resource_usage: None,
+            error: None,
+            client_reasons: None,
+            started: Instant::now(),
+            egress: EgressHold::default(),
+        }
+    }
+
+    /// Writes an allow that has no run to report, such as a created grant.
+    pub(crate) fn allow(self) {
+        write_audit_record(&self);
+    }
+
+    pub(crate) fn deny(mut self, reason: &impl Display) {
+        self.decision = AuditDecision::Deny;
+        self.reason = Some(reason.to_string());
+        write_audit_record(&self);
+    }
+
+    /// A [`deny`](Self::deny) by the policy, whose reason is cut down to `detail` when the
+    /// record is read back with [`recent_audit_records`].
+    pub(crate) fn deny_validation(mut self, error: &ValidationError) {
+        self.client_reasons = Some([
+            error.client_message(DenialDetail::Summary),
+            error.client_message(DenialDetail::Minimal),
+        ]);
+        self.deny(error);
+    }
+
+    pub(crate) fn fail(mut self, error: &impl Display) {
+        self.error = Some(error.to_string());
+        self.duration_ms = Some(self.elapsed_ms());
+        write_audit_record(&self);
+    }
+
+    pub(crate) fn complete(
+        mut self,
+        exit_code: Option<i32>,
+        resource_usage: Option<ResourceUsage>,
+        stdout_truncated: bool,
+        stderr_truncated: bool,
+    ) {
+        self.exit_code = exit_code;
+        self.resource_usage = resource_usage;
+        self.stdout_truncated = stdout_truncated;
+        self.stderr_truncated = stderr_truncated;
+        self.duration_ms = Some(self.elapsed_ms());
+        write_audit_record(&self);
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+/// The `limit` most recent records, oldest first, with policy denials given at `detail`.
+pub(crate) fn recent_audit_records(limit: usize, detail: DenialDetail) -> Vec<AuditRecord> {
+    let history = AUDIT_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    history
+        .iter()
+        .skip(history.len().saturating_sub(limit))
+        .cloned()
+        .map(|mut record| {
+            if let Some([summary, minimal]) = record.client_reasons.take() {
+                match detail {
+                    DenialDetail::Full => {}
+                    DenialDetail::Summary => record.reason = Some(summary),
+                    DenialDetail::Minimal => record.reason = Some(minimal),
+                }
+            }
+            record
+        })
+        .collect()
+}
+
+fn write_audit_record(record: &AuditRecord) {
+    {
+        let mut history = AUDIT_HISTORY
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if history.len() == MAX_AUDIT_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(record.clone());
+    }
+    count_audit_record(record);
+    let Some(log) = AUDIT_LOG.get() else {
+        return;
+    };
+    if let Err(error) = log.append(record) {
+        tracing::error!(path = %log.path().display(), error = %error, "failed writing audit record");
+    }
+}
+
+/// Formats `time` as RFC 3339 in UTC with millisecond precision.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's `civil_from_days`).
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Installs a shared audit log in a temp file for tests and returns its path. Tests tell their
+/// records apart by a unique client id.
+#[cfg(test)]
+pub(crate) fn audit_log_for_tests() -> &'static Path {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| {
+        let path = std::env::temp_dir().join(format!("mcp-run-audit-{}.jsonl", std::process::id()));
+        install_audit_log(&path, AuditRotation::default()).expect("install audit log");
+        path
+    })
+}
+
+#[cfg(test)]
+pub(crate) fn audit_records_for_client(client_id: &str) -> Vec<serde_json::Value> {
+    std::fs::read_to_string(audit_log_for_tests())
+        .expect("read audit log")
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("audit line is json"))
+        .filter(|record| record["clientId"] == client_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn formats_rfc3339_utc_timestamps() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_millis(951_782_400_250)),
+            "2000-02-29T00:00:00.250Z"
+        );
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_767_225_599)),
+            "2025-12-31T23:59:59.000Z"
+        );
+    }
+
+    fn cleanup_record(executable: &str) -> AuditRecord {
+        let mut record =
+            AuditRecord::begin_cleanup(&PolicyMode::Rego, Path::new("/tmp/stale"), "/tmp/*");
+        record.executable = executable.to_string();
+        record
+    }
+
+    #[test]
+    fn rotation_renames_the_log_and_keeps_the_newest_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("audit.jsonl");
+        let line_len = serde_json::to_vec(&cleanup_record("a")).unwrap().len() as u64 + 1;
+        let rotation = AuditRotation {
+            max_bytes: Some(line_len * 2),
+            max_age: None,
+            keep: 2,
+        };
+        let log = AuditLog::open(&path, rotation).expect("open");
+        for executable in ["a", "b", "c", "d", "e", "f", "g"] {
+            log.append(&cleanup_record(executable)).expect("append");
+        }
+
+        let executables = |path: &Path| -> Vec<String> {
+            std::fs::read_to_string(path)
+                .expect("read")
+                .lines()
+                .map(|line| {
+                    let record: serde_json::Value = serde_json::from_str(line).expect("json");
+                    assert_eq!(record["schemaVersion"], AUDIT_SCHEMA_VERSION);
+                    record["executable"].as_str().unwrap().to_string()
+                })
+                .collect()
+        };
+        assert_eq!(executables(&path), ["g"]);
+        assert_eq!(executables(&log.rotated_path(1)), ["e", "f"]);
+        assert_eq!(executables(&log.rotated_path(2)), ["c", "d"]);
+        assert!(!log.rotated_path(3).exists());
+        assert_eq!(log.file_stats().rotations, 3);
+        assert_eq!(log.file_stats().bytes, line_len);
+    }
+
+    #[test]
+    fn stats_count_records_by_decision_and_command() {
+        let executable = format!("stats-test-{}", std::process::id());
+        cleanup_record(&executable).allow();
+        cleanup_record(&executable).deny(&"denied");
+        cleanup_record(&executable).deny(&"denied");
+
+        let stats = audit_stats();
+        assert_eq!(stats.schema_version, AUDIT_SCHEMA_VERSION);
+        assert_eq!(stats.by_command[&executable], DecisionCounts { allow: 1, deny: 2 });
+        assert!(stats.total >= 3);
+        assert!(stats.by_decision.deny >= 2);
+    }
+}
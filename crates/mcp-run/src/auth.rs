@@ -0,0 +1,274 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::raw::error_response;
+use crate::redaction::REDACTED;
+
+/// Environment variable holding the bearer token every HTTP request must present.
+pub const AUTH_TOKEN_ENV: &str = "MCP_AUTH_TOKEN";
+/// Environment variable naming a file to read the bearer token from instead.
+pub const AUTH_TOKEN_FILE_ENV: &str = "MCP_AUTH_TOKEN_FILE";
+/// Environment variable holding the separate bearer token of the `/admin` listener.
+pub const ADMIN_TOKEN_ENV: &str = "MCP_ADMIN_TOKEN";
+/// Environment variable naming a file to read the admin token from instead.
+pub const ADMIN_TOKEN_FILE_ENV: &str = "MCP_ADMIN_TOKEN_FILE";
+
+/// Shared secret for `Authorization: Bearer` authentication. Never printed by `Debug`.
+#[derive(Clone)]
+pub struct AuthToken(Arc<str>);
+
+impl AuthToken {
+    pub fn new(token: &str) -> Result<Self, AuthTokenError> {
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(AuthTokenError::Empty);
+        }
+        Ok(Self(Arc::from(token)))
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Compares SHA-256 digests so the time taken reveals neither the token's length nor how
+    /// many leading bytes of `candidate` were right.
+    pub(crate) fn matches(&self, candidate: &str) -> bool {
+        let expected = Sha256::digest(self.0.as_bytes());
+        let presented = Sha256::digest(candidate.as_bytes());
+        expected
+            .iter()
+            .zip(presented.iter())
+            .fold(0u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+    }
+}
+
+impl std::fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AuthToken").field(&REDACTED).finish()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AuthTokenError {
+    #[error("auth token must not be empty")]
+    Empty,
+    #[error("set only one of {env} and {file_env}")]
+    Conflict {
+        env: &'static str,
+        file_env: &'static str,
+    },
+    #[error("failed to read {file_env} '{}': {source}", path.display())]
+    Read {
+        file_env: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Token from `MCP_AUTH_TOKEN` or the file named by `MCP_AUTH_TOKEN_FILE`; `None` when neither
+/// is set, which leaves the server unauthenticated.
+pub fn auth_token_from_env() -> Result<Option<AuthToken>, AuthTokenError> {
+    token_from_env(AUTH_TOKEN_ENV, AUTH_TOKEN_FILE_ENV)
+}
+
+/// Token from `MCP_ADMIN_TOKEN` or the file named by `MCP_ADMIN_TOKEN_FILE`; `None` when
+/// neither is set, which leaves `/admin` behind `MCP_AUTH_TOKEN` and allow grants disabled.
+pub fn admin_token_from_env() -> Result<Option<AuthToken>, AuthTokenError> {
+    token_from_env(ADMIN_TOKEN_ENV, ADMIN_TOKEN_FILE_ENV)
+}
+
+fn token_from_env(
+    env: &'static str,
+    file_env: &'static str,
+) -> Result<Option<AuthToken>, AuthTokenError> {
+    let non_empty = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+    };
+    match (non_empty(env), non_empty(file_env)) {
+        (Some(_), Some(_)) => Err(AuthTokenError::Conflict { env, file_env }),
+        (Some(token), None) => AuthToken::new(&token).map(Some),
+        (None, Some(path)) => {
+            let path = PathBuf::from(path.trim());
+            let token = std::fs::read_to_string(&path).map_err(|source| AuthTokenError::Read {
+                file_env,
+                path,
+                source,
+            })?;
+            AuthToken::new(&token).map(Some)
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Rejects requests without a matching `Authorization: Bearer <token>` header with a JSON 401.
+pub async fn require_bearer_token(
+    State(token): State<AuthToken>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match bearer_token(request.headers()) {
+        Some(presented) if token.matches(presented) => next.run(request).await,
+        Some(_) => unauthorized("invalid bearer token"),
+        None => unauthorized("missing bearer token"),
+    }
+}
+
+/// Presents `token` on a client request to a server started with the same token.
+pub(crate) fn authorize(
+    request: reqwest::RequestBuilder,
+    token: Option<&AuthToken>,
+) -> reqwest::RequestBuilder {
+    match token {
+        Some(token) => request.bearer_auth(token.as_str()),
+        None => request,
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response = error_response(StatusCode::UNAUTHORIZED, message.to_string());
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::mcp::{ServerOptions, build_app_with_options, build_apps};
+    use crate::policy::PolicyEngine;
+    use crate::raw::RawErrorBody;
+    use crate::test_support::serve_app;
+
+    #[test]
+    fn token_is_trimmed_and_hidden_from_debug() {
+        let token = AuthToken::new(" secret-value\n").expect("token");
+        assert!(token.matches("secret-value"));
+        assert!(!token.matches("secret-valu"));
+        assert!(!token.matches("secret-value2"));
+        assert!(!format!("{token:?}").contains("secret"));
+        assert!(matches!(AuthToken::new(" \n"), Err(AuthTokenError::Empty)));
+    }
+
+    #[tokio::test]
+    async fn every_route_but_the_egress_list_requires_the_bearer_token() {
+        let options = ServerOptions {
+            auth_token: Some(AuthToken::new("s3cret").expect("token")),
+            ..Default::default()
+        };
+        let app = build_app_with_options(
+            Arc::new(PolicyEngine::from_rego_for_tests(&[])),
+            PathBuf::from("."),
+            options,
+        );
+        let (base, server_task) = serve_app(app).await;
+        let client = reqwest::Client::new();
+
+        for path in [
+            "/version",
+            "/raw",
+            "/mcp",
+            "/admin/status",
+            "/no-such-route",
+        ] {
+            let response = client
+                .post(format!("{base}{path}"))
+                .send()
+                .await
+                .expect("request");
+            assert_eq!(
+                response.status(),
+                reqwest::StatusCode::UNAUTHORIZED,
+                "{path}"
+            );
+            assert_eq!(
+                response
+                    .headers()
+                    .get("www-authenticate")
+                    .map(|v| v.as_bytes()),
+                Some(&b"Bearer"[..])
+            );
+            let body: RawErrorBody = response.json().await.expect("json error body");
+            assert_eq!(body.error, "missing bearer token");
+        }
+
+        let wrong = client
+            .get(format!("{base}/version"))
+            .bearer_auth("guess")
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(wrong.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let body: RawErrorBody = wrong.json().await.expect("json error body");
+        assert_eq!(body.error, "invalid bearer token");
+
+        let allowed = client
+            .get(format!("{base}/version"))
+            .header("authorization", "bearer s3cret")
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(allowed.status(), reqwest::StatusCode::OK);
+
+        let egress = client
+            .get(format!("{base}/egress/domains"))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(egress.status(), reqwest::StatusCode::OK);
+        assert_eq!(egress.text().await.expect("body"), "");
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn admin_token_replaces_the_auth_token_on_the_admin_listener() {
+        let options = ServerOptions {
+            auth_token: Some(AuthToken::new("s3cret").expect("token")),
+            admin_token: Some(AuthToken::new("adm1n").expect("admin token")),
+            ..Default::default()
+        };
+        let (app, admin) = build_apps(
+            Arc::new(PolicyEngine::from_rego_for_tests(&[])),
+            PathBuf::from("."),
+            options,
+        );
+        let (base, server_task) = serve_app(app).await;
+        let (admin_base, admin_task) = serve_app(admin).await;
+        let client = reqwest::Client::new();
+        let status = |base: &str, token: &str| {
+            client
+                .get(format!("{base}/admin/status"))
+                .bearer_auth(token)
+                .send()
+        };
+
+        let with_auth = status(&admin_base, "s3cret").await.expect("request");
+        assert_eq!(with_auth.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let with_admin = status(&admin_base, "adm1n").await.expect("request");
+        assert_eq!(with_admin.status(), reqwest::StatusCode::OK);
+        let public = status(&base, "adm1n").await.expect("request");
+        assert_eq!(public.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        server_task.abort();
+        admin_task.abort();
+    }
+}
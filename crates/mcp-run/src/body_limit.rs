@@ -0,0 +1,221 @@
+//! Size and nesting limits for `/raw` and `/mcp` request bodies, enforced before any handler
+//! parses them so an oversized or deeply nested payload cannot exhaust memory or the stack.
+
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use http_body_util::LengthLimitError;
+
+use crate::raw::error_response;
+
+/// Default for [`RequestLimits::max_body_bytes`], the limit axum's JSON extractor used before.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+/// Default for [`RequestLimits::max_json_depth`]. Requests to `/raw` and `/mcp` nest a few
+/// levels at most.
+pub const DEFAULT_MAX_JSON_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// Largest JSON body accepted (`MCP_MAX_BODY_BYTES`). For a streamed `/raw` request it
+    /// bounds each line instead, so stdin itself is not limited.
+    pub max_body_bytes: usize,
+    /// Deepest nesting of objects and arrays accepted in a JSON body (`MCP_MAX_JSON_DEPTH`).
+    pub max_json_depth: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            max_json_depth: DEFAULT_MAX_JSON_DEPTH,
+        }
+    }
+}
+
+/// Buffers a JSON body up to the limit and checks its depth before passing it on. Other
+/// bodies pass through; a `413` from an inner limit is rewritten as a JSON error.
+pub(crate) async fn limit_request_body(
+    State(limits): State<RequestLimits>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !is_json(request.headers()) {
+        let response = next.run(request).await;
+        if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json(response.headers()) {
+            return body_too_large(&limits);
+        }
+        return response;
+    }
+    if content_length(request.headers()).is_some_and(|length| length > limits.max_body_bytes) {
+        return body_too_large(&limits);
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, limits.max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(error) if is_length_limit(&error) => return body_too_large(&limits),
+        Err(error) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Failed to read request body: {error}"),
+            );
+        }
+    };
+    if json_depth_exceeds(&bytes, limits.max_json_depth) {
+        tracing::warn!(
+            max_json_depth = limits.max_json_depth,
+            "request rejected: JSON nested too deeply"
+        );
+        return error_response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Request JSON nests deeper than {} levels", limits.max_json_depth),
+        );
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+fn body_too_large(limits: &RequestLimits) -> Response {
+    tracing::warn!(
+        max_body_bytes = limits.max_body_bytes,
+        "request rejected: body too large"
+    );
+    error_response(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!("Request body exceeds {} bytes", limits.max_body_bytes),
+    )
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"))
+}
+
+fn content_length(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn is_length_limit(error: &axum::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(current) = source {
+        if current.is::<LengthLimitError>() {
+            return true;
+        }
+        source = current.source();
+    }
+    false
+}
+
+/// Whether objects and arrays in `json` nest deeper than `max_depth`. Brackets inside strings
+/// are skipped; the document is otherwise not validated.
+pub(crate) fn json_depth_exceeds(json: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::mcp::{ServerOptions, build_app_with_options};
+    use crate::policy::PolicyEngine;
+    use crate::raw::RawErrorBody;
+    use crate::test_support::serve_app;
+
+    #[test]
+    fn depth_counts_nesting_outside_strings() {
+        assert!(!json_depth_exceeds(br#"{"a": [1, {"b": 2}]}"#, 3));
+        assert!(json_depth_exceeds(br#"{"a": [1, {"b": 2}]}"#, 2));
+        assert!(!json_depth_exceeds(br#"{"a": "[[[{{{\"[["}"#, 1));
+        assert!(json_depth_exceeds(&b"[".repeat(10_000), 32));
+    }
+
+    #[tokio::test]
+    async fn oversized_and_deep_bodies_get_a_json_413() {
+        let options = ServerOptions {
+            request_limits: RequestLimits {
+                max_body_bytes: 1024,
+                max_json_depth: 4,
+            },
+            ..Default::default()
+        };
+        let app = build_app_with_options(
+            Arc::new(PolicyEngine::from_rego_for_tests(&[])),
+            PathBuf::from("."),
+            options,
+        );
+        let (base, server_task) = serve_app(app).await;
+        let client = reqwest::Client::new();
+
+        let oversized = serde_json::json!({"executable": "x".repeat(2048)}).to_string();
+        let deep = format!(r#"{{"executable": "true", "env": {}}}"#, "[".repeat(8));
+        let cases = [
+            ("/raw", "application/json", oversized.clone(), "exceeds 1024 bytes"),
+            ("/mcp", "application/json", oversized, "exceeds 1024 bytes"),
+            ("/mcp", "text/plain", "x".repeat(2048), "exceeds 1024 bytes"),
+            ("/raw", "application/json", deep.clone(), "deeper than 4 levels"),
+            ("/mcp", "application/json", deep, "deeper than 4 levels"),
+        ];
+        for (path, content_type, body, expected) in cases {
+            let response = client
+                .post(format!("{base}{path}"))
+                .header(header::CONTENT_TYPE, content_type)
+                .body(body)
+                .send()
+                .await
+                .expect("request");
+            assert_eq!(
+                response.status(),
+                reqwest::StatusCode::PAYLOAD_TOO_LARGE,
+                "{path} {content_type}"
+            );
+            let body: RawErrorBody = response.json().await.expect("json error body");
+            assert!(body.error.contains(expected), "{}", body.error);
+        }
+
+        // Requests within the limits still reach the handler.
+        let denied = client
+            .post(format!("{base}/raw"))
+            .json(&serde_json::json!({"executable": "true"}))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(denied.status(), reqwest::StatusCode::FORBIDDEN);
+
+        server_task.abort();
+    }
+}
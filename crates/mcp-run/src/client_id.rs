@@ -0,0 +1,68 @@
+use axum::http::HeaderMap;
+use thiserror::Error;
+
+use crate::policy::InvocationContext;
+
+/// Header agents set to identify themselves when they share one server.
+pub const CLIENT_ID_HEADER: &str = "x-client-id";
+const MAX_CLIENT_ID_LEN: usize = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid X-Client-Id header: expected 1-{MAX_CLIENT_ID_LEN} characters of [A-Za-z0-9._-]")]
+pub struct InvalidClientId;
+
+/// Builds the invocation context from request headers. A missing header is anonymous; a
+/// malformed one is rejected so it can never reach policy input, logs or metric labels.
+pub fn context_from_headers(headers: &HeaderMap) -> Result<InvocationContext, InvalidClientId> {
+    let Some(value) = headers.get(CLIENT_ID_HEADER) else {
+        return Ok(InvocationContext::default());
+    };
+    let client_id = value.to_str().map_err(|_| InvalidClientId)?;
+    let valid = !client_id.is_empty()
+        && client_id.len() <= MAX_CLIENT_ID_LEN
+        && client_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b'-'));
+    if !valid {
+        return Err(InvalidClientId);
+    }
+    Ok(InvocationContext {
+        client_id: Some(client_id.to_string()),
+        cwd: None,
+        canonical_cwd: None,
+        hops: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn validates_client_id_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            context_from_headers(&headers),
+            Ok(InvocationContext::default())
+        );
+
+        headers.insert(CLIENT_ID_HEADER, HeaderValue::from_static("agent-1.review_bot"));
+        assert_eq!(
+            context_from_headers(&headers)
+                .expect("valid id")
+                .client_id
+                .as_deref(),
+            Some("agent-1.review_bot")
+        );
+
+        for invalid in ["", "has space", "quote\"", &"x".repeat(65)] {
+            headers.insert(
+                CLIENT_ID_HEADER,
+                HeaderValue::from_str(invalid).expect("header value"),
+            );
+            assert_eq!(context_from_headers(&headers), Err(InvalidClientId));
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Deprecated free-function API from before the executor/policy/mcp split.
+//!
+//! These wrappers keep the old call shapes compiling on top of
+//! [`PolicyEngine`]. The policy source is now a Rego directory rather than
+//! a JSON file; see the README for the decision contract.
+#![allow(deprecated)]
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::executor::{RunNetworkToolInput, RunNetworkToolOutput, ToolError, resolve_executable};
+use crate::policy::{PolicyEngine, ValidationError};
+
+#[deprecated(note = "use mcp_run::PolicyEngine")]
+pub type Policy = PolicyEngine;
+
+/// Marker appended to truncated output before stdout and stderr had separate markers.
+#[deprecated(note = "use mcp_run::STDOUT_TRUNCATION_MARKER or mcp_run::STDERR_TRUNCATION_MARKER")]
+pub const TRUNCATION_MARKER: &str = "\n...truncated...";
+
+#[deprecated(note = "PolicyEngine::from_sources falls back to deny-all instead of failing")]
+#[derive(Debug, Error)]
+pub enum PolicyLoadError {
+    #[error("Failed to load policy from '{path}': {details}")]
+    Load { path: PathBuf, details: String },
+}
+
+/// Loads the Rego policy directory at `path`, failing instead of falling back
+/// to deny-all so callers keep the old load-time error behavior.
+#[deprecated(note = "use mcp_run::PolicyEngine::from_sources")]
+pub fn load_policy(path: impl AsRef<Path>) -> Result<Policy, PolicyLoadError> {
+    let path = path.as_ref().to_path_buf();
+    PolicyEngine::try_from_dir(path.clone())
+        .map_err(|details| PolicyLoadError::Load { path, details })
+}
+
+/// Resolves `command` on PATH, hashes it, and validates the invocation.
+#[deprecated(note = "use mcp_run::PolicyEngine::validate_invocation")]
+pub fn validate_invocation(
+    policy: &Policy,
+    command: &str,
+    args: &[String],
+    env: &BTreeMap<String, String>,
+) -> Result<(), ValidationError> {
+    let (path, hash) = resolve_executable(command)?;
+    policy.validate_invocation(command, &path, &hash, args, env)
+}
+
+/// Runs `input` with the process working directory as the default cwd.
+#[deprecated(note = "use mcp_run::run_network_tool_impl with an explicit default cwd")]
+pub async fn run_network_tool_impl(
+    policy: &Policy,
+    input: RunNetworkToolInput,
+) -> Result<RunNetworkToolOutput, ToolError> {
+    let default_cwd = std::env::current_dir().map_err(|source| ToolError::Spawn { source })?;
+    crate::executor::run_network_tool_impl(policy, &default_cwd, input).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::find_executable;
+
+    #[test]
+    fn load_policy_reports_missing_directory() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let missing = dir.path().join("missing");
+        let error = load_policy(&missing).expect_err("missing dir should fail");
+        assert!(matches!(error, PolicyLoadError::Load { path, .. } if path == missing));
+    }
+
+    #[tokio::test]
+    async fn old_signatures_delegate_to_policy_engine() {
+        let env_path = match find_executable("env") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let escaped = env_path.replace('\\', "\\\\").replace('"', "\\\"");
+        std::fs::write(
+            dir.path().join("main.rego"),
+            format!(
+                "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n}}\n"
+            ),
+        )
+        .expect("write policy");
+
+        let policy = load_policy(dir.path()).expect("load policy");
+        let env = BTreeMap::new();
+        validate_invocation(&policy, &env_path, &[], &env).expect("env allowed");
+        assert!(matches!(
+            validate_invocation(&policy, "sh", &[], &env),
+            Err(ValidationError::CommandNotAllowed(_))
+        ));
+
+        let output = run_network_tool_impl(
+            &policy,
+            RunNetworkToolInput {
+                executable: env_path,
+                args: vec!["printf".to_string(), "compat".to_string()],
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect("run tool");
+        assert_eq!(output.stdout, "compat");
+        assert_eq!(output.exit_code, Some(0));
+    }
+}
@@ -0,0 +1,131 @@
+//! Keeps the `/metrics` execution counters across restarts. With `MCP_COUNTERS_FILE` set the
+//! server adds the saved counts back at startup, then rewrites the file every flush interval
+//! and once more on shutdown, so restarting the server does not reset them.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::metrics::{CounterSnapshot, counter_snapshot, restore_counters};
+
+pub const COUNTERS_FILE_ENV: &str = "MCP_COUNTERS_FILE";
+pub const COUNTERS_FLUSH_ENV: &str = "MCP_COUNTERS_FLUSH_SECS";
+pub const DEFAULT_COUNTERS_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum CountersError {
+    #[error("failed to read counters file '{}': {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("invalid counters file '{}': {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("failed to write counters file '{}': {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+}
+
+/// The saved counters, or empty ones before the first flush.
+pub(crate) fn load_counters(path: &Path) -> Result<CounterSnapshot, CountersError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(source) if source.kind() == io::ErrorKind::NotFound => {
+            return Ok(CounterSnapshot::default());
+        }
+        Err(source) => {
+            return Err(CountersError::Read {
+                path: path.to_path_buf(),
+                source,
+            });
+        }
+    };
+    serde_json::from_str(&contents).map_err(|source| CountersError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Replaces the file in one rename, so a crash mid-write keeps the previous counts.
+pub(crate) fn save_counters(path: &Path, snapshot: &CounterSnapshot) -> Result<(), CountersError> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    let contents = serde_json::to_string(snapshot).expect("counter snapshot serializes") + "\n";
+    std::fs::write(&temp_path, contents)
+        .and_then(|()| std::fs::rename(&temp_path, path))
+        .map_err(|source| CountersError::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+/// Adds the counts saved in `path` to the live counters.
+pub(crate) fn restore_counters_from(path: &Path) -> Result<(), CountersError> {
+    restore_counters(&load_counters(path)?);
+    Ok(())
+}
+
+/// Saves the live counters every `interval` until `stop` is cancelled, then once more.
+pub(crate) async fn flush_counters_periodically(
+    path: PathBuf,
+    interval: Duration,
+    stop: CancellationToken,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    ticks.tick().await;
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            () = stop.cancelled() => break,
+        }
+        flush_counters(&path);
+    }
+    flush_counters(&path);
+}
+
+fn flush_counters(path: &Path) {
+    if let Err(error) = save_counters(path, &counter_snapshot()) {
+        tracing::warn!(%error, "failed to save execution counters");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_round_trip_through_the_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "mcp-run-counters-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("counters.json");
+        assert_eq!(load_counters(&path).unwrap(), CounterSnapshot::default());
+
+        let mut snapshot = CounterSnapshot {
+            commands_exited: 7,
+            user_cpu_ms: 1200,
+            max_rss_bytes: 4096,
+            ..Default::default()
+        };
+        snapshot.client_executions.insert("builder".to_string(), 5);
+        save_counters(&path, &snapshot).unwrap();
+        assert_eq!(load_counters(&path).unwrap(), snapshot);
+
+        std::fs::write(&path, "{not json").unwrap();
+        assert!(matches!(
+            load_counters(&path),
+            Err(CountersError::Parse { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
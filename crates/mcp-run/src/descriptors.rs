@@ -0,0 +1,175 @@
+//! Keeps the server's own descriptors (listening sockets, policy files, the audit log) out of
+//! spawned commands, which should only ever inherit stdin, stdout and stderr.
+
+/// Makes every descriptor above stderr close-on-exec in the child, whatever flags the parent
+/// opened it with.
+///
+/// Descriptors are marked rather than closed so the pipe std uses to report `exec` failures,
+/// which is already close-on-exec, keeps working.
+#[cfg(unix)]
+pub(crate) fn close_inherited_descriptors(command: &mut tokio::process::Command) {
+    // SAFETY: the hook only makes raw syscalls, which are async-signal-safe, and does not
+    // allocate or take locks.
+    unsafe {
+        command.pre_exec(|| {
+            mark_cloexec_from(3);
+            Ok(())
+        });
+    }
+}
+
+#[cfg(unix)]
+fn mark_cloexec_from(first: libc::c_int) {
+    #[cfg(target_os = "linux")]
+    {
+        // Linux 5.11+ does this in one call; older kernels fall through to the loop.
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_close_range,
+                first as libc::c_uint,
+                libc::c_uint::MAX,
+                libc::CLOSE_RANGE_CLOEXEC,
+            )
+        };
+        if rc == 0 {
+            return;
+        }
+    }
+    for fd in first..descriptor_limit() {
+        unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFD);
+            if flags >= 0 && flags & libc::FD_CLOEXEC == 0 {
+                libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn descriptor_limit() -> libc::c_int {
+    let mut limit: libc::rlimit = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0
+        || limit.rlim_cur == libc::RLIM_INFINITY
+    {
+        return 1024;
+    }
+    libc::c_int::try_from(limit.rlim_cur).unwrap_or(libc::c_int::MAX)
+}
+
+/// Descriptors above stderr that are currently open without close-on-exec, and so would be
+/// inherited by a command spawned without [`close_inherited_descriptors`].
+#[cfg(unix)]
+pub fn inheritable_descriptors() -> Vec<i32> {
+    open_descriptors()
+        .into_iter()
+        .filter(|fd| *fd > 2)
+        .filter(|fd| {
+            let flags = unsafe { libc::fcntl(*fd, libc::F_GETFD) };
+            flags >= 0 && flags & libc::FD_CLOEXEC == 0
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+pub fn inheritable_descriptors() -> Vec<i32> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn open_descriptors() -> Vec<i32> {
+    match std::fs::read_dir("/proc/self/fd") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .collect(),
+        Err(_) => (0..descriptor_limit().min(4096)).collect(),
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn open_descriptors() -> Vec<i32> {
+    (0..descriptor_limit().min(4096)).collect()
+}
+
+/// Startup check that the server holds no descriptor a command could inherit. Offenders are
+/// logged and marked close-on-exec; spawns are protected either way, so this is a tripwire
+/// for a dependency that opens descriptors carelessly.
+pub fn descriptor_self_check() {
+    let leaked = inheritable_descriptors();
+    if leaked.is_empty() {
+        tracing::info!("descriptor self-check passed: no inheritable descriptors");
+        return;
+    }
+    tracing::warn!(descriptors = ?leaked, "descriptor self-check found inheritable descriptors; marking them close-on-exec");
+    #[cfg(unix)]
+    for fd in leaked {
+        unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFD);
+            if flags >= 0 {
+                libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::os::fd::AsRawFd;
+    use std::path::Path;
+
+    use super::*;
+    use crate::executor::{RunNetworkToolInput, run_network_tool_impl};
+    use crate::policy::PolicyEngine;
+    use crate::test_support::find_executable;
+
+    #[tokio::test]
+    async fn spawned_commands_inherit_only_stdio() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let file = tempfile::tempfile().expect("tempfile");
+        // dup() clears close-on-exec, like a descriptor opened by careless native code.
+        let leaked = unsafe { libc::dup(file.as_raw_fd()) };
+        assert!(leaked > 2, "dup failed");
+
+        let escaped = sh_path.replace('\\', "\\\\").replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n}}\n"
+        );
+        let engine = PolicyEngine::from_rego_for_tests(&[("main.rego", &main)]);
+        let output = run_network_tool_impl(
+            &engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: sh_path,
+                args: vec![
+                    "-c".to_string(),
+                    format!(
+                        "for fd in 0 1 2 {leaked}; do [ -e /proc/$$/fd/$fd ] && printf '%s ' $fd; done"
+                    ),
+                ],
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect("run");
+        unsafe {
+            libc::close(leaked);
+        }
+
+        assert_eq!(output.stdout, "0 1 2 ");
+    }
+
+    #[test]
+    fn self_check_marks_leaked_descriptors() {
+        let file = tempfile::tempfile().expect("tempfile");
+        let leaked = unsafe { libc::dup(file.as_raw_fd()) };
+        assert!(inheritable_descriptors().contains(&leaked));
+
+        descriptor_self_check();
+        assert!(!inheritable_descriptors().contains(&leaked));
+        unsafe {
+            libc::close(leaked);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Opens the domains a policy's `domains` rule declares in the proxy for as long as the
+//! command runs. The server keeps the domains of every running command in memory and serves
+//! them at `GET /egress/domains`, one per line. The proxy polls that list into a file of its
+//! own and reconfigures squid when it changes, so nothing the sandbox's commands can write
+//! feeds squid's allowlist.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+
+/// Process-wide list, shared by every command the server runs.
+static EGRESS_DOMAINS: LazyLock<Arc<EgressDomains>> =
+    LazyLock::new(|| Arc::new(EgressDomains::default()));
+
+/// How many running commands hold each domain open.
+#[derive(Debug, Default)]
+pub(crate) struct EgressDomains {
+    open: Mutex<BTreeMap<String, usize>>,
+}
+
+impl EgressDomains {
+    /// Holds `domains` open until the lease is dropped.
+    pub(crate) fn open(self: &Arc<Self>, domains: &BTreeSet<String>) -> EgressLease {
+        let mut open = self.lock();
+        for domain in domains {
+            *open.entry(domain.clone()).or_default() += 1;
+        }
+        EgressLease {
+            domains: Arc::clone(self),
+            held: domains.iter().cloned().collect(),
+        }
+    }
+
+    fn release(&self, domains: &[String]) {
+        let mut open = self.lock();
+        for domain in domains {
+            if let Some(count) = open.get_mut(domain) {
+                *count -= 1;
+                if *count == 0 {
+                    open.remove(domain);
+                }
+            }
+        }
+    }
+
+    /// The open domains, one per line, in the `dstdomain` list format squid reads.
+    pub(crate) fn list(&self) -> String {
+        let mut contents = String::new();
+        for domain in self.lock().keys() {
+            contents.push_str(domain);
+            contents.push('\n');
+        }
+        contents
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, usize>> {
+        self.open.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Domains one running command holds open in [`EgressDomains`].
+#[derive(Debug)]
+pub(crate) struct EgressLease {
+    domains: Arc<EgressDomains>,
+    held: Vec<String>,
+}
+
+impl Drop for EgressLease {
+    fn drop(&mut self) {
+        self.domains.release(&self.held);
+    }
+}
+
+/// Where an audit record keeps its command's lease. Copies of the record, such as the one
+/// kept for `/admin/history`, do not hold the domains open.
+#[derive(Debug, Default)]
+pub(crate) struct EgressHold {
+    _lease: Option<EgressLease>,
+}
+
+impl Clone for EgressHold {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Holds `domains` open in the server's list.
+pub(crate) fn hold_egress_domains(domains: &BTreeSet<String>) -> EgressHold {
+    if domains.is_empty() {
+        return EgressHold::default();
+    }
+    EgressHold {
+        _lease: Some(EGRESS_DOMAINS.open(domains)),
+    }
+}
+
+/// `GET /egress/domains`: the domains running commands hold open, for the proxy to poll.
+pub(crate) async fn egress_domains_handler() -> String {
+    EGRESS_DOMAINS.list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domains_stay_listed_while_any_command_holds_them() {
+        let egress = Arc::new(EgressDomains::default());
+        assert_eq!(egress.list(), "");
+
+        let set = |domains: &[&str]| domains.iter().map(|d| d.to_string()).collect();
+        let npm = egress.open(&set(&["registry.npmjs.org", ".github.com"]));
+        let git = egress.open(&set(&[".github.com"]));
+        assert_eq!(egress.list(), ".github.com\nregistry.npmjs.org\n");
+
+        drop(npm);
+        assert_eq!(egress.list(), ".github.com\n");
+        let copy = EgressHold { _lease: Some(git) }.clone();
+        assert!(copy._lease.is_none());
+        assert_eq!(egress.list(), "");
+    }
+}
@@ -0,0 +1,455 @@
+//! Long-polled executions (`POST /run`, `GET /executions/{id}/wait`): a non-streaming way to
+//! run a command for clients that cannot read chunked responses.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Path, Query, State, rejection::JsonRejection};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::Instrument;
+
+use crate::audit::AuditSource;
+use crate::client_id::context_from_headers;
+use crate::executor::{
+    RunNetworkToolInput, RunNetworkToolOutput, ToolError, collect_audited_output,
+    spawn_limited_process,
+};
+use crate::limiter::StreamPermit;
+use crate::raw::{RawEndpointState, error_response, limit_response};
+use crate::redaction::redact_env_values;
+
+/// How long `GET /executions/{id}/wait` waits without a `timeout` parameter.
+pub const DEFAULT_EXECUTION_WAIT: Duration = Duration::from_secs(30);
+/// Longer `timeout` parameters are cut down to this.
+pub const MAX_EXECUTION_WAIT: Duration = Duration::from_secs(300);
+/// Finished executions kept for waiting on; beyond this the oldest are forgotten.
+pub const MAX_FINISHED_EXECUTIONS: usize = 32;
+pub(crate) const EXECUTION_KILLED: &str = "Execution killed";
+
+/// Response to `POST /run`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionStarted {
+    pub execution_id: String,
+}
+
+/// Response to `GET /executions/{id}/wait`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    /// Still running when the wait timed out.
+    Running {},
+    /// Exited; the same output `run_network_tool` returns.
+    Finished(RunNetworkToolOutput),
+    /// Did not run to completion, e.g. it timed out or its output could not be read.
+    Failed { error: String },
+}
+
+/// Executions by id, shared by the `POST /run` and wait handlers.
+#[derive(Debug, Default)]
+pub struct ExecutionRegistry {
+    table: Mutex<ExecutionTable>,
+}
+
+#[derive(Debug, Default)]
+struct ExecutionTable {
+    next_id: u64,
+    executions: BTreeMap<u64, Arc<Execution>>,
+}
+
+impl ExecutionRegistry {
+    fn table(&self) -> MutexGuard<'_, ExecutionTable> {
+        self.table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn insert(&self, execution: Arc<Execution>) -> String {
+        let mut table = self.table();
+        table.next_id += 1;
+        let id = table.next_id;
+        table.executions.insert(id, execution);
+
+        let finished: Vec<u64> = table
+            .executions
+            .iter()
+            .filter(|(_, execution)| execution.status().is_some())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished.iter().take(finished.len().saturating_sub(MAX_FINISHED_EXECUTIONS)) {
+            table.executions.remove(id);
+        }
+        format_execution_id(id)
+    }
+
+    fn get(&self, execution_id: &str) -> Option<Arc<Execution>> {
+        let id = parse_execution_id(execution_id)?;
+        self.table().executions.get(&id).cloned()
+    }
+
+    /// Kills the execution's process group if it is still running and waits for it to end.
+    /// Returns whether it had to be killed, or `None` for an unknown id.
+    pub(crate) async fn kill(&self, execution_id: &str) -> Option<bool> {
+        let execution = self.get(execution_id)?;
+        if execution.status().is_some() {
+            return Some(false);
+        }
+        execution.killed.store(true, Ordering::SeqCst);
+        if let Some(process_group) = execution.process_group {
+            kill_process_group_id(process_group);
+        }
+        execution.wait(MAX_EXECUTION_WAIT).await;
+        Some(true)
+    }
+
+    /// Running and finished executions.
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        let table = self.table();
+        let finished = table
+            .executions
+            .values()
+            .filter(|execution| execution.status().is_some())
+            .count();
+        (table.executions.len() - finished, finished)
+    }
+}
+
+fn kill_process_group_id(process_group: u32) {
+    #[cfg(unix)]
+    if let Ok(pgid) = libc::pid_t::try_from(process_group) {
+        unsafe {
+            libc::killpg(pgid, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = process_group;
+}
+
+fn format_execution_id(id: u64) -> String {
+    format!("exec-{id}")
+}
+
+fn parse_execution_id(execution_id: &str) -> Option<u64> {
+    execution_id.strip_prefix("exec-")?.parse().ok()
+}
+
+#[derive(Debug)]
+struct Execution {
+    /// `None` until the command has finished or failed.
+    result: watch::Sender<Option<ExecutionStatus>>,
+    /// The child's process group, which `mcp-run admin kill` signals.
+    process_group: Option<u32>,
+    killed: AtomicBool,
+}
+
+impl Execution {
+    fn new(process_group: Option<u32>) -> Self {
+        Self {
+            result: watch::Sender::new(None),
+            process_group,
+            killed: AtomicBool::new(false),
+        }
+    }
+
+    fn status(&self) -> Option<ExecutionStatus> {
+        self.result.borrow().clone()
+    }
+
+    fn finish(&self, status: ExecutionStatus) {
+        self.result.send_replace(Some(status));
+    }
+
+    /// The final status, or `None` if the command is still running after `timeout`.
+    async fn wait(&self, timeout: Duration) -> Option<ExecutionStatus> {
+        let mut result = self.result.subscribe();
+        let finished = tokio::time::timeout(timeout, result.wait_for(Option::is_some)).await;
+        match finished {
+            Ok(Ok(status)) => status.clone(),
+            _ => self.status(),
+        }
+    }
+}
+
+/// Parses a `timeout` query parameter: `500ms`, `30s`, `2m`, or a plain number of seconds.
+fn parse_wait_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+/// Starts a command with the `/raw` payload and returns its execution id without waiting.
+pub async fn start_run_handler(
+    State(state): State<RawEndpointState>,
+    headers: HeaderMap,
+    payload: Result<Json<RunNetworkToolInput>, JsonRejection>,
+) -> Response {
+    let input = match payload {
+        Ok(Json(input)) => input,
+        Err(error) => {
+            tracing::warn!(error = %error, "run request rejected before validation");
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid request payload: {error}"),
+            );
+        }
+    };
+    let context = match context_from_headers(&headers) {
+        Ok(context) => context,
+        Err(error) => {
+            tracing::warn!(error = %error, "run request rejected before validation");
+            return error_response(StatusCode::BAD_REQUEST, error.to_string());
+        }
+    };
+    let client_id = context.client_id.clone().unwrap_or_default();
+    let executable = input.executable.clone();
+    let args_for_log = redact_env_values(
+        &input.args,
+        input.env.as_ref().unwrap_or(&BTreeMap::new()),
+        |_| true,
+    );
+    let timeout_ms = input.timeout_ms;
+    let spills = (input.spill_output == Some(true)).then(|| state.spills.clone());
+
+    let Some(execution_permit) = state.executions.try_acquire() else {
+        let limit = state.executions.limits().max_active.unwrap_or_default();
+        tracing::warn!(command = %executable, args = ?args_for_log, limit, "run request rejected: concurrency limit reached");
+        return limit_response(
+            ToolError::ConcurrencyLimit { limit }.to_string(),
+            state.executions.retry_guidance(),
+        );
+    };
+
+    let (child, limits, redact, audit) = match spawn_limited_process(
+        &state.policy_engine,
+        &state.default_cwd,
+        input,
+        state.output_limits,
+        &context,
+        AuditSource::Run,
+    ) {
+        Ok(spawned) => spawned,
+        Err(ToolError::Validation(error)) => {
+            tracing::warn!(command = %executable, args = ?args_for_log, client_id, error = %error, "run request denied by policy");
+            let message = state.policy_engine.denial_message(&error);
+            return error_response(StatusCode::FORBIDDEN, message);
+        }
+        Err(error @ (ToolError::InvalidStdin { .. } | ToolError::PtyModeUnsupported)) => {
+            tracing::warn!(command = %executable, args = ?args_for_log, error = %error, "run request rejected before validation");
+            return error_response(StatusCode::BAD_REQUEST, error.to_string());
+        }
+        Err(error) => {
+            tracing::error!(command = %executable, args = ?args_for_log, error = %error, "run request failed before start");
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
+    };
+    let args_for_log = audit.args.clone();
+
+    let execution = Arc::new(Execution::new(child.id()));
+    let execution_id = state.runs.insert(execution.clone());
+    tracing::info!(execution_id, command = %executable, args = ?args_for_log, client_id, "execution started");
+
+    let task_execution_id = execution_id.clone();
+    let task = async move {
+        // The execution slot is held until the child is reaped, not just until the request ends.
+        let _permit: StreamPermit = execution_permit;
+        let result = collect_audited_output(
+            child,
+            limits,
+            &redact,
+            timeout_ms,
+            audit,
+            spills.as_deref(),
+        )
+        .await;
+        let status = match result {
+            _ if execution.killed.load(Ordering::SeqCst) => {
+                tracing::info!(execution_id = task_execution_id, command = %executable, "execution killed");
+                ExecutionStatus::Failed {
+                    error: EXECUTION_KILLED.to_string(),
+                }
+            }
+            Ok(output) => {
+                tracing::info!(execution_id = task_execution_id, command = %executable, exit_code = ?output.exit_code, "execution finished");
+                ExecutionStatus::Finished(output)
+            }
+            Err(error) => {
+                tracing::warn!(execution_id = task_execution_id, command = %executable, error = %error, "execution failed");
+                ExecutionStatus::Failed {
+                    error: error.to_string(),
+                }
+            }
+        };
+        execution.finish(status);
+    };
+    // Later log lines keep the id of the request that started the execution.
+    tokio::spawn(task.in_current_span());
+
+    (StatusCode::CREATED, Json(ExecutionStarted { execution_id })).into_response()
+}
+
+/// Query of `GET /executions/{id}/wait`.
+#[derive(Debug, Deserialize)]
+pub struct WaitQuery {
+    pub timeout: Option<String>,
+}
+
+/// Waits up to `timeout` for the execution to end: `200` with its final status, or `202` with
+/// `{"status": "running"}` so the client can wait again.
+pub async fn wait_execution_handler(
+    State(state): State<RawEndpointState>,
+    Path(execution_id): Path<String>,
+    Query(query): Query<WaitQuery>,
+) -> Response {
+    let timeout = match query.timeout.as_deref() {
+        Some(value) => match parse_wait_timeout(value) {
+            Some(timeout) => timeout.min(MAX_EXECUTION_WAIT),
+            None => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid timeout: {value} (expected e.g. 500ms, 30s or 2m)"),
+                );
+            }
+        },
+        None => DEFAULT_EXECUTION_WAIT,
+    };
+    let Some(execution) = state.runs.get(&execution_id) else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            format!("Unknown execution: {execution_id}"),
+        );
+    };
+    match execution.wait(timeout).await {
+        Some(status) => Json(status).into_response(),
+        None => (StatusCode::ACCEPTED, Json(ExecutionStatus::Running {})).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::test_support::{find_executable, sh_input, start_sh_server};
+
+    async fn start_run(base: &str, input: &RunNetworkToolInput) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{base}/run"))
+            .json(input)
+            .send()
+            .await
+            .expect("start run")
+    }
+
+    #[tokio::test]
+    async fn wait_returns_running_then_the_final_output() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let (base, server_task) = start_sh_server(&sh_path).await;
+        let response = start_run(&base, &sh_input(&sh_path, "sleep 0.5; echo done; exit 3")).await;
+        assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+        let execution_id = response
+            .json::<ExecutionStarted>()
+            .await
+            .expect("execution id")
+            .execution_id;
+
+        let started = Instant::now();
+        let pending = reqwest::get(format!("{base}/executions/{execution_id}/wait?timeout=50ms"))
+            .await
+            .expect("short wait");
+        assert_eq!(pending.status(), reqwest::StatusCode::ACCEPTED);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            pending.json::<ExecutionStatus>().await.expect("running body"),
+            ExecutionStatus::Running {}
+        );
+
+        let finished = reqwest::get(format!("{base}/executions/{execution_id}/wait?timeout=30s"))
+            .await
+            .expect("long wait");
+        assert_eq!(finished.status(), reqwest::StatusCode::OK);
+        let ExecutionStatus::Finished(output) =
+            finished.json::<ExecutionStatus>().await.expect("final body")
+        else {
+            panic!("execution did not finish");
+        };
+        assert_eq!(output.stdout, "done\n");
+        assert_eq!(output.exit_code, Some(3));
+
+        // The result stays available to later waits.
+        let again = reqwest::get(format!("{base}/executions/{execution_id}/wait?timeout=0"))
+            .await
+            .expect("repeat wait");
+        assert_eq!(again.status(), reqwest::StatusCode::OK);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn run_rejects_denied_commands_and_wait_rejects_unknown_ids() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let Some(echo_path) = find_executable("echo") else {
+            return;
+        };
+        let (base, server_task) = start_sh_server(&sh_path).await;
+
+        let denied = start_run(&base, &sh_input(&echo_path, "hi")).await;
+        assert_eq!(denied.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let unknown = reqwest::get(format!("{base}/executions/exec-999/wait"))
+            .await
+            .expect("wait unknown");
+        assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let bad_timeout = reqwest::get(format!("{base}/executions/exec-1/wait?timeout=soon"))
+            .await
+            .expect("wait bad timeout");
+        assert_eq!(bad_timeout.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        server_task.abort();
+    }
+
+    #[test]
+    fn parses_wait_timeouts() {
+        assert_eq!(parse_wait_timeout("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_wait_timeout("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_wait_timeout("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_wait_timeout("15"), Some(Duration::from_secs(15)));
+        assert_eq!(parse_wait_timeout("1h"), None);
+        assert_eq!(parse_wait_timeout("s"), None);
+        assert_eq!(parse_wait_timeout("-1s"), None);
+    }
+
+    #[test]
+    fn finished_status_flattens_the_tool_output() {
+        let status = ExecutionStatus::Finished(RunNetworkToolOutput {
+            stdout: "hi\n".to_string(),
+            stderr: String::new(),
+            exit_code: Some(0),
+            duration_ms: 12,
+            resource_usage: None,
+            stdout_continuation: None,
+            stderr_continuation: None,
+        });
+        assert_eq!(
+            serde_json::to_value(&status).expect("serialize"),
+            serde_json::json!({"status": "finished", "stdout": "hi\n", "stderr": "", "exitCode": 0, "durationMs": 12})
+        );
+    }
+}
@@ -1,22 +1,91 @@
-use std::collections::BTreeMap;
-use std::fmt::Write as _;
-use std::io::Read;
+use std::collections::{BTreeMap, VecDeque};
 use std::path::Path;
 use std::process::Stdio;
+use std::time::{Duration, Instant};
 
+use base64::Engine as _;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use thiserror::Error;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::process::{Child, Command};
+use tracing::Instrument;
+
+use crate::audit::{AuditRecord, AuditSource};
+use crate::hashing::{HashAlgorithm, hash_file_hex};
+#[cfg(unix)]
+use crate::descriptors::close_inherited_descriptors;
+use crate::egress::hold_egress_domains;
+use crate::metrics::{record_child_exit, record_client_execution};
+use crate::pty::{PtyMaster, TerminalSize, attach_terminal};
+use crate::policy::{
+    InvocationContext, OutputLimitOverrides, PolicyDecision, PolicyEngine, ValidationError,
+    normalize_lexically,
+};
+use crate::redaction::{REDACT_LOOKAHEAD_BYTES, RedactPatterns};
+use crate::runtime::run_blocking;
+use crate::self_invocation::HOPS_ENV;
+use crate::spill::{OutputSpills, SpillWriter};
+
+/// Default per-stream capture limit for `run_network_tool`, unless `MCP_MAX_OUTPUT_BYTES` is set.
+pub const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+pub const STDOUT_TRUNCATION_MARKER: &str = "\n...stdout truncated...";
+pub const STDERR_TRUNCATION_MARKER: &str = "\n...stderr truncated...";
 
-use crate::policy::{PolicyEngine, ValidationError};
+/// Which part of a stream over its limit `run_network_tool` keeps.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Truncation {
+    /// The first bytes, followed by the truncation marker.
+    #[default]
+    Head,
+    /// The last bytes, after the truncation marker; where a failing build reports its error.
+    Tail,
+    /// Half the limit from each end, with the marker between them.
+    HeadAndTail,
+}
 
-pub const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
-pub const TRUNCATION_MARKER: &str = "\n...truncated...";
+/// Bytes of stdout and stderr kept by `run_network_tool`; each stream is truncated on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputLimits {
+    pub stdout_bytes: usize,
+    pub stderr_bytes: usize,
+    pub truncation: Truncation,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+impl Default for OutputLimits {
+    fn default() -> Self {
+        Self {
+            stdout_bytes: MAX_OUTPUT_BYTES,
+            stderr_bytes: MAX_OUTPUT_BYTES,
+            truncation: Truncation::default(),
+        }
+    }
+}
+
+impl OutputLimits {
+    fn with_overrides(self, overrides: OutputLimitOverrides) -> Self {
+        Self {
+            stdout_bytes: overrides.stdout.unwrap_or(self.stdout_bytes),
+            stderr_bytes: overrides.stderr.unwrap_or(self.stderr_bytes),
+            ..self
+        }
+    }
+
+    /// Lowers both streams to at most `max_bytes`, when given.
+    fn capped(self, max_bytes: Option<usize>) -> Self {
+        let Some(max_bytes) = max_bytes else {
+            return self;
+        };
+        Self {
+            stdout_bytes: self.stdout_bytes.min(max_bytes),
+            stderr_bytes: self.stderr_bytes.min(max_bytes),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RunNetworkToolInput {
     pub executable: String,
@@ -26,6 +95,31 @@ pub struct RunNetworkToolInput {
     pub cwd: Option<String>,
     #[serde(default)]
     pub env: Option<BTreeMap<String, String>>,
+    /// Kill the command (and its process group) after this many milliseconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Base64-encoded bytes written to the command's stdin, which is then closed.
+    /// Without it, stdin is `/dev/null`.
+    #[serde(default)]
+    pub stdin_b64: Option<String>,
+    /// Keep at most this many bytes of each stream. Only lowers the server and policy limits.
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+    /// Which part of a stream over its limit is kept; the start when unset.
+    #[serde(default)]
+    pub truncation: Option<Truncation>,
+    /// Keep the whole of a truncated stream on the server and return a continuation token
+    /// for the part left out, which `get_output_chunk` pages through.
+    #[serde(default)]
+    pub spill_output: Option<bool>,
+    /// Run the command on a pseudo-terminal instead of pipes. Only `/raw` supports it.
+    #[serde(default)]
+    #[schemars(skip)]
+    pub pty_mode: Option<bool>,
+    /// Initial window size of the `ptyMode` terminal; 24 rows by 80 columns when unset.
+    #[serde(default)]
+    #[schemars(skip)]
+    pub terminal_size: Option<TerminalSize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
@@ -35,6 +129,27 @@ pub struct RunNetworkToolOutput {
     pub stderr: String,
     #[serde(rename = "exitCode")]
     pub exit_code: Option<i32>,
+    /// Wall-clock time from spawn until the command exited and its output was read.
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Kernel resource accounting for the command and the descendants it waited for.
+    /// Only reported on unix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_usage: Option<ResourceUsage>,
+    /// Token for the stdout `spillOutput` kept after truncating it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout_continuation: Option<String>,
+    /// Token for the stderr `spillOutput` kept after truncating it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_continuation: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub max_rss_bytes: u64,
+    pub user_cpu_ms: u64,
+    pub system_cpu_ms: u64,
 }
 
 #[derive(Debug, Error)]
@@ -53,6 +168,16 @@ pub enum ToolError {
     StdoutJoin { source: tokio::task::JoinError },
     #[error("Failed to join stderr reader: {source}")]
     StderrJoin { source: tokio::task::JoinError },
+    #[error("Command timed out after {timeout_ms} ms")]
+    Timeout { timeout_ms: u64 },
+    #[error("Invalid stdinB64: {source}")]
+    InvalidStdin { source: base64::DecodeError },
+    #[error("Too many concurrent executions (limit {limit})")]
+    ConcurrencyLimit { limit: usize },
+    #[error("ptyMode is only supported by the /raw endpoint")]
+    PtyModeUnsupported,
+    #[error("spillOutput is not supported here: the caller keeps no spilled output")]
+    SpillUnsupported,
 }
 
 pub async fn run_network_tool_impl(
@@ -60,7 +185,145 @@ pub async fn run_network_tool_impl(
     default_cwd: &Path,
     input: RunNetworkToolInput,
 ) -> Result<RunNetworkToolOutput, ToolError> {
-    let mut child = spawn_network_tool_process(policy_engine, default_cwd, input)?;
+    run_network_tool_with_limits(policy_engine, default_cwd, input, OutputLimits::default()).await
+}
+
+/// Runs `input` keeping at most `limits` bytes per stream, unless the policy overrides them.
+pub async fn run_network_tool_with_limits(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    limits: OutputLimits,
+) -> Result<RunNetworkToolOutput, ToolError> {
+    run_network_tool_in_context(
+        policy_engine,
+        default_cwd,
+        input,
+        limits,
+        &InvocationContext::default(),
+    )
+    .await
+}
+
+/// Like [`run_network_tool_with_limits`], evaluating the policy with the caller's `context`.
+pub async fn run_network_tool_in_context(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    limits: OutputLimits,
+    context: &InvocationContext,
+) -> Result<RunNetworkToolOutput, ToolError> {
+    if input.spill_output == Some(true) {
+        return Err(ToolError::SpillUnsupported);
+    }
+    run_limited(policy_engine, default_cwd, input, limits, context, None).await
+}
+
+/// Like [`run_network_tool_in_context`], keeping truncated streams in `spills` when the input
+/// sets `spillOutput`.
+pub async fn run_network_tool_with_spills(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    limits: OutputLimits,
+    context: &InvocationContext,
+    spills: &OutputSpills,
+) -> Result<RunNetworkToolOutput, ToolError> {
+    run_limited(policy_engine, default_cwd, input, limits, context, Some(spills)).await
+}
+
+async fn run_limited(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    limits: OutputLimits,
+    context: &InvocationContext,
+    spills: Option<&OutputSpills>,
+) -> Result<RunNetworkToolOutput, ToolError> {
+    let timeout_ms = input.timeout_ms;
+    let spills = spills.filter(|_| input.spill_output == Some(true));
+    let (child, limits, redact, audit) = spawn_limited_process(
+        policy_engine,
+        default_cwd,
+        input,
+        limits,
+        context,
+        AuditSource::Mcp,
+    )?;
+    collect_audited_output(child, limits, &redact, timeout_ms, audit, spills).await
+}
+
+/// Spawns a command whose output is captured whole, returning `limits` with the policy's
+/// overrides applied, its `redact` patterns and the audit record [`collect_audited_output`]
+/// completes.
+pub(crate) fn spawn_limited_process(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    limits: OutputLimits,
+    context: &InvocationContext,
+    source: AuditSource,
+) -> Result<(Child, OutputLimits, RedactPatterns, AuditRecord), ToolError> {
+    let requested_max = input.max_output_bytes;
+    let truncation = input.truncation.unwrap_or(limits.truncation);
+    let (child, _, decision, audit) = spawn_validated_process(
+        policy_engine,
+        default_cwd,
+        input,
+        context,
+        source,
+        SpawnStdio::Pipes,
+        true,
+    )?;
+    let limits = OutputLimits {
+        truncation,
+        ..limits
+            .with_overrides(decision.output_limits)
+            .capped(requested_max)
+    };
+    Ok((child, limits, decision.redact, audit))
+}
+
+/// Collects the output of a command from [`spawn_limited_process`], redacting matches of
+/// `redact` and spilling truncated streams to `spills` when given.
+pub(crate) async fn collect_audited_output(
+    child: Child,
+    limits: OutputLimits,
+    redact: &RedactPatterns,
+    timeout_ms: Option<u64>,
+    audit: AuditRecord,
+    spills: Option<&OutputSpills>,
+) -> Result<RunNetworkToolOutput, ToolError> {
+    // Spilled chunks are read back as written, so redacted output is never spilled.
+    let spills = spills.filter(|_| redact.is_empty());
+    match collect_output(child, limits, timeout_ms, spills, redact).await {
+        Ok((output, stdout_truncated, stderr_truncated)) => {
+            audit.complete(
+                output.exit_code,
+                output.resource_usage,
+                stdout_truncated,
+                stderr_truncated,
+            );
+            Ok(output)
+        }
+        Err(error) => {
+            audit.fail(&error);
+            Err(error)
+        }
+    }
+}
+
+/// Waits for `child` and captures its output with matches of `redact` replaced, also
+/// reporting whether stdout and stderr were truncated.
+async fn collect_output(
+    mut child: Child,
+    limits: OutputLimits,
+    timeout_ms: Option<u64>,
+    spills: Option<&OutputSpills>,
+    redact: &RedactPatterns,
+) -> Result<(RunNetworkToolOutput, bool, bool), ToolError> {
+    let process_group = child.id();
+    let started = Instant::now();
 
     let stdout = child.stdout.take().ok_or_else(|| ToolError::StdoutRead {
         source: std::io::Error::other("stdout pipe missing"),
@@ -69,31 +332,84 @@ pub async fn run_network_tool_impl(
         source: std::io::Error::other("stderr pipe missing"),
     })?;
 
-    let stdout_task = tokio::spawn(read_limited(stdout));
-    let stderr_task = tokio::spawn(read_limited(stderr));
-
-    let status = child
-        .wait()
-        .await
-        .map_err(|source| ToolError::Wait { source })?;
+    let truncation = limits.truncation;
+    // Redaction looks past each cut, so a match crossing it is not kept in part.
+    let lookahead = if redact.is_empty() {
+        0
+    } else {
+        REDACT_LOOKAHEAD_BYTES
+    };
+    let (stdout_spill, stderr_spill) = match spills {
+        Some(spills) => (start_spill(spills).await, start_spill(spills).await),
+        None => (None, None),
+    };
+    let mut stdout_task = tokio::spawn(
+        read_limited(stdout, limits.stdout_bytes, truncation, lookahead, stdout_spill)
+            .in_current_span(),
+    );
+    let mut stderr_task = tokio::spawn(
+        read_limited(stderr, limits.stderr_bytes, truncation, lookahead, stderr_spill)
+            .in_current_span(),
+    );
 
-    let stdout_capture = stdout_task
-        .await
-        .map_err(|source| ToolError::StdoutJoin { source })?;
-    let stderr_capture = stderr_task
-        .await
-        .map_err(|source| ToolError::StderrJoin { source })?;
+    let completion = async {
+        let resource_usage = spawn_exit_watch(&child).await.ok().flatten();
+        let status = child
+            .wait()
+            .await
+            .map_err(|source| ToolError::Wait { source })?;
+        record_child_exit(resource_usage);
+        let stdout_capture = (&mut stdout_task)
+            .await
+            .map_err(|source| ToolError::StdoutJoin { source })?;
+        let stderr_capture = (&mut stderr_task)
+            .await
+            .map_err(|source| ToolError::StderrJoin { source })?;
+        Ok::<_, ToolError>((status, resource_usage, stdout_capture, stderr_capture))
+    };
+
+    let (status, resource_usage, stdout_capture, stderr_capture) = match timeout_ms {
+        Some(timeout_ms) => {
+            match tokio::time::timeout(Duration::from_millis(timeout_ms), completion).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    stdout_task.abort();
+                    stderr_task.abort();
+                    kill_process_group(&mut child, process_group).await;
+                    return Err(ToolError::Timeout { timeout_ms });
+                }
+            }
+        }
+        None => completion.await?,
+    };
 
-    let (stdout_bytes, stdout_truncated) =
+    let (mut stdout_bytes, stdout_truncated) =
         stdout_capture.map_err(|source| ToolError::StdoutRead { source })?;
-    let (stderr_bytes, stderr_truncated) =
+    let (mut stderr_bytes, stderr_truncated) =
         stderr_capture.map_err(|source| ToolError::StderrRead { source })?;
-
-    Ok(RunNetworkToolOutput {
-        stdout: finalize_capture(stdout_bytes, stdout_truncated),
-        stderr: finalize_capture(stderr_bytes, stderr_truncated),
+    let stdout_continuation = keep_omitted(spills, &mut stdout_bytes, stdout_truncated).await;
+    let stderr_continuation = keep_omitted(spills, &mut stderr_bytes, stderr_truncated).await;
+
+    let output = RunNetworkToolOutput {
+        stdout: finalize_capture(
+            stdout_bytes,
+            stdout_truncated,
+            STDOUT_TRUNCATION_MARKER,
+            redact,
+        ),
+        stderr: finalize_capture(
+            stderr_bytes,
+            stderr_truncated,
+            STDERR_TRUNCATION_MARKER,
+            redact,
+        ),
         exit_code: status.code(),
-    })
+        duration_ms: started.elapsed().as_millis() as u64,
+        resource_usage,
+        stdout_continuation,
+        stderr_continuation,
+    };
+    Ok((output, stdout_truncated, stderr_truncated))
 }
 
 pub fn spawn_network_tool_process(
@@ -101,53 +417,444 @@ pub fn spawn_network_tool_process(
     default_cwd: &Path,
     input: RunNetworkToolInput,
 ) -> Result<Child, ToolError> {
-    let user_env = input.env.unwrap_or_default();
-    let resolved_executable =
-        resolve_executable_path(&input.executable).map_err(|details| ToolError::Validation(
-            ValidationError::PathResolutionFailed {
-                command: input.executable.clone(),
-                details,
-            },
-        ))?;
-    let executable_hash = compute_executable_sha256_hex(&resolved_executable).map_err(|details| {
-        ToolError::Validation(ValidationError::HashResolutionFailed {
-            command: input.executable.clone(),
-            details,
+    spawn_network_tool_process_in_context(
+        policy_engine,
+        default_cwd,
+        input,
+        &InvocationContext::default(),
+    )
+}
+
+/// Denials are audited, but the caller owns the child, so its exit is not. The policy's
+/// `domains` are released as soon as the command has started.
+pub fn spawn_network_tool_process_in_context(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    context: &InvocationContext,
+) -> Result<Child, ToolError> {
+    spawn_validated_process(
+        policy_engine,
+        default_cwd,
+        input,
+        context,
+        AuditSource::Raw,
+        SpawnStdio::Pipes,
+        false,
+    )
+    .map(|(child, _, _, _)| child)
+}
+
+/// Spawns for `/raw` and `/jobs`, returning the audit record the caller completes once the
+/// child exits.
+pub(crate) fn spawn_audited_process(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    context: &InvocationContext,
+    source: AuditSource,
+) -> Result<(Child, AuditRecord), ToolError> {
+    spawn_validated_process(
+        policy_engine,
+        default_cwd,
+        input,
+        context,
+        source,
+        SpawnStdio::Pipes,
+        false,
+    )
+    .map(|(child, _, _, audit)| (child, audit))
+}
+
+/// Like [`spawn_audited_process`], but stdin is piped and left on the child for the caller
+/// to feed, for `/raw` requests that stream it.
+pub(crate) fn spawn_audited_process_with_stdin_pipe(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    context: &InvocationContext,
+    source: AuditSource,
+) -> Result<(Child, AuditRecord), ToolError> {
+    spawn_validated_process(
+        policy_engine,
+        default_cwd,
+        input,
+        context,
+        source,
+        SpawnStdio::PipedStdin,
+        false,
+    )
+    .map(|(child, _, _, audit)| (child, audit))
+}
+
+/// For `/raw` requests with `ptyMode`: the command's stdin, stdout and stderr are one
+/// pseudo-terminal, whose other side is returned for the caller to read and type into.
+pub(crate) fn spawn_audited_process_on_pty(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    context: &InvocationContext,
+    source: AuditSource,
+) -> Result<(Child, PtyMaster, AuditRecord), ToolError> {
+    let size = input.terminal_size.unwrap_or_default();
+    spawn_validated_process(
+        policy_engine,
+        default_cwd,
+        input,
+        context,
+        source,
+        SpawnStdio::Terminal(size),
+        false,
+    )
+    .and_then(|(child, master, _, audit)| {
+        let master = master.ok_or_else(|| ToolError::Spawn {
+            source: std::io::Error::other("pseudo-terminal missing"),
+        })?;
+        Ok((child, master, audit))
+    })
+}
+
+/// How a spawned command's stdin, stdout and stderr are connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpawnStdio {
+    /// Output is piped; stdin carries `stdinB64` or is `/dev/null`.
+    Pipes,
+    /// Like `Pipes`, but stdin is always piped for the caller to feed.
+    PipedStdin,
+    /// All three on a new pseudo-terminal of this size.
+    Terminal(TerminalSize),
+}
+
+/// Validates `input` against the policy and spawns it. A command whose output the policy
+/// redacts is denied unless `captures_output`: streamed output is not redacted.
+fn spawn_validated_process(
+    policy_engine: &PolicyEngine,
+    default_cwd: &Path,
+    input: RunNetworkToolInput,
+    context: &InvocationContext,
+    source: AuditSource,
+    stdio: SpawnStdio,
+    captures_output: bool,
+) -> Result<(Child, Option<PtyMaster>, PolicyDecision, AuditRecord), ToolError> {
+    if input.pty_mode == Some(true) && !matches!(stdio, SpawnStdio::Terminal(_)) {
+        return Err(ToolError::PtyModeUnsupported);
+    }
+    let stdin_bytes = input
+        .stdin_b64
+        .as_deref()
+        .map(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded))
+        .transpose()
+        .map_err(|source| ToolError::InvalidStdin { source })?;
+    // The policy checks both forms of the cwd and the spawn uses the canonical one, so a
+    // symlink cannot take the command outside the directory that was checked.
+    let cwd = normalize_lexically(&match input.cwd.as_deref() {
+        Some(cwd) => default_cwd.join(cwd),
+        None => default_cwd.to_path_buf(),
+    });
+    let canonical_cwd = std::fs::canonicalize(&cwd).ok();
+    let context = &InvocationContext {
+        cwd: Some(cwd.clone()),
+        canonical_cwd: canonical_cwd.clone(),
+        ..context.clone()
+    };
+    let mut audit = AuditRecord::begin(source, policy_engine.mode(), context, &input, &cwd);
+    let user_env = input.env.clone().unwrap_or_default();
+    // Before the grant lookup, which a grant would otherwise let skip the limits.
+    if let Err(error) = policy_engine.check_arg_limits(&input.executable, &input.args) {
+        audit.deny_validation(&error);
+        return Err(error.into());
+    }
+    // Hashing the executable and any `file_hash` arguments reads whole files.
+    let evaluated = run_blocking(|| {
+        resolve_executable(&input.executable).and_then(|(path, hash)| {
+            audit.resolved_path = Some(path.clone());
+            audit.sha256 = Some(hash.clone());
+            policy_engine.check_self_invocation(&input.executable, &path)?;
+            if let Some(grant) =
+                policy_engine.take_grant(&input.executable, &input.args, &user_env, context)
+            {
+                tracing::info!(grant_id = %grant.id, command = %input.executable, client_id = %grant.client_id, "invocation allowed by grant");
+                audit.grant_id = Some(grant.id);
+                return Ok((path, PolicyDecision::default()));
+            }
+            policy_engine
+                .authorize_invocation(
+                    &input.executable,
+                    &path,
+                    &hash,
+                    &input.args,
+                    &user_env,
+                    context,
+                )
+                .map(|decision| (path, decision))
         })
-    })?;
-    policy_engine.validate_invocation(
-        &input.executable,
-        &resolved_executable,
-        &executable_hash,
-        &input.args,
-        &user_env,
-    )?;
+    });
+    let evaluated = evaluated.and_then(|(path, decision)| {
+        // A cwd that cannot be resolved cannot be shown to stay inside the allowed ones.
+        if canonical_cwd.is_none() && !decision.cwd_patterns.is_empty() {
+            return Err(ValidationError::CwdNotAllowed {
+                command: input.executable.clone(),
+                cwd: cwd.display().to_string(),
+            });
+        }
+        if !captures_output && !decision.redact.is_empty() {
+            return Err(ValidationError::RedactionUnsupported {
+                command: input.executable.clone(),
+            });
+        }
+        Ok((path, decision))
+    });
+    let (resolved_executable, decision) = match evaluated {
+        Ok(evaluated) => evaluated,
+        Err(error) => {
+            audit.deny_validation(&error);
+            return Err(error.into());
+        }
+    };
+    audit.args = decision.redact_args(&input.args, &user_env);
 
     let mut command = Command::new(&resolved_executable);
-    command
-        .args(&input.args)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .kill_on_drop(true);
-
-    if let Some(cwd) = input.cwd.as_deref() {
-        command.current_dir(cwd);
-    } else {
-        command.current_dir(default_cwd);
-    }
+    command.args(&input.args).kill_on_drop(true);
+    let master = match stdio {
+        SpawnStdio::Terminal(size) => {
+            let attached = PtyMaster::open(size).and_then(|(master, terminal)| {
+                attach_terminal(&mut command, terminal).map(|()| master)
+            });
+            match attached {
+                Ok(master) => Some(master),
+                Err(source) => {
+                    let error = ToolError::Spawn { source };
+                    audit.fail(&error);
+                    return Err(error);
+                }
+            }
+        }
+        SpawnStdio::Pipes | SpawnStdio::PipedStdin => {
+            command
+                .stdin(if stdin_bytes.is_some() || stdio == SpawnStdio::PipedStdin {
+                    Stdio::piped()
+                } else {
+                    Stdio::null()
+                })
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            // Lead a new process group so timeouts can also stop helpers the tool forks. A
+            // terminal's command leads its own session, and so its own group, instead.
+            #[cfg(unix)]
+            command.process_group(0);
+            None
+        }
+    };
+    #[cfg(unix)]
+    close_inherited_descriptors(&mut command);
+
+    command.current_dir(canonical_cwd.as_deref().unwrap_or(&cwd));
 
-    let command_env = build_command_env(&user_env);
+    let command_env = build_command_env(&user_env, &decision.inject_env);
     command.env_clear();
     command.envs(
         command_env
             .iter()
             .map(|(key, value)| (key.as_str(), value.as_str())),
     );
+    // Last, so neither the caller nor the policy can reset it.
+    command.env(HOPS_ENV, (context.hops + 1).to_string());
+
+    // Before the spawn, so the command's first request already finds its domains open.
+    audit.egress = hold_egress_domains(&decision.domains);
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(source) => {
+            let error = ToolError::Spawn { source };
+            audit.fail(&error);
+            return Err(error);
+        }
+    };
+    record_client_execution(context.client_id.as_deref());
+
+    if let Some(bytes) = stdin_bytes {
+        if let Some(stdin) = child.stdin.take() {
+            tokio::spawn(write_stdin(stdin, bytes));
+        } else if let Some(master) = &master {
+            // Typed into the terminal, which stays open: the command sees no EOF.
+            match master.try_clone() {
+                Ok(input) => {
+                    tokio::spawn(write_stdin(input, bytes));
+                }
+                Err(error) => tracing::warn!(error = %error, "failed writing stdin payload"),
+            }
+        }
+    }
+
+    Ok((child, master, decision, audit))
+}
+
+/// Feeds the payload to the child and closes stdin so it sees EOF. Runs concurrently with the
+/// output readers so a child that echoes large inputs cannot deadlock on a full pipe.
+async fn write_stdin<W>(mut stdin: W, bytes: Vec<u8>)
+where
+    W: AsyncWrite + Unpin,
+{
+    match stdin.write_all(&bytes).await {
+        Ok(()) => {}
+        // The child exited or closed stdin without reading everything; that is its choice.
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => {}
+        Err(error) => tracing::warn!(error = %error, "failed writing stdin payload"),
+    }
+}
+
+/// Resolves once the child has exited, with its resource usage where the platform reports it.
+///
+/// The child is left unreaped so `Child::wait` still returns its status afterwards; call that
+/// only once this completes, or the rusage is lost to the reap. Off unix it resolves immediately
+/// with `None` and callers fall back to `Child::wait` alone.
+///
+/// A running child holds no blocking-pool thread, so long commands cannot starve hashing and
+/// policy evaluation of `MCP_MAX_BLOCKING_THREADS`: on Linux the exit is awaited on a pidfd,
+/// and elsewhere, or on kernels without pidfds, a thread of its own waits for it.
+pub(crate) fn spawn_exit_watch(child: &Child) -> tokio::task::JoinHandle<Option<ResourceUsage>> {
+    let Some(pid) = child.id() else {
+        return tokio::spawn(async { None });
+    };
+    #[cfg(target_os = "linux")]
+    if let Some(pidfd) = open_pidfd(pid) {
+        return tokio::spawn(async move {
+            // Readable once the child has exited, after which waitid returns at once.
+            let _ = pidfd.readable().await;
+            wait_exit_unreaped(pid)
+        });
+    }
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let spawned = std::thread::Builder::new()
+        .name("mcp-run-exit-watch".to_string())
+        .spawn(move || {
+            let _ = sender.send(wait_exit_unreaped(pid));
+        });
+    if let Err(error) = spawned {
+        tracing::warn!(%error, "failed to start exit watch thread; resource usage is not recorded");
+    }
+    tokio::spawn(async move { receiver.await.ok().flatten() })
+}
+
+/// A pidfd for `pid`, or `None` before Linux 5.3.
+#[cfg(target_os = "linux")]
+fn open_pidfd(pid: u32) -> Option<tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    // SAFETY: `pidfd_open` takes no pointers.
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    let fd = std::os::fd::RawFd::try_from(fd).ok().filter(|fd| *fd >= 0)?;
+    // SAFETY: `pidfd_open` succeeded, so this is an open descriptor that nothing else owns.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    tokio::io::unix::AsyncFd::with_interest(fd, tokio::io::Interest::READABLE).ok()
+}
 
-    command
-        .spawn()
-        .map_err(|source| ToolError::Spawn { source })
+#[cfg(target_os = "linux")]
+fn wait_exit_unreaped(pid: u32) -> Option<ResourceUsage> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // The glibc waitid wrapper drops the kernel's rusage argument, so call it directly.
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_waitid,
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info as *mut libc::siginfo_t,
+                libc::WEXITED | libc::WNOWAIT,
+                &mut usage as *mut libc::rusage,
+            )
+        };
+        if rc == 0 {
+            return Some(ResourceUsage {
+                // ru_maxrss is reported in KiB on Linux.
+                max_rss_bytes: (usage.ru_maxrss.max(0) as u64) * 1024,
+                user_cpu_ms: timeval_ms(usage.ru_utime),
+                system_cpu_ms: timeval_ms(usage.ru_stime),
+            });
+        }
+        if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            return None;
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn wait_exit_unreaped(pid: u32) -> Option<ResourceUsage> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    loop {
+        let rc = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if rc == 0
+            || std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted
+        {
+            return None;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn wait_exit_unreaped(_pid: u32) -> Option<ResourceUsage> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn timeval_ms(value: libc::timeval) -> u64 {
+    (value.tv_sec.max(0) as u64) * 1000 + (value.tv_usec.max(0) as u64) / 1000
+}
+
+/// Kills the process group led by `process_group` (the child's pid at spawn) and reaps the child.
+pub(crate) async fn kill_process_group(child: &mut Child, process_group: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(pgid) = process_group.and_then(|pid| libc::pid_t::try_from(pid).ok()) {
+        unsafe {
+            libc::killpg(pgid, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = process_group;
+    let _ = child.start_kill();
+    let _ = child.wait().await;
+}
+
+/// Sends SIGTERM to the process group led by `process_group`, then kills what is left of it
+/// as [`kill_process_group`] does once the child has exited or `grace` has passed.
+pub(crate) async fn stop_process_group(
+    child: &mut Child,
+    process_group: Option<u32>,
+    grace: Duration,
+) {
+    #[cfg(unix)]
+    if let Some(pgid) = process_group.and_then(|pid| libc::pid_t::try_from(pid).ok()) {
+        unsafe {
+            libc::killpg(pgid, libc::SIGTERM);
+        }
+    }
+    let _ = tokio::time::timeout(grace, child.wait()).await;
+    kill_process_group(child, process_group).await;
+}
+
+/// Resolves `command` to the path and SHA-256 that the policy is evaluated against.
+pub(crate) fn resolve_executable(command: &str) -> Result<(String, String), ValidationError> {
+    let path = resolve_executable_path(command).map_err(|details| {
+        ValidationError::PathResolutionFailed {
+            command: command.to_string(),
+            details,
+        }
+    })?;
+    let hash = compute_executable_sha256_hex(&path).map_err(|details| {
+        ValidationError::HashResolutionFailed {
+            command: command.to_string(),
+            details,
+        }
+    })?;
+    Ok((path, hash))
 }
 
 pub(crate) fn resolve_executable_path(command: &str) -> Result<String, String> {
@@ -202,11 +909,27 @@ pub(crate) fn resolve_executable_path(command: &str) -> Result<String, String> {
     Err(format!("'{}' was not found on PATH", command))
 }
 
-pub(crate) fn build_command_env(user_env: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+/// The command's environment: the caller's `user_env` over the server's `HOME` and `LANG`,
+/// the server's `PATH` and proxy settings over that, and the policy's `inject_env` over all.
+pub(crate) fn build_command_env(
+    user_env: &BTreeMap<String, String>,
+    inject_env: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    merge_command_env(user_env, inject_env, |key| std::env::var(key).ok())
+}
+
+/// [`build_command_env`] with the server's environment supplied by `host_env`, so the
+/// guarantee that callers cannot override `PATH` or the proxy settings is checkable without
+/// touching the process environment.
+pub(crate) fn merge_command_env(
+    user_env: &BTreeMap<String, String>,
+    inject_env: &BTreeMap<String, String>,
+    host_env: impl Fn(&str) -> Option<String>,
+) -> BTreeMap<String, String> {
     let mut command_env = BTreeMap::new();
 
     for key in ["HOME", "LANG"] {
-        if let Ok(value) = std::env::var(key) {
+        if let Some(value) = host_env(key) {
             command_env.insert(key.to_string(), value);
         }
     }
@@ -229,13 +952,13 @@ pub(crate) fn build_command_env(user_env: &BTreeMap<String, String>) -> BTreeMap
         command_env.remove(key);
     }
 
-    if let Ok(path) = std::env::var("PATH") {
+    if let Some(path) = host_env("PATH") {
         command_env.insert("PATH".to_string(), path);
     }
 
-    let http_proxy = std::env::var("http_proxy").ok();
-    let https_proxy = std::env::var("https_proxy").ok();
-    let no_proxy = std::env::var("no_proxy").ok();
+    let http_proxy = host_env("http_proxy");
+    let https_proxy = host_env("https_proxy");
+    let no_proxy = host_env("no_proxy");
 
     if let Some(value) = http_proxy.clone() {
         command_env.insert("http_proxy".to_string(), value);
@@ -257,68 +980,177 @@ pub(crate) fn build_command_env(user_env: &BTreeMap<String, String>) -> BTreeMap
         command_env.insert("NO_PROXY".to_string(), value);
     }
 
+    command_env.extend(
+        inject_env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+
     command_env
 }
 
 pub(crate) fn compute_executable_sha256_hex(path: &str) -> Result<String, String> {
-    let mut file = std::fs::File::open(path)
-        .map_err(|error| format!("failed opening '{path}' for hashing: {error}"))?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 8192];
+    hash_file_hex(Path::new(path), HashAlgorithm::Sha256)
+        .map_err(|error| format!("failed hashing '{path}': {error}"))
+}
 
-    loop {
-        let bytes_read = file
-            .read(&mut buffer)
-            .map_err(|error| format!("failed reading '{path}' for hashing: {error}"))?;
-        if bytes_read == 0 {
-            break;
+/// Spilling is best effort: without a spill file the command still runs, and its response
+/// just has no continuation token.
+async fn start_spill(spills: &OutputSpills) -> Option<SpillWriter> {
+    match spills.create().await {
+        Ok(writer) => Some(writer),
+        Err(error) => {
+            tracing::warn!(error = %error, "could not create spill file; output will not be kept");
+            None
         }
-        hasher.update(&buffer[..bytes_read]);
     }
+}
 
-    let digest = hasher.finalize();
-    let mut output = String::with_capacity(digest.len() * 2);
-    for byte in digest {
-        let _ = write!(&mut output, "{byte:02x}");
+/// Keeps the bytes a truncated `capture` left out, returning their continuation token.
+async fn keep_omitted(
+    spills: Option<&OutputSpills>,
+    capture: &mut Capture,
+    truncated: bool,
+) -> Option<String> {
+    // Taken first, so an untruncated stream's file is deleted.
+    let writer = capture.spill.take()?;
+    let spills = spills.filter(|_| truncated)?;
+    let omitted = capture.head_len() as u64..capture.total_bytes - capture.tail_len() as u64;
+    match spills.keep(writer, omitted).await {
+        Ok(token) => token,
+        Err(error) => {
+            tracing::warn!(error = %error, "could not keep spilled output");
+            None
+        }
     }
-    Ok(output)
 }
 
+/// Keeps `limit` bytes from the ends `truncation` asks for, plus up to `lookahead` bytes past
+/// each cut for redaction to look at.
 async fn read_limited<R: tokio::io::AsyncRead + Unpin>(
     mut reader: R,
-) -> Result<(Vec<u8>, bool), std::io::Error> {
-    let mut output = Vec::new();
+    limit: usize,
+    truncation: Truncation,
+    lookahead: usize,
+    spill: Option<SpillWriter>,
+) -> Result<(Capture, bool), std::io::Error> {
+    let head_limit = match truncation {
+        Truncation::Head => limit,
+        Truncation::Tail => 0,
+        Truncation::HeadAndTail => limit / 2,
+    };
+    let tail_limit = limit - head_limit;
+    let mut capture = Capture {
+        spill,
+        head_limit,
+        tail_limit,
+        ..Capture::default()
+    };
+    // A head with no room keeps no lookahead either; nothing of it is returned.
+    let head_capacity = if head_limit == 0 { 0 } else { head_limit + lookahead };
+    let tail_capacity = if tail_limit == 0 { 0 } else { tail_limit + lookahead };
     let mut buffer = [0u8; 8192];
-    let mut truncated = false;
 
     loop {
         let bytes_read = reader.read(&mut buffer).await?;
         if bytes_read == 0 {
             break;
         }
+        capture.total_bytes += bytes_read as u64;
+        if let Some(spill) = &mut capture.spill
+            && let Err(error) = spill.write(&buffer[..bytes_read]).await
+        {
+            tracing::warn!(error = %error, "could not write spill file; output will not be kept");
+            capture.spill = None;
+        }
 
-        if truncated {
+        let chunk = &buffer[..bytes_read];
+        let head_room = head_capacity.saturating_sub(capture.head.len());
+        capture
+            .head
+            .extend_from_slice(&chunk[..head_room.min(chunk.len())]);
+        // The head's lookahead is also the start of the tail.
+        let past_head = (capture.total_bytes - bytes_read as u64)
+            .max(head_limit as u64)
+            .min(capture.total_bytes);
+        let chunk = &chunk[chunk.len() - (capture.total_bytes - past_head) as usize..];
+        if chunk.is_empty() {
             continue;
         }
 
-        let remaining = MAX_OUTPUT_BYTES.saturating_sub(output.len());
-        if bytes_read <= remaining {
-            output.extend_from_slice(&buffer[..bytes_read]);
-        } else {
-            if remaining > 0 {
-                output.extend_from_slice(&buffer[..remaining]);
-            }
-            truncated = true;
+        // Everything past the head competes for the tail; what falls out of it is dropped.
+        capture.tail.extend(chunk);
+        if capture.tail.len() > tail_capacity {
+            capture.tail.drain(..capture.tail.len() - tail_capacity);
         }
     }
 
-    Ok((output, truncated))
+    let truncated = capture.total_bytes > limit as u64;
+    Ok((capture, truncated))
+}
+
+/// The bytes [`read_limited`] kept from either end of a stream.
+#[derive(Debug, Default)]
+struct Capture {
+    /// The first bytes, up to `head_limit` plus any lookahead.
+    head: Vec<u8>,
+    /// The bytes past `head_limit`, up to the last `tail_limit` plus any lookahead.
+    tail: VecDeque<u8>,
+    head_limit: usize,
+    tail_limit: usize,
+    total_bytes: u64,
+    /// The whole stream, when it is being spilled.
+    spill: Option<SpillWriter>,
+}
+
+impl Capture {
+    /// Bytes of the head that are returned.
+    fn head_len(&self) -> usize {
+        self.head.len().min(self.head_limit)
+    }
+
+    /// Bytes of the tail that are returned once the stream is truncated.
+    fn tail_len(&self) -> usize {
+        self.tail.len().min(self.tail_limit)
+    }
 }
 
-fn finalize_capture(bytes: Vec<u8>, truncated: bool) -> String {
-    let mut value = String::from_utf8_lossy(&bytes).into_owned();
-    if truncated {
-        value.push_str(TRUNCATION_MARKER);
+fn finalize_capture(
+    capture: Capture,
+    truncated: bool,
+    marker: &str,
+    redact: &RedactPatterns,
+) -> String {
+    let head_len = capture.head_len();
+    let tail_len = capture.tail_len();
+    let Capture { mut head, tail, .. } = capture;
+    let tail = Vec::from(tail);
+    if !truncated {
+        head.truncate(head_len);
+        head.extend(tail);
+        let value = String::from_utf8_lossy(&head);
+        return if redact.is_empty() {
+            value.into_owned()
+        } else {
+            redact.redact(&value)
+        };
+    }
+    let (mut value, tail) = if redact.is_empty() {
+        (
+            String::from_utf8_lossy(&head[..head_len]).into_owned(),
+            String::from_utf8_lossy(&tail[tail.len() - tail_len..]).into_owned(),
+        )
+    } else {
+        (
+            redact.redact_head(&String::from_utf8_lossy(&head), head_len),
+            redact.redact_tail(&String::from_utf8_lossy(&tail), tail_len),
+        )
+    };
+    // A tail-only capture starts with the marker, without the newline leading into it.
+    value.push_str(if value.is_empty() { marker.trim_start() } else { marker });
+    if !tail.is_empty() {
+        value.push('\n');
+        value.push_str(&tail);
     }
     value
 }
@@ -330,17 +1162,9 @@ mod tests {
 
     use super::*;
     use crate::policy::PolicyEngine;
-
-    fn find_executable(name: &str) -> Option<String> {
-        let path = std::env::var_os("PATH")?;
-        for dir in std::env::split_paths(&path) {
-            let candidate = dir.join(name);
-            if candidate.is_file() {
-                return Some(candidate.to_string_lossy().into_owned());
-            }
-        }
-        None
-    }
+    use crate::self_invocation::OwnBinaries;
+    use crate::test_support::find_executable;
+    use proptest::prelude::*;
 
     fn parse_env_output(output: &str) -> BTreeMap<String, String> {
         output
@@ -366,6 +1190,171 @@ mod tests {
         PolicyEngine::from_rego_for_tests(&[("main.rego", &main)])
     }
 
+    #[tokio::test]
+    async fn audit_log_records_decisions_and_exits() {
+        let (sh_path, true_path) = match (find_executable("sh"), find_executable("true")) {
+            (Some(sh_path), Some(true_path)) => (sh_path, true_path),
+            _ => return,
+        };
+        crate::audit::audit_log_for_tests();
+        let engine = rego_engine_allow_commands(&[&sh_path]);
+        let context = InvocationContext {
+            client_id: Some("executor-audit".to_string()),
+            cwd: None,
+            canonical_cwd: None,
+            hops: 0,
+        };
+        let limits = OutputLimits {
+            stdout_bytes: 2,
+            stderr_bytes: MAX_OUTPUT_BYTES,
+            truncation: Truncation::Head,
+        };
+        let input = |executable: &str| RunNetworkToolInput {
+            executable: executable.to_string(),
+            args: vec!["-c".to_string(), "printf 12345; exit 3".to_string()],
+            env: Some(BTreeMap::from([(
+                "API_TOKEN".to_string(),
+                "secret-value".to_string(),
+            )])),
+            ..RunNetworkToolInput::default()
+        };
+
+        run_network_tool_in_context(&engine, Path::new("."), input(&sh_path), limits, &context)
+            .await
+            .expect("allowed run");
+        run_network_tool_in_context(&engine, Path::new("."), input(&true_path), limits, &context)
+            .await
+            .expect_err("denied run");
+
+        let records = crate::audit::audit_records_for_client("executor-audit");
+        assert_eq!(records.len(), 2);
+        let allowed = &records[0];
+        assert_eq!(allowed["source"], "mcp");
+        assert_eq!(allowed["decision"], "allow");
+        assert_eq!(allowed["resolvedPath"], sh_path.as_str());
+        assert_eq!(allowed["policyMode"], "rego");
+        assert_eq!(allowed["envKeys"], serde_json::json!(["API_TOKEN"]));
+        assert_eq!(allowed["cwd"], ".");
+        assert_eq!(allowed["exitCode"], 3);
+        assert_eq!(allowed["stdoutTruncated"], true);
+        assert_eq!(allowed["stderrTruncated"], false);
+        assert!(allowed["durationMs"].is_u64());
+        assert!(!allowed.to_string().contains("secret-value"));
+
+        let denied = &records[1];
+        assert_eq!(denied["decision"], "deny");
+        assert!(denied["reason"].as_str().is_some_and(|reason| reason.contains("not allowed")));
+        assert!(denied["exitCode"].is_null());
+    }
+
+    #[tokio::test]
+    async fn sensitive_env_values_are_redacted_from_audit_args() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        crate::audit::audit_log_for_tests();
+        let escaped = sh_path.replace('\\', "\\\\").replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n}}\n\nenv_keys := {{\"API_TOKEN\": {{\"sensitive\": true}}}}\n"
+        );
+        let engine = PolicyEngine::from_rego_for_tests(&[("main.rego", &main)]);
+        let context = InvocationContext {
+            client_id: Some("executor-sensitive-env".to_string()),
+            cwd: None,
+            canonical_cwd: None,
+            hops: 0,
+        };
+
+        let output = run_network_tool_in_context(
+            &engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: sh_path,
+                args: vec![
+                    "-c".to_string(),
+                    "[ \"$API_TOKEN\" = \"$1\" ] && printf '%s|%s' \"$API_TOKEN\" \"$2\"".to_string(),
+                    "sh".to_string(),
+                    "tok-123".to_string(),
+                    "public".to_string(),
+                ],
+                env: Some(BTreeMap::from([
+                    ("API_TOKEN".to_string(), "tok-123".to_string()),
+                    ("MODE".to_string(), "public".to_string()),
+                ])),
+                ..RunNetworkToolInput::default()
+            },
+            OutputLimits::default(),
+            &context,
+        )
+        .await
+        .expect("allowed run");
+        assert_eq!(output.stdout, "tok-123|public");
+
+        let records = crate::audit::audit_records_for_client("executor-sensitive-env");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["args"][3], "[REDACTED]");
+        assert_eq!(records[0]["args"][4], "public");
+        assert_eq!(records[0]["envKeys"], serde_json::json!(["API_TOKEN", "MODE"]));
+        assert!(!records[0].to_string().contains("tok-123"));
+    }
+
+    #[tokio::test]
+    async fn cwd_outside_policy_pattern_is_denied_before_spawn() {
+        let pwd_path = match find_executable("pwd") {
+            Some(path) => path,
+            None => return,
+        };
+        let workspace = tempfile::tempdir().expect("workspace");
+        let escaped = pwd_path.replace('\\', "\\\\").replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n}}\n\ncwd_pattern := \"{{workspace}}\"\n"
+        );
+        let engine = PolicyEngine::from_rego_for_tests(&[("main.rego", &main)])
+            .with_workspace(workspace.path().to_path_buf());
+        let run = |cwd: Option<&str>| {
+            run_network_tool_impl(
+                &engine,
+                workspace.path(),
+                RunNetworkToolInput {
+                    executable: pwd_path.clone(),
+                    cwd: cwd.map(str::to_string),
+                    ..RunNetworkToolInput::default()
+                },
+            )
+        };
+
+        let output = run(None).await.expect("default cwd is the workspace");
+        assert_eq!(output.stdout.trim(), workspace.path().to_string_lossy());
+        assert!(matches!(
+            run(Some("..")).await,
+            Err(ToolError::Validation(ValidationError::CwdNotAllowed { .. }))
+        ));
+        assert!(matches!(
+            run(Some("missing")).await,
+            Err(ToolError::Validation(ValidationError::CwdNotAllowed { .. }))
+        ));
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().expect("outside");
+            std::os::unix::fs::symlink(outside.path(), workspace.path().join("link-out"))
+                .expect("symlink out");
+            assert!(matches!(
+                run(Some("link-out")).await,
+                Err(ToolError::Validation(ValidationError::CwdNotAllowed { .. }))
+            ));
+            std::fs::create_dir(workspace.path().join("sub")).expect("sub");
+            std::os::unix::fs::symlink(workspace.path().join("sub"), workspace.path().join("link-in"))
+                .expect("symlink in");
+            let output = run(Some("link-in")).await.expect("link inside the workspace");
+            assert_eq!(
+                output.stdout.trim(),
+                workspace.path().join("sub").to_string_lossy()
+            );
+        }
+    }
+
     #[cfg(unix)]
     #[test]
     fn resolve_executable_path_preserves_symlink_in_path_lookup() {
@@ -416,7 +1405,7 @@ mod tests {
             ("NO_PROXY".to_string(), "user-no-upper".to_string()),
         ]);
 
-        let merged = build_command_env(&user_env);
+        let merged = build_command_env(&user_env, &BTreeMap::new());
 
         assert_eq!(
             merged.get("CUSTOM_USER_ENV").map(String::as_str),
@@ -427,7 +1416,7 @@ mod tests {
 
         assert_ne!(merged.get("PATH").map(String::as_str), Some("user-path"));
         if std::env::var_os("PATH").is_some() {
-            assert!(merged.get("PATH").is_some());
+            assert!(merged.contains_key("PATH"));
         } else {
             assert!(!merged.contains_key("PATH"));
         }
@@ -466,6 +1455,63 @@ mod tests {
         }
     }
 
+    const MERGE_KEYS: &[&str] = &[
+        "HOME",
+        "LANG",
+        "PATH",
+        "http_proxy",
+        "https_proxy",
+        "no_proxy",
+        "HTTP_PROXY",
+        "HTTPS_PROXY",
+        "NO_PROXY",
+        "CUSTOM",
+    ];
+
+    /// Some of [`MERGE_KEYS`], each set to `{prefix}-{n}`.
+    fn env_strategy(prefix: &'static str) -> impl Strategy<Value = BTreeMap<String, String>> {
+        prop::collection::vec(prop::option::of(any::<u8>()), MERGE_KEYS.len()).prop_map(
+            move |values| {
+                MERGE_KEYS
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(key, value)| {
+                        Some((key.to_string(), format!("{prefix}-{}", value?)))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn merge_command_env_keeps_server_path_and_proxies_property(
+            host in env_strategy("host"),
+            user in env_strategy("user"),
+            injected in env_strategy("policy"),
+        ) {
+            let merged = merge_command_env(&user, &BTreeMap::new(), |key| host.get(key).cloned());
+            for key in ["PATH", "http_proxy", "https_proxy", "no_proxy"] {
+                prop_assert_eq!(merged.get(key), host.get(key), "{}", key);
+                let upper = key.to_ascii_uppercase();
+                prop_assert_eq!(merged.get(&upper), host.get(key), "{}", upper);
+            }
+            for key in ["HOME", "LANG", "CUSTOM"] {
+                let expected = user.get(key).or(host.get(key).filter(|_| key != "CUSTOM"));
+                prop_assert_eq!(merged.get(key), expected, "{}", key);
+            }
+
+            // Injected values win over the caller and the server alike.
+            let with_policy = merge_command_env(&user, &injected, |key| host.get(key).cloned());
+            for key in MERGE_KEYS {
+                let expected = injected.get(*key).or(merged.get(*key));
+                prop_assert_eq!(with_policy.get(*key), expected, "{}", key);
+            }
+        }
+    }
+
     #[test]
     fn compute_executable_sha256_hex_uses_lowercase_hex() {
         let temp = tempfile::tempdir().expect("tempdir");
@@ -494,8 +1540,7 @@ mod tests {
             RunNetworkToolInput {
                 executable: env_path,
                 args: vec!["printf".to_string(), "ok".to_string()],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             },
         )
         .await
@@ -506,6 +1551,245 @@ mod tests {
         assert_eq!(output.stderr, "");
     }
 
+    #[tokio::test]
+    async fn stdin_payload_is_written_to_child() {
+        let cat_path = match find_executable("cat") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let policy_engine = rego_engine_allow_commands(&[&cat_path]);
+        let output = run_network_tool_impl(
+            &policy_engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: cat_path.clone(),
+                stdin_b64: Some(base64::engine::general_purpose::STANDARD.encode("piped in")),
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect("command should run");
+        assert_eq!(output.exit_code, Some(0));
+        assert_eq!(output.stdout, "piped in");
+
+        let error = run_network_tool_impl(
+            &policy_engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: cat_path.clone(),
+                stdin_b64: Some("not base64!".to_string()),
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect_err("invalid stdin should fail");
+        assert!(matches!(error, ToolError::InvalidStdin { .. }));
+
+        // Only `/raw` has a terminal to give the command.
+        let error = run_network_tool_impl(
+            &policy_engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: cat_path,
+                pty_mode: Some(true),
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect_err("ptyMode should fail outside /raw");
+        assert!(matches!(error, ToolError::PtyModeUnsupported));
+    }
+
+    #[tokio::test]
+    async fn stdout_and_stderr_limits_are_independent() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let escaped = sh_path.replace('\\', "\\\\").replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n}}\n\noutput_limits := {{\"stderr\": 2}}\n"
+        );
+        let policy_engine = PolicyEngine::from_rego_for_tests(&[("main.rego", &main)]);
+        let output = run_network_tool_with_limits(
+            &policy_engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: sh_path,
+                args: vec![
+                    "-c".to_string(),
+                    "printf hello-world; printf noise >&2".to_string(),
+                ],
+                ..RunNetworkToolInput::default()
+            },
+            OutputLimits {
+                stdout_bytes: 5,
+                stderr_bytes: MAX_OUTPUT_BYTES,
+                truncation: Truncation::Head,
+            },
+        )
+        .await
+        .expect("command should run");
+
+        assert_eq!(output.stdout, format!("hello{STDOUT_TRUNCATION_MARKER}"));
+        assert_eq!(output.stderr, format!("no{STDERR_TRUNCATION_MARKER}"));
+    }
+
+    #[tokio::test]
+    async fn policy_redact_patterns_are_applied_and_refused_when_streaming() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let escaped = sh_path.replace('\\', "\\\\").replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\nallow if input.command == \"{escaped}\"\n\nredact := \"tok_[a-z]+\"\n"
+        );
+        let policy_engine = PolicyEngine::from_rego_for_tests(&[("main.rego", &main)]);
+        let input = RunNetworkToolInput {
+            executable: sh_path,
+            args: vec![
+                "-c".to_string(),
+                "echo token=tok_secret; echo tok_other >&2".to_string(),
+            ],
+            ..RunNetworkToolInput::default()
+        };
+        let output = run_network_tool_impl(&policy_engine, Path::new("."), input.clone())
+            .await
+            .expect("command should run");
+        assert_eq!(output.stdout, "token=[REDACTED]\n");
+        assert_eq!(output.stderr, "[REDACTED]\n");
+
+        let error = spawn_network_tool_process(&policy_engine, Path::new("."), input)
+            .expect_err("streamed output cannot be redacted");
+        assert!(matches!(
+            error,
+            ToolError::Validation(ValidationError::RedactionUnsupported { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn requested_max_output_bytes_only_lowers_limits() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let escaped = sh_path.replace('\\', "\\\\").replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n}}\n\noutput_limits := {{\"stderr\": 2}}\n"
+        );
+        let policy_engine = PolicyEngine::from_rego_for_tests(&[("main.rego", &main)]);
+        let output = run_network_tool_impl(
+            &policy_engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: sh_path,
+                args: vec![
+                    "-c".to_string(),
+                    "printf hello-world; printf noise >&2".to_string(),
+                ],
+                max_output_bytes: Some(4),
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect("command should run");
+
+        assert_eq!(output.stdout, format!("hell{STDOUT_TRUNCATION_MARKER}"));
+        // The policy's lower stderr limit still applies.
+        assert_eq!(output.stderr, format!("no{STDERR_TRUNCATION_MARKER}"));
+    }
+
+    #[tokio::test]
+    async fn spilled_output_pages_through_what_truncation_left_out() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let policy_engine = rego_engine_allow_commands(&[&sh_path]);
+        let dir = tempfile::tempdir().expect("tempdir");
+        let spills = OutputSpills::new(dir.path().to_path_buf());
+        let input = RunNetworkToolInput {
+            executable: sh_path,
+            args: vec![
+                "-c".to_string(),
+                "printf 0123456789; printf ok >&2".to_string(),
+            ],
+            max_output_bytes: Some(4),
+            truncation: Some(Truncation::HeadAndTail),
+            spill_output: Some(true),
+            ..RunNetworkToolInput::default()
+        };
+        let context = InvocationContext::default();
+
+        let error = run_network_tool_in_context(
+            &policy_engine,
+            Path::new("."),
+            input.clone(),
+            OutputLimits::default(),
+            &context,
+        )
+        .await
+        .expect_err("spillOutput needs somewhere to keep the output");
+        assert!(matches!(error, ToolError::SpillUnsupported));
+
+        let output = run_network_tool_with_spills(
+            &policy_engine,
+            Path::new("."),
+            input,
+            OutputLimits::default(),
+            &context,
+            &spills,
+        )
+        .await
+        .expect("command should run");
+        assert_eq!(output.stdout, format!("01{STDOUT_TRUNCATION_MARKER}\n89"));
+        assert_eq!(output.stderr, "ok");
+        assert_eq!(output.stderr_continuation, None);
+
+        let token = output.stdout_continuation.expect("stdout continuation");
+        let chunk = spills.chunk(&token, None).await.expect("chunk");
+        assert_eq!((chunk.data.as_str(), chunk.offset, chunk.end), ("234567", 2, 8));
+        assert_eq!(chunk.next_token, None);
+        // Only the truncated stream's file is kept.
+        assert_eq!(std::fs::read_dir(dir.path()).expect("read dir").count(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn reports_resource_usage_on_linux() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let policy_engine = rego_engine_allow_commands(&[&sh_path]);
+        let output = run_network_tool_impl(
+            &policy_engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: sh_path,
+                args: vec![
+                    "-c".to_string(),
+                    "i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done".to_string(),
+                ],
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect("command should run");
+
+        assert_eq!(output.exit_code, Some(0));
+        let usage = output.resource_usage.expect("rusage on linux");
+        assert!(usage.max_rss_bytes > 0);
+        assert!(usage.user_cpu_ms + usage.system_cpu_ms > 0);
+    }
+
     #[tokio::test]
     async fn command_runs_with_sanitized_environment() {
         let env_path = match find_executable("env") {
@@ -519,8 +1803,6 @@ mod tests {
             Path::new("."),
             RunNetworkToolInput {
                 executable: env_path,
-                args: vec![],
-                cwd: None,
                 env: Some(BTreeMap::from([
                     ("CUSTOM_USER_ENV".to_string(), "allowed".to_string()),
                     ("HOME".to_string(), "user-home".to_string()),
@@ -533,6 +1815,7 @@ mod tests {
                     ("HTTPS_PROXY".to_string(), "user-https-upper".to_string()),
                     ("NO_PROXY".to_string(), "user-no-upper".to_string()),
                 ])),
+                ..RunNetworkToolInput::default()
             },
         )
         .await
@@ -607,8 +1890,7 @@ mod tests {
             RunNetworkToolInput {
                 executable: "echo".to_string(),
                 args: vec!["blocked".to_string()],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             },
         )
         .await
@@ -617,6 +1899,187 @@ mod tests {
         assert!(error.to_string().contains("Command not allowed"));
     }
 
+    #[tokio::test]
+    async fn timeout_kills_process_group() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let policy_engine = rego_engine_allow_commands(&[&sh_path]);
+        let started = std::time::Instant::now();
+        let error = run_network_tool_impl(
+            &policy_engine,
+            Path::new("."),
+            RunNetworkToolInput {
+                executable: sh_path,
+                args: vec!["-c".to_string(), "sleep 30 & wait".to_string()],
+                timeout_ms: Some(200),
+                ..RunNetworkToolInput::default()
+            },
+        )
+        .await
+        .expect_err("command should time out");
+
+        assert!(matches!(error, ToolError::Timeout { timeout_ms: 200 }));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stop_process_group_kills_after_the_grace_period() {
+        // `sh` and `sleep` both ignore SIGTERM, so only the kill after the grace stops them.
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; echo ready; sleep 30"])
+            .process_group(0)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        let mut ready = [0u8; 6];
+        let mut stdout = child.stdout.take().expect("stdout");
+        tokio::io::AsyncReadExt::read_exact(&mut stdout, &mut ready)
+            .await
+            .expect("ready line");
+        let process_group = child.id();
+        let started = std::time::Instant::now();
+        stop_process_group(&mut child, process_group, Duration::from_millis(200)).await;
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(child.try_wait().expect("child status").is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exit_watches_leave_the_blocking_pool_free() {
+        let runtime = crate::RuntimeConfig {
+            worker_threads: Some(1),
+            max_blocking_threads: Some(1),
+        }
+        .build()
+        .expect("runtime");
+
+        runtime.block_on(async {
+            let mut children = Vec::new();
+            let mut watches = Vec::new();
+            for _ in 0..3 {
+                let child = tokio::process::Command::new("sleep")
+                    .arg("30")
+                    .kill_on_drop(true)
+                    .spawn()
+                    .expect("spawn sleep");
+                watches.push(spawn_exit_watch(&child));
+                children.push(child);
+            }
+
+            // With the single blocking thread held by a watch, this would wait for `sleep`.
+            let hashed = tokio::time::timeout(
+                Duration::from_secs(5),
+                tokio::task::spawn_blocking(|| 1),
+            )
+            .await;
+            assert!(matches!(hashed, Ok(Ok(1))));
+
+            for (mut child, watch) in children.into_iter().zip(watches) {
+                child.start_kill().expect("kill sleep");
+                let usage = tokio::time::timeout(Duration::from_secs(5), watch)
+                    .await
+                    .expect("watch resolves after the exit")
+                    .expect("join");
+                assert_eq!(usage.is_some(), cfg!(target_os = "linux"));
+                assert!(child.wait().await.expect("reap").code().is_none());
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn truncation_keeps_the_requested_end_of_the_stream() {
+        let capture = |limit, truncation| async move {
+            let (capture, truncated) =
+                read_limited(&b"0123456789"[..], limit, truncation, 0, None)
+                    .await
+                    .expect("read");
+            finalize_capture(
+                capture,
+                truncated,
+                STDOUT_TRUNCATION_MARKER,
+                &RedactPatterns::default(),
+            )
+        };
+
+        assert_eq!(capture(4, Truncation::Head).await, "0123\n...stdout truncated...");
+        assert_eq!(capture(4, Truncation::Tail).await, "...stdout truncated...\n6789");
+        assert_eq!(
+            capture(5, Truncation::HeadAndTail).await,
+            "01\n...stdout truncated...\n789"
+        );
+        for truncation in [Truncation::Head, Truncation::Tail, Truncation::HeadAndTail] {
+            assert_eq!(capture(10, truncation).await, "0123456789");
+        }
+    }
+
+    #[tokio::test]
+    async fn redaction_sees_matches_crossing_the_truncation() {
+        let redact = RedactPatterns::new(&[r"tok_[a-z]+"]).expect("patterns");
+        let capture = |output: &'static [u8], limit, truncation| {
+            let redact = redact.clone();
+            async move {
+                let (capture, truncated) =
+                    read_limited(output, limit, truncation, REDACT_LOOKAHEAD_BYTES, None)
+                        .await
+                        .expect("read");
+                finalize_capture(capture, truncated, STDOUT_TRUNCATION_MARKER, &redact)
+            }
+        };
+
+        let output = b"id tok_abcdef tok_ghijkl ok";
+        assert_eq!(
+            capture(output, 8, Truncation::Head).await,
+            "id [REDACTED]\n...stdout truncated..."
+        );
+        assert_eq!(
+            capture(output, 8, Truncation::Tail).await,
+            "...stdout truncated...\n[REDACTED] ok"
+        );
+        assert_eq!(
+            capture(output, 12, Truncation::HeadAndTail).await,
+            "id [REDACTED]\n...stdout truncated...\n[REDACTED] ok"
+        );
+        for truncation in [Truncation::Head, Truncation::Tail, Truncation::HeadAndTail] {
+            assert_eq!(
+                capture(output, 64, truncation).await,
+                "id [REDACTED] [REDACTED] ok"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn own_binaries_are_denied_by_resolved_path() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let own = OwnBinaries::from_paths([std::fs::canonicalize(&sh_path).expect("canonical")]);
+        let input = || RunNetworkToolInput {
+            executable: sh_path.clone(),
+            args: vec!["-c".to_string(), "true".to_string()],
+            ..RunNetworkToolInput::default()
+        };
+
+        let guarded = rego_engine_allow_commands(&[&sh_path]).with_own_binaries(own);
+        let result = run_network_tool_impl(&guarded, Path::new("."), input()).await;
+        assert!(matches!(
+            result,
+            Err(ToolError::Validation(ValidationError::SelfInvocation { .. }))
+        ));
+
+        let allowed = rego_engine_allow_commands(&[&sh_path]).with_self_invocation_allowed(true);
+        let output = run_network_tool_impl(&allowed, Path::new("."), input())
+            .await
+            .expect("allowed");
+        assert_eq!(output.exit_code, Some(0));
+    }
+
     #[tokio::test]
     async fn truncates_stdout_at_one_mb() {
         let head_path = match find_executable("head") {
@@ -635,14 +2098,13 @@ mod tests {
                     (MAX_OUTPUT_BYTES + 5).to_string(),
                     "/dev/zero".to_string(),
                 ],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             },
         )
         .await
         .expect("head should run");
 
         assert_eq!(output.exit_code, Some(0));
-        assert!(output.stdout.ends_with(TRUNCATION_MARKER));
+        assert!(output.stdout.ends_with(STDOUT_TRUNCATION_MARKER));
     }
 }
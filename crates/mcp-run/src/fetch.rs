@@ -0,0 +1,381 @@
+//! The `fetch_verified` MCP tool: downloads a URL pinned to a SHA-256 digest into a directory
+//! the policy approves, so agents can fetch artifacts without being allowed `curl` and writes.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+
+use crate::audit::AuditRecord;
+use crate::hashing::{HashAlgorithm, hash_file_hex};
+use crate::policy::{InvocationContext, PolicyEngine, ValidationError};
+
+/// Largest download `fetch_verified` accepts.
+pub const MAX_FETCH_BYTES: u64 = 1024 * 1024 * 1024;
+/// Limit on a whole download, including connecting and reading the body.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Distinguishes concurrent partial downloads of the same digest.
+static PARTIAL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchVerifiedInput {
+    /// `http` or `https` URL, fetched through the server's proxy settings.
+    pub url: String,
+    /// Expected SHA-256 of the content, as 64 hex digits.
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchVerifiedOutput {
+    /// Where the verified content is stored: `<fetch_dir>/<sha256>`.
+    pub path: String,
+    pub sha256: String,
+    pub bytes: u64,
+    /// Whether the file was already present with the expected digest and nothing was fetched.
+    pub cached: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error("Invalid sha256 '{0}': expected 64 hex digits")]
+    InvalidDigest(String),
+    #[error("Invalid URL '{0}': expected an http or https URL")]
+    InvalidUrl(String),
+    #[error("Download failed: {source}")]
+    Request { source: reqwest::Error },
+    #[error("Download failed: server returned {status}")]
+    Status { status: reqwest::StatusCode },
+    #[error("Download exceeds {limit} bytes")]
+    TooLarge { limit: u64 },
+    #[error("SHA-256 mismatch: expected {expected}, got {actual}")]
+    DigestMismatch { expected: String, actual: String },
+    #[error("Failed to write '{}': {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Downloads `input.url` into the directory the policy's `fetch_dir` rule approves and keeps
+/// it only if its SHA-256 matches. Every attempt is audited.
+pub async fn fetch_verified(
+    policy_engine: &PolicyEngine,
+    input: FetchVerifiedInput,
+    context: &InvocationContext,
+) -> Result<FetchVerifiedOutput, FetchError> {
+    let sha256 = input.sha256.trim().to_ascii_lowercase();
+    let policy_mode = policy_engine.mode();
+    let audit = |dir: Option<&Path>| {
+        AuditRecord::begin_fetch(policy_mode.clone(), context, &input.url, &sha256, dir)
+    };
+    if sha256.len() != 64 || !sha256.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        let error = FetchError::InvalidDigest(input.sha256.clone());
+        audit(None).deny(&error);
+        return Err(error);
+    }
+    let url_ok =
+        reqwest::Url::parse(&input.url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+    if !url_ok {
+        let error = FetchError::InvalidUrl(input.url.clone());
+        audit(None).deny(&error);
+        return Err(error);
+    }
+    let dir = match policy_engine.authorize_fetch(&input.url, &sha256, context) {
+        Ok(dir) => dir,
+        Err(error) => {
+            tracing::warn!(url = %input.url, error = %error, "download denied");
+            audit(None).deny_validation(&error);
+            return Err(error.into());
+        }
+    };
+
+    let audit = audit(Some(&dir));
+    match download_into(&dir, &input.url, &sha256).await {
+        Ok(output) => {
+            tracing::info!(url = %input.url, path = %output.path, cached = output.cached, "download verified");
+            audit.complete(None, None, false, false);
+            Ok(output)
+        }
+        Err(error) => {
+            tracing::warn!(url = %input.url, error = %error, "download failed");
+            audit.fail(&error);
+            Err(error)
+        }
+    }
+}
+
+async fn download_into(
+    dir: &Path,
+    url: &str,
+    sha256: &str,
+) -> Result<FetchVerifiedOutput, FetchError> {
+    let target = dir.join(sha256);
+    if let Some(bytes) = verified_size(&target, sha256).await {
+        return Ok(FetchVerifiedOutput {
+            path: target.display().to_string(),
+            sha256: sha256.to_string(),
+            bytes,
+            cached: true,
+        });
+    }
+
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|source| FetchError::Io {
+            path: dir.to_path_buf(),
+            source,
+        })?;
+    let partial = dir.join(format!(
+        ".{sha256}.{}.{}.partial",
+        std::process::id(),
+        PARTIAL_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let bytes = match download_to(url, &partial, sha256).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            // Content that failed verification never reaches `target`.
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(error);
+        }
+    };
+    if let Err(source) = tokio::fs::rename(&partial, &target).await {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(FetchError::Io {
+            path: target,
+            source,
+        });
+    }
+    Ok(FetchVerifiedOutput {
+        path: target.display().to_string(),
+        sha256: sha256.to_string(),
+        bytes,
+        cached: false,
+    })
+}
+
+/// Streams `url` into `path`, hashing as it goes, and returns the size once the digest matches.
+async fn download_to(url: &str, path: &Path, sha256: &str) -> Result<u64, FetchError> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|source| FetchError::Request { source })?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|source| FetchError::Request { source })?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchError::Status { status });
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_FETCH_BYTES)
+    {
+        return Err(FetchError::TooLarge {
+            limit: MAX_FETCH_BYTES,
+        });
+    }
+
+    let io_error = |source| FetchError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut file = tokio::fs::File::create(path).await.map_err(io_error)?;
+    let mut hasher = Sha256::new();
+    let mut bytes = 0u64;
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|source| FetchError::Request { source })?;
+        bytes += chunk.len() as u64;
+        if bytes > MAX_FETCH_BYTES {
+            return Err(FetchError::TooLarge {
+                limit: MAX_FETCH_BYTES,
+            });
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.map_err(io_error)?;
+    }
+    file.sync_all().await.map_err(io_error)?;
+
+    let actual: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if actual != sha256 {
+        return Err(FetchError::DigestMismatch {
+            expected: sha256.to_string(),
+            actual,
+        });
+    }
+    Ok(bytes)
+}
+
+/// The size of `path` if it exists and already has the expected digest.
+async fn verified_size(path: &Path, sha256: &str) -> Option<u64> {
+    let path = path.to_path_buf();
+    let sha256 = sha256.to_string();
+    tokio::task::spawn_blocking(move || {
+        let metadata = std::fs::metadata(&path)
+            .ok()
+            .filter(|metadata| metadata.is_file())?;
+        (hash_file_hex(&path, HashAlgorithm::Sha256).ok()? == sha256).then_some(metadata.len())
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+    use axum::routing::get;
+
+    use super::*;
+    use crate::test_support::serve_app;
+
+    async fn serve_artifact(body: &'static [u8]) -> (String, tokio::task::JoinHandle<()>) {
+        serve_app(Router::new().route("/artifact", get(move || async move { body }))).await
+    }
+
+    fn sha256_hex(bytes: &[u8]) -> String {
+        Sha256::digest(bytes)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    fn policy_with_fetch_dir(dir: &Path) -> PolicyEngine {
+        let escaped = dir
+            .display()
+            .to_string()
+            .replace('\\', "\\\\")
+            .replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\ndefault allow = false\n\nfetch_dir := \"{escaped}\" if startswith(input.url, \"http://127.0.0.1:\")\n"
+        );
+        PolicyEngine::from_rego_for_tests(&[("main.rego", &main)])
+    }
+
+    #[tokio::test]
+    async fn fetch_verified_stores_matching_content_by_digest() {
+        let (base, server_task) = serve_artifact(b"artifact-bytes").await;
+        let dir = tempfile::tempdir().expect("temp dir");
+        let fetch_dir = dir.path().join("artifacts");
+        let policy_engine = policy_with_fetch_dir(&fetch_dir);
+        let sha256 = sha256_hex(b"artifact-bytes");
+        let input = FetchVerifiedInput {
+            url: format!("{base}/artifact"),
+            sha256: sha256.to_uppercase(),
+        };
+
+        let output = fetch_verified(&policy_engine, input.clone(), &InvocationContext::default())
+            .await
+            .expect("download verifies");
+        let stored = fetch_dir.join(&sha256);
+        assert_eq!(
+            output,
+            FetchVerifiedOutput {
+                path: stored.display().to_string(),
+                sha256: sha256.clone(),
+                bytes: 14,
+                cached: false,
+            }
+        );
+        assert_eq!(
+            std::fs::read(&stored).expect("stored file"),
+            b"artifact-bytes"
+        );
+
+        let again = fetch_verified(&policy_engine, input, &InvocationContext::default())
+            .await
+            .expect("cached download");
+        assert!(again.cached);
+        assert_eq!(
+            std::fs::read_dir(&fetch_dir).expect("fetch dir").count(),
+            1,
+            "no partial files are left behind"
+        );
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn fetch_verified_rejects_mismatches_and_unapproved_urls() {
+        let (base, server_task) = serve_artifact(b"tampered").await;
+        let dir = tempfile::tempdir().expect("temp dir");
+        let policy_engine = policy_with_fetch_dir(dir.path());
+        let expected = sha256_hex(b"artifact-bytes");
+
+        let error = fetch_verified(
+            &policy_engine,
+            FetchVerifiedInput {
+                url: format!("{base}/artifact"),
+                sha256: expected.clone(),
+            },
+            &InvocationContext::default(),
+        )
+        .await
+        .expect_err("digest mismatch");
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "SHA-256 mismatch: expected {expected}, got {}",
+                sha256_hex(b"tampered")
+            )
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).expect("dir").count(), 0);
+
+        let error = fetch_verified(
+            &policy_engine,
+            FetchVerifiedInput {
+                url: "https://example.com/artifact".to_string(),
+                sha256: expected.clone(),
+            },
+            &InvocationContext::default(),
+        )
+        .await
+        .expect_err("not approved");
+        assert!(matches!(
+            error,
+            FetchError::Validation(ValidationError::FetchNotAllowed(_))
+        ));
+
+        let error = fetch_verified(
+            &policy_engine,
+            FetchVerifiedInput {
+                url: "file:///etc/passwd".to_string(),
+                sha256: expected,
+            },
+            &InvocationContext::default(),
+        )
+        .await
+        .expect_err("not http");
+        assert!(matches!(error, FetchError::InvalidUrl(_)));
+        let error = fetch_verified(
+            &policy_engine,
+            FetchVerifiedInput {
+                url: format!("{base}/artifact"),
+                sha256: "abc".to_string(),
+            },
+            &InvocationContext::default(),
+        )
+        .await
+        .expect_err("short digest");
+        assert!(matches!(error, FetchError::InvalidDigest(_)));
+
+        server_task.abort();
+    }
+}
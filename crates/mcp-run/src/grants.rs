@@ -0,0 +1,357 @@
+//! Ephemeral allow grants: an operator lets one client run one exact command once, without
+//! editing the policy. Grants live in memory, expire after a TTL and are consumed by the
+//! first matching invocation, which is checked before the Rego policy.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::audit::{AuditRecord, format_timestamp};
+use crate::policy::{InvocationContext, PolicyMode, normalize_lexically};
+
+/// Lifetime of a grant created without `ttlSecs`.
+pub const DEFAULT_GRANT_TTL: Duration = Duration::from_secs(600);
+/// Longer `ttlSecs` values are rejected.
+pub const MAX_GRANT_TTL: Duration = Duration::from_secs(3600);
+/// Unused grants held at once; creating more fails until some are used, revoked or expire.
+pub const MAX_ACTIVE_GRANTS: usize = 64;
+
+/// Body of `POST /admin/grants`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct GrantRequest {
+    /// The `X-Client-Id` the invocation must come from.
+    pub client_id: String,
+    /// Matched against the requested executable exactly as the client sends it.
+    pub executable: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Exact env the invocation must send; the default allows none.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Absolute working directory the invocation must use; unset allows any.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    /// Operator's note, copied to the audit records.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// An unused grant, as returned by the `/admin/grants` endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Grant {
+    pub id: String,
+    pub client_id: String,
+    pub executable: String,
+    pub args: Vec<String>,
+    /// Env keys the invocation must send; values are never returned.
+    pub env_keys: Vec<String>,
+    pub cwd: Option<String>,
+    /// RFC 3339 UTC time after which the grant can no longer be used.
+    pub expires_at: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GrantError {
+    #[error("clientId must not be empty")]
+    MissingClientId,
+    #[error("executable must not be empty")]
+    MissingExecutable,
+    #[error("cwd must be absolute: {0}")]
+    RelativeCwd(String),
+    #[error("ttlSecs must be between 1 and {}", MAX_GRANT_TTL.as_secs())]
+    InvalidTtl,
+    #[error("too many active grants (limit {MAX_ACTIVE_GRANTS}); revoke or use some first")]
+    TooMany,
+}
+
+/// Unused grants by id, held by the [`crate::PolicyEngine`] so every execution path sees them.
+#[derive(Debug, Default)]
+pub struct GrantRegistry {
+    table: Mutex<GrantTable>,
+}
+
+#[derive(Debug, Default)]
+struct GrantTable {
+    next_id: u64,
+    grants: BTreeMap<u64, StoredGrant>,
+}
+
+#[derive(Debug)]
+struct StoredGrant {
+    client_id: String,
+    executable: String,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    cwd: Option<PathBuf>,
+    expires_at: SystemTime,
+    reason: Option<String>,
+}
+
+impl StoredGrant {
+    fn view(&self, id: u64) -> Grant {
+        Grant {
+            id: format_grant_id(id),
+            client_id: self.client_id.clone(),
+            executable: self.executable.clone(),
+            args: self.args.clone(),
+            env_keys: self.env.keys().cloned().collect(),
+            cwd: self.cwd.as_ref().map(|cwd| cwd.display().to_string()),
+            expires_at: format_timestamp(self.expires_at),
+            reason: self.reason.clone(),
+        }
+    }
+
+    fn matches(
+        &self,
+        command: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        context: &InvocationContext,
+    ) -> bool {
+        context.client_id.as_deref() == Some(self.client_id.as_str())
+            && self.executable == command
+            && self.args == args
+            && &self.env == env
+            && self
+                .cwd
+                .as_ref()
+                .is_none_or(|cwd| context.cwd.as_deref() == Some(cwd.as_path()))
+    }
+}
+
+impl GrantRegistry {
+    fn table(&self) -> MutexGuard<'_, GrantTable> {
+        self.table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn insert(
+        &self,
+        request: GrantRequest,
+        now: SystemTime,
+        policy_mode: &PolicyMode,
+    ) -> Result<Grant, GrantError> {
+        if request.client_id.trim().is_empty() {
+            return Err(GrantError::MissingClientId);
+        }
+        if request.executable.trim().is_empty() {
+            return Err(GrantError::MissingExecutable);
+        }
+        let cwd = match &request.cwd {
+            Some(cwd) if !cwd.is_absolute() => {
+                return Err(GrantError::RelativeCwd(cwd.display().to_string()));
+            }
+            Some(cwd) => Some(normalize_lexically(cwd)),
+            None => None,
+        };
+        let ttl = match request.ttl_secs {
+            None => DEFAULT_GRANT_TTL,
+            Some(secs) if secs > 0 && secs <= MAX_GRANT_TTL.as_secs() => Duration::from_secs(secs),
+            Some(_) => return Err(GrantError::InvalidTtl),
+        };
+
+        let mut table = self.table();
+        prune_expired(&mut table, now, policy_mode);
+        if table.grants.len() >= MAX_ACTIVE_GRANTS {
+            return Err(GrantError::TooMany);
+        }
+        table.next_id += 1;
+        let id = table.next_id;
+        let stored = StoredGrant {
+            client_id: request.client_id,
+            executable: request.executable,
+            args: request.args,
+            env: request.env,
+            cwd,
+            expires_at: now + ttl,
+            reason: request.reason,
+        };
+        let grant = stored.view(id);
+        audit_grant(&grant, policy_mode, None);
+        table.grants.insert(id, stored);
+        Ok(grant)
+    }
+
+    /// Removes and returns the oldest unexpired grant matching the invocation.
+    pub(crate) fn take(
+        &self,
+        command: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        context: &InvocationContext,
+        now: SystemTime,
+        policy_mode: &PolicyMode,
+    ) -> Option<Grant> {
+        // Deny-all stays fail-closed; the grant is kept for when a policy loads again.
+        if *policy_mode == PolicyMode::DenyAll {
+            return None;
+        }
+        context.client_id.as_ref()?;
+        let mut table = self.table();
+        prune_expired(&mut table, now, policy_mode);
+        let id = table
+            .grants
+            .iter()
+            .find(|(_, grant)| grant.matches(command, args, env, context))
+            .map(|(id, _)| *id)?;
+        table.grants.remove(&id).map(|grant| grant.view(id))
+    }
+
+    pub(crate) fn list(&self, now: SystemTime, policy_mode: &PolicyMode) -> Vec<Grant> {
+        let mut table = self.table();
+        prune_expired(&mut table, now, policy_mode);
+        table
+            .grants
+            .iter()
+            .map(|(id, grant)| grant.view(*id))
+            .collect()
+    }
+
+    /// Returns whether an unused grant `grant_id` existed.
+    pub(crate) fn revoke(&self, grant_id: &str, policy_mode: &PolicyMode) -> bool {
+        let Some(id) = parse_grant_id(grant_id) else {
+            return false;
+        };
+        let Some(grant) = self.table().grants.remove(&id) else {
+            return false;
+        };
+        audit_grant(&grant.view(id), policy_mode, Some("grant revoked unused"));
+        true
+    }
+}
+
+fn prune_expired(table: &mut GrantTable, now: SystemTime, policy_mode: &PolicyMode) {
+    let expired: Vec<u64> = table
+        .grants
+        .iter()
+        .filter(|(_, grant)| grant.expires_at <= now)
+        .map(|(id, _)| *id)
+        .collect();
+    for id in expired {
+        if let Some(grant) = table.grants.remove(&id) {
+            audit_grant(&grant.view(id), policy_mode, Some("grant expired unused"));
+        }
+    }
+}
+
+/// Records a grant being created (`ended` unset) or ending without being used.
+fn audit_grant(grant: &Grant, policy_mode: &PolicyMode, ended: Option<&str>) {
+    let record = AuditRecord::begin_grant(policy_mode, grant);
+    match ended {
+        None => {
+            tracing::info!(grant_id = %grant.id, client_id = %grant.client_id, command = %grant.executable, "allow grant created");
+            record.allow();
+        }
+        Some(reason) => {
+            tracing::info!(grant_id = %grant.id, client_id = %grant.client_id, command = %grant.executable, reason, "allow grant ended");
+            record.deny(&reason);
+        }
+    }
+}
+
+fn format_grant_id(id: u64) -> String {
+    format!("grant-{id}")
+}
+
+fn parse_grant_id(grant_id: &str) -> Option<u64> {
+    grant_id.strip_prefix("grant-")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(client_id: &str, args: &[&str]) -> GrantRequest {
+        GrantRequest {
+            client_id: client_id.to_string(),
+            executable: "curl".to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            env: BTreeMap::new(),
+            cwd: None,
+            ttl_secs: None,
+            reason: Some("debugging".to_string()),
+        }
+    }
+
+    fn context(client_id: Option<&str>) -> InvocationContext {
+        InvocationContext {
+            client_id: client_id.map(str::to_string),
+            cwd: Some(PathBuf::from("/workspace")),
+            canonical_cwd: None,
+            hops: 0,
+        }
+    }
+
+    #[test]
+    fn grants_are_single_use_and_exact() {
+        let registry = GrantRegistry::default();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let args = vec!["-X".to_string(), "POST".to_string()];
+        let no_env = BTreeMap::new();
+        let grant = registry
+            .insert(request("grant-test-a", &["-X", "POST"]), now, &PolicyMode::Rego)
+            .expect("grant");
+        assert_eq!(grant.id, "grant-1");
+        assert_eq!(grant.expires_at, "1970-01-01T00:26:40.000Z");
+
+        let take = |client: Option<&str>, args: &[String], env: &BTreeMap<String, String>| {
+            registry.take("curl", args, env, &context(client), now, &PolicyMode::Rego)
+        };
+        assert!(take(None, &args, &no_env).is_none());
+        assert!(take(Some("other-client"), &args, &no_env).is_none());
+        assert!(take(Some("grant-test-a"), &args[..1], &no_env).is_none());
+        let env = BTreeMap::from([("LD_PRELOAD".to_string(), "/tmp/x.so".to_string())]);
+        assert!(take(Some("grant-test-a"), &args, &env).is_none());
+
+        assert!(registry
+            .take("curl", &args, &no_env, &context(Some("grant-test-a")), now, &PolicyMode::DenyAll)
+            .is_none());
+        assert_eq!(take(Some("grant-test-a"), &args, &no_env), Some(grant));
+        assert!(take(Some("grant-test-a"), &args, &no_env).is_none());
+    }
+
+    #[test]
+    fn grants_expire_and_can_be_revoked() {
+        let registry = GrantRegistry::default();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut expiring = request("grant-test-b", &[]);
+        expiring.ttl_secs = Some(5);
+        registry
+            .insert(expiring, now, &PolicyMode::Rego)
+            .expect("grant");
+        let revoked = registry
+            .insert(request("grant-test-b", &["-I"]), now, &PolicyMode::Rego)
+            .expect("grant");
+        assert_eq!(registry.list(now, &PolicyMode::Rego).len(), 2);
+
+        let later = now + Duration::from_secs(5);
+        let no_env = BTreeMap::new();
+        assert!(registry
+            .take("curl", &[], &no_env, &context(Some("grant-test-b")), later, &PolicyMode::Rego)
+            .is_none());
+        assert!(registry.revoke(&revoked.id, &PolicyMode::Rego));
+        assert!(!registry.revoke(&revoked.id, &PolicyMode::Rego));
+        assert!(registry.list(later, &PolicyMode::Rego).is_empty());
+
+        let mut invalid = request("grant-test-b", &[]);
+        invalid.ttl_secs = Some(MAX_GRANT_TTL.as_secs() + 1);
+        assert_eq!(
+            registry.insert(invalid, now, &PolicyMode::Rego),
+            Err(GrantError::InvalidTtl)
+        );
+        let mut relative = request("grant-test-b", &[]);
+        relative.cwd = Some(PathBuf::from("src"));
+        assert_eq!(
+            registry.insert(relative, now, &PolicyMode::Rego),
+            Err(GrantError::RelativeCwd("src".to_string()))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt::Write as _;
+use std::io::Read;
+use std::path::Path;
+
+use sha2::{Digest, Sha256, Sha512};
+
+/// Read size for streaming files through a hasher; memory use is independent of file size.
+const HASH_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Digests accepted by the `file_hash` policy builtin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(Self::Sha256),
+            "sha512" => Some(Self::Sha512),
+            "blake3" => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+}
+
+/// Lowercase hex digest of the file at `path`, read in fixed-size chunks.
+pub fn hash_file_hex(path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    let file = std::fs::File::open(path)?;
+    match algorithm {
+        HashAlgorithm::Sha256 => digest_reader::<Sha256>(file),
+        HashAlgorithm::Sha512 => digest_reader::<Sha512>(file),
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            read_chunks(file, |chunk| {
+                hasher.update(chunk);
+            })?;
+            Ok(hex(hasher.finalize().as_bytes()))
+        }
+    }
+}
+
+/// Where the `file_hash` policy builtin gets its digests, so policy evaluation can run against
+/// an in-memory table (in tests and fuzzing) instead of the filesystem.
+pub trait FileHasher: std::fmt::Debug + Send + Sync {
+    fn hash_hex(&self, path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String>;
+}
+
+/// Hashes files on disk with [`hash_file_hex`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsHasher;
+
+impl FileHasher for FsHasher {
+    fn hash_hex(&self, path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+        hash_file_hex(path, algorithm)
+    }
+}
+
+fn digest_reader<D: Digest>(reader: impl Read) -> std::io::Result<String> {
+    let mut hasher = D::new();
+    read_chunks(reader, |chunk| hasher.update(chunk))?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Feeds `reader` to `update` in [`HASH_BUFFER_BYTES`] chunks.
+fn read_chunks(mut reader: impl Read, mut update: impl FnMut(&[u8])) -> std::io::Result<()> {
+    let mut buffer = vec![0u8; HASH_BUFFER_BYTES];
+    loop {
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(bytes_read) => bytes_read,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        update(&buffer[..bytes_read]);
+    }
+}
+
+fn hex(digest: &[u8]) -> String {
+    let mut output = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        let _ = write!(&mut output, "{byte:02x}");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_files_larger_than_the_buffer() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let empty = dir.path().join("empty");
+        std::fs::write(&empty, b"").expect("write empty");
+        assert_eq!(
+            hash_file_hex(&empty, HashAlgorithm::Sha256).expect("sha256"),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hash_file_hex(&empty, HashAlgorithm::Sha512).expect("sha512"),
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"
+        );
+
+        // Spans several reads, so chunk boundaries must not change the digest.
+        let large = dir.path().join("large");
+        let contents = vec![b'a'; HASH_BUFFER_BYTES * 3 + 17];
+        std::fs::write(&large, &contents).expect("write large");
+        let expected: String = Sha512::digest(&contents)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        assert_eq!(
+            hash_file_hex(&large, HashAlgorithm::Sha512).expect("sha512"),
+            expected
+        );
+    }
+
+    #[test]
+    fn hashes_files_with_blake3() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let empty = dir.path().join("empty");
+        std::fs::write(&empty, b"").expect("write empty");
+        assert_eq!(
+            hash_file_hex(&empty, HashAlgorithm::Blake3).expect("blake3"),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+
+        let large = dir.path().join("large");
+        let contents = vec![b'a'; HASH_BUFFER_BYTES * 3 + 17];
+        std::fs::write(&large, &contents).expect("write large");
+        assert_eq!(
+            hash_file_hex(&large, HashAlgorithm::Blake3).expect("blake3"),
+            blake3::hash(&contents).to_hex().as_str()
+        );
+    }
+
+    #[test]
+    fn parses_algorithm_names() {
+        assert_eq!(HashAlgorithm::parse("sha512"), Some(HashAlgorithm::Sha512));
+        assert_eq!(HashAlgorithm::Sha256.as_str(), "sha256");
+        assert_eq!(HashAlgorithm::parse("blake3"), Some(HashAlgorithm::Blake3));
+        assert_eq!(HashAlgorithm::Blake3.as_str(), "blake3");
+        assert_eq!(HashAlgorithm::parse("md5"), None);
+    }
+}
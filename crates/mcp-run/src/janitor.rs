@@ -0,0 +1,185 @@
+//! Removes stale working files, such as checkouts left by `git clone`, under the policy's
+//! `cleanup` rule. The rule is re-read on every sweep, so policy reloads apply without a
+//! restart. Each removal is logged and written to the audit trail.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use globset::GlobBuilder;
+use walkdir::WalkDir;
+
+use crate::audit::AuditRecord;
+use crate::policy::{CleanupRule, PolicyEngine, PolicyMode};
+
+pub const CLEANUP_INTERVAL_ENV: &str = "MCP_CLEANUP_INTERVAL_SECS";
+pub const DEFAULT_CLEANUP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Sweeps every `interval`, starting one interval after startup.
+pub(crate) async fn run_janitor(policy_engine: Arc<PolicyEngine>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    ticks.tick().await;
+    loop {
+        ticks.tick().await;
+        let rules = match policy_engine.cleanup_rules() {
+            Ok(rules) => rules,
+            Err(error) => {
+                tracing::warn!(%error, "cleanup rule failed to evaluate; nothing removed");
+                continue;
+            }
+        };
+        if rules.is_empty() {
+            continue;
+        }
+        let policy_mode = policy_engine.mode();
+        let _ = tokio::task::spawn_blocking(move || sweep(&rules, &policy_mode, SystemTime::now()))
+            .await;
+    }
+}
+
+/// Removes each path matching `rules` in which nothing was modified within the rule's
+/// `older_than` before `now`, and returns the removed paths. A matched directory is removed
+/// or kept whole; nothing below it is matched separately.
+pub(crate) fn sweep(rules: &[CleanupRule], policy_mode: &PolicyMode, now: SystemTime) -> Vec<PathBuf> {
+    let mut removed = Vec::new();
+    for rule in rules {
+        for pattern in &rule.paths {
+            let Ok(glob) = GlobBuilder::new(pattern).literal_separator(true).build() else {
+                continue;
+            };
+            let matcher = glob.compile_matcher();
+            let (base, max_depth) = walk_root(pattern);
+            let mut walker = WalkDir::new(&base);
+            if let Some(max_depth) = max_depth {
+                walker = walker.max_depth(max_depth);
+            }
+            let mut entries = walker.into_iter();
+            while let Some(entry) = entries.next() {
+                let Ok(entry) = entry else {
+                    continue;
+                };
+                if !matcher.is_match(entry.path()) {
+                    continue;
+                }
+                let is_dir = entry.file_type().is_dir();
+                if is_dir {
+                    entries.skip_current_dir();
+                }
+                if !is_stale(entry.path(), rule.older_than, now) {
+                    continue;
+                }
+                let audit = AuditRecord::begin_cleanup(policy_mode, entry.path(), pattern);
+                let result = if is_dir {
+                    fs::remove_dir_all(entry.path())
+                } else {
+                    fs::remove_file(entry.path())
+                };
+                match result {
+                    Ok(()) => {
+                        tracing::info!(path = %entry.path().display(), pattern, "removed stale path");
+                        audit.allow();
+                        removed.push(entry.path().to_path_buf());
+                    }
+                    Err(error) => {
+                        tracing::warn!(
+                            path = %entry.path().display(),
+                            pattern,
+                            %error,
+                            "failed to remove stale path"
+                        );
+                        audit.fail(&error);
+                    }
+                }
+            }
+        }
+    }
+    removed
+}
+
+/// The directory before the first glob in `pattern`, and how deep below it a match can be
+/// (`None` when `**` allows any depth).
+fn walk_root(pattern: &str) -> (PathBuf, Option<usize>) {
+    let components: Vec<&str> = pattern.split('/').filter(|part| !part.is_empty()).collect();
+    let literal = components
+        .iter()
+        .take_while(|part| !part.contains(['*', '?', '[', '{', '\\']))
+        .count();
+    let mut base = PathBuf::from("/");
+    base.extend(&components[..literal]);
+    let rest = &components[literal..];
+    let max_depth = if rest.iter().any(|part| part.contains("**")) {
+        None
+    } else {
+        Some(rest.len())
+    };
+    (base, max_depth)
+}
+
+/// Whether nothing at or below `path` was modified within `older_than` before `now`. Links
+/// are not followed.
+fn is_stale(path: &Path, older_than: Duration, now: SystemTime) -> bool {
+    let mut newest = SystemTime::UNIX_EPOCH;
+    for entry in WalkDir::new(path) {
+        let Some(modified) = entry
+            .ok()
+            .and_then(|entry| entry.metadata().ok())
+            .and_then(|metadata| metadata.modified().ok())
+        else {
+            // Unreadable entries might be in use; keep the path.
+            return false;
+        };
+        newest = newest.max(modified);
+    }
+    now.duration_since(newest)
+        .is_ok_and(|age| age >= older_than)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::recent_audit_records;
+    use crate::policy::DenialDetail;
+
+    #[test]
+    fn walk_root_stops_at_the_first_glob() {
+        assert_eq!(walk_root("/ws/.clones/*"), (PathBuf::from("/ws/.clones"), Some(1)));
+        assert_eq!(walk_root("/ws/**/target"), (PathBuf::from("/ws"), None));
+        assert_eq!(walk_root("/ws/tmp"), (PathBuf::from("/ws/tmp"), Some(0)));
+    }
+
+    #[test]
+    fn sweep_removes_only_stale_matches() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let clones = temp.path().join(".clones");
+        fs::create_dir_all(clones.join("repo/src")).expect("create clone");
+        fs::write(clones.join("repo/src/main.rs"), "fn main() {}").expect("write file");
+        fs::write(clones.join("notes.txt"), "keep").expect("write file");
+        fs::write(temp.path().join("other.txt"), "keep").expect("write file");
+
+        let rule = CleanupRule {
+            paths: vec![format!("{}/.clones/*", temp.path().display())],
+            older_than: Duration::from_secs(3600),
+        };
+        let rules = [rule];
+        assert!(sweep(&rules, &PolicyMode::Rego, SystemTime::now()).is_empty());
+        assert!(clones.join("repo").exists());
+
+        let later = SystemTime::now() + Duration::from_secs(7200);
+        let mut removed = sweep(&rules, &PolicyMode::Rego, later);
+        removed.sort();
+        assert_eq!(removed, vec![clones.join("notes.txt"), clones.join("repo")]);
+        assert!(!clones.join("repo").exists());
+        assert!(clones.exists());
+        assert!(temp.path().join("other.txt").exists());
+
+        let repo = clones.join("repo").display().to_string();
+        let record = recent_audit_records(crate::audit::MAX_AUDIT_HISTORY, DenialDetail::Full)
+            .into_iter()
+            .find(|record| record.args == [repo.clone()])
+            .expect("cleanup audit record");
+        assert_eq!(record.executable, "cleanup");
+        assert_eq!(record.reason.as_deref(), Some(rules[0].paths[0].as_str()));
+    }
+}
@@ -0,0 +1,666 @@
+//! Background jobs (`/jobs`): commands that keep running after the request that started them
+//! is gone, with their output buffered so clients can attach and re-attach.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, State, rejection::JsonRejection};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use base64::Engine as _;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::process::{Child, ChildStderr, ChildStdout};
+use tokio::sync::{Notify, mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::Instrument;
+
+use crate::audit::{AuditRecord, AuditSource};
+use crate::client_id::context_from_headers;
+use crate::executor::{
+    ResourceUsage, RunNetworkToolInput, ToolError, spawn_audited_process, spawn_exit_watch,
+};
+use crate::limiter::StreamPermit;
+use crate::metrics::record_child_exit;
+use crate::raw::{
+    OutputStreamKind, RawEndpointState, RawStreamEvent, ReaderEvent, error_response,
+    limit_response, read_output_stream, send_event, terminate_child,
+};
+use crate::redaction::redact_env_values;
+use crate::request_id::current_request_id;
+
+/// Output kept per job; beyond this the oldest chunks are dropped.
+pub const MAX_JOB_BUFFER_BYTES: usize = 8 * 1024 * 1024;
+/// Finished jobs kept for re-attaching; beyond this the oldest are forgotten.
+pub const MAX_FINISHED_JOBS: usize = 32;
+/// Set on `/jobs/{id}/stream` responses: output bytes dropped from the start of the buffer.
+pub const JOB_DROPPED_BYTES_HEADER: &str = "x-job-dropped-bytes";
+const JOB_KILLED: &str = "Job killed";
+
+/// Response to `POST /jobs`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStarted {
+    pub job_id: String,
+}
+
+/// Response to `DELETE /jobs/{id}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobDeleted {
+    pub job_id: String,
+    /// Whether the job was still running and had to be killed.
+    pub killed: bool,
+}
+
+/// Jobs by id, shared by the `/jobs` handlers.
+#[derive(Debug, Default)]
+pub struct JobRegistry {
+    table: Mutex<JobTable>,
+}
+
+#[derive(Debug, Default)]
+struct JobTable {
+    next_id: u64,
+    jobs: BTreeMap<u64, Arc<Job>>,
+}
+
+impl JobRegistry {
+    fn table(&self) -> MutexGuard<'_, JobTable> {
+        self.table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn insert(&self, job: Arc<Job>) -> String {
+        let mut table = self.table();
+        table.next_id += 1;
+        let id = table.next_id;
+        table.jobs.insert(id, job);
+
+        let finished: Vec<u64> = table
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.output().finished)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished.iter().take(finished.len().saturating_sub(MAX_FINISHED_JOBS)) {
+            table.jobs.remove(id);
+        }
+        format_job_id(id)
+    }
+
+    fn get(&self, job_id: &str) -> Option<Arc<Job>> {
+        let id = parse_job_id(job_id)?;
+        self.table().jobs.get(&id).cloned()
+    }
+
+    fn remove(&self, job_id: &str) -> Option<Arc<Job>> {
+        let id = parse_job_id(job_id)?;
+        self.table().jobs.remove(&id)
+    }
+
+    /// Kills the job if it is still running, keeping it for re-attaching. Returns whether it
+    /// had to be killed, or `None` for an unknown id.
+    pub(crate) async fn kill(&self, job_id: &str) -> Option<bool> {
+        let job = self.get(job_id)?;
+        Some(job.kill_and_wait().await)
+    }
+
+    /// Running and finished jobs.
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        let table = self.table();
+        let finished = table
+            .jobs
+            .values()
+            .filter(|job| job.output().finished)
+            .count();
+        (table.jobs.len() - finished, finished)
+    }
+}
+
+fn format_job_id(id: u64) -> String {
+    format!("job-{id}")
+}
+
+fn parse_job_id(job_id: &str) -> Option<u64> {
+    job_id.strip_prefix("job-")?.parse().ok()
+}
+
+#[derive(Debug)]
+struct Job {
+    output: Mutex<JobOutput>,
+    /// Bumped whenever an event is appended, so attached streams wake up.
+    updates: watch::Sender<u64>,
+    kill: Notify,
+}
+
+#[derive(Debug, Default)]
+struct JobOutput {
+    /// Output events with their decoded size, ending with the exit or error event.
+    events: VecDeque<(RawStreamEvent, usize)>,
+    /// Position of `events[0]` among all events the job produced.
+    first_index: u64,
+    buffered_bytes: usize,
+    dropped_bytes: u64,
+    finished: bool,
+}
+
+impl Job {
+    fn new() -> Self {
+        Self {
+            output: Mutex::new(JobOutput::default()),
+            updates: watch::Sender::new(0),
+            kill: Notify::new(),
+        }
+    }
+
+    fn output(&self) -> MutexGuard<'_, JobOutput> {
+        self.output.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn push_output(&self, stream: OutputStreamKind, data: &[u8]) {
+        let data_b64 = base64::engine::general_purpose::STANDARD.encode(data);
+        let event = match stream {
+            OutputStreamKind::Stdout => RawStreamEvent::Stdout { data_b64 },
+            OutputStreamKind::Stderr => RawStreamEvent::Stderr { data_b64 },
+            OutputStreamKind::Terminal => RawStreamEvent::Terminal { data_b64 },
+        };
+        let mut output = self.output();
+        output.events.push_back((event, data.len()));
+        output.buffered_bytes += data.len();
+        while output.buffered_bytes > MAX_JOB_BUFFER_BYTES && output.events.len() > 1 {
+            let Some((_, size)) = output.events.pop_front() else {
+                break;
+            };
+            output.buffered_bytes -= size;
+            output.dropped_bytes += size as u64;
+            output.first_index += 1;
+        }
+        drop(output);
+        self.updates.send_modify(|version| *version += 1);
+    }
+
+    fn finish(&self, event: RawStreamEvent) {
+        let mut output = self.output();
+        output.events.push_back((event, 0));
+        output.finished = true;
+        drop(output);
+        self.updates.send_modify(|version| *version += 1);
+    }
+
+    /// Kills the job and waits for its final event; `false` if it had already finished.
+    async fn kill_and_wait(&self) -> bool {
+        if self.output().finished {
+            return false;
+        }
+        self.kill.notify_one();
+        self.wait_finished().await;
+        true
+    }
+
+    async fn wait_finished(&self) {
+        let mut updates = self.updates.subscribe();
+        while !self.output().finished {
+            if updates.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+pub async fn start_job_handler(
+    State(state): State<RawEndpointState>,
+    headers: HeaderMap,
+    payload: Result<Json<RunNetworkToolInput>, JsonRejection>,
+) -> Response {
+    let input = match payload {
+        Ok(Json(input)) => input,
+        Err(error) => {
+            tracing::warn!(error = %error, "job request rejected before validation");
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid request payload: {error}"),
+            );
+        }
+    };
+    let context = match context_from_headers(&headers) {
+        Ok(context) => context,
+        Err(error) => {
+            tracing::warn!(error = %error, "job request rejected before validation");
+            return error_response(StatusCode::BAD_REQUEST, error.to_string());
+        }
+    };
+    let client_id = context.client_id.clone().unwrap_or_default();
+    let executable = input.executable.clone();
+    let args_for_log = redact_env_values(
+        &input.args,
+        input.env.as_ref().unwrap_or(&BTreeMap::new()),
+        |_| true,
+    );
+    let timeout_ms = input.timeout_ms;
+
+    let Some(execution_permit) = state.executions.try_acquire() else {
+        let limit = state.executions.limits().max_active.unwrap_or_default();
+        tracing::warn!(command = %executable, args = ?args_for_log, limit, "job request rejected: concurrency limit reached");
+        return limit_response(
+            ToolError::ConcurrencyLimit { limit }.to_string(),
+            state.executions.retry_guidance(),
+        );
+    };
+
+    let (mut child, audit) = match spawn_audited_process(
+        &state.policy_engine,
+        &state.default_cwd,
+        input,
+        &context,
+        AuditSource::Job,
+    ) {
+        Ok(spawned) => spawned,
+        Err(ToolError::Validation(error)) => {
+            tracing::warn!(command = %executable, args = ?args_for_log, client_id, error = %error, "job request denied by policy");
+            let message = state.policy_engine.denial_message(&error);
+            return error_response(StatusCode::FORBIDDEN, message);
+        }
+        Err(error @ (ToolError::InvalidStdin { .. } | ToolError::PtyModeUnsupported)) => {
+            tracing::warn!(command = %executable, args = ?args_for_log, error = %error, "job request rejected before validation");
+            return error_response(StatusCode::BAD_REQUEST, error.to_string());
+        }
+        Err(error) => {
+            tracing::error!(command = %executable, args = ?args_for_log, error = %error, "job request failed before start");
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
+    };
+    let args_for_log = audit.args.clone();
+
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        terminate_child(&mut child).await;
+        audit.fail(&"output pipes missing");
+        tracing::error!(command = %executable, args = ?args_for_log, "output pipes missing");
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "output pipes missing".to_string(),
+        );
+    };
+
+    let job = Arc::new(Job::new());
+    let job_id = state.jobs.insert(job.clone());
+    tracing::info!(job_id, command = %executable, args = ?args_for_log, client_id, "job started");
+
+    let runner = JobRunner {
+        job,
+        job_id: job_id.clone(),
+        executable,
+        args: args_for_log,
+        timeout_ms,
+    };
+    tokio::spawn(
+        async move {
+            // The execution slot is held until the child is reaped, not just until the request
+            // ends.
+            let _permit: StreamPermit = execution_permit;
+            runner.run(child, stdout, stderr, audit).await;
+        }
+        .in_current_span(),
+    );
+
+    (StatusCode::CREATED, Json(JobStarted { job_id })).into_response()
+}
+
+/// Drives one job's child to completion, buffering its output into the job.
+struct JobRunner {
+    job: Arc<Job>,
+    job_id: String,
+    executable: String,
+    args: Vec<String>,
+    timeout_ms: Option<u64>,
+}
+
+impl JobRunner {
+    async fn run(self, child: Child, stdout: ChildStdout, stderr: ChildStderr, audit: AuditRecord) {
+        let started = Instant::now();
+        match self.collect_output(child, stdout, stderr).await {
+            Ok((exit_code, resource_usage)) => {
+                self.job.finish(RawStreamEvent::Exit {
+                    exit_code,
+                    duration_ms: Some(started.elapsed().as_millis() as u64),
+                    resource_usage,
+                });
+                tracing::info!(
+                    job_id = self.job_id,
+                    command = %self.executable,
+                    args = ?self.args,
+                    exit_code = ?exit_code,
+                    duration_ms = started.elapsed().as_millis() as u64,
+                    "job finished",
+                );
+                audit.complete(exit_code, resource_usage, false, false);
+            }
+            Err(message) => {
+                self.job.finish(RawStreamEvent::Error {
+                    message: message.clone(),
+                });
+                tracing::warn!(job_id = self.job_id, command = %self.executable, args = ?self.args, error = %message, "job ended early");
+                audit.fail(&message);
+            }
+        }
+    }
+
+    async fn collect_output(
+        &self,
+        mut child: Child,
+        stdout: ChildStdout,
+        stderr: ChildStderr,
+    ) -> Result<(Option<i32>, Option<ResourceUsage>), String> {
+        let (reader_tx, mut reader_rx) = mpsc::channel::<ReaderEvent>(64);
+        tokio::spawn(
+            read_output_stream(stdout, OutputStreamKind::Stdout, reader_tx.clone())
+                .in_current_span(),
+        );
+        tokio::spawn(
+            read_output_stream(stderr, OutputStreamKind::Stderr, reader_tx).in_current_span(),
+        );
+
+        let mut streams_done = false;
+        let mut exit_code: Option<Option<i32>> = None;
+        let mut exit_watch = spawn_exit_watch(&child);
+        let mut exit_watched = false;
+        let mut resource_usage = None;
+
+        let deadline = async {
+            match self.timeout_ms {
+                Some(timeout_ms) => tokio::time::sleep(Duration::from_millis(timeout_ms)).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(deadline);
+
+        loop {
+            tokio::select! {
+                _ = &mut deadline => {
+                    terminate_child(&mut child).await;
+                    let timeout_ms = self.timeout_ms.unwrap_or_default();
+                    return Err(ToolError::Timeout { timeout_ms }.to_string());
+                }
+                _ = self.job.kill.notified() => {
+                    terminate_child(&mut child).await;
+                    return Err(JOB_KILLED.to_string());
+                }
+                usage = &mut exit_watch, if !exit_watched => {
+                    exit_watched = true;
+                    resource_usage = usage.ok().flatten();
+                }
+                status = child.wait(), if exit_watched && exit_code.is_none() => {
+                    let status = status.map_err(|error| format!("Runtime wait failure: {error}"))?;
+                    exit_code = Some(status.code());
+                    record_child_exit(resource_usage);
+                }
+                maybe_event = reader_rx.recv(), if !streams_done => match maybe_event {
+                    Some(ReaderEvent::Chunk { stream, data }) => self.job.push_output(stream, &data),
+                    Some(ReaderEvent::Done) => {}
+                    Some(ReaderEvent::ReadError { stream, message }) => {
+                        terminate_child(&mut child).await;
+                        return Err(format!("Failed reading {}: {}", stream.as_str(), message));
+                    }
+                    // Both readers have finished and dropped their senders.
+                    None => streams_done = true,
+                },
+            }
+
+            if let Some(exit_code) = exit_code
+                && streams_done
+            {
+                return Ok((exit_code, resource_usage));
+            }
+        }
+    }
+}
+
+/// Streams a job as `/raw` NDJSON events: `start`, the buffered output, then live output until
+/// the job's `exit` or `error` event. Disconnecting leaves the job running.
+pub async fn job_stream_handler(
+    State(state): State<RawEndpointState>,
+    Path(job_id): Path<String>,
+) -> Response {
+    let Some(job) = state.jobs.get(&job_id) else {
+        return error_response(StatusCode::NOT_FOUND, format!("Unknown job: {job_id}"));
+    };
+    let dropped_bytes = job.output().dropped_bytes;
+
+    let (tx, rx) = mpsc::channel::<Bytes>(64);
+    let start = RawStreamEvent::Start {
+        execution_id: None,
+        request_id: current_request_id(),
+    };
+    tokio::spawn(async move {
+        if !send_event(&tx, &start).await {
+            return;
+        }
+        let mut updates = job.updates.subscribe();
+        let mut next_index = 0u64;
+        loop {
+            updates.borrow_and_update();
+            let (pending, finished) = {
+                let output = job.output();
+                next_index = next_index.max(output.first_index);
+                let skip = (next_index - output.first_index) as usize;
+                let pending: Vec<RawStreamEvent> = output
+                    .events
+                    .iter()
+                    .skip(skip)
+                    .map(|(event, _)| event.clone())
+                    .collect();
+                (pending, output.finished)
+            };
+            next_index += pending.len() as u64;
+            for event in &pending {
+                if !send_event(&tx, event).await {
+                    return;
+                }
+            }
+            if finished {
+                return;
+            }
+            tokio::select! {
+                changed = updates.changed() => if changed.is_err() {
+                    return;
+                },
+                _ = tx.closed() => return,
+            }
+        }
+    });
+
+    let body_stream = ReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    let mut response = Response::new(Body::from_stream(body_stream));
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    headers.insert(JOB_DROPPED_BYTES_HEADER, HeaderValue::from(dropped_bytes));
+    response
+}
+
+/// Kills the job if it is still running and forgets it.
+pub async fn delete_job_handler(
+    State(state): State<RawEndpointState>,
+    Path(job_id): Path<String>,
+) -> Response {
+    let Some(job) = state.jobs.remove(&job_id) else {
+        return error_response(StatusCode::NOT_FOUND, format!("Unknown job: {job_id}"));
+    };
+    let killed = job.kill_and_wait().await;
+    if killed {
+        tracing::info!(job_id, "job killed");
+    }
+    Json(JobDeleted { job_id, killed }).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{find_executable, start_sh_server};
+
+    async fn start_job(base: &str, sh_path: &str, script: &str) -> String {
+        let response = reqwest::Client::new()
+            .post(format!("{base}/jobs"))
+            .json(&RunNetworkToolInput {
+                executable: sh_path.to_string(),
+                args: vec!["-c".to_string(), script.to_string()],
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("start job");
+        assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+        response.json::<JobStarted>().await.expect("job id").job_id
+    }
+
+    fn stdout_of(events: &[RawStreamEvent]) -> String {
+        let mut stdout = Vec::new();
+        for event in events {
+            if let RawStreamEvent::Stdout { data_b64 } = event {
+                stdout.extend(
+                    base64::engine::general_purpose::STANDARD
+                        .decode(data_b64)
+                        .expect("decode stdout"),
+                );
+            }
+        }
+        String::from_utf8(stdout).expect("utf8 stdout")
+    }
+
+    async fn read_events(response: reqwest::Response) -> Vec<RawStreamEvent> {
+        response
+            .text()
+            .await
+            .expect("stream text")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid event"))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn job_survives_disconnect_and_replays_output_on_reattach() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let (base, server_task) = start_sh_server(&sh_path).await;
+        let job_id = start_job(&base, &sh_path, "echo first; sleep 0.3; echo second").await;
+
+        // Attach and hang up after the first event; the job must keep running.
+        let mut first = reqwest::get(format!("{base}/jobs/{job_id}/stream"))
+            .await
+            .expect("attach");
+        assert_eq!(first.status(), reqwest::StatusCode::OK);
+        assert_eq!(first.headers()[JOB_DROPPED_BYTES_HEADER], "0");
+        first.chunk().await.expect("first chunk");
+        drop(first);
+
+        let events = read_events(
+            reqwest::get(format!("{base}/jobs/{job_id}/stream"))
+                .await
+                .expect("reattach"),
+        )
+        .await;
+        assert!(matches!(
+            events.first(),
+            Some(RawStreamEvent::Start { execution_id: None, request_id: Some(_) })
+        ));
+        assert_eq!(stdout_of(&events), "first\nsecond\n");
+        assert!(matches!(
+            events.last(),
+            Some(RawStreamEvent::Exit {
+                exit_code: Some(0),
+                duration_ms: Some(_),
+                ..
+            })
+        ));
+
+        // A finished job can still be read, and deleting it does not need a kill.
+        let deleted = reqwest::Client::new()
+            .delete(format!("{base}/jobs/{job_id}"))
+            .send()
+            .await
+            .expect("delete")
+            .json::<JobDeleted>()
+            .await
+            .expect("delete body");
+        assert!(!deleted.killed);
+        let missing = reqwest::get(format!("{base}/jobs/{job_id}/stream"))
+            .await
+            .expect("stream after delete");
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn delete_kills_a_running_job() {
+        let Some(sh_path) = find_executable("sh") else {
+            return;
+        };
+        let (base, server_task) = start_sh_server(&sh_path).await;
+        let job_id = start_job(&base, &sh_path, "echo ready; sleep 30").await;
+        let attached = reqwest::get(format!("{base}/jobs/{job_id}/stream"))
+            .await
+            .expect("attach");
+
+        let started = Instant::now();
+        let deleted = reqwest::Client::new()
+            .delete(format!("{base}/jobs/{job_id}"))
+            .send()
+            .await
+            .expect("delete")
+            .json::<JobDeleted>()
+            .await
+            .expect("delete body");
+        assert!(deleted.killed);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // The attached stream ends with the kill rather than hanging.
+        let events = read_events(attached).await;
+        assert_eq!(
+            events.last(),
+            Some(&RawStreamEvent::Error {
+                message: JOB_KILLED.to_string()
+            })
+        );
+
+        server_task.abort();
+    }
+
+    #[test]
+    fn buffer_drops_oldest_output_beyond_the_cap() {
+        let job = Job::new();
+        let chunk = vec![b'x'; MAX_JOB_BUFFER_BYTES / 2 + 1];
+        job.push_output(OutputStreamKind::Stdout, &chunk);
+        job.push_output(OutputStreamKind::Stdout, &chunk);
+        let output = job.output();
+        assert_eq!(output.events.len(), 1);
+        assert_eq!(output.first_index, 1);
+        assert_eq!(output.dropped_bytes, chunk.len() as u64);
+    }
+
+    #[test]
+    fn registry_forgets_the_oldest_finished_jobs() {
+        let registry = JobRegistry::default();
+        let ids: Vec<String> = (0..MAX_FINISHED_JOBS + 2)
+            .map(|_| {
+                let job = Arc::new(Job::new());
+                job.finish(RawStreamEvent::Exit {
+                    exit_code: Some(0),
+                    duration_ms: None,
+                    resource_usage: None,
+                });
+                registry.insert(job)
+            })
+            .collect();
+        assert!(registry.get(&ids[0]).is_none());
+        assert!(registry.get(&ids[1]).is_none());
+        assert!(registry.get(&ids[2]).is_some());
+        assert!(registry.get("nonsense").is_none());
+    }
+}
@@ -1,17 +1,137 @@
+mod admin;
+mod audit;
+mod auth;
+mod body_limit;
+mod client_id;
+pub mod compat;
+mod counters;
+mod descriptors;
+mod egress;
+mod executions;
 mod executor;
+mod fetch;
+mod grants;
+mod hashing;
+mod janitor;
+mod jobs;
+mod limiter;
 mod mcp;
+mod metrics;
+mod migration;
 mod policy;
+mod policy_check;
+mod policy_diff;
+mod policy_test;
+mod pty;
 mod raw;
+mod redaction;
 mod remote;
+mod request_id;
+mod runtime;
+mod self_invocation;
+mod shutdown;
+mod signing;
+mod spill;
+#[cfg(test)]
+mod test_support;
+mod validity;
+mod version;
 
+#[allow(deprecated)]
+pub use compat::TRUNCATION_MARKER;
+pub use admin::{
+    AdminError, AdminKilled, AdminStatus, DEFAULT_HISTORY_LIMIT, GrantRevoked, PolicyReloaded,
+    admin_create_grant_handler, admin_from_args, admin_history_handler, admin_kill_handler,
+    admin_list_grants_handler, admin_reload_handler, admin_revoke_grant_handler,
+    admin_status_handler, audit_stats_handler,
+};
+pub use audit::{
+    AUDIT_SCHEMA_VERSION, AuditDecision, AuditFileStats, AuditLog, AuditRecord, AuditRotation,
+    AuditSource, AuditStats, DEFAULT_AUDIT_KEEP, DecisionCounts, MAX_AUDIT_HISTORY,
+    install_audit_log,
+};
+pub use auth::{
+    ADMIN_TOKEN_ENV, ADMIN_TOKEN_FILE_ENV, AUTH_TOKEN_ENV, AUTH_TOKEN_FILE_ENV, AuthToken,
+    AuthTokenError, admin_token_from_env, auth_token_from_env, require_bearer_token,
+};
+pub use body_limit::{DEFAULT_MAX_BODY_BYTES, DEFAULT_MAX_JSON_DEPTH, RequestLimits};
+pub use client_id::{CLIENT_ID_HEADER, InvalidClientId, context_from_headers};
+pub use counters::{
+    COUNTERS_FILE_ENV, COUNTERS_FLUSH_ENV, CountersError, DEFAULT_COUNTERS_FLUSH_INTERVAL,
+};
+pub use descriptors::{descriptor_self_check, inheritable_descriptors};
+pub use executions::{
+    DEFAULT_EXECUTION_WAIT, ExecutionRegistry, ExecutionStarted, ExecutionStatus,
+    MAX_EXECUTION_WAIT, MAX_FINISHED_EXECUTIONS, start_run_handler, wait_execution_handler,
+};
 pub use executor::{
-    MAX_OUTPUT_BYTES, RunNetworkToolInput, RunNetworkToolOutput, TRUNCATION_MARKER, ToolError,
-    run_network_tool_impl, spawn_network_tool_process,
+    MAX_OUTPUT_BYTES, OutputLimits, ResourceUsage, RunNetworkToolInput, RunNetworkToolOutput,
+    STDERR_TRUNCATION_MARKER, STDOUT_TRUNCATION_MARKER, ToolError, Truncation,
+    run_network_tool_impl, run_network_tool_in_context, run_network_tool_with_limits,
+    run_network_tool_with_spills, spawn_network_tool_process,
+    spawn_network_tool_process_in_context,
+};
+pub use fetch::{
+    FETCH_TIMEOUT, FetchError, FetchVerifiedInput, FetchVerifiedOutput, MAX_FETCH_BYTES,
+    fetch_verified,
+};
+pub use grants::{
+    DEFAULT_GRANT_TTL, Grant, GrantError, GrantRegistry, GrantRequest, MAX_ACTIVE_GRANTS,
+    MAX_GRANT_TTL,
+};
+pub use hashing::{FileHasher, FsHasher, HashAlgorithm, hash_file_hex};
+pub use janitor::{CLEANUP_INTERVAL_ENV, DEFAULT_CLEANUP_INTERVAL};
+pub use jobs::{
+    JOB_DROPPED_BYTES_HEADER, JobDeleted, JobRegistry, JobStarted, MAX_FINISHED_JOBS,
+    MAX_JOB_BUFFER_BYTES, delete_job_handler, job_stream_handler, start_job_handler,
+};
+pub use limiter::{
+    MAX_RETRY_AFTER_MS, MIN_RETRY_AFTER_MS, RawStreamLimits, RetryGuidance, StreamLimiter,
+    StreamLimiterSnapshot, StreamPermit,
 };
 pub use mcp::{
-    AppConfig, AppError, ConfigError, DEFAULT_BIND_ADDR, NetworkMcpServer, build_app, serve,
-    tool_error_result,
+    ADMIN_BIND_ADDR_ENV, AppConfig, AppError, ConfigError, DEFAULT_ADMIN_BIND_ADDR,
+    DEFAULT_BIND_ADDR, NetworkMcpServer, ServerOptions, build_app, build_app_with_options,
+    build_apps, serve, tool_error_result,
 };
-pub use policy::{PolicyEngine, PolicyMode, ValidationError};
-pub use raw::{RawEndpointState, RawErrorBody, RawStreamEvent, raw_handler};
+pub use metrics::metrics_handler;
+pub use migration::{
+    LegacyPolicy, MigrationError, MigrationReport, migrate_from_args, migrate_legacy_policy,
+    scan_legacy_policy,
+};
+pub use policy::{
+    ArgLimits, CleanupRule, CwdPattern, DenialDetail, EnvValueConstraint, InvocationContext,
+    OutputLimitOverrides, PolicyDecision, PolicyEngine, PolicyMode, PolicyPackageSummary, PolicySummary, ValidationError,
+};
+pub use policy_check::{PolicyCheckResponse, policy_check_handler};
+pub use policy_diff::{
+    PolicyDiffChange, PolicyDiffError, PolicyDiffReport, diff_policies, policy_diff_from_args,
+};
+pub use policy_test::{
+    POLICY_TEST_SUFFIX, PolicyTestError, PolicyTestReport, PolicyTestResult,
+    policy_test_from_args, run_policy_tests,
+};
+pub use pty::TerminalSize;
+pub use raw::{
+    RAW_KILL_GRACE, RAW_PROTOCOL_VERSION, RawCommandRegistry, RawEndpointState, RawErrorBody,
+    RawStdinFrame, RawStreamEvent, raw_handler, raw_kill_handler,
+};
+pub use redaction::{REDACTED, RedactPatterns};
 pub use remote::{LOCAL_FAILURE_EXIT_CODE, RemoteClientError, run_remote_from_env};
+pub use request_id::{REQUEST_ID_HEADER, RequestId, request_id_from_headers};
+pub use runtime::{MAX_BLOCKING_THREADS_ENV, RuntimeConfig, WORKER_THREADS_ENV};
+pub use self_invocation::{
+    ALLOW_SELF_INVOCATION_ENV, DEFAULT_MAX_HOPS, HOPS_ENV, HOPS_HEADER, InvalidHopCount,
+    MAX_HOPS_ENV, OwnBinaries, hops_from_headers,
+};
+pub use shutdown::{DEFAULT_SHUTDOWN_TIMEOUT, SHUTDOWN_TIMEOUT_ENV, shutdown_signal};
+pub use signing::{
+    BUNDLE_SIGNATURE_FILE, POLICY_SIGNING_KEY_ENV, PolicySigningKey, bundle_manifest,
+    policy_manifest_from_args,
+};
+pub use spill::{
+    DEFAULT_CHUNK_BYTES, GetOutputChunkInput, MAX_SPILL_BYTES, MAX_SPILLED_STREAMS, OutputChunk,
+    OutputChunkQuery, OutputSpills, SPILL_DIR_ENV, SpillError, output_chunk_handler,
+};
+pub use validity::{Clock, Schedule, SystemClock, Validity};
+pub use version::{GIT_COMMIT, ProtocolVersions, VersionInfo, enabled_features, version_handler};
@@ -0,0 +1,300 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Shortest back-off suggested to a rejected client.
+pub const MIN_RETRY_AFTER_MS: u64 = 100;
+/// Longest back-off suggested to a rejected client.
+pub const MAX_RETRY_AFTER_MS: u64 = 60_000;
+
+/// Admission limits for concurrent `/raw` streams.
+///
+/// Also used for the server-wide execution limit, with `max_queued` set to zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawStreamLimits {
+    /// Maximum number of streams running at once; `None` is unlimited.
+    pub max_active: Option<usize>,
+    /// Requests allowed to wait for a free slot once `max_active` is reached.
+    /// Requests beyond this are rejected.
+    pub max_queued: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamLimiterSnapshot {
+    pub active: usize,
+    pub queued: usize,
+    pub rejected_total: u64,
+}
+
+/// Back-off hint sent with a limit rejection, so clients wait instead of retrying at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryGuidance {
+    /// Suggested wait before retrying, from recent slot hold times and the queue ahead.
+    pub retry_after_ms: u64,
+    /// Slots in use when the request was rejected.
+    pub active: usize,
+    /// Requests already waiting for a slot.
+    pub queue_length: usize,
+    /// Maximum slots; `0` when unlimited.
+    pub limit: usize,
+}
+
+#[derive(Debug)]
+pub struct StreamLimiter {
+    limits: RawStreamLimits,
+    semaphore: Option<Arc<Semaphore>>,
+    active: AtomicUsize,
+    queued: AtomicUsize,
+    rejected_total: AtomicU64,
+    /// Moving average of how long permits are held, in milliseconds; `0` until one is released.
+    mean_hold_ms: AtomicU64,
+}
+
+/// Holds one stream slot; the slot is released (and the active gauge decremented) on drop.
+#[derive(Debug)]
+pub struct StreamPermit {
+    limiter: Arc<StreamLimiter>,
+    acquired: Instant,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for StreamPermit {
+    fn drop(&mut self) {
+        self.limiter.active.fetch_sub(1, Ordering::SeqCst);
+        let held_ms = u64::try_from(self.acquired.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let _ = self
+            .limiter
+            .mean_hold_ms
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |mean| {
+                Some(if mean == 0 {
+                    held_ms.max(1)
+                } else {
+                    // Weight 1/8 keeps one unusually long command from dominating the hint.
+                    (mean - mean / 8).saturating_add(held_ms / 8).max(1)
+                })
+            });
+    }
+}
+
+/// Tracks a waiting request so the queued gauge stays correct if the client disconnects.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl StreamLimiter {
+    pub fn new(limits: RawStreamLimits) -> Self {
+        Self {
+            limits,
+            semaphore: limits
+                .max_active
+                .map(|max_active| Arc::new(Semaphore::new(max_active))),
+            active: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            rejected_total: AtomicU64::new(0),
+            mean_hold_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// A limiter that admits at most `max_active` holders and rejects instead of queueing.
+    pub fn rejecting(max_active: Option<usize>) -> Self {
+        Self::new(RawStreamLimits {
+            max_active,
+            max_queued: 0,
+        })
+    }
+
+    pub fn limits(&self) -> RawStreamLimits {
+        self.limits
+    }
+
+    /// Takes a free slot without waiting, or returns `None` (counted as rejected) when full.
+    pub fn try_acquire(self: &Arc<Self>) -> Option<StreamPermit> {
+        let Some(semaphore) = self.semaphore.clone() else {
+            self.active.fetch_add(1, Ordering::SeqCst);
+            return Some(self.permit(None));
+        };
+        match semaphore.try_acquire_owned() {
+            Ok(permit) => {
+                self.active.fetch_add(1, Ordering::SeqCst);
+                Some(self.permit(Some(permit)))
+            }
+            Err(_) => {
+                self.rejected_total.fetch_add(1, Ordering::SeqCst);
+                None
+            }
+        }
+    }
+
+    /// Waits for a free slot, or returns `None` when the queue is already full.
+    pub async fn acquire(self: &Arc<Self>) -> Option<StreamPermit> {
+        let Some(semaphore) = self.semaphore.clone() else {
+            self.active.fetch_add(1, Ordering::SeqCst);
+            return Some(self.permit(None));
+        };
+
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            self.active.fetch_add(1, Ordering::SeqCst);
+            return Some(self.permit(Some(permit)));
+        }
+
+        let reserved = self
+            .queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < self.limits.max_queued).then_some(queued + 1)
+            });
+        if reserved.is_err() {
+            self.rejected_total.fetch_add(1, Ordering::SeqCst);
+            return None;
+        }
+
+        let permit = {
+            let _queued = QueuedGuard(&self.queued);
+            // The semaphore is never closed, so acquiring only fails if that invariant breaks.
+            semaphore.acquire_owned().await.ok()?
+        };
+        self.active.fetch_add(1, Ordering::SeqCst);
+        Some(self.permit(Some(permit)))
+    }
+
+    pub fn snapshot(&self) -> StreamLimiterSnapshot {
+        StreamLimiterSnapshot {
+            active: self.active.load(Ordering::SeqCst),
+            queued: self.queued.load(Ordering::SeqCst),
+            rejected_total: self.rejected_total.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Suggests waiting long enough for every queued request, plus this one, to get a slot.
+    pub fn retry_guidance(&self) -> RetryGuidance {
+        let snapshot = self.snapshot();
+        let limit = self.limits.max_active.unwrap_or_default();
+        let per_slot_ms = self.mean_hold_ms.load(Ordering::SeqCst);
+        let waiting = u64::try_from(snapshot.queued + 1).unwrap_or(u64::MAX);
+        let slots = u64::try_from(limit.max(1)).unwrap_or(u64::MAX);
+        RetryGuidance {
+            retry_after_ms: (per_slot_ms.saturating_mul(waiting) / slots)
+                .clamp(MIN_RETRY_AFTER_MS, MAX_RETRY_AFTER_MS),
+            active: snapshot.active,
+            queue_length: snapshot.queued,
+            limit,
+        }
+    }
+
+    fn permit(self: &Arc<Self>, permit: Option<OwnedSemaphorePermit>) -> StreamPermit {
+        StreamPermit {
+            limiter: self.clone(),
+            acquired: Instant::now(),
+            _permit: permit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn queues_then_rejects_beyond_limits() {
+        let limiter = Arc::new(StreamLimiter::new(RawStreamLimits {
+            max_active: Some(1),
+            max_queued: 1,
+        }));
+
+        let first = limiter.acquire().await.expect("first slot");
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await.is_some() })
+        };
+        while limiter.snapshot().queued == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        assert!(limiter.acquire().await.is_none());
+        assert_eq!(
+            limiter.snapshot(),
+            StreamLimiterSnapshot {
+                active: 1,
+                queued: 1,
+                rejected_total: 1,
+            }
+        );
+
+        drop(first);
+        assert!(waiter.await.expect("waiter task"));
+        assert_eq!(limiter.snapshot().active, 0);
+        assert_eq!(limiter.snapshot().queued, 0);
+    }
+
+    #[test]
+    fn try_acquire_rejects_without_queueing() {
+        let limiter = Arc::new(StreamLimiter::rejecting(Some(2)));
+        let first = limiter.try_acquire().expect("first slot");
+        let _second = limiter.try_acquire().expect("second slot");
+        assert!(limiter.try_acquire().is_none());
+        assert_eq!(limiter.snapshot().rejected_total, 1);
+
+        drop(first);
+        assert!(limiter.try_acquire().is_some());
+        assert_eq!(limiter.snapshot().queued, 0);
+    }
+
+    #[tokio::test]
+    async fn retry_guidance_follows_hold_times_and_queue() {
+        let limiter = Arc::new(StreamLimiter::new(RawStreamLimits {
+            max_active: Some(1),
+            max_queued: 2,
+        }));
+        let first = limiter.acquire().await.expect("first slot");
+        assert_eq!(
+            limiter.retry_guidance(),
+            RetryGuidance {
+                retry_after_ms: MIN_RETRY_AFTER_MS,
+                active: 1,
+                queue_length: 0,
+                limit: 1,
+            }
+        );
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        drop(first);
+        let _second = limiter.acquire().await.expect("second slot");
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await.is_some() })
+        };
+        while limiter.snapshot().queued == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let guidance = limiter.retry_guidance();
+        assert_eq!(guidance.queue_length, 1);
+        assert!(
+            (600..MAX_RETRY_AFTER_MS).contains(&guidance.retry_after_ms),
+            "{guidance:?}"
+        );
+        waiter.abort();
+    }
+
+    #[tokio::test]
+    async fn abandoned_waiter_leaves_queue() {
+        let limiter = Arc::new(StreamLimiter::new(RawStreamLimits {
+            max_active: Some(1),
+            max_queued: 4,
+        }));
+        let _first = limiter.acquire().await.expect("first slot");
+
+        let result = tokio::time::timeout(Duration::from_millis(20), limiter.acquire()).await;
+        assert!(result.is_err());
+        assert_eq!(limiter.snapshot().queued, 0);
+    }
+}
@@ -1,7 +1,86 @@
-use mcp_run::{AppConfig, serve};
+use mcp_run::{
+    AppConfig, RuntimeConfig, admin_from_args, migrate_from_args, policy_diff_from_args,
+    policy_manifest_from_args, policy_test_from_args, serve,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    RuntimeConfig::from_env()?.build()?.block_on(run())
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--migrate") {
+        let report = migrate_from_args(&args[1..])?;
+        for path in &report.written {
+            println!("wrote {}", path.display());
+        }
+        for path in &report.skipped {
+            println!("kept existing {}", path.display());
+        }
+        for note in &report.unmigrated {
+            eprintln!("not migrated: {note}");
+        }
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("policy")
+        && args.get(1).map(String::as_str) == Some("manifest")
+    {
+        print!("{}", policy_manifest_from_args(&args[1..])?);
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("policy") {
+        let report = policy_test_from_args(&args[1..])?;
+        for result in &report.results {
+            let status = if result.passed { "PASS" } else { "FAIL" };
+            println!(
+                "{status} {}: {} ({})",
+                result.file.display(),
+                result.name,
+                result.outcome
+            );
+        }
+        let failed = report.failed();
+        println!("{} passed, {failed} failed", report.results.len() - failed);
+        if failed > 0 {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("diff-policy") {
+        let report = policy_diff_from_args(&args[1..])?;
+        for change in &report.changes {
+            let command = std::iter::once(&change.executable)
+                .chain(&change.args)
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(" ");
+            match (&change.old_denial, &change.new_denial) {
+                (Some(reason), None) => {
+                    println!("newly allowed line {}: {command} (was: {reason})", change.line)
+                }
+                (_, reason) => println!(
+                    "newly denied line {}: {command} ({})",
+                    change.line,
+                    reason.as_deref().unwrap_or_default()
+                ),
+            }
+        }
+        println!(
+            "{} evaluated, {} newly allowed, {} newly denied",
+            report.evaluated,
+            report.newly_allowed(),
+            report.newly_denied()
+        );
+        if !report.changes.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("admin") {
+        admin_from_args(&args[1..]).await?;
+        return Ok(());
+    }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt().with_target(true).init();
 
     let config = AppConfig::from_env()?;
@@ -1,28 +1,160 @@
 use std::net::{AddrParseError, SocketAddr};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::Router;
-use axum::routing::{any_service, post};
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::routing::{any_service, delete, get, post};
 use rmcp::handler::server::tool::ToolRouter;
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::model::{CallToolResult, Implementation, ServerCapabilities, ServerInfo};
+use rmcp::service::RequestContext;
 use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
 use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
-use rmcp::{Json, ServerHandler, tool, tool_handler, tool_router};
+use rmcp::{Json, RoleServer, ServerHandler, tool, tool_handler, tool_router};
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use tower_http::limit::RequestBodyLimitLayer;
+use tracing::Instrument;
 
-use crate::executor::{RunNetworkToolInput, RunNetworkToolOutput, run_network_tool_impl};
-use crate::policy::{PolicyEngine, PolicyMode};
-use crate::raw::{RawEndpointState, raw_handler};
+use crate::admin::{
+    admin_create_grant_handler, admin_history_handler, admin_kill_handler,
+    admin_list_grants_handler, admin_reload_handler, admin_revoke_grant_handler,
+    admin_status_handler, audit_stats_handler,
+};
+use crate::audit::{AuditRotation, DEFAULT_AUDIT_KEEP, install_audit_log};
+use crate::auth::{
+    ADMIN_TOKEN_ENV, AUTH_TOKEN_ENV, AuthToken, AuthTokenError, admin_token_from_env,
+    auth_token_from_env, require_bearer_token,
+};
+use crate::body_limit::{
+    DEFAULT_MAX_BODY_BYTES, DEFAULT_MAX_JSON_DEPTH, RequestLimits, limit_request_body,
+};
+use crate::client_id::context_from_headers;
+use crate::descriptors::descriptor_self_check;
+use crate::executions::{ExecutionRegistry, start_run_handler, wait_execution_handler};
+use crate::executor::{
+    MAX_OUTPUT_BYTES, OutputLimits, RunNetworkToolInput, RunNetworkToolOutput, ToolError,
+    Truncation, run_network_tool_with_spills,
+};
+use crate::egress::egress_domains_handler;
+use crate::fetch::{FetchError, FetchVerifiedInput, FetchVerifiedOutput, fetch_verified};
+use crate::jobs::{JobRegistry, delete_job_handler, job_stream_handler, start_job_handler};
+use crate::limiter::{RawStreamLimits, StreamLimiter};
+use crate::counters::{
+    COUNTERS_FILE_ENV, COUNTERS_FLUSH_ENV, CountersError, DEFAULT_COUNTERS_FLUSH_INTERVAL,
+    flush_counters_periodically, restore_counters_from,
+};
+use crate::janitor::{CLEANUP_INTERVAL_ENV, DEFAULT_CLEANUP_INTERVAL, run_janitor};
+use crate::metrics::metrics_handler;
+use crate::migration::{LegacyPolicy, scan_legacy_policy};
+use crate::policy::{
+    ArgLimits, DEFAULT_MAX_ARG_BYTES, DEFAULT_MAX_ARGS, DEFAULT_MAX_COMMAND_LINE_BYTES,
+    DenialDetail, InvocationContext, PolicyEngine, PolicyMode, PolicySummary,
+};
+use crate::policy_check::policy_check_handler;
+use crate::signing::{POLICY_SIGNING_KEY_ENV, PolicySigningKey};
+use crate::raw::{
+    RawCommandRegistry, RawEndpointState, RawErrorBody, raw_handler, raw_kill_handler,
+};
+use crate::request_id::{assign_request_id, mcp_request_id};
+use crate::self_invocation::{ALLOW_SELF_INVOCATION_ENV, DEFAULT_MAX_HOPS, MAX_HOPS_ENV};
+use crate::shutdown::{
+    CONNECTION_CLOSE_GRACE, DEFAULT_SHUTDOWN_TIMEOUT, InFlight, SHUTDOWN_TIMEOUT_ENV,
+    kill_child_process_groups, shutdown_signal,
+};
+use crate::spill::{
+    GetOutputChunkInput, OutputChunk, OutputSpills, SPILL_DIR_ENV, output_chunk_handler,
+};
+use crate::version::{GIT_COMMIT, version_handler};
 
 pub const DEFAULT_BIND_ADDR: &str = "127.0.0.1:8000";
+/// Where the `/admin` endpoints are served unless `MCP_ADMIN_BIND_ADDR` says otherwise.
+pub const DEFAULT_ADMIN_BIND_ADDR: &str = "127.0.0.1:8001";
+pub const ADMIN_BIND_ADDR_ENV: &str = "MCP_ADMIN_BIND_ADDR";
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub bind_addr: SocketAddr,
+    /// Loopback address of the separate listener the `/admin` endpoints are served on
+    /// (`MCP_ADMIN_BIND_ADDR`), never reachable through `bind_addr`.
+    pub admin_bind_addr: SocketAddr,
     pub policy_dir: Option<PathBuf>,
+    /// `POLICY_MODE`: [`PolicyMode::Rego`] (`rego`, the default) or [`PolicyMode::Both`]
+    /// (`both`), which also enforces `legacy_policy_file`.
+    pub policy_mode: PolicyMode,
+    /// Legacy JSON policy from older releases (`POLICY_FILE`). Loaded only in `both` mode;
+    /// otherwise reported at startup with migration instructions.
+    pub legacy_policy_file: Option<PathBuf>,
     pub default_cwd: PathBuf,
+    /// Directory `{workspace}` stands for in policy `cwd_pattern` rules (`MCP_WORKSPACE`,
+    /// default `default_cwd`).
+    pub workspace: PathBuf,
+    /// JSONL file every policy decision and command exit is appended to (`MCP_AUDIT_LOG`).
+    pub audit_log: Option<PathBuf>,
+    /// When `audit_log` is rotated (`MCP_AUDIT_MAX_BYTES`, `MCP_AUDIT_MAX_AGE_SECS`,
+    /// `MCP_AUDIT_KEEP`).
+    pub audit_rotation: AuditRotation,
+    /// File the `/metrics` execution counters are kept in across restarts
+    /// (`MCP_COUNTERS_FILE`).
+    pub counters_file: Option<PathBuf>,
+    /// How often the counters are saved to `counters_file` (`MCP_COUNTERS_FLUSH_SECS`).
+    pub counters_flush_interval: Duration,
+    /// How often stale paths matching the policy's `cleanup` rule are removed
+    /// (`MCP_CLEANUP_INTERVAL_SECS`, `0` disables); `None` never removes them.
+    pub cleanup_interval: Option<Duration>,
+    /// How long SIGTERM/SIGINT waits for in-flight commands before killing them
+    /// (`MCP_SHUTDOWN_TIMEOUT_SECS`).
+    pub shutdown_timeout: Duration,
+    /// Whether commands may run the server's own binaries (`MCP_ALLOW_SELF_INVOCATION`).
+    pub allow_self_invocation: bool,
+    /// How much of a denial clients are told (`POLICY_DENIAL_DETAIL`).
+    pub denial_detail: DenialDetail,
+    /// Key the policy directory's `bundle.sig` must verify with (`POLICY_SIGNING_KEY`).
+    pub policy_signing_key: Option<PolicySigningKey>,
+    /// Argument count and size limits every command is checked against (`MCP_MAX_ARGS`,
+    /// `MCP_MAX_ARG_BYTES`, `MCP_MAX_COMMAND_LINE_BYTES`; `0` disables one).
+    pub arg_limits: ArgLimits,
+    pub server_options: ServerOptions,
+}
+
+/// Server behaviour that is independent of the bind address and policy source.
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    pub raw_stream_limits: RawStreamLimits,
+    /// Commands allowed to run at once across the MCP tool and `/raw`; `None` is unlimited.
+    pub max_concurrent: Option<usize>,
+    /// Default capture limits for `run_network_tool`; `/raw` output is never truncated.
+    pub output_limits: OutputLimits,
+    /// Bearer token required on every route (`MCP_AUTH_TOKEN`/`MCP_AUTH_TOKEN_FILE`); `None`
+    /// leaves the server unauthenticated.
+    pub auth_token: Option<AuthToken>,
+    /// Bearer token the `/admin` listener requires instead of `auth_token`
+    /// (`MCP_ADMIN_TOKEN`/`MCP_ADMIN_TOKEN_FILE`). Allow grants can only be created with it.
+    pub admin_token: Option<AuthToken>,
+    /// Body size and JSON depth limits for `/raw` and `/mcp`.
+    pub request_limits: RequestLimits,
+    /// Servers a `/raw` request may already have passed through (`MCP_MAX_HOPS`).
+    pub max_hops: u32,
+    /// Directory `spillOutput` keeps truncated streams in (`MCP_SPILL_DIR`); the system
+    /// temporary directory when unset.
+    pub spill_dir: Option<PathBuf>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            raw_stream_limits: RawStreamLimits::default(),
+            max_concurrent: None,
+            output_limits: OutputLimits::default(),
+            auth_token: None,
+            admin_token: None,
+            request_limits: RequestLimits::default(),
+            max_hops: DEFAULT_MAX_HOPS,
+            spill_dir: None,
+        }
+    }
 }
 
 impl AppConfig {
@@ -35,22 +167,211 @@ impl AppConfig {
                     value: bind_raw,
                     source,
                 })?;
+        let admin_raw = std::env::var(ADMIN_BIND_ADDR_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| DEFAULT_ADMIN_BIND_ADDR.into());
+        let admin_bind_addr = match admin_raw.parse::<SocketAddr>() {
+            Ok(addr) if addr.ip().is_loopback() => addr,
+            Ok(_) => return Err(ConfigError::AdminBindNotLoopback(admin_raw)),
+            Err(source) => {
+                return Err(ConfigError::InvalidAdminBindAddr {
+                    value: admin_raw,
+                    source,
+                });
+            }
+        };
         let policy_dir = std::env::var("POLICY_DIR")
             .ok()
             .map(|value| value.trim().to_string())
             .filter(|value| !value.is_empty())
             .map(PathBuf::from);
+        let legacy_policy_file = std::env::var("POLICY_FILE")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+        let policy_mode = match std::env::var("POLICY_MODE")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .as_deref()
+        {
+            None | Some("rego") => PolicyMode::Rego,
+            Some("both") if legacy_policy_file.is_some() => PolicyMode::Both,
+            Some("both") => return Err(ConfigError::MissingLegacyPolicyFile),
+            Some(other) => return Err(ConfigError::InvalidPolicyMode(other.to_string())),
+        };
+        let denial_detail = match std::env::var("POLICY_DENIAL_DETAIL")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .as_deref()
+        {
+            None | Some("full") => DenialDetail::Full,
+            Some("summary") => DenialDetail::Summary,
+            Some("minimal") => DenialDetail::Minimal,
+            Some(other) => return Err(ConfigError::InvalidDenialDetail(other.to_string())),
+        };
+        let policy_signing_key = match std::env::var(POLICY_SIGNING_KEY_ENV)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        {
+            None => None,
+            Some(value) => {
+                Some(PolicySigningKey::parse(&value).ok_or(ConfigError::InvalidSigningKey)?)
+            }
+        };
         let default_cwd =
             std::env::current_dir().map_err(|source| ConfigError::CurrentDir { source })?;
+        let workspace = std::env::var("MCP_WORKSPACE")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map_or_else(|| default_cwd.clone(), PathBuf::from);
+        let audit_log = std::env::var("MCP_AUDIT_LOG")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+        let audit_rotation = AuditRotation {
+            max_bytes: parse_env_count("MCP_AUDIT_MAX_BYTES")?
+                .filter(|max| *max > 0)
+                .map(|max| u64::try_from(max).unwrap_or(u64::MAX)),
+            max_age: parse_env_count("MCP_AUDIT_MAX_AGE_SECS")?
+                .filter(|secs| *secs > 0)
+                .map(|secs| Duration::from_secs(u64::try_from(secs).unwrap_or(u64::MAX))),
+            keep: parse_env_count("MCP_AUDIT_KEEP")?.unwrap_or(DEFAULT_AUDIT_KEEP),
+        };
+        let counters_file = std::env::var(COUNTERS_FILE_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+        let counters_flush_interval = parse_env_count(COUNTERS_FLUSH_ENV)?
+            .filter(|secs| *secs > 0)
+            .map_or(DEFAULT_COUNTERS_FLUSH_INTERVAL, |secs| {
+                Duration::from_secs(u64::try_from(secs).unwrap_or(u64::MAX))
+            });
+        let cleanup_interval = match parse_env_count(CLEANUP_INTERVAL_ENV)? {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(u64::try_from(secs).unwrap_or(u64::MAX))),
+            None => Some(DEFAULT_CLEANUP_INTERVAL),
+        };
+        let raw_stream_limits = RawStreamLimits {
+            max_active: parse_env_count("MCP_RAW_MAX_STREAMS")?.filter(|max| *max > 0),
+            max_queued: parse_env_count("MCP_RAW_MAX_QUEUED")?.unwrap_or(0),
+        };
+        let max_concurrent = parse_env_count("MCP_MAX_CONCURRENT")?.filter(|max| *max > 0);
+        let max_output_bytes =
+            parse_env_count("MCP_MAX_OUTPUT_BYTES")?.unwrap_or(MAX_OUTPUT_BYTES);
+        let output_limits = OutputLimits {
+            stdout_bytes: parse_env_count("MCP_MAX_STDOUT_BYTES")?.unwrap_or(max_output_bytes),
+            stderr_bytes: parse_env_count("MCP_MAX_STDERR_BYTES")?.unwrap_or(max_output_bytes),
+            truncation: Truncation::default(),
+        };
+        let request_limits = RequestLimits {
+            max_body_bytes: parse_env_count("MCP_MAX_BODY_BYTES")?
+                .filter(|max| *max > 0)
+                .unwrap_or(DEFAULT_MAX_BODY_BYTES),
+            max_json_depth: parse_env_count("MCP_MAX_JSON_DEPTH")?
+                .filter(|max| *max > 0)
+                .unwrap_or(DEFAULT_MAX_JSON_DEPTH),
+        };
+        let auth_token = auth_token_from_env()?;
+        let admin_token = admin_token_from_env()?;
+        if let (Some(auth), Some(admin)) = (&auth_token, &admin_token)
+            && auth.matches(admin.as_str())
+        {
+            return Err(ConfigError::AdminTokenReused);
+        }
+        let shutdown_timeout = parse_env_count(SHUTDOWN_TIMEOUT_ENV)?
+            .map_or(DEFAULT_SHUTDOWN_TIMEOUT, |secs| {
+                Duration::from_secs(u64::try_from(secs).unwrap_or(u64::MAX))
+            });
+        let allow_self_invocation = match std::env::var(ALLOW_SELF_INVOCATION_ENV)
+            .unwrap_or_default()
+            .trim()
+        {
+            "" | "0" | "false" => false,
+            "1" | "true" => true,
+            other => {
+                return Err(ConfigError::InvalidFlag {
+                    name: ALLOW_SELF_INVOCATION_ENV,
+                    value: other.to_string(),
+                });
+            }
+        };
+        let spill_dir = std::env::var(SPILL_DIR_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+        let max_hops = parse_env_count(MAX_HOPS_ENV)?
+            .map_or(DEFAULT_MAX_HOPS, |hops| u32::try_from(hops).unwrap_or(u32::MAX));
+        let arg_limit = |name, default| -> Result<Option<usize>, ConfigError> {
+            Ok(match parse_env_count(name)? {
+                Some(0) => None,
+                Some(limit) => Some(limit),
+                None => Some(default),
+            })
+        };
+        let arg_limits = ArgLimits {
+            max_args: arg_limit("MCP_MAX_ARGS", DEFAULT_MAX_ARGS)?,
+            max_arg_bytes: arg_limit("MCP_MAX_ARG_BYTES", DEFAULT_MAX_ARG_BYTES)?,
+            max_command_line_bytes: arg_limit(
+                "MCP_MAX_COMMAND_LINE_BYTES",
+                DEFAULT_MAX_COMMAND_LINE_BYTES,
+            )?,
+        };
 
         Ok(Self {
             bind_addr,
+            admin_bind_addr,
             policy_dir,
+            policy_mode,
+            legacy_policy_file,
             default_cwd,
+            workspace,
+            audit_log,
+            audit_rotation,
+            counters_file,
+            counters_flush_interval,
+            cleanup_interval,
+            shutdown_timeout,
+            allow_self_invocation,
+            denial_detail,
+            policy_signing_key,
+            arg_limits,
+            server_options: ServerOptions {
+                raw_stream_limits,
+                max_concurrent,
+                output_limits,
+                auth_token,
+                admin_token,
+                request_limits,
+                max_hops,
+                spill_dir,
+            },
         })
     }
 }
 
+pub(crate) fn parse_env_count(name: &'static str) -> Result<Option<usize>, ConfigError> {
+    let Some(value) = std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    else {
+        return Ok(None);
+    };
+    value
+        .parse::<usize>()
+        .map(Some)
+        .map_err(|_| ConfigError::InvalidCount { name, value })
+}
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("invalid MCP_BIND_ADDR '{value}': {source}")]
@@ -58,14 +379,44 @@ pub enum ConfigError {
         value: String,
         source: AddrParseError,
     },
+    #[error("invalid {ADMIN_BIND_ADDR_ENV} '{value}': {source}")]
+    InvalidAdminBindAddr {
+        value: String,
+        source: AddrParseError,
+    },
+    #[error("{ADMIN_BIND_ADDR_ENV} '{0}' is not a loopback address")]
+    AdminBindNotLoopback(String),
+    #[error("invalid POLICY_MODE '{0}': expected 'rego' or 'both'")]
+    InvalidPolicyMode(String),
+    #[error("invalid POLICY_DENIAL_DETAIL '{0}': expected 'full', 'summary' or 'minimal'")]
+    InvalidDenialDetail(String),
+    #[error("invalid {POLICY_SIGNING_KEY_ENV}: expected a base64 or PEM ed25519 public key")]
+    InvalidSigningKey,
+    #[error("POLICY_MODE=both requires POLICY_FILE, the legacy JSON policy to enforce")]
+    MissingLegacyPolicyFile,
     #[error("failed to get current working directory: {source}")]
     CurrentDir { source: std::io::Error },
+    #[error("invalid {name} '{value}': expected a non-negative integer")]
+    InvalidCount { name: &'static str, value: String },
+    #[error("invalid {name} '{value}': expected 1, true, 0 or false")]
+    InvalidFlag { name: &'static str, value: String },
+    #[error("{ADMIN_TOKEN_ENV} must differ from {AUTH_TOKEN_ENV}")]
+    AdminTokenReused,
+    #[error(transparent)]
+    AuthToken(#[from] AuthTokenError),
 }
 
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error(transparent)]
     Config(#[from] ConfigError),
+    #[error("failed to open audit log '{}': {source}", path.display())]
+    AuditLog {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Counters(#[from] CountersError),
     #[error("server I/O failure: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -74,6 +425,9 @@ pub enum AppError {
 pub struct NetworkMcpServer {
     policy_engine: Arc<PolicyEngine>,
     default_cwd: PathBuf,
+    output_limits: OutputLimits,
+    executions: Arc<StreamLimiter>,
+    spills: Arc<OutputSpills>,
     tool_router: ToolRouter<Self>,
 }
 
@@ -83,10 +437,31 @@ impl NetworkMcpServer {
         Self {
             policy_engine,
             default_cwd,
+            output_limits: OutputLimits::default(),
+            executions: Arc::new(StreamLimiter::rejecting(None)),
+            spills: Arc::new(OutputSpills::default()),
             tool_router: Self::tool_router(),
         }
     }
 
+    /// Shares `executions` with other handlers so the concurrency limit covers all of them.
+    pub fn with_execution_limiter(mut self, executions: Arc<StreamLimiter>) -> Self {
+        self.executions = executions;
+        self
+    }
+
+    pub fn with_output_limits(mut self, output_limits: OutputLimits) -> Self {
+        self.output_limits = output_limits;
+        self
+    }
+
+    /// Shares `spills` with `GET /output/{token}`, so either can page through the other's
+    /// spilled output.
+    pub fn with_output_spills(mut self, spills: Arc<OutputSpills>) -> Self {
+        self.spills = spills;
+        self
+    }
+
     #[tool(
         name = "run_network_tool",
         description = "Execute a policy-allowlisted command without shell wrappers."
@@ -94,12 +469,104 @@ impl NetworkMcpServer {
     async fn run_network_tool(
         &self,
         Parameters(input): Parameters<RunNetworkToolInput>,
+        request_context: RequestContext<RoleServer>,
     ) -> Result<Json<RunNetworkToolOutput>, String> {
-        run_network_tool_impl(&self.policy_engine, &self.default_cwd, input)
+        let request_id = mcp_request_id(&request_context);
+        let call = async {
+            let context = invocation_context(&request_context)?;
+            let Some(_permit) = self.executions.try_acquire() else {
+                let limit = self.executions.limits().max_active.unwrap_or_default();
+                tracing::warn!(command = %input.executable, limit, "tool call rejected: concurrency limit reached");
+                // Same JSON shape as the HTTP 429 body, so agents can parse the back-off hint.
+                let body = RawErrorBody {
+                    error: ToolError::ConcurrencyLimit { limit }.to_string(),
+                    retry: Some(self.executions.retry_guidance()),
+                    request_id: Some(request_id.clone()),
+                };
+                return Err(serde_json::to_string(&body).unwrap_or(body.error));
+            };
+            run_network_tool_with_spills(
+                &self.policy_engine,
+                &self.default_cwd,
+                input,
+                self.output_limits,
+                &context,
+                &self.spills,
+            )
+            .await
+            .map(Json)
+            .map_err(|error| match error {
+                ToolError::Validation(error) => self.policy_engine.denial_message(&error),
+                error => error.to_string(),
+            })
+            .map_err(|error| with_request_id(error, &request_id))
+        };
+        call.instrument(tracing::info_span!("request", request_id = %request_id))
+            .await
+    }
+
+    #[tool(
+        name = "get_output_chunk",
+        description = "Read the part of a truncated run_network_tool stream that was left out, given the stdoutContinuation or stderrContinuation from a call with spillOutput set. Pass each chunk's nextToken to read on."
+    )]
+    async fn get_output_chunk(
+        &self,
+        Parameters(input): Parameters<GetOutputChunkInput>,
+    ) -> Result<Json<OutputChunk>, String> {
+        self.spills
+            .chunk(&input.continuation_token, input.max_bytes)
             .await
             .map(Json)
             .map_err(|error| error.to_string())
     }
+
+    #[tool(
+        name = "list_allowed_commands",
+        description = "List the commands the active policy routes to, with their descriptions. Call this before run_network_tool instead of guessing."
+    )]
+    async fn list_allowed_commands(&self) -> Result<Json<PolicySummary>, String> {
+        self.policy_engine.summary().map(Json)
+    }
+
+    #[tool(
+        name = "fetch_verified",
+        description = "Download a URL into the policy-approved directory and return the stored path, only if its SHA-256 matches."
+    )]
+    async fn fetch_verified(
+        &self,
+        Parameters(input): Parameters<FetchVerifiedInput>,
+        request_context: RequestContext<RoleServer>,
+    ) -> Result<Json<FetchVerifiedOutput>, String> {
+        let request_id = mcp_request_id(&request_context);
+        let call = async {
+            let context = invocation_context(&request_context)?;
+            fetch_verified(&self.policy_engine, input, &context)
+                .await
+                .map(Json)
+                .map_err(|error| match error {
+                    FetchError::Validation(error) => self.policy_engine.denial_message(&error),
+                    error => error.to_string(),
+                })
+                .map_err(|error| with_request_id(error, &request_id))
+        };
+        call.instrument(tracing::info_span!("request", request_id = %request_id))
+            .await
+    }
+}
+
+/// A tool error with the id its log lines carry, so a transcript can be matched to them.
+fn with_request_id(error: impl std::fmt::Display, request_id: &str) -> String {
+    format!("{error} (request id: {request_id})")
+}
+
+/// The HTTP transport attaches the request head; other transports have no client id.
+fn invocation_context(
+    request_context: &RequestContext<RoleServer>,
+) -> Result<InvocationContext, String> {
+    match request_context.extensions.get::<axum::http::request::Parts>() {
+        Some(parts) => context_from_headers(&parts.headers).map_err(|error| error.to_string()),
+        None => Ok(InvocationContext::default()),
+    }
 }
 
 #[tool_handler]
@@ -119,7 +586,7 @@ impl ServerHandler for NetworkMcpServer {
                 website_url: None,
             },
             instructions: Some(
-                "Use run_network_tool with executable/args/cwd/env. Requests are validated against POLICY_DIR Rego policy modules."
+                "Use run_network_tool with executable/args/cwd/env; set spillOutput to page through truncated output with get_output_chunk. Requests are validated against POLICY_DIR Rego policy modules; list_allowed_commands shows which commands they cover. fetch_verified downloads a pinned artifact."
                     .to_string(),
             ),
             ..Default::default()
@@ -128,48 +595,301 @@ impl ServerHandler for NetworkMcpServer {
 }
 
 pub fn build_app(policy_engine: Arc<PolicyEngine>, default_cwd: PathBuf) -> Router {
+    build_app_with_options(policy_engine, default_cwd, ServerOptions::default())
+}
+
+pub fn build_app_with_options(
+    policy_engine: Arc<PolicyEngine>,
+    default_cwd: PathBuf,
+    options: ServerOptions,
+) -> Router {
+    build_apps(policy_engine, default_cwd, options).0
+}
+
+/// The app and the `/admin` app, which share commands, jobs and runs but are served on
+/// separate listeners.
+pub fn build_apps(
+    policy_engine: Arc<PolicyEngine>,
+    default_cwd: PathBuf,
+    options: ServerOptions,
+) -> (Router, Router) {
+    let (app, admin, _) = build_router(policy_engine, default_cwd, options, CancellationToken::new());
+    (app, admin)
+}
+
+/// The app, the `/admin` app and the in-flight work `serve` drains on shutdown. Cancelling
+/// `mcp_sessions` ends every MCP session.
+fn build_router(
+    policy_engine: Arc<PolicyEngine>,
+    default_cwd: PathBuf,
+    options: ServerOptions,
+    mcp_sessions: CancellationToken,
+) -> (Router, Router, InFlight) {
     let session_manager = Arc::new(LocalSessionManager::default());
     let policy_for_factory = policy_engine.clone();
     let cwd_for_factory = default_cwd.clone();
+    let executions = Arc::new(StreamLimiter::rejecting(options.max_concurrent));
+    let executions_for_factory = executions.clone();
+    let spills = Arc::new(
+        options
+            .spill_dir
+            .clone()
+            .map_or_else(OutputSpills::default, OutputSpills::new),
+    );
+    let spills_for_factory = spills.clone();
     let raw_state = RawEndpointState {
         policy_engine,
         default_cwd,
+        raw_streams: Arc::new(StreamLimiter::new(options.raw_stream_limits)),
+        executions,
+        jobs: Arc::new(JobRegistry::default()),
+        runs: Arc::new(ExecutionRegistry::default()),
+        raw_commands: Arc::new(RawCommandRegistry::default()),
+        output_limits: options.output_limits,
+        spills,
+        request_limits: options.request_limits,
+        max_hops: options.max_hops,
+        grants_enabled: options.admin_token.is_some(),
+        auth_required: options.auth_token.is_some(),
+    };
+    let in_flight = InFlight {
+        executions: raw_state.executions.clone(),
+        raw_streams: raw_state.raw_streams.clone(),
     };
 
     let mcp_service = StreamableHttpService::new(
         move || {
-            Ok(NetworkMcpServer::new(
-                policy_for_factory.clone(),
-                cwd_for_factory.clone(),
-            ))
+            Ok(
+                NetworkMcpServer::new(policy_for_factory.clone(), cwd_for_factory.clone())
+                    .with_output_limits(options.output_limits)
+                    .with_execution_limiter(executions_for_factory.clone())
+                    .with_output_spills(spills_for_factory.clone()),
+            )
         },
         session_manager,
-        StreamableHttpServerConfig::default(),
+        StreamableHttpServerConfig {
+            cancellation_token: mcp_sessions,
+            ..Default::default()
+        },
     );
 
-    Router::new()
-        .route_service("/mcp", any_service(mcp_service))
-        .route("/raw", post(raw_handler))
-        .with_state(raw_state)
+    let limits = options.request_limits;
+    let router = Router::new()
+        .route_service(
+            "/mcp",
+            any_service(mcp_service)
+                .layer(RequestBodyLimitLayer::new(limits.max_body_bytes))
+                .layer(from_fn_with_state(limits, limit_request_body)),
+        )
+        // Not capped by `RequestBodyLimitLayer`: a streamed request bounds each line instead.
+        .route(
+            "/raw",
+            post(raw_handler).layer(from_fn_with_state(limits, limit_request_body)),
+        )
+        .route("/raw/{id}/kill", post(raw_kill_handler))
+        .route("/jobs", post(start_job_handler))
+        .route("/jobs/{id}", delete(delete_job_handler))
+        .route("/jobs/{id}/stream", get(job_stream_handler))
+        .route("/run", post(start_run_handler))
+        .route("/executions/{id}/wait", get(wait_execution_handler))
+        .route("/output/{token}", get(output_chunk_handler))
+        .route("/policy/check", post(policy_check_handler))
+        .route("/version", get(version_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/audit/stats", get(audit_stats_handler))
+        .with_state(raw_state.clone());
+    let admin = Router::new()
+        .route("/admin/status", get(admin_status_handler))
+        .route("/admin/reload", post(admin_reload_handler))
+        .route("/admin/history", get(admin_history_handler))
+        .route("/admin/kill/{id}", post(admin_kill_handler))
+        .route(
+            "/admin/grants",
+            get(admin_list_grants_handler).post(admin_create_grant_handler),
+        )
+        .route("/admin/grants/{id}", delete(admin_revoke_grant_handler))
+        .with_state(raw_state);
+
+    // A router-wide layer also covers unmatched paths and any route added later.
+    let router = match options.auth_token.clone() {
+        Some(token) => router.layer(from_fn_with_state(token, require_bearer_token)),
+        None => router,
+    };
+    // Outside the token check: the proxy polls it without a token, and it only lists domains
+    // that policies already opened for the sandbox.
+    let router = router.route("/egress/domains", get(egress_domains_handler));
+    let admin = match options.admin_token.or(options.auth_token) {
+        Some(token) => admin.layer(from_fn_with_state(token, require_bearer_token)),
+        None => admin,
+    };
+    // Outermost, so even a request refused for its token gets an id.
+    (
+        router.layer(from_fn(assign_request_id)),
+        admin.layer(from_fn(assign_request_id)),
+        in_flight,
+    )
 }
 
 pub async fn serve(config: AppConfig) -> Result<(), AppError> {
-    let policy_engine = Arc::new(PolicyEngine::from_sources(config.policy_dir.clone()));
+    if let Some(path) = &config.audit_log {
+        install_audit_log(path, config.audit_rotation).map_err(|source| AppError::AuditLog {
+            path: path.clone(),
+            source,
+        })?;
+    }
+    if let Some(path) = &config.counters_file {
+        restore_counters_from(path)?;
+    }
+    let enforced_legacy_file = match config.policy_mode {
+        PolicyMode::Both => config.legacy_policy_file.as_deref(),
+        _ => None,
+    };
+    for legacy in scan_legacy_policy(
+        config.legacy_policy_file.as_deref(),
+        config.policy_dir.as_deref(),
+    ) {
+        let enforced = match &legacy {
+            LegacyPolicy::PolicyFileVariable(path)
+            | LegacyPolicy::JsonRules(path)
+            | LegacyPolicy::WrapperObject(path) => Some(path.as_path()) == enforced_legacy_file,
+            LegacyPolicy::AllowedHosts(_) => false,
+        };
+        if !enforced {
+            tracing::error!("legacy policy not loaded: {legacy}");
+        }
+    }
+    let policy_engine = match (&config.policy_signing_key, enforced_legacy_file) {
+        (Some(key), legacy) => PolicyEngine::from_signed_sources(
+            config.policy_dir.clone(),
+            legacy.map(Path::to_path_buf),
+            key.clone(),
+        ),
+        (None, Some(legacy)) => {
+            PolicyEngine::from_sources_with_legacy(config.policy_dir.clone(), legacy.to_path_buf())
+        }
+        (None, None) => PolicyEngine::from_sources(config.policy_dir.clone()),
+    };
+    let policy_engine = Arc::new(
+        policy_engine
+            .with_workspace(config.workspace.clone())
+            .with_arg_limits(config.arg_limits)
+            .with_self_invocation_allowed(config.allow_self_invocation)
+            .with_denial_detail(config.denial_detail),
+    );
     policy_engine.start_watcher();
 
     tracing::info!(
         bind_addr = %config.bind_addr,
-        policy_mode = match policy_engine.mode() {
-            PolicyMode::Rego => "rego",
-            PolicyMode::DenyAll => "deny-all",
-        },
+        admin_bind_addr = %config.admin_bind_addr,
+        policy_mode = policy_engine.mode().as_str(),
+        denial_detail = config.denial_detail.as_str(),
+        policy_signed = config.policy_signing_key.is_some(),
+        git_commit = GIT_COMMIT,
         policy_dir = ?config.policy_dir.as_ref().map(|path| path.display().to_string()),
+        workspace = %config.workspace.display(),
+        audit_log = ?config.audit_log.as_ref().map(|path| path.display().to_string()),
+        counters_file = ?config.counters_file.as_ref().map(|path| path.display().to_string()),
+        cleanup_interval_secs = ?config.cleanup_interval.map(|interval| interval.as_secs()),
+        raw_max_streams = ?config.server_options.raw_stream_limits.max_active,
+        raw_max_queued = config.server_options.raw_stream_limits.max_queued,
+        max_concurrent = ?config.server_options.max_concurrent,
+        max_stdout_bytes = config.server_options.output_limits.stdout_bytes,
+        max_stderr_bytes = config.server_options.output_limits.stderr_bytes,
+        auth = config.server_options.auth_token.is_some(),
+        admin_auth = config.server_options.admin_token.is_some(),
         "starting network MCP server",
     );
+    if config.server_options.auth_token.is_none() && !config.bind_addr.ip().is_loopback() {
+        tracing::warn!(
+            bind_addr = %config.bind_addr,
+            "no MCP_AUTH_TOKEN set: anything that can reach this address can run allowed commands"
+        );
+    }
 
-    let app = build_app(policy_engine, config.default_cwd.clone());
+    let janitor = config
+        .cleanup_interval
+        .map(|interval| tokio::spawn(run_janitor(policy_engine.clone(), interval)));
+    let mcp_sessions = CancellationToken::new();
+    let (app, admin, in_flight) = build_router(
+        policy_engine,
+        config.default_cwd.clone(),
+        config.server_options.clone(),
+        mcp_sessions.clone(),
+    );
     let listener = tokio::net::TcpListener::bind(config.bind_addr).await?;
-    axum::serve(listener, app).await?;
+    let admin_listener = tokio::net::TcpListener::bind(config.admin_bind_addr).await?;
+    // Runs last so the listener and audit log are open and covered by the check.
+    descriptor_self_check();
+
+    let stop_flushing = CancellationToken::new();
+    let counters_flush = config.counters_file.clone().map(|path| {
+        tokio::spawn(flush_counters_periodically(
+            path,
+            config.counters_flush_interval,
+            stop_flushing.clone(),
+        ))
+    });
+    let stop_accepting = CancellationToken::new();
+    let mut server = tokio::spawn(
+        axum::serve(listener, app)
+            .with_graceful_shutdown(stop_accepting.clone().cancelled_owned())
+            .into_future(),
+    );
+    let mut admin_server = tokio::spawn(
+        axum::serve(admin_listener, admin)
+            .with_graceful_shutdown(stop_accepting.clone().cancelled_owned())
+            .into_future(),
+    );
+    tokio::select! {
+        result = &mut server => {
+            admin_server.abort();
+            result.map_err(std::io::Error::other)??;
+            return Ok(());
+        }
+        result = &mut admin_server => {
+            server.abort();
+            result.map_err(std::io::Error::other)??;
+            return Ok(());
+        }
+        () = shutdown_signal() => {}
+    }
+
+    tracing::info!(
+        in_flight = in_flight.count(),
+        timeout_secs = config.shutdown_timeout.as_secs(),
+        "shutting down: no longer accepting connections, draining in-flight commands",
+    );
+    stop_accepting.cancel();
+    if tokio::time::timeout(config.shutdown_timeout, in_flight.drained())
+        .await
+        .is_err()
+    {
+        let killed = kill_child_process_groups();
+        tracing::warn!(
+            in_flight = in_flight.count(),
+            killed_process_groups = killed,
+            "drain timed out; killed the remaining commands",
+        );
+    }
+    if let Some(janitor) = janitor {
+        janitor.abort();
+    }
+    // Open MCP sessions hold event streams that would otherwise keep the server waiting.
+    mcp_sessions.cancel();
+    match tokio::time::timeout(CONNECTION_CLOSE_GRACE, &mut server).await {
+        Ok(result) => result.map_err(std::io::Error::other)??,
+        Err(_) => server.abort(),
+    }
+    match tokio::time::timeout(CONNECTION_CLOSE_GRACE, &mut admin_server).await {
+        Ok(result) => result.map_err(std::io::Error::other)??,
+        Err(_) => admin_server.abort(),
+    }
+    // After the drain, so commands that finished during it are counted.
+    stop_flushing.cancel();
+    if let Some(flush) = counters_flush {
+        let _ = flush.await;
+    }
+    tracing::info!("shutdown complete");
     Ok(())
 }
 
@@ -182,23 +902,13 @@ mod tests {
     use std::sync::Arc;
 
     use super::*;
-    use crate::executor::{MAX_OUTPUT_BYTES, RunNetworkToolOutput, TRUNCATION_MARKER};
+    use crate::executor::{MAX_OUTPUT_BYTES, RunNetworkToolOutput, STDOUT_TRUNCATION_MARKER};
     use crate::policy::PolicyEngine;
+    use crate::test_support::{find_executable, serve_app};
     use rmcp::ServiceExt;
     use rmcp::model::CallToolRequestParams;
     use rmcp::transport::StreamableHttpClientTransport;
 
-    fn find_executable(name: &str) -> Option<String> {
-        let path = std::env::var_os("PATH")?;
-        for dir in std::env::split_paths(&path) {
-            let candidate = dir.join(name);
-            if candidate.is_file() {
-                return Some(candidate.to_string_lossy().into_owned());
-            }
-        }
-        None
-    }
-
     fn rego_engine_allow_commands(commands: &[&str]) -> PolicyEngine {
         let mut allowed_map = String::new();
         for command in commands {
@@ -225,16 +935,9 @@ mod tests {
             Arc::new(policy_engine),
             std::env::current_dir().expect("current dir"),
         );
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
-            .await
-            .expect("bind test listener");
-        let addr = listener.local_addr().expect("listener addr");
+        let (base, server_task) = serve_app(app).await;
 
-        let server_task = tokio::spawn(async move {
-            let _ = axum::serve(listener, app).await;
-        });
-
-        let url = format!("http://{addr}/mcp");
+        let url = format!("{base}/mcp");
         let client =
             ().serve(StreamableHttpClientTransport::from_uri(url))
                 .await
@@ -248,6 +951,21 @@ mod tests {
                 .any(|tool| tool.name == "run_network_tool")
         );
 
+        let summary: PolicySummary = client
+            .call_tool(CallToolRequestParams {
+                meta: None,
+                name: "list_allowed_commands".to_string().into(),
+                arguments: None,
+                task: None,
+            })
+            .await
+            .expect("invoke list_allowed_commands")
+            .into_typed()
+            .expect("typed summary");
+        assert_eq!(summary.policy_mode, "rego");
+        assert_eq!(summary.packages.len(), 1);
+        assert_eq!(summary.packages[0].package, "sandbox.main");
+
         let arguments = serde_json::json!({
             "executable": env_path,
             "args": ["printf", "smoke"]
@@ -273,6 +991,67 @@ mod tests {
         server_task.abort();
     }
 
+    #[tokio::test]
+    async fn max_concurrent_is_shared_between_raw_and_mcp_tool() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+
+        let app = build_app_with_options(
+            Arc::new(rego_engine_allow_commands(&[&sh_path])),
+            std::env::current_dir().expect("current dir"),
+            ServerOptions {
+                max_concurrent: Some(1),
+                ..ServerOptions::default()
+            },
+        );
+        let (base, server_task) = serve_app(app).await;
+
+        let running = reqwest::Client::new()
+            .post(format!("{base}/raw"))
+            .json(&serde_json::json!({
+                "executable": sh_path,
+                "args": ["-c", "sleep 30"]
+            }))
+            .send()
+            .await
+            .expect("raw request");
+        assert_eq!(running.status(), reqwest::StatusCode::OK);
+
+        let url = format!("{base}/mcp");
+        let client =
+            ().serve(StreamableHttpClientTransport::from_uri(url))
+                .await
+                .expect("connect MCP client");
+        let arguments = serde_json::json!({
+            "executable": sh_path,
+            "args": ["-c", "true"]
+        })
+        .as_object()
+        .cloned();
+        let call_result = client
+            .call_tool(CallToolRequestParams {
+                meta: None,
+                name: "run_network_tool".to_string().into(),
+                arguments,
+                task: None,
+            })
+            .await;
+        let rejected = match call_result {
+            Ok(result) => {
+                result.is_error == Some(true)
+                    && format!("{result:?}").contains("Too many concurrent executions")
+            }
+            Err(error) => error.to_string().contains("Too many concurrent executions"),
+        };
+        assert!(rejected, "tool call should hit the shared concurrency limit");
+
+        client.cancel().await.expect("cancel client");
+        drop(running);
+        server_task.abort();
+    }
+
     #[tokio::test]
     async fn mcp_tool_output_still_truncates_at_one_mb() {
         let head_path = match find_executable("head") {
@@ -286,16 +1065,9 @@ mod tests {
             Arc::new(policy_engine),
             std::env::current_dir().expect("current dir"),
         );
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
-            .await
-            .expect("bind test listener");
-        let addr = listener.local_addr().expect("listener addr");
-
-        let server_task = tokio::spawn(async move {
-            let _ = axum::serve(listener, app).await;
-        });
+        let (base, server_task) = serve_app(app).await;
 
-        let url = format!("http://{addr}/mcp");
+        let url = format!("{base}/mcp");
         let client =
             ().serve(StreamableHttpClientTransport::from_uri(url))
                 .await
@@ -319,7 +1091,7 @@ mod tests {
             .expect("invoke run_network_tool");
 
         let typed: RunNetworkToolOutput = call_result.into_typed().expect("typed response");
-        assert!(typed.stdout.ends_with(TRUNCATION_MARKER));
+        assert!(typed.stdout.ends_with(STDOUT_TRUNCATION_MARKER));
         assert_eq!(typed.exit_code, Some(0));
 
         client.cancel().await.expect("cancel client");
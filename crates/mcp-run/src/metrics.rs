@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write as _};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::extract::State;
+use axum::http::{HeaderValue, header};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::executor::ResourceUsage;
+use crate::raw::RawEndpointState;
+
+/// Process-wide totals over every command that ran to completion (MCP tool and `/raw`).
+struct ChildExitMetrics {
+    exited_total: AtomicU64,
+    user_cpu_ms_total: AtomicU64,
+    system_cpu_ms_total: AtomicU64,
+    max_rss_bytes: AtomicU64,
+}
+
+static CHILD_EXITS: ChildExitMetrics = ChildExitMetrics {
+    exited_total: AtomicU64::new(0),
+    user_cpu_ms_total: AtomicU64::new(0),
+    system_cpu_ms_total: AtomicU64::new(0),
+    max_rss_bytes: AtomicU64::new(0),
+};
+
+/// Started commands per `X-Client-Id`. Only ids that passed the policy are recorded, so the
+/// policy bounds the label set.
+static CLIENT_EXECUTIONS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+const ANONYMOUS_CLIENT: &str = "anonymous";
+
+pub(crate) fn record_client_execution(client_id: Option<&str>) {
+    let mut executions = CLIENT_EXECUTIONS
+        .lock()
+        .expect("client metrics lock poisoned");
+    *executions
+        .entry(client_id.unwrap_or(ANONYMOUS_CLIENT).to_string())
+        .or_default() += 1;
+}
+
+pub(crate) fn record_child_exit(usage: Option<ResourceUsage>) {
+    CHILD_EXITS.exited_total.fetch_add(1, Ordering::Relaxed);
+    if let Some(usage) = usage {
+        CHILD_EXITS
+            .user_cpu_ms_total
+            .fetch_add(usage.user_cpu_ms, Ordering::Relaxed);
+        CHILD_EXITS
+            .system_cpu_ms_total
+            .fetch_add(usage.system_cpu_ms, Ordering::Relaxed);
+        CHILD_EXITS
+            .max_rss_bytes
+            .fetch_max(usage.max_rss_bytes, Ordering::Relaxed);
+    }
+}
+
+/// The counters above, as saved to and restored from `MCP_COUNTERS_FILE`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CounterSnapshot {
+    #[serde(default)]
+    pub client_executions: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub commands_exited: u64,
+    #[serde(default)]
+    pub user_cpu_ms: u64,
+    #[serde(default)]
+    pub system_cpu_ms: u64,
+    #[serde(default)]
+    pub max_rss_bytes: u64,
+}
+
+pub(crate) fn counter_snapshot() -> CounterSnapshot {
+    CounterSnapshot {
+        client_executions: CLIENT_EXECUTIONS
+            .lock()
+            .expect("client metrics lock poisoned")
+            .clone(),
+        commands_exited: CHILD_EXITS.exited_total.load(Ordering::Relaxed),
+        user_cpu_ms: CHILD_EXITS.user_cpu_ms_total.load(Ordering::Relaxed),
+        system_cpu_ms: CHILD_EXITS.system_cpu_ms_total.load(Ordering::Relaxed),
+        max_rss_bytes: CHILD_EXITS.max_rss_bytes.load(Ordering::Relaxed),
+    }
+}
+
+/// Adds counts saved by an earlier run to the live counters.
+pub(crate) fn restore_counters(snapshot: &CounterSnapshot) {
+    let mut executions = CLIENT_EXECUTIONS
+        .lock()
+        .expect("client metrics lock poisoned");
+    for (client_id, count) in &snapshot.client_executions {
+        *executions.entry(client_id.clone()).or_default() += count;
+    }
+    CHILD_EXITS
+        .exited_total
+        .fetch_add(snapshot.commands_exited, Ordering::Relaxed);
+    CHILD_EXITS
+        .user_cpu_ms_total
+        .fetch_add(snapshot.user_cpu_ms, Ordering::Relaxed);
+    CHILD_EXITS
+        .system_cpu_ms_total
+        .fetch_add(snapshot.system_cpu_ms, Ordering::Relaxed);
+    CHILD_EXITS
+        .max_rss_bytes
+        .fetch_max(snapshot.max_rss_bytes, Ordering::Relaxed);
+}
+
+/// Serves gauges and counters in the Prometheus text exposition format.
+pub async fn metrics_handler(State(state): State<RawEndpointState>) -> Response {
+    let raw_streams = state.raw_streams.snapshot();
+    let executions = state.executions.snapshot();
+    let mut body = String::new();
+    write_metric(
+        &mut body,
+        "mcp_run_raw_streams_active",
+        "gauge",
+        "Raw streams currently running a command.",
+        raw_streams.active,
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_raw_streams_queued",
+        "gauge",
+        "Raw requests waiting for a free stream slot.",
+        raw_streams.queued,
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_raw_streams_rejected_total",
+        "counter",
+        "Raw requests rejected because the stream queue was full.",
+        raw_streams.rejected_total,
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_executions_active",
+        "gauge",
+        "Commands currently running via the MCP tool or /raw.",
+        executions.active,
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_executions_rejected_total",
+        "counter",
+        "Commands rejected because MCP_MAX_CONCURRENT was reached.",
+        executions.rejected_total,
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_commands_exited_total",
+        "counter",
+        "Commands that exited and were reaped.",
+        CHILD_EXITS.exited_total.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_command_user_cpu_seconds_total",
+        "counter",
+        "User CPU time consumed by exited commands.",
+        ms_as_seconds(CHILD_EXITS.user_cpu_ms_total.load(Ordering::Relaxed)),
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_command_system_cpu_seconds_total",
+        "counter",
+        "System CPU time consumed by exited commands.",
+        ms_as_seconds(CHILD_EXITS.system_cpu_ms_total.load(Ordering::Relaxed)),
+    );
+    write_metric(
+        &mut body,
+        "mcp_run_command_max_rss_bytes",
+        "gauge",
+        "Largest peak resident set size of any exited command.",
+        CHILD_EXITS.max_rss_bytes.load(Ordering::Relaxed),
+    );
+    write_client_executions(&mut body);
+
+    let mut response = body.into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; version=0.0.4"),
+    );
+    response
+}
+
+fn ms_as_seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
+
+fn write_client_executions(body: &mut String) {
+    let name = "mcp_run_client_executions_total";
+    let _ = writeln!(body, "# HELP {name} Commands started, by X-Client-Id.");
+    let _ = writeln!(body, "# TYPE {name} counter");
+    let executions = CLIENT_EXECUTIONS
+        .lock()
+        .expect("client metrics lock poisoned");
+    // Client ids are restricted to [A-Za-z0-9._-], so they need no label escaping.
+    for (client_id, count) in executions.iter() {
+        let _ = writeln!(body, "{name}{{client_id=\"{client_id}\"}} {count}");
+    }
+}
+
+fn write_metric(body: &mut String, name: &str, kind: &str, help: &str, value: impl Display) {
+    let _ = writeln!(body, "# HELP {name} {help}");
+    let _ = writeln!(body, "# TYPE {name} {kind}");
+    let _ = writeln!(body, "{name} {value}");
+}
@@ -0,0 +1,783 @@
+//! Detection and conversion of policy formats from before the Rego engine: the `POLICY_FILE`
+//! JSON command rules, their `{"commands": [...]}` wrapper object and `allowedHosts`. The
+//! command rules are also enforced next to Rego when `POLICY_MODE=both`.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::hashing::{FileHasher, HashAlgorithm};
+
+/// Where migration errors point users for the current policy format.
+const MIGRATION_DOCS: &str = "see \"Migrating JSON policies\" in the mcp-run README";
+
+/// A construct from an older release that the server no longer reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LegacyPolicy {
+    /// `POLICY_FILE` is set; only `POLICY_DIR` is read.
+    PolicyFileVariable(PathBuf),
+    /// A top-level JSON array of command rules.
+    JsonRules(PathBuf),
+    /// Command rules wrapped in `{"commands": [...]}`.
+    WrapperObject(PathBuf),
+    /// Proxy host filtering, which moved out of mcp-run.
+    AllowedHosts(PathBuf),
+}
+
+impl fmt::Display for LegacyPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LegacyPolicy::PolicyFileVariable(path) => write!(
+                f,
+                "POLICY_FILE ({}) is no longer read; policies are Rego modules in POLICY_DIR. Convert it with `mcp-run --migrate {} <POLICY_DIR>` ({MIGRATION_DOCS})",
+                path.display(),
+                path.display()
+            ),
+            LegacyPolicy::JsonRules(path) => write!(
+                f,
+                "{} holds legacy JSON command rules, which are ignored. Convert them with `mcp-run --migrate {} <POLICY_DIR>` ({MIGRATION_DOCS})",
+                path.display(),
+                path.display()
+            ),
+            LegacyPolicy::WrapperObject(path) => write!(
+                f,
+                "{} wraps legacy command rules in {{\"commands\": [...]}}, which is ignored. Convert them with `mcp-run --migrate {} <POLICY_DIR>` ({MIGRATION_DOCS})",
+                path.display(),
+                path.display()
+            ),
+            LegacyPolicy::AllowedHosts(path) => write!(
+                f,
+                "{} sets allowedHosts, which is not supported; host filtering is done by the proxy (sandbox_domains.lst in the cladding config) ({MIGRATION_DOCS})",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Looks for legacy constructs in `POLICY_FILE` and in the JSON files of `POLICY_DIR`. JSON
+/// that does not look like a legacy policy is left alone.
+pub fn scan_legacy_policy(
+    policy_file: Option<&Path>,
+    policy_dir: Option<&Path>,
+) -> Vec<LegacyPolicy> {
+    let mut findings = Vec::new();
+    let mut candidates = Vec::new();
+    if let Some(policy_file) = policy_file {
+        findings.push(LegacyPolicy::PolicyFileVariable(policy_file.to_path_buf()));
+        candidates.push(policy_file.to_path_buf());
+    }
+    if let Some(policy_dir) = policy_dir
+        && let Ok(entries) = std::fs::read_dir(policy_dir)
+    {
+        let mut json_files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+            .filter(|path| Some(path.as_path()) != policy_file)
+            .collect();
+        json_files.sort();
+        candidates.extend(json_files);
+    }
+    for path in candidates {
+        let Some(document) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        else {
+            continue;
+        };
+        findings.extend(classify_document(&path, &document));
+    }
+    findings
+}
+
+fn classify_document(path: &Path, document: &serde_json::Value) -> Vec<LegacyPolicy> {
+    let mut findings = Vec::new();
+    match document {
+        serde_json::Value::Array(rules)
+            if !rules.is_empty() && rules.iter().all(is_command_rule) =>
+        {
+            findings.push(LegacyPolicy::JsonRules(path.to_path_buf()));
+        }
+        serde_json::Value::Object(object) => {
+            if object.get("commands").is_some_and(serde_json::Value::is_array) {
+                findings.push(LegacyPolicy::WrapperObject(path.to_path_buf()));
+            }
+            if object.contains_key("allowedHosts") {
+                findings.push(LegacyPolicy::AllowedHosts(path.to_path_buf()));
+            }
+        }
+        _ => {}
+    }
+    findings
+}
+
+fn is_command_rule(value: &serde_json::Value) -> bool {
+    value.get("command").is_some_and(serde_json::Value::is_string)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LegacyCommandRule {
+    command: String,
+    #[serde(default)]
+    args: Vec<LegacyArgCheck>,
+    /// Env keys the rule allows the caller to forward.
+    #[serde(default)]
+    env: Vec<LegacyEnvEntry>,
+}
+
+/// An env key, optionally with the value it must have.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LegacyEnvEntry {
+    Name(String),
+    Constrained(LegacyEnvConstraint),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LegacyEnvConstraint {
+    name: String,
+    exact: Option<String>,
+    /// Must match the whole value.
+    regex: Option<String>,
+}
+
+impl LegacyEnvEntry {
+    fn name(&self) -> &str {
+        match self {
+            LegacyEnvEntry::Name(name) => name,
+            LegacyEnvEntry::Constrained(constraint) => &constraint.name,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LegacyArgCheck {
+    #[serde(rename = "type")]
+    kind: LegacyArgKind,
+    value: String,
+    position: Option<usize>,
+    #[serde(default)]
+    required: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LegacyArgKind {
+    Exact,
+    Regex,
+    /// `*` and `?` stop at `/`, `**` crosses it, as `glob.match(pattern, ["/"], arg)` does.
+    Glob,
+    Hash,
+}
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("usage: mcp-run --migrate [LEGACY_JSON] [POLICY_DIR] (defaults: $POLICY_FILE, $POLICY_DIR)")]
+    Usage,
+    #[error("failed to read '{}': {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("'{}' is not a legacy policy: {details}", path.display())]
+    Parse { path: PathBuf, details: String },
+    #[error("failed to write '{}': {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// What `--migrate` wrote and what it had to leave for a human.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub written: Vec<PathBuf>,
+    /// Existing files that were not overwritten.
+    pub skipped: Vec<PathBuf>,
+    /// Parts of the legacy policy that have no Rego equivalent.
+    pub unmigrated: Vec<String>,
+}
+
+/// Parses `mcp-run --migrate [LEGACY_JSON] [POLICY_DIR]`, falling back to `POLICY_FILE` and
+/// `POLICY_DIR`, and runs [`migrate_legacy_policy`].
+pub fn migrate_from_args(args: &[String]) -> Result<MigrationReport, MigrationError> {
+    let from_env = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let mut args = args.iter().cloned();
+    let legacy = args
+        .next()
+        .or_else(|| from_env("POLICY_FILE"))
+        .ok_or(MigrationError::Usage)?;
+    let policy_dir = args
+        .next()
+        .or_else(|| from_env("POLICY_DIR"))
+        .ok_or(MigrationError::Usage)?;
+    if args.next().is_some() {
+        return Err(MigrationError::Usage);
+    }
+    migrate_legacy_policy(Path::new(&legacy), Path::new(&policy_dir))
+}
+
+/// Converts the legacy JSON policy at `legacy` into one Rego module per command in
+/// `policy_dir`, plus the router `main.rego` when the directory has none. Existing modules are
+/// never overwritten.
+pub fn migrate_legacy_policy(
+    legacy: &Path,
+    policy_dir: &Path,
+) -> Result<MigrationReport, MigrationError> {
+    let (rules, allowed_hosts) = read_legacy_rules(legacy)?;
+    for rule in &rules {
+        for check in rule.args.iter().filter(|check| matches!(check.kind, LegacyArgKind::Glob)) {
+            compile_arg_glob(legacy, &rule.command, &check.value)?;
+        }
+    }
+    let mut report = MigrationReport::default();
+    if allowed_hosts {
+        report.unmigrated.push(
+            "allowedHosts: add the hosts to sandbox_domains.lst in the cladding config".to_string(),
+        );
+    }
+
+    let mut by_command: BTreeMap<String, Vec<LegacyCommandRule>> = BTreeMap::new();
+    for rule in rules {
+        by_command.entry(rule.command.clone()).or_default().push(rule);
+    }
+
+    std::fs::create_dir_all(policy_dir).map_err(|source| MigrationError::Write {
+        path: policy_dir.to_path_buf(),
+        source,
+    })?;
+    let source_name = legacy
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| legacy.display().to_string());
+    for (command, rules) in &by_command {
+        if !is_rego_identifier(command) {
+            report.unmigrated.push(format!(
+                "command '{command}': not a valid Rego package name; write its module by hand"
+            ));
+            continue;
+        }
+        let module = render_command_module(command, rules, &source_name);
+        write_new_file(&policy_dir.join(format!("{command}.rego")), &module, &mut report)?;
+    }
+    write_new_file(&policy_dir.join("main.rego"), ROUTER_MODULE, &mut report)?;
+    Ok(report)
+}
+
+/// Reads the command rules of the legacy policy at `legacy`, and whether it also set
+/// `allowedHosts`.
+fn read_legacy_rules(legacy: &Path) -> Result<(Vec<LegacyCommandRule>, bool), MigrationError> {
+    let contents = std::fs::read_to_string(legacy).map_err(|source| MigrationError::Read {
+        path: legacy.to_path_buf(),
+        source,
+    })?;
+    parse_legacy_rules(legacy, &contents)
+}
+
+fn parse_legacy_rules(
+    legacy: &Path,
+    contents: &str,
+) -> Result<(Vec<LegacyCommandRule>, bool), MigrationError> {
+    let parse_error = |details: String| MigrationError::Parse {
+        path: legacy.to_path_buf(),
+        details,
+    };
+    let document: serde_json::Value =
+        serde_json::from_str(contents).map_err(|error| parse_error(error.to_string()))?;
+
+    let mut allowed_hosts = false;
+    let rules = match document {
+        serde_json::Value::Array(rules) => rules,
+        serde_json::Value::Object(mut object) => {
+            allowed_hosts = object.remove("allowedHosts").is_some();
+            match object.remove("commands") {
+                Some(serde_json::Value::Array(rules)) => rules,
+                _ => return Err(parse_error("expected an array of command rules".to_string())),
+            }
+        }
+        _ => return Err(parse_error("expected an array of command rules".to_string())),
+    };
+    let rules = serde_json::from_value(serde_json::Value::Array(rules))
+        .map_err(|error| parse_error(error.to_string()))?;
+    Ok((rules, allowed_hosts))
+}
+
+/// The legacy JSON command rules enforced directly, for `POLICY_MODE=both`. A rule matches
+/// exactly when the module `--migrate` writes for it allows the invocation; `allowedHosts`
+/// is left to the proxy.
+#[derive(Debug)]
+pub(crate) struct LegacyAllowlist {
+    rules: BTreeMap<String, Vec<LegacyCommandRule>>,
+    /// Every `regex` of the rules, compiled: arg patterns as written, env patterns anchored.
+    patterns: BTreeMap<String, Regex>,
+    /// Every arg `glob` of the rules, compiled.
+    globs: BTreeMap<String, GlobMatcher>,
+}
+
+impl LegacyAllowlist {
+    pub(crate) fn load(legacy: &Path) -> Result<Self, MigrationError> {
+        Self::from_rules(legacy, read_legacy_rules(legacy)?.0)
+    }
+
+    /// Like [`LegacyAllowlist::load`], from `contents` already read from `legacy`.
+    pub(crate) fn parse(legacy: &Path, contents: &str) -> Result<Self, MigrationError> {
+        Self::from_rules(legacy, parse_legacy_rules(legacy, contents)?.0)
+    }
+
+    fn from_rules(legacy: &Path, rules: Vec<LegacyCommandRule>) -> Result<Self, MigrationError> {
+        let mut allowlist = Self {
+            rules: BTreeMap::new(),
+            patterns: BTreeMap::new(),
+            globs: BTreeMap::new(),
+        };
+        for rule in rules {
+            let arg_patterns = rule
+                .args
+                .iter()
+                .filter(|check| matches!(check.kind, LegacyArgKind::Regex))
+                .map(|check| check.value.clone());
+            let env_patterns = rule.env.iter().filter_map(|entry| match entry {
+                LegacyEnvEntry::Constrained(constraint) => {
+                    constraint.regex.as_deref().map(anchored)
+                }
+                LegacyEnvEntry::Name(_) => None,
+            });
+            for pattern in arg_patterns.chain(env_patterns).collect::<Vec<_>>() {
+                let regex = Regex::new(&pattern).map_err(|error| MigrationError::Parse {
+                    path: legacy.to_path_buf(),
+                    details: format!("command '{}': {error}", rule.command),
+                })?;
+                allowlist.patterns.insert(pattern, regex);
+            }
+            let arg_globs = rule
+                .args
+                .iter()
+                .filter(|check| matches!(check.kind, LegacyArgKind::Glob));
+            for check in arg_globs {
+                let glob = compile_arg_glob(legacy, &rule.command, &check.value)?;
+                allowlist.globs.insert(check.value.clone(), glob);
+            }
+            allowlist
+                .rules
+                .entry(rule.command.clone())
+                .or_default()
+                .push(rule);
+        }
+        Ok(allowlist)
+    }
+
+    /// Whether any rule for `command` allows `args` and `env`. `hash` checks go to `hasher`.
+    pub(crate) fn allows(
+        &self,
+        command: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        hasher: &dyn FileHasher,
+    ) -> bool {
+        self.rules.get(command).is_some_and(|rules| {
+            rules
+                .iter()
+                .any(|rule| self.rule_allows(rule, args, env, hasher))
+        })
+    }
+
+    fn rule_allows(
+        &self,
+        rule: &LegacyCommandRule,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        hasher: &dyn FileHasher,
+    ) -> bool {
+        let passes = |check: &LegacyArgCheck, arg: &str| match check.kind {
+            LegacyArgKind::Exact => arg == check.value,
+            LegacyArgKind::Regex => self.patterns[&check.value].is_match(arg),
+            LegacyArgKind::Glob => self.globs[&check.value].is_match(arg),
+            LegacyArgKind::Hash => hasher
+                .hash_hex(Path::new(arg), HashAlgorithm::Sha256)
+                .is_ok_and(|hash| hash == check.value),
+        };
+        let args_allowed = if rule.args.is_empty() {
+            args.is_empty()
+        } else {
+            args.iter().enumerate().all(|(index, arg)| {
+                rule.args.iter().any(|check| {
+                    check.position.is_none_or(|position| position == index) && passes(check, arg)
+                })
+            })
+        };
+        let required_met = rule
+            .args
+            .iter()
+            .filter(|check| check.required)
+            .all(|check| match check.position {
+                Some(position) => args.get(position).is_some_and(|arg| passes(check, arg)),
+                None => args.iter().any(|arg| passes(check, arg)),
+            });
+        let env_allowed = env.iter().all(|(key, value)| {
+            rule.env.iter().any(|entry| match entry {
+                LegacyEnvEntry::Name(name) => name == key,
+                LegacyEnvEntry::Constrained(constraint) => {
+                    constraint.name == *key
+                        && constraint.exact.as_ref().is_none_or(|exact| exact == value)
+                        && constraint
+                            .regex
+                            .as_deref()
+                            .is_none_or(|pattern| self.patterns[&anchored(pattern)].is_match(value))
+                }
+            })
+        });
+        args_allowed && required_met && env_allowed
+    }
+}
+
+/// The router from the README's Decision Contract.
+const ROUTER_MODULE: &str = "package sandbox.main
+
+default allow = false
+
+allow if {
+    data.sandbox[input.command].allow
+    env_allowed
+}
+
+env_allowed if {
+    count(object.keys(input.env)) == 0
+}
+
+env_allowed if {
+    data.sandbox[input.command].allow_env
+}
+";
+
+fn write_new_file(
+    path: &Path,
+    contents: &str,
+    report: &mut MigrationReport,
+) -> Result<(), MigrationError> {
+    if path.exists() {
+        report.skipped.push(path.to_path_buf());
+        return Ok(());
+    }
+    std::fs::write(path, contents).map_err(|source| MigrationError::Write {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    report.written.push(path.to_path_buf());
+    Ok(())
+}
+
+fn is_rego_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "main"
+}
+
+/// Rules for one command are OR-ed, as before. Within a rule every argument must pass one of
+/// its checks (positional checks only at their position), every `required` check must be met
+/// and forwarded env vars must pass one of the rule's `env` entries. Env is checked per rule
+/// inside `allow`, so `allow_env` is unconditional.
+fn render_command_module(command: &str, rules: &[LegacyCommandRule], source_name: &str) -> String {
+    let mut module = format!(
+        "# Migrated from {source_name} by `mcp-run --migrate`; review before relying on it.\npackage sandbox.{command}\n\ndefault allow = false\n\n# Env keys are checked per rule in `allow`.\nallow_env := true\n"
+    );
+    for (index, rule) in rules.iter().enumerate() {
+        let _ = write!(module, "\nallow if {{\n");
+        if rule.args.is_empty() {
+            module.push_str("    count(input.args) == 0\n");
+        } else {
+            let _ = writeln!(
+                module,
+                "    every i, arg in input.args {{\n        rule_{index}_arg(i, arg)\n    }}"
+            );
+        }
+        for (check_index, check) in rule.args.iter().enumerate().filter(|(_, c)| c.required) {
+            match check.position {
+                Some(position) => {
+                    let _ = writeln!(
+                        module,
+                        "    {}",
+                        arg_condition(check, &format!("input.args[{position}]"))
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        module,
+                        "    some required_{check_index} in input.args\n    {}",
+                        arg_condition(check, &format!("required_{check_index}"))
+                    );
+                }
+            }
+        }
+        if rule.env.is_empty() {
+            module.push_str("    count(object.keys(input.env)) == 0\n");
+        } else {
+            let _ = writeln!(
+                module,
+                "    every key, value in input.env {{\n        rule_{index}_env(key, value)\n    }}"
+            );
+        }
+        module.push_str("}\n");
+        for entry in &rule.env {
+            let mut conditions = format!("    key == {}\n", rego_string(entry.name()));
+            if let LegacyEnvEntry::Constrained(constraint) = entry {
+                if let Some(exact) = &constraint.exact {
+                    let _ = writeln!(conditions, "    value == {}", rego_string(exact));
+                }
+                if let Some(pattern) = &constraint.regex {
+                    let _ = writeln!(
+                        conditions,
+                        "    regex.match({}, value)",
+                        rego_string(&anchored(pattern))
+                    );
+                }
+            }
+            let _ = write!(
+                module,
+                "\nrule_{index}_env(key, value) if {{\n{conditions}}}\n"
+            );
+        }
+        for check in &rule.args {
+            let (param, index_pattern) = match check.position {
+                Some(position) => ("i", format!("    i == {position}\n")),
+                None => ("_", String::new()),
+            };
+            let _ = write!(
+                module,
+                "\nrule_{index}_arg({param}, arg) if {{\n{index_pattern}    {}\n}}\n",
+                arg_condition(check, "arg")
+            );
+        }
+    }
+    module
+}
+
+fn arg_condition(check: &LegacyArgCheck, arg: &str) -> String {
+    let value = rego_string(&check.value);
+    match check.kind {
+        LegacyArgKind::Exact => format!("{arg} == {value}"),
+        LegacyArgKind::Regex => format!("regex.match({value}, {arg})"),
+        LegacyArgKind::Glob => format!("glob.match({value}, [\"/\"], {arg})"),
+        LegacyArgKind::Hash => format!("file_hash(\"sha256\", {arg}) == {value}"),
+    }
+}
+
+/// Compiled as the Rego engine compiles `glob.match` patterns with the `/` delimiter, so an
+/// invalid glob fails here rather than on every evaluation of the migrated module.
+fn compile_arg_glob(
+    legacy: &Path,
+    command: &str,
+    pattern: &str,
+) -> Result<GlobMatcher, MigrationError> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|error| MigrationError::Parse {
+            path: legacy.to_path_buf(),
+            details: format!("command '{command}': {error}"),
+        })
+}
+
+/// Env value patterns must match the whole value.
+fn anchored(pattern: &str) -> String {
+    format!("^(?:{pattern})$")
+}
+
+/// JSON string syntax is valid Rego string syntax.
+fn rego_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::policy::{PolicyEngine, ValidationError};
+
+    const LEGACY_POLICY: &str = r#"{
+  "allowedHosts": ["example.com"],
+  "commands": [
+    {
+      "command": "curl",
+      "args": [
+        {"type": "exact", "value": "-I", "position": 0, "required": true},
+        {"type": "regex", "value": "^https://example\\.com/"}
+      ],
+      "env": ["API_TOKEN", {"name": "CURL_HOME", "regex": "/home/[a-z]+"}]
+    },
+    {
+      "command": "git",
+      "env": [{"name": "GIT_SSH_COMMAND", "exact": "ssh -o BatchMode=yes"}]
+    },
+    {
+      "command": "tar",
+      "args": [
+        {"type": "exact", "value": "-xzf", "position": 0, "required": true},
+        {"type": "glob", "value": "dist/*.tar.gz", "position": 1, "required": true}
+      ]
+    },
+    {"command": "date"},
+    {"command": "my-tool"}
+  ]
+}"#;
+
+    #[test]
+    fn scan_reports_legacy_constructs_with_pointers() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let legacy = dir.path().join("sandbox_commands.json");
+        std::fs::write(&legacy, LEGACY_POLICY).expect("write legacy");
+        std::fs::write(
+            dir.path().join("rules.json"),
+            r#"[{"command": "git", "args": []}]"#,
+        )
+        .expect("write rules");
+        std::fs::write(dir.path().join("data.json"), r#"{"mirrors": ["a"]}"#).expect("write data");
+
+        let findings = scan_legacy_policy(Some(&legacy), Some(dir.path()));
+        assert_eq!(
+            findings,
+            vec![
+                LegacyPolicy::PolicyFileVariable(legacy.clone()),
+                LegacyPolicy::WrapperObject(legacy.clone()),
+                LegacyPolicy::AllowedHosts(legacy.clone()),
+                LegacyPolicy::JsonRules(dir.path().join("rules.json")),
+            ]
+        );
+        assert!(findings[0].to_string().contains("mcp-run --migrate"));
+        assert!(findings[2].to_string().contains("sandbox_domains.lst"));
+        assert!(scan_legacy_policy(None, Some(&dir.path().join("missing"))).is_empty());
+    }
+
+    #[test]
+    fn migrate_writes_modules_the_engine_enforces() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let legacy = dir.path().join("sandbox_commands.json");
+        std::fs::write(&legacy, LEGACY_POLICY).expect("write legacy");
+        let policy_dir = dir.path().join("policy");
+
+        let report = migrate_legacy_policy(&legacy, &policy_dir).expect("migrate");
+        assert_eq!(
+            report.written,
+            vec![
+                policy_dir.join("curl.rego"),
+                policy_dir.join("date.rego"),
+                policy_dir.join("git.rego"),
+                policy_dir.join("tar.rego"),
+                policy_dir.join("main.rego"),
+            ]
+        );
+        assert_eq!(report.unmigrated.len(), 2, "{:?}", report.unmigrated);
+
+        let engine = PolicyEngine::try_from_dir(policy_dir.clone()).expect("modules load");
+        let check = |command: &str, args: &[&str], env: &[(&str, &str)]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let env: BTreeMap<String, String> = env
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            engine.validate_invocation(
+                command,
+                "/usr/bin/tool",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &args,
+                &env,
+            )
+        };
+        assert!(
+            check("curl", &["-I", "https://example.com/x"], &[("API_TOKEN", "value")]).is_ok()
+        );
+        assert!(check("curl", &["-I"], &[]).is_ok());
+        assert!(matches!(
+            check("curl", &["https://example.com/x"], &[]),
+            Err(ValidationError::CommandNotAllowed(_))
+        ));
+        assert!(check("curl", &["-I", "https://other.com/"], &[]).is_err());
+        assert!(check("curl", &["-I"], &[("HOME", "value")]).is_err());
+        assert!(check("curl", &["-I"], &[("CURL_HOME", "/home/agent")]).is_ok());
+        assert!(check("curl", &["-I"], &[("CURL_HOME", "/home/agent/x")]).is_err());
+        assert!(check("git", &[], &[("GIT_SSH_COMMAND", "ssh -o BatchMode=yes")]).is_ok());
+        assert!(check("git", &[], &[("GIT_SSH_COMMAND", "sh -c id")]).is_err());
+        assert!(check("date", &[], &[]).is_ok());
+        assert!(check("date", &["+%s"], &[]).is_err());
+        assert!(check("tar", &["-xzf", "dist/app.tar.gz"], &[]).is_ok());
+        assert!(check("tar", &["-xzf", "dist/nested/app.tar.gz"], &[]).is_err());
+        assert!(check("tar", &["-xzf", "dist/app.zip"], &[]).is_err());
+
+        let again = migrate_legacy_policy(&legacy, &policy_dir).expect("migrate again");
+        assert!(again.written.is_empty());
+        assert_eq!(again.skipped.len(), 5);
+    }
+
+    #[test]
+    fn legacy_allowlist_agrees_with_the_migrated_modules() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let legacy = dir.path().join("sandbox_commands.json");
+        std::fs::write(&legacy, LEGACY_POLICY).expect("write legacy");
+        let policy_dir = dir.path().join("policy");
+        migrate_legacy_policy(&legacy, &policy_dir).expect("migrate");
+        let engine = PolicyEngine::try_from_dir(policy_dir).expect("modules load");
+        let allowlist = LegacyAllowlist::load(&legacy).expect("allowlist loads");
+
+        let agrees = |command: &str, args: &[&str], env: &[(&str, &str)]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let env: BTreeMap<String, String> = env
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            let rego = engine
+                .validate_invocation(command, "/usr/bin/tool", "00", &args, &env)
+                .is_ok();
+            allowlist.allows(command, &args, &env, &crate::hashing::FsHasher) == rego
+        };
+        assert!(agrees("curl", &["-I", "https://example.com/x"], &[("API_TOKEN", "value")]));
+        assert!(agrees("curl", &["-I"], &[]));
+        assert!(agrees("curl", &["https://example.com/x"], &[]));
+        assert!(agrees("curl", &["-I", "https://other.com/"], &[]));
+        assert!(agrees("curl", &["-I"], &[("CURL_HOME", "/home/agent")]));
+        assert!(agrees("curl", &["-I"], &[("CURL_HOME", "/home/agent/x")]));
+        assert!(agrees("git", &[], &[("GIT_SSH_COMMAND", "ssh -o BatchMode=yes")]));
+        assert!(agrees("git", &[], &[("GIT_SSH_COMMAND", "sh -c id")]));
+        assert!(agrees("date", &["+%s"], &[]));
+        assert!(agrees("tar", &["-xzf", "dist/app.tar.gz"], &[]));
+        assert!(agrees("tar", &["-xzf", "dist/nested/app.tar.gz"], &[]));
+        assert!(agrees("tar", &["-xzf", "dist/app.zip"], &[]));
+        assert!(agrees("tar", &["-xzf"], &[]));
+        assert!(agrees("ls", &[], &[]));
+    }
+
+    #[test]
+    fn invalid_arg_globs_fail_to_load() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let legacy = dir.path().join("sandbox_commands.json");
+        std::fs::write(
+            &legacy,
+            r#"[{"command": "tar", "args": [{"type": "glob", "value": "dist/[a"}]}]"#,
+        )
+        .expect("write legacy");
+
+        let error = LegacyAllowlist::load(&legacy).expect_err("unclosed class");
+        assert!(error.to_string().contains("command 'tar'"), "{error}");
+        let policy_dir = dir.path().join("policy");
+        assert!(matches!(
+            migrate_legacy_policy(&legacy, &policy_dir),
+            Err(MigrationError::Parse { .. })
+        ));
+        assert!(!policy_dir.join("tar.rego").exists());
+    }
+}
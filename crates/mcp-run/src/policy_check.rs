@@ -0,0 +1,186 @@
+use axum::Json;
+use axum::extract::{State, rejection::JsonRejection};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::client_id::context_from_headers;
+use crate::executor::{RunNetworkToolInput, resolve_executable};
+use crate::policy::{
+    InvocationContext, OutputLimitOverrides, PolicyMode, REGO_ALLOW_QUERY, normalize_lexically,
+};
+use crate::raw::{RawEndpointState, error_response};
+use crate::redaction::redact_env_values;
+use crate::runtime::run_blocking;
+
+/// Result of evaluating a `/raw`-shaped payload against the policy without running it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyCheckResponse {
+    pub allowed: bool,
+    pub policy_mode: String,
+    /// The Rego query that produced the decision; `None` in deny-all mode or when the
+    /// executable could not be resolved.
+    pub query: Option<String>,
+    pub resolved_path: Option<String>,
+    pub sha256: Option<String>,
+    /// Why the invocation was denied.
+    pub reason: Option<String>,
+    /// Per-stream limits from the policy's `output_limits` rule, when allowed.
+    pub output_limits: Option<OutputLimitOverrides>,
+    /// Patterns from the policy's `redact` rule, when allowed.
+    pub redact: Option<Vec<String>>,
+}
+
+pub async fn policy_check_handler(
+    State(state): State<RawEndpointState>,
+    headers: HeaderMap,
+    payload: Result<Json<RunNetworkToolInput>, JsonRejection>,
+) -> Response {
+    let input = match payload {
+        Ok(Json(input)) => input,
+        Err(error) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid request payload: {error}"),
+            );
+        }
+    };
+    let context = match context_from_headers(&headers) {
+        Ok(context) => context,
+        Err(error) => return error_response(StatusCode::BAD_REQUEST, error.to_string()),
+    };
+
+    let policy_mode = state.policy_engine.mode();
+    let mut response = PolicyCheckResponse {
+        allowed: false,
+        policy_mode: policy_mode.as_str().to_string(),
+        query: None,
+        resolved_path: None,
+        sha256: None,
+        reason: None,
+        output_limits: None,
+        redact: None,
+    };
+
+    let (path, hash) = match run_blocking(|| resolve_executable(&input.executable)) {
+        Ok(resolved) => resolved,
+        Err(error) => {
+            response.reason = Some(state.policy_engine.denial_message(&error));
+            return Json(response).into_response();
+        }
+    };
+    if policy_mode != PolicyMode::DenyAll {
+        response.query = Some(REGO_ALLOW_QUERY.to_string());
+    }
+
+    let env = input.env.unwrap_or_default();
+    let cwd = normalize_lexically(&match input.cwd.as_deref() {
+        Some(cwd) => state.default_cwd.join(cwd),
+        None => state.default_cwd.clone(),
+    });
+    let context = InvocationContext {
+        canonical_cwd: std::fs::canonicalize(&cwd).ok(),
+        cwd: Some(cwd),
+        ..context
+    };
+    let mut args_for_log = redact_env_values(&input.args, &env, |_| true);
+    match run_blocking(|| {
+        state
+            .policy_engine
+            .authorize_invocation(&input.executable, &path, &hash, &input.args, &env, &context)
+    }) {
+        Ok(decision) => {
+            response.allowed = true;
+            args_for_log = decision.redact_args(&input.args, &env);
+            response.output_limits = Some(decision.output_limits);
+            response.redact = Some(decision.redact.patterns());
+        }
+        Err(error) => response.reason = Some(state.policy_engine.denial_message(&error)),
+    }
+    tracing::info!(
+        command = %input.executable,
+        args = ?args_for_log,
+        client_id = context.client_id.as_deref().unwrap_or_default(),
+        allowed = response.allowed,
+        "policy check evaluated",
+    );
+    response.resolved_path = Some(path);
+    response.sha256 = Some(hash);
+    Json(response).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::mcp::build_app;
+    use crate::policy::PolicyEngine;
+    use crate::test_support::{find_executable, serve_app};
+
+    #[tokio::test]
+    async fn policy_check_reports_decision_without_running() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let escaped = sh_path.replace('\\', "\\\\").replace('\"', "\\\"");
+        let main = format!(
+            "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n  count(input.args) == 2\n}}\n"
+        );
+        let app = build_app(
+            Arc::new(PolicyEngine::from_rego_for_tests(&[("main.rego", &main)])),
+            PathBuf::from("."),
+        );
+        let (base, server_task) = serve_app(app).await;
+
+        let marker = std::env::temp_dir().join(format!(
+            "mcp-run-policy-check-{}",
+            std::process::id()
+        ));
+        let check = |args: Vec<String>| {
+            let url = format!("{base}/policy/check");
+            let body = RunNetworkToolInput {
+                executable: sh_path.clone(),
+                args,
+                ..RunNetworkToolInput::default()
+            };
+            async move {
+                reqwest::Client::new()
+                    .post(url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .expect("request")
+                    .json::<PolicyCheckResponse>()
+                    .await
+                    .expect("json response")
+            }
+        };
+
+        let allowed = check(vec![
+            "-c".to_string(),
+            format!("touch {}", marker.display()),
+        ])
+        .await;
+        assert!(allowed.allowed);
+        assert_eq!(allowed.policy_mode, "rego");
+        assert_eq!(allowed.query.as_deref(), Some(REGO_ALLOW_QUERY));
+        assert_eq!(allowed.resolved_path.as_deref(), Some(sh_path.as_str()));
+        assert_eq!(allowed.sha256.as_ref().map(String::len), Some(64));
+        assert!(!marker.exists(), "policy check must not run the command");
+
+        let denied = check(vec!["-c".to_string()]).await;
+        assert!(!denied.allowed);
+        assert!(
+            denied
+                .reason
+                .as_deref()
+                .is_some_and(|reason| reason.contains("Command not allowed"))
+        );
+
+        server_task.abort();
+    }
+}
@@ -0,0 +1,268 @@
+//! `mcp-run diff-policy`: replays recorded invocations through two policy directories and
+//! reports each one whose decision differs, so a policy change can be reviewed for what it
+//! newly allows or denies before it is deployed.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::policy::{InvocationContext, PolicyEngine, normalize_lexically};
+use crate::policy_test::load_offline_engine;
+
+/// Audit record sources that are command invocations; other records in a corpus are skipped.
+const INVOCATION_SOURCES: [&str; 4] = ["mcp", "raw", "job", "run"];
+
+#[derive(Debug, Error)]
+pub enum PolicyDiffError {
+    #[error("usage: mcp-run diff-policy --old <POLICY_DIR> --new <POLICY_DIR> --corpus <FILE>")]
+    Usage,
+    #[error("policy '{}' failed to load: {message}", dir.display())]
+    Load { dir: PathBuf, message: String },
+    #[error("failed to read '{}': {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid corpus line {line} in '{}': {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+/// One recorded invocation: an `MCP_AUDIT_LOG` line, or a hand-written line with the same
+/// fields. Audit records only carry env keys, which are replayed with empty values unless
+/// `env` is given.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CorpusEntry {
+    source: Option<String>,
+    executable: String,
+    resolved_path: Option<String>,
+    sha256: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env_keys: Vec<String>,
+    env: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    cwd: String,
+    client_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyDiffChange {
+    /// 1-based line of the invocation in the corpus.
+    pub line: usize,
+    pub executable: String,
+    pub args: Vec<String>,
+    /// The deny reason under each policy, or `None` where it is allowed.
+    pub old_denial: Option<String>,
+    pub new_denial: Option<String>,
+}
+
+impl PolicyDiffChange {
+    pub fn newly_allowed(&self) -> bool {
+        self.new_denial.is_none()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PolicyDiffReport {
+    /// Invocations replayed through both policies.
+    pub evaluated: usize,
+    pub changes: Vec<PolicyDiffChange>,
+}
+
+impl PolicyDiffReport {
+    pub fn newly_allowed(&self) -> usize {
+        self.changes.iter().filter(|change| change.newly_allowed()).count()
+    }
+
+    pub fn newly_denied(&self) -> usize {
+        self.changes.len() - self.newly_allowed()
+    }
+}
+
+/// Runs `mcp-run diff-policy --old <dir> --new <dir> --corpus <file>`.
+pub fn policy_diff_from_args(args: &[String]) -> Result<PolicyDiffReport, PolicyDiffError> {
+    let (mut old, mut new, mut corpus) = (None, None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--old" => &mut old,
+            "--new" => &mut new,
+            "--corpus" => &mut corpus,
+            _ => return Err(PolicyDiffError::Usage),
+        };
+        *slot = Some(PathBuf::from(args.next().ok_or(PolicyDiffError::Usage)?));
+    }
+    match (old, new, corpus) {
+        (Some(old), Some(new), Some(corpus)) => diff_policies(&old, &new, &corpus),
+        _ => Err(PolicyDiffError::Usage),
+    }
+}
+
+/// Loads both policy directories as `mcp-run policy test` does and evaluates every
+/// invocation in `corpus`, one JSON object per line, against each.
+pub fn diff_policies(
+    old_dir: &Path,
+    new_dir: &Path,
+    corpus: &Path,
+) -> Result<PolicyDiffReport, PolicyDiffError> {
+    let load = |dir: &Path| {
+        load_offline_engine(dir).map_err(|message| PolicyDiffError::Load {
+            dir: dir.to_path_buf(),
+            message,
+        })
+    };
+    let old = load(old_dir)?;
+    let new = load(new_dir)?;
+    let contents = std::fs::read_to_string(corpus).map_err(|source| PolicyDiffError::Read {
+        path: corpus.to_path_buf(),
+        source,
+    })?;
+
+    let mut report = PolicyDiffReport::default();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: CorpusEntry =
+            serde_json::from_str(line).map_err(|source| PolicyDiffError::Parse {
+                path: corpus.to_path_buf(),
+                line: index + 1,
+                source,
+            })?;
+        if entry
+            .source
+            .as_deref()
+            .is_some_and(|source| !INVOCATION_SOURCES.contains(&source))
+        {
+            continue;
+        }
+        report.evaluated += 1;
+        let old_denial = evaluate_entry(&old, &entry).err();
+        let new_denial = evaluate_entry(&new, &entry).err();
+        if old_denial.is_some() != new_denial.is_some() {
+            report.changes.push(PolicyDiffChange {
+                line: index + 1,
+                executable: entry.executable,
+                args: entry.args,
+                old_denial,
+                new_denial,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// The checks of a real request, with the recorded path and hash standing in for resolving
+/// the executable.
+fn evaluate_entry(engine: &PolicyEngine, entry: &CorpusEntry) -> Result<(), String> {
+    let env = entry.env.clone().unwrap_or_else(|| {
+        entry
+            .env_keys
+            .iter()
+            .map(|key| (key.clone(), String::new()))
+            .collect()
+    });
+    let context = InvocationContext {
+        client_id: entry.client_id.clone(),
+        cwd: (!entry.cwd.is_empty()).then(|| normalize_lexically(Path::new(&entry.cwd))),
+        canonical_cwd: None,
+        hops: 0,
+    };
+    engine
+        .authorize_invocation(
+            &entry.executable,
+            entry.resolved_path.as_deref().unwrap_or(&entry.executable),
+            entry.sha256.as_deref().unwrap_or_default(),
+            &entry.args,
+            &env,
+            &context,
+        )
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy_dir(rego: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("main.rego"), rego).expect("write policy");
+        dir
+    }
+
+    #[test]
+    fn reports_invocations_whose_decision_changed() {
+        let old = policy_dir(
+            r#"package sandbox.main
+
+default allow = false
+
+allow if { input.command == "curl" }
+allow if { input.command == "git"; input.args[0] == "status" }
+"#,
+        );
+        let new = policy_dir(
+            r#"package sandbox.main
+
+default allow = false
+
+allow if { input.command == "curl"; input.args[0] == "-I" }
+allow if { input.command == "git" }
+"#,
+        );
+        let corpus_dir = tempfile::tempdir().expect("tempdir");
+        let corpus = corpus_dir.path().join("requests.jsonl");
+        std::fs::write(
+            &corpus,
+            [
+                r#"{"source":"raw","executable":"curl","resolvedPath":"/usr/bin/curl","args":["-I","https://example.com"],"envKeys":["HOME"],"cwd":"/workspace","decision":"allow"}"#,
+                r#"{"source":"mcp","executable":"curl","args":["-X","POST","https://example.com"],"cwd":"/workspace"}"#,
+                "",
+                r#"{"source":"cleanup","executable":"cleanup","args":["/workspace/.clones/x"],"cwd":""}"#,
+                r#"{"executable":"git","args":["push"]}"#,
+            ]
+            .join("\n"),
+        )
+        .expect("write corpus");
+
+        let report = diff_policies(old.path(), new.path(), &corpus).expect("diff runs");
+        assert_eq!(report.evaluated, 3);
+        let changed: Vec<(usize, bool)> = report
+            .changes
+            .iter()
+            .map(|change| (change.line, change.newly_allowed()))
+            .collect();
+        assert_eq!(changed, vec![(2, false), (5, true)]);
+        assert_eq!(
+            report.changes[0].new_denial.as_deref(),
+            Some("Command not allowed: curl")
+        );
+        assert_eq!((report.newly_allowed(), report.newly_denied()), (1, 1));
+    }
+
+    #[test]
+    fn usage_and_corpus_errors_are_reported() {
+        let args = ["--old", "a", "--new", "b"].map(String::from);
+        assert!(matches!(
+            policy_diff_from_args(&args),
+            Err(PolicyDiffError::Usage)
+        ));
+
+        let policy = policy_dir("package sandbox.main\n\ndefault allow = false\n");
+        let corpus = policy.path().join("requests.jsonl");
+        std::fs::write(&corpus, "{\"executable\":\"curl\"}\nnot json\n").expect("write corpus");
+        assert!(matches!(
+            diff_policies(policy.path(), policy.path(), &corpus),
+            Err(PolicyDiffError::Parse { line: 2, .. })
+        ));
+    }
+}
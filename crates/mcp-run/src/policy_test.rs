@@ -0,0 +1,279 @@
+//! `mcp-run policy test`: evaluates table-driven cases from `*_test.json` files against a
+//! policy directory, so Rego changes can be checked before the watcher loads them.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::policy::{InvocationContext, PolicyEngine, normalize_lexically};
+
+/// Suffix of the files holding test cases, searched for recursively under the policy directory.
+pub const POLICY_TEST_SUFFIX: &str = "_test.json";
+
+#[derive(Debug, Error)]
+pub enum PolicyTestError {
+    #[error("usage: mcp-run policy test [POLICY_DIR] (default: $POLICY_DIR)")]
+    Usage,
+    #[error("policy failed to load: {0}")]
+    Load(String),
+    #[error("failed to read '{}': {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid test file '{}': {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("no *{POLICY_TEST_SUFFIX} files found under '{}'", .0.display())]
+    NoTests(PathBuf),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyTestFile {
+    cases: Vec<PolicyTestCase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyTestCase {
+    name: String,
+    input: PolicyTestInput,
+    allow: bool,
+}
+
+/// The fields of the Rego input a case controls. `path` defaults to `command` and `hash` to
+/// the empty string, so cases never depend on what is installed where the tests run.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyTestInput {
+    command: String,
+    path: Option<String>,
+    #[serde(default)]
+    hash: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    cwd: Option<PathBuf>,
+    #[serde(default)]
+    context: PolicyTestContext,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyTestContext {
+    client_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyTestResult {
+    pub file: PathBuf,
+    pub name: String,
+    pub passed: bool,
+    /// The deny reason, or `allowed`.
+    pub outcome: String,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PolicyTestReport {
+    pub results: Vec<PolicyTestResult>,
+}
+
+impl PolicyTestReport {
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|result| !result.passed).count()
+    }
+}
+
+/// Runs `mcp-run policy test [POLICY_DIR]`, defaulting to `$POLICY_DIR`.
+pub fn policy_test_from_args(args: &[String]) -> Result<PolicyTestReport, PolicyTestError> {
+    let policy_dir = match args {
+        [command] if command == "test" => std::env::var("POLICY_DIR")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .ok_or(PolicyTestError::Usage)?,
+        [command, dir] if command == "test" => dir.clone(),
+        _ => return Err(PolicyTestError::Usage),
+    };
+    run_policy_tests(Path::new(&policy_dir))
+}
+
+/// Loads `policy_dir` as the server would and evaluates every case in its `*_test.json`
+/// files. `{workspace}` in `cwd_pattern` rules is `$MCP_WORKSPACE`, or the current directory.
+pub fn run_policy_tests(policy_dir: &Path) -> Result<PolicyTestReport, PolicyTestError> {
+    let engine = load_offline_engine(policy_dir).map_err(PolicyTestError::Load)?;
+
+    let mut files = Vec::new();
+    collect_test_files(policy_dir, &mut files).map_err(|source| PolicyTestError::Read {
+        path: policy_dir.to_path_buf(),
+        source,
+    })?;
+    if files.is_empty() {
+        return Err(PolicyTestError::NoTests(policy_dir.to_path_buf()));
+    }
+    files.sort();
+
+    let mut report = PolicyTestReport::default();
+    for file in files {
+        let contents = std::fs::read_to_string(&file).map_err(|source| PolicyTestError::Read {
+            path: file.clone(),
+            source,
+        })?;
+        let tests: PolicyTestFile =
+            serde_json::from_str(&contents).map_err(|source| PolicyTestError::Parse {
+                path: file.clone(),
+                source,
+            })?;
+        for case in tests.cases {
+            let outcome = evaluate_case(&engine, &case.input);
+            report.results.push(PolicyTestResult {
+                file: file.clone(),
+                name: case.name,
+                passed: outcome.is_ok() == case.allow,
+                outcome: outcome.err().unwrap_or_else(|| "allowed".to_string()),
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Loads `policy_dir` for evaluation outside the server, with `{workspace}` set to
+/// `$MCP_WORKSPACE` or the current directory.
+pub(crate) fn load_offline_engine(policy_dir: &Path) -> Result<PolicyEngine, String> {
+    let workspace = std::env::var("MCP_WORKSPACE")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok());
+    let mut engine = PolicyEngine::try_from_dir(policy_dir.to_path_buf())?;
+    if let Some(workspace) = workspace {
+        engine = engine.with_workspace(workspace);
+    }
+    Ok(engine)
+}
+
+/// Applies the same checks as a real request, including `cwd_pattern`, `env_keys` and
+/// `validity`, but never resolves or runs the executable.
+fn evaluate_case(engine: &PolicyEngine, input: &PolicyTestInput) -> Result<(), String> {
+    let context = InvocationContext {
+        client_id: input.context.client_id.clone(),
+        cwd: input.cwd.as_deref().map(normalize_lexically),
+        canonical_cwd: None,
+        hops: 0,
+    };
+    engine
+        .authorize_invocation(
+            &input.command,
+            input.path.as_deref().unwrap_or(&input.command),
+            &input.hash,
+            &input.args,
+            &input.env,
+            &context,
+        )
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
+fn collect_test_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_test_files(&path, out)?;
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(POLICY_TEST_SUFFIX))
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN_REGO: &str = r#"package sandbox.main
+
+default allow = false
+
+allow if {
+  input.command == "curl"
+  input.args == ["-I", "https://example.com"]
+}
+"#;
+
+    #[test]
+    fn reports_pass_and_fail_per_case() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("main.rego"), MAIN_REGO).expect("write policy");
+        std::fs::create_dir(dir.path().join("curl")).expect("mkdir");
+        std::fs::write(
+            dir.path().join("curl/curl_test.json"),
+            r#"{"cases": [
+                {"name": "head allowed", "allow": true,
+                 "input": {"command": "curl", "args": ["-I", "https://example.com"]}},
+                {"name": "post denied", "allow": false,
+                 "input": {"command": "curl", "args": ["-X", "POST", "https://example.com"]}},
+                {"name": "wrong expectation", "allow": true,
+                 "input": {"command": "wget"}}
+            ]}"#,
+        )
+        .expect("write tests");
+
+        let report = run_policy_tests(dir.path()).expect("tests run");
+        let summary: Vec<(&str, bool)> = report
+            .results
+            .iter()
+            .map(|result| (result.name.as_str(), result.passed))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("head allowed", true),
+                ("post denied", true),
+                ("wrong expectation", false),
+            ]
+        );
+        assert_eq!(report.results[2].outcome, "Command not allowed: wget");
+        assert_eq!(report.failed(), 1);
+    }
+
+    #[test]
+    fn load_and_file_errors_are_reported() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("main.rego"), MAIN_REGO).expect("write policy");
+        assert!(matches!(
+            run_policy_tests(dir.path()),
+            Err(PolicyTestError::NoTests(_))
+        ));
+
+        std::fs::write(
+            dir.path().join("x_test.json"),
+            r#"{"cases": [{"name": "x"}]}"#,
+        )
+        .expect("write tests");
+        assert!(matches!(
+            run_policy_tests(dir.path()),
+            Err(PolicyTestError::Parse { .. })
+        ));
+
+        std::fs::write(
+            dir.path().join("main.rego"),
+            "package sandbox.main\nallow if {",
+        )
+        .expect("write policy");
+        assert!(matches!(
+            run_policy_tests(dir.path()),
+            Err(PolicyTestError::Load(_))
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+//! Pseudo-terminals for `/raw` commands run with `ptyMode`, so tools that only prompt when
+//! attached to a terminal (`npm init`, interactive installers) can be driven remotely.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::process::Command;
+
+/// Window size of a command's terminal, set at spawn and changed by `resize` frames.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct TerminalSize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl Default for TerminalSize {
+    fn default() -> Self {
+        Self { rows: 24, cols: 80 }
+    }
+}
+
+/// The server's side of a command's terminal: reads return what the command wrote to the
+/// terminal, and writes are typed into it.
+#[cfg(unix)]
+#[derive(Debug)]
+pub(crate) struct PtyMaster {
+    fd: tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>,
+}
+
+#[cfg(unix)]
+impl PtyMaster {
+    /// Opens a terminal of `size`, returning the server's side and the command's side, which
+    /// [`attach_terminal`] hands to the command.
+    pub(crate) fn open(size: TerminalSize) -> io::Result<(Self, std::os::fd::OwnedFd)> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        let mut master = -1;
+        let mut terminal = -1;
+        let mut window = window_size(size);
+        // SAFETY: both out-pointers are valid, no name buffer is requested and `window` is a
+        // live `winsize`.
+        let rc = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut terminal,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                // Mutable where some platforms declare it so, though it is only read.
+                &raw mut window,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `openpty` succeeded, so both are open descriptors that nothing else owns.
+        let (master, terminal) =
+            unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(terminal)) };
+        for fd in [master.as_raw_fd(), terminal.as_raw_fd()] {
+            set_fd_flag(fd, libc::F_GETFD, libc::F_SETFD, libc::FD_CLOEXEC)?;
+        }
+        set_fd_flag(master.as_raw_fd(), libc::F_GETFL, libc::F_SETFL, libc::O_NONBLOCK)?;
+        Ok((
+            Self {
+                fd: tokio::io::unix::AsyncFd::new(master)?,
+            },
+            terminal,
+        ))
+    }
+
+    /// Another handle on the same terminal, so output can be read while input is written.
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            fd: tokio::io::unix::AsyncFd::new(self.fd.get_ref().try_clone()?)?,
+        })
+    }
+
+    /// Changes the window size; the command gets `SIGWINCH`.
+    pub(crate) fn resize(&self, size: TerminalSize) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let window = window_size(size);
+        // SAFETY: `TIOCSWINSZ` reads one `winsize`, which `window` is.
+        if unsafe { libc::ioctl(self.fd.get_ref().as_raw_fd(), libc::TIOCSWINSZ, &window) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn window_size(size: TerminalSize) -> libc::winsize {
+    libc::winsize {
+        ws_row: size.rows,
+        ws_col: size.cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+#[cfg(unix)]
+fn set_fd_flag(
+    fd: libc::c_int,
+    get: libc::c_int,
+    set: libc::c_int,
+    flag: libc::c_int,
+) -> io::Result<()> {
+    // SAFETY: `fd` is open for the duration of the call and `fcntl` only reads or sets flags.
+    let flags = unsafe { libc::fcntl(fd, get) };
+    if flags < 0 || unsafe { libc::fcntl(fd, set, flags | flag) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Makes `terminal` the command's stdin, stdout, stderr and controlling terminal. The command
+/// leads a new session, and so its own process group, which timeouts and kills signal as usual.
+#[cfg(unix)]
+pub(crate) fn attach_terminal(
+    command: &mut Command,
+    terminal: std::os::fd::OwnedFd,
+) -> io::Result<()> {
+    use std::process::Stdio;
+
+    command
+        .stdin(Stdio::from(terminal.try_clone()?))
+        .stdout(Stdio::from(terminal.try_clone()?))
+        .stderr(Stdio::from(terminal));
+    // SAFETY: the hook only makes raw syscalls, which are async-signal-safe, and does not
+    // allocate or take locks.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+impl AsyncRead for PtyMaster {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        use std::os::fd::AsRawFd;
+
+        loop {
+            let mut guard = std::task::ready!(self.fd.poll_read_ready(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            let read = guard.try_io(|fd| {
+                // SAFETY: `unfilled` is initialized and writable for its whole length.
+                let read = unsafe {
+                    libc::read(fd.as_raw_fd(), unfilled.as_mut_ptr().cast(), unfilled.len())
+                };
+                if read < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(read as usize)
+            });
+            match read {
+                Ok(Ok(read)) => {
+                    buf.advance(read);
+                    return Poll::Ready(Ok(()));
+                }
+                // Linux reports EIO once every process has closed the terminal; that is the
+                // end of the command's output.
+                Ok(Err(error)) if error.raw_os_error() == Some(libc::EIO) => {
+                    return Poll::Ready(Ok(()));
+                }
+                Ok(Err(error)) => return Poll::Ready(Err(error)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl AsyncWrite for PtyMaster {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        use std::os::fd::AsRawFd;
+
+        loop {
+            let mut guard = std::task::ready!(self.fd.poll_write_ready(cx))?;
+            let written = guard.try_io(|fd| {
+                // SAFETY: `buf` is readable for its whole length.
+                let written =
+                    unsafe { libc::write(fd.as_raw_fd(), buf.as_ptr().cast(), buf.len()) };
+                if written < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(written as usize)
+            });
+            match written {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Off unix there are no pseudo-terminals; [`PtyMaster::open`] always fails, so no value of
+/// this type is ever made.
+#[cfg(not(unix))]
+#[derive(Debug)]
+pub(crate) struct PtyMaster(std::convert::Infallible);
+
+#[cfg(not(unix))]
+impl PtyMaster {
+    pub(crate) fn open(_size: TerminalSize) -> io::Result<(Self, std::convert::Infallible)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "pseudo-terminals are only available on unix",
+        ))
+    }
+
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        match self.0 {}
+    }
+
+    pub(crate) fn resize(&self, _size: TerminalSize) -> io::Result<()> {
+        match self.0 {}
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn attach_terminal(
+    _command: &mut Command,
+    terminal: std::convert::Infallible,
+) -> io::Result<()> {
+    match terminal {}
+}
+
+#[cfg(not(unix))]
+impl AsyncRead for PtyMaster {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.0 {}
+    }
+}
+
+#[cfg(not(unix))]
+impl AsyncWrite for PtyMaster {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.0 {}
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.0 {}
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.0 {}
+    }
+}
@@ -1,90 +1,292 @@
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
 use axum::Json;
-use axum::body::{Body, Bytes};
-use axum::extract::{State, rejection::JsonRejection};
-use axum::http::{HeaderValue, StatusCode, header};
+use axum::body::{Body, BodyDataStream, Bytes};
+use axum::extract::{FromRequest, Path, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use base64::Engine as _;
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use tokio::io::AsyncReadExt;
-use tokio::process::{Child, ChildStderr, ChildStdout};
-use tokio::sync::mpsc;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout};
+use tokio::sync::{Notify, mpsc, watch};
 use tokio_stream::wrappers::ReceiverStream;
+use tracing::Instrument;
 
-use crate::executor::{RunNetworkToolInput, ToolError, spawn_network_tool_process};
-use crate::policy::PolicyEngine;
+use crate::admin::AdminKilled;
+use crate::audit::AuditSource;
+use crate::body_limit::{RequestLimits, json_depth_exceeds};
+use crate::client_id::context_from_headers;
+use crate::executions::ExecutionRegistry;
+use crate::executor::{
+    OutputLimits, ResourceUsage, RunNetworkToolInput, ToolError, kill_process_group,
+    spawn_audited_process, spawn_audited_process_on_pty, spawn_audited_process_with_stdin_pipe,
+    spawn_exit_watch, stop_process_group,
+};
+use crate::jobs::JobRegistry;
+use crate::limiter::{RetryGuidance, StreamLimiter};
+use crate::metrics::record_child_exit;
+use crate::policy::{InvocationContext, PolicyEngine};
+use crate::pty::{PtyMaster, TerminalSize};
+use crate::redaction::redact_env_values;
+use crate::request_id::current_request_id;
+use crate::self_invocation::hops_from_headers;
+use crate::spill::OutputSpills;
+
+/// Version of the `/raw` NDJSON event format, bumped on incompatible changes.
+pub const RAW_PROTOCOL_VERSION: u32 = 1;
+/// How long `POST /raw/{id}/kill` lets the process group exit after SIGTERM before killing it.
+pub const RAW_KILL_GRACE: Duration = Duration::from_secs(5);
+const CLIENT_DISCONNECTED: &str = "Client disconnected";
+pub(crate) const RAW_KILLED: &str = "Killed by request";
 
 #[derive(Debug, Clone)]
 pub struct RawEndpointState {
     pub policy_engine: Arc<PolicyEngine>,
     pub default_cwd: PathBuf,
+    pub raw_streams: Arc<StreamLimiter>,
+    /// Server-wide limit on running commands, shared with the MCP tool handler.
+    pub executions: Arc<StreamLimiter>,
+    pub jobs: Arc<JobRegistry>,
+    pub runs: Arc<ExecutionRegistry>,
+    /// Running `/raw` commands, by the id in their `start` event.
+    pub raw_commands: Arc<RawCommandRegistry>,
+    /// Output kept per stream by `POST /run`, as for the MCP tool.
+    pub output_limits: OutputLimits,
+    /// Truncated `POST /run` and MCP tool output kept for `GET /output/{token}`.
+    pub spills: Arc<OutputSpills>,
+    /// `max_body_bytes` also bounds each line of a streamed request.
+    pub request_limits: RequestLimits,
+    /// Requests relayed through more servers than this are refused with `508`.
+    pub max_hops: u32,
+    /// Whether `POST /admin/grants` may create grants, which takes an admin token that the
+    /// commands the server runs do not hold.
+    pub grants_enabled: bool,
+    /// Whether every route but `/egress/domains` requires a bearer token.
+    pub auth_required: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RawErrorBody {
     pub error: String,
+    /// Back-off hint, present when a concurrency or stream limit rejected the request.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryGuidance>,
+    /// Id of the rejected request, as in the server's log lines.
+    #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(tag = "event", rename_all = "lowercase")]
 pub enum RawStreamEvent {
-    Start {},
+    Start {
+        /// Id for `POST /raw/{id}/kill`; absent from `/jobs` streams and from servers that
+        /// predate it.
+        #[serde(rename = "executionId", default, skip_serializing_if = "Option::is_none")]
+        execution_id: Option<String>,
+        /// Id of the request that opened the stream, as in the server's log lines; absent
+        /// from servers that predate it.
+        #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+    },
     Stdout {
         data_b64: String,
     },
     Stderr {
         data_b64: String,
     },
+    /// Output of a `ptyMode` command, whose stdout and stderr are the same terminal. Raw
+    /// terminal bytes: escape sequences, `\r\n` line endings and the echo of typed input.
+    Terminal {
+        data_b64: String,
+    },
     Exit {
         #[serde(rename = "exitCode")]
         exit_code: Option<i32>,
+        /// Wall-clock time since spawn; absent from servers that predate it.
+        #[serde(rename = "durationMs", default, skip_serializing_if = "Option::is_none")]
+        duration_ms: Option<u64>,
+        /// As in `RunNetworkToolOutput`; only reported on unix.
+        #[serde(rename = "resourceUsage", default, skip_serializing_if = "Option::is_none")]
+        resource_usage: Option<ResourceUsage>,
     },
     Error {
         message: String,
     },
 }
 
+/// Running `/raw` commands, so a client that gave up on a stream can still stop its command.
+#[derive(Debug, Default)]
+pub struct RawCommandRegistry {
+    table: Mutex<RawCommandTable>,
+}
+
+#[derive(Debug, Default)]
+struct RawCommandTable {
+    next_id: u64,
+    commands: BTreeMap<u64, Arc<RawCommand>>,
+}
+
+#[derive(Debug)]
+struct RawCommand {
+    kill: Notify,
+    /// Set once the stream acted on a kill request.
+    killed: AtomicBool,
+    /// `true` once the command's stream has ended and it left the registry.
+    finished: watch::Sender<bool>,
+}
+
+impl RawCommandRegistry {
+    fn table(&self) -> MutexGuard<'_, RawCommandTable> {
+        self.table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Adds a command that stays listed until the handle is dropped. Called while handling
+    /// the request that runs it, whose id the handle keeps.
+    fn register(self: &Arc<Self>) -> RawCommandHandle {
+        let command = Arc::new(RawCommand {
+            kill: Notify::new(),
+            killed: AtomicBool::new(false),
+            finished: watch::Sender::new(false),
+        });
+        let mut table = self.table();
+        table.next_id += 1;
+        let id = table.next_id;
+        table.commands.insert(id, command.clone());
+        RawCommandHandle {
+            id,
+            request_id: current_request_id(),
+            command,
+            registry: self.clone(),
+        }
+    }
+
+    /// Asks the command's stream to stop it and waits for the stream to end. Returns whether
+    /// it was killed, or `None` for an unknown or finished id.
+    pub(crate) async fn kill(&self, execution_id: &str) -> Option<bool> {
+        let id = execution_id.strip_prefix("raw-")?.parse().ok()?;
+        let command = self.table().commands.get(&id).cloned()?;
+        let mut finished = command.finished.subscribe();
+        command.kill.notify_one();
+        let _ = tokio::time::timeout(RAW_KILL_GRACE * 2, finished.wait_for(|done| *done)).await;
+        Some(command.killed.load(Ordering::SeqCst))
+    }
+}
+
+struct RawCommandHandle {
+    id: u64,
+    request_id: Option<String>,
+    command: Arc<RawCommand>,
+    registry: Arc<RawCommandRegistry>,
+}
+
+impl RawCommandHandle {
+    fn execution_id(&self) -> String {
+        format!("raw-{}", self.id)
+    }
+
+    /// Resolves when `POST /raw/{id}/kill` asks for the command to stop.
+    async fn kill_requested(&self) {
+        self.command.kill.notified().await;
+        self.command.killed.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for RawCommandHandle {
+    fn drop(&mut self) {
+        self.registry.table().commands.remove(&self.id);
+        self.command.finished.send_replace(true);
+    }
+}
+
+/// A line after the request in an `application/x-ndjson` request body, fed to stdin.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum RawStdinFrame {
+    Stdin { data_b64: String },
+    /// Resizes a `ptyMode` command's terminal; ignored for piped stdin.
+    Resize { rows: u16, cols: u16 },
+}
+
+/// Where streamed stdin frames go: the command's stdin pipe or its terminal.
+trait StdinSink: AsyncWrite + Unpin + Send + 'static {
+    fn resize(&self, _size: TerminalSize) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl StdinSink for ChildStdin {}
+
+impl StdinSink for PtyMaster {
+    fn resize(&self, size: TerminalSize) -> std::io::Result<()> {
+        PtyMaster::resize(self, size)
+    }
+}
+
+/// What a `/raw` command writes to: separate stdout and stderr pipes, or one terminal.
+enum RawOutput {
+    Pipes(ChildStdout, ChildStderr),
+    Terminal(PtyMaster),
+}
+
+/// The rest of a streamed request body once the request line has been read.
+struct StreamedStdin {
+    pending: Vec<u8>,
+    body: BodyDataStream,
+}
+
 #[derive(Debug, Clone, Copy)]
-enum OutputStreamKind {
+pub(crate) enum OutputStreamKind {
     Stdout,
     Stderr,
+    Terminal,
 }
 
 impl OutputStreamKind {
-    fn as_str(self) -> &'static str {
+    pub(crate) fn as_str(self) -> &'static str {
         match self {
             OutputStreamKind::Stdout => "stdout",
             OutputStreamKind::Stderr => "stderr",
+            OutputStreamKind::Terminal => "terminal",
         }
     }
 }
 
 #[derive(Debug)]
-enum ReaderEvent {
+pub(crate) enum ReaderEvent {
     Chunk {
         stream: OutputStreamKind,
         data: Vec<u8>,
     },
-    Done {
-        stream: OutputStreamKind,
-    },
+    Done,
     ReadError {
         stream: OutputStreamKind,
         message: String,
     },
 }
 
-pub async fn raw_handler(
-    State(state): State<RawEndpointState>,
-    payload: Result<Json<RunNetworkToolInput>, JsonRejection>,
-) -> Response {
-    let input = match payload {
-        Ok(Json(input)) => input,
+pub async fn raw_handler(State(state): State<RawEndpointState>, request: Request) -> Response {
+    let headers = request.headers().clone();
+    // An NDJSON body carries the request on its first line and stdin frames after it.
+    let payload = if is_ndjson(&headers) {
+        read_streamed_request(request.into_body(), state.request_limits)
+            .await
+            .map(|(input, stdin)| (input, Some(stdin)))
+    } else {
+        Json::<RunNetworkToolInput>::from_request(request, &())
+            .await
+            .map(|Json(input)| (input, None))
+            .map_err(|error| error.to_string())
+    };
+    let (input, streamed_stdin) = match payload {
+        Ok(payload) => payload,
         Err(error) => {
             tracing::warn!(error = %error, "raw request rejected before validation");
             return error_response(
@@ -93,15 +295,102 @@ pub async fn raw_handler(
             );
         }
     };
+    if streamed_stdin.is_some() && input.stdin_b64.is_some() {
+        tracing::warn!(command = %input.executable, "raw request rejected before validation");
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "stdinB64 cannot be combined with streamed stdin".to_string(),
+        );
+    }
+    let context = match context_from_headers(&headers) {
+        Ok(context) => context,
+        Err(error) => {
+            tracing::warn!(error = %error, "raw request rejected before validation");
+            return error_response(StatusCode::BAD_REQUEST, error.to_string());
+        }
+    };
+    let hops = match hops_from_headers(&headers) {
+        Ok(hops) => hops,
+        Err(error) => {
+            tracing::warn!(error = %error, "raw request rejected before validation");
+            return error_response(StatusCode::BAD_REQUEST, error.to_string());
+        }
+    };
+    if hops > state.max_hops {
+        tracing::warn!(command = %input.executable, hops, max_hops = state.max_hops, "raw request rejected: too many hops");
+        return error_response(
+            StatusCode::LOOP_DETECTED,
+            format!(
+                "Request passed through {hops} mcp-run servers (limit {}); a command is calling \
+                 back into the server",
+                state.max_hops
+            ),
+        );
+    }
+    let context = InvocationContext { hops, ..context };
+    let client_id = context.client_id.clone().unwrap_or_default();
 
     let executable = input.executable.clone();
-    let args_for_log = input.args.clone();
+    // Until the policy names the sensitive env keys, every env value is kept out of the logs.
+    let args_for_log = redact_env_values(
+        &input.args,
+        input.env.as_ref().unwrap_or(&BTreeMap::new()),
+        |_| true,
+    );
+    let timeout_ms = input.timeout_ms;
 
-    let mut child = match spawn_network_tool_process(&state.policy_engine, &state.default_cwd, input) {
-        Ok(child) => child,
+    // Admission happens before spawning so a rejected request never leaves a child behind.
+    let Some(permit) = state.raw_streams.acquire().await else {
+        tracing::warn!(command = %executable, args = ?args_for_log, "raw request rejected: stream limit reached");
+        return limit_response(
+            "Too many concurrent raw streams".to_string(),
+            state.raw_streams.retry_guidance(),
+        );
+    };
+    let Some(execution_permit) = state.executions.try_acquire() else {
+        let limit = state.executions.limits().max_active.unwrap_or_default();
+        tracing::warn!(command = %executable, args = ?args_for_log, limit, "raw request rejected: concurrency limit reached");
+        return limit_response(
+            ToolError::ConcurrencyLimit { limit }.to_string(),
+            state.executions.retry_guidance(),
+        );
+    };
+
+    let pty_mode = input.pty_mode == Some(true);
+    let spawned = if pty_mode {
+        spawn_audited_process_on_pty(
+            &state.policy_engine,
+            &state.default_cwd,
+            input,
+            &context,
+            AuditSource::Raw,
+        )
+        .map(|(child, master, audit)| (child, Some(master), audit))
+    } else {
+        let spawn = if streamed_stdin.is_some() {
+            spawn_audited_process_with_stdin_pipe
+        } else {
+            spawn_audited_process
+        };
+        spawn(
+            &state.policy_engine,
+            &state.default_cwd,
+            input,
+            &context,
+            AuditSource::Raw,
+        )
+        .map(|(child, audit)| (child, None, audit))
+    };
+    let (mut child, master, audit) = match spawned {
+        Ok(spawned) => spawned,
         Err(ToolError::Validation(error)) => {
-            tracing::warn!(command = %executable, args = ?args_for_log, error = %error, "raw request denied by policy");
-            return error_response(StatusCode::FORBIDDEN, error.to_string());
+            tracing::warn!(command = %executable, args = ?args_for_log, client_id, error = %error, "raw request denied by policy");
+            let message = state.policy_engine.denial_message(&error);
+            return error_response(StatusCode::FORBIDDEN, message);
+        }
+        Err(error @ ToolError::InvalidStdin { .. }) => {
+            tracing::warn!(command = %executable, args = ?args_for_log, error = %error, "raw request rejected before validation");
+            return error_response(StatusCode::BAD_REQUEST, error.to_string());
         }
         Err(error) => {
             tracing::error!(command = %executable, args = ?args_for_log, error = %error, "raw request failed before stream start");
@@ -109,40 +398,100 @@ pub async fn raw_handler(
         }
     };
 
-    let stdout = match child.stdout.take() {
-        Some(stdout) => stdout,
-        None => {
-            terminate_child(&mut child).await;
-            tracing::error!(command = %executable, args = ?args_for_log, "stdout pipe missing");
-            return error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "stdout pipe missing".to_string(),
-            );
+    let args_for_log = audit.args.clone();
+
+    let output = match master {
+        Some(master) => {
+            // Stdin frames are typed into the terminal while the stream reads it.
+            if let Some(streamed_stdin) = streamed_stdin {
+                match master.try_clone() {
+                    Ok(input) => {
+                        tokio::spawn(
+                            forward_stdin(
+                                streamed_stdin,
+                                input,
+                                state.request_limits.max_body_bytes,
+                            )
+                            .in_current_span(),
+                        );
+                    }
+                    Err(error) => {
+                        terminate_child(&mut child).await;
+                        audit.fail(&error);
+                        tracing::error!(command = %executable, args = ?args_for_log, error = %error, "raw terminal input setup failure");
+                        return error_response(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            format!("Failed to open terminal input: {error}"),
+                        );
+                    }
+                }
+            }
+            RawOutput::Terminal(master)
         }
-    };
-    let stderr = match child.stderr.take() {
-        Some(stderr) => stderr,
         None => {
-            terminate_child(&mut child).await;
-            tracing::error!(command = %executable, args = ?args_for_log, "stderr pipe missing");
-            return error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "stderr pipe missing".to_string(),
-            );
+            let stdout = match child.stdout.take() {
+                Some(stdout) => stdout,
+                None => {
+                    terminate_child(&mut child).await;
+                    audit.fail(&"stdout pipe missing");
+                    tracing::error!(command = %executable, args = ?args_for_log, "stdout pipe missing");
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "stdout pipe missing".to_string(),
+                    );
+                }
+            };
+            let stderr = match child.stderr.take() {
+                Some(stderr) => stderr,
+                None => {
+                    terminate_child(&mut child).await;
+                    audit.fail(&"stderr pipe missing");
+                    tracing::error!(command = %executable, args = ?args_for_log, "stderr pipe missing");
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "stderr pipe missing".to_string(),
+                    );
+                }
+            };
+            if let (Some(streamed_stdin), Some(stdin)) = (streamed_stdin, child.stdin.take()) {
+                tokio::spawn(
+                    forward_stdin(streamed_stdin, stdin, state.request_limits.max_body_bytes)
+                        .in_current_span(),
+                );
+            }
+            RawOutput::Pipes(stdout, stderr)
         }
     };
 
-    tracing::info!(command = %executable, args = ?args_for_log, "raw request accepted");
+    tracing::info!(command = %executable, args = ?args_for_log, client_id, pty_mode, "raw request accepted");
 
     let (tx, rx) = mpsc::channel::<Bytes>(64);
-    tokio::spawn(stream_process_events(
-        child,
-        stdout,
-        stderr,
-        tx,
-        executable,
-        args_for_log,
-    ));
+    let command = state.raw_commands.register();
+    tokio::spawn(
+        async move {
+            // The slots are released only after the stream has finished and the child is
+            // reaped.
+            let _permits = (permit, execution_permit);
+            match stream_process_events(
+                child,
+                output,
+                tx,
+                &command,
+                executable,
+                args_for_log,
+                timeout_ms,
+            )
+            .await
+            {
+                // `/raw` streams everything, so output is never truncated.
+                Ok((exit_code, resource_usage)) => {
+                    audit.complete(exit_code, resource_usage, false, false)
+                }
+                Err(error) => audit.fail(&error),
+            }
+        }
+        .in_current_span(),
+    );
 
     let body_stream = ReceiverStream::new(rx).map(Ok::<_, Infallible>);
     let mut response = Response::new(Body::from_stream(body_stream));
@@ -154,90 +503,133 @@ pub async fn raw_handler(
     response
 }
 
+/// Streams the child's output until it exits. Returns its exit code and resource usage, or
+/// why the stream ended early.
 async fn stream_process_events(
     mut child: Child,
-    stdout: ChildStdout,
-    stderr: ChildStderr,
+    output: RawOutput,
     tx: mpsc::Sender<Bytes>,
+    command: &RawCommandHandle,
     executable: String,
     args: Vec<String>,
-) {
+    timeout_ms: Option<u64>,
+) -> Result<(Option<i32>, Option<ResourceUsage>), String> {
     let started = Instant::now();
-    if !send_event(&tx, &RawStreamEvent::Start {}).await {
+    let start = RawStreamEvent::Start {
+        execution_id: Some(command.execution_id()),
+        request_id: command.request_id.clone(),
+    };
+    if !send_event(&tx, &start).await {
         tracing::info!(command = %executable, args = ?args, "raw client disconnected before start event");
         terminate_child(&mut child).await;
-        return;
+        return Err(CLIENT_DISCONNECTED.to_string());
     }
 
     let (reader_tx, mut reader_rx) = mpsc::channel::<ReaderEvent>(64);
-    tokio::spawn(read_output_stream(
-        stdout,
-        OutputStreamKind::Stdout,
-        reader_tx.clone(),
-    ));
-    tokio::spawn(read_output_stream(
-        stderr,
-        OutputStreamKind::Stderr,
-        reader_tx,
-    ));
-
-    let mut stdout_done = false;
-    let mut stderr_done = false;
+    let mut open_streams = match output {
+        RawOutput::Pipes(stdout, stderr) => {
+            tokio::spawn(
+                read_output_stream(stdout, OutputStreamKind::Stdout, reader_tx.clone())
+                    .in_current_span(),
+            );
+            tokio::spawn(
+                read_output_stream(stderr, OutputStreamKind::Stderr, reader_tx)
+                    .in_current_span(),
+            );
+            2
+        }
+        RawOutput::Terminal(master) => {
+            tokio::spawn(
+                read_output_stream(master, OutputStreamKind::Terminal, reader_tx)
+                    .in_current_span(),
+            );
+            1
+        }
+    };
     let mut exit_code: Option<Option<i32>> = None;
+    // Reaping waits for the exit watch so the child's rusage is read before it is gone.
+    let mut exit_watch = spawn_exit_watch(&child);
+    let mut exit_watched = false;
+    let mut resource_usage = None;
+
+    let deadline = async {
+        match timeout_ms {
+            Some(timeout_ms) => tokio::time::sleep(Duration::from_millis(timeout_ms)).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
 
     loop {
         tokio::select! {
-            status = child.wait(), if exit_code.is_none() => {
+            _ = &mut deadline => {
+                let timeout_ms = timeout_ms.unwrap_or_default();
+                tracing::warn!(command = %executable, args = ?args, timeout_ms, "raw request timed out");
+                terminate_child(&mut child).await;
+                let message = ToolError::Timeout { timeout_ms }.to_string();
+                let _ = send_event(&tx, &RawStreamEvent::Error { message: message.clone() }).await;
+                return Err(message);
+            }
+            _ = command.kill_requested() => {
+                tracing::info!(command = %executable, args = ?args, execution_id = command.execution_id(), "raw command killed by request");
+                let process_group = child.id();
+                stop_process_group(&mut child, process_group, RAW_KILL_GRACE).await;
+                let message = RAW_KILLED.to_string();
+                let _ = send_event(&tx, &RawStreamEvent::Error { message: message.clone() }).await;
+                return Err(message);
+            }
+            _ = tx.closed() => {
+                tracing::info!(command = %executable, args = ?args, "raw client disconnected during stream");
+                terminate_child(&mut child).await;
+                return Err(CLIENT_DISCONNECTED.to_string());
+            }
+            usage = &mut exit_watch, if !exit_watched => {
+                exit_watched = true;
+                resource_usage = usage.ok().flatten();
+            }
+            status = child.wait(), if exit_watched && exit_code.is_none() => {
                 match status {
                     Ok(status) => {
                         exit_code = Some(status.code());
+                        record_child_exit(resource_usage);
                     }
                     Err(error) => {
                         tracing::error!(command = %executable, args = ?args, error = %error, "raw runtime wait failure");
-                        let _ = send_event(&tx, &RawStreamEvent::Error { message: format!("Runtime wait failure: {error}") }).await;
-                        return;
+                        let message = format!("Runtime wait failure: {error}");
+                        let _ = send_event(&tx, &RawStreamEvent::Error { message: message.clone() }).await;
+                        return Err(message);
                     }
                 }
             }
-            maybe_event = reader_rx.recv(), if !(stdout_done && stderr_done) => {
+            maybe_event = reader_rx.recv(), if open_streams > 0 => {
                 match maybe_event {
                     Some(ReaderEvent::Chunk { stream, data }) => {
                         let data_b64 = base64::engine::general_purpose::STANDARD.encode(data);
                         let event = match stream {
                             OutputStreamKind::Stdout => RawStreamEvent::Stdout { data_b64 },
                             OutputStreamKind::Stderr => RawStreamEvent::Stderr { data_b64 },
+                            OutputStreamKind::Terminal => RawStreamEvent::Terminal { data_b64 },
                         };
                         if !send_event(&tx, &event).await {
                             tracing::info!(command = %executable, args = ?args, "raw client disconnected during stream");
                             terminate_child(&mut child).await;
-                            return;
+                            return Err(CLIENT_DISCONNECTED.to_string());
                         }
                     }
-                    Some(ReaderEvent::Done { stream }) => match stream {
-                        OutputStreamKind::Stdout => stdout_done = true,
-                        OutputStreamKind::Stderr => stderr_done = true,
-                    },
+                    Some(ReaderEvent::Done) => open_streams -= 1,
                     Some(ReaderEvent::ReadError { stream, message }) => {
                         tracing::error!(command = %executable, args = ?args, stream = stream.as_str(), error = %message, "raw stream read failure");
-                        let _ = send_event(
-                            &tx,
-                            &RawStreamEvent::Error {
-                                message: format!("Failed reading {}: {}", stream.as_str(), message),
-                            },
-                        )
-                        .await;
+                        let message = format!("Failed reading {}: {}", stream.as_str(), message);
+                        let _ = send_event(&tx, &RawStreamEvent::Error { message: message.clone() }).await;
                         terminate_child(&mut child).await;
-                        return;
-                    }
-                    None => {
-                        stdout_done = true;
-                        stderr_done = true;
+                        return Err(message);
                     }
+                    None => open_streams = 0,
                 }
             }
         }
 
-        if exit_code.is_some() && stdout_done && stderr_done {
+        if exit_code.is_some() && open_streams == 0 {
             break;
         }
     }
@@ -247,13 +639,16 @@ async fn stream_process_events(
         &tx,
         &RawStreamEvent::Exit {
             exit_code: final_exit_code,
+            duration_ms: Some(started.elapsed().as_millis() as u64),
+            resource_usage,
         },
     )
     .await
     {
+        // The command already finished; only the client missed the exit event.
         tracing::info!(command = %executable, args = ?args, "raw client disconnected before exit event");
         terminate_child(&mut child).await;
-        return;
+        return Ok((final_exit_code, resource_usage));
     }
 
     tracing::info!(
@@ -261,11 +656,139 @@ async fn stream_process_events(
         args = ?args,
         exit_code = ?final_exit_code,
         duration_ms = started.elapsed().as_millis() as u64,
+        resource_usage = ?resource_usage,
         "raw request completed",
     );
+    Ok((final_exit_code, resource_usage))
+}
+
+/// `POST /raw/{id}/kill`: stops a running `/raw` command with SIGTERM, then SIGKILL after
+/// [`RAW_KILL_GRACE`]. Its stream ends with an `error` event.
+pub async fn raw_kill_handler(
+    State(state): State<RawEndpointState>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.raw_commands.kill(&id).await {
+        Some(killed) => Json(AdminKilled { id, killed }).into_response(),
+        None => error_response(
+            StatusCode::NOT_FOUND,
+            format!("Unknown or finished raw command: {id}"),
+        ),
+    }
+}
+
+fn is_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/x-ndjson"))
+}
+
+/// Reads the request line of a streamed body, leaving the stdin frames after it unread.
+async fn read_streamed_request(
+    body: Body,
+    limits: RequestLimits,
+) -> Result<(RunNetworkToolInput, StreamedStdin), String> {
+    let mut body = body.into_data_stream();
+    let mut buffer = Vec::new();
+    let pending = loop {
+        if let Some(newline_index) = buffer.iter().position(|byte| *byte == b'\n') {
+            let pending = buffer.split_off(newline_index + 1);
+            buffer.pop();
+            break pending;
+        }
+        if buffer.len() > limits.max_body_bytes {
+            return Err(format!("request line exceeds {} bytes", limits.max_body_bytes));
+        }
+        match body.next().await {
+            Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+            Some(Err(error)) => return Err(error.to_string()),
+            // A body without stdin frames is just the request.
+            None => break Vec::new(),
+        }
+    };
+    if json_depth_exceeds(&buffer, limits.max_json_depth) {
+        return Err(format!("request nests deeper than {} levels", limits.max_json_depth));
+    }
+    let input = serde_json::from_slice(&buffer).map_err(|error| error.to_string())?;
+    Ok((input, StreamedStdin { pending, body }))
+}
+
+/// Writes each stdin frame to the child as it arrives and closes stdin when the request body
+/// ends. A malformed frame or a failed read also closes it, so the child sees EOF. A terminal
+/// stays open when its input is dropped, so a `ptyMode` command only sees EOF if it is typed.
+async fn forward_stdin<S: StdinSink>(
+    streamed: StreamedStdin,
+    mut stdin: S,
+    max_line_bytes: usize,
+) {
+    let StreamedStdin {
+        pending: mut buffer,
+        mut body,
+    } = streamed;
+    loop {
+        while let Some(newline_index) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line = buffer.drain(..=newline_index).collect::<Vec<u8>>();
+            if !write_stdin_frame(&mut stdin, &line[..line.len() - 1]).await {
+                return;
+            }
+        }
+        if buffer.len() > max_line_bytes {
+            tracing::warn!(max_line_bytes, "raw stdin frame too long; closing stdin");
+            return;
+        }
+        match body.next().await {
+            Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+            Some(Err(error)) => {
+                tracing::info!(error = %error, "raw stdin stream ended early; closing stdin");
+                return;
+            }
+            None => break,
+        }
+    }
+    write_stdin_frame(&mut stdin, &buffer).await;
+}
+
+/// Returns whether more frames should be written.
+async fn write_stdin_frame<S: StdinSink>(stdin: &mut S, line: &[u8]) -> bool {
+    if line.trim_ascii().is_empty() {
+        return true;
+    }
+    let data = match serde_json::from_slice::<RawStdinFrame>(line) {
+        Ok(RawStdinFrame::Stdin { data_b64 }) => {
+            base64::engine::general_purpose::STANDARD.decode(data_b64)
+        }
+        Ok(RawStdinFrame::Resize { rows, cols }) => {
+            if let Err(error) = stdin.resize(TerminalSize { rows, cols }) {
+                tracing::warn!(error = %error, rows, cols, "failed resizing raw terminal");
+            }
+            return true;
+        }
+        Err(error) => {
+            tracing::warn!(error = %error, "invalid raw stdin frame; closing stdin");
+            return false;
+        }
+    };
+    let data = match data {
+        Ok(data) => data,
+        Err(error) => {
+            tracing::warn!(error = %error, "invalid raw stdin base64 payload; closing stdin");
+            return false;
+        }
+    };
+    match stdin.write_all(&data).await {
+        Ok(()) => true,
+        // The child exited or closed stdin; the rest of the input is dropped.
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => false,
+        Err(error) => {
+            tracing::warn!(error = %error, "failed writing raw stdin frame");
+            false
+        }
+    }
 }
 
-async fn read_output_stream<R>(
+pub(crate) async fn read_output_stream<R>(
     mut reader: R,
     stream: OutputStreamKind,
     tx: mpsc::Sender<ReaderEvent>,
@@ -276,7 +799,7 @@ async fn read_output_stream<R>(
     loop {
         match reader.read(&mut buffer).await {
             Ok(0) => {
-                let _ = tx.send(ReaderEvent::Done { stream }).await;
+                let _ = tx.send(ReaderEvent::Done).await;
                 return;
             }
             Ok(bytes_read) => {
@@ -304,7 +827,7 @@ async fn read_output_stream<R>(
     }
 }
 
-async fn send_event(tx: &mpsc::Sender<Bytes>, event: &RawStreamEvent) -> bool {
+pub(crate) async fn send_event(tx: &mpsc::Sender<Bytes>, event: &RawStreamEvent) -> bool {
     let mut line = match serde_json::to_vec(event) {
         Ok(line) => line,
         Err(error) => {
@@ -316,13 +839,36 @@ async fn send_event(tx: &mpsc::Sender<Bytes>, event: &RawStreamEvent) -> bool {
     tx.send(Bytes::from(line)).await.is_ok()
 }
 
-async fn terminate_child(child: &mut Child) {
-    let _ = child.start_kill();
-    let _ = child.wait().await;
+pub(crate) async fn terminate_child(child: &mut Child) {
+    let process_group = child.id();
+    kill_process_group(child, process_group).await;
+}
+
+pub(crate) fn error_response(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        Json(RawErrorBody {
+            error: message,
+            retry: None,
+            request_id: current_request_id(),
+        }),
+    )
+        .into_response()
 }
 
-fn error_response(status: StatusCode, message: String) -> Response {
-    (status, Json(RawErrorBody { error: message })).into_response()
+/// `429 Too Many Requests` carrying `retry` in the body and as a `Retry-After` header.
+pub(crate) fn limit_response(message: String, retry: RetryGuidance) -> Response {
+    let retry_after_secs = retry.retry_after_ms.div_ceil(1000).max(1);
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(RawErrorBody {
+            error: message,
+            retry: Some(retry),
+            request_id: current_request_id(),
+        }),
+    )
+        .into_response()
 }
 
 #[cfg(test)]
@@ -332,19 +878,12 @@ mod tests {
 
     use super::*;
     use crate::executor::{MAX_OUTPUT_BYTES, RunNetworkToolInput};
-    use crate::mcp::build_app;
+    use crate::limiter::RawStreamLimits;
+    use crate::mcp::{ServerOptions, build_app, build_app_with_options};
     use crate::policy::PolicyEngine;
-
-    fn find_executable(name: &str) -> Option<String> {
-        let path = std::env::var_os("PATH")?;
-        for dir in std::env::split_paths(&path) {
-            let candidate = dir.join(name);
-            if candidate.is_file() {
-                return Some(candidate.to_string_lossy().into_owned());
-            }
-        }
-        None
-    }
+    use crate::request_id::REQUEST_ID_HEADER;
+    use crate::self_invocation::HOPS_HEADER;
+    use crate::test_support::{find_executable, serve_app};
 
     fn rego_engine_allow_commands(commands: &[&str]) -> PolicyEngine {
         let mut allowed_map = String::new();
@@ -361,15 +900,7 @@ mod tests {
     }
 
     async fn start_server(policy_engine: PolicyEngine) -> (String, tokio::task::JoinHandle<()>) {
-        let app = build_app(Arc::new(policy_engine), PathBuf::from("."));
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
-            .await
-            .expect("bind test listener");
-        let addr = listener.local_addr().expect("local addr");
-        let server_task = tokio::spawn(async move {
-            let _ = axum::serve(listener, app).await;
-        });
-        (format!("http://{addr}"), server_task)
+        serve_app(build_app(Arc::new(policy_engine), PathBuf::from("."))).await
     }
 
     async fn decode_events(response: reqwest::Response) -> Vec<RawStreamEvent> {
@@ -401,6 +932,14 @@ mod tests {
                         .expect("decode stderr");
                     bytes.extend_from_slice(&decoded);
                 }
+                RawStreamEvent::Terminal { data_b64 }
+                    if matches!(stream, OutputStreamKind::Terminal) =>
+                {
+                    let decoded = base64::engine::general_purpose::STANDARD
+                        .decode(data_b64)
+                        .expect("decode terminal");
+                    bytes.extend_from_slice(&decoded);
+                }
                 _ => {}
             }
         }
@@ -409,14 +948,14 @@ mod tests {
 
     fn assert_has_event(events: &[RawStreamEvent], expected: &str) {
         assert!(
-            events.iter().any(|event| match (expected, event) {
-                ("start", RawStreamEvent::Start {}) => true,
-                ("stdout", RawStreamEvent::Stdout { .. }) => true,
-                ("stderr", RawStreamEvent::Stderr { .. }) => true,
-                ("exit", RawStreamEvent::Exit { .. }) => true,
-                ("error", RawStreamEvent::Error { .. }) => true,
-                _ => false,
-            }),
+            events.iter().any(|event| matches!(
+                (expected, event),
+                ("start", RawStreamEvent::Start { .. })
+                    | ("stdout", RawStreamEvent::Stdout { .. })
+                    | ("stderr", RawStreamEvent::Stderr { .. })
+                    | ("exit", RawStreamEvent::Exit { .. })
+                    | ("error", RawStreamEvent::Error { .. })
+            )),
             "missing expected event: {expected}",
         );
     }
@@ -434,8 +973,7 @@ mod tests {
             .json(&RunNetworkToolInput {
                 executable: sh_path,
                 args: vec!["-c".to_string(), script.to_string()],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             })
             .send()
             .await
@@ -462,7 +1000,7 @@ mod tests {
         assert_eq!(stderr, b"oops");
         assert!(matches!(
             events.last(),
-            Some(RawStreamEvent::Exit { exit_code: Some(0) })
+            Some(RawStreamEvent::Exit { exit_code: Some(0), .. })
         ));
 
         server_task.abort();
@@ -482,8 +1020,7 @@ mod tests {
             .json(&RunNetworkToolInput {
                 executable: "echo".to_string(),
                 args: vec!["blocked".to_string()],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             })
             .send()
             .await
@@ -499,6 +1036,39 @@ mod tests {
         server_task.abort();
     }
 
+    #[tokio::test]
+    async fn raw_passes_hops_on_and_refuses_requests_over_the_limit() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&sh_path])).await;
+        let request = |hops: &'static str| {
+            reqwest::Client::new()
+                .post(format!("{base_url}/raw"))
+                .header(HOPS_HEADER, hops)
+                .json(&RunNetworkToolInput {
+                    executable: sh_path.clone(),
+                    args: vec!["-c".to_string(), "printf %s \"$MCP_RUN_HOPS\"".to_string()],
+                    env: Some(BTreeMap::from([("MCP_RUN_HOPS".to_string(), "0".to_string())])),
+                    ..RunNetworkToolInput::default()
+                })
+                .send()
+        };
+
+        let response = request("1").await.expect("request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let events = decode_events(response).await;
+        assert_eq!(decode_output(&events, OutputStreamKind::Stdout), b"2");
+
+        let response = request("2").await.expect("request");
+        assert_eq!(response.status(), StatusCode::LOOP_DETECTED);
+        let body = response.json::<RawErrorBody>().await.expect("json error");
+        assert!(body.error.contains("passed through 2 mcp-run servers (limit 1)"));
+
+        server_task.abort();
+    }
+
     #[tokio::test]
     async fn raw_does_not_truncate_output_beyond_one_mb() {
         let head_path = match find_executable("head") {
@@ -518,8 +1088,7 @@ mod tests {
                     requested.to_string(),
                     "/dev/zero".to_string(),
                 ],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             })
             .send()
             .await
@@ -531,7 +1100,7 @@ mod tests {
         assert_eq!(stdout.len(), requested);
         assert!(matches!(
             events.last(),
-            Some(RawStreamEvent::Exit { exit_code: Some(0) })
+            Some(RawStreamEvent::Exit { exit_code: Some(0), .. })
         ));
 
         server_task.abort();
@@ -551,8 +1120,7 @@ mod tests {
             .json(&RunNetworkToolInput {
                 executable: sh_path,
                 args: vec!["-c".to_string(), script.to_string()],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             })
             .send()
             .await
@@ -580,8 +1148,7 @@ mod tests {
             .json(&RunNetworkToolInput {
                 executable: sh_path,
                 args: vec!["-c".to_string(), script.to_string()],
-                cwd: None,
-                env: None,
+                ..RunNetworkToolInput::default()
             })
             .send()
             .await
@@ -598,4 +1165,502 @@ mod tests {
 
         server_task.abort();
     }
+
+    #[tokio::test]
+    async fn raw_writes_stdin_payload() {
+        let cat_path = match find_executable("cat") {
+            Some(path) => path,
+            None => return,
+        };
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&cat_path])).await;
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/raw"))
+            .json(&RunNetworkToolInput {
+                executable: cat_path.clone(),
+                stdin_b64: Some(base64::engine::general_purpose::STANDARD.encode([0u8, 1, 255])),
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let events = decode_events(response).await;
+        assert_eq!(decode_output(&events, OutputStreamKind::Stdout), vec![0, 1, 255]);
+        assert!(matches!(
+            events.last(),
+            Some(RawStreamEvent::Exit { exit_code: Some(0), .. })
+        ));
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/raw"))
+            .json(&RunNetworkToolInput {
+                executable: cat_path,
+                stdin_b64: Some("%%%".to_string()),
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        server_task.abort();
+    }
+
+    fn stdin_frame(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut line = serde_json::to_vec(&RawStdinFrame::Stdin {
+            data_b64: base64::engine::general_purpose::STANDARD.encode(data),
+        })
+        .expect("serialize frame");
+        line.push(b'\n');
+        Ok(line)
+    }
+
+    #[tokio::test]
+    async fn raw_forwards_streamed_stdin_as_it_arrives() {
+        let cat_path = match find_executable("cat") {
+            Some(path) => path,
+            None => return,
+        };
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&cat_path])).await;
+
+        let (body_tx, body_rx) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(4);
+        let mut request_line = serde_json::to_vec(&RunNetworkToolInput {
+            executable: cat_path.clone(),
+            timeout_ms: Some(10_000),
+            ..RunNetworkToolInput::default()
+        })
+        .expect("serialize request");
+        request_line.push(b'\n');
+        body_tx.send(Ok(request_line)).await.expect("send request");
+        body_tx.send(stdin_frame(b"one\n")).await.expect("send frame");
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/raw"))
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(reqwest::Body::wrap_stream(ReceiverStream::new(body_rx)))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The first line comes back while the request body is still open.
+        let mut stream = response.bytes_stream();
+        let mut received = Vec::new();
+        while !String::from_utf8_lossy(&received).contains("\"stdout\"") {
+            received.extend_from_slice(&stream.next().await.expect("event").expect("chunk"));
+        }
+        body_tx.send(stdin_frame(b"two\n")).await.expect("send frame");
+        drop(body_tx);
+        while let Some(chunk) = stream.next().await {
+            received.extend_from_slice(&chunk.expect("chunk"));
+        }
+
+        let events = String::from_utf8(received)
+            .expect("utf8 events")
+            .lines()
+            .map(|line| serde_json::from_str::<RawStreamEvent>(line).expect("valid event"))
+            .collect::<Vec<_>>();
+        assert_eq!(decode_output(&events, OutputStreamKind::Stdout), b"one\ntwo\n");
+        assert!(matches!(
+            events.last(),
+            Some(RawStreamEvent::Exit { exit_code: Some(0), .. })
+        ));
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/raw"))
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(format!(
+                "{}\n",
+                serde_json::json!({"executable": cat_path, "stdinB64": "AA=="})
+            ))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn raw_pty_mode_runs_the_command_on_a_resizable_terminal() {
+        let (Some(sh_path), Some(_)) = (find_executable("sh"), find_executable("stty")) else {
+            return;
+        };
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&sh_path])).await;
+
+        let (body_tx, body_rx) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(4);
+        let mut request_line = serde_json::to_vec(&RunNetworkToolInput {
+            executable: sh_path.clone(),
+            args: vec![
+                "-c".to_string(),
+                "test -t 0 && test -t 1 && test -t 2 || exit 3; stty size; read line; \
+                 stty size; echo \"got $line\""
+                    .to_string(),
+            ],
+            timeout_ms: Some(10_000),
+            pty_mode: Some(true),
+            terminal_size: Some(TerminalSize { rows: 30, cols: 100 }),
+            ..RunNetworkToolInput::default()
+        })
+        .expect("serialize request");
+        request_line.push(b'\n');
+        body_tx.send(Ok(request_line)).await.expect("send request");
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/raw"))
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(reqwest::Body::wrap_stream(ReceiverStream::new(body_rx)))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let terminal_output = |received: &[u8]| {
+            let events = String::from_utf8_lossy(received)
+                .split_inclusive('\n')
+                .filter(|line| line.ends_with('\n'))
+                .map(|line| serde_json::from_str::<RawStreamEvent>(line).expect("valid event"))
+                .collect::<Vec<_>>();
+            String::from_utf8(decode_output(&events, OutputStreamKind::Terminal)).expect("utf8")
+        };
+
+        // Resize only once the command has reported the size it started with.
+        let mut stream = response.bytes_stream();
+        let mut received = Vec::new();
+        while !terminal_output(&received).contains("30 100") {
+            received.extend_from_slice(&stream.next().await.expect("event").expect("chunk"));
+        }
+        let mut resize = serde_json::to_vec(&RawStdinFrame::Resize { rows: 40, cols: 120 })
+            .expect("serialize frame");
+        resize.push(b'\n');
+        body_tx.send(Ok(resize)).await.expect("send frame");
+        body_tx.send(stdin_frame(b"hello\n")).await.expect("send frame");
+        drop(body_tx);
+        while let Some(chunk) = stream.next().await {
+            received.extend_from_slice(&chunk.expect("chunk"));
+        }
+        let events = String::from_utf8(received.clone())
+            .expect("utf8 events")
+            .lines()
+            .map(|line| serde_json::from_str::<RawStreamEvent>(line).expect("valid event"))
+            .collect::<Vec<_>>();
+
+        // Stdout and stderr are the terminal, so nothing arrives as a separate stream.
+        assert!(decode_output(&events, OutputStreamKind::Stdout).is_empty());
+        let output = terminal_output(&received);
+        assert!(output.contains("40 120\r\n"), "{output:?}");
+        assert!(output.contains("got hello\r\n"), "{output:?}");
+        assert!(matches!(
+            events.last(),
+            Some(RawStreamEvent::Exit { exit_code: Some(0), .. })
+        ));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn raw_rejects_streams_beyond_limit() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let app = build_app_with_options(
+            Arc::new(rego_engine_allow_commands(&[&sh_path])),
+            PathBuf::from("."),
+            ServerOptions {
+                raw_stream_limits: RawStreamLimits {
+                    max_active: Some(1),
+                    max_queued: 0,
+                },
+                ..ServerOptions::default()
+            },
+        );
+        let (base_url, server_task) = serve_app(app).await;
+        let client = reqwest::Client::new();
+        let request = |script: &str| RunNetworkToolInput {
+            executable: sh_path.clone(),
+            args: vec!["-c".to_string(), script.to_string()],
+            ..RunNetworkToolInput::default()
+        };
+
+        let running = client
+            .post(format!("{base_url}/raw"))
+            .json(&request("sleep 30"))
+            .send()
+            .await
+            .expect("first request");
+        assert_eq!(running.status(), StatusCode::OK);
+
+        let rejected = client
+            .post(format!("{base_url}/raw"))
+            .json(&request("true"))
+            .send()
+            .await
+            .expect("second request");
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            rejected.headers().get(header::RETRY_AFTER),
+            Some(&HeaderValue::from_static("1"))
+        );
+        let body: RawErrorBody = rejected.json().await.expect("error body");
+        assert_eq!(
+            body.retry,
+            Some(RetryGuidance {
+                retry_after_ms: crate::limiter::MIN_RETRY_AFTER_MS,
+                active: 1,
+                queue_length: 0,
+                limit: 1,
+            })
+        );
+
+        let metrics = client
+            .get(format!("{base_url}/metrics"))
+            .send()
+            .await
+            .expect("metrics request")
+            .text()
+            .await
+            .expect("metrics body");
+        assert!(metrics.contains("mcp_run_raw_streams_active 1\n"));
+        assert!(metrics.contains("mcp_run_raw_streams_queued 0\n"));
+        assert!(metrics.contains("mcp_run_raw_streams_rejected_total 1\n"));
+        assert!(metrics.contains("# TYPE mcp_run_command_user_cpu_seconds_total counter\n"));
+
+        // Disconnecting kills the running child and frees its slot.
+        drop(running);
+        let mut freed = false;
+        for _ in 0..100 {
+            let response = client
+                .post(format!("{base_url}/raw"))
+                .json(&request("true"))
+                .send()
+                .await
+                .expect("retry request");
+            if response.status() == StatusCode::OK {
+                freed = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(freed, "slot was not released after client disconnect");
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn raw_passes_client_id_to_policy_and_metrics() {
+        let true_path = match find_executable("true") {
+            Some(path) => path,
+            None => return,
+        };
+        let main = "package sandbox.main\n\ndefault allow = false\n\nallow if {\n  input.context.client_id == \"raw-builder\"\n}\n";
+        let (base_url, server_task) =
+            start_server(PolicyEngine::from_rego_for_tests(&[("main.rego", main)])).await;
+        let client = reqwest::Client::new();
+        let send = |client_id: Option<&'static str>| {
+            let mut request = client.post(format!("{base_url}/raw")).json(&RunNetworkToolInput {
+                executable: true_path.clone(),
+                ..RunNetworkToolInput::default()
+            });
+            if let Some(client_id) = client_id {
+                request = request.header(crate::client_id::CLIENT_ID_HEADER, client_id);
+            }
+            async move { request.send().await.expect("raw request") }
+        };
+
+        let allowed = send(Some("raw-builder")).await;
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_has_event(&decode_events(allowed).await, "exit");
+        assert_eq!(send(None).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(send(Some("not valid")).await.status(), StatusCode::BAD_REQUEST);
+
+        let metrics = client
+            .get(format!("{base_url}/metrics"))
+            .send()
+            .await
+            .expect("metrics request")
+            .text()
+            .await
+            .expect("metrics body");
+        assert!(metrics.contains("mcp_run_client_executions_total{client_id=\"raw-builder\"} 1\n"));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn raw_writes_audit_record_after_stream() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        crate::audit::audit_log_for_tests();
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&sh_path])).await;
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/raw"))
+            .header(crate::client_id::CLIENT_ID_HEADER, "raw-audit")
+            .json(&RunNetworkToolInput {
+                executable: sh_path.clone(),
+                args: vec!["-c".to_string(), "exit 7".to_string()],
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("request");
+        assert_has_event(&decode_events(response).await, "exit");
+
+        // The record is written once the stream task finishes, just after the exit event.
+        let mut records = Vec::new();
+        for _ in 0..100 {
+            records = crate::audit::audit_records_for_client("raw-audit");
+            if !records.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["source"], "raw");
+        assert_eq!(records[0]["decision"], "allow");
+        assert_eq!(records[0]["resolvedPath"], sh_path.as_str());
+        assert_eq!(records[0]["exitCode"], 7);
+        assert_eq!(records[0]["stdoutTruncated"], false);
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn raw_timeout_emits_error_event() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&sh_path])).await;
+
+        let response = reqwest::Client::new()
+            .post(format!("{base_url}/raw"))
+            .json(&RunNetworkToolInput {
+                executable: sh_path,
+                args: vec!["-c".to_string(), "printf started; sleep 30".to_string()],
+                timeout_ms: Some(200),
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let events = decode_events(response).await;
+        assert_has_event(&events, "start");
+        assert!(!events.iter().any(|event| matches!(event, RawStreamEvent::Exit { .. })));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            RawStreamEvent::Error { message } if message.contains("timed out after 200 ms")
+        )));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn request_ids_reach_the_start_event_and_error_bodies() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&sh_path])).await;
+        let client = reqwest::Client::new();
+        let input = |executable: &str| RunNetworkToolInput {
+            executable: executable.to_string(),
+            args: vec!["-c".to_string(), "true".to_string()],
+            ..RunNetworkToolInput::default()
+        };
+
+        let response = client
+            .post(format!("{base_url}/raw"))
+            .header(REQUEST_ID_HEADER, "trace-42")
+            .json(&input(&sh_path))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "trace-42");
+        let events = decode_events(response).await;
+        assert!(matches!(
+            events.first(),
+            Some(RawStreamEvent::Start { request_id: Some(request_id), .. }) if request_id == "trace-42"
+        ));
+
+        let denied = client
+            .post(format!("{base_url}/raw"))
+            .json(&input("/definitely/not/allowed"))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        let header = denied.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .expect("header")
+            .to_string();
+        let body: RawErrorBody = denied.json().await.expect("error body");
+        assert_eq!(body.request_id.as_deref(), Some(header.as_str()));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn raw_kill_stops_the_command_by_its_start_event_id() {
+        let sh_path = match find_executable("sh") {
+            Some(path) => path,
+            None => return,
+        };
+        let (base_url, server_task) = start_server(rego_engine_allow_commands(&[&sh_path])).await;
+        let client = reqwest::Client::new();
+
+        let mut response = client
+            .post(format!("{base_url}/raw"))
+            .json(&RunNetworkToolInput {
+                executable: sh_path,
+                args: vec!["-c".to_string(), "sleep 30".to_string()],
+                ..RunNetworkToolInput::default()
+            })
+            .send()
+            .await
+            .expect("request");
+        let first = response.chunk().await.expect("read").expect("start event");
+        let first = std::str::from_utf8(&first).expect("utf8").lines().next().unwrap_or_default();
+        let RawStreamEvent::Start {
+            execution_id: Some(execution_id),
+            ..
+        } = serde_json::from_str(first).expect("start event")
+        else {
+            panic!("start event without an execution id: {first}");
+        };
+
+        let started = Instant::now();
+        let killed = client
+            .post(format!("{base_url}/raw/{execution_id}/kill"))
+            .send()
+            .await
+            .expect("kill request");
+        assert_eq!(killed.status(), StatusCode::OK);
+        let killed: AdminKilled = killed.json().await.expect("kill response");
+        assert!(killed.killed);
+        assert!(started.elapsed() < RAW_KILL_GRACE);
+
+        let events = decode_events(response).await;
+        assert!(!events.iter().any(|event| matches!(event, RawStreamEvent::Exit { .. })));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            RawStreamEvent::Error { message } if message == RAW_KILLED
+        )));
+
+        let again = client
+            .post(format!("{base_url}/raw/{execution_id}/kill"))
+            .send()
+            .await
+            .expect("kill request");
+        assert_eq!(again.status(), StatusCode::NOT_FOUND);
+
+        server_task.abort();
+    }
 }
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use regex::Regex;
+
+/// Stands in for a sensitive value wherever an invocation is logged.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Copies `args` for logging, replacing every occurrence of a sensitive env value with
+/// [`REDACTED`]. The command itself always receives the real values.
+pub(crate) fn redact_env_values(
+    args: &[String],
+    env: &BTreeMap<String, String>,
+    is_sensitive: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut values: Vec<&str> = env
+        .iter()
+        .filter(|(key, value)| !value.is_empty() && is_sensitive(key))
+        .map(|(_, value)| value.as_str())
+        .collect();
+    // Longest first, so a value containing another is not left half-redacted.
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    args.iter()
+        .map(|arg| {
+            values
+                .iter()
+                .fold(arg.clone(), |arg, value| arg.replace(value, REDACTED))
+        })
+        .collect()
+}
+
+/// Bytes read past each cut of a truncated stream, so that a match crossing the cut is seen
+/// and redacted whole rather than left partly in the kept output.
+pub(crate) const REDACT_LOOKAHEAD_BYTES: usize = 4096;
+
+/// The policy's `redact` regexes, compiled once when the decision is built.
+#[derive(Debug, Clone, Default)]
+pub struct RedactPatterns(Vec<Regex>);
+
+impl RedactPatterns {
+    pub fn new(patterns: &[&str]) -> Result<Self, regex::Error> {
+        patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The regexes as the policy wrote them.
+    pub fn patterns(&self) -> Vec<String> {
+        self.0.iter().map(|regex| regex.as_str().to_string()).collect()
+    }
+
+    /// `output` with every match replaced by [`REDACTED`].
+    pub(crate) fn redact(&self, output: &str) -> String {
+        self.redact_range(output, 0..output.len())
+    }
+
+    /// The first `keep` bytes of `output`, redacted; a match crossing the cut is replaced
+    /// whole, so `output` should run [`REDACT_LOOKAHEAD_BYTES`] past it.
+    pub(crate) fn redact_head(&self, output: &str, keep: usize) -> String {
+        self.redact_range(output, 0..floor_char_boundary(output, keep))
+    }
+
+    /// The last `keep` bytes of `output`, redacted; a match crossing the cut is replaced
+    /// whole, so `output` should start [`REDACT_LOOKAHEAD_BYTES`] before it.
+    pub(crate) fn redact_tail(&self, output: &str, keep: usize) -> String {
+        let start = output.len().saturating_sub(keep);
+        let start = (start..=output.len())
+            .find(|index| output.is_char_boundary(*index))
+            .unwrap_or(output.len());
+        self.redact_range(output, start..output.len())
+    }
+
+    /// The part of `output` in `kept`, with every match that overlaps it replaced. Matches are
+    /// found in the whole of `output`, so one crossing either end of `kept` is still found.
+    fn redact_range(&self, output: &str, kept: Range<usize>) -> String {
+        let mut matches: Vec<Range<usize>> = self
+            .0
+            .iter()
+            .flat_map(|regex| regex.find_iter(output).map(|found| found.range()))
+            .filter(|found| found.start < kept.end && found.end > kept.start && !found.is_empty())
+            .collect();
+        matches.sort_by_key(|found| found.start);
+        let mut redacted = String::with_capacity(kept.len());
+        let mut position = kept.start;
+        for found in matches {
+            if found.end <= position {
+                continue;
+            }
+            if found.start > position {
+                redacted.push_str(&output[position..found.start]);
+            }
+            redacted.push_str(REDACTED);
+            position = found.end;
+        }
+        if position < kept.end {
+            redacted.push_str(&output[position..kept.end]);
+        }
+        redacted
+    }
+}
+
+impl PartialEq for RedactPatterns {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&other.0)
+                .all(|(left, right)| left.as_str() == right.as_str())
+    }
+}
+
+impl Eq for RedactPatterns {}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    (0..=index.min(text.len()))
+        .rev()
+        .find(|index| text.is_char_boundary(*index))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_only_sensitive_values() {
+        let env = BTreeMap::from([
+            ("API_TOKEN".to_string(), "s3cr3t".to_string()),
+            ("LONG_TOKEN".to_string(), "s3cr3t-extended".to_string()),
+            ("CI".to_string(), "1".to_string()),
+            ("EMPTY".to_string(), String::new()),
+        ]);
+        let args = vec![
+            "-H".to_string(),
+            "Authorization: Bearer s3cr3t".to_string(),
+            "--data=s3cr3t-extended".to_string(),
+            "CI=1".to_string(),
+        ];
+
+        let redacted = redact_env_values(&args, &env, |key| key != "CI");
+        assert_eq!(
+            redacted,
+            vec![
+                "-H".to_string(),
+                "Authorization: Bearer [REDACTED]".to_string(),
+                "--data=[REDACTED]".to_string(),
+                "CI=1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn redacts_every_output_match() {
+        let output = "//registry.npmjs.org/:_authToken=npm_abc123\nuser = gho_xyz gho_uvw\n";
+        let patterns = RedactPatterns::new(&["npm_[a-z0-9]+", r"gho_\w+"]).expect("patterns");
+        assert_eq!(
+            patterns.redact(output),
+            "//registry.npmjs.org/:_authToken=[REDACTED]\nuser = [REDACTED] [REDACTED]\n"
+        );
+        assert_eq!(RedactPatterns::default().redact(output), output);
+        assert_eq!(patterns.patterns(), ["npm_[a-z0-9]+", r"gho_\w+"]);
+        assert!(RedactPatterns::new(&["("]).is_err());
+    }
+
+    #[test]
+    fn matches_crossing_a_cut_are_redacted_whole() {
+        let patterns = RedactPatterns::new(&[r"tok_\w+"]).expect("patterns");
+        let output = "key tok_abcdef end";
+        assert_eq!(patterns.redact_head(output, 9), "key [REDACTED]");
+        assert_eq!(patterns.redact_head(output, 4), "key ");
+        assert_eq!(patterns.redact_tail(output, 6), "[REDACTED] end");
+        assert_eq!(patterns.redact_tail(output, 4), " end");
+        assert_eq!(patterns.redact_head("é", 1), "");
+    }
+}
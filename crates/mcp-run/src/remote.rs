@@ -1,16 +1,26 @@
 use std::collections::{BTreeMap, HashSet};
-use std::io::Write;
+use std::io::{IsTerminal, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use base64::Engine as _;
 use futures_util::StreamExt;
-use reqwest::{StatusCode, Url};
+use reqwest::{RequestBuilder, StatusCode, Url, header};
 use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
+use crate::auth::{AuthToken, AuthTokenError, auth_token_from_env, authorize};
 use crate::executor::RunNetworkToolInput;
-use crate::raw::{RawErrorBody, RawStreamEvent};
+use crate::raw::{RawErrorBody, RawStdinFrame, RawStreamEvent};
+use crate::self_invocation::forward_hops;
 
 pub const LOCAL_FAILURE_EXIT_CODE: i32 = 125;
 const REMOTE_EXIT_CODE_UNAVAILABLE: i32 = 1;
+/// Bytes of local stdin sent per `stdin` frame.
+const STDIN_CHUNK_BYTES: usize = 64 * 1024;
+/// The longest wait between retries once the backoff has doubled past it.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Error)]
 pub enum RemoteClientError {
@@ -26,6 +36,14 @@ pub enum RemoteClientError {
     UnknownOption(String),
     #[error("missing value for --keep-env")]
     MissingKeepEnvValue,
+    #[error("missing value for {0}")]
+    MissingOptionValue(&'static str),
+    #[error("invalid value for --retries: {0} (expected a whole number)")]
+    InvalidRetries(String),
+    #[error("invalid value for --retry-delay: {0} (expected e.g. 500ms or 2s)")]
+    InvalidRetryDelay(String),
+    #[error("failed to encode request: {0}")]
+    EncodeRequest(#[source] serde_json::Error),
     #[error("local environment variable(s) are not set: {0}")]
     MissingLocalEnv(String),
     #[error("failed to determine current working directory: {0}")]
@@ -40,15 +58,46 @@ pub enum RemoteClientError {
     OutputWrite(#[source] std::io::Error),
     #[error("remote runtime error: {0}")]
     RemoteRuntime(String),
+    #[error(transparent)]
+    AuthToken(#[from] AuthTokenError),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct ParsedArgs {
     keep_env: Vec<String>,
+    /// `--no-stdin`: leave the remote stdin at `/dev/null` even when local stdin is piped.
+    no_stdin: bool,
+    retry: RetryPolicy,
     executable: String,
     args: Vec<String>,
 }
 
+/// `--retries` and `--retry-delay`: how often a request is sent again when the server cannot
+/// be reached or answers 5xx before the output stream starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    /// Wait before the first retry; it doubles for each further one, up to 30s.
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_RETRY_BACKOFF.max(self.delay))
+    }
+}
+
 pub async fn run_remote_from_env(args: Vec<String>) -> Result<i32, RemoteClientError> {
     let mut stdout = std::io::stdout().lock();
     let mut stderr = std::io::stderr().lock();
@@ -62,6 +111,7 @@ async fn run_remote_from_env_with_io<WOut: Write, WErr: Write>(
 ) -> Result<i32, RemoteClientError> {
     let parsed = parse_args(&args)?;
     let server_url = resolve_server_url(std::env::var("RUN_REMOTE_SERVER").ok())?;
+    let auth_token = auth_token_from_env()?;
     let env = collect_forwarded_env(&parsed.keep_env, |name| std::env::var(name).ok())?;
     let cwd = std::env::current_dir().map_err(RemoteClientError::CurrentDir)?;
 
@@ -70,30 +120,158 @@ async fn run_remote_from_env_with_io<WOut: Write, WErr: Write>(
         args: parsed.args,
         cwd: Some(cwd.to_string_lossy().to_string()),
         env: Some(env),
+        ..RunNetworkToolInput::default()
     };
 
-    run_remote_request(&server_url, payload, stdout, stderr).await
+    // A terminal is never forwarded: the remote command would wait on input nobody typed.
+    if parsed.no_stdin || std::io::stdin().is_terminal() {
+        run_remote_request(
+            &server_url,
+            auth_token.as_ref(),
+            payload,
+            parsed.retry,
+            stdout,
+            stderr,
+        )
+        .await
+    } else {
+        run_remote_request_with_stdin(
+            &server_url,
+            auth_token.as_ref(),
+            payload,
+            parsed.retry,
+            tokio::io::stdin(),
+            stdout,
+            stderr,
+        )
+        .await
+    }
 }
 
 pub async fn run_remote_request<WOut: Write, WErr: Write>(
     server_url: &str,
+    auth_token: Option<&AuthToken>,
     payload: RunNetworkToolInput,
+    retry: RetryPolicy,
     stdout: &mut WOut,
     stderr: &mut WErr,
 ) -> Result<i32, RemoteClientError> {
     let client = reqwest::Client::new();
-    let response = client
-        .post(server_url)
-        .json(&payload)
-        .send()
-        .await
-        .map_err(RemoteClientError::Request)?;
+    let request = || forward_hops(authorize(client.post(server_url), auth_token)).json(&payload);
+    send_request(request, retry, || true, stdout, stderr).await
+}
+
+/// Like [`run_remote_request`], but sends the request as NDJSON followed by `stdin` frames,
+/// so the remote command reads `stdin` as it arrives and sees EOF when it ends. Once any
+/// stdin has been read the request is not retried, as the bytes cannot be sent again.
+pub async fn run_remote_request_with_stdin<R, WOut, WErr>(
+    server_url: &str,
+    auth_token: Option<&AuthToken>,
+    payload: RunNetworkToolInput,
+    retry: RetryPolicy,
+    stdin: R,
+    stdout: &mut WOut,
+    stderr: &mut WErr,
+) -> Result<i32, RemoteClientError>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+    WOut: Write,
+    WErr: Write,
+{
+    let mut request_line =
+        serde_json::to_vec(&payload).map_err(RemoteClientError::EncodeRequest)?;
+    request_line.push(b'\n');
+    // Each attempt streams from the same reader; `stdin_read` records whether one took bytes.
+    let stdin = Arc::new(tokio::sync::Mutex::new(stdin));
+    let stdin_read = Arc::new(AtomicBool::new(false));
+
+    let client = reqwest::Client::new();
+    let request = || {
+        let state = (Arc::clone(&stdin), Arc::clone(&stdin_read));
+        let frames = futures_util::stream::unfold(state, |(stdin, stdin_read)| async move {
+            let mut buffer = vec![0u8; STDIN_CHUNK_BYTES];
+            let read = stdin.lock().await.read(&mut buffer).await;
+            match read {
+                Ok(0) => None,
+                Ok(bytes_read) => {
+                    stdin_read.store(true, Ordering::SeqCst);
+                    let line = stdin_frame_line(&buffer[..bytes_read]);
+                    Some((Ok(line), (stdin, stdin_read)))
+                }
+                Err(error) => Some((Err(error), (stdin, stdin_read))),
+            }
+        });
+        let request_line = request_line.clone();
+        let body = futures_util::stream::once(async move { Ok(request_line) }).chain(frames);
+        forward_hops(authorize(client.post(server_url), auth_token))
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(reqwest::Body::wrap_stream(body))
+    };
+    let can_retry = || !stdin_read.load(Ordering::SeqCst);
+    send_request(request, retry, can_retry, stdout, stderr).await
+}
+
+fn stdin_frame_line(data: &[u8]) -> Vec<u8> {
+    let frame = RawStdinFrame::Stdin {
+        data_b64: base64::engine::general_purpose::STANDARD.encode(data),
+    };
+    let mut line = serde_json::to_vec(&frame).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
+
+/// Sends the request built by `request`, again for each of `retry.retries` while the server
+/// cannot be reached or answers 5xx and `can_retry` allows it. Nothing is retried once the
+/// output stream has started.
+async fn send_request<WOut: Write, WErr: Write>(
+    mut request: impl FnMut() -> RequestBuilder,
+    retry: RetryPolicy,
+    can_retry: impl Fn() -> bool,
+    stdout: &mut WOut,
+    stderr: &mut WErr,
+) -> Result<i32, RemoteClientError> {
+    let mut attempt = 0;
+    let response = loop {
+        let result = request().send().await;
+        let failure = match &result {
+            Err(error) if error.is_connect() => Some("could not connect to server".to_string()),
+            Ok(response) if response.status().is_server_error() => {
+                Some(format!("server answered {}", response.status()))
+            }
+            _ => None,
+        };
+        match failure {
+            Some(failure) if attempt < retry.retries && can_retry() => {
+                let delay = retry.backoff(attempt);
+                attempt += 1;
+                writeln!(
+                    stderr,
+                    "run-remote: {failure}; retrying in {} ms ({attempt} of {})",
+                    delay.as_millis(),
+                    retry.retries
+                )
+                .map_err(RemoteClientError::OutputWrite)?;
+                tokio::time::sleep(delay).await;
+            }
+            _ => break result.map_err(RemoteClientError::Request)?,
+        }
+    };
 
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.map_err(RemoteClientError::Request)?;
         let message = serde_json::from_str::<RawErrorBody>(&body)
-            .map(|decoded| decoded.error)
+            .map(|decoded| {
+                let mut message = decoded.error;
+                if let Some(retry) = decoded.retry {
+                    message.push_str(&format!(" (retry after {} ms)", retry.retry_after_ms));
+                }
+                // Lets the failure be found in the server's logs.
+                if let Some(request_id) = decoded.request_id {
+                    message.push_str(&format!(" (request id: {request_id})"));
+                }
+                message
+            })
             .unwrap_or_else(|_| body.trim().to_string());
         return Err(RemoteClientError::ServerRejected { status, message });
     }
@@ -152,11 +330,12 @@ fn handle_event_line<WOut: Write, WErr: Write>(
         .map_err(|error| RemoteClientError::Protocol(format!("invalid event JSON: {error}")))?;
 
     match event {
-        RawStreamEvent::Start {} => {
+        RawStreamEvent::Start { .. } => {
             *saw_start = true;
             Ok(())
         }
-        RawStreamEvent::Stdout { data_b64 } => {
+        // The client never asks for a terminal, but its output would belong on stdout.
+        RawStreamEvent::Stdout { data_b64 } | RawStreamEvent::Terminal { data_b64 } => {
             let bytes = base64::engine::general_purpose::STANDARD
                 .decode(data_b64)
                 .map_err(|error| {
@@ -178,7 +357,9 @@ fn handle_event_line<WOut: Write, WErr: Write>(
                 .and_then(|_| stderr.flush())
                 .map_err(RemoteClientError::OutputWrite)
         }
-        RawStreamEvent::Exit { exit_code: remote } => {
+        RawStreamEvent::Exit {
+            exit_code: remote, ..
+        } => {
             if !*saw_start {
                 return Err(RemoteClientError::Protocol(
                     "received exit event before start event".to_string(),
@@ -199,6 +380,8 @@ fn parse_args(args: &[String]) -> Result<ParsedArgs, RemoteClientError> {
 
     let mut keep_env = Vec::new();
     let mut seen = HashSet::new();
+    let mut no_stdin = false;
+    let mut retry = RetryPolicy::default();
 
     let mut index = 0;
     while index < delimiter {
@@ -219,6 +402,26 @@ fn parse_args(args: &[String]) -> Result<ParsedArgs, RemoteClientError> {
             index += 2;
             continue;
         }
+        if arg == "--no-stdin" {
+            no_stdin = true;
+            index += 1;
+            continue;
+        }
+        if let Some((option, value, consumed)) =
+            option_value(args, index, delimiter, &["--retries", "--retry-delay"])?
+        {
+            if option == "--retries" {
+                retry.retries = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| RemoteClientError::InvalidRetries(value.to_string()))?;
+            } else {
+                retry.delay = parse_retry_delay(value)
+                    .ok_or_else(|| RemoteClientError::InvalidRetryDelay(value.to_string()))?;
+            }
+            index += consumed;
+            continue;
+        }
         return Err(RemoteClientError::UnknownOption(arg.clone()));
     }
 
@@ -230,11 +433,46 @@ fn parse_args(args: &[String]) -> Result<ParsedArgs, RemoteClientError> {
 
     Ok(ParsedArgs {
         keep_env,
+        no_stdin,
+        retry,
         executable,
         args: command[1..].to_vec(),
     })
 }
 
+/// Matches `--option=value` or `--option value` at `index` for one of `options`, returning the
+/// option, its value and how many arguments it took.
+fn option_value<'a>(
+    args: &'a [String],
+    index: usize,
+    delimiter: usize,
+    options: &[&'static str],
+) -> Result<Option<(&'static str, &'a str, usize)>, RemoteClientError> {
+    let arg = &args[index];
+    for option in options {
+        if let Some(value) = arg.strip_prefix(option).and_then(|rest| rest.strip_prefix('=')) {
+            return Ok(Some((option, value, 1)));
+        }
+        if arg == option {
+            if index + 1 >= delimiter {
+                return Err(RemoteClientError::MissingOptionValue(option));
+            }
+            return Ok(Some((option, &args[index + 1], 2)));
+        }
+    }
+    Ok(None)
+}
+
+/// `500ms`, `2s`, or a number of seconds.
+fn parse_retry_delay(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Some(millis) = value.strip_suffix("ms") {
+        return millis.parse().ok().map(Duration::from_millis);
+    }
+    let seconds = value.strip_suffix('s').unwrap_or(value);
+    seconds.parse().ok().map(Duration::from_secs)
+}
+
 fn append_keep_env(value: &str, keep_env: &mut Vec<String>, seen: &mut HashSet<String>) {
     for name in value.split(',') {
         let trimmed = name.trim();
@@ -304,6 +542,7 @@ mod tests {
     use axum::routing::post;
 
     use super::*;
+    use crate::test_support::serve_app;
 
     #[test]
     fn parse_requires_delimiter() {
@@ -335,14 +574,8 @@ mod tests {
     }
 
     async fn start_server(router: Router) -> (String, tokio::task::JoinHandle<()>) {
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
-            .await
-            .expect("bind");
-        let addr = listener.local_addr().expect("local addr");
-        let task = tokio::spawn(async move {
-            let _ = axum::serve(listener, router).await;
-        });
-        (format!("http://{addr}/raw"), task)
+        let (base, task) = serve_app(router).await;
+        (format!("{base}/raw"), task)
     }
 
     fn event_line(event: RawStreamEvent) -> Vec<u8> {
@@ -354,14 +587,21 @@ mod tests {
     #[tokio::test]
     async fn parses_and_replays_stdout_stderr_and_exit_code() {
         let lines = [
-            event_line(RawStreamEvent::Start {}),
+            event_line(RawStreamEvent::Start {
+                execution_id: None,
+                request_id: None,
+            }),
             event_line(RawStreamEvent::Stdout {
                 data_b64: base64::engine::general_purpose::STANDARD.encode(b"hello"),
             }),
             event_line(RawStreamEvent::Stderr {
                 data_b64: base64::engine::general_purpose::STANDARD.encode([255u8, 0u8]),
             }),
-            event_line(RawStreamEvent::Exit { exit_code: Some(7) }),
+            event_line(RawStreamEvent::Exit {
+                exit_code: Some(7),
+                duration_ms: None,
+                resource_usage: None,
+            }),
         ]
         .concat();
 
@@ -371,9 +611,7 @@ mod tests {
 
         async fn handler(State(chunks): State<Vec<Bytes>>) -> Response {
             let stream = futures_util::stream::iter(
-                chunks
-                    .into_iter()
-                    .map(|chunk| Ok::<Bytes, Infallible>(chunk)),
+                chunks.into_iter().map(Ok::<Bytes, Infallible>),
             );
             let mut response = Response::new(Body::from_stream(stream));
             *response.status_mut() = StatusCode::OK;
@@ -391,16 +629,22 @@ mod tests {
 
         let payload = RunNetworkToolInput {
             executable: "cmd".to_string(),
-            args: vec![],
-            cwd: None,
             env: Some(BTreeMap::new()),
+            ..RunNetworkToolInput::default()
         };
 
         let mut stdout = Vec::new();
         let mut stderr = Vec::new();
-        let code = run_remote_request(&url, payload, &mut stdout, &mut stderr)
-            .await
-            .expect("request should succeed");
+        let code = run_remote_request(
+            &url,
+            None,
+            payload,
+            RetryPolicy::default(),
+            &mut stdout,
+            &mut stderr,
+        )
+        .await
+        .expect("request should succeed");
 
         assert_eq!(code, 7);
         assert_eq!(stdout, b"hello");
@@ -409,6 +653,227 @@ mod tests {
         server_task.abort();
     }
 
+    #[test]
+    fn parse_accepts_no_stdin_before_delimiter() {
+        let args = ["--no-stdin", "--", "psql", "--no-stdin"].map(String::from);
+        let parsed = parse_args(&args).expect("valid args");
+        assert!(parsed.no_stdin);
+        assert_eq!(parsed.args, vec!["--no-stdin".to_string()]);
+    }
+
+    #[test]
+    fn parse_reads_retry_options() {
+        let args = ["--retries", "3", "--retry-delay=250ms", "--", "curl"].map(String::from);
+        let parsed = parse_args(&args).expect("valid args");
+        assert_eq!(
+            parsed.retry,
+            RetryPolicy {
+                retries: 3,
+                delay: Duration::from_millis(250),
+            }
+        );
+        assert_eq!(parse_retry_delay("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_retry_delay("5"), Some(Duration::from_secs(5)));
+
+        let err = parse_args(&["--retries=many", "--", "curl"].map(String::from))
+            .expect_err("invalid retries");
+        assert!(matches!(err, RemoteClientError::InvalidRetries(_)));
+        let err = parse_args(&["--retry-delay", "--", "curl"].map(String::from))
+            .expect_err("missing delay");
+        assert!(matches!(err, RemoteClientError::MissingOptionValue("--retry-delay")));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let retry = RetryPolicy {
+            retries: 10,
+            delay: Duration::from_secs(1),
+        };
+        assert_eq!(retry.backoff(0), Duration::from_secs(1));
+        assert_eq!(retry.backoff(3), Duration::from_secs(8));
+        assert_eq!(retry.backoff(9), MAX_RETRY_BACKOFF);
+    }
+
+    fn exit_zero_events() -> Vec<u8> {
+        [
+            event_line(RawStreamEvent::Start {
+                execution_id: None,
+                request_id: None,
+            }),
+            event_line(RawStreamEvent::Exit {
+                exit_code: Some(0),
+                duration_ms: None,
+                resource_usage: None,
+            }),
+        ]
+        .concat()
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_before_the_stream_starts() {
+        // Answers 503 to the first request and streams an exit to the next.
+        async fn handler(State(calls): State<Arc<std::sync::atomic::AtomicUsize>>) -> Response {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                return StatusCode::SERVICE_UNAVAILABLE.into_response();
+            }
+            Response::new(Body::from(exit_zero_events()))
+        }
+
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/raw", post(handler))
+            .with_state(Arc::clone(&calls));
+        let (url, server_task) = start_server(router).await;
+
+        let retry = RetryPolicy {
+            retries: 2,
+            delay: Duration::from_millis(10),
+        };
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let code = run_remote_request_with_stdin(
+            &url,
+            None,
+            payload("cmd"),
+            retry,
+            std::io::Cursor::new(Vec::new()),
+            &mut stdout,
+            &mut stderr,
+        )
+        .await
+        .expect("retried request should succeed");
+
+        assert_eq!(code, 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(
+            String::from_utf8_lossy(&stderr).contains("retrying in 10 ms (1 of 2)"),
+            "{}",
+            String::from_utf8_lossy(&stderr)
+        );
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn requests_are_not_retried_once_stdin_was_sent() {
+        async fn handler(body: Bytes) -> Response {
+            assert!(!body.is_empty());
+            StatusCode::BAD_GATEWAY.into_response()
+        }
+
+        let router = Router::new().route("/raw", post(handler));
+        let (url, server_task) = start_server(router).await;
+
+        let retry = RetryPolicy {
+            retries: 3,
+            delay: Duration::from_millis(10),
+        };
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let err = run_remote_request_with_stdin(
+            &url,
+            None,
+            payload("psql"),
+            retry,
+            std::io::Cursor::new(b"rows".to_vec()),
+            &mut stdout,
+            &mut stderr,
+        )
+        .await
+        .expect_err("request should fail");
+
+        assert!(matches!(
+            err,
+            RemoteClientError::ServerRejected {
+                status: StatusCode::BAD_GATEWAY,
+                ..
+            }
+        ));
+        assert!(stderr.is_empty());
+
+        server_task.abort();
+    }
+
+    fn payload(executable: &str) -> RunNetworkToolInput {
+        RunNetworkToolInput {
+            executable: executable.to_string(),
+            env: Some(BTreeMap::new()),
+            ..RunNetworkToolInput::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn piped_stdin_is_sent_as_frames_after_the_request() {
+        // Echoes the streamed stdin back as stdout, once the request body has ended.
+        async fn handler(headers: axum::http::HeaderMap, body: Bytes) -> Response {
+            assert_eq!(
+                headers.get(header::CONTENT_TYPE).expect("content type"),
+                "application/x-ndjson"
+            );
+            let mut lines = body.split(|byte| *byte == b'\n');
+            let request: RunNetworkToolInput =
+                serde_json::from_slice(lines.next().expect("request line")).expect("request");
+            assert_eq!(request.executable, "psql");
+            let mut stdin = Vec::new();
+            for line in lines.filter(|line| !line.is_empty()) {
+                let RawStdinFrame::Stdin { data_b64 } =
+                    serde_json::from_slice(line).expect("stdin frame")
+                else {
+                    panic!("unexpected frame");
+                };
+                stdin.extend(
+                    base64::engine::general_purpose::STANDARD
+                        .decode(data_b64)
+                        .expect("base64"),
+                );
+            }
+            let events = [
+                event_line(RawStreamEvent::Start {
+                execution_id: None,
+                request_id: None,
+            }),
+                event_line(RawStreamEvent::Stdout {
+                    data_b64: base64::engine::general_purpose::STANDARD.encode(stdin),
+                }),
+                event_line(RawStreamEvent::Exit {
+                    exit_code: Some(0),
+                    duration_ms: None,
+                    resource_usage: None,
+                }),
+            ]
+            .concat();
+            Response::new(Body::from(events))
+        }
+
+        let router = Router::new().route("/raw", post(handler));
+        let (url, server_task) = start_server(router).await;
+
+        let input = vec![b'x'; STDIN_CHUNK_BYTES * 2 + 3];
+        let payload = RunNetworkToolInput {
+            executable: "psql".to_string(),
+            env: Some(BTreeMap::new()),
+            ..RunNetworkToolInput::default()
+        };
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let code = run_remote_request_with_stdin(
+            &url,
+            None,
+            payload,
+            RetryPolicy::default(),
+            std::io::Cursor::new(input.clone()),
+            &mut stdout,
+            &mut stderr,
+        )
+        .await
+        .expect("request should succeed");
+
+        assert_eq!(code, 0);
+        assert_eq!(stdout, input);
+
+        server_task.abort();
+    }
+
     #[tokio::test]
     async fn non_200_json_errors_are_reported_cleanly() {
         async fn handler() -> Response {
@@ -416,6 +881,8 @@ mod tests {
                 StatusCode::FORBIDDEN,
                 axum::Json(RawErrorBody {
                     error: "blocked".to_string(),
+                    retry: None,
+                    request_id: Some("req-1".to_string()),
                 }),
             )
                 .into_response()
@@ -426,15 +893,21 @@ mod tests {
 
         let payload = RunNetworkToolInput {
             executable: "cmd".to_string(),
-            args: vec![],
-            cwd: None,
             env: Some(BTreeMap::new()),
+            ..RunNetworkToolInput::default()
         };
         let mut stdout = Vec::new();
         let mut stderr = Vec::new();
-        let err = run_remote_request(&url, payload, &mut stdout, &mut stderr)
-            .await
-            .expect_err("request should fail");
+        let err = run_remote_request(
+            &url,
+            None,
+            payload,
+            RetryPolicy::default(),
+            &mut stdout,
+            &mut stderr,
+        )
+        .await
+        .expect_err("request should fail");
 
         assert!(matches!(
             err,
@@ -443,7 +916,7 @@ mod tests {
                 ..
             }
         ));
-        assert!(err.to_string().contains("blocked"));
+        assert!(err.to_string().contains("blocked (request id: req-1)"));
 
         server_task.abort();
     }
@@ -0,0 +1,96 @@
+//! Request ids, which tie an agent's call to the server's log lines, `/raw` start events and
+//! error bodies.
+
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use rmcp::RoleServer;
+use rmcp::service::RequestContext;
+use tracing::Instrument;
+
+/// Header a client sets to choose the id of its request; echoed on every response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id of one HTTP request, kept in its extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+/// The inbound `X-Request-Id` when it is 1-128 characters of `[A-Za-z0-9._:-]`, otherwise a
+/// new UUID. A malformed id is replaced rather than rejected, since proxies may set it.
+pub fn request_id_from_headers(headers: &HeaderMap) -> RequestId {
+    let inbound = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|byte| {
+                    byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b':' | b'-')
+                })
+        });
+    RequestId(inbound.map_or_else(|| uuid::Uuid::new_v4().to_string(), str::to_string))
+}
+
+/// Gives each request an id, logs everything it does inside a `request` span carrying the
+/// id, and returns the id in the `X-Request-Id` response header.
+pub(crate) async fn assign_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request_id_from_headers(request.headers());
+    request.extensions_mut().insert(request_id.clone());
+    let span = tracing::info_span!("request", request_id = %request_id.0);
+    let mut response = CURRENT_REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// The id of the request being handled, outside of tasks the handler spawned.
+pub(crate) fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|request_id| request_id.0.clone()).ok()
+}
+
+/// The id of the HTTP request carrying an MCP call; a new one for other transports.
+pub(crate) fn mcp_request_id(request_context: &RequestContext<RoleServer>) -> String {
+    request_context
+        .extensions
+        .get::<axum::http::request::Parts>()
+        .and_then(|parts| parts.extensions.get::<RequestId>())
+        .map_or_else(|| uuid::Uuid::new_v4().to_string(), |id| id.0.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_well_formed_inbound_ids_and_replaces_others() {
+        let mut headers = HeaderMap::new();
+        let generated = request_id_from_headers(&headers);
+        assert!(uuid::Uuid::parse_str(&generated.0).is_ok());
+        assert_ne!(request_id_from_headers(&headers), generated);
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("trace:agent-1.step_7"));
+        assert_eq!(
+            request_id_from_headers(&headers),
+            RequestId("trace:agent-1.step_7".to_string())
+        );
+
+        for invalid in ["", "has space", "quote\"", &"x".repeat(129)] {
+            headers.insert(
+                REQUEST_ID_HEADER,
+                HeaderValue::from_str(invalid).expect("header value"),
+            );
+            let replaced = request_id_from_headers(&headers);
+            assert!(uuid::Uuid::parse_str(&replaced.0).is_ok(), "{invalid:?}");
+        }
+    }
+}
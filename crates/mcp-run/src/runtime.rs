@@ -0,0 +1,86 @@
+use tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+
+use crate::mcp::{ConfigError, parse_env_count};
+
+/// Env var overriding the number of async worker threads.
+pub const WORKER_THREADS_ENV: &str = "MCP_WORKER_THREADS";
+/// Env var capping the threads that hash files and evaluate policy off the async workers.
+pub const MAX_BLOCKING_THREADS_ENV: &str = "MCP_MAX_BLOCKING_THREADS";
+
+/// Sizes of the server's tokio runtime. `None` keeps tokio's default: one worker per CPU and
+/// 512 blocking threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    pub worker_threads: Option<usize>,
+    pub max_blocking_threads: Option<usize>,
+}
+
+impl RuntimeConfig {
+    /// Reads [`WORKER_THREADS_ENV`] and [`MAX_BLOCKING_THREADS_ENV`]; `0` keeps the default.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(Self {
+            worker_threads: parse_env_count(WORKER_THREADS_ENV)?.filter(|count| *count > 0),
+            max_blocking_threads: parse_env_count(MAX_BLOCKING_THREADS_ENV)?
+                .filter(|count| *count > 0),
+        })
+    }
+
+    pub fn build(&self) -> std::io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all().thread_name("mcp-run-worker");
+        if let Some(count) = self.worker_threads {
+            builder.worker_threads(count);
+        }
+        if let Some(count) = self.max_blocking_threads {
+            builder.max_blocking_threads(count);
+        }
+        builder.build()
+    }
+}
+
+/// Runs `work`, which hashes files or evaluates policy, on a blocking thread so streams served
+/// by the same worker keep moving. The policy engine and request are borrowed, so this hands
+/// the worker's queue to a blocking-pool thread (`block_in_place`) rather than moving `work`
+/// there; without a multi-threaded runtime, as in tests, it runs inline.
+pub(crate) fn run_blocking<R>(work: impl FnOnce() -> R) -> R {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(work)
+        }
+        _ => work(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_blocking_runs_inline_outside_a_multi_threaded_runtime() {
+        assert_eq!(run_blocking(|| 1), 1);
+        let current = Builder::new_current_thread().build().expect("runtime");
+        assert_eq!(current.block_on(async { run_blocking(|| 2) }), 2);
+    }
+
+    #[test]
+    fn configured_runtime_moves_blocking_work_off_the_worker() {
+        let runtime = RuntimeConfig {
+            worker_threads: Some(1),
+            max_blocking_threads: Some(1),
+        }
+        .build()
+        .expect("runtime");
+
+        // With one worker, the task queued behind the blocking work only runs if the worker
+        // is handed over while that work waits for it.
+        let finished = runtime.block_on(async {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let blocked = tokio::spawn(async move {
+                run_blocking(|| receiver.recv_timeout(std::time::Duration::from_secs(5)))
+            });
+            tokio::spawn(async move { sender.send(()).expect("send") });
+            blocked.await.expect("join")
+        });
+        assert!(finished.is_ok());
+    }
+}
@@ -0,0 +1,120 @@
+//! Guards against commands that reach back into mcp-run: the server's own binaries are denied
+//! by resolved path, and `/raw` refuses requests relayed through too many servers.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use axum::http::HeaderMap;
+use thiserror::Error;
+
+/// Env var that lets commands run the server's own binaries (`1` or `true`).
+pub const ALLOW_SELF_INVOCATION_ENV: &str = "MCP_ALLOW_SELF_INVOCATION";
+/// Env var setting how many servers a `/raw` request may already have passed through.
+pub const MAX_HOPS_ENV: &str = "MCP_MAX_HOPS";
+pub const DEFAULT_MAX_HOPS: u32 = 1;
+/// Header `run-remote` sends with the hop count it inherited.
+pub const HOPS_HEADER: &str = "x-mcp-run-hops";
+/// Set on every command the server starts to the number of servers above it.
+pub const HOPS_ENV: &str = "MCP_RUN_HOPS";
+
+/// Names the server's binaries are installed under next to it; cladding ships `run-remote`
+/// as `run-with-network`.
+const OWN_BINARY_NAMES: [&str; 3] = ["mcp-run", "run-remote", "run-with-network"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid {HOPS_HEADER} header: expected a non-negative integer")]
+pub struct InvalidHopCount;
+
+/// Canonical paths of the running server and the binaries installed next to it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnBinaries {
+    paths: BTreeSet<PathBuf>,
+}
+
+impl OwnBinaries {
+    /// The current executable and any [`OWN_BINARY_NAMES`] in its directory.
+    pub fn detect() -> Self {
+        let Some(exe) = std::env::current_exe()
+            .ok()
+            .and_then(|exe| std::fs::canonicalize(exe).ok())
+        else {
+            return Self::default();
+        };
+        let siblings = exe.parent().map_or_else(Vec::new, |dir| {
+            OWN_BINARY_NAMES
+                .iter()
+                .filter_map(|name| std::fs::canonicalize(dir.join(name)).ok())
+                .collect()
+        });
+        Self::from_paths(std::iter::once(exe).chain(siblings))
+    }
+
+    pub fn from_paths(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            paths: paths.into_iter().collect(),
+        }
+    }
+
+    /// Whether `path`, with symlinks resolved, is one of these binaries.
+    pub fn contains(&self, path: &Path) -> bool {
+        std::fs::canonicalize(path).is_ok_and(|path| self.paths.contains(&path))
+    }
+}
+
+/// The hop count a `/raw` caller sent, `0` without the header.
+pub fn hops_from_headers(headers: &HeaderMap) -> Result<u32, InvalidHopCount> {
+    let Some(value) = headers.get(HOPS_HEADER) else {
+        return Ok(0);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .ok_or(InvalidHopCount)
+}
+
+/// Passes the hop count a server gave this process on to the next one.
+pub(crate) fn forward_hops(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match std::env::var(HOPS_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+    {
+        Some(hops) => request.header(HOPS_HEADER, hops),
+        None => request,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn own_binaries_match_through_symlinks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let server = dir.path().join("mcp-run");
+        std::fs::write(&server, b"").expect("write");
+        let link = dir.path().join("alias");
+        std::os::unix::fs::symlink(&server, &link).expect("symlink");
+        let other = dir.path().join("other");
+        std::fs::write(&other, b"").expect("write");
+
+        let own = OwnBinaries::from_paths([std::fs::canonicalize(&server).expect("canonical")]);
+        assert!(own.contains(&server));
+        assert!(own.contains(&link));
+        assert!(!own.contains(&other));
+        assert!(!own.contains(&dir.path().join("missing")));
+    }
+
+    #[test]
+    fn hop_count_header_defaults_to_zero() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(hops_from_headers(&headers), Ok(0));
+        headers.insert(HOPS_HEADER, HeaderValue::from_static("2"));
+        assert_eq!(hops_from_headers(&headers), Ok(2));
+        headers.insert(HOPS_HEADER, HeaderValue::from_static("-1"));
+        assert_eq!(hops_from_headers(&headers), Err(InvalidHopCount));
+    }
+}
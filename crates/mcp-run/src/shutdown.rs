@@ -0,0 +1,187 @@
+//! Graceful shutdown for `serve`: on SIGTERM or SIGINT the server stops accepting connections,
+//! waits for running commands and `/raw` streams, then exits. Commands still running when the
+//! drain times out are killed with their whole process group, so a pod restart leaves no
+//! orphaned subprocess trees.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::limiter::StreamLimiter;
+
+/// Environment variable overriding [`DEFAULT_SHUTDOWN_TIMEOUT`], in seconds.
+pub const SHUTDOWN_TIMEOUT_ENV: &str = "MCP_SHUTDOWN_TIMEOUT_SECS";
+/// How long a shutdown waits for in-flight work. Stays under podman's default 10 s stop
+/// timeout, so the drain finishes before the container is killed.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(8);
+/// Time left for responses to flush once no command is running, e.g. a `/raw` exit event.
+pub(crate) const CONNECTION_CLOSE_GRACE: Duration = Duration::from_secs(1);
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The work a graceful shutdown waits for.
+#[derive(Debug, Clone)]
+pub(crate) struct InFlight {
+    pub(crate) executions: Arc<StreamLimiter>,
+    pub(crate) raw_streams: Arc<StreamLimiter>,
+}
+
+impl InFlight {
+    /// Running commands across all endpoints, plus `/raw` requests queued to start one.
+    pub(crate) fn count(&self) -> usize {
+        self.executions.snapshot().active + self.raw_streams.snapshot().queued
+    }
+
+    /// Resolves once [`InFlight::count`] reaches zero.
+    pub(crate) async fn drained(&self) {
+        while self.count() > 0 {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Resolves on the first SIGTERM or SIGINT.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => tracing::info!("received SIGTERM"),
+                    _ = tokio::signal::ctrl_c() => tracing::info!("received SIGINT"),
+                }
+                return;
+            }
+            Err(error) => tracing::warn!(error = %error, "failed to install SIGTERM handler"),
+        }
+    }
+    if tokio::signal::ctrl_c().await.is_ok() {
+        tracing::info!("received SIGINT");
+    } else {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// SIGKILLs the process group of every child this process still has. Each command leads its
+/// own group, so this also stops helpers it forked. Returns how many groups were signalled.
+pub(crate) fn kill_child_process_groups() -> usize {
+    kill_process_groups(&child_process_group_leaders())
+}
+
+fn kill_process_groups(leaders: &[u32]) -> usize {
+    let mut killed = 0;
+    for &pid in leaders {
+        #[cfg(unix)]
+        if let Ok(pgid) = libc::pid_t::try_from(pid)
+            && unsafe { libc::killpg(pgid, libc::SIGKILL) } == 0
+        {
+            killed += 1;
+        }
+        #[cfg(not(unix))]
+        let _ = pid;
+    }
+    killed
+}
+
+/// Children that lead their own process group, read from `/proc/<pid>/stat`.
+#[cfg(target_os = "linux")]
+fn child_process_group_leaders() -> Vec<u32> {
+    let own_pid = std::process::id();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            std::fs::read_to_string(format!("/proc/{pid}/stat"))
+                .ok()
+                .and_then(|stat| parse_parent_and_group(&stat))
+                .is_some_and(|(parent, group)| parent == own_pid && group == *pid)
+        })
+        .collect()
+}
+
+/// Without `/proc`, only the direct children are stopped, by `kill_on_drop` when the runtime
+/// shuts down.
+#[cfg(not(target_os = "linux"))]
+fn child_process_group_leaders() -> Vec<u32> {
+    Vec::new()
+}
+
+/// The parent pid and process group from a `/proc/<pid>/stat` line. The command name may
+/// contain spaces and parentheses, so fields are counted from its last `)`.
+#[cfg(target_os = "linux")]
+fn parse_parent_and_group(stat: &str) -> Option<(u32, u32)> {
+    let mut fields = stat.get(stat.rfind(')')? + 1..)?.split_whitespace();
+    let _state = fields.next()?;
+    let parent = fields.next()?.parse().ok()?;
+    let group = fields.next()?.parse().ok()?;
+    Some((parent, group))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_parent_and_group_after_command_name() {
+        assert_eq!(
+            parse_parent_and_group("4242 (sh (x) y) S 17 4242 17 0 -1 4194560"),
+            Some((17, 4242))
+        );
+        assert_eq!(parse_parent_and_group("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn killing_child_groups_stops_forked_helpers() {
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "sleep 30 & echo $!; wait"])
+            .stdout(std::process::Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn()
+            .expect("spawn sh");
+        let mut stdout = child.stdout.take().expect("stdout");
+        let mut line = Vec::new();
+        loop {
+            let mut byte = [0u8];
+            tokio::io::AsyncReadExt::read_exact(&mut stdout, &mut byte)
+                .await
+                .expect("read helper pid");
+            if byte[0] == b'\n' {
+                break;
+            }
+            line.push(byte[0]);
+        }
+        let helper: u32 = String::from_utf8(line)
+            .expect("utf8")
+            .parse()
+            .expect("helper pid");
+
+        let leader = child.id().expect("child pid");
+        // Only this test's group is killed; other tests' commands are children too.
+        assert!(child_process_group_leaders().contains(&leader));
+        assert_eq!(kill_process_groups(&[leader]), 1);
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait())
+            .await
+            .expect("child stopped")
+            .expect("wait");
+        assert!(!status.success());
+        // The killed helper is reparented, and may linger as a zombie until reaped.
+        let mut alive = true;
+        for _ in 0..100 {
+            let state = std::fs::read_to_string(format!("/proc/{helper}/stat"))
+                .ok()
+                .and_then(|stat| {
+                    let after_name = stat.get(stat.rfind(')')? + 1..)?;
+                    after_name.split_whitespace().next().map(str::to_string)
+                });
+            if state.is_none_or(|state| state == "Z") {
+                alive = false;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!alive, "forked helper survived");
+    }
+}
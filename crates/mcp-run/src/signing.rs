@@ -0,0 +1,273 @@
+//! Signed policy bundles (`POLICY_SIGNING_KEY`): the policy directory is only enforced when
+//! `bundle.sig` is an ed25519 signature over its manifest, so whoever can write the directory
+//! without the private key cannot change the policy.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use aws_lc_rs::signature::{ED25519, UnparsedPublicKey};
+use base64::Engine as _;
+use sha2::{Digest, Sha256};
+
+pub const POLICY_SIGNING_KEY_ENV: &str = "POLICY_SIGNING_KEY";
+/// The signature over the manifest, at the top of the policy directory.
+pub const BUNDLE_SIGNATURE_FILE: &str = "bundle.sig";
+/// DER header of an ed25519 `SubjectPublicKeyInfo`, which `openssl pkey -pubout` wraps the
+/// 32 key bytes in.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+/// The ed25519 public key a policy bundle must be signed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicySigningKey([u8; 32]);
+
+impl PolicySigningKey {
+    /// Base64 of the 32 key bytes or of their `SubjectPublicKeyInfo`, with or without the PEM
+    /// armor `openssl pkey -pubout` prints.
+    pub fn parse(value: &str) -> Option<Self> {
+        let encoded: String = value
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()?;
+        let key = bytes.strip_prefix(&ED25519_SPKI_PREFIX).unwrap_or(&bytes);
+        Some(Self(key.try_into().ok()?))
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        UnparsedPublicKey::new(&ED25519, &self.0)
+            .verify(message, signature)
+            .is_ok()
+    }
+}
+
+/// Reads every policy source under `policy_dir` and checks `bundle.sig` against them. Returns
+/// the contents that were verified, by path, so the policy is compiled from exactly those
+/// bytes and not from files changed after the check.
+pub(crate) fn verify_bundle(
+    policy_dir: &Path,
+    key: &PolicySigningKey,
+) -> Result<BTreeMap<PathBuf, Vec<u8>>, String> {
+    let files = read_bundle(policy_dir)?;
+    let signature_path = policy_dir.join(BUNDLE_SIGNATURE_FILE);
+    let signature = std::fs::read(&signature_path).map_err(|error| {
+        format!(
+            "policy bundle is not signed: failed reading '{}': {error}",
+            signature_path.display()
+        )
+    })?;
+    let signature = decode_signature(&signature).ok_or_else(|| {
+        format!(
+            "'{}' is not an ed25519 signature (64 bytes, raw or base64)",
+            signature_path.display()
+        )
+    })?;
+    if !key.verify(manifest(policy_dir, &files).as_bytes(), &signature) {
+        return Err(format!(
+            "{BUNDLE_SIGNATURE_FILE} does not match the files under '{}' or was not made with {POLICY_SIGNING_KEY_ENV}",
+            policy_dir.display()
+        ));
+    }
+    Ok(files)
+}
+
+/// The text `bundle.sig` signs: a `sha256sum`-style line per `.rego`, `.json`, `.yaml` and
+/// `.yml` file under `policy_dir`, ordered by `/`-separated relative path.
+pub fn bundle_manifest(policy_dir: &Path) -> Result<String, String> {
+    Ok(manifest(policy_dir, &read_bundle(policy_dir)?))
+}
+
+/// Runs `mcp-run policy manifest [POLICY_DIR]`, which prints the text to sign.
+pub fn policy_manifest_from_args(args: &[String]) -> Result<String, String> {
+    let usage =
+        || "usage: mcp-run policy manifest [POLICY_DIR] (default: $POLICY_DIR)".to_string();
+    let policy_dir = match args {
+        [command] if command == "manifest" => std::env::var("POLICY_DIR")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .ok_or_else(usage)?,
+        [command, dir] if command == "manifest" => dir.clone(),
+        _ => return Err(usage()),
+    };
+    bundle_manifest(Path::new(&policy_dir))
+}
+
+fn manifest(policy_dir: &Path, files: &BTreeMap<PathBuf, Vec<u8>>) -> String {
+    let mut lines: Vec<(String, String)> = files
+        .iter()
+        .map(|(path, contents)| {
+            let relative = path
+                .strip_prefix(policy_dir)
+                .unwrap_or(path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let digest = Sha256::digest(contents)
+                .iter()
+                .fold(String::new(), |mut hex, byte| {
+                    let _ = write!(hex, "{byte:02x}");
+                    hex
+                });
+            (relative, digest)
+        })
+        .collect();
+    lines.sort();
+    lines
+        .into_iter()
+        .map(|(relative, digest)| format!("{digest}  {relative}\n"))
+        .collect()
+}
+
+fn read_bundle(policy_dir: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, String> {
+    let mut paths = Vec::new();
+    collect_bundle_files(policy_dir, &mut paths).map_err(|error| {
+        format!(
+            "failed reading policy directory '{}': {error}",
+            policy_dir.display()
+        )
+    })?;
+    paths
+        .into_iter()
+        .map(|path| {
+            let contents = std::fs::read(&path)
+                .map_err(|error| format!("failed reading '{}': {error}", path.display()))?;
+            Ok((path, contents))
+        })
+        .collect()
+}
+
+/// Walks the directory the way the policy loader does, so both see the same paths.
+fn collect_bundle_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_bundle_files(&path, files)?;
+        } else if matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("rego" | "json" | "yaml" | "yml")
+        ) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `openssl pkeyutl -sign` writes the 64 bytes raw; base64 of them is accepted too.
+fn decode_signature(contents: &[u8]) -> Option<Vec<u8>> {
+    if contents.len() == ED25519_SIGNATURE_LEN {
+        return Some(contents.to_vec());
+    }
+    let text = std::str::from_utf8(contents).ok()?.trim();
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .ok()
+        .filter(|signature| signature.len() == ED25519_SIGNATURE_LEN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_lc_rs::signature::{Ed25519KeyPair, KeyPair};
+
+    fn signing_pair(seed: u8) -> (Ed25519KeyPair, PolicySigningKey) {
+        let pair = Ed25519KeyPair::from_seed_unchecked(&[seed; 32]).expect("key pair");
+        let raw = base64::engine::general_purpose::STANDARD.encode(pair.public_key());
+        let key = PolicySigningKey::parse(&raw).expect("raw key");
+        (pair, key)
+    }
+
+    fn signed_bundle(pair: &Ed25519KeyPair) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("main.rego"), "package sandbox.main\n").expect("write");
+        std::fs::write(dir.path().join("data.json"), "{}").expect("write");
+        let manifest = bundle_manifest(dir.path()).expect("manifest");
+        let signature = pair.sign(manifest.as_bytes());
+        std::fs::write(dir.path().join(BUNDLE_SIGNATURE_FILE), signature.as_ref()).expect("sign");
+        dir
+    }
+
+    #[test]
+    fn tampered_files_fail_verification() {
+        let (pair, key) = signing_pair(7);
+        let dir = signed_bundle(&pair);
+        assert!(verify_bundle(dir.path(), &key).is_ok());
+        std::fs::write(dir.path().join("main.rego"), "package sandbox.other\n").expect("tamper");
+        assert!(verify_bundle(dir.path(), &key).is_err_and(|error| error.contains("does not match")));
+    }
+
+    #[test]
+    fn bundles_without_a_signature_fail_verification() {
+        let (pair, key) = signing_pair(7);
+        let dir = signed_bundle(&pair);
+        std::fs::remove_file(dir.path().join(BUNDLE_SIGNATURE_FILE)).expect("unsign");
+        assert!(verify_bundle(dir.path(), &key).is_err_and(|error| error.contains("not signed")));
+    }
+
+    #[test]
+    fn signatures_from_another_key_fail_verification() {
+        let (pair, _) = signing_pair(7);
+        let (_, other_key) = signing_pair(8);
+        let dir = signed_bundle(&pair);
+        assert!(
+            verify_bundle(dir.path(), &other_key)
+                .is_err_and(|error| error.contains("does not match"))
+        );
+    }
+
+    #[test]
+    fn files_added_after_signing_fail_verification() {
+        let (pair, key) = signing_pair(7);
+        let dir = signed_bundle(&pair);
+        std::fs::write(dir.path().join("extra.rego"), "package sandbox.extra\n").expect("write");
+        assert!(verify_bundle(dir.path(), &key).is_err_and(|error| error.contains("does not match")));
+    }
+
+    #[test]
+    fn bundles_verify_only_with_a_matching_signature() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir(dir.path().join("registries")).expect("mkdir");
+        std::fs::write(dir.path().join("main.rego"), "package sandbox.main\n").expect("write");
+        std::fs::write(dir.path().join("registries/data.json"), "{}").expect("write");
+        std::fs::write(dir.path().join("README.md"), "not signed").expect("write");
+
+        let manifest = bundle_manifest(dir.path()).expect("manifest");
+        let lines: Vec<&str> = manifest.lines().map(|line| &line[66..]).collect();
+        assert_eq!(lines, ["main.rego", "registries/data.json"]);
+
+        let pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).expect("key pair");
+        let spki: Vec<u8> = ED25519_SPKI_PREFIX
+            .iter()
+            .chain(pair.public_key().as_ref())
+            .copied()
+            .collect();
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            base64::engine::general_purpose::STANDARD.encode(spki)
+        );
+        let key = PolicySigningKey::parse(&pem).expect("pem key");
+        let raw = base64::engine::general_purpose::STANDARD.encode(pair.public_key());
+        assert_eq!(PolicySigningKey::parse(&raw), Some(key.clone()));
+        assert_eq!(PolicySigningKey::parse("AAAA"), None);
+
+        assert!(verify_bundle(dir.path(), &key).is_err_and(|error| error.contains("not signed")));
+        let signature = pair.sign(manifest.as_bytes());
+        std::fs::write(dir.path().join(BUNDLE_SIGNATURE_FILE), signature.as_ref()).expect("sign");
+        let verified = verify_bundle(dir.path(), &key).expect("verified");
+        assert_eq!(verified[&dir.path().join("main.rego")], b"package sandbox.main\n");
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(signature.as_ref());
+        std::fs::write(dir.path().join(BUNDLE_SIGNATURE_FILE), encoded).expect("sign");
+        assert!(verify_bundle(dir.path(), &key).is_ok());
+
+        std::fs::write(dir.path().join("registries/data.json"), "{\"npm\": []}").expect("tamper");
+        assert!(verify_bundle(dir.path(), &key).is_err_and(|error| error.contains("does not match")));
+    }
+}
@@ -0,0 +1,322 @@
+//! Truncated output kept on disk (`spillOutput`), so an agent can page through what
+//! `run_network_tool` or `POST /run` left out with continuation tokens instead of losing it.
+
+use std::collections::BTreeMap;
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::executor::MAX_OUTPUT_BYTES;
+use crate::raw::{RawEndpointState, error_response};
+
+pub const SPILL_DIR_ENV: &str = "MCP_SPILL_DIR";
+/// Bytes of a stream written to disk; output past this cannot be paged through.
+pub const MAX_SPILL_BYTES: u64 = 32 * 1024 * 1024;
+/// Spilled streams kept; beyond this the oldest are deleted and their tokens expire.
+pub const MAX_SPILLED_STREAMS: usize = 32;
+/// Bytes returned per chunk when the request does not say.
+pub const DEFAULT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Numbers spill files across every registry in the process, so servers sharing a directory
+/// never collide.
+static NEXT_SPILL_FILE: AtomicU64 = AtomicU64::new(0);
+
+/// Input of the `get_output_chunk` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GetOutputChunkInput {
+    /// A `stdoutContinuation` or `stderrContinuation`, or the `nextToken` of a previous chunk.
+    pub continuation_token: String,
+    /// Return at most this many bytes; 64 KiB when unset, never more than 1 MiB.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+}
+
+/// Query of `GET /output/{token}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputChunkQuery {
+    pub max_bytes: Option<usize>,
+}
+
+/// One page of the output a truncated stream left out.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputChunk {
+    pub data: String,
+    /// Byte offset of `data` in the stream.
+    pub offset: u64,
+    /// Byte offset where the left-out part ends; a kept tail starts here.
+    pub end: u64,
+    /// Token for the next chunk, absent once `end` is reached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum SpillError {
+    #[error("Unknown or expired continuation token: {0}")]
+    UnknownToken(String),
+    #[error("Failed to read spilled output: {source}")]
+    Read { source: io::Error },
+}
+
+/// Spilled streams by id, shared by the MCP tool and the `/run` and `/output` handlers.
+#[derive(Debug)]
+pub struct OutputSpills {
+    dir: PathBuf,
+    table: Mutex<SpillTable>,
+}
+
+#[derive(Debug, Default)]
+struct SpillTable {
+    next_id: u64,
+    spills: BTreeMap<u64, Arc<SpilledStream>>,
+}
+
+#[derive(Debug)]
+struct SpilledStream {
+    file: SpillFile,
+    /// The bytes of the stream the response left out.
+    omitted: Range<u64>,
+}
+
+/// A file in the spill directory, deleted when dropped.
+#[derive(Debug)]
+struct SpillFile(PathBuf);
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// The whole of one stream, written as it is read; the file is deleted unless
+/// [`OutputSpills::keep`] takes it.
+#[derive(Debug)]
+pub(crate) struct SpillWriter {
+    file: tokio::fs::File,
+    path: SpillFile,
+    written: u64,
+}
+
+impl SpillWriter {
+    /// Appends `bytes`, dropping whatever falls past [`MAX_SPILL_BYTES`].
+    pub(crate) async fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let room = usize::try_from(MAX_SPILL_BYTES - self.written).unwrap_or(usize::MAX);
+        let bytes = &bytes[..bytes.len().min(room)];
+        self.file.write_all(bytes).await?;
+        self.written += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+impl Default for OutputSpills {
+    fn default() -> Self {
+        Self::new(std::env::temp_dir())
+    }
+}
+
+impl OutputSpills {
+    /// Spills into files under `dir`, which is created on first use.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            table: Mutex::default(),
+        }
+    }
+
+    fn table(&self) -> MutexGuard<'_, SpillTable> {
+        self.table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Opens a new spill file for one stream.
+    pub(crate) async fn create(&self) -> io::Result<SpillWriter> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let number = NEXT_SPILL_FILE.fetch_add(1, Ordering::Relaxed);
+        let path = self
+            .dir
+            .join(format!("mcp-run-output-{}-{number}", std::process::id()));
+        let file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await?;
+        Ok(SpillWriter {
+            file,
+            path: SpillFile(path),
+            written: 0,
+        })
+    }
+
+    /// Keeps the `omitted` bytes of `writer`'s stream and returns the token for their first
+    /// chunk, or `None` when none of them made it to disk.
+    pub(crate) async fn keep(
+        &self,
+        mut writer: SpillWriter,
+        omitted: Range<u64>,
+    ) -> io::Result<Option<String>> {
+        writer.file.flush().await?;
+        let omitted = omitted.start..omitted.end.min(writer.written);
+        if omitted.is_empty() {
+            return Ok(None);
+        }
+
+        let mut table = self.table();
+        table.next_id += 1;
+        let id = table.next_id;
+        let token = format_token(id, omitted.start);
+        table.spills.insert(
+            id,
+            Arc::new(SpilledStream {
+                file: writer.path,
+                omitted,
+            }),
+        );
+        while table.spills.len() > MAX_SPILLED_STREAMS {
+            table.spills.pop_first();
+        }
+        Ok(Some(token))
+    }
+
+    /// Reads the chunk `token` points at, at most `max_bytes` long. A chunk ends on a
+    /// character boundary where it can, so UTF-8 text is not split between two chunks.
+    pub async fn chunk(
+        &self,
+        token: &str,
+        max_bytes: Option<usize>,
+    ) -> Result<OutputChunk, SpillError> {
+        let unknown = || SpillError::UnknownToken(token.to_string());
+        let (id, offset) = parse_token(token).ok_or_else(unknown)?;
+        // Held until the read is done, so eviction cannot delete the file under it.
+        let spill = self
+            .table()
+            .spills
+            .get(&id)
+            .cloned()
+            .filter(|spill| spill.omitted.contains(&offset))
+            .ok_or_else(unknown)?;
+
+        let max_bytes = max_bytes
+            .unwrap_or(DEFAULT_CHUNK_BYTES)
+            .clamp(1, MAX_OUTPUT_BYTES) as u64;
+        let end = spill.omitted.end;
+        let mut bytes = vec![0; max_bytes.min(end - offset) as usize];
+        let read = async {
+            let mut file = tokio::fs::File::open(&spill.file.0).await?;
+            file.seek(SeekFrom::Start(offset)).await?;
+            file.read_exact(&mut bytes).await
+        };
+        read.await.map_err(|source| SpillError::Read { source })?;
+
+        if offset + (bytes.len() as u64) < end
+            && let Err(error) = std::str::from_utf8(&bytes)
+            && error.error_len().is_none()
+            && error.valid_up_to() > 0
+        {
+            bytes.truncate(error.valid_up_to());
+        }
+        let next = offset + bytes.len() as u64;
+        Ok(OutputChunk {
+            data: String::from_utf8_lossy(&bytes).into_owned(),
+            offset,
+            end,
+            next_token: (next < end).then(|| format_token(id, next)),
+        })
+    }
+}
+
+fn format_token(id: u64, offset: u64) -> String {
+    format!("out-{id}-{offset}")
+}
+
+fn parse_token(token: &str) -> Option<(u64, u64)> {
+    let (id, offset) = token.strip_prefix("out-")?.split_once('-')?;
+    Some((id.parse().ok()?, offset.parse().ok()?))
+}
+
+/// Returns the chunk of spilled output a continuation token points at.
+pub async fn output_chunk_handler(
+    State(state): State<RawEndpointState>,
+    Path(token): Path<String>,
+    Query(query): Query<OutputChunkQuery>,
+) -> Response {
+    match state.spills.chunk(&token, query.max_bytes).await {
+        Ok(chunk) => Json(chunk).into_response(),
+        Err(error @ SpillError::UnknownToken(_)) => {
+            error_response(StatusCode::NOT_FOUND, error.to_string())
+        }
+        Err(error) => {
+            tracing::warn!(token, error = %error, "spilled output could not be read");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn spill(spills: &OutputSpills, bytes: &[u8], omitted: Range<u64>) -> Option<String> {
+        let mut writer = spills.create().await.expect("create spill file");
+        writer.write(bytes).await.expect("write spill file");
+        spills.keep(writer, omitted).await.expect("keep spill file")
+    }
+
+    #[tokio::test]
+    async fn chunks_page_through_the_omitted_bytes_without_splitting_characters() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let spills = OutputSpills::new(dir.path().to_path_buf());
+        let token = spill(&spills, "ab\u{e9}cdef".as_bytes(), 1..6)
+            .await
+            .expect("token");
+
+        let first = spills.chunk(&token, Some(2)).await.expect("first chunk");
+        assert_eq!((first.data.as_str(), first.offset, first.end), ("b", 1, 6));
+        let second = spills
+            .chunk(first.next_token.as_deref().expect("next"), Some(3))
+            .await
+            .expect("second chunk");
+        assert_eq!((second.data.as_str(), second.offset), ("\u{e9}c", 2));
+        let last = spills
+            .chunk(second.next_token.as_deref().expect("next"), None)
+            .await
+            .expect("last chunk");
+        assert_eq!((last.data.as_str(), last.next_token), ("d", None));
+
+        assert!(matches!(
+            spills.chunk("out-1-6", None).await,
+            Err(SpillError::UnknownToken(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn the_oldest_spills_are_deleted_past_the_limit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let spills = OutputSpills::new(dir.path().to_path_buf());
+        let first = spill(&spills, b"0123", 0..2).await.expect("token");
+        for _ in 0..MAX_SPILLED_STREAMS {
+            spill(&spills, b"0123", 0..2).await.expect("token");
+        }
+        assert!(spills.chunk(&first, None).await.is_err());
+        let files = std::fs::read_dir(dir.path()).expect("read dir").count();
+        assert_eq!(files, MAX_SPILLED_STREAMS);
+
+        assert_eq!(spill(&spills, b"01", 2..4).await, None);
+        assert_eq!(
+            std::fs::read_dir(dir.path()).expect("read dir").count(),
+            MAX_SPILLED_STREAMS
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! Helpers shared by the unit tests that run commands or serve the app.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::Router;
+use tokio::task::JoinHandle;
+
+use crate::executor::RunNetworkToolInput;
+use crate::mcp::build_app;
+use crate::policy::PolicyEngine;
+
+/// Absolute path of `name` on `PATH`; tests that need it return early without it.
+pub(crate) fn find_executable(name: &str) -> Option<String> {
+    let path = std::env::var_os("PATH")?;
+    for dir in std::env::split_paths(&path) {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate.to_string_lossy().into_owned());
+        }
+    }
+    None
+}
+
+/// Serves `app` on a loopback port, returning its base URL and the server task.
+pub(crate) async fn serve_app(app: Router) -> (String, JoinHandle<()>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind test listener");
+    let base = format!("http://{}", listener.local_addr().expect("local addr"));
+    let server_task = tokio::spawn(async move {
+        let _ = axum::serve(listener, app).await;
+    });
+    (base, server_task)
+}
+
+/// A policy that allows `sh_path` and nothing else.
+pub(crate) fn sh_policy(sh_path: &str) -> PolicyEngine {
+    let escaped = sh_path.replace('\\', "\\\\").replace('\"', "\\\"");
+    let main = format!(
+        "package sandbox.main\n\ndefault allow = false\n\nallow if {{\n  input.command == \"{escaped}\"\n}}\n"
+    );
+    PolicyEngine::from_rego_for_tests(&[("main.rego", &main)])
+}
+
+/// Serves the app with [`sh_policy`].
+pub(crate) async fn start_sh_server(sh_path: &str) -> (String, JoinHandle<()>) {
+    serve_app(build_app(Arc::new(sh_policy(sh_path)), PathBuf::from("."))).await
+}
+
+/// `sh -c script`, with everything else left unset.
+pub(crate) fn sh_input(sh_path: &str, script: &str) -> RunNetworkToolInput {
+    RunNetworkToolInput {
+        executable: sh_path.to_string(),
+        args: vec!["-c".to_string(), script.to_string()],
+        ..RunNetworkToolInput::default()
+    }
+}
@@ -0,0 +1,376 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::policy::REGO_VALIDITY_QUERY;
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Where policy evaluation gets the current time, so `validity` windows can be tested
+/// without waiting for the wall clock.
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// Reads [`SystemTime::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// When an allowed invocation may run, from the policy's `validity` rule. The default has no
+/// bounds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validity {
+    /// Inclusive start, as given in the policy.
+    pub valid_from: Option<(SystemTime, String)>,
+    /// Exclusive end, as given in the policy.
+    pub valid_until: Option<(SystemTime, String)>,
+    pub schedule: Option<Schedule>,
+}
+
+/// Weekly window from `validity.schedule`, in a fixed UTC offset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// Allowed days, indexed from Monday.
+    pub days: [bool; 7],
+    /// Minutes after local midnight; `from` is inclusive and `until` exclusive.
+    pub from_minute: u32,
+    pub until_minute: u32,
+    pub utc_offset_minutes: i32,
+}
+
+impl Validity {
+    /// Why `now` is outside the window, or `None` when it is inside.
+    pub fn violation(&self, now: SystemTime) -> Option<String> {
+        if let Some((from, text)) = &self.valid_from
+            && now < *from
+        {
+            return Some(format!("not valid before {text}"));
+        }
+        if let Some((until, text)) = &self.valid_until
+            && now >= *until
+        {
+            return Some(format!("expired at {text}"));
+        }
+        let schedule = self.schedule.as_ref()?;
+        (!schedule.contains(now)).then(|| format!("outside schedule {}", schedule.describe()))
+    }
+}
+
+impl Schedule {
+    fn contains(&self, now: SystemTime) -> bool {
+        let secs = match now.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        let local_minutes = secs.div_euclid(60) + i64::from(self.utc_offset_minutes);
+        let days = local_minutes.div_euclid(1440);
+        let minute_of_day = local_minutes.rem_euclid(1440) as u32;
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 3).rem_euclid(7) as usize;
+        self.days[weekday] && (self.from_minute..self.until_minute).contains(&minute_of_day)
+    }
+
+    fn describe(&self) -> String {
+        let days: Vec<&str> = DAY_NAMES
+            .iter()
+            .zip(self.days)
+            .filter_map(|(name, allowed)| allowed.then_some(*name))
+            .collect();
+        let offset = self.utc_offset_minutes.unsigned_abs();
+        format!(
+            "{} {:02}:{:02}-{:02}:{:02} UTC{}{:02}:{:02}",
+            days.join(","),
+            self.from_minute / 60,
+            self.from_minute % 60,
+            self.until_minute / 60,
+            self.until_minute % 60,
+            if self.utc_offset_minutes < 0 {
+                '-'
+            } else {
+                '+'
+            },
+            offset / 60,
+            offset % 60
+        )
+    }
+}
+
+pub(crate) fn parse_validity(value: &serde_json::Value) -> Result<Validity, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{REGO_VALIDITY_QUERY} must be an object"))?;
+    let mut validity = Validity::default();
+    for (key, value) in object {
+        let field = format!("{REGO_VALIDITY_QUERY}.{key}");
+        match key.as_str() {
+            "validFrom" => validity.valid_from = Some(parse_bound(&field, value)?),
+            "validUntil" => validity.valid_until = Some(parse_bound(&field, value)?),
+            "schedule" => validity.schedule = Some(parse_schedule(&field, value)?),
+            _ => {
+                return Err(format!(
+                    "{REGO_VALIDITY_QUERY} has unknown key '{key}' (expected validFrom, validUntil or schedule)"
+                ));
+            }
+        }
+    }
+    Ok(validity)
+}
+
+fn parse_bound(field: &str, value: &serde_json::Value) -> Result<(SystemTime, String), String> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| format!("{field} must be a string"))?;
+    let time = parse_timestamp(text).ok_or_else(|| {
+        format!("{field} must be an RFC 3339 timestamp or YYYY-MM-DD date, got '{text}'")
+    })?;
+    Ok((time, text.to_string()))
+}
+
+fn parse_schedule(field: &str, value: &serde_json::Value) -> Result<Schedule, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{field} must be an object"))?;
+    let mut schedule = Schedule {
+        days: [true; 7],
+        from_minute: 0,
+        until_minute: 1440,
+        utc_offset_minutes: 0,
+    };
+    for (key, value) in object {
+        let text = || {
+            value
+                .as_str()
+                .ok_or_else(|| format!("{field}.{key} must be a string"))
+        };
+        match key.as_str() {
+            "days" => {
+                let days = value
+                    .as_array()
+                    .ok_or_else(|| format!("{field}.days must be an array of day names"))?;
+                schedule.days = [false; 7];
+                for day in days {
+                    let index = day
+                        .as_str()
+                        .and_then(|day| DAY_NAMES.iter().position(|name| *name == day))
+                        .ok_or_else(|| {
+                            format!(
+                                "{field}.days entries must be one of {}",
+                                DAY_NAMES.join(", ")
+                            )
+                        })?;
+                    schedule.days[index] = true;
+                }
+            }
+            "from" => {
+                schedule.from_minute = parse_time_of_day(text()?)
+                    .ok_or_else(|| format!("{field}.from must be HH:MM"))?;
+            }
+            "until" => {
+                schedule.until_minute = parse_time_of_day(text()?)
+                    .ok_or_else(|| format!("{field}.until must be HH:MM (up to 24:00)"))?;
+            }
+            "utcOffset" => {
+                schedule.utc_offset_minutes = parse_utc_offset(text()?)
+                    .ok_or_else(|| format!("{field}.utcOffset must be Z or +HH:MM / -HH:MM"))?;
+            }
+            _ => {
+                return Err(format!(
+                    "{field} has unknown key '{key}' (expected days, from, until or utcOffset)"
+                ));
+            }
+        }
+    }
+    if schedule.from_minute >= schedule.until_minute {
+        return Err(format!("{field}.from must be before {field}.until"));
+    }
+    Ok(schedule)
+}
+
+/// Minutes after midnight for `HH:MM`; `24:00` is accepted as the end of the day.
+fn parse_time_of_day(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.split_once(':')?;
+    let hours = parse_digits(hours, 2)?;
+    let minutes = parse_digits(minutes, 2)?;
+    match (hours, minutes) {
+        (24, 0) => Some(1440),
+        (0..=23, 0..=59) => Some(hours * 60 + minutes),
+        _ => None,
+    }
+}
+
+fn parse_utc_offset(text: &str) -> Option<i32> {
+    if text == "Z" {
+        return Some(0);
+    }
+    let sign = match text.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let minutes = parse_time_of_day(&text[1..]).filter(|minutes| *minutes < 1440)?;
+    Some(sign * minutes as i32)
+}
+
+/// `YYYY-MM-DD` (midnight UTC) or `YYYY-MM-DDTHH:MM:SS[.fraction](Z|±HH:MM)`.
+fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let (date, time) = match text.split_once(['T', 't']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+    let mut parts = date.splitn(3, '-');
+    let year = parse_digits(parts.next()?, 4)?;
+    let month = parse_digits(parts.next()?, 2)?;
+    let day = parse_digits(parts.next()?, 2)?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    let mut secs = days_from_civil(i64::from(year), month, day) * 86_400;
+    if let Some(time) = time {
+        let offset_at = time.find(['Z', 'z', '+', '-'])?;
+        let (clock, offset) = time.split_at(offset_at);
+        let offset = if offset.eq_ignore_ascii_case("z") {
+            0
+        } else {
+            parse_utc_offset(offset)?
+        };
+        let (clock, _fraction) = match clock.split_once('.') {
+            Some((clock, fraction)) if !fraction.is_empty() => fraction
+                .bytes()
+                .all(|byte| byte.is_ascii_digit())
+                .then_some((clock, fraction))?,
+            Some(_) => return None,
+            None => (clock, ""),
+        };
+        let (hour_minute, second) = clock.rsplit_once(':')?;
+        let minute_of_day = parse_time_of_day(hour_minute).filter(|minutes| *minutes < 1440)?;
+        let second = parse_digits(second, 2).filter(|second| *second < 60)?;
+        secs += i64::from(minute_of_day * 60 + second) - i64::from(offset) * 60;
+    }
+    u64::try_from(secs)
+        .ok()
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+fn parse_digits(text: &str, width: usize) -> Option<u32> {
+    (text.len() == width && text.bytes().all(|byte| byte.is_ascii_digit()))
+        .then(|| text.parse().ok())
+        .flatten()
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> SystemTime {
+        parse_timestamp(text).expect("valid timestamp")
+    }
+
+    #[test]
+    fn parses_rfc3339_timestamps_and_dates() {
+        assert_eq!(at("1970-01-01"), UNIX_EPOCH);
+        assert_eq!(
+            at("2026-01-05T09:14:03.512Z"),
+            UNIX_EPOCH + Duration::from_secs(1_767_604_443)
+        );
+        assert_eq!(at("2026-01-05T10:14:03+01:00"), at("2026-01-05T09:14:03Z"));
+        assert_eq!(
+            at("2000-02-29"),
+            UNIX_EPOCH + Duration::from_secs(951_782_400)
+        );
+        for invalid in [
+            "2026-1-05",
+            "2026-02-30",
+            "2026-01-05T09:14Z",
+            "2026-01-05T09:14:03",
+            "2026-01-05T25:00:00Z",
+            "2026-01-05T09:14:03.Z",
+            "1969-12-31",
+        ] {
+            assert_eq!(parse_timestamp(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn validity_checks_bounds_and_weekly_schedule() {
+        let validity = parse_validity(&serde_json::json!({
+            "validFrom": "2026-01-01",
+            "validUntil": "2026-07-01T00:00:00Z",
+            "schedule": {
+                "days": ["mon", "tue", "wed", "thu", "fri"],
+                "from": "09:00",
+                "until": "17:30",
+                "utcOffset": "+01:00"
+            }
+        }))
+        .expect("valid rule");
+
+        // Monday 2026-01-05, 09:30 at +01:00.
+        assert_eq!(validity.violation(at("2026-01-05T08:30:00Z")), None);
+        assert_eq!(
+            validity.violation(at("2026-01-05T07:59:59Z")).as_deref(),
+            Some("outside schedule mon,tue,wed,thu,fri 09:00-17:30 UTC+01:00")
+        );
+        assert!(validity.violation(at("2026-01-05T16:30:00Z")).is_some());
+        // Saturday.
+        assert!(validity.violation(at("2026-01-10T10:00:00Z")).is_some());
+        assert_eq!(
+            validity.violation(at("2025-12-31T10:00:00Z")).as_deref(),
+            Some("not valid before 2026-01-01")
+        );
+        assert_eq!(
+            validity.violation(at("2026-07-01T08:30:00Z")).as_deref(),
+            Some("expired at 2026-07-01T00:00:00Z")
+        );
+
+        let late = parse_validity(&serde_json::json!({
+            "schedule": { "from": "22:00", "until": "24:00", "utcOffset": "-05:00" }
+        }))
+        .expect("valid rule");
+        // 23:30 on Sunday at -05:00 is already Monday in UTC.
+        assert_eq!(late.violation(at("2026-01-05T04:30:00Z")), None);
+        assert!(late.violation(at("2026-01-05T05:00:00Z")).is_some());
+    }
+
+    #[test]
+    fn malformed_validity_rules_are_rejected() {
+        for rule in [
+            serde_json::json!("2026-01-01"),
+            serde_json::json!({ "expires": "2026-01-01" }),
+            serde_json::json!({ "validUntil": 1_767_225_600 }),
+            serde_json::json!({ "validUntil": "next week" }),
+            serde_json::json!({ "schedule": { "days": ["monday"] } }),
+            serde_json::json!({ "schedule": { "from": "17:00", "until": "09:00" } }),
+            serde_json::json!({ "schedule": { "from": "9:00" } }),
+            serde_json::json!({ "schedule": { "utcOffset": "CET" } }),
+        ] {
+            assert!(parse_validity(&rule).is_err(), "{rule}");
+        }
+    }
+}
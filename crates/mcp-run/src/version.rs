@@ -0,0 +1,126 @@
+use axum::Json;
+use axum::extract::State;
+use rmcp::model::ProtocolVersion;
+use serde::{Deserialize, Serialize};
+
+use crate::policy::PolicyMode;
+use crate::raw::{RAW_PROTOCOL_VERSION, RawEndpointState};
+
+pub const GIT_COMMIT: &str = env!("MCP_RUN_GIT_COMMIT");
+
+/// Optional capabilities of this server, reported by `/version`: `auth` when it requires a
+/// bearer token and `pty` when `/raw` can run commands on a terminal.
+pub fn enabled_features(auth_required: bool) -> Vec<String> {
+    let mut features = Vec::new();
+    if auth_required {
+        features.push("auth".to_string());
+    }
+    if cfg!(unix) {
+        features.push("pty".to_string());
+    }
+    features
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub name: String,
+    pub version: String,
+    pub git_commit: String,
+    pub features: Vec<String>,
+    pub policy_mode: String,
+    pub protocols: ProtocolVersions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolVersions {
+    pub mcp: String,
+    pub raw: u32,
+}
+
+impl VersionInfo {
+    pub fn current(policy_mode: PolicyMode, auth_required: bool) -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: GIT_COMMIT.to_string(),
+            features: enabled_features(auth_required),
+            policy_mode: policy_mode.as_str().to_string(),
+            protocols: ProtocolVersions {
+                mcp: ProtocolVersion::LATEST.to_string(),
+                raw: RAW_PROTOCOL_VERSION,
+            },
+        }
+    }
+}
+
+pub async fn version_handler(State(state): State<RawEndpointState>) -> Json<VersionInfo> {
+    Json(VersionInfo::current(state.policy_engine.mode(), state.auth_required))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::auth::AuthToken;
+    use crate::mcp::{ServerOptions, build_app, build_app_with_options};
+    use crate::policy::PolicyEngine;
+    use crate::test_support::serve_app;
+
+    #[tokio::test]
+    async fn version_endpoint_reports_build_and_policy_mode() {
+        let policy_engine = PolicyEngine::from_sources(None);
+        let app = build_app(
+            Arc::new(policy_engine),
+            std::env::current_dir().expect("current dir"),
+        );
+        let (base, server_task) = serve_app(app).await;
+
+        let response = reqwest::get(format!("{base}/version"))
+            .await
+            .expect("version request");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let info: VersionInfo = response.json().await.expect("version body");
+
+        assert_eq!(info.name, "mcp-run");
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_commit.is_empty());
+        assert_eq!(info.policy_mode, "deny-all");
+        assert_eq!(info.protocols.raw, RAW_PROTOCOL_VERSION);
+        assert_eq!(info.protocols.mcp, ProtocolVersion::LATEST.to_string());
+        assert!(!info.features.iter().any(|feature| feature == "auth"));
+        assert_eq!(info.features.iter().any(|feature| feature == "pty"), cfg!(unix));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn version_endpoint_reports_auth_when_a_token_is_required() {
+        let options = ServerOptions {
+            auth_token: Some(AuthToken::new("s3cret").expect("token")),
+            ..ServerOptions::default()
+        };
+        let app = build_app_with_options(
+            Arc::new(PolicyEngine::from_sources(None)),
+            std::env::current_dir().expect("current dir"),
+            options,
+        );
+        let (base, server_task) = serve_app(app).await;
+
+        let info: VersionInfo = reqwest::Client::new()
+            .get(format!("{base}/version"))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .expect("version request")
+            .json()
+            .await
+            .expect("version body");
+
+        assert_eq!(info.features.first().map(String::as_str), Some("auth"));
+
+        server_task.abort();
+    }
+}
@@ -10,6 +10,9 @@ CFG_SRC="/opt/config/squid.conf"
 CFG_DST="/tmp/squid_generated.conf"
 CLI_IPS_FILE="/tmp/cli_ips.lst"
 SANDBOX_IPS_FILE="/tmp/sandbox_ips.lst"
+# Domains mcp-run opens for running commands, polled from the sandbox below. It lives only in
+# this container, so nothing the sandbox's commands can write reaches squid's allowlist.
+EGRESS_DOMAINS_FILE="/tmp/sandbox_domains.lst"
 DNS_IP=""
 
 # 2. Wait for Peers (CLI and Sandbox)
@@ -42,7 +45,24 @@ while [ -z "$CLI_IP" ] || [ -z "$SANDBOX_IP" ]; do
     fi
 done
 
-DNS_IP=$(awk '$1 == "nameserver" && $2 ~ /^[0-9]+\./ { print $2; exit }' /etc/resolv.conf)
+# Agents' cli pods (cladding.json "agents") have fixed addresses passed in by cladding.
+if [ -n "${CLADDING_AGENT_IPS:-}" ]; then
+    CLI_IPS="$(printf "%s\n" "$CLI_IPS" $CLADDING_AGENT_IPS | awk 'NF' | sort -u)"
+fi
+
+if [ "${CLADDING_ISOLATED:-0}" = "1" ]; then
+    # The project network is internal; cladding attaches the egress network after the pod
+    # starts, adding its resolver to resolv.conf. Only that resolver can answer external names.
+    TRIES=0
+    while [ "$(awk '$1 == "nameserver" && $2 ~ /^[0-9]+\./' /etc/resolv.conf | wc -l)" -lt 2 ] && [ "$TRIES" -lt 30 ]; do
+        echo "Waiting for egress network..."
+        sleep 1
+        TRIES=$((TRIES + 1))
+    done
+    DNS_IP=$(awk '$1 == "nameserver" && $2 ~ /^[0-9]+\./ { ip = $2 } END { print ip }' /etc/resolv.conf)
+else
+    DNS_IP=$(awk '$1 == "nameserver" && $2 ~ /^[0-9]+\./ { print $2; exit }' /etc/resolv.conf)
+fi
 if [ -z "$DNS_IP" ]; then
     DNS_IP="10.89.0.1"
 fi
@@ -59,10 +79,38 @@ echo "Sandbox allow-list file: $SANDBOX_IPS_FILE"
 # Copy the template to /tmp/
 cp "$CFG_SRC" "$CFG_DST"
 
-# Replace placeholders with actual runtime values
+# Replace placeholders with actual runtime values. Configs from before the list was polled
+# read it from /opt/egress, which is no longer mounted.
 sed -i "s/REPLACE_DNS_IP/$DNS_IP/g" "$CFG_DST"
+sed -i "s|/opt/egress/sandbox_domains.lst|$EGRESS_DOMAINS_FILE|g" "$CFG_DST"
+: > "$EGRESS_DOMAINS_FILE"
 
 echo "Config generated at $CFG_DST. Starting Squid..."
 
-# 4. Start Squid in foreground using generated config.
+# 4. Reconfigure Squid when mcp-run changes the domains opened by command policies.
+# The list is fetched over plain HTTP with bash's /dev/tcp, so the image needs no curl. Any
+# failure, including an error status, empties it: the domains close rather than stay open.
+#
+# Revocation is not immediate. A command's domains stay allowed after it exits until the
+# next poll sees the change and squid finishes reconfiguring, up to about a second, and
+# connections squid already accepted are not cut.
+fetch_egress_domains() {
+    bash -c 'exec 3<>/dev/tcp/sandbox-pod/3000 &&
+        printf "GET /egress/domains HTTP/1.0\r\nHost: sandbox-pod\r\n\r\n" >&3 &&
+        cat <&3' 2>/dev/null |
+        tr -d '\r' |
+        awk 'NR == 1 { ok = ($2 == "200"); next } ok && body { print } /^$/ { body = 1 }'
+}
+(
+    NEXT="$EGRESS_DOMAINS_FILE.next"
+    while sleep 1; do
+        fetch_egress_domains > "$NEXT" || : > "$NEXT"
+        if ! cmp -s "$NEXT" "$EGRESS_DOMAINS_FILE"; then
+            mv "$NEXT" "$EGRESS_DOMAINS_FILE"
+            squid -k reconfigure -f "$CFG_DST" || true
+        fi
+    done
+) &
+
+# 5. Start Squid in foreground using generated config.
 exec squid -N -f "$CFG_DST"